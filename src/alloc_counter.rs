@@ -1,10 +1,136 @@
 // Allocation counter module for tracking object allocations and deallocations
-use std::collections::HashMap;
-use std::sync::{Mutex, OnceLock};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+use std::sync::OnceLock;
 
 static DEBUG_ENABLED: OnceLock<bool> = OnceLock::new();
-static ALLOC_COUNTS: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
-static DEALLOC_COUNTS: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
+
+/// Off until start_tracking(): the allocator and the track_* hooks only
+/// check this flag, so programs that never ask for stats pay nothing
+static TRACKING: AtomicBool = AtomicBool::new(false);
+
+/// Net bytes allocated since tracking started (memory allocated earlier and
+/// freed afterwards makes this go down, so it can be negative)
+static CURRENT_BYTES: AtomicIsize = AtomicIsize::new(0);
+static PEAK_BYTES: AtomicIsize = AtomicIsize::new(0);
+static TOTAL_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Types that report to track_alloc/track_clone/track_dealloc, sorted
+const TRACKED_TYPES: [&str; 15] = [
+    "Array", "Bool", "Bytes", "BytesBuffer", "Counter", "Deque", "Dict", "Float",
+    "Heap", "Int", "Iterator", "NDArray", "PriorityQueue", "Set", "Str",
+];
+
+/// Per-type object counts. Values are created and dropped on the interpreter
+/// thread, so the counts are kept per thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjectCounts {
+    pub created: usize,
+    pub cloned: usize,
+    pub dropped: usize,
+}
+
+impl ObjectCounts {
+    const ZERO: ObjectCounts = ObjectCounts { created: 0, cloned: 0, dropped: 0 };
+
+    /// Values (including clones) made since tracking started that haven't
+    /// been dropped; negative when older values were dropped in the meantime
+    pub fn live(&self) -> i64 {
+        self.created as i64 + self.cloned as i64 - self.dropped as i64
+    }
+}
+
+thread_local! {
+    static OBJECT_COUNTS: RefCell<[ObjectCounts; TRACKED_TYPES.len()]> =
+        const { RefCell::new([ObjectCounts::ZERO; TRACKED_TYPES.len()]) };
+}
+
+/// Turn on heap and object counting for the rest of the process
+pub fn start_tracking() {
+    TRACKING.store(true, Ordering::Relaxed);
+}
+
+fn is_tracking() -> bool {
+    TRACKING.load(Ordering::Relaxed)
+}
+
+/// Global allocator that keeps track of heap usage for sys.memory_stats()
+/// once tracking is on
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() && is_tracking() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() && is_tracking() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        if is_tracking() {
+            CURRENT_BYTES.fetch_sub(layout.size() as isize, Ordering::Relaxed);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() && is_tracking() {
+            CURRENT_BYTES.fetch_sub(layout.size() as isize, Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn record_alloc(size: usize) {
+    let current = CURRENT_BYTES.fetch_add(size as isize, Ordering::Relaxed) + size as isize;
+    PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+    TOTAL_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Net heap bytes allocated since tracking started
+pub fn current_bytes() -> isize {
+    CURRENT_BYTES.load(Ordering::Relaxed)
+}
+
+/// Highest value current_bytes() has reached
+pub fn peak_bytes() -> isize {
+    PEAK_BYTES.load(Ordering::Relaxed)
+}
+
+/// Number of heap allocations made since tracking started
+pub fn total_allocations() -> usize {
+    TOTAL_ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// Snapshot of the object counts of the current thread, sorted by type name
+pub fn object_counts() -> Vec<(&'static str, ObjectCounts)> {
+    let counts = OBJECT_COUNTS.with(|c| *c.borrow());
+    TRACKED_TYPES.iter().copied().zip(counts).collect()
+}
+
+fn update_counts(type_name: &'static str, f: impl FnOnce(&mut ObjectCounts)) {
+    let Ok(slot) = TRACKED_TYPES.binary_search(&type_name) else {
+        return;
+    };
+    // try_with: values can be dropped while thread-locals are torn down
+    let _ = OBJECT_COUNTS.try_with(|c| {
+        if let Ok(mut counts) = c.try_borrow_mut() {
+            f(&mut counts[slot]);
+        }
+    });
+}
 
 /// Check if QUEST_CLONE_DEBUG is enabled
 pub fn is_debug_enabled() -> bool {
@@ -13,44 +139,42 @@ pub fn is_debug_enabled() -> bool {
     })
 }
 
-/// Get the allocation counts map
-fn alloc_counts() -> &'static Mutex<HashMap<String, usize>> {
-    ALLOC_COUNTS.get_or_init(|| Mutex::new(HashMap::new()))
-}
+/// Track an object allocation
+pub fn track_alloc(type_name: &'static str, id: u64) {
+    if !is_tracking() {
+        return;
+    }
+    update_counts(type_name, |c| c.created += 1);
 
-/// Get the deallocation counts map
-fn dealloc_counts() -> &'static Mutex<HashMap<String, usize>> {
-    DEALLOC_COUNTS.get_or_init(|| Mutex::new(HashMap::new()))
+    // Print allocation
+    if is_debug_enabled() {
+        eprintln!("[QUEST_CLONE_DEBUG] ALLOC: {} (id={})", type_name, id);
+    }
 }
 
-/// Track an object allocation
-pub fn track_alloc(type_name: &str, id: u64) {
-    if !is_debug_enabled() {
+/// Track a copy of an existing object (clones share its id)
+pub fn track_clone(type_name: &'static str, id: u64) {
+    if !is_tracking() {
         return;
     }
+    update_counts(type_name, |c| c.cloned += 1);
 
-    // Increment count
-    if let Ok(mut counts) = alloc_counts().lock() {
-        *counts.entry(type_name.to_string()).or_insert(0) += 1;
+    if is_debug_enabled() {
+        eprintln!("[QUEST_CLONE_DEBUG] CLONE: {} (id={})", type_name, id);
     }
-
-    // Print allocation
-    eprintln!("[QUEST_CLONE_DEBUG] ALLOC: {} (id={})", type_name, id);
 }
 
 /// Track an object deallocation
-pub fn track_dealloc(type_name: &str, id: u64) {
-    if !is_debug_enabled() {
+pub fn track_dealloc(type_name: &'static str, id: u64) {
+    if !is_tracking() {
         return;
     }
-
-    // Increment count
-    if let Ok(mut counts) = dealloc_counts().lock() {
-        *counts.entry(type_name.to_string()).or_insert(0) += 1;
-    }
+    update_counts(type_name, |c| c.dropped += 1);
 
     // Print deallocation
-    eprintln!("[QUEST_CLONE_DEBUG] DEALLOC: {} (id={})", type_name, id);
+    if is_debug_enabled() {
+        eprintln!("[QUEST_CLONE_DEBUG] DEALLOC: {} (id={})", type_name, id);
+    }
 }
 
 /// Print allocation/deallocation statistics
@@ -61,36 +185,27 @@ pub fn print_stats() {
 
     eprintln!("\n=== QUEST_CLONE_DEBUG: Object Allocation Statistics ===");
 
-    let alloc_map = alloc_counts().lock().unwrap();
-    let dealloc_map = dealloc_counts().lock().unwrap();
-
-    // Collect all unique types
-    let mut types: Vec<String> = alloc_map.keys()
-        .chain(dealloc_map.keys())
-        .cloned()
-        .collect();
-    types.sort();
-    types.dedup();
-
-    eprintln!("\n{:<20} {:>12} {:>12} {:>12}", "Type", "Allocated", "Deallocated", "Live");
-    eprintln!("{}", "-".repeat(60));
-
-    let mut total_alloc = 0;
-    let mut total_dealloc = 0;
+    eprintln!("\n{:<20} {:>12} {:>12} {:>12} {:>12}", "Type", "Allocated", "Cloned", "Deallocated", "Live");
+    eprintln!("{}", "-".repeat(72));
 
-    for type_name in &types {
-        let alloc = *alloc_map.get(type_name).unwrap_or(&0);
-        let dealloc = *dealloc_map.get(type_name).unwrap_or(&0);
-        let live = alloc.saturating_sub(dealloc);
+    let mut total = ObjectCounts::default();
 
-        total_alloc += alloc;
-        total_dealloc += dealloc;
+    for (type_name, counts) in object_counts() {
+        if counts.created + counts.cloned + counts.dropped == 0 {
+            continue;
+        }
+        total.created += counts.created;
+        total.cloned += counts.cloned;
+        total.dropped += counts.dropped;
 
-        eprintln!("{:<20} {:>12} {:>12} {:>12}", type_name, alloc, dealloc, live);
+        eprintln!("{:<20} {:>12} {:>12} {:>12} {:>12}",
+            type_name, counts.created, counts.cloned, counts.dropped, counts.live());
     }
 
-    eprintln!("{}", "-".repeat(60));
-    eprintln!("{:<20} {:>12} {:>12} {:>12}",
-        "TOTAL", total_alloc, total_dealloc, total_alloc.saturating_sub(total_dealloc));
+    eprintln!("{}", "-".repeat(72));
+    eprintln!("{:<20} {:>12} {:>12} {:>12} {:>12}",
+        "TOTAL", total.created, total.cloned, total.dropped, total.live());
+    eprintln!("\nHeap: {} bytes in use, {} bytes peak, {} allocations (since startup)",
+        current_bytes(), peak_bytes(), total_allocations());
     eprintln!();
 }
@@ -0,0 +1,1096 @@
+// Static checks for `quest check`
+//
+// Files are parsed but never evaluated. Each file is summarised first (its
+// top-level functions, types and traits, and which names are `pub`), then
+// walked with a stack of block frames to find names that can't resolve, type
+// annotations naming unknown types, types that don't satisfy the traits they
+// implement, and calls to known functions whose arguments can't bind. Local
+// modules reached through `use` are checked as well; std modules may be backed
+// by Rust, so their members are taken on trust.
+//
+// Messages mirror the runtime errors the same mistakes would raise. Anything
+// the checker can't see statically (decorated functions, calls that unpack
+// with `*`/`**`, methods on values of unknown type) is left alone.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use pest::iterators::Pair;
+use pest::Parser;
+
+use crate::scope::Scope;
+use crate::{QuestParser, Rule, BUILTIN_FUNCTIONS};
+
+/// A problem found in a file
+pub struct Diagnostic {
+    pub path: String,
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.path, self.line, self.col, self.message)
+    }
+}
+
+/// Parameters of a user function, as far as argument binding cares
+#[derive(Clone)]
+struct Signature {
+    /// Name and whether it must be supplied (no default)
+    params: Vec<(String, bool)>,
+    varargs: bool,
+    kwargs: bool,
+}
+
+#[derive(Clone, Default)]
+struct TypeInfo {
+    /// Field name and whether `.new()` must supply it
+    fields: Vec<(String, bool)>,
+    /// `fun self.name` methods; None when decorated
+    static_methods: HashMap<String, Option<Signature>>,
+}
+
+/// Top-level declarations of a file
+#[derive(Clone, Default)]
+struct ModuleInfo {
+    public: HashSet<String>,
+    functions: HashMap<String, Signature>,
+    types: HashMap<String, TypeInfo>,
+    /// Required methods of each trait with their parameter counts
+    traits: HashMap<String, Vec<(String, usize)>>,
+}
+
+pub struct Checker {
+    builtins: HashSet<String>,
+    /// Summaries of local modules by canonical path (None when the file doesn't parse)
+    modules: HashMap<PathBuf, Option<Rc<ModuleInfo>>>,
+    checked: HashSet<PathBuf>,
+    pending: Vec<PathBuf>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        let mut builtins: HashSet<String> = Scope::new().to_flat_map().into_keys().collect();
+        builtins.extend(BUILTIN_FUNCTIONS.iter().map(|name| name.to_string()));
+        // Set.new is special-cased by the evaluator, and std overlays see their Rust module as __builtin__
+        builtins.extend(["Set".to_string(), "__builtin__".to_string()]);
+        Checker {
+            builtins,
+            modules: HashMap::new(),
+            checked: HashSet::new(),
+            pending: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Check a file and every local module it uses, directly or not
+    pub fn check_file(&mut self, path: &Path) {
+        self.pending.push(path.to_path_buf());
+        while let Some(path) = self.pending.pop() {
+            let key = canonical(&path);
+            if self.checked.insert(key) {
+                self.check_one(&path);
+            }
+        }
+    }
+
+    /// Number of distinct files checked so far
+    pub fn files_checked(&self) -> usize {
+        self.checked.len()
+    }
+
+    fn check_one(&mut self, path: &Path) {
+        let display = path.display().to_string();
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                self.report(&display, 1, 1, format!("Failed to read file: {}", e));
+                return;
+            }
+        };
+        let program = match QuestParser::parse(Rule::program, &source) {
+            Ok(mut pairs) => pairs.next().unwrap(),
+            Err(e) => {
+                let (line, col) = match e.line_col {
+                    pest::error::LineColLocation::Pos(pos) => pos,
+                    pest::error::LineColLocation::Span(start, _) => start,
+                };
+                self.report(&display, line, col, format!("Parse error: {}", e.variant.message()));
+                return;
+            }
+        };
+        let statements: Vec<_> = program.into_inner().filter(|p| p.as_rule() == Rule::statement).collect();
+        let known = collect_module(&statements, &source);
+        self.modules.entry(canonical(path)).or_insert_with(|| Some(Rc::new(known.clone())));
+
+        let first = self.diagnostics.len();
+        let mut file = FileChecker {
+            checker: self,
+            path: display,
+            source: &source,
+            frames: Vec::new(),
+            fn_base: 0,
+            known,
+            aliases: HashMap::new(),
+        };
+        file.block(statements, Vec::new());
+        self.diagnostics[first..].sort_by_key(|d| (d.line, d.col));
+    }
+
+    /// Resolve a `use` path from `from`; Ok(None) for std modules, whose members aren't inspected
+    fn load_module(&mut self, use_path: &str, from: &str) -> Result<Option<Rc<ModuleInfo>>, String> {
+        let resolved = crate::module_loader::resolve_module_path_from(use_path, Some(from), &[]);
+        if use_path.starts_with("std/") {
+            return Ok(None);
+        }
+        let path = PathBuf::from(resolved?);
+        if !path.exists() {
+            return Err(format!("ImportErr: Module '{}' not found at '{}'", use_path, path.display()));
+        }
+        let key = canonical(&path);
+        if !self.checked.contains(&key) {
+            self.pending.push(path.clone());
+        }
+        if let Some(info) = self.modules.get(&key) {
+            return Ok(info.clone());
+        }
+        let info = fs::read_to_string(&path).ok().and_then(|source| {
+            let program = QuestParser::parse(Rule::program, &source).ok()?.next()?;
+            let statements: Vec<_> = program.into_inner().filter(|p| p.as_rule() == Rule::statement).collect();
+            Some(Rc::new(collect_module(&statements, &source)))
+        });
+        self.modules.insert(key, info.clone());
+        Ok(info)
+    }
+
+    fn report(&mut self, path: &str, line: usize, col: usize, message: String) {
+        self.diagnostics.push(Diagnostic { path: path.to_string(), line, col, message });
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Names declared in a block
+struct Frame {
+    /// Declared so far, in source order
+    declared: HashSet<String>,
+    /// Everything the block declares, for code that runs later (function bodies)
+    all: HashSet<String>,
+}
+
+struct FileChecker<'c, 's> {
+    checker: &'c mut Checker,
+    path: String,
+    source: &'s str,
+    frames: Vec<Frame>,
+    /// Index of the innermost function's frame; frames below it are seen whole
+    fn_base: usize,
+    /// This file's declarations plus selectively imported functions and types
+    known: ModuleInfo,
+    /// `use ... as` aliases of local modules
+    aliases: HashMap<String, Rc<ModuleInfo>>,
+}
+
+impl<'c, 's> FileChecker<'c, 's> {
+    fn report(&mut self, pair: &Pair<'s, Rule>, message: String) {
+        let (line, col) = pair.as_span().start_pos().line_col();
+        let path = self.path.clone();
+        self.checker.report(&path, line, col, message);
+    }
+
+    fn block(&mut self, statements: Vec<Pair<'s, Rule>>, bound: Vec<String>) {
+        let mut all = block_names(&statements);
+        all.extend(bound.iter().cloned());
+        self.frames.push(Frame { declared: bound.into_iter().collect(), all });
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.frames.pop();
+    }
+
+    fn declare(&mut self, name: &str) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.declared.insert(name.to_string());
+        }
+    }
+
+    /// Index of the frame `name` resolves to
+    fn lookup(&self, name: &str) -> Option<usize> {
+        (0..self.frames.len()).rev().find(|&i| {
+            let frame = &self.frames[i];
+            if i < self.fn_base {
+                frame.all.contains(name)
+            } else {
+                frame.declared.contains(name)
+            }
+        })
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.lookup(name).is_some() || self.checker.builtins.contains(name)
+    }
+
+    /// Declared anywhere in an enclosing block, whether or not it has run yet
+    fn visible_anywhere(&self, name: &str) -> bool {
+        self.frames.iter().any(|f| f.all.contains(name) || f.declared.contains(name))
+            || self.checker.builtins.contains(name)
+    }
+
+    /// Whether `name` refers to the file's top-level declaration of it
+    fn is_top_level(&self, name: &str) -> bool {
+        self.lookup(name) == Some(0)
+    }
+
+    fn reference(&mut self, ident: &Pair<'s, Rule>) {
+        let name = ident.as_str();
+        if !self.is_defined(name) {
+            let hint = crate::suggest::did_you_mean(name, &self.visible_names());
+            self.report(ident, format!("Undefined variable: {}{}", name, hint));
+        }
+    }
+
+    /// Every name `lookup` would find, plus the builtins
+    fn visible_names(&self) -> Vec<&str> {
+        self.frames.iter().enumerate()
+            .flat_map(|(i, f)| if i < self.fn_base { f.all.iter() } else { f.declared.iter() })
+            .chain(self.checker.builtins.iter())
+            .map(|name| name.as_str())
+            .collect()
+    }
+
+    fn statement(&mut self, pair: Pair<'s, Rule>) {
+        let stmt = if pair.as_rule() == Rule::statement {
+            match pair.into_inner().next() {
+                Some(stmt) => stmt,
+                None => return,
+            }
+        } else {
+            pair
+        };
+        match stmt.as_rule() {
+            Rule::pub_statement => {
+                for inner in stmt.into_inner() {
+                    self.statement(inner);
+                }
+            }
+            Rule::use_statement => self.use_statement(stmt),
+            Rule::let_statement => {
+                for binding in stmt.into_inner() {
+                    let mut parts = binding.into_inner();
+                    let name = parts.next().unwrap();
+                    for part in parts {
+                        self.expr(part);
+                    }
+                    self.declare(name.as_str());
+                }
+            }
+            Rule::const_declaration | Rule::variable_declaration => {
+                let mut parts = stmt.into_inner();
+                let name = parts.next().unwrap();
+                for part in parts {
+                    self.expr(part);
+                }
+                self.declare(name.as_str());
+            }
+            Rule::del_statement => {
+                let ident = stmt.into_inner().next().unwrap();
+                self.reference(&ident);
+            }
+            Rule::assignment => {
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::assignment_target => {
+                            let mut target = part.into_inner();
+                            let ident = target.next().unwrap();
+                            self.reference(&ident);
+                            // Later identifiers are member names
+                            for access in target.filter(|p| p.as_rule() == Rule::index_access) {
+                                self.expr(access);
+                            }
+                        }
+                        Rule::compound_op => {}
+                        _ => self.expr(part),
+                    }
+                }
+            }
+            Rule::function_declaration => {
+                if let Some(name) = function_name(&stmt) {
+                    self.declare(name.as_str());
+                }
+                self.function(stmt, false);
+            }
+            Rule::type_declaration => self.type_declaration(stmt),
+            Rule::trait_declaration => {
+                let mut parts = stmt.into_inner();
+                let name = parts.next().unwrap();
+                self.declare(name.as_str());
+                for method in parts.filter(|p| p.as_rule() == Rule::trait_method) {
+                    for part in method.into_inner() {
+                        match part.as_rule() {
+                            Rule::parameter_list => self.parameter_types(part),
+                            Rule::type_expr => self.type_expr(part),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Rule::if_statement | Rule::elif_clause | Rule::while_statement => {
+                let mut statements = Vec::new();
+                let mut clauses = Vec::new();
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::statement => statements.push(part),
+                        Rule::elif_clause | Rule::else_clause => clauses.push(part),
+                        _ => self.expr(part),
+                    }
+                }
+                self.block(statements, Vec::new());
+                for clause in clauses {
+                    self.statement(clause);
+                }
+            }
+            Rule::else_clause | Rule::ensure_clause => {
+                let statements = stmt.into_inner().collect();
+                self.block(statements, Vec::new());
+            }
+            Rule::match_statement => {
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::match_arm => {
+                            let mut statements = Vec::new();
+                            for arm_part in part.into_inner() {
+                                match arm_part.as_rule() {
+                                    Rule::statement => statements.push(arm_part),
+                                    _ => self.expr(arm_part),
+                                }
+                            }
+                            self.block(statements, Vec::new());
+                        }
+                        Rule::else_clause => self.statement(part),
+                        _ => self.expr(part),
+                    }
+                }
+            }
+            Rule::for_statement => {
+                let mut bound = Vec::new();
+                let mut statements = Vec::new();
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::identifier => bound.push(part.as_str().to_string()),
+                        Rule::statement => statements.push(part),
+                        _ => self.expr(part),
+                    }
+                }
+                self.block(statements, bound);
+            }
+            // try and with bodies run in the enclosing scope
+            Rule::with_statement => {
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::with_item => {
+                            for item in part.into_inner() {
+                                match item.as_rule() {
+                                    Rule::as_clause => {
+                                        let name = item.into_inner().next().unwrap();
+                                        self.declare(name.as_str());
+                                    }
+                                    _ => self.expr(item),
+                                }
+                            }
+                        }
+                        _ => self.statement(part),
+                    }
+                }
+            }
+            Rule::try_statement => {
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::catch_clause => {
+                            for catch_part in part.into_inner() {
+                                match catch_part.as_rule() {
+                                    Rule::identifier => self.declare(catch_part.as_str()),
+                                    Rule::type_expr => self.type_expr(catch_part),
+                                    _ => self.statement(catch_part),
+                                }
+                            }
+                        }
+                        Rule::ensure_clause => {
+                            for ensure_part in part.into_inner() {
+                                self.statement(ensure_part);
+                            }
+                        }
+                        _ => self.statement(part),
+                    }
+                }
+            }
+            Rule::expression_statement => self.expr(stmt),
+            Rule::return_statement | Rule::raise_statement => {
+                for part in stmt.into_inner() {
+                    self.expr(part);
+                }
+            }
+            // Doc declarations, impl declarations (unused at runtime), break and continue
+            _ => {}
+        }
+    }
+
+    fn use_statement(&mut self, pair: Pair<'s, Rule>) {
+        let (path, alias, imports) = parse_use(&pair);
+        let module = match self.checker.load_module(&path, &self.path.clone()) {
+            Ok(module) => module,
+            Err(e) => {
+                self.report(&pair, e);
+                None
+            }
+        };
+
+        if let Some(module) = &module {
+            for (name, local, item) in &imports {
+                if !module.public.contains(name) {
+                    self.report(item, format!("ImportErr: Module has no public member '{}'", name));
+                    continue;
+                }
+                if let Some(sig) = module.functions.get(name) {
+                    self.known.functions.insert(local.clone(), sig.clone());
+                }
+                if let Some(info) = module.types.get(name) {
+                    self.known.types.insert(local.clone(), info.clone());
+                }
+                if let Some(methods) = module.traits.get(name) {
+                    self.known.traits.insert(local.clone(), methods.clone());
+                }
+            }
+        }
+        for (_, local, _) in &imports {
+            self.declare(local);
+        }
+        if let Some(alias) = alias {
+            if let Some(module) = module {
+                self.aliases.insert(alias.clone(), module);
+            }
+            self.declare(&alias);
+        }
+    }
+
+    fn function(&mut self, pair: Pair<'s, Rule>, is_method: bool) {
+        let mut params = None;
+        let mut body = Vec::new();
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::decorator => self.decorator(part),
+                Rule::parameter_list => params = Some(part),
+                Rule::type_expr => self.type_expr(part),
+                Rule::statement => body.push(part),
+                _ => {}
+            }
+        }
+        self.function_body(params, body, is_method);
+    }
+
+    fn function_body(&mut self, params: Option<Pair<'s, Rule>>, body: Vec<Pair<'s, Rule>>, bind_self: bool) {
+        let saved = self.fn_base;
+        self.fn_base = self.frames.len();
+        let mut bound = Vec::new();
+        if bind_self {
+            bound.push("self".to_string());
+        }
+        if let Some(params) = &params {
+            bound.extend(params.clone().into_inner().map(|p| p.into_inner().next().unwrap().as_str().to_string()));
+        }
+        let mut all = block_names(&body);
+        all.extend(bound.iter().cloned());
+        self.frames.push(Frame { declared: bound.into_iter().collect(), all });
+        if let Some(params) = params {
+            for param in params.into_inner() {
+                for part in param.into_inner().skip(1) {
+                    self.expr(part);
+                }
+            }
+        }
+        for statement in body {
+            self.statement(statement);
+        }
+        self.frames.pop();
+        self.fn_base = saved;
+    }
+
+    fn parameter_types(&mut self, params: Pair<'s, Rule>) {
+        for param in params.into_inner() {
+            for part in param.into_inner().filter(|p| p.as_rule() == Rule::type_expr) {
+                self.type_expr(part);
+            }
+        }
+    }
+
+    fn decorator(&mut self, pair: Pair<'s, Rule>) {
+        let expression = pair.into_inner().next().unwrap();
+        let mut parts = expression.into_inner();
+        let ident = parts.next().unwrap();
+        self.reference(&ident);
+        for part in parts.filter(|p| p.as_rule() == Rule::decorator_args) {
+            self.expr(part);
+        }
+    }
+
+    fn type_declaration(&mut self, pair: Pair<'s, Rule>) {
+        let mut parts = pair.clone().into_inner();
+        let name = parts.next().unwrap();
+        let type_name = name.as_str().to_string();
+        self.declare(&type_name);
+
+        let mut methods: HashMap<String, usize> = HashMap::new();
+        let mut impls = Vec::new();
+        let mut bodies = Vec::new();
+        for member in parts.filter(|p| p.as_rule() == Rule::type_member) {
+            let first = member.clone().into_inner().next().unwrap();
+            match first.as_rule() {
+                Rule::identifier => {
+                    let field = first.as_str();
+                    for part in member.into_inner().skip(1) {
+                        match part.as_rule() {
+                            Rule::type_expr => self.field_type(part, field),
+                            _ => self.expr(part),
+                        }
+                    }
+                }
+                Rule::impl_block => {
+                    let mut block = first.into_inner();
+                    let trait_name = block.next().unwrap();
+                    for method in block {
+                        if let Some(method_name) = function_name(&method) {
+                            methods.insert(method_name.as_str().to_string(), parameter_count(&method));
+                        }
+                        bodies.push(method);
+                    }
+                    impls.push(trait_name);
+                }
+                _ => {
+                    if let Some(method_name) = function_name(&first) {
+                        methods.insert(method_name.as_str().to_string(), parameter_count(&first));
+                    }
+                    bodies.push(first);
+                }
+            }
+        }
+
+        for trait_name in impls {
+            let name = trait_name.as_str();
+            match self.known.traits.get(name).cloned() {
+                Some(required) if self.is_top_level(name) => {
+                    for (method, expected) in required {
+                        match methods.get(&method) {
+                            Some(&actual) if actual != expected => self.report(&trait_name, format!(
+                                "Type {} implements trait {} but method '{}' has {} parameters, expected {}",
+                                type_name, name, method, actual, expected
+                            )),
+                            Some(_) => {}
+                            None => self.report(&trait_name, format!(
+                                "Type {} implements trait {} but missing required method '{}'",
+                                type_name, name, method
+                            )),
+                        }
+                    }
+                }
+                _ if !self.is_defined(name) => self.report(&trait_name, format!("Trait {} not found", name)),
+                _ => {}
+            }
+        }
+
+        for body in bodies {
+            self.function(body, true);
+        }
+    }
+
+    /// Field types must already be declared when the type is
+    fn field_type(&mut self, pair: Pair<'s, Rule>, field: &str) {
+        let Some(name) = base_type_name(&pair) else {
+            return self.type_expr(pair);
+        };
+        let type_name = name.as_str();
+        if name.as_rule() == Rule::qualified_type || self.is_defined(type_name) {
+            return self.type_expr(pair);
+        }
+        let capitalized = title_case(type_name);
+        if capitalized != type_name && self.is_defined(&capitalized) {
+            self.report(&name, format!(
+                "Built-in types use CamelCase. Use '{}' instead of '{}' in field '{}'.",
+                capitalized, type_name, field
+            ));
+        } else {
+            self.report(&name, format!(
+                "Unknown type '{}' in field '{}'. Type must be a built-in or defined user type.",
+                type_name, field
+            ));
+        }
+    }
+
+    fn type_expr(&mut self, pair: Pair<'s, Rule>) {
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::base_type => {
+                    let base = part.into_inner().next().unwrap();
+                    match base.as_rule() {
+                        Rule::identifier => {
+                            let name = base.as_str();
+                            if !self.visible_anywhere(name) && !self.visible_anywhere(&title_case(name)) {
+                                self.report(&base, format!("Unknown type '{}'", name));
+                            }
+                        }
+                        Rule::qualified_type => {
+                            let module = base.into_inner().next().unwrap();
+                            if !self.visible_anywhere(module.as_str()) {
+                                self.report(&module, format!("Undefined variable: {}", module.as_str()));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Rule::type_expr => self.type_expr(part),
+                _ => self.expr(part),
+            }
+        }
+    }
+
+    fn expr(&mut self, pair: Pair<'s, Rule>) {
+        match pair.as_rule() {
+            Rule::identifier => self.reference(&pair),
+            Rule::expression | Rule::expression_statement if is_lambda(&pair) => {
+                let mut params = None;
+                let mut body = Vec::new();
+                for part in pair.into_inner() {
+                    match part.as_rule() {
+                        Rule::parameter_list => params = Some(part),
+                        _ => body.push(part),
+                    }
+                }
+                self.function_body(params, body, false);
+            }
+            Rule::postfix => self.postfix(pair),
+            Rule::primary => self.primary(pair),
+            Rule::named_arg | Rule::dict_pair => {
+                for part in pair.into_inner().skip(1) {
+                    self.expr(part);
+                }
+            }
+            Rule::interpolation => {
+                let ident = pair.into_inner().next().unwrap();
+                self.reference(&ident);
+            }
+            Rule::type_expr => self.type_expr(pair),
+            _ => {
+                for part in pair.into_inner() {
+                    self.expr(part);
+                }
+            }
+        }
+    }
+
+    fn primary(&mut self, pair: Pair<'s, Rule>) {
+        let mut parts = pair.clone().into_inner();
+        let Some(first) = parts.next() else { return };
+        if first.as_rule() != Rule::identifier {
+            return self.expr(first);
+        }
+        self.reference(&first);
+        let args = parts.next();
+        let rest = pair.as_str()[first.as_str().len()..].trim_start();
+        let name = first.as_str();
+
+        if rest.starts_with(".new") {
+            if let Some(info) = self.type_info(name) {
+                match info.static_methods.get("new").cloned() {
+                    Some(Some(sig)) => self.check_call(&format!("{}.new", name), &sig, args.clone(), &first),
+                    Some(None) => {}
+                    None => self.check_constructor(&info, args.clone(), &first),
+                }
+            }
+        } else if rest.starts_with('(') && self.is_top_level(name) {
+            if let Some(sig) = self.known.functions.get(name).cloned() {
+                self.check_call(name, &sig, args.clone(), &first);
+            }
+        }
+        if let Some(args) = args {
+            self.expr(args);
+        }
+    }
+
+    fn postfix(&mut self, pair: Pair<'s, Rule>) {
+        let mut parts = pair.into_inner().peekable();
+        let primary = parts.next().unwrap();
+        // A bare identifier receiver, for module and static method calls
+        let receiver = match primary.clone().into_inner().next() {
+            Some(ident) if ident.as_rule() == Rule::identifier && ident.as_str() == primary.as_str() => Some(ident),
+            _ => None,
+        };
+        self.expr(primary);
+
+        let mut first = true;
+        while let Some(part) = parts.next() {
+            if part.as_rule() == Rule::method_name {
+                let is_call = self.source[part.as_span().end()..].trim_start().starts_with('(');
+                let args = if is_call && parts.peek().map(|p| p.as_rule()) == Some(Rule::argument_list) {
+                    parts.next()
+                } else {
+                    None
+                };
+                if let (true, true, Some(receiver)) = (first, is_call, &receiver) {
+                    self.member_call(receiver, &part, args.clone());
+                }
+                if let Some(args) = args {
+                    self.expr(args);
+                }
+            } else {
+                self.expr(part);
+            }
+            first = false;
+        }
+    }
+
+    /// `alias.f(...)` on a local module or `T.m(...)` on a known type
+    fn member_call(&mut self, receiver: &Pair<'s, Rule>, method: &Pair<'s, Rule>, args: Option<Pair<'s, Rule>>) {
+        let name = receiver.as_str();
+        let member = method.as_str();
+        if !self.is_top_level(name) {
+            return;
+        }
+        if let Some(module) = self.aliases.get(name).cloned() {
+            if !module.public.contains(member) && !member.starts_with('_') {
+                self.report(method, format!("Module {} has no public member '{}'", name, member));
+            } else if let Some(sig) = module.functions.get(member) {
+                self.check_call(&format!("{}.{}", name, member), sig, args, method);
+            }
+        } else if let Some(info) = self.known.types.get(name).cloned() {
+            match info.static_methods.get(member) {
+                Some(Some(sig)) => self.check_call(&format!("{}.{}", name, member), sig, args, method),
+                Some(None) => {}
+                None if member == "new" => self.check_constructor(&info, args, method),
+                None => {}
+            }
+        }
+    }
+
+    fn type_info(&self, name: &str) -> Option<TypeInfo> {
+        if self.is_top_level(name) {
+            self.known.types.get(name).cloned()
+        } else {
+            None
+        }
+    }
+
+    /// Mirrors the binding rules of function_call::call_user_function
+    fn check_call(&mut self, name: &str, sig: &Signature, args: Option<Pair<'s, Rule>>, at: &Pair<'s, Rule>) {
+        let Some((positional, keywords)) = split_arguments(args) else { return };
+        let message = if positional > sig.params.len() && !sig.varargs {
+            Some(format!(
+                "Function {} takes at most {} positional arguments, got {}",
+                name, sig.params.len(), positional
+            ))
+        } else if let Some((param, _)) = sig.params.iter().take(positional).find(|(p, _)| keywords.contains(p)) {
+            Some(format!("Parameter '{}' specified both positionally and by keyword", param))
+        } else if let Some((param, _)) = sig.params.iter().skip(positional).find(|(p, required)| *required && !keywords.contains(p)) {
+            Some(format!("Missing required parameter '{}'", param))
+        } else {
+            let unknown: Vec<_> = keywords.iter()
+                .filter(|k| !sig.params.iter().any(|(p, _)| p == *k))
+                .map(|k| k.as_str())
+                .collect();
+            if unknown.is_empty() || sig.kwargs {
+                None
+            } else {
+                Some(format!("Unknown keyword arguments: {}", unknown.join(", ")))
+            }
+        };
+        if let Some(message) = message {
+            self.report(at, message);
+        }
+    }
+
+    /// `T.new()` with no arguments or only named ones must cover every required field
+    fn check_constructor(&mut self, info: &TypeInfo, args: Option<Pair<'s, Rule>>, at: &Pair<'s, Rule>) {
+        let Some((positional, keywords)) = split_arguments(args) else { return };
+        if positional > 0 {
+            return;
+        }
+        if let Some((field, _)) = info.fields.iter().find(|(f, required)| *required && !keywords.contains(f)) {
+            self.report(at, format!("Required field '{}' not provided and has no default", field));
+        }
+    }
+}
+
+/// Positional count and keyword names of a call; None if it unpacks with `*` or `**`
+fn split_arguments(args: Option<Pair<'_, Rule>>) -> Option<(usize, Vec<String>)> {
+    let mut positional = 0;
+    let mut keywords = Vec::new();
+    for item in args.into_iter().flat_map(|a| a.into_inner()) {
+        let arg = item.into_inner().next().unwrap();
+        match arg.as_rule() {
+            Rule::unpack_args | Rule::unpack_kwargs => return None,
+            Rule::named_arg => keywords.push(arg.into_inner().next().unwrap().as_str().to_string()),
+            _ => positional += 1,
+        }
+    }
+    Some((positional, keywords))
+}
+
+fn is_lambda(pair: &Pair<'_, Rule>) -> bool {
+    pair.clone().into_inner().next().map(|p| p.as_rule()) != Some(Rule::elvis_expr)
+}
+
+fn title_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The outer `base_type` of a type expression (identifier or qualified_type)
+fn base_type_name<'s>(pair: &Pair<'s, Rule>) -> Option<Pair<'s, Rule>> {
+    let base = pair.clone().into_inner().next()?;
+    let inner = base.into_inner().next()?;
+    match inner.as_rule() {
+        Rule::identifier | Rule::qualified_type => Some(inner),
+        _ => None,
+    }
+}
+
+fn function_name<'s>(pair: &Pair<'s, Rule>) -> Option<Pair<'s, Rule>> {
+    pair.clone().into_inner().find(|p| p.as_rule() == Rule::identifier)
+}
+
+fn is_decorated(pair: &Pair<'_, Rule>) -> bool {
+    pair.clone().into_inner().any(|p| p.as_rule() == Rule::decorator)
+}
+
+/// `fun self.name` (a static method)
+fn is_static_method(pair: &Pair<'_, Rule>, source: &str) -> bool {
+    match function_name(pair) {
+        Some(name) => source[pair.as_span().start()..name.as_span().start()].trim_end().ends_with('.'),
+        None => false,
+    }
+}
+
+/// Regular (non-variadic) parameters, as counted for trait conformance
+fn parameter_count(pair: &Pair<'_, Rule>) -> usize {
+    pair.clone().into_inner()
+        .filter(|p| p.as_rule() == Rule::parameter_list)
+        .flat_map(|p| p.into_inner())
+        .filter(|p| p.as_rule() == Rule::parameter)
+        .count()
+}
+
+fn signature(pair: &Pair<'_, Rule>) -> Signature {
+    let mut sig = Signature { params: Vec::new(), varargs: false, kwargs: false };
+    let params = pair.clone().into_inner()
+        .filter(|p| p.as_rule() == Rule::parameter_list)
+        .flat_map(|p| p.into_inner());
+    for param in params {
+        match param.as_rule() {
+            Rule::parameter => {
+                let mut parts = param.into_inner();
+                let name = parts.next().unwrap().as_str().to_string();
+                let required = !parts.any(|p| p.as_rule() == Rule::expression);
+                sig.params.push((name, required));
+            }
+            Rule::varargs => sig.varargs = true,
+            _ => sig.kwargs = true,
+        }
+    }
+    sig
+}
+
+/// Imported name, local name, and the import item
+type Import<'s> = (String, String, Pair<'s, Rule>);
+
+/// Path, alias, and selective imports of a use statement
+fn parse_use<'s>(pair: &Pair<'s, Rule>) -> (String, Option<String>, Vec<Import<'s>>) {
+    let mut path = String::new();
+    let mut alias = None;
+    let mut imports = Vec::new();
+    for part in pair.clone().into_inner() {
+        match part.as_rule() {
+            Rule::string => path = crate::string_utils::parse_string(part.as_str()),
+            Rule::identifier => alias = Some(part.as_str().to_string()),
+            Rule::import_list => {
+                for item in part.into_inner() {
+                    let mut names = item.clone().into_inner();
+                    let name = names.next().unwrap().as_str().to_string();
+                    let local = names.next().map(|p| p.as_str().to_string()).unwrap_or_else(|| name.clone());
+                    imports.push((name, local, item));
+                }
+            }
+            _ => {}
+        }
+    }
+    // Without an import list the module is bound under its last path segment
+    if alias.is_none() && imports.is_empty() {
+        let stem = path.rsplit('/').next().unwrap_or(&path);
+        alias = Some(stem.strip_suffix(".q").unwrap_or(stem).to_string());
+    }
+    (path, alias, imports)
+}
+
+/// Names a block declares, including inside try and with bodies (which don't open a scope)
+fn block_names(statements: &[Pair<'_, Rule>]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for statement in statements {
+        let Some(mut stmt) = statement.clone().into_inner().next() else { continue };
+        if stmt.as_rule() == Rule::pub_statement {
+            stmt = stmt.into_inner().next().unwrap();
+        }
+        match stmt.as_rule() {
+            Rule::let_statement => {
+                for binding in stmt.into_inner() {
+                    names.insert(binding.into_inner().next().unwrap().as_str().to_string());
+                }
+            }
+            Rule::const_declaration | Rule::variable_declaration | Rule::type_declaration | Rule::trait_declaration => {
+                names.insert(stmt.into_inner().next().unwrap().as_str().to_string());
+            }
+            Rule::function_declaration => {
+                if let Some(name) = function_name(&stmt) {
+                    names.insert(name.as_str().to_string());
+                }
+            }
+            Rule::use_statement => {
+                let (_, alias, imports) = parse_use(&stmt);
+                names.extend(alias);
+                names.extend(imports.into_iter().map(|(_, local, _)| local));
+            }
+            Rule::try_statement | Rule::with_statement => {
+                let mut inner = Vec::new();
+                for part in stmt.into_inner() {
+                    match part.as_rule() {
+                        Rule::statement => inner.push(part),
+                        Rule::catch_clause | Rule::ensure_clause | Rule::with_item => {
+                            for clause_part in part.into_inner() {
+                                match clause_part.as_rule() {
+                                    Rule::statement => inner.push(clause_part),
+                                    Rule::identifier => { names.insert(clause_part.as_str().to_string()); }
+                                    Rule::as_clause => {
+                                        names.insert(clause_part.into_inner().next().unwrap().as_str().to_string());
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                names.extend(block_names(&inner));
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Summarise a file's top-level declarations
+fn collect_module(statements: &[Pair<'_, Rule>], source: &str) -> ModuleInfo {
+    let mut info = ModuleInfo::default();
+    let mut seen = HashSet::new();
+    let mut shadowed = HashSet::new();
+    for statement in statements {
+        let Some(mut stmt) = statement.clone().into_inner().next() else { continue };
+        let public = stmt.as_rule() == Rule::pub_statement;
+        if public {
+            stmt = stmt.into_inner().next().unwrap();
+        }
+        let mut declared = Vec::new();
+        match stmt.as_rule() {
+            Rule::function_declaration => {
+                if let Some(name) = function_name(&stmt) {
+                    let name = name.as_str().to_string();
+                    if !is_decorated(&stmt) {
+                        info.functions.insert(name.clone(), signature(&stmt));
+                    } else {
+                        shadowed.insert(name.clone());
+                    }
+                    declared.push(name);
+                }
+            }
+            Rule::type_declaration => {
+                let name = stmt.clone().into_inner().next().unwrap().as_str().to_string();
+                info.types.insert(name.clone(), type_info(&stmt, source));
+                declared.push(name);
+            }
+            Rule::trait_declaration => {
+                let mut parts = stmt.into_inner();
+                let name = parts.next().unwrap().as_str().to_string();
+                let methods = parts
+                    .filter(|p| p.as_rule() == Rule::trait_method)
+                    .map(|m| (function_name(&m).unwrap().as_str().to_string(), parameter_count(&m)))
+                    .collect();
+                info.traits.insert(name.clone(), methods);
+                declared.push(name);
+            }
+            Rule::let_statement => {
+                for binding in stmt.into_inner() {
+                    declared.push(binding.into_inner().next().unwrap().as_str().to_string());
+                }
+            }
+            Rule::const_declaration | Rule::variable_declaration => {
+                declared.push(stmt.into_inner().next().unwrap().as_str().to_string());
+            }
+            Rule::use_statement => {
+                let (_, alias, imports) = parse_use(&stmt);
+                declared.extend(alias);
+                declared.extend(imports.into_iter().map(|(_, local, _)| local));
+            }
+            _ => {}
+        }
+        for name in declared {
+            if !seen.insert(name.clone()) {
+                shadowed.insert(name.clone());
+            }
+            if public {
+                info.public.insert(name);
+            }
+        }
+    }
+    // A name bound more than once could refer to either declaration
+    for name in &shadowed {
+        info.functions.remove(name);
+        info.types.remove(name);
+        info.traits.remove(name);
+    }
+    info
+}
+
+fn type_info(pair: &Pair<'_, Rule>, source: &str) -> TypeInfo {
+    let mut info = TypeInfo::default();
+    for member in pair.clone().into_inner().filter(|p| p.as_rule() == Rule::type_member) {
+        let mut parts = member.clone().into_inner();
+        let first = parts.next().unwrap();
+        match first.as_rule() {
+            Rule::identifier => {
+                let mut optional = false;
+                let mut has_default = false;
+                for part in parts {
+                    match part.as_rule() {
+                        Rule::type_expr => {
+                            optional = source[part.as_span().end()..member.as_span().end()].trim_start().starts_with('?');
+                        }
+                        _ => has_default = true,
+                    }
+                }
+                info.fields.push((first.as_str().to_string(), !optional && !has_default));
+            }
+            Rule::function_declaration if is_static_method(&first, source) => {
+                let name = function_name(&first).unwrap().as_str().to_string();
+                let sig = if is_decorated(&first) { None } else { Some(signature(&first)) };
+                info.static_methods.insert(name, sig);
+            }
+            _ => {}
+        }
+    }
+    info
+}
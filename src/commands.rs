@@ -3,6 +3,7 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::rc::Rc;
 use serde::Deserialize;
 use toml;
 use crate::scope::Scope;
@@ -31,6 +32,11 @@ pub struct ProjectConfig {
 
 /// Run a Quest script from source code
 pub fn run_script(source: &str, args: &[String], script_path: Option<&str>) -> Result<(), String> {
+    eval_script(source, args, script_path).map(|_| ())
+}
+
+/// Run a Quest script from source code and return the value of its last statement
+pub fn eval_script(source: &str, args: &[String], script_path: Option<&str>) -> Result<QValue, String> {
     // Set global script args and path for sys module (only set once)
     let _ = SCRIPT_ARGS.set(args.to_vec());
     let _ = SCRIPT_PATH.set(script_path.map(|s| s.to_string()));
@@ -45,6 +51,8 @@ pub fn run_script(source: &str, args: &[String], script_path: Option<&str>) -> R
             .and_then(|p| p.to_str().map(|s| s.to_string()))
             .unwrap_or_else(|| path.to_string());
         *scope.current_script_path.borrow_mut() = Some(canonical_path.clone());
+        scope.source_file = Some(Rc::new(canonical_path.clone()));
+        crate::strict::check_pragma(&canonical_path, source);
         // QEP-057: Set current file for magic variables
         scope.current_file = Some(canonical_path);
     }
@@ -57,7 +65,7 @@ pub fn run_script(source: &str, args: &[String], script_path: Option<&str>) -> R
         .map_err(|e| format!("Parse error: {}", e))?;
 
     // Evaluate each statement in the program
-    let mut _last_result = QValue::Nil(QNil);
+    let mut last_result = QValue::Nil(QNil);
     for pair in pairs {
         // Skip EOI and SOI
         if matches!(pair.as_rule(), Rule::EOI) {
@@ -69,12 +77,12 @@ pub fn run_script(source: &str, args: &[String], script_path: Option<&str>) -> R
                 continue;
             }
             match eval_pair(statement, &mut scope) {
-                Ok(val) => _last_result = val,
-                Err(EvalError::ControlFlow(ControlFlow::FunctionReturn(_))) => {
+                Ok(val) => last_result = val,
+                Err(EvalError::ControlFlow(ControlFlow::FunctionReturn(value))) => {
                     // QEP-056: Top-level return: exit script cleanly (Bug #021 fix)
                     // This allows scripts to use `return` to exit early,
                     // similar to Python, Ruby, and other scripting languages
-                    return Ok(());
+                    return Ok(value);
                 }
                 Err(e) => {
                     // QEP-057: Format error with stack trace if available
@@ -128,9 +136,148 @@ pub fn run_script(source: &str, args: &[String], script_path: Option<&str>) -> R
         }
     }
 
+    Ok(last_result)
+}
+
+/// Handle 'quest run --watch <script> [args...]': a .q file, or a quest.toml script that names one
+pub fn handle_watch_command(target: &str, remaining_args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let script = script_target(target, "--watch")?;
+    crate::watch::watch_script(&script, remaining_args)
+}
+
+/// Handle 'quest run --trace [--trace-calls] [--trace-module=M] [--trace-output=FILE] <file.q|script_name> [args...]'
+pub fn handle_trace_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut options = crate::trace::TraceOptions::default();
+    let mut rest = args;
+    while let Some(arg) = rest.first().filter(|a| a.starts_with("--trace")) {
+        if arg == "--trace-calls" {
+            options.calls = true;
+        } else if let Some(module) = arg.strip_prefix("--trace-module=") {
+            options.modules.push(module.to_string());
+        } else if let Some(path) = arg.strip_prefix("--trace-output=") {
+            options.output = Some(path.to_string());
+        } else if arg != "--trace" {
+            return Err(format!("Unknown option '{}'", arg).into());
+        }
+        rest = &rest[1..];
+    }
+    let Some(target) = rest.first() else {
+        eprintln!("Usage: quest run --trace [--trace-calls] [--trace-module=M] [--trace-output=FILE] <script_name> [args...]");
+        std::process::exit(1);
+    };
+
+    let script = script_target(target, "--trace")?;
+    let source = fs::read_to_string(&script)
+        .map_err(|e| format!("Failed to read file '{}': {}", script.display(), e))?;
+    let mut script_args = vec![script.to_string_lossy().to_string()];
+    script_args.extend_from_slice(&rest[1..]);
+
+    crate::trace::enable(options)?;
+    if let Err(e) = run_script(&source, &script_args, Some(&script.to_string_lossy())) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     Ok(())
 }
 
+/// Handle 'quest profile [OPTIONS] <file.q|script_name> [args...]'
+pub fn handle_profile_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut interval = std::time::Duration::from_millis(1);
+    let mut collapsed_path = None;
+    let mut flamegraph_path = None;
+    let mut top = 20;
+    let mut rest = args;
+
+    while let Some(arg) = rest.first().filter(|a| a.starts_with('-')) {
+        if arg == "--help" || arg == "-h" {
+            println!("Usage: quest profile [OPTIONS] <script.q|script_name> [args...]");
+            println!();
+            println!("Run a script while sampling its Quest call stack, then print the functions");
+            println!("that used the most time to stderr.");
+            println!();
+            println!("Options:");
+            println!("  --interval=MS        Time between samples in milliseconds (default 1)");
+            println!("  --top=N              Number of functions in the report (default 20)");
+            println!("  --collapsed=FILE     Write the stacks in collapsed format (for flamegraph.pl etc.)");
+            println!("  --flamegraph=FILE    Write a flamegraph SVG");
+            println!("  -h, --help           Print help information");
+            return Ok(());
+        } else if let Some(value) = arg.strip_prefix("--interval=") {
+            let ms: f64 = value.parse().ok().filter(|ms: &f64| *ms > 0.0)
+                .ok_or_else(|| format!("Invalid interval '{}'", value))?;
+            interval = std::time::Duration::from_secs_f64(ms / 1000.0);
+        } else if let Some(value) = arg.strip_prefix("--top=") {
+            top = value.parse().map_err(|_| format!("Invalid --top value '{}'", value))?;
+        } else if let Some(path) = arg.strip_prefix("--collapsed=") {
+            collapsed_path = Some(path.to_string());
+        } else if let Some(path) = arg.strip_prefix("--flamegraph=") {
+            flamegraph_path = Some(path.to_string());
+        } else {
+            return Err(format!("Unknown option '{}'", arg).into());
+        }
+        rest = &rest[1..];
+    }
+    let Some(target) = rest.first() else {
+        eprintln!("Usage: quest profile [OPTIONS] <script.q|script_name> [args...]");
+        std::process::exit(1);
+    };
+
+    let script = script_target(target, "profile")?;
+    let source = fs::read_to_string(&script)
+        .map_err(|e| format!("Failed to read file '{}': {}", script.display(), e))?;
+    let mut script_args = vec![script.to_string_lossy().to_string()];
+    script_args.extend_from_slice(&rest[1..]);
+    let name = script.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+    let sampler = crate::profiler::start(interval, &name);
+    let result = run_script(&source, &script_args, Some(&script.to_string_lossy()));
+    drop(sampler);
+
+    let samples = crate::profiler::samples();
+    eprint!("\n{}", crate::profiler::report(&samples, top));
+    if let Some(path) = collapsed_path {
+        fs::write(&path, crate::profiler::collapsed(&samples))
+            .map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+        eprintln!("Wrote collapsed stacks to {}", path);
+    }
+    if let Some(path) = flamegraph_path {
+        let svg = crate::profiler::flamegraph(&samples, &format!("quest profile {}", name))?;
+        fs::write(&path, svg).map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+        eprintln!("Wrote flamegraph to {}", path);
+    }
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The .q file `quest run <flag> <target>` runs: `target` itself if it is a
+/// file, otherwise the quest.toml script of that name
+fn script_target(target: &str, flag: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let direct = PathBuf::from(target);
+    let script = if direct.is_file() {
+        direct
+    } else {
+        let project_path = PathBuf::from("quest.toml");
+        if !project_path.exists() {
+            return Err(format!("No such file '{}' and quest.toml not found in current directory", target).into());
+        }
+        let content = fs::read_to_string(&project_path)?;
+        let project: ProjectConfig = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse quest.toml: {}", e))?;
+        let script_value = project.scripts.as_ref()
+            .and_then(|scripts| scripts.get(target))
+            .ok_or_else(|| format!("Script '{}' not found in quest.toml", target))?;
+        if !script_value.ends_with(".q") {
+            return Err(format!("Script '{}' is not a Quest script; {} only runs .q files", target, flag).into());
+        }
+        project_path.canonicalize()?.parent().map(|dir| dir.join(script_value)).unwrap_or_default()
+    };
+    Ok(script)
+}
+
 /// Handle the 'quest run <script_name>' command
 pub fn handle_run_command(script_name: &str, remaining_args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     // Look for quest.toml
@@ -245,6 +392,7 @@ use "std/test"
 use "std/sys"
 use "std/io" as io
 use "std/toml" as toml
+use "std/process" as process
 
 # Load configuration from quest.toml if it exists
 let config = {}
@@ -271,6 +419,18 @@ let capture_output = get_config("capture", "all")
 let test_paths = get_config("paths", [])
 let filter_tags = get_config("tags", [])
 let skip_tags = get_config("skip_tags", [])
+let update_snapshots = false
+let jobs = get_config("jobs", 1)
+let timeout = get_config("timeout", nil)
+let report_format = get_config("format", "text")
+let report_output = get_config("output", nil)
+let coverage = get_config("coverage", false)
+let coverage_dir = get_config("coverage_dir", "coverage")
+let coverage_file = nil
+let name_filter = nil
+let fail_fast = get_config("fail_fast", false)
+let worker = false
+let cli_paths = []
 
 # Build test paths array from arguments
 let i = 1
@@ -290,7 +450,16 @@ while i < sys.argv.len()
         puts("  --condensed, -c    Enable condensed output (default)")
         puts("  --tag=<name>       Run only tests with this tag")
         puts("  --skip-tag=<name>  Skip tests with this tag")
+        puts("  --filter=<text>    Run only tests whose name, describe or module name contains <text>")
+        puts("  --fail-fast        Stop after the first failing test")
         puts("  --cap=<mode>       Capture output: all (default), no, 0, 1, stdout, stderr")
+        puts("  --update-snapshots Rewrite stored snapshots instead of comparing")
+        puts("  --jobs=<n>, -j<n>  Run test files in <n> worker processes (0 = one per CPU)")
+        puts("  --format=<fmt>     Report format: text (default), json, junit, tap")
+        puts("  --output=<path>    Write the report to <path> and keep text output on stdout")
+        puts("  --timeout=<secs>   Fail a test file that runs longer than <secs> (runs files in workers)")
+        puts("  --coverage         Record line and branch coverage (lcov.info and HTML in coverage/)")
+        puts("  --coverage-dir=<dir> Write coverage reports to <dir>")
         puts("  -h, --help         Print help information")
         sys.exit(0)
     elif arg == "--no-color"
@@ -307,6 +476,43 @@ while i < sys.argv.len()
         # Extract tag name after =
         let tag = arg.slice(11, arg.len())
         skip_tags = skip_tags.concat([tag])
+    elif arg.startswith("--filter=")
+        name_filter = arg.slice(9, arg.len())
+    elif arg == "--filter"
+        # Also accept the text as the next argument
+        if i + 1 >= sys.argv.len()
+            puts("Error: --filter expects a value")
+            sys.exit(1)
+        end
+        i = i + 1
+        name_filter = sys.argv[i]
+    elif arg == "--fail-fast"
+        fail_fast = true
+    elif arg == "--update-snapshots"
+        update_snapshots = true
+    elif arg.startswith("--jobs=")
+        # Extract worker count after =
+        jobs = arg.slice(7, arg.len()).to_int()
+    elif arg.startswith("-j") and arg.len() > 2
+        jobs = arg.slice(2, arg.len()).to_int()
+    elif arg.startswith("--format=")
+        report_format = arg.slice(9, arg.len())
+    elif arg.startswith("--output=")
+        report_output = arg.slice(9, arg.len())
+    elif arg.startswith("--timeout=")
+        timeout = arg.slice(10, arg.len()).to_float()
+    elif arg == "--coverage"
+        coverage = true
+    elif arg.startswith("--coverage-dir=")
+        coverage = true
+        coverage_dir = arg.slice(15, arg.len())
+    elif arg.startswith("--coverage-file=")
+        # Internal: where a worker writes its coverage for the parent to merge
+        coverage = true
+        coverage_file = arg.slice(16, arg.len())
+    elif arg == "--worker"
+        # Internal: run the given files and report counts to a parent runner
+        worker = true
     elif arg.startswith("--cap=")
         # Extract capture mode after =
         let mode = arg.slice(6, arg.len())
@@ -319,11 +525,18 @@ while i < sys.argv.len()
         sys.exit(1)
     else
         # It's a test path (file or directory)
-        test_paths = test_paths.concat([arg])
+        cli_paths = cli_paths.concat([arg])
     end
     i = i + 1
 end
 
+if worker
+    # Workers only run the files the parent hands them
+    test_paths = cli_paths
+else
+    test_paths = test_paths.concat(cli_paths)
+end
+
 # If no paths specified, default to test directory or current directory
 if test_paths.len() == 0
     if io.is_dir("test")
@@ -350,10 +563,90 @@ if skip_tags.len() > 0
     test.set_skip_tags(skip_tags)
 end
 
+if name_filter != nil
+    test.set_filter(name_filter)
+end
+
+if fail_fast
+    test.set_fail_fast(true)
+end
+
 # Set output capture mode
 test.set_capture(capture_output)
 
+if update_snapshots
+    test.set_update_snapshots(true)
+end
+
+try
+    test.set_format(report_format)
+catch e
+    puts("Error: " .. e.message())
+    sys.exit(1)
+end
+
+# With a report format and no --output file, the report is the only thing
+# printed: buffer the human-readable output and drop it at the end
+let quiet_guard = nil
+if report_format != "text" and report_output == nil and not worker
+    quiet_guard = sys.redirect_stream(sys.stdout, io.StringIO.new())
+end
+
+if coverage
+    sys.coverage_start()
+end
+
+# Print the summary (or report) and exit with the number of failures
+fun finish()
+    let status = test.stats()
+    if coverage
+        write_coverage()
+    end
+    if report_format != "text"
+        let report = test.report()
+        if quiet_guard != nil
+            quiet_guard.restore()
+            puts(report)
+        else
+            io.write(report_output, report .. "\n")
+        end
+    end
+    sys.exit(status)
+end
+
 let tests = test.find_tests(test_paths)
+let worker_coverage_dir = coverage_dir .. "/workers"
+
+# Write lcov.info and the HTML report for the project's files (not the test
+# files themselves), merging in whatever parallel workers recorded
+fun write_coverage()
+    let merge = []
+    if io.is_dir(worker_coverage_dir)
+        merge = io.glob(worker_coverage_dir .. "/*.info")
+    end
+    let summary = sys.coverage_report({
+        "lcov": coverage_dir .. "/lcov.info",
+        "html": coverage_dir,
+        "merge": merge,
+        "include": ["."],
+        "exclude": tests
+    })
+    if io.is_dir(worker_coverage_dir)
+        io.remove(worker_coverage_dir)
+    end
+
+    fun percent(hit, found)
+        if found == 0
+            return "-"
+        end
+        ((hit * 1000 + found / 2) / found / 10.0).str() .. "%"
+    end
+    puts("Coverage: " .. percent(summary["lines_hit"], summary["lines_found"]) .. " of lines ("
+        .. summary["lines_hit"].str() .. "/" .. summary["lines_found"].str() .. "), "
+        .. percent(summary["branches_hit"], summary["branches_found"]) .. " of branches ("
+        .. summary["branches_hit"].str() .. "/" .. summary["branches_found"].str() .. ") - "
+        .. coverage_dir .. "/index.html")
+end
 
 # Only filter out directories if we're scanning from current directory
 # If user specified specific paths, run all found tests
@@ -379,12 +672,112 @@ else
     filtered_tests = tests
 end
 
-filtered_tests.each(fun (t)
+# Run files in worker processes with --jobs or --timeout; each worker is a
+# separate interpreter, so files can't leak state into each other
+if not worker and (jobs != 1 or timeout != nil) and filtered_tests.len() > 0
+    test.start_timer()
+    let worker_args = [sys.executable, "test", "--worker", "--cap=" .. capture_output]
+    if not use_colors
+        worker_args.push("--no-color")
+    end
+    if use_condensed
+        worker_args.push("--condensed")
+    else
+        worker_args.push("--verbose")
+    end
+    filter_tags.each(fun (tag) worker_args.push("--tag=" .. tag) end)
+    skip_tags.each(fun (tag) worker_args.push("--skip-tag=" .. tag) end)
+    if name_filter != nil
+        worker_args.push("--filter=" .. name_filter)
+    end
+    if fail_fast
+        worker_args.push("--fail-fast")
+    end
+    if update_snapshots
+        worker_args.push("--update-snapshots")
+    end
+    if report_format != "text"
+        worker_args.push("--format=" .. report_format)
+    end
+    if coverage and io.is_dir(worker_coverage_dir)
+        io.remove(worker_coverage_dir)
+    end
+
+    let options = {}
+    if jobs > 0
+        options["jobs"] = jobs
+    end
+    if timeout != nil
+        options["timeout"] = timeout
+    end
+
+    # Print each file's output in discovery order as soon as it and every file
+    # before it have finished. Counts are merged as files are printed, so with
+    # --fail-fast nothing after the first failing file is reported.
+    let outputs = filtered_tests.map(fun (t) nil end)
+    let next_to_print = 0
+    options["on_complete"] = fun (index, result)
+        let path = filtered_tests[index]
+        let output = nil
+        if result.timed_out()
+            output = fun () test.record_file_failure(path, "timed out after " .. timeout.str() .. "s") end
+        else
+            output = fun ()
+                let merged = test.merge_worker_output(result.stdout())
+                if merged == nil
+                    print(result.stdout())
+                    print(result.stderr())
+                    test.record_file_failure(path, "worker exited with code " .. result.code().str())
+                else
+                    print(merged)
+                    print(result.stderr())
+                end
+            end
+        end
+        outputs[index] = output
+        while next_to_print < outputs.len() and outputs[next_to_print] != nil
+            let emit = outputs[next_to_print]
+            emit()
+            next_to_print = next_to_print + 1
+            if test.should_stop()
+                # Raising from on_complete kills the workers still running
+                raise RuntimeErr.new("stopped after the first failure (--fail-fast)")
+            end
+        end
+    end
+
+    let commands = []
+    for index in 0 until filtered_tests.len()
+        let command = worker_args.concat([])
+        if coverage
+            command.push("--coverage-file=" .. worker_coverage_dir .. "/" .. index.str() .. ".info")
+        end
+        command.push(filtered_tests[index])
+        commands.push(command)
+    end
+    try
+        process.run_all(commands, options)
+    catch e
+        if not test.should_stop()
+            raise e
+        end
+    end
+    finish()
+end
+
+for t in filtered_tests
+    if test.should_stop()
+        break
+    end
     # Loading the module automatically executes it, registering the tests
     try
+        test.set_current_file(t)
         sys.load_module(t)
     catch e
         # Module loading failed - print error with context
+        if quiet_guard != nil
+            quiet_guard.restore()
+        end
         puts("\n" .. test.red("✗ Failed to load module: " .. t))
         puts("  " .. test.red("Error: " .. e.type() .. ": " .. e.message()))
 
@@ -401,11 +794,18 @@ filtered_tests.each(fun (t)
         puts("")
         sys.exit(1)
     end
-end)
+end
+
+if worker
+    if coverage_file != nil
+        sys.coverage_report({"lcov": coverage_file})
+    end
+    test.worker_summary()
+    sys.exit(0)
+end
 
 # Print overall summary
-let status = test.stats()
-sys.exit(status)
+finish()
 "#;
 
     // Create a temporary vector with "quest test" as argv[0] and pass all args
@@ -423,6 +823,634 @@ sys.exit(status)
         })
 }
 
+/// Handle the 'quest bench [OPTIONS] [PATHS...]' command
+/// Discovers bench_*.q files and runs them with std/bench configured from the
+/// command line and the [bench] section of quest.toml
+pub fn handle_bench_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let bench_script = r#"
+use "std/bench"
+use "std/sys"
+use "std/io" as io
+use "std/toml" as toml
+
+let config = {}
+if io.exists("quest.toml")
+    let parsed = toml.parse(io.read("quest.toml"))
+    if parsed.contains("bench")
+        config = parsed["bench"]
+    end
+end
+
+fun get_config(key, default)
+    if config.contains(key)
+        return config[key]
+    end
+    return default
+end
+
+let paths = get_config("paths", [])
+let samples = get_config("samples", nil)
+let min_time = get_config("min_time", nil)
+let warmup = get_config("warmup", nil)
+let filter = nil
+let use_colors = get_config("colors", true)
+
+let i = 1
+while i < sys.argv.len()
+    let arg = sys.argv[i]
+    if arg == "--help" or arg == "-h"
+        puts("Usage: quest bench [OPTIONS] [PATHS...]")
+        puts("")
+        puts("Run Quest benchmarks (bench_*.q files)")
+        puts("")
+        puts("Arguments:")
+        puts("  [PATHS...]  Benchmark files or directories (default: bench/, benches/ or ./)")
+        puts("")
+        puts("Options:")
+        puts("  --filter=<text>     Run only benchmarks whose name contains <text>")
+        puts("  --samples=<n>       Timed samples per benchmark (default 10)")
+        puts("  --min-time=<secs>   Seconds spent measuring each benchmark (default 1.0)")
+        puts("  --warmup=<secs>     Seconds spent warming up each benchmark (default 0.2)")
+        puts("  --no-color          Disable colored output")
+        puts("  -h, --help          Print help information")
+        sys.exit(0)
+    elif arg == "--no-color"
+        use_colors = false
+    elif arg.startswith("--filter=")
+        filter = arg.slice(9, arg.len())
+    elif arg.startswith("--samples=")
+        samples = arg.slice(10, arg.len()).to_int()
+    elif arg.startswith("--min-time=")
+        min_time = arg.slice(11, arg.len()).to_float()
+    elif arg.startswith("--warmup=")
+        warmup = arg.slice(9, arg.len()).to_float()
+    elif arg.startswith("-")
+        puts("Error: Unknown flag '" .. arg .. "'")
+        puts("")
+        puts("Run 'quest bench --help' for usage information")
+        sys.exit(1)
+    else
+        paths = paths.concat([arg])
+    end
+    i = i + 1
+end
+
+if paths.len() == 0
+    if io.is_dir("bench")
+        paths = ["bench"]
+    elif io.is_dir("benches")
+        paths = ["benches"]
+    else
+        paths = ["."]
+    end
+end
+
+bench.configure(samples: samples, min_time: min_time, warmup: warmup, filter: filter, colors: use_colors)
+
+let files = bench.find_benches(paths)
+if files.len() == 0
+    puts("No benchmark files (bench_*.q) found in " .. paths.join(", "))
+    sys.exit(1)
+end
+
+for file in files
+    puts(file)
+    try
+        sys.load_module(file)
+    catch e
+        puts("  Error: " .. e.type() .. ": " .. e.message())
+        sys.exit(1)
+    end
+    puts("")
+end
+
+puts(bench.results().len().str() .. " benchmark(s) in " .. files.len().str() .. " file(s)")
+"#;
+
+    let mut bench_args = vec!["quest bench".to_string()];
+    bench_args.extend_from_slice(args);
+
+    run_script(bench_script, &bench_args, Some("<bench command>"))
+        .map_err(|e| {
+            if e.starts_with("Error: ") || e.contains(": ") {
+                e.into()
+            } else {
+                format!("Error: {}", e).into()
+            }
+        })
+}
+
+/// Handle the 'quest fmt [OPTIONS] [PATHS...]' command
+/// Rewrites .q files in canonical style, or with --check only reports the
+/// files that would change (exit status 1) so CI can enforce it
+/// Handle one-liners: quest -e CODE, quest -p CODE, quest -r MODULE
+pub fn handle_eval_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut code = Vec::new();
+    let mut modules = Vec::new();
+    let mut print_result = false;
+    let mut script_args = vec!["-e".to_string()];
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--help" || arg == "-h" {
+            println!("Usage: quest [-r MODULE]... (-e CODE | -p CODE)... [ARGS...]");
+            println!();
+            println!("Run Quest code given on the command line");
+            println!();
+            println!("Options:");
+            println!("  -e <code>        Run code (may be repeated; the pieces run as one program)");
+            println!("  -p <code>        Like -e, then print the value of the last statement unless it is nil");
+            println!("  -r <module>      Load a module first, as `use`; bare names are std modules (-r json is std/encoding/json)");
+            println!("  -h, --help       Print help information");
+            println!();
+            println!("Arguments after the code are available as sys.argv[1..].");
+            return Ok(());
+        } else if matches!(arg.as_str(), "-e" | "-p" | "-r") {
+            let Some(value) = args.get(i + 1) else {
+                eprintln!("Error: {} requires a value", arg);
+                std::process::exit(1);
+            };
+            match arg.as_str() {
+                "-r" => modules.push(value.clone()),
+                flag => {
+                    print_result |= flag == "-p";
+                    code.push(value.clone());
+                }
+            }
+            i += 2;
+        } else {
+            // The rest belongs to the program
+            let rest = if arg == "--" { &args[i + 1..] } else { &args[i..] };
+            script_args.extend(rest.iter().cloned());
+            break;
+        }
+    }
+    if code.is_empty() {
+        eprintln!("Error: No code given (use -e CODE or -p CODE)");
+        eprintln!();
+        eprintln!("Run 'quest -e --help' for usage information");
+        std::process::exit(1);
+    }
+
+    let mut source = String::new();
+    for module in &modules {
+        source.push_str(&format!("use \"{}\"\n", one_liner_module_path(module)));
+    }
+    source.push_str(&code.join("\n"));
+
+    match eval_script(&source, &script_args, None) {
+        Ok(value) => {
+            if print_result && !matches!(value, QValue::Nil(_)) {
+                println!("{}", value.as_str());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `use` path for a -r argument: paths are kept, bare names are std modules
+fn one_liner_module_path(module: &str) -> String {
+    if let Some(local) = module.strip_prefix("./") {
+        // No script file to be relative to; the current directory is searched first anyway
+        return local.to_string();
+    }
+    if module.contains('/') || module.ends_with(".q") {
+        return module.to_string();
+    }
+    if crate::module_loader::ENCODING_MODULES.contains(&module) {
+        format!("std/encoding/{}", module)
+    } else {
+        format!("std/{}", module)
+    }
+}
+
+pub fn handle_fmt_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut check = false;
+    let mut width = crate::formatter::DEFAULT_WIDTH;
+    let mut paths = Vec::new();
+
+    for arg in args {
+        if arg == "--help" || arg == "-h" {
+            println!("Usage: quest fmt [OPTIONS] [PATHS...]");
+            println!();
+            println!("Format Quest source files (*.q) in place");
+            println!();
+            println!("Arguments:");
+            println!("  [PATHS...]  Files or directories to format (default: .). Use - for stdin to stdout");
+            println!();
+            println!("Options:");
+            println!("  --check          Don't write files; list the ones that need formatting and exit 1");
+            println!("  --width=<n>      Maximum line width before long lines are wrapped (default {})", crate::formatter::DEFAULT_WIDTH);
+            println!("  -h, --help       Print help information");
+            return Ok(());
+        } else if arg == "--check" {
+            check = true;
+        } else if let Some(value) = arg.strip_prefix("--width=") {
+            width = value.parse().map_err(|_| format!("Error: Invalid width '{}'", value))?;
+        } else if arg.starts_with('-') && arg != "-" {
+            eprintln!("Error: Unknown flag '{}'", arg);
+            eprintln!();
+            eprintln!("Run 'quest fmt --help' for usage information");
+            std::process::exit(1);
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    if paths.iter().any(|p| p.as_os_str() == "-") {
+        let mut source = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut source)?;
+        match crate::formatter::format_source(&source, width) {
+            Ok(formatted) if check => {
+                if formatted != source {
+                    std::process::exit(1);
+                }
+            }
+            Ok(formatted) => print!("{}", formatted),
+            Err(e) => {
+                eprintln!("<stdin>: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path.is_dir() {
+            collect_quest_files(path, &mut files)?;
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            return Err(format!("Error: No such file or directory '{}'", path.display()).into());
+        }
+    }
+    files.sort();
+
+    let mut changed = 0;
+    let mut failed = 0;
+    for file in &files {
+        let source = fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read file '{}': {}", file.display(), e))?;
+        match crate::formatter::format_source(&source, width) {
+            Ok(formatted) if formatted == source => {}
+            Ok(formatted) => {
+                changed += 1;
+                if check {
+                    println!("Would reformat {}", file.display());
+                } else {
+                    fs::write(file, formatted)
+                        .map_err(|e| format!("Failed to write file '{}': {}", file.display(), e))?;
+                    println!("Formatted {}", file.display());
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("{}: {}", file.display(), e);
+            }
+        }
+    }
+
+    if check && changed > 0 {
+        println!("{} of {} file(s) need formatting", changed, files.len());
+    }
+    if failed > 0 || (check && changed > 0) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+pub fn handle_check_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut paths = Vec::new();
+
+    for arg in args {
+        if arg == "--help" || arg == "-h" {
+            println!("Usage: quest check [PATHS...]");
+            println!();
+            println!("Parse Quest source files (*.q) and the local modules they use, without running them,");
+            println!("and report undefined names, unknown type annotations, incomplete trait");
+            println!("implementations and calls whose arguments don't match the function");
+            println!();
+            println!("Arguments:");
+            println!("  [PATHS...]  Files or directories to check (default: .)");
+            println!();
+            println!("Options:");
+            println!("  -h, --help       Print help information");
+            println!();
+            println!("Exits with status 1 if any problem is found.");
+            return Ok(());
+        } else if arg.starts_with('-') {
+            eprintln!("Error: Unknown flag '{}'", arg);
+            eprintln!();
+            eprintln!("Run 'quest check --help' for usage information");
+            std::process::exit(1);
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path.is_dir() {
+            collect_quest_files(path, &mut files)?;
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            return Err(format!("Error: No such file or directory '{}'", path.display()).into());
+        }
+    }
+    files.sort();
+
+    let mut checker = crate::checker::Checker::new();
+    for file in &files {
+        checker.check_file(file);
+    }
+    for diagnostic in &checker.diagnostics {
+        println!("{}", diagnostic);
+    }
+
+    let problems = checker.diagnostics.len();
+    if problems > 0 {
+        println!("{} problem(s) in {} file(s) checked", problems, checker.files_checked());
+        std::process::exit(1);
+    }
+    println!("{} file(s) checked, no problems found", checker.files_checked());
+    Ok(())
+}
+
+/// Quest files under a directory, skipping hidden directories, target/ and quest_modules/
+fn collect_quest_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" && name != crate::package::MODULES_DIR {
+                collect_quest_files(&path, files)?;
+            }
+        } else if name.ends_with(".q") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Handle the 'quest debug' command
+pub fn handle_debug_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut dap = false;
+    let mut port = None;
+    let mut rest = args;
+
+    while let Some(arg) = rest.first() {
+        match arg.as_str() {
+            "--help" | "-h" => {
+                println!("Usage: quest debug <script.q> [args...]");
+                println!("       quest debug --dap [--port PORT]");
+                println!();
+                println!("Run a script under the interactive debugger. It stops before the first");
+                println!("statement; type 'help' at the (qdb) prompt for commands (breakpoints,");
+                println!("step/next/finish/continue, print, locals, backtrace).");
+                println!();
+                println!("Options:");
+                println!("  --dap            Serve the Debug Adapter Protocol on stdin/stdout for an editor;");
+                println!("                   the script comes from the client's launch request");
+                println!("  --port PORT      With --dap, listen on 127.0.0.1:PORT instead of stdio");
+                println!("  -h, --help       Print help information");
+                return Ok(());
+            }
+            "--dap" => dap = true,
+            "--port" => {
+                let value = rest.get(1).ok_or("--port needs a value")?;
+                port = Some(value.parse::<u16>().map_err(|_| format!("Invalid port '{}'", value))?);
+                rest = &rest[1..];
+            }
+            _ => break,
+        }
+        rest = &rest[1..];
+    }
+
+    if dap {
+        return crate::dap::serve(port);
+    }
+    let Some(script) = rest.first() else {
+        eprintln!("Usage: quest debug <script.q> [args...]");
+        std::process::exit(1);
+    };
+
+    println!("Debugging {}; type 'help' for commands", script);
+    match crate::debugger::run(Box::new(crate::debugger::Cli::default()), script, rest, true) {
+        Ok(_) => {
+            println!("Program finished");
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Handle the 'quest install' command
+pub fn handle_install_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut update = false;
+
+    for arg in args {
+        if arg == "--help" || arg == "-h" {
+            println!("Usage: quest install [OPTIONS]");
+            println!();
+            println!("Install the [dependencies] listed in quest.toml into quest_modules/ and");
+            println!("record the exact versions in quest.lock. Once installed, a package is");
+            println!("loaded with `use \"name\"` (or `use \"name/module\"` for one of its files).");
+            println!();
+            println!("Dependency forms:");
+            println!("  utils = {{ path = \"../utils\" }}");
+            println!("  http_extra = {{ git = \"https://example.com/http_extra.git\", tag = \"v1.2.0\" }}");
+            println!("  colors = \"1.2\"          (registry version; needs `registry` or QUEST_REGISTRY)");
+            println!();
+            println!("Options:");
+            println!("  -u, --update     Ignore quest.lock and resolve every dependency again");
+            println!("  -h, --help       Print help information");
+            return Ok(());
+        } else if arg == "--update" || arg == "-u" {
+            update = true;
+        } else {
+            eprintln!("Error: Unknown argument '{}'", arg);
+            eprintln!();
+            eprintln!("Run 'quest install --help' for usage information");
+            std::process::exit(1);
+        }
+    }
+
+    if !Path::new(crate::package::MANIFEST_FILE).exists() {
+        return Err("quest.toml not found in current directory".into());
+    }
+    let project_dir = std::env::current_dir()?;
+    let packages = crate::package::install(&project_dir, update)?;
+    println!("{} package(s) installed; wrote {}", packages.len(), crate::package::LOCK_FILE);
+    Ok(())
+}
+
+pub fn handle_migrate_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let migrate_script = r#"
+use "std/db/migrate"
+use "std/sys"
+use "std/io" as io
+use "std/os" as os
+use "std/toml" as toml
+
+let config = {}
+if io.exists("quest.toml")
+    let parsed = toml.parse(io.read("quest.toml"))
+    if parsed.contains("migrate")
+        config = parsed["migrate"]
+    end
+end
+
+fun get_config(key, default)
+    if config.contains(key)
+        return config[key]
+    end
+    return default
+end
+
+fun usage_error(message)
+    puts("Error: " .. message)
+    puts("")
+    puts("Run 'quest migrate --help' for usage information")
+    sys.exit(1)
+end
+
+let database = os.getenv("DATABASE_URL") ?: get_config("database", nil)
+let dir = get_config("dir", migrate.DEFAULT_DIR)
+let action = nil
+let positional = []
+let steps = 1
+let target = nil
+let sql = false
+
+let i = 1
+while i < sys.argv.len()
+    let arg = sys.argv[i]
+    if arg == "--help" or arg == "-h"
+        puts("Usage: quest migrate [COMMAND] [OPTIONS]")
+        puts("")
+        puts("Apply and roll back schema migrations (<version>_<name>.q or .sql files)")
+        puts("")
+        puts("Commands:")
+        puts("  status       List migrations and whether they are applied (default)")
+        puts("  up           Apply pending migrations")
+        puts("  down         Roll back the most recent migration")
+        puts("  new <name>   Create an empty migration named <timestamp>_<name>.q")
+        puts("")
+        puts("Options:")
+        puts("  --database=<url>    Database URL (default: $DATABASE_URL or [migrate] database in quest.toml)")
+        puts("  --dir=<path>        Migrations directory (default: migrations)")
+        puts("  --target=<version>  up: stop after <version>; down: roll back everything after <version>")
+        puts("  --steps=<n>         down: number of migrations to roll back (default 1)")
+        puts("  --sql               new: create a .sql migration")
+        puts("  -h, --help          Print help information")
+        puts("")
+        puts("Database URLs: postgres://..., mysql://..., sqlite://<path> or a SQLite file path")
+        sys.exit(0)
+    elif arg.startswith("--database=")
+        database = arg.slice(11, arg.len())
+    elif arg.startswith("--dir=")
+        dir = arg.slice(6, arg.len())
+    elif arg.startswith("--target=")
+        target = arg.slice(9, arg.len()).to_int()
+    elif arg.startswith("--steps=")
+        steps = arg.slice(8, arg.len()).to_int()
+    elif arg == "--sql"
+        sql = true
+    elif arg.startswith("-")
+        usage_error("Unknown flag '" .. arg .. "'")
+    elif action == nil
+        action = arg
+    else
+        positional.push(arg)
+    end
+    i = i + 1
+end
+
+action = action ?: "status"
+
+if action == "new"
+    if positional.len() == 0
+        usage_error("quest migrate new expects a migration name")
+    end
+    puts("Created " .. migrate.create(positional.join(" "), dir: dir, sql: sql))
+    sys.exit(0)
+end
+
+if action != "status" and action != "up" and action != "down"
+    usage_error("Unknown command '" .. action .. "'")
+end
+if database == nil
+    usage_error("No database given; pass --database=<url>, set DATABASE_URL or add [migrate] database to quest.toml")
+end
+
+let conn = migrate.connect(database)
+
+if action == "status"
+    let statuses = migrate.status(conn, dir: dir)
+    if statuses.len() == 0
+        puts("No migrations found in " .. dir)
+    end
+    let pending = 0
+    for s in statuses
+        if s.applied
+            puts("  [x] " .. s.str())
+        else
+            puts("  [ ] " .. s.str())
+            pending = pending + 1
+        end
+    end
+    puts("")
+    puts(pending.str() .. " pending migration(s)")
+elif action == "up"
+    let todo = migrate.pending(conn, dir: dir, target: target)
+    if todo.len() == 0
+        puts("Database is up to date")
+    end
+    for m in todo
+        puts("Applying " .. m.str())
+        migrate.apply(conn, m)
+    end
+else
+    let done = migrate.down(conn, dir: dir, steps: steps, target: target)
+    if done.len() == 0
+        puts("Nothing to roll back")
+    end
+    for m in done
+        puts("Rolled back " .. m.str())
+    end
+end
+conn.close()
+"#;
+
+    let mut migrate_args = vec!["quest migrate".to_string()];
+    migrate_args.extend_from_slice(args);
+
+    run_script(migrate_script, &migrate_args, Some("<migrate command>"))
+        .map_err(|e| {
+            if e.starts_with("Error: ") || e.contains(": ") {
+                e.into()
+            } else {
+                format!("Error: {}", e).into()
+            }
+        })
+}
+
 /// Load web configuration from Quest script (QEP-051)
 /// Executes the script to load std/web module and extract configuration
 ///
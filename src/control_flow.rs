@@ -22,7 +22,7 @@
 //
 // ============================================================================
 
-use crate::types::QValue;
+use crate::types::{QException, QValue};
 use std::fmt;
 
 /// Control flow signals for the evaluator
@@ -87,6 +87,7 @@ impl ControlFlow {
 /// - Ok(QValue): Normal evaluation succeeded
 /// - Err(EvalError::ControlFlow(cf)): Control flow signal (return/break/continue)
 /// - Err(EvalError::Runtime(msg)): Actual runtime error
+/// - Err(EvalError::Raised(exc)): Exception raised by a `raise` statement
 #[derive(Debug, Clone)]
 pub enum EvalError {
     /// Control flow signal (return, break, continue)
@@ -94,6 +95,13 @@ pub enum EvalError {
 
     /// Actual runtime error
     Runtime(String),
+
+    /// Exception raised by a `raise` statement (QEP-037)
+    ///
+    /// Carries the exception itself, so the catch clause that handles it
+    /// binds the original value of a user-defined error type, even when it
+    /// was raised in another function or module.
+    Raised(Box<QException>),
 }
 
 impl EvalError {
@@ -102,6 +110,11 @@ impl EvalError {
         EvalError::Runtime(msg.into())
     }
 
+    /// Create a raised exception
+    pub fn raised(exc: QException) -> Self {
+        EvalError::Raised(Box::new(exc))
+    }
+
     /// Create a function return control flow
     pub fn function_return(val: QValue) -> Self {
         EvalError::ControlFlow(ControlFlow::FunctionReturn(val))
@@ -122,9 +135,9 @@ impl EvalError {
         matches!(self, EvalError::ControlFlow(_))
     }
 
-    /// Check if this is a runtime error
+    /// Check if this is a runtime error (including a raised exception)
     pub fn is_runtime(&self) -> bool {
-        matches!(self, EvalError::Runtime(_))
+        matches!(self, EvalError::Runtime(_) | EvalError::Raised(_))
     }
 
     /// Extract control flow if present
@@ -143,11 +156,20 @@ impl EvalError {
         }
     }
 
+    /// Extract the raised exception if present
+    pub fn as_raised(&self) -> Option<&QException> {
+        match self {
+            EvalError::Raised(exc) => Some(exc),
+            _ => None,
+        }
+    }
+
     /// Convert to string representation
     pub fn to_string(&self) -> String {
         match self {
             EvalError::ControlFlow(cf) => format!("ControlFlow::{:?}", cf),
             EvalError::Runtime(msg) => msg.clone(),
+            EvalError::Raised(exc) => format!("{}: {}", exc.exception_type, exc.message),
         }
     }
 }
@@ -171,6 +193,7 @@ impl From<EvalError> for String {
         match err {
             EvalError::ControlFlow(cf) => format!("ControlFlow::{:?}", cf),
             EvalError::Runtime(msg) => msg,
+            EvalError::Raised(exc) => format!("{}: {}", exc.exception_type, exc.message),
         }
     }
 }
@@ -181,6 +204,7 @@ impl fmt::Display for EvalError {
         match self {
             EvalError::ControlFlow(cf) => write!(f, "ControlFlow::{:?}", cf),
             EvalError::Runtime(msg) => write!(f, "{}", msg),
+            EvalError::Raised(exc) => write!(f, "{}: {}", exc.exception_type, exc.message),
         }
     }
 }
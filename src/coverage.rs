@@ -0,0 +1,349 @@
+// Code coverage collection for `quest test --coverage`
+//
+// The evaluators call record_line() for every statement they run and
+// record_branch() for every if/elif/else decision. Pair lines are relative to
+// the text that was parsed, so they are mapped back to files through
+// Scope::source_file and Scope::line_offset (function bodies are re-parsed from
+// their own text when called). Results are written as lcov tracefiles, which is
+// also how parallel test workers hand their data to the parent runner, plus a
+// static HTML report.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use pest::Parser;
+
+use crate::scope::Scope;
+use crate::types::QUserFun;
+use crate::{QuestParser, Rule};
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    static HITS: RefCell<HashMap<Rc<String>, FileCoverage>> = RefCell::new(HashMap::new());
+}
+
+/// Line and branch counts for one source file
+#[derive(Debug, Clone, Default)]
+pub struct FileCoverage {
+    /// Executable line -> times executed
+    pub lines: BTreeMap<usize, u64>,
+    /// Line of an `if` -> times each branch was taken (if, elifs..., else)
+    pub branches: BTreeMap<usize, Vec<u64>>,
+}
+
+impl FileCoverage {
+    fn merge(&mut self, other: &FileCoverage) {
+        for (line, hits) in &other.lines {
+            *self.lines.entry(*line).or_insert(0) += hits;
+        }
+        for (line, taken) in &other.branches {
+            let counts = self.branches.entry(*line).or_default();
+            if counts.len() < taken.len() {
+                counts.resize(taken.len(), 0);
+            }
+            for (i, n) in taken.iter().enumerate() {
+                counts[i] += n;
+            }
+        }
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&n| n > 0).count()
+    }
+
+    pub fn branches_found(&self) -> usize {
+        self.branches.values().map(|b| b.len()).sum()
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.branches.values().flatten().filter(|&&n| n > 0).count()
+    }
+}
+
+/// Coverage for a set of files, keyed by canonical path
+pub type CoverageData = BTreeMap<String, FileCoverage>;
+
+/// Start recording coverage for the rest of the process
+pub fn enable() {
+    ENABLED.with(|e| e.set(true));
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.with(|e| e.get())
+}
+
+/// Record that the statement starting at `line` (relative to the parsed text) ran
+pub fn record_line(scope: &Scope, line: usize) {
+    let Some(file) = &scope.source_file else {
+        return;
+    };
+    let line = line + scope.line_offset;
+    HITS.with(|hits| {
+        let mut hits = hits.borrow_mut();
+        let file_cov = hits.entry(Rc::clone(file)).or_default();
+        *file_cov.lines.entry(line).or_insert(0) += 1;
+    });
+}
+
+/// Record that branch `taken` of the `if` starting at `line` (relative to the
+/// parsed text) ran. `total` counts the if, every elif and the (possibly
+/// implicit) else.
+pub fn record_branch(scope: &Scope, line: usize, taken: usize, total: usize) {
+    let Some(file) = &scope.source_file else {
+        return;
+    };
+    let line = line + scope.line_offset;
+    HITS.with(|hits| {
+        let mut hits = hits.borrow_mut();
+        let counts = hits.entry(Rc::clone(file)).or_default().branches.entry(line).or_default();
+        if counts.len() < total {
+            counts.resize(total, 0);
+        }
+        counts[taken] += 1;
+    });
+}
+
+/// Number of branches of an if_statement pair: the if, each elif and the else
+pub fn branch_count(if_pair: &pest::iterators::Pair<Rule>) -> usize {
+    2 + if_pair.clone().into_inner().filter(|p| p.as_rule() == Rule::elif_clause).count()
+}
+
+/// Point a new function at the file it was defined in, so lines of its
+/// re-parsed body map back to the file. `def_str` is the text of the
+/// definition and `def_line` its line in the text being evaluated.
+pub fn tag_function(func: &mut QUserFun, scope: &Scope, def_str: &str, def_line: usize) {
+    // The body is the slice just before the closing `end`, so search backwards
+    let search = def_str.trim_end();
+    let search = search.strip_suffix("end").unwrap_or(search);
+    let body_start = if func.body.is_empty() { 0 } else { search.rfind(func.body.as_str()).unwrap_or(0) };
+    let newlines = def_str[..body_start].matches('\n').count();
+    func.source_file = scope.source_file.clone();
+    func.line_offset = scope.line_offset + def_line - 1 + newlines;
+}
+
+/// Everything recorded so far, with never-executed lines and branches of each
+/// file filled in from its source. Files that can't be read (the runner
+/// script, embedded modules) are left out.
+pub fn collect() -> CoverageData {
+    let recorded: Vec<(String, FileCoverage)> = HITS.with(|hits| {
+        hits.borrow().iter().map(|(file, cov)| (file.to_string(), cov.clone())).collect()
+    });
+
+    let mut data = CoverageData::new();
+    for (file, runtime) in recorded {
+        let Some(mut file_cov) = analyze_file(&file) else {
+            continue;
+        };
+        file_cov.merge(&runtime);
+        data.entry(file).or_default().merge(&file_cov);
+    }
+    data
+}
+
+/// Executable lines (with zero hits) and if-branches of a source file
+fn analyze_file(path: &str) -> Option<FileCoverage> {
+    let source = fs::read_to_string(path).ok()?;
+    let pairs = QuestParser::parse(Rule::program, source.trim_end()).ok()?;
+    let mut cov = FileCoverage::default();
+    let mut stack: Vec<_> = pairs.collect();
+    while let Some(pair) = stack.pop() {
+        match pair.as_rule() {
+            Rule::statement => {
+                cov.lines.insert(pair.as_span().start_pos().line_col().0, 0);
+            }
+            Rule::if_statement => {
+                let line = pair.as_span().start_pos().line_col().0;
+                cov.branches.insert(line, vec![0; branch_count(&pair)]);
+            }
+            _ => {}
+        }
+        stack.extend(pair.into_inner());
+    }
+    Some(cov)
+}
+
+/// Merge `from` into `into`
+pub fn merge(into: &mut CoverageData, from: &CoverageData) {
+    for (file, cov) in from {
+        into.entry(file.clone()).or_default().merge(cov);
+    }
+}
+
+/// Keep files under one of `include` (all files when empty) and not under
+/// any of `exclude`
+pub fn filter(data: &mut CoverageData, include: &[String], exclude: &[String]) {
+    let canonical = |paths: &[String]| -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| fs::canonicalize(p).map(|c| c.to_string_lossy().to_string()).unwrap_or_else(|_| p.clone()))
+            .collect()
+    };
+    let (include, exclude) = (canonical(include), canonical(exclude));
+    data.retain(|file, _| {
+        let path = Path::new(file);
+        (include.is_empty() || include.iter().any(|p| path.starts_with(p)))
+            && !exclude.iter().any(|p| path.starts_with(p))
+    });
+}
+
+// ============================================================================
+// lcov
+// ============================================================================
+
+pub fn to_lcov(data: &CoverageData) -> String {
+    let mut out = String::new();
+    for (file, cov) in data {
+        let _ = writeln!(out, "TN:");
+        let _ = writeln!(out, "SF:{}", file);
+        for (line, taken) in &cov.branches {
+            for (i, n) in taken.iter().enumerate() {
+                let _ = writeln!(out, "BRDA:{},0,{},{}", line, i, n);
+            }
+        }
+        let _ = writeln!(out, "BRF:{}", cov.branches_found());
+        let _ = writeln!(out, "BRH:{}", cov.branches_hit());
+        for (line, hits) in &cov.lines {
+            let _ = writeln!(out, "DA:{},{}", line, hits);
+        }
+        let _ = writeln!(out, "LF:{}", cov.lines.len());
+        let _ = writeln!(out, "LH:{}", cov.lines_hit());
+        let _ = writeln!(out, "end_of_record");
+    }
+    out
+}
+
+pub fn parse_lcov(text: &str) -> CoverageData {
+    let mut data = CoverageData::new();
+    let mut current: Option<(String, FileCoverage)> = None;
+    for line in text.lines() {
+        if let Some(file) = line.strip_prefix("SF:") {
+            current = Some((file.to_string(), FileCoverage::default()));
+        } else if let Some(rest) = line.strip_prefix("DA:") {
+            if let (Some((_, cov)), Some((l, n))) = (current.as_mut(), rest.split_once(',')) {
+                let n = n.split(',').next().unwrap_or("0");
+                if let (Ok(l), Ok(n)) = (l.parse(), n.parse::<u64>()) {
+                    *cov.lines.entry(l).or_insert(0) += n;
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("BRDA:") {
+            let parts: Vec<&str> = rest.split(',').collect();
+            if let (Some((_, cov)), 4) = (current.as_mut(), parts.len()) {
+                if let (Ok(l), Ok(i)) = (parts[0].parse::<usize>(), parts[2].parse::<usize>()) {
+                    let n = parts[3].parse::<u64>().unwrap_or(0);
+                    let counts = cov.branches.entry(l).or_default();
+                    if counts.len() <= i {
+                        counts.resize(i + 1, 0);
+                    }
+                    counts[i] += n;
+                }
+            }
+        } else if line == "end_of_record" {
+            if let Some((file, cov)) = current.take() {
+                data.entry(file).or_default().merge(&cov);
+            }
+        }
+    }
+    data
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn percent(hit: usize, found: usize) -> String {
+    if found == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", hit as f64 * 100.0 / found as f64)
+    }
+}
+
+/// Path shown in reports: relative to `root` when it is inside it
+fn display_path(file: &str, root: &Path) -> String {
+    Path::new(file)
+        .strip_prefix(root)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file.to_string())
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse}td,th{padding:2px 10px;text-align:left}\
+tr:nth-child(even){background:#f4f4f4}\
+pre{margin:0}.src td{padding:0 8px;font-family:monospace;white-space:pre}\
+.hit{background:#dfd}.miss{background:#fdd}.partial{background:#ffd}.n{color:#888;text-align:right}";
+
+/// Write index.html plus one page per file into `dir`
+pub fn write_html(data: &CoverageData, dir: &Path, root: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut rows = String::new();
+    let (mut lf, mut lh, mut bf, mut bh) = (0, 0, 0, 0);
+    for (file, cov) in data {
+        let name = display_path(file, root);
+        let page = format!("{}.html", name.replace(['/', '\\'], "_"));
+        let (hit, found) = (cov.lines_hit(), cov.lines.len());
+        let (bhit, bfound) = (cov.branches_hit(), cov.branches_found());
+        lf += found;
+        lh += hit;
+        bf += bfound;
+        bh += bhit;
+        let _ = writeln!(
+            rows,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}/{}</td><td>{}</td><td>{}/{}</td></tr>",
+            html_escape(&page), html_escape(&name), percent(hit, found), hit, found, percent(bhit, bfound), bhit, bfound
+        );
+        fs::write(dir.join(&page), file_page(file, &name, cov))?;
+    }
+
+    let index = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Coverage</title><style>{}</style></head><body>\n\
+<h1>Coverage</h1>\n<p>Lines: {} ({}/{}) &middot; Branches: {} ({}/{})</p>\n\
+<table><tr><th>File</th><th>Lines</th><th></th><th>Branches</th><th></th></tr>\n{}</table>\n</body></html>\n",
+        HTML_STYLE, percent(lh, lf), lh, lf, percent(bh, bf), bh, bf, rows
+    );
+    fs::write(dir.join("index.html"), index)
+}
+
+fn file_page(file: &str, name: &str, cov: &FileCoverage) -> String {
+    let source = fs::read_to_string(file).unwrap_or_default();
+    let mut rows = String::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let (class, count) = match cov.lines.get(&line) {
+            Some(0) => ("miss", "0".to_string()),
+            Some(n) => {
+                let partial = cov.branches.get(&line).is_some_and(|b| b.contains(&0));
+                (if partial { "partial" } else { "hit" }, n.to_string())
+            }
+            None => ("", String::new()),
+        };
+        let branches = cov
+            .branches
+            .get(&line)
+            .map(|b| format!(" title=\"branches taken: {}\"", b.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")))
+            .unwrap_or_default();
+        let _ = writeln!(
+            rows,
+            "<tr class=\"{}\"{}><td class=\"n\">{}</td><td class=\"n\">{}</td><td>{}</td></tr>",
+            class, branches, line, count, html_escape(text)
+        );
+    }
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{name}</title><style>{}</style></head><body>\n\
+<p><a href=\"index.html\">&larr; All files</a></p>\n<h1>{name}</h1>\n\
+<p>Lines: {} ({}/{}) &middot; Branches: {} ({}/{})</p>\n<table class=\"src\">\n{}</table>\n</body></html>\n",
+        HTML_STYLE,
+        percent(cov.lines_hit(), cov.lines.len()), cov.lines_hit(), cov.lines.len(),
+        percent(cov.branches_hit(), cov.branches_found()), cov.branches_hit(), cov.branches_found(),
+        rows,
+        name = html_escape(name)
+    )
+}
@@ -0,0 +1,427 @@
+// Debug Adapter Protocol server for `quest debug --dap`
+//
+// Editors talk to the debugger with DAP: JSON messages framed by a
+// Content-Length header, over stdin/stdout by default or over a TCP connection
+// with --port. A reader thread turns incoming messages into a channel, so the
+// interpreter thread can block on it while paused and poll it between
+// statements while running (for `pause` and breakpoint changes).
+//
+// On stdio, the program's own output would corrupt the protocol stream, so
+// on Unix file descriptors 1 and 2 are redirected into pipes whose contents
+// are forwarded to the client as `output` events.
+//
+// There is one thread (id 1). Variable references are handed out per stop:
+// the Locals and Globals scopes of each frame, and any array, dict or struct
+// value that can be expanded.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use serde_json::{json, Value};
+
+use crate::debugger::{self, Frontend, Pause, Resume, StopReason};
+use crate::types::QValue;
+
+/// The client connection's write half; responses and events share one sequence
+struct Output {
+    writer: Mutex<Box<dyn Write + Send>>,
+    seq: AtomicI64,
+}
+
+impl Output {
+    fn send(&self, mut message: Value) {
+        message["seq"] = json!(self.seq.fetch_add(1, Ordering::SeqCst));
+        let body = message.to_string();
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = writer.flush();
+    }
+
+    fn event(&self, event: &str, body: Value) {
+        self.send(json!({"type": "event", "event": event, "body": body}));
+    }
+
+    fn respond(&self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+}
+
+/// Read one Content-Length framed message
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let mut body = vec![0; length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// What a variables reference points at
+enum VarRef {
+    Scope { frame: usize, globals: bool },
+    Value(Box<QValue>),
+}
+
+struct Session {
+    out: Arc<Output>,
+    messages: Receiver<Value>,
+    vars: Vec<VarRef>,
+}
+
+impl Session {
+    fn var_ref(&mut self, value: &QValue) -> usize {
+        if debugger::children(value).is_empty() {
+            return 0;
+        }
+        self.vars.push(VarRef::Value(Box::new(value.clone())));
+        self.vars.len()
+    }
+
+    fn variable(&mut self, name: String, value: &QValue) -> Value {
+        json!({
+            "name": name,
+            "value": value.as_obj()._rep(),
+            "type": value.as_obj().cls(),
+            "variablesReference": self.var_ref(value),
+        })
+    }
+
+    fn set_breakpoints(&self, request: &Value) {
+        let args = &request["arguments"];
+        let Some(path) = args["source"]["path"].as_str() else {
+            return self.out.fail(request, "setBreakpoints needs source.path");
+        };
+        let lines: Vec<(usize, Option<String>)> = args["breakpoints"].as_array().map(|bps| {
+            bps.iter().filter_map(|bp| {
+                let line = bp["line"].as_u64()? as usize;
+                let condition = bp["condition"].as_str().filter(|c| !c.trim().is_empty()).map(str::to_string);
+                Some((line, condition))
+            }).collect()
+        }).unwrap_or_default();
+        let set = debugger::set_file_breakpoints(path, &lines);
+        let breakpoints: Vec<Value> = set.iter()
+            .map(|bp| json!({"id": bp.id, "verified": true, "line": bp.line}))
+            .collect();
+        self.out.respond(request, json!({"breakpoints": breakpoints}));
+    }
+
+    /// Requests that are answered the same way whether or not the program is paused
+    fn common(&self, request: &Value) -> bool {
+        match request["command"].as_str().unwrap_or("") {
+            "setBreakpoints" => self.set_breakpoints(request),
+            "setExceptionBreakpoints" | "configurationDone" => self.out.respond(request, json!({})),
+            "threads" => self.out.respond(request, json!({"threads": [{"id": 1, "name": "main"}]})),
+            "disconnect" | "terminate" => {
+                self.out.respond(request, json!({}));
+                std::process::exit(0);
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+struct DapFrontend(Session);
+
+impl Frontend for DapFrontend {
+    fn poll(&mut self) {
+        loop {
+            let request = match self.0.messages.try_recv() {
+                Ok(request) => request,
+                Err(TryRecvError::Empty) => return,
+                // The client went away: nobody is left to stop for
+                Err(TryRecvError::Disconnected) => return debugger::detach(),
+            };
+            if self.0.common(&request) {
+                continue;
+            }
+            match request["command"].as_str().unwrap_or("") {
+                "pause" => {
+                    debugger::request_pause();
+                    self.0.out.respond(&request, json!({}));
+                }
+                _ => self.0.out.fail(&request, "The program is running"),
+            }
+        }
+    }
+
+    fn paused(&mut self, pause: &mut Pause, reason: StopReason) -> Resume {
+        let session = &mut self.0;
+        session.vars.clear();
+        let (reason, hit) = match reason {
+            StopReason::Entry => ("entry", None),
+            StopReason::Step => ("step", None),
+            StopReason::Breakpoint(id) => ("breakpoint", Some(id)),
+            StopReason::Pause => ("pause", None),
+        };
+        let mut stopped = json!({"reason": reason, "threadId": 1, "allThreadsStopped": true});
+        if let Some(id) = hit {
+            stopped["hitBreakpointIds"] = json!([id]);
+        }
+        session.out.event("stopped", stopped);
+
+        loop {
+            let Ok(request) = session.messages.recv() else {
+                debugger::detach();
+                return Resume::Continue;
+            };
+            if session.common(&request) {
+                continue;
+            }
+            let args = &request["arguments"];
+            match request["command"].as_str().unwrap_or("") {
+                "continue" => {
+                    session.out.respond(&request, json!({"allThreadsContinued": true}));
+                    return Resume::Continue;
+                }
+                "next" => {
+                    session.out.respond(&request, json!({}));
+                    return Resume::StepOver;
+                }
+                "stepIn" => {
+                    session.out.respond(&request, json!({}));
+                    return Resume::StepIn;
+                }
+                "stepOut" => {
+                    session.out.respond(&request, json!({}));
+                    return Resume::StepOut;
+                }
+                "pause" => session.out.respond(&request, json!({})),
+                "stackTrace" => {
+                    let frames: Vec<Value> = pause.frames().iter().enumerate().map(|(id, frame)| {
+                        let mut value = json!({"id": id, "name": frame.function, "line": frame.line, "column": 1});
+                        if let Some(path) = frame.file.as_deref() {
+                            let name = std::path::Path::new(path.as_str()).file_name()
+                                .map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                            value["source"] = json!({"name": name, "path": path.as_str()});
+                        }
+                        value
+                    }).collect();
+                    let total = frames.len();
+                    session.out.respond(&request, json!({"stackFrames": frames, "totalFrames": total}));
+                }
+                "scopes" => {
+                    let frame = args["frameId"].as_u64().unwrap_or(0) as usize;
+                    session.vars.push(VarRef::Scope { frame, globals: false });
+                    let locals = session.vars.len();
+                    session.vars.push(VarRef::Scope { frame, globals: true });
+                    let globals = session.vars.len();
+                    session.out.respond(&request, json!({"scopes": [
+                        {"name": "Locals", "variablesReference": locals, "expensive": false},
+                        {"name": "Globals", "variablesReference": globals, "expensive": false},
+                    ]}));
+                }
+                "variables" => {
+                    let reference = args["variablesReference"].as_u64().unwrap_or(0) as usize;
+                    let entries = match reference.checked_sub(1).and_then(|i| session.vars.get(i)) {
+                        Some(VarRef::Scope { frame, globals }) => pause.variables(*frame, *globals),
+                        Some(VarRef::Value(value)) => debugger::children(value),
+                        None => Vec::new(),
+                    };
+                    let variables: Vec<Value> = entries.into_iter()
+                        .map(|(name, value)| session.variable(name, &value))
+                        .collect();
+                    session.out.respond(&request, json!({"variables": variables}));
+                }
+                "evaluate" => {
+                    let frame = args["frameId"].as_u64().unwrap_or(0) as usize;
+                    let expression = args["expression"].as_str().unwrap_or("");
+                    match pause.evaluate(frame, expression) {
+                        Ok(value) => {
+                            let reference = session.var_ref(&value);
+                            session.out.respond(&request, json!({
+                                "result": value.as_obj()._rep(),
+                                "type": value.as_obj().cls(),
+                                "variablesReference": reference,
+                            }));
+                        }
+                        Err(e) => session.out.fail(&request, &e),
+                    }
+                }
+                _ => session.out.fail(&request, "Unsupported request"),
+            }
+        }
+    }
+}
+
+/// Send the program's stdout and stderr to the client as output events, and
+/// return a handle to the real stdout for the protocol itself
+#[cfg(unix)]
+fn redirect_output(out: &Arc<Mutex<Option<Arc<Output>>>>, forwarders: &mut Vec<JoinHandle<()>>) -> Box<dyn Write + Send> {
+    use std::fs::File;
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: plain descriptor juggling on fds this process owns
+    unsafe {
+        let protocol = libc::dup(1);
+        for (fd, category) in [(1, "stdout"), (2, "stderr")] {
+            let mut pipe = [0; 2];
+            if libc::pipe(pipe.as_mut_ptr()) != 0 {
+                continue;
+            }
+            libc::dup2(pipe[1], fd);
+            libc::close(pipe[1]);
+            let mut reader = File::from_raw_fd(pipe[0]);
+            let out = Arc::clone(out);
+            forwarders.push(std::thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                while let Ok(n) = reader.read(&mut buf) {
+                    if n == 0 {
+                        break;
+                    }
+                    let text = String::from_utf8_lossy(&buf[..n]).to_string();
+                    if let Some(out) = out.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                        out.event("output", json!({"category": category, "output": text}));
+                    }
+                }
+            }));
+        }
+        Box::new(File::from_raw_fd(protocol))
+    }
+}
+
+#[cfg(not(unix))]
+fn redirect_output(_out: &Arc<Mutex<Option<Arc<Output>>>>, _forwarders: &mut Vec<JoinHandle<()>>) -> Box<dyn Write + Send> {
+    Box::new(std::io::stdout())
+}
+
+/// Close the redirected stdout and stderr so the forwarding threads drain and finish
+fn finish_output(forwarders: Vec<JoinHandle<()>>) {
+    if forwarders.is_empty() {
+        return;
+    }
+    let _ = std::io::stdout().flush();
+    #[cfg(unix)]
+    // SAFETY: replaces fds 1 and 2 with /dev/null; nothing writes program output after this
+    unsafe {
+        let null = libc::open(c"/dev/null".as_ptr(), libc::O_WRONLY);
+        libc::dup2(null, 1);
+        libc::dup2(null, 2);
+        libc::close(null);
+    }
+    for forwarder in forwarders {
+        let _ = forwarder.join();
+    }
+}
+
+/// Serve one debugging session over stdio, or over TCP when `port` is given
+pub fn serve(port: Option<u16>) -> Result<(), Box<dyn std::error::Error>> {
+    let forward = Arc::new(Mutex::new(None));
+    let mut forwarders = Vec::new();
+    let (reader, writer): (Box<dyn Read + Send>, Box<dyn Write + Send>) = match port {
+        Some(port) => {
+            let listener = TcpListener::bind(("127.0.0.1", port))?;
+            eprintln!("Quest debug adapter listening on 127.0.0.1:{}", listener.local_addr()?.port());
+            let (stream, _) = listener.accept()?;
+            (Box::new(stream.try_clone()?), Box::new(stream))
+        }
+        None => (Box::new(std::io::stdin()), redirect_output(&forward, &mut forwarders)),
+    };
+    let out = Arc::new(Output { writer: Mutex::new(writer), seq: AtomicI64::new(1) });
+    *forward.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&out));
+
+    let (tx, messages) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        while let Some(message) = read_message(&mut reader) {
+            if tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let session = Session { out: Arc::clone(&out), messages, vars: Vec::new() };
+
+    // Configuration: wait for both `launch` and `configurationDone`
+    let mut launch: Option<Value> = None;
+    let mut configured = false;
+    while launch.is_none() || !configured {
+        let Ok(request) = session.messages.recv() else { return Ok(()) };
+        match request["command"].as_str().unwrap_or("") {
+            "initialize" => {
+                out.respond(&request, json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsEvaluateForHovers": true,
+                    "supportsConditionalBreakpoints": true,
+                    "supportsTerminateRequest": true,
+                }));
+                out.event("initialized", json!({}));
+            }
+            "launch" => {
+                if request["arguments"]["program"].as_str().is_none() {
+                    out.fail(&request, "launch needs a `program` (path to a .q file)");
+                    continue;
+                }
+                out.respond(&request, json!({}));
+                launch = Some(request);
+            }
+            "configurationDone" => {
+                out.respond(&request, json!({}));
+                configured = true;
+            }
+            _ => {
+                if !session.common(&request) {
+                    out.fail(&request, "Not launched yet");
+                }
+            }
+        }
+    }
+
+    let args = &launch.as_ref().map(|l| l["arguments"].clone()).unwrap_or_default();
+    if let Some(cwd) = args["cwd"].as_str() {
+        std::env::set_current_dir(cwd)?;
+    }
+    let program = args["program"].as_str().unwrap_or_default().to_string();
+    let mut argv = vec![program.clone()];
+    if let Some(extra) = args["args"].as_array() {
+        argv.extend(extra.iter().filter_map(|a| a.as_str().map(str::to_string)));
+    }
+    let stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+
+    let result = debugger::run(Box::new(DapFrontend(session)), &program, &argv, stop_on_entry);
+    finish_output(forwarders);
+    let exit_code = match result {
+        Ok(_) => 0,
+        Err(e) => {
+            out.event("output", json!({"category": "stderr", "output": format!("{}\n", e)}));
+            1
+        }
+    };
+    out.event("exited", json!({"exitCode": exit_code}));
+    out.event("terminated", json!({}));
+    Ok(())
+}
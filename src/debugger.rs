@@ -0,0 +1,609 @@
+// Interactive debugger for `quest debug`
+//
+// Both evaluators call on_statement() before every statement, next to the
+// coverage hook. While debugging, that checks breakpoints (file and line,
+// optionally with a condition) and the current step mode, and when execution
+// should stop hands control to a Frontend: the command-line debugger below, or
+// the Debug Adapter Protocol server in dap.rs. The frontend can inspect
+// variables and evaluate expressions in the paused frame, or in any caller
+// frame, before choosing how to resume.
+//
+// Lines are mapped to files through Scope::source_file and Scope::line_offset,
+// the same way coverage does, so function definitions are tagged with their
+// file while the debugger is enabled. Calls and module loads record a snapshot
+// of the caller (enter_call), which gives the stack for backtraces, the depth
+// for next/finish and the scopes for inspecting caller frames.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use pest::Parser;
+
+use crate::control_flow::EvalError;
+use crate::scope::Scope;
+use crate::types::{QNil, QValue};
+use crate::{QuestParser, Rule};
+
+type ScopeLevel = Rc<RefCell<HashMap<String, QValue>>>;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    // False while a frontend has control, so evaluating expressions doesn't re-enter
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+    static STATE: RefCell<State> = RefCell::new(State::default());
+    static FRONTEND: RefCell<Option<Box<dyn Frontend>>> = RefCell::new(None);
+    static BUILTIN_NAMES: HashSet<String> = Scope::new().scopes[0].borrow().keys().cloned().collect();
+}
+
+/// How execution continues after a stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resume {
+    /// Run until a breakpoint
+    Continue,
+    /// Stop at the next statement, entering calls
+    StepIn,
+    /// Stop at the next statement in this frame or a caller
+    StepOver,
+    /// Stop at the next statement in a caller
+    StepOut,
+}
+
+/// Why execution stopped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Entry,
+    Step,
+    Breakpoint(usize),
+    Pause,
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: usize,
+    /// Canonical path when the file exists, otherwise as given (matched as a path suffix)
+    pub file: String,
+    pub line: usize,
+    pub condition: Option<String>,
+}
+
+impl Breakpoint {
+    fn matches(&self, file: &str, line: usize) -> bool {
+        self.line == line && (file == self.file || file.ends_with(&format!("/{}", self.file)))
+    }
+}
+
+#[derive(Default)]
+enum Mode {
+    #[default]
+    Run,
+    StepIn,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+/// A frame suspended in a call or module load
+struct CallerFrame {
+    function: String,
+    file: Option<Rc<String>>,
+    line: usize,
+    scopes: Vec<ScopeLevel>,
+    module_cache: Rc<RefCell<HashMap<String, QValue>>>,
+    script_path: Rc<RefCell<Option<String>>>,
+}
+
+#[derive(Default)]
+struct State {
+    breakpoints: Vec<Breakpoint>,
+    next_id: usize,
+    mode: Mode,
+    pause_requested: bool,
+    callers: Vec<CallerFrame>,
+    // The first stop of a session started with stop_on_entry
+    at_entry: bool,
+    // Set by enter_module_call for the call_user_function that follows
+    skip_next_call: bool,
+}
+
+/// Drives the debugger when execution stops
+pub trait Frontend {
+    /// Execution stopped; inspect `pause` and decide how to resume
+    fn paused(&mut self, pause: &mut Pause, reason: StopReason) -> Resume;
+
+    /// Called before every statement while running, for requests that arrive meanwhile
+    fn poll(&mut self) {}
+}
+
+/// A frame in the paused program's stack (0 is where execution stopped)
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub file: Option<Rc<String>>,
+    pub line: usize,
+}
+
+/// The paused program, as seen by a frontend
+pub struct Pause<'a> {
+    scope: &'a mut Scope,
+    pub file: Option<Rc<String>>,
+    pub line: usize,
+}
+
+impl Pause<'_> {
+    /// The current frame first, then its callers
+    pub fn frames(&self) -> Vec<Frame> {
+        let mut frames = vec![Frame {
+            function: self.scope.current_function.clone().unwrap_or_else(|| "<module>".to_string()),
+            file: self.file.clone(),
+            line: self.line,
+        }];
+        STATE.with(|state| {
+            for caller in state.borrow().callers.iter().rev() {
+                frames.push(Frame { function: caller.function.clone(), file: caller.file.clone(), line: caller.line });
+            }
+        });
+        frames
+    }
+
+    /// Variables of `frame`: its locals, or its globals (without built-in names)
+    pub fn variables(&self, frame: usize, globals: bool) -> Vec<(String, QValue)> {
+        let levels = if frame == 0 {
+            self.scope.scopes.clone()
+        } else {
+            STATE.with(|state| {
+                let state = state.borrow();
+                state.callers.iter().rev().nth(frame - 1).map(|c| c.scopes.clone()).unwrap_or_default()
+            })
+        };
+        let mut vars: Vec<(String, QValue)> = Vec::new();
+        let mut seen = HashSet::new();
+        let selected: Vec<&ScopeLevel> = if globals { levels.iter().take(1).collect() } else { levels.iter().skip(1).rev().collect() };
+        for level in selected {
+            let mut names: Vec<(String, QValue)> = level.borrow().iter()
+                .filter(|(name, _)| !globals || !BUILTIN_NAMES.with(|b| b.contains(*name)))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            names.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, value) in names {
+                if seen.insert(name.clone()) {
+                    vars.push((name, value));
+                }
+            }
+        }
+        vars
+    }
+
+    /// Evaluate Quest code in `frame` and return the value of its last statement
+    pub fn evaluate(&mut self, frame: usize, source: &str) -> Result<QValue, String> {
+        if frame == 0 {
+            return evaluate_in(self.scope, source);
+        }
+        let mut scope = STATE.with(|state| {
+            let state = state.borrow();
+            let caller = state.callers.iter().rev().nth(frame - 1)?;
+            let mut scope = Scope::new();
+            scope.scopes = caller.scopes.clone();
+            scope.module_cache = Rc::clone(&caller.module_cache);
+            scope.current_script_path = Rc::clone(&caller.script_path);
+            scope.current_function = Some(caller.function.clone());
+            Some(scope)
+        }).ok_or_else(|| format!("No frame {}", frame))?;
+        scope.call_stack = Rc::clone(&self.scope.call_stack);
+        evaluate_in(&mut scope, source)
+    }
+}
+
+/// Whether a debugging session is running (function definitions are tagged with their file)
+pub fn is_enabled() -> bool {
+    ENABLED.with(|e| e.get())
+}
+
+/// Stop debugging and let the program run to completion
+pub fn detach() {
+    ENABLED.with(|e| e.set(false));
+    ACTIVE.with(|a| a.set(false));
+}
+
+/// Ask the running program to stop at its next statement
+pub fn request_pause() {
+    STATE.with(|state| state.borrow_mut().pause_requested = true);
+}
+
+fn normalize(file: &str) -> String {
+    Path::new(file).canonicalize().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|_| file.to_string())
+}
+
+/// Add a breakpoint at `file:line`
+pub fn add_breakpoint(file: &str, line: usize, condition: Option<String>) -> Breakpoint {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.next_id += 1;
+        let bp = Breakpoint { id: state.next_id, file: normalize(file), line, condition };
+        state.breakpoints.push(bp.clone());
+        bp
+    })
+}
+
+/// Replace every breakpoint in `file` (how DAP clients send them)
+pub fn set_file_breakpoints(file: &str, lines: &[(usize, Option<String>)]) -> Vec<Breakpoint> {
+    let file = normalize(file);
+    STATE.with(|state| state.borrow_mut().breakpoints.retain(|bp| bp.file != file));
+    lines.iter().map(|(line, condition)| add_breakpoint(&file, *line, condition.clone())).collect()
+}
+
+/// Remove breakpoint `id`, or all of them; returns how many were removed
+pub fn remove_breakpoints(id: Option<usize>) -> usize {
+    STATE.with(|state| {
+        let breakpoints = &mut state.borrow_mut().breakpoints;
+        let before = breakpoints.len();
+        breakpoints.retain(|bp| id.is_some_and(|id| bp.id != id));
+        before - breakpoints.len()
+    })
+}
+
+pub fn breakpoints() -> Vec<Breakpoint> {
+    STATE.with(|state| state.borrow().breakpoints.clone())
+}
+
+/// Debug `script` with `frontend` in control; returns what the script returned
+pub fn run(frontend: Box<dyn Frontend>, script: &str, args: &[String], stop_on_entry: bool) -> Result<QValue, String> {
+    let source = std::fs::read_to_string(script)
+        .map_err(|e| format!("Failed to read file '{}': {}", script, e))?;
+    FRONTEND.with(|f| *f.borrow_mut() = Some(frontend));
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.mode = if stop_on_entry { Mode::StepIn } else { Mode::Run };
+        state.at_entry = stop_on_entry;
+    });
+    ENABLED.with(|e| e.set(true));
+    ACTIVE.with(|a| a.set(true));
+    let result = crate::commands::eval_script(&source, args, Some(script));
+    detach();
+    result
+}
+
+/// Marks a suspended caller for the duration of a call or module load
+pub struct CallGuard;
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        STATE.with(|state| {
+            state.borrow_mut().callers.pop();
+        });
+    }
+}
+
+/// Record `caller` as suspended until the returned guard is dropped
+pub fn enter_call(caller: &Scope) -> Option<CallGuard> {
+    if !is_enabled() || STATE.with(|state| std::mem::take(&mut state.borrow_mut().skip_next_call)) {
+        return None;
+    }
+    let frame = CallerFrame {
+        function: caller.current_function.clone().unwrap_or_else(|| "<module>".to_string()),
+        file: caller.source_file.clone(),
+        line: caller.current_line.unwrap_or(0) + caller.line_offset,
+        scopes: caller.scopes.clone(),
+        module_cache: Rc::clone(&caller.module_cache),
+        script_path: Rc::clone(&caller.current_script_path),
+    };
+    STATE.with(|state| state.borrow_mut().callers.push(frame));
+    Some(CallGuard)
+}
+
+/// Like enter_call, for module functions: they are called through a temporary
+/// scope holding the module's members, so the real caller is recorded here and
+/// the temporary scope is skipped
+pub fn enter_module_call(caller: &Scope) -> Option<CallGuard> {
+    let guard = enter_call(caller);
+    if guard.is_some() {
+        STATE.with(|state| state.borrow_mut().skip_next_call = true);
+    }
+    guard
+}
+
+/// Called before each statement; `line` is relative to the text being evaluated
+pub fn on_statement(scope: &mut Scope, line: usize) {
+    if !ACTIVE.with(|a| a.get()) {
+        return;
+    }
+    let line = line + scope.line_offset;
+    let file = scope.source_file.clone();
+
+    FRONTEND.with(|f| {
+        if let Some(frontend) = f.borrow_mut().as_mut() {
+            frontend.poll();
+        }
+    });
+
+    let (reason, condition) = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let depth = state.callers.len();
+        if std::mem::take(&mut state.pause_requested) {
+            return (Some(StopReason::Pause), None);
+        }
+        let stepping = match state.mode {
+            Mode::Run => false,
+            Mode::StepIn => true,
+            Mode::StepOver(d) => depth <= d,
+            Mode::StepOut(d) => depth < d,
+        };
+        if stepping {
+            let reason = if std::mem::take(&mut state.at_entry) { StopReason::Entry } else { StopReason::Step };
+            return (Some(reason), None);
+        }
+        let Some(path) = file.as_deref() else { return (None, None) };
+        state.breakpoints.iter()
+            .find(|bp| bp.matches(path, line))
+            .map(|bp| (Some(StopReason::Breakpoint(bp.id)), bp.condition.clone()))
+            .unwrap_or((None, None))
+    });
+    let Some(reason) = reason else { return };
+
+    // A breakpoint whose condition fails (or can't be evaluated) doesn't stop
+    if let Some(condition) = condition {
+        if !evaluate_in(scope, &condition).map(|v| v.as_bool()).unwrap_or(false) {
+            return;
+        }
+    }
+    stop(scope, file, line, reason);
+}
+
+fn stop(scope: &mut Scope, file: Option<Rc<String>>, line: usize, reason: StopReason) {
+    let Some(mut frontend) = FRONTEND.with(|f| f.borrow_mut().take()) else { return };
+    ACTIVE.with(|a| a.set(false));
+    let resume = {
+        let mut pause = Pause { scope, file, line };
+        frontend.paused(&mut pause, reason)
+    };
+    FRONTEND.with(|f| *f.borrow_mut() = Some(frontend));
+
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let depth = state.callers.len();
+        state.mode = match resume {
+            Resume::Continue => Mode::Run,
+            Resume::StepIn => Mode::StepIn,
+            Resume::StepOver => Mode::StepOver(depth),
+            Resume::StepOut => Mode::StepOut(depth),
+        };
+    });
+    ACTIVE.with(|a| a.set(is_enabled()));
+}
+
+/// Run `source` in `scope` without triggering the debugger, leaving the
+/// scope's execution state (line, exception, call stack) as it was
+fn evaluate_in(scope: &mut Scope, source: &str) -> Result<QValue, String> {
+    let active = ACTIVE.with(|a| a.replace(false));
+    let saved_line = scope.current_line;
+    let saved_depth = scope.eval_depth;
+    let saved_exception = scope.current_exception.take();
+    let saved_stack = scope.call_stack.borrow().len();
+
+    let result = QuestParser::parse(Rule::program, source.trim())
+        .map_err(|e| format!("Parse error: {}", e))
+        .and_then(|pairs| {
+            let mut value = QValue::Nil(QNil);
+            for statement in pairs.flat_map(|p| p.into_inner()) {
+                if statement.as_rule() == Rule::EOI {
+                    continue;
+                }
+                value = match crate::eval_pair(statement, scope) {
+                    Ok(v) => v,
+                    Err(EvalError::ControlFlow(_)) => return Err("break, continue and return can't be evaluated here".to_string()),
+                    Err(e) => return Err(e.to_string()),
+                };
+            }
+            Ok(value)
+        });
+
+    scope.current_line = saved_line;
+    scope.eval_depth = saved_depth;
+    scope.current_exception = saved_exception;
+    scope.call_stack.borrow_mut().truncate(saved_stack);
+    ACTIVE.with(|a| a.set(active));
+    result
+}
+
+/// Children of a compound value, for expanding it in a variables view
+pub fn children(value: &QValue) -> Vec<(String, QValue)> {
+    match value {
+        QValue::Array(arr) => arr.elements.borrow().iter().enumerate()
+            .map(|(i, v)| (i.to_string(), v.clone()))
+            .collect(),
+        QValue::Dict(dict) => dict.map.borrow().iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+        QValue::Struct(s) => {
+            let s = s.borrow();
+            let mut fields: Vec<(String, QValue)> = s.fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            fields
+        }
+        _ => Vec::new(),
+    }
+}
+
+// ============================================================================
+// Command-line frontend
+// ============================================================================
+
+/// The `quest debug` prompt
+#[derive(Default)]
+pub struct Cli {
+    last_command: String,
+    frame: usize,
+    sources: HashMap<String, Vec<String>>,
+}
+
+impl Cli {
+    fn source_line(&mut self, file: &str, line: usize) -> Option<String> {
+        let lines = self.sources.entry(file.to_string()).or_insert_with(|| {
+            std::fs::read_to_string(file).map(|s| s.lines().map(str::to_string).collect()).unwrap_or_default()
+        });
+        lines.get(line.checked_sub(1)?).cloned()
+    }
+
+    fn show_location(&mut self, frame: &Frame) {
+        let file = frame.file.as_deref().map(String::as_str).unwrap_or("<unknown>");
+        println!("> {}:{} ({})", file, frame.line, frame.function);
+        if let Some(text) = self.source_line(file, frame.line) {
+            println!("{:>5}   {}", frame.line, text);
+        }
+    }
+
+    fn list(&mut self, frame: &Frame) {
+        let Some(file) = frame.file.as_deref() else { return };
+        let start = frame.line.saturating_sub(5).max(1);
+        for n in start..frame.line + 6 {
+            if let Some(text) = self.source_line(file, n) {
+                let marker = if n == frame.line { "->" } else { "  " };
+                println!("{:>5} {} {}", n, marker, text);
+            }
+        }
+    }
+
+    fn print_vars(vars: &[(String, QValue)]) {
+        if vars.is_empty() {
+            println!("  (none)");
+        }
+        for (name, value) in vars {
+            println!("  {} = {}", name, value.as_obj()._rep());
+        }
+    }
+
+    fn set_breakpoint(&mut self, arg: &str, frames: &[Frame]) {
+        let (location, condition) = match arg.split_once(" if ") {
+            Some((location, condition)) => (location.trim(), Some(condition.trim().to_string())),
+            None => (arg.trim(), None),
+        };
+        let (file, line) = match location.rsplit_once(':') {
+            Some((file, line)) => (file.to_string(), line),
+            None => {
+                let current = frames.get(self.frame).and_then(|f| f.file.as_deref()).map(|f| f.to_string());
+                (current.unwrap_or_default(), location)
+            }
+        };
+        match line.parse::<usize>() {
+            Ok(line) if !file.is_empty() => {
+                let bp = add_breakpoint(&file, line, condition);
+                println!("Breakpoint {} at {}:{}", bp.id, bp.file, bp.line);
+            }
+            _ => println!("Usage: break [FILE:]LINE [if CONDITION]"),
+        }
+    }
+}
+
+const CLI_HELP: &str = "\
+Commands:
+  c, continue          Run until the next breakpoint
+  s, step              Run to the next statement, entering function calls
+  n, next              Run to the next statement in this function
+  f, finish            Run until the current function returns
+  b, break [FILE:]LINE [if COND]
+                       Set a breakpoint (no argument lists them)
+  d, delete [N]        Delete breakpoint N, or all breakpoints
+  p, print EXPR        Evaluate EXPR in the selected frame and print the result
+  locals, globals      Show the variables of the selected frame
+  bt, where            Show the call stack
+  up, down             Select the caller / callee frame
+  l, list              Show the source around the selected frame's line
+  q, quit              Stop the program and exit
+  h, help              Show this help
+An empty line repeats the last step command.";
+
+impl Frontend for Cli {
+    fn paused(&mut self, pause: &mut Pause, reason: StopReason) -> Resume {
+        self.frame = 0;
+        let mut frames = pause.frames();
+        if let StopReason::Breakpoint(id) = reason {
+            println!("Breakpoint {} hit", id);
+        }
+        self.show_location(&frames[0]);
+
+        let stdin = std::io::stdin();
+        loop {
+            print!("(qdb) ");
+            let _ = std::io::stdout().flush();
+            let mut input = String::new();
+            if stdin.lock().read_line(&mut input).unwrap_or(0) == 0 {
+                // End of input: let the program finish on its own
+                println!();
+                detach();
+                return Resume::Continue;
+            }
+            let input = input.trim();
+            let input = if input.is_empty() { self.last_command.clone() } else { input.to_string() };
+            let (command, arg) = input.split_once(' ').map(|(c, a)| (c, a.trim())).unwrap_or((input.as_str(), ""));
+
+            let resume = match command {
+                "" => continue,
+                "c" | "continue" => Some(Resume::Continue),
+                "s" | "step" => Some(Resume::StepIn),
+                "n" | "next" => Some(Resume::StepOver),
+                "f" | "finish" => Some(Resume::StepOut),
+                _ => None,
+            };
+            if let Some(resume) = resume {
+                self.last_command = command.to_string();
+                return resume;
+            }
+
+            match command {
+                "b" | "break" if arg.is_empty() => {
+                    let all = breakpoints();
+                    if all.is_empty() {
+                        println!("No breakpoints");
+                    }
+                    for bp in all {
+                        match bp.condition {
+                            Some(cond) => println!("  {}: {}:{} if {}", bp.id, bp.file, bp.line, cond),
+                            None => println!("  {}: {}:{}", bp.id, bp.file, bp.line),
+                        }
+                    }
+                }
+                "b" | "break" => self.set_breakpoint(arg, &frames),
+                "d" | "delete" => {
+                    let id = if arg.is_empty() { None } else { arg.parse().ok() };
+                    if !arg.is_empty() && id.is_none() {
+                        println!("Usage: delete [N]");
+                    } else {
+                        println!("Deleted {} breakpoint(s)", remove_breakpoints(id));
+                    }
+                }
+                "p" | "print" => match pause.evaluate(self.frame, arg) {
+                    Ok(value) => println!("{}", value.as_obj()._rep()),
+                    Err(e) => println!("{}", e),
+                },
+                "locals" => Self::print_vars(&pause.variables(self.frame, false)),
+                "globals" => Self::print_vars(&pause.variables(self.frame, true)),
+                "bt" | "where" | "backtrace" => {
+                    for (i, frame) in frames.iter().enumerate() {
+                        let marker = if i == self.frame { ">" } else { " " };
+                        let file = frame.file.as_deref().map(String::as_str).unwrap_or("<unknown>");
+                        println!("{} #{} {} at {}:{}", marker, i, frame.function, file, frame.line);
+                    }
+                }
+                "up" | "down" => {
+                    let target = if command == "up" { self.frame + 1 } else { self.frame.wrapping_sub(1) };
+                    if target < frames.len() {
+                        self.frame = target;
+                        let frame = frames[target].clone();
+                        self.show_location(&frame);
+                    } else {
+                        println!("No {} frame", if command == "up" { "caller" } else { "callee" });
+                    }
+                }
+                "l" | "list" => {
+                    let frame = frames[self.frame].clone();
+                    self.list(&frame);
+                }
+                "q" | "quit" => std::process::exit(0),
+                "h" | "help" => println!("{}", CLI_HELP),
+                _ => println!("Unknown command '{}'; type 'help' for a list", command),
+            }
+            frames = pause.frames();
+        }
+    }
+}
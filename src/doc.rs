@@ -50,7 +50,7 @@ pub fn format_with_quest(text: &str) -> Result<String, String> {
 }
 
 /// Load documentation from overlay file
-fn load_doc_from_overlay(module_path: &str, item_name: &str) -> String {
+pub fn load_doc_from_overlay(module_path: &str, item_name: &str) -> String {
     // For builtin modules, try both with and without "std/" prefix
     // This handles the case where parent_type is "math" but overlay is at "lib/std/math.q"
     let paths_to_try = if !module_path.starts_with("std/") {
@@ -157,10 +157,9 @@ fn extract_item_doc(source: &str, item_name: &str) -> String {
 }
 
 /// Parse a doc_declaration and extract name and docstring
-fn parse_doc_declaration(pair: pest::iterators::Pair<Rule>) -> Option<(String, String)> {
-    // doc_declaration contains one of: doc_fun, doc_const, doc_type, doc_trait
-    let inner = pair.into_inner().next()?;
-
+fn parse_doc_declaration(inner: pest::iterators::Pair<Rule>) -> Option<(String, String)> {
+    // doc_declaration is inlined into statement, so this is the
+    // doc_fun/doc_const/doc_type/doc_trait pair itself
     match inner.as_rule() {
         Rule::doc_fun => {
             // %fun name(...) "docstring"
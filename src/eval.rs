@@ -13,6 +13,7 @@ use crate::string_utils;
 use crate::{value_err, runtime_err, attr_err, name_err};
 use crate::control_flow::{EvalError, EvalResult, ControlFlow};
 use std::collections::HashMap;
+use indexmap::IndexMap;
 
 // ============================================================================
 // Core Data Structures
@@ -240,6 +241,9 @@ pub struct LoopState<'i> {
     pub loop_var: Option<String>,
     /// Collection being iterated (for for-loops)
     pub collection: Option<Vec<QValue>>,
+    /// Lazy element source read one item per iteration instead of `collection`
+    /// (db cursor row iterators)
+    pub source: Option<QValue>,
     /// Current iteration index
     #[allow(dead_code)]
     pub current_iteration: usize,
@@ -354,6 +358,23 @@ macro_rules! int_comparison {
     };
 }
 
+/// Unwrap a Result in the eval loop, handing an error to the enclosing try
+/// block on the stack (if any) instead of returning it.
+macro_rules! catchable {
+    ($stack:expr, $scope:expr, $label:lifetime, $result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(e) => {
+                let e: EvalError = e.into();
+                if handle_exception_in_try(&mut $stack, $scope, e.clone())? {
+                    continue $label;
+                }
+                return Err(e);
+            }
+        }
+    };
+}
+
 // ============================================================================
 // Helper Functions for Block Evaluation
 // ============================================================================
@@ -483,7 +504,20 @@ pub fn eval_pair_iterative<'i>(
             // ================================================================
 
             (Rule::statement, EvalState::Initial) => {
+                if crate::coverage::is_enabled() {
+                    crate::coverage::record_line(scope, frame.pair.as_span().start_pos().line_col().0);
+                }
+                // line_col() scans the source from the start, so only when it's used
+                let line = (crate::debugger::is_enabled() || crate::trace::is_enabled())
+                    .then(|| frame.pair.as_span().start_pos().line_col().0);
+                if let Some(line) = line {
+                    crate::debugger::on_statement(scope, line);
+                }
                 let inner = frame.pair.into_inner().next().unwrap();
+                if let Some(line) = line {
+                    crate::trace::statement(scope, line, inner.as_rule(), inner.as_str());
+                }
+                crate::profiler::on_statement(scope);
                 stack.push(EvalFrame::new(inner));
             }
 
@@ -541,6 +575,12 @@ pub fn eval_pair_iterative<'i>(
                         // Evaluate right operand (using recursive eval for now)
                         let right = crate::eval_pair_impl(right_pair, scope)?;
 
+                        // NDArray arithmetic broadcasts against numbers and other arrays
+                        if matches!(result, QValue::NDArray(_)) || matches!(right, QValue::NDArray(_)) {
+                            result = crate::types::QNDArray::apply_operator(op, &result, &right)?;
+                            continue;
+                        }
+
                         result = match op {
                             "*" => {
                                 // Fast path for Int * Int (QEP-042 optimization)
@@ -764,7 +804,16 @@ pub fn eval_pair_iterative<'i>(
 
                                     if needs_fallback {
                                         // Has named args, unpacking, etc - fall back to recursive
-                                        let result = crate::eval_pair_impl(frame.pair.clone(), scope)?;
+                                        // (errors must still reach an enclosing try block)
+                                        let result = match crate::eval_pair_impl(frame.pair.clone(), scope) {
+                                            Ok(val) => val,
+                                            Err(e) => {
+                                                if handle_exception_in_try(&mut stack, scope, e.clone())? {
+                                                    continue 'eval_loop;
+                                                }
+                                                return Err(e);
+                                            }
+                                        };
                                         push_result_to_parent(&mut stack, result, &mut final_result)?;
                                     } else {
                                         // Store method name and next operation index
@@ -806,7 +855,7 @@ pub fn eval_pair_iterative<'i>(
                                         QValue::Module(module) => {
                                             // Access module member
                                             module.get_member(method_name)
-                                                .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'", module.name, method_name))?
+                                                .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'{}", module.name, method_name, crate::suggest::for_member(method_name, module)))?
                                         }
                                         QValue::Process(proc) => {
                                             // Access process streams
@@ -847,7 +896,10 @@ pub fn eval_pair_iterative<'i>(
                                                 }
                                                 field_value
                                             } else {
-                                                return attr_err!("Struct {} has no field '{}'", type_name, method_name);
+                                                let hint = crate::find_type_definition(&type_name, scope)
+                                                    .map(|qtype| crate::suggest::for_field(method_name, &qtype))
+                                                    .unwrap_or_default();
+                                                return attr_err!("Struct {} has no field '{}'{}", type_name, method_name, hint);
                                             }
                                         }
                                         _ => {
@@ -871,15 +923,24 @@ pub fn eval_pair_iterative<'i>(
                             }
 
                             Rule::index_access => {
+                                // Slices and multi-dimensional indices (a[1:3], nd[0, :])
+                                // evaluate their entries recursively
+                                if crate::is_extended_index(operation) {
+                                    let entries = crate::eval_index_entries(operation.clone(), scope)?;
+                                    let base = postfix_state.current_base.as_ref().unwrap();
+                                    postfix_state.current_base = Some(crate::index_with_entries(base, &entries)?);
+                                    stack.push(EvalFrame {
+                                        pair: frame.pair.clone(),
+                                        state: EvalState::PostfixApplyOperation(op_index + 1),
+                                        partial_results: Vec::new(),
+                                        context: Some(context),
+                                    });
+                                    continue 'eval_loop;
+                                }
+
                                 // INDEX ACCESS - arr[i] or dict[key]
                                 // Evaluate index expression iteratively
-                                let mut index_inner = operation.clone().into_inner();
-                                let index_expr = index_inner.next().unwrap();
-
-                                // Check for multi-dimensional access (not yet supported)
-                                if index_inner.next().is_some() {
-                                    return Err("Multi-dimensional array access not yet implemented".to_string().into());
-                                }
+                                let index_expr = operation.clone().into_inner().next().unwrap();
 
                                 // Push frame to apply index after it's evaluated
                                 stack.push(EvalFrame {
@@ -938,7 +999,16 @@ pub fn eval_pair_iterative<'i>(
 
                                     if needs_fallback {
                                         // Fall back to recursive eval for complex args
-                                        let result = crate::eval_pair_impl(frame.pair.clone(), scope)?;
+                                        // (errors must still reach an enclosing try block)
+                                        let result = match crate::eval_pair_impl(frame.pair.clone(), scope) {
+                                            Ok(val) => val,
+                                            Err(e) => {
+                                                if handle_exception_in_try(&mut stack, scope, e.clone())? {
+                                                    continue 'eval_loop;
+                                                }
+                                                return Err(e);
+                                            }
+                                        };
                                         push_result_to_parent(&mut stack, result, &mut final_result)?;
                                     } else {
                                         // Setup call state for direct function call
@@ -1009,11 +1079,13 @@ pub fn eval_pair_iterative<'i>(
                         }
                         QValue::Dict(dict) => {
                             let key = index_value.as_str();
-                            match dict.get(&key) {
-                                Some(v) => v.clone(),
+                            match dict.get_or_default(&key, scope)? {
+                                Some(v) => v,
                                 None => QValue::Nil(QNil),
                             }
                         }
+                        QValue::Counter(counter) => counter.count(&index_value)?,
+                        QValue::NDArray(nda) => nda.index(&[crate::types::IndexEntry::At(index_value)])?,
                         QValue::Str(s) => {
                             // String indexing requires Int or BigInt (that fits in Int)
                             use crate::type_err;
@@ -1188,10 +1260,10 @@ pub fn eval_pair_iterative<'i>(
                                 match crate::call_user_function(&user_fn, call_args, scope, call_state.call_line) {
                                     Ok(val) => val,
                                     Err(e) => {
-                                        if handle_exception_in_try(&mut stack, scope, e.clone().into())? {
+                                        if handle_exception_in_try(&mut stack, scope, e.clone())? {
                                             continue 'eval_loop;
                                         }
-                                        return Err(e.into());
+                                        return Err(e);
                                     }
                                 }
                             }
@@ -1210,10 +1282,10 @@ pub fn eval_pair_iterative<'i>(
                                             Ok(val) => val,
                                             Err(e) => {
                                                 scope.pop();
-                                                if handle_exception_in_try(&mut stack, scope, e.clone().into())? {
+                                                if handle_exception_in_try(&mut stack, scope, e.clone())? {
                                                     continue 'eval_loop;
                                                 }
-                                                return Err(e.into());
+                                                return Err(e);
                                             }
                                         };
                                         scope.pop();
@@ -1231,13 +1303,14 @@ pub fn eval_pair_iterative<'i>(
                         // Check for built-in module methods first
                         match method_name {
                             "_doc" => QValue::Str(QString::new(module._doc())),
+                            "cls" => QValue::Str(QString::new(module.cls())),
                             "str" => QValue::Str(QString::new(module.str())),
                             "_rep" => QValue::Str(QString::new(module._rep())),
                             "_id" => QValue::Int(QInt::new(module._id() as i64)),
                             _ => {
                                 // Get member and call it as a function
                                 let func = module.get_member(method_name)
-                                    .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'", module.name, method_name))?;
+                                    .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'{}", module.name, method_name, crate::suggest::for_member(method_name, module)))?;
 
                                 match func {
                                     QValue::Fun(f) => {
@@ -1277,13 +1350,14 @@ pub fn eval_pair_iterative<'i>(
 
                                         // Convert args to CallArguments
                                         let call_args = crate::function_call::CallArguments::positional_only(call_state.args.clone());
+                                        let _caller = crate::debugger::enter_module_call(scope);
                                         match crate::call_user_function(&user_fn, call_args, &mut module_scope, call_state.call_line) {
                                             Ok(val) => val,
                                             Err(e) => {
-                                                if handle_exception_in_try(&mut stack, scope, e.clone().into())? {
+                                                if handle_exception_in_try(&mut stack, scope, e.clone())? {
                                                     continue 'eval_loop;
                                                 }
-                                                return Err(e.into());
+                                                return Err(e);
                                             }
                                         }
                                     }
@@ -1344,10 +1418,10 @@ pub fn eval_pair_iterative<'i>(
                                     match crate::call_user_function(&class_method, call_args, scope, call_state.call_line) {
                                         Ok(val) => val,
                                         Err(e) => {
-                                            if handle_exception_in_try(&mut stack, scope, e.clone().into())? {
+                                            if handle_exception_in_try(&mut stack, scope, e.clone())? {
                                                 continue 'eval_loop;
                                             }
-                                            return Err(e.into());
+                                            return Err(e);
                                         }
                                     }
                                 } else if qtype.name == "BigInt" {
@@ -1384,7 +1458,7 @@ pub fn eval_pair_iterative<'i>(
                                         }
                                     }
                                 } else {
-                                    return attr_err!("Type {} has no method '{}'", qtype.name, method_name);
+                                    return attr_err!("Type {} has no method '{}'{}", qtype.name, method_name, crate::suggest::for_static_method(method_name, qtype));
                                 }
                             }
                         }
@@ -1651,6 +1725,7 @@ pub fn eval_pair_iterative<'i>(
 
                         // Evaluate right operand (using recursive eval for now)
                         let right = crate::eval_pair_impl(right_pair, scope)?;
+                        catchable!(stack, scope, 'eval_loop, crate::strict::check_comparison(&result, &right, op, scope));
 
                         // Type-aware comparison with fast path for Int comparisons
                         let cmp_result = match op {
@@ -1807,14 +1882,14 @@ pub fn eval_pair_iterative<'i>(
             (Rule::bitwise_or, EvalState::EvalLeft) => {
                 // Left evaluated, apply bitwise OR to remaining operands
                 let left_result = frame.partial_results.pop().unwrap();
-                let mut int_result = left_result.as_num()? as i64;
+                let mut int_result = catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&left_result, scope, "|"));
 
                 let mut inner = frame.pair.clone().into_inner();
                 inner.next(); // Skip left
 
                 for next in inner {
-                    let right = crate::eval_pair_impl(next, scope)?.as_num()? as i64;
-                    int_result |= right;
+                    let right = crate::eval_pair_impl(next, scope)?;
+                    int_result |= catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&right, scope, "|"));
                 }
 
                 let value = QValue::Int(QInt::new(int_result));
@@ -1842,14 +1917,14 @@ pub fn eval_pair_iterative<'i>(
 
             (Rule::bitwise_xor, EvalState::EvalLeft) => {
                 let left_result = frame.partial_results.pop().unwrap();
-                let mut int_result = left_result.as_num()? as i64;
+                let mut int_result = catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&left_result, scope, "^"));
 
                 let mut inner = frame.pair.clone().into_inner();
                 inner.next(); // Skip left
 
                 for next in inner {
-                    let right = crate::eval_pair_impl(next, scope)?.as_num()? as i64;
-                    int_result ^= right;
+                    let right = crate::eval_pair_impl(next, scope)?;
+                    int_result ^= catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&right, scope, "^"));
                 }
 
                 let value = QValue::Int(QInt::new(int_result));
@@ -1877,14 +1952,14 @@ pub fn eval_pair_iterative<'i>(
 
             (Rule::bitwise_and, EvalState::EvalLeft) => {
                 let left_result = frame.partial_results.pop().unwrap();
-                let mut int_result = left_result.as_num()? as i64;
+                let mut int_result = catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&left_result, scope, "&"));
 
                 let mut inner = frame.pair.clone().into_inner();
                 inner.next(); // Skip left
 
                 for next in inner {
-                    let right = crate::eval_pair_impl(next, scope)?.as_num()? as i64;
-                    int_result &= right;
+                    let right = crate::eval_pair_impl(next, scope)?;
+                    int_result &= catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&right, scope, "&"));
                 }
 
                 let value = QValue::Int(QInt::new(int_result));
@@ -1920,8 +1995,8 @@ pub fn eval_pair_iterative<'i>(
                     let operator = op_pair.as_str();
                     let right = crate::eval_pair_impl(inner.next().unwrap(), scope)?;
 
-                    let left_val = result.as_num()? as i64;
-                    let right_val = right.as_num()? as i64;
+                    let left_val = catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&result, scope, operator));
+                    let right_val = catchable!(stack, scope, 'eval_loop, crate::strict::bitwise_operand(&right, scope, operator));
 
                     let shifted = match operator {
                         "<<" => left_val.checked_shl(right_val as u32)
@@ -2125,7 +2200,7 @@ pub fn eval_pair_iterative<'i>(
                 let name = frame.pair.as_str();
                 let value = match scope.get(name) {
                     Some(v) => v,
-                    None => return name_err!("Undefined variable: {}", name),
+                    None => return name_err!("Undefined variable: {}{}", name, crate::suggest::for_variable(name, scope)),
                 };
                 push_result_to_parent(&mut stack, value, &mut final_result)?;
             }
@@ -2155,12 +2230,23 @@ pub fn eval_pair_iterative<'i>(
             (Rule::if_statement, EvalState::IfEvalCondition) => {
                 // Condition has been evaluated
                 let condition_value = frame.partial_results.pop().unwrap();
-                let condition_bool = condition_value.as_bool();
+                let condition_bool = catchable!(stack, scope, 'eval_loop, crate::strict::condition(&condition_value, scope, "if"));
 
                 let mut iter = frame.pair.clone().into_inner();
                 iter.next(); // Skip condition (already evaluated)
 
+                // Coverage: branch 0 is the if, then each elif, then the (possibly implicit) else
+                let branches = crate::coverage::is_enabled().then(|| {
+                    (frame.pair.as_span().start_pos().line_col().0, crate::coverage::branch_count(&frame.pair))
+                });
+                let record_branch = |scope: &Scope, taken: Option<usize>| {
+                    if let Some((line, total)) = branches {
+                        crate::coverage::record_branch(scope, line, taken.unwrap_or(total - 1), total);
+                    }
+                };
+
                 if condition_bool {
+                    record_branch(scope, Some(0));
                     // Execute if block
                     scope.push(); // New scope for if block
 
@@ -2200,14 +2286,17 @@ pub fn eval_pair_iterative<'i>(
                 } else {
                     // Check elif/else clauses
                     let mut found_match = false;
+                    let mut elif_index = 0;
 
                     for clause_pair in iter {
                         match clause_pair.as_rule() {
                             Rule::elif_clause => {
+                                elif_index += 1;
                                 let mut elif_inner = clause_pair.into_inner();
                                 let elif_condition = crate::eval_pair_impl(elif_inner.next().unwrap(), scope)?;
 
-                                if elif_condition.as_bool() {
+                                if catchable!(stack, scope, 'eval_loop, crate::strict::condition(&elif_condition, scope, "elif")) {
+                                    record_branch(scope, Some(elif_index));
                                     scope.push();
 
                                     // Execute elif body
@@ -2240,6 +2329,7 @@ pub fn eval_pair_iterative<'i>(
                                 }
                             }
                             Rule::else_clause => {
+                                record_branch(scope, None);
                                 scope.push();
 
                                 // Execute else body
@@ -2275,6 +2365,7 @@ pub fn eval_pair_iterative<'i>(
                     }
 
                     if !found_match {
+                        record_branch(scope, None);
                         push_result_to_parent(&mut stack, QValue::Nil(QNil), &mut final_result)?;
                     }
                 }
@@ -2305,7 +2396,7 @@ pub fn eval_pair_iterative<'i>(
                 // Condition evaluated - check if we should continue
                 let condition_value = frame.partial_results.pop().unwrap();
 
-                if condition_value.as_bool() {
+                if catchable!(stack, scope, 'eval_loop, crate::strict::condition(&condition_value, scope, "while")) {
                     // Condition is true - execute body iteratively
                     scope.push(); // New scope for loop iteration
 
@@ -2333,6 +2424,7 @@ pub fn eval_pair_iterative<'i>(
                         let loop_state = LoopState {
                             loop_var: None,
                             collection: None,
+                            source: None,
                             current_iteration: 0,
                             body_pairs: body_stmts,
                             current_stmt: 0,
@@ -2463,6 +2555,7 @@ pub fn eval_pair_iterative<'i>(
                         context: Some(EvalContext::Loop(LoopState {
                             loop_var: Some(loop_var),
                             collection: None,
+                            source: None,
                             current_iteration: 0,
                             body_pairs: iter.collect(), // Remaining are body statements
                             current_stmt: 0,
@@ -2505,13 +2598,22 @@ pub fn eval_pair_iterative<'i>(
                                 .map(|c| QValue::Str(QString::new(c.to_string())))
                                 .collect()
                         }
+                        // The pipeline runs once, fused, before the loop starts
+                        QValue::Iterator(it) => it.collect(scope)?,
+                        // Each date from start through end
+                        QValue::DateRange(range) => range.days()?,
+                        QValue::RowIterator(_) | QValue::CsvReader(_) | QValue::JsonStream(_) => {
+                            // Rows are fetched as the loop advances
+                            loop_state.source = Some(collection_value);
+                            Vec::new()
+                        }
                         _ => return Err(format!("Cannot iterate over {}", collection_value.as_obj().cls()).into()),
                     };
 
                     loop_state.collection = Some(elements);
 
                     // Start iteration at index 0
-                    if loop_state.collection.as_ref().unwrap().is_empty() {
+                    if loop_state.source.is_none() && loop_state.collection.as_ref().unwrap().is_empty() {
                         // Empty collection - skip loop
                         push_result_to_parent(&mut stack, QValue::Nil(QNil), &mut final_result)?;
                     } else {
@@ -2533,16 +2635,18 @@ pub fn eval_pair_iterative<'i>(
 
                 if let EvalContext::Loop(loop_state) = context {
                     let index = *index;
-                    let collection = loop_state.collection.as_ref().unwrap();
+                    let element = match &loop_state.source {
+                        Some(QValue::RowIterator(rows)) => rows.next_row()?,
+                        Some(QValue::CsvReader(rows)) => rows.next_row()?,
+                        Some(QValue::JsonStream(values)) => values.next_value()?,
+                        _ => loop_state.collection.as_ref().unwrap().get(index).cloned(),
+                    };
 
-                    if index >= collection.len() {
-                        // Finished iterating
-                        push_result_to_parent(&mut stack, QValue::Nil(QNil), &mut final_result)?;
-                    } else {
+                    if let Some(element) = element {
                         // Bind loop variable and start evaluating body statements
                         scope.push();
                         let loop_var = loop_state.loop_var.as_ref().unwrap();
-                        scope.declare(loop_var, collection[index].clone())?;
+                        scope.declare(loop_var, element)?;
 
                         if loop_state.body_pairs.is_empty() {
                             // Empty body - move to next element
@@ -2564,6 +2668,9 @@ pub fn eval_pair_iterative<'i>(
                                 context: Some(EvalContext::Loop(loop_state)),
                             });
                         }
+                    } else {
+                        // Finished iterating
+                        push_result_to_parent(&mut stack, QValue::Nil(QNil), &mut final_result)?;
                     }
                 } else {
                     return Err("Invalid context for ForIterateBody".to_string().into());
@@ -2689,7 +2796,7 @@ pub fn eval_pair_iterative<'i>(
                         if !is_builtin {
                             // Custom type - must implement Error trait
                             // Look up type in scope
-                            let type_found = if let Some(QValue::Type(qtype)) = scope.get(type_name) {
+                            let type_found = if let Some(QValue::Type(qtype)) = crate::resolve_qualified(type_name, scope) {
                                 Some((*qtype).clone())
                             } else {
                                 None
@@ -2800,10 +2907,15 @@ pub fn eval_pair_iterative<'i>(
                     if exception_flag.as_bool() {
                         // Exception occurred - parse it and try catch clauses
                         let error_msg = result_or_error.as_str();
+                        let raised = match result_or_error {
+                            QValue::Exception(exc) => Some(exc),
+                            _ => None,
+                        };
+                        let was_raised = raised.is_some();
 
-                        // QEP-037: Use current_exception from scope if available
-                        // (preserves original_value for user-defined exceptions)
-                        let exception = if let Some(exc) = scope.current_exception.clone() {
+                        // QEP-037: Use the raised exception, or current_exception from scope
+                        // if available (preserves original_value for user-defined exceptions)
+                        let exception = if let Some(exc) = raised.or_else(|| scope.current_exception.clone()) {
                             // Exception was set by raise statement - use it directly
                             let mut exc = exc;
                             if exc.stack.is_empty() {
@@ -2833,7 +2945,8 @@ pub fn eval_pair_iterative<'i>(
                         let mut matched_clause_idx = None;
                         for (idx, (_, exception_type_filter, _)) in try_state.catch_clauses.iter().enumerate() {
                             let matches = if let Some(ref expected_type_str) = exception_type_filter {
-                                let expected_type = ExceptionType::from_str(expected_type_str);
+                                // A module-qualified filter (mod.Type) matches on the type's own name
+                                let expected_type = ExceptionType::from_str(expected_type_str.rsplit('.').next().unwrap_or(expected_type_str));
                                 exception.exception_type.is_subtype_of(&expected_type)
                             } else {
                                 true // Catch-all
@@ -2885,10 +2998,15 @@ pub fn eval_pair_iterative<'i>(
                         } else {
                             // No matching catch - will re-throw after ensure
                             try_state.result = None;
+                            let error_value = if was_raised {
+                                QValue::Exception(exception)
+                            } else {
+                                QValue::Str(QString::new(error_msg))
+                            };
                             stack.push(EvalFrame {
                                 pair: frame.pair.clone(),
                                 state: EvalState::TryEvalEnsure,
-                                partial_results: vec![QValue::Str(QString::new(error_msg)), QValue::Bool(QBool::new(true))],
+                                partial_results: vec![error_value, QValue::Bool(QBool::new(true))],
                                 context: Some(context),
                             });
                         }
@@ -2993,7 +3111,7 @@ pub fn eval_pair_iterative<'i>(
                     // Return result or propagate exception
                     if exception_flag.as_bool() {
                         // Re-throw exception
-                        return Err(result_or_error.as_str().into());
+                        return Err(rethrow(result_or_error));
                     } else {
                         // Return result
                         push_result_to_parent(&mut stack, result_or_error, &mut final_result)?;
@@ -3031,7 +3149,7 @@ pub fn eval_pair_iterative<'i>(
                         // Return result or propagate exception
                         if exception_flag.as_bool() {
                             // Re-throw exception
-                            return Err(result_or_error.as_str().into());
+                            return Err(rethrow(result_or_error));
                         } else {
                             // Return result
                             push_result_to_parent(&mut stack, result_or_error, &mut final_result)?;
@@ -3149,6 +3267,16 @@ pub fn eval_pair_iterative<'i>(
                         // TODO: Make this properly iterative by using states
                         let right_result = crate::eval_pair_impl(right_pair, scope)?;
 
+                        // NDArray arithmetic broadcasts against numbers and other arrays
+                        if matches!(result, QValue::NDArray(_)) || matches!(right_result, QValue::NDArray(_)) {
+                            result = crate::types::QNDArray::apply_operator(op, &result, &right_result)?;
+                            continue;
+                        }
+                        if let (QValue::Counter(l), QValue::Counter(r)) = (&result, &right_result) {
+                            result = QValue::Counter(l.combine(op, r)?);
+                            continue;
+                        }
+
                         result = match op {
                             "+" => {
                                 // Fast path for Int + Int (QEP-042 optimization #3)
@@ -3283,7 +3411,7 @@ pub fn eval_pair_iterative<'i>(
             (Rule::dict_literal, EvalState::Initial) => {
                 // {key: value, key: value, ...}
                 let inner = frame.pair.clone().into_inner();
-                let mut map = std::collections::HashMap::new();
+                let mut map = IndexMap::new();
 
                 for dict_pair in inner {
                     if dict_pair.as_rule() == Rule::dict_pair {
@@ -3446,9 +3574,9 @@ pub fn eval_pair_iterative<'i>(
                                     continue 'eval_loop;
                                 }
                             }
-                            // Not inside try block - propagate error
-                            return Err(e.into());
                         }
+                        // Not inside try block (or raised from the outermost catch body) - propagate error
+                        return Err(e);
                     }
                 }
             }
@@ -3471,8 +3599,18 @@ fn handle_exception_in_try<'i>(
     scope: &mut Scope,
     error: EvalError,
 ) -> Result<bool, EvalError> {
-    // Convert EvalError to string for exception handling
-    let error_str: String = error.into();
+    // Bug #022: return/break/continue are never exceptions. Propagate them untouched:
+    // stringifying a return value Debug-formats it, which never terminates for
+    // closures whose captured scope contains themselves
+    if error.is_control_flow() {
+        return Ok(false);
+    }
+
+    // Keep a raised exception whole (QEP-037); other errors are handled as strings
+    let error_value = match error {
+        EvalError::Raised(exc) => QValue::Exception(*exc),
+        error => QValue::Str(QString::new(String::from(error))),
+    };
 
     // Check if we're inside a try block body evaluation
     // Special case: if we're in a catch block (TryEvalCatchStmt), skip it and look for outer try
@@ -3517,7 +3655,7 @@ fn handle_exception_in_try<'i>(
             if matches!(try_frame.state, EvalState::TryEvalBodyStmt(_)) {
                 scope.pop(); // Close try scope
                 try_frame.partial_results.clear();
-                try_frame.partial_results.push(QValue::Str(QString::new(error_str)));
+                try_frame.partial_results.push(error_value);
                 try_frame.partial_results.push(QValue::Bool(QBool::new(true)));
                 try_frame.state = EvalState::TryEvalBody;
                 return Ok(true); // Exception handled, continue loop
@@ -3527,7 +3665,7 @@ fn handle_exception_in_try<'i>(
                     scope.delete(var_name).ok();
                 }
                 try_frame.partial_results.clear();
-                try_frame.partial_results.push(QValue::Str(QString::new(error_str)));
+                try_frame.partial_results.push(error_value);
                 try_frame.partial_results.push(QValue::Bool(QBool::new(true)));
                 try_frame.state = EvalState::TryEvalEnsure;
                 return Ok(true); // Exception handled, continue loop
@@ -3537,6 +3675,15 @@ fn handle_exception_in_try<'i>(
     Ok(false) // Not in try block, should propagate
 }
 
+/// The error to re-throw for an exception a try statement didn't catch,
+/// as stored by `handle_exception_in_try`
+fn rethrow(error_value: QValue) -> EvalError {
+    match error_value {
+        QValue::Exception(exc) => EvalError::raised(exc),
+        other => other.as_str().into(),
+    }
+}
+
 /// Push a result to the parent frame's partial_results.
 /// If there's no parent frame, return the result (it's the final value).
 fn push_result_to_parent<'i>(
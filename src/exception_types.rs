@@ -19,6 +19,7 @@ pub fn register_exception_types(scope: &mut Scope) -> Result<(), String> {
     scope.declare("KeyErr", create_exception_type("KeyErr"))?;
     scope.declare("SyntaxErr", create_exception_type("SyntaxErr"))?;
     scope.declare("ConfigurationErr", create_exception_type("ConfigurationErr"))?;
+    scope.declare("WarningErr", create_exception_type("WarningErr"))?;
 
     Ok(())
 }
@@ -0,0 +1,471 @@
+// Source formatter for `quest fmt`
+//
+// Newlines are ordinary whitespace to the grammar, so a file can be
+// re-indented and re-spaced without changing how it parses. The formatter
+// lexes the source into tokens (keeping the comments that the grammar
+// discards) and re-prints it line by line: `fun`/`if`/... `end` blocks and
+// open brackets set the indentation, adjacent tokens get canonical spacing,
+// and lines over the width limit are broken at the commas of a bracket group.
+// Existing line breaks are kept, apart from collapsing runs of blank lines.
+//
+// Before anything is returned the output is parsed again and its tokens are
+// compared with the input's, so a formatter bug can't change a program.
+
+use pest::Parser;
+
+use crate::{QuestParser, Rule};
+
+/// Default maximum line width
+pub const DEFAULT_WIDTH: usize = 100;
+
+const INDENT: &str = "    ";
+
+/// Marks the `{` of `use "path" { names }` while spacing a line
+const IMPORT_LIST: &str = "use {";
+
+const KEYWORDS: &[&str] = &[
+    "if", "elif", "else", "end", "fun", "type", "trait", "impl", "with", "return", "and", "or",
+    "not", "true", "false", "nil", "use", "let", "del", "as", "for", "in", "to", "until", "step",
+    "while", "match", "try", "catch", "ensure", "raise", "const", "break", "continue", "pub",
+];
+
+/// Keywords that start a block closed by `end`
+const BLOCK_KEYWORDS: &[&str] = &["fun", "if", "while", "for", "type", "trait", "impl", "try", "match", "with"];
+
+/// Keywords that continue a block at the block's own indentation
+const MID_KEYWORDS: &[&str] = &["elif", "else", "catch", "ensure"];
+
+/// Longest operators first, so `**` wins over `*` and `?:` over `?`
+const OPERATORS: &[&str] = &[
+    "**", "..", "?:", "->", "==", "!=", "<=", ">=", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+];
+
+const BINARY_OPERATORS: &[&str] = &[
+    "=", "+=", "-=", "*=", "/=", "%=", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%",
+    "..", "|", "^", "&", "<<", ">>", "?:", "->",
+];
+
+const PREFIX_OPERATORS: &[&str] = &["-", "+", "*", "**", "~", "%", "@"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Word,
+    Number,
+    Str,
+    Comment,
+    Punct,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token<'a> {
+    pub kind: Kind,
+    pub text: &'a str,
+    /// Whitespace between this token and the previous one on the same line
+    gap: &'a str,
+    /// Word directly after `.`, i.e. a member name rather than a keyword
+    member: bool,
+}
+
+impl Token<'_> {
+    fn is(&self, text: &str) -> bool {
+        self.kind == Kind::Punct && self.text == text
+    }
+
+    pub fn is_keyword(&self) -> bool {
+        self.kind == Kind::Word && !self.member && KEYWORDS.contains(&self.text)
+    }
+
+    fn is_keyword_of(&self, words: &[&str]) -> bool {
+        self.is_keyword() && words.contains(&self.text)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is("(") || self.is("[") || self.is("{")
+    }
+
+    pub fn is_close(&self) -> bool {
+        self.is(")") || self.is("]") || self.is("}")
+    }
+
+    /// Whether an operator after this token is binary (`a - b`) rather than
+    /// a prefix (`= -b`)
+    fn ends_value(&self) -> bool {
+        match self.kind {
+            Kind::Number | Kind::Str => true,
+            Kind::Word => !self.is_keyword() || matches!(self.text, "end" | "nil" | "true" | "false"),
+            Kind::Punct => self.is_close(),
+            Kind::Comment => false,
+        }
+    }
+}
+
+/// Tokens of one source line; a multi-line string stays on the line it starts on
+pub type Line<'a> = Vec<Token<'a>>;
+
+/// Format Quest source. Fails if the source doesn't parse.
+pub fn format_source(source: &str, width: usize) -> Result<String, String> {
+    QuestParser::parse(Rule::program, source).map_err(|e| format!("Parse error: {}", e))?;
+
+    let lines = lex(source);
+    let output = render(&lines, width);
+
+    // Safety net: same program, just laid out differently
+    let expected = significant_tokens(&lines);
+    let actual = significant_tokens(&lex(&output));
+    if expected != actual || QuestParser::parse(Rule::program, &output).is_err() {
+        return Err("formatting would change the program (this is a formatter bug)".to_string());
+    }
+    Ok(output)
+}
+
+fn significant_tokens<'a>(lines: &[Line<'a>]) -> Vec<(Kind, &'a str)> {
+    lines.iter().flatten().map(|t| (t.kind, t.text.trim_end())).collect()
+}
+
+/// Split source into tokens, one Vec per line (also used by the REPL)
+pub fn lex(source: &str) -> Vec<Line<'_>> {
+    let mut lines = vec![Vec::new()];
+    let mut pos = 0;
+    let mut gap_start = 0;
+
+    while pos < source.len() {
+        let rest = &source[pos..];
+        let c = rest.chars().next().unwrap_or(' ');
+
+        if c == '\n' {
+            lines.push(Vec::new());
+            pos += 1;
+            gap_start = pos;
+            continue;
+        }
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+
+        let start = pos;
+        let kind = if c == '#' {
+            pos += rest[..rest.find('\n').unwrap_or(rest.len())].trim_end().len();
+            Kind::Comment
+        } else if let Some(len) = string_length(rest) {
+            pos += len;
+            Kind::Str
+        } else if c.is_ascii_digit() {
+            pos += number_length(rest);
+            Kind::Number
+        } else if c.is_alphabetic() || c == '_' {
+            pos += rest.find(|ch: char| !(ch.is_alphanumeric() || ch == '_')).unwrap_or(rest.len());
+            Kind::Word
+        } else {
+            pos += OPERATORS.iter()
+                .find(|op| rest.starts_with(*op))
+                .map(|op| op.len())
+                .unwrap_or(c.len_utf8());
+            Kind::Punct
+        };
+
+        let line = lines.last_mut().expect("at least one line");
+        let member = kind == Kind::Word && line.last().is_some_and(|t: &Token| t.is("."));
+        line.push(Token { kind, text: &source[start..pos], gap: &source[gap_start..start], member });
+
+        gap_start = pos;
+    }
+    lines
+}
+
+/// Length of a string or bytes literal at the start of `s`
+fn string_length(s: &str) -> Option<usize> {
+    let body = if s.starts_with("f\"") || s.starts_with("f'") || s.starts_with("b\"") || s.starts_with("b'")
+        || s.starts_with("B\"") || s.starts_with("B'") {
+        1
+    } else if s.starts_with('"') || s.starts_with('\'') {
+        0
+    } else {
+        return None;
+    };
+
+    let rest = &s[body..];
+    for triple in ["\"\"\"", "'''"] {
+        if body == 0 && rest.starts_with(triple) {
+            let end = rest[3..].find(triple).map(|i| i + 6).unwrap_or(rest.len());
+            return Some(body + end);
+        }
+    }
+
+    let quote = rest.chars().next()?;
+    let mut escaped = false;
+    for (i, ch) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return Some(body + i + 1);
+        }
+    }
+    Some(s.len())
+}
+
+/// Length of a number literal at the start of `s`
+fn number_length(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let radix_prefix = bytes.len() > 1 && bytes[0] == b'0' && matches!(bytes[1], b'x' | b'X' | b'b' | b'B' | b'o' | b'O');
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_alphanumeric() || b == b'_' {
+            i += 1;
+        } else if b == b'.' && bytes.get(i + 1).is_some_and(|n| n.is_ascii_digit()) {
+            i += 1;
+        } else if (b == b'+' || b == b'-') && !radix_prefix && matches!(bytes[i - 1], b'e' | b'E')
+            && bytes.get(i + 1).is_some_and(|n| n.is_ascii_digit()) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// An open block or bracket, with the indentation of the line it opened on
+struct Frame<'a> {
+    opener: &'a str,
+    indent: usize,
+}
+
+fn render(lines: &[Line<'_>], width: usize) -> String {
+    let mut out = String::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut blank_lines = 0;
+    let mut continues = false;
+
+    for line in lines {
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !out.is_empty() && blank_lines > 0 {
+            out.push('\n');
+        }
+        blank_lines = 0;
+
+        let indent = line_indent(line, &stack, continues);
+        let gaps = gaps(line, &stack);
+        for (indent, start, end) in wrap(line, &gaps, indent, 0, line.len(), width) {
+            out.push_str(&INDENT.repeat(indent));
+            out.push_str(&join(line, &gaps, start, end));
+            out.push('\n');
+        }
+
+        update_stack(line, indent, &mut stack);
+        continues = ends_with_operator(line) && stack.last().is_none_or(|f| !is_bracket(f.opener));
+    }
+    out
+}
+
+fn is_bracket(opener: &str) -> bool {
+    matches!(opener, "(" | "[" | "{")
+}
+
+fn line_indent(line: &Line<'_>, stack: &[Frame], continues: bool) -> usize {
+    let first = &line[0];
+    let top = stack.last();
+    let closes_top = first.is_close()
+        || first.is_keyword_of(&["end"])
+        || first.is_keyword_of(MID_KEYWORDS)
+        || (first.is_keyword_of(&["in"]) && top.is_some_and(|f| f.opener == "match"));
+    let continuation = usize::from(continues || first.is("."));
+    match top {
+        Some(frame) if closes_top => frame.indent,
+        Some(frame) => frame.indent + 1 + continuation,
+        None => continuation,
+    }
+}
+
+fn update_stack<'a>(line: &Line<'a>, indent: usize, stack: &mut Vec<Frame<'a>>) {
+    let starts_impl = line[0].is_keyword_of(&["impl"]);
+    for (i, token) in line.iter().enumerate() {
+        let after_percent = i > 0 && line[i - 1].is("%");
+        if token.is_open() {
+            stack.push(Frame { opener: token.text, indent });
+        } else if token.is_close() {
+            if stack.last().is_some_and(|f| is_bracket(f.opener)) {
+                stack.pop();
+            }
+        } else if token.is_keyword_of(&["end"]) {
+            if stack.last().is_some_and(|f| !is_bracket(f.opener)) {
+                stack.pop();
+            }
+        } else if token.is_keyword_of(BLOCK_KEYWORDS) && !after_percent {
+            let top = stack.last().map(|f| f.opener);
+            let opens = match token.text {
+                // Trait methods are signatures without a body
+                "fun" => top != Some("trait"),
+                // `impl Name ... end` inside a type; `impl Name { }` elsewhere
+                "impl" => top == Some("type"),
+                // `impl Name with A, B { }` isn't a with statement
+                "with" => !starts_impl,
+                _ => true,
+            };
+            if opens {
+                stack.push(Frame { opener: token.text, indent });
+            }
+        }
+    }
+}
+
+/// Whether the statement carries on to the next line (`x = a +`)
+fn ends_with_operator(line: &Line<'_>) -> bool {
+    match line.iter().rev().find(|t| t.kind != Kind::Comment) {
+        Some(t) if t.kind == Kind::Punct => BINARY_OPERATORS.contains(&t.text) || t.is(","),
+        Some(t) => t.is_keyword_of(&["and", "or", "not"]),
+        None => false,
+    }
+}
+
+/// Whitespace to print before each token of a line
+fn gaps<'a>(line: &Line<'a>, stack: &[Frame]) -> Vec<&'a str> {
+    // Open blocks and brackets, to tell slices (`a[1:2]`) from dict pairs
+    // and annotations (`x: 1`), and import lists from dicts
+    let mut open: Vec<&str> = stack.iter().map(|f| f.opener).collect();
+    let mut gaps = Vec::with_capacity(line.len());
+    for (i, token) in line.iter().enumerate() {
+        let prev = if i > 0 { Some(&line[i - 1]) } else { None };
+        let import_list = token.is("{") && prev.is_some_and(|p| p.kind == Kind::Str);
+        match prev {
+            None => gaps.push(""),
+            // use "std/test" { it, describe }
+            Some(p) if (p.is("{") && open.last() == Some(&IMPORT_LIST))
+                || (token.is("}") && open.last() == Some(&IMPORT_LIST)) => gaps.push(" "),
+            Some(p) => {
+                let before = if i > 1 { Some(&line[i - 2]) } else { None };
+                gaps.push(spacing(before, p, token, open.last() == Some(&"[")));
+            }
+        }
+        if import_list {
+            open.push(IMPORT_LIST);
+        } else if token.is_open() || token.is_keyword_of(BLOCK_KEYWORDS) {
+            open.push(token.text);
+        } else if token.is_close() || token.is_keyword_of(&["end"]) {
+            open.pop();
+        }
+    }
+    gaps
+}
+
+fn join(line: &Line<'_>, gaps: &[&str], start: usize, end: usize) -> String {
+    let mut out = String::new();
+    for i in start..end {
+        if i > start {
+            out.push_str(gaps[i]);
+        }
+        out.push_str(line[i].text);
+    }
+    out
+}
+
+/// Split `line[start..end]` if it is too long, at the commas of its first
+/// bracket group that has any, one item per line
+fn wrap(line: &Line<'_>, gaps: &[&str], indent: usize, start: usize, end: usize, width: usize) -> Vec<(usize, usize, usize)> {
+    let tokens = &line[start..end];
+    let too_long = INDENT.len() * indent + join(line, gaps, start, end).chars().count() > width;
+    let splittable = tokens.iter().all(|t| t.kind != Kind::Comment && !t.text.contains('\n'));
+    if !too_long || !splittable {
+        return vec![(indent, start, end)];
+    }
+
+    // Bracket groups that close within the piece, outermost first. Only a
+    // group followed by nothing but closing brackets is split, so calls in
+    // the middle of a chain or a concatenation stay whole.
+    let mut groups = Vec::new();
+    let mut open = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_open() {
+            open.push(start + i);
+        } else if token.is_close() {
+            if let Some(group_start) = open.pop() {
+                groups.push((open.len(), group_start, start + i));
+            }
+        }
+    }
+    groups.retain(|&(_, group_start, group_end)| {
+        line[group_end + 1..end].iter().all(|t| t.is_close())
+            && INDENT.len() * indent + join(line, gaps, start, group_start + 1).chars().count() <= width
+    });
+    groups.sort();
+
+    for (_, group_start, group_end) in groups {
+        let mut depth = 0;
+        let mut items = Vec::new();
+        let mut item_start = group_start + 1;
+        for i in group_start + 1..group_end {
+            let token = &line[i];
+            if token.is_open() {
+                depth += 1;
+            } else if token.is_close() {
+                depth -= 1;
+            } else if depth == 0 && (token.is(",") || token.is(";")) {
+                items.push((item_start, i + 1));
+                item_start = i + 1;
+            }
+        }
+        if items.is_empty() {
+            continue;
+        }
+        items.push((item_start, group_end));
+
+        let mut pieces = vec![(indent, start, group_start + 1)];
+        for (item_start, item_end) in items.into_iter().filter(|(s, e)| s < e) {
+            pieces.extend(wrap(line, gaps, indent + 1, item_start, item_end, width));
+        }
+        pieces.extend(wrap(line, gaps, indent, group_end, end, width));
+        return pieces;
+    }
+    vec![(indent, start, end)]
+}
+
+/// Whether `token` is a prefix operator, given the token before it
+fn is_prefix(before: Option<&Token<'_>>, token: &Token<'_>) -> bool {
+    token.kind == Kind::Punct
+        && PREFIX_OPERATORS.contains(&token.text)
+        && before.is_none_or(|b| !b.ends_value())
+}
+
+/// Whitespace between `prev` and `cur`
+fn spacing<'a>(before: Option<&Token<'a>>, prev: &Token<'a>, cur: &Token<'a>, in_slice: bool) -> &'a str {
+    if cur.kind == Kind::Comment {
+        // Keep trailing comments where the author aligned them
+        return if cur.gap.is_empty() { " " } else { cur.gap };
+    }
+    if prev.is_open() || cur.is_close() || cur.is(",") || cur.is(";") || cur.is(".") || prev.is(".") {
+        return "";
+    }
+    if prev.is(",") || prev.is(";") {
+        return " ";
+    }
+    if is_prefix(before, prev) {
+        return "";
+    }
+    if cur.is(":") || cur.is("?") {
+        return "";
+    }
+    if prev.is(":") {
+        return if in_slice { "" } else { " " };
+    }
+    if cur.is("(") || cur.is("[") {
+        let call_or_index = match prev.kind {
+            Kind::Word => !prev.is_keyword(),
+            Kind::Str => cur.is("["),
+            Kind::Punct => prev.is(")") || prev.is("]") || (cur.is("[") && prev.is("}")),
+            _ => false,
+        };
+        if call_or_index {
+            return "";
+        }
+    }
+    if cur.is("{") && prev.kind == Kind::Word && !prev.is_keyword() {
+        // `Array{Int}` vs `impl Name {`
+        return if cur.gap.is_empty() { "" } else { " " };
+    }
+    " "
+}
@@ -3,13 +3,14 @@
 // ============================================================================
 
 use crate::scope::{Scope, StackFrame};
-use crate::types::{QValue, QUserFun, QNil};
+use crate::types::{QValue, QUserFun, QNil, QDict};
 use crate::{QuestParser, Rule};
 use crate::control_flow::{EvalError, ControlFlow};
 use pest::Parser;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use crate::arg_err;
 
@@ -18,7 +19,7 @@ use crate::arg_err;
 #[derive(Debug, Clone)]
 pub struct CallArguments {
     pub positional: Vec<QValue>,
-    pub keyword: HashMap<String, QValue>,
+    pub keyword: IndexMap<String, QValue>,
 }
 
 impl CallArguments {
@@ -26,14 +27,25 @@ impl CallArguments {
     pub fn positional_only(args: Vec<QValue>) -> Self {
         CallArguments {
             positional: args,
-            keyword: HashMap::new(),
+            keyword: IndexMap::new(),
         }
     }
 
     /// Create new CallArguments with both positional and keyword args
-    pub fn new(positional: Vec<QValue>, keyword: HashMap<String, QValue>) -> Self {
+    pub fn new(positional: Vec<QValue>, keyword: IndexMap<String, QValue>) -> Self {
         CallArguments { positional, keyword }
     }
+
+    /// Flatten into positional args for builtin (Rust) functions and methods.
+    /// Keyword args are appended as a trailing options Dict, matching the
+    /// `func(args..., options?: Dict)` convention used by builtin modules.
+    pub fn into_builtin_args(self) -> Vec<QValue> {
+        let mut args = self.positional;
+        if !self.keyword.is_empty() {
+            args.push(QValue::Dict(Box::new(QDict::new(self.keyword))));
+        }
+        args
+    }
 }
 
 /// Call a user-defined function with proper closure semantics (QEP-035)
@@ -50,7 +62,7 @@ pub fn call_user_function(
     call_args: CallArguments,
     parent_scope: &mut Scope,
     call_line: Option<usize>,  // QEP-057: Line number where function was called
-) -> Result<QValue, String> {
+) -> Result<QValue, EvalError> {
     let anon = "<anonymous>".to_string();
     let func_name = user_fun.name.as_ref().unwrap_or(&anon);
 
@@ -82,15 +94,20 @@ pub fn call_user_function(
     func_scope.current_file = parent_scope.current_file.clone();
     func_scope.current_line = parent_scope.current_line;
     func_scope.current_function = Some(func_name.clone());
+    func_scope.source_file = user_fun.source_file.clone();
+    func_scope.line_offset = user_fun.line_offset;
 
     // Push stack frame for exception tracking (QEP-057 enhanced)
     // Since call_stack is now shared, this automatically updates both scopes
     let stack_frame = StackFrame::with_location(
         func_name.clone(),
         parent_scope.current_file.clone(),
-        call_line  // QEP-057: Use explicit call site line number
+        // QEP-057: Use explicit call site line number, mapped from a function
+        // body back to its file
+        call_line.map(|line| line + parent_scope.line_offset)
     );
     func_scope.push_stack_frame(stack_frame);
+    let _caller = crate::debugger::enter_call(parent_scope);
 
     // Push new scope level for local variables and parameters
     func_scope.push();
@@ -148,7 +165,7 @@ pub fn call_user_function(
     for i in param_index..user_fun.params.len() {
         let param_name = &user_fun.params[i];
 
-        if let Some(kw_value) = unmatched_kwargs.remove(param_name) {
+        if let Some(kw_value) = unmatched_kwargs.shift_remove(param_name) {
             // Keyword arg provided for this param
             if let Some(param_type) = &user_fun.param_types[i] {
                 check_parameter_type(&kw_value, param_type, param_name)?;
@@ -183,7 +200,7 @@ pub fn call_user_function(
 
     // Handle kwargs (if any)
     if let Some(kwargs_name) = &user_fun.kwargs {
-        // QDict expects HashMap<String, QValue>, so unmatched_kwargs is already the right format
+        // QDict expects IndexMap<String, QValue>, so unmatched_kwargs is already the right format
         let kwargs_dict = crate::types::QDict::new(unmatched_kwargs);
         func_scope.declare(kwargs_name, QValue::Dict(Box::new(kwargs_dict)))?;
     } else if !unmatched_kwargs.is_empty() {
@@ -195,6 +212,8 @@ pub fn call_user_function(
         );
     }
 
+    let traced_call = crate::trace::enter_call(user_fun, func_name, &func_scope);
+
     // Parse and evaluate function body
     let pairs = QuestParser::parse(Rule::program, &user_fun.body)
         .map_err(|e| format!("Parse error in function body: {}", e))?;
@@ -224,7 +243,14 @@ pub fn call_user_function(
                     // Pop scope but keep stack frame for exception tracing
                     // Stack frames will be cleared by try/catch handler after capturing
                     func_scope.pop();
-                    return Err(e.to_string());
+                    // QEP-037: a raised exception keeps its value for the caller's catch
+                    // clause. It is also left in the caller's scope for callers that only
+                    // pass the error on as a string (e.g. builtins running a callback)
+                    if let EvalError::Raised(exc) = &e {
+                        parent_scope.current_exception = Some((**exc).clone());
+                        return Err(e);
+                    }
+                    return Err(e.to_string().into());
                 }
             }
         }
@@ -252,9 +278,36 @@ pub fn call_user_function(
         check_return_type(&result, return_type, func_name)?;
     }
 
+    traced_call.returned(func_name, &result);
     Ok(result)
 }
 
+/// Call any callable value: a user function, a builtin, or a struct with a
+/// `_call` method (e.g. a decorated function)
+pub fn call_value(func: &QValue, call_args: CallArguments, scope: &mut Scope) -> Result<QValue, String> {
+    use crate::type_err;
+
+    match func {
+        QValue::UserFun(f) => call_user_function(f, call_args, scope, scope.current_line).map_err(|e| e.to_string()),
+        QValue::Fun(f) => {
+            let name = if f.parent_type.is_empty() { f.name.clone() } else { format!("{}.{}", f.parent_type, f.name) };
+            crate::call_builtin_function(&name, call_args.into_builtin_args(), scope).map_err(|e| e.to_string())
+        }
+        QValue::Struct(s) => {
+            let type_name = s.borrow().type_name.clone();
+            let Some(method) = crate::find_type_definition(&type_name, scope).and_then(|t| t.get_method("_call").cloned()) else {
+                return type_err!("Cannot call value of type {} - no _call method", type_name);
+            };
+            scope.push();
+            scope.declare("self", func.clone())?;
+            let result = call_user_function(&method, call_args, scope, scope.current_line);
+            scope.pop();
+            result.map_err(|e| e.to_string())
+        }
+        _ => type_err!("Expected a function, got {}", func.as_obj().cls()),
+    }
+}
+
 /// Helper to capture current scope chain for function creation
 /// Returns a clone of the entire scope chain (all levels)
 /// This allows closures to:
@@ -1,6 +1,7 @@
 use pest::Parser;
 use pest_derive::Parser;
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::env;
 use std::fs;
 use std::io::{self, IsTerminal, Read};
@@ -14,6 +15,11 @@ use std::sync::OnceLock;
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
 
+// Otherwise count heap usage for sys.memory_stats()
+#[cfg(not(feature = "dhat-heap"))]
+#[global_allocator]
+static ALLOC: alloc_counter::CountingAlloc = alloc_counter::CountingAlloc;
+
 mod types;
 use types::*;
 
@@ -35,13 +41,25 @@ mod commands;
 mod function_call;
 mod numeric_ops;
 mod alloc_counter;
+mod formatter;
+mod checker;
+mod watch;
+mod package;
+mod debugger;
+mod dap;
+mod trace;
+mod profiler;
+mod warnings;
 mod eval;
+mod suggest;
+mod strict;
 mod server;
+mod coverage;
 
 use scope::Scope;
 use module_loader::{load_external_module, extract_docstring};
 use repl::{run_repl, show_help};
-use commands::{run_script, handle_run_command, handle_test_command};
+use commands::{run_script, handle_run_command, handle_test_command, handle_bench_command, handle_migrate_command, handle_fmt_command, handle_check_command, handle_eval_command, handle_watch_command, handle_trace_command, handle_profile_command, handle_install_command, handle_debug_command};
 use function_call::call_user_function;
 use numeric_ops::apply_compound_op;
 
@@ -99,6 +117,68 @@ fn normalize_index(idx: i64, len: usize, type_name: &str) -> Result<usize, Strin
     }
 }
 
+/// Evaluate the entries of an index_access: plain expressions and
+/// start:stop:step slices
+fn eval_index_entries(index_access: pest::iterators::Pair<Rule>, scope: &mut Scope) -> EvalResult<Vec<IndexEntry>> {
+    let mut entries = Vec::new();
+    for entry in index_access.into_inner() {
+        if entry.as_rule() != Rule::slice {
+            entries.push(IndexEntry::At(eval_pair(entry, scope)?));
+            continue;
+        }
+        let mut range = SliceRange::default();
+        for part in entry.into_inner() {
+            let bound = match eval_pair(part.clone().into_inner().next().unwrap(), scope)? {
+                QValue::Int(i) => Some(i.value),
+                QValue::Nil(_) => None,
+                other => return type_err!("Slice bounds must be Int or nil, got {}", other.as_obj().cls()),
+            };
+            match part.as_rule() {
+                Rule::slice_start => range.start = bound,
+                Rule::slice_stop => range.stop = bound,
+                _ => range.step = bound,
+            }
+        }
+        entries.push(IndexEntry::Range(range));
+    }
+    Ok(entries)
+}
+
+/// Whether an index_access needs index_with_entries (a slice or several indices)
+fn is_extended_index(index_access: &pest::iterators::Pair<Rule>) -> bool {
+    let mut entries = index_access.clone().into_inner();
+    let first = entries.next();
+    entries.next().is_some() || first.is_some_and(|e| e.as_rule() == Rule::slice)
+}
+
+/// Apply slices and multi-dimensional indices: nd[1:3, 0], arr[::2], s[1:]
+fn index_with_entries(base: &QValue, entries: &[IndexEntry]) -> EvalResult<QValue> {
+    if let QValue::NDArray(nda) = base {
+        return nda.index(entries);
+    }
+    let range = match entries {
+        [IndexEntry::Range(range)] => range,
+        _ => return type_err!("{} does not support multi-dimensional indexing", base.as_obj().cls()),
+    };
+    match base {
+        QValue::Array(arr) => {
+            let elements = arr.elements.borrow();
+            let picked = range.indices(elements.len())?.into_iter().map(|i| elements[i].clone()).collect();
+            Ok(QValue::Array(QArray::new(picked)))
+        }
+        QValue::Str(s) => {
+            let chars: Vec<char> = s.value.chars().collect();
+            let picked: String = range.indices(chars.len())?.into_iter().map(|i| chars[i]).collect();
+            Ok(QValue::Str(QString::new(picked)))
+        }
+        QValue::Bytes(b) => {
+            let picked = range.indices(b.data.len())?.into_iter().map(|i| b.data[i]).collect();
+            Ok(QValue::Bytes(QBytes::new(picked)))
+        }
+        _ => type_err!("{} does not support slicing", base.as_obj().cls()),
+    }
+}
+
 /// QEP-015: Check if a value matches a type constraint for variable assignment
 fn check_variable_type(value: &QValue, type_constraint: &str, var_name: &str) -> Result<(), String> {
     // Helper to convert to title case (e.g., "int" -> "Int", "str" -> "Str")
@@ -212,7 +292,7 @@ fn call_method_on_value(
                         .ok_or_else(|| format!("Index {} out of bounds for array of length {}", index, elements.len()).into())
                 }
                 // Higher-order methods that need scope
-                "map" | "filter" | "each" | "reduce" | "any" | "all" | "find" | "find_index" => {
+                "map" | "filter" | "each" | "reduce" | "any" | "all" | "find" | "find_index" | "sort_by" | "group_by" | "unique" => {
                     call_array_higher_order_method(a, method_name, args, scope, call_user_function_compat)
                 }
                 // Fallback to regular method dispatch for less common methods
@@ -222,7 +302,7 @@ fn call_method_on_value(
         QValue::Dict(d) => {
             // Dict has special higher-order methods that need scope
             match method_name {
-                "each" => call_dict_higher_order_method(d, method_name, args, scope, call_user_function_compat),
+                "each" | "map_values" | "filter" => call_dict_higher_order_method(d, method_name, args, scope, call_user_function_compat),
                 _ => d.call_method(method_name, args),
             }
         }
@@ -230,7 +310,13 @@ fn call_method_on_value(
             // Structs require special handling - lookup type, find method, bind self
             // IMPORTANT: Extract type_name first to drop borrow before executing method
             let type_name = qstruct.borrow().type_name.clone();
-            
+
+            match method_name {
+                "cls" if args.is_empty() => return Ok(QValue::Str(QString::new(type_name))),
+                "_id" if args.is_empty() => return Ok(QValue::Int(QInt::new(qstruct.borrow().id as i64))),
+                _ => {}
+            }
+
             if let Some(qtype) = find_type_definition(&type_name, scope) {
                 if let Some(method) = qtype.get_method(method_name) {
                     scope.push();
@@ -239,7 +325,7 @@ fn call_method_on_value(
                     scope.pop();
                     Ok(return_value)
                 } else {
-                    attr_err!("Struct {} has no method '{}'", type_name, method_name)
+                    attr_err!("Struct {} has no method '{}'{}", type_name, method_name, suggest::for_method(method_name, &qtype))
                 }
             } else {
                 type_err!("Type {} not found", type_name)
@@ -256,9 +342,9 @@ fn call_method_on_value(
                     // Try class methods (Ruby-style: stored with __class__: prefix)
                     let class_method_name = format!("__class__:{}", method_name);
                     if let Some(class_method) = t.get_method(&class_method_name) {
-                        call_user_function(&class_method, function_call::CallArguments::positional_only(args), scope, scope.current_line).map_err(|e| e.into())
+                        call_user_function(&class_method, function_call::CallArguments::positional_only(args), scope, scope.current_line)
                     } else {
-                        attr_err!("Type {} has no method '{}'", t.name, method_name)
+                        attr_err!("Type {} has no method '{}'{}", t.name, method_name, suggest::for_static_method(method_name, t))
                     }
                 }
             }
@@ -269,37 +355,80 @@ fn call_method_on_value(
         QValue::Module(m) => {
             match method_name {
                 "_doc" => Ok(QValue::Str(QString::new(m._doc()))),
+                "cls" => Ok(QValue::Str(QString::new(m.cls()))),
                 "str" => Ok(QValue::Str(QString::new(m.str()))),
                 "_rep" => Ok(QValue::Str(QString::new(m._rep()))),
                 "_id" => Ok(QValue::Int(QInt::new(m._id() as i64))),
-                _ => attr_err!("Module {} has no method '{}'", m.name, method_name),
+                _ => attr_err!("Module {} has no method '{}'{}", m.name, method_name, suggest::for_member(method_name, m)),
             }
         }
         QValue::Trait(_) => attr_err!("Cannot call methods on traits"),
         QValue::Exception(e) => e.call_method(method_name, args),
         QValue::Set(s) => s.call_method(method_name, args),
+        QValue::Iterator(it) => it.call_method(method_name, args, scope),
+        QValue::BytesBuffer(b) => b.call_method(method_name, args),
+        QValue::Deque(d) => d.call_method(method_name, args),
+        QValue::Heap(h) => h.call_method(method_name, args, scope),
+        QValue::PriorityQueue(pq) => pq.call_method(method_name, args, scope),
+        QValue::Counter(c) => c.call_method(method_name, args),
         QValue::Timestamp(ts) => ts.call_method(method_name, args),
         QValue::Zoned(z) => z.call_method(method_name, args),
         QValue::Date(d) => d.call_method(method_name, args),
         QValue::Time(t) => t.call_method(method_name, args),
         QValue::Span(s) => s.call_method(method_name, args),
         QValue::DateRange(dr) => dr.call_method(method_name, args),
+        QValue::Stopwatch(sw) => sw.call_method(method_name, args),
         QValue::SerialPort(sp) => sp.call_method(method_name, args),
+        QValue::ModbusClient(c) => c.call_method(method_name, args),
+        QValue::CanBus(b) => b.call_method(method_name, args),
         QValue::SqliteConnection(conn) => conn.call_method(method_name, args),
         QValue::SqliteCursor(cursor) => cursor.call_method(method_name, args),
         QValue::PostgresConnection(conn) => conn.call_method(method_name, args),
         QValue::PostgresCursor(cursor) => cursor.call_method(method_name, args),
         QValue::MysqlConnection(conn) => conn.call_method(method_name, args),
         QValue::MysqlCursor(cursor) => cursor.call_method(method_name, args),
-        QValue::HtmlTemplate(tmpl) => tmpl.call_method(method_name, args),
+        QValue::ConnectionPool(pool) => pool.call_method(method_name, args),
+        QValue::PoolLease(lease) => lease.call_method(method_name, args),
+        QValue::Transaction(tx) => tx.call_method(method_name, args),
+        QValue::RowIterator(it) => it.call_method(method_name, args),
+        QValue::ImapClient(c) => c.call_method(method_name, args),
+        QValue::Pop3Client(c) => c.call_method(method_name, args),
+        QValue::FtpClient(c) => c.call_method(method_name, args),
+        QValue::SftpClient(c) => c.call_method(method_name, args),
+        QValue::NatsClient(c) => c.call_method(method_name, args),
+        QValue::NatsSubscription(s) => s.call_method(method_name, args),
+        QValue::HtmlTemplate(tmpl) => tmpl.call_method(method_name, args, scope),
+        QValue::SafeString(s) => s.call_method(method_name, args),
+        QValue::RegexPattern(p) => p.call_method(method_name, args, scope),
+        QValue::RegexMatch(m) => m.call_method(method_name, args),
         QValue::HttpClient(client) => client.call_method(method_name, args),
         QValue::HttpRequest(req) => req.call_method(method_name, args),
         QValue::HttpResponse(resp) => resp.call_method(method_name, args),
         QValue::ProcessResult(pr) => pr.call_method(method_name, args),
-        QValue::Process(p) => p.call_method(method_name, args),
+        QValue::PipelineResult(pr) => pr.call_method(method_name, args),
+        QValue::Process(p) => p.call_method(method_name, args, scope),
         QValue::WritableStream(ws) => ws.call_method(method_name, args),
         QValue::ReadableStream(rs) => rs.call_method(method_name, args),
+        QValue::XmlElement(el) => el.call_method(method_name, args),
+        QValue::XmlReader(reader) => reader.call_method(method_name, args),
+        QValue::CsvReader(reader) => reader.call_method(method_name, args),
+        QValue::CsvWriter(writer) => writer.call_method(method_name, args),
+        QValue::JsonStream(stream) => stream.call_method(method_name, args),
+        QValue::JsonSchema(schema) => schema.call_method(method_name, args),
+        QValue::ProtoSchema(v) => v.call_method(method_name, args),
+        QValue::TarReader(v) => v.call_method(method_name, args),
+        QValue::TarWriter(v) => v.call_method(method_name, args),
+        QValue::Compressor(v) => v.call_method(method_name, args),
+        QValue::Decompressor(v) => v.call_method(method_name, args),
+        QValue::CompressedReader(v) => v.call_method(method_name, args),
+        QValue::CompressedWriter(v) => v.call_method(method_name, args),
+        QValue::CryptoKey(v) => v.call_method(method_name, args),
         QValue::Rng(rng) => modules::call_rng_method(rng, method_name, args).map_err(|e| e.into()),
+        QValue::Cache(c) => c.call_method(method_name, args, scope),
+        QValue::Url(u) => u.call_method(method_name, args),
+        QValue::IpAddr(a) => a.call_method(method_name, args),
+        QValue::Cidr(n) => n.call_method(method_name, args),
+        QValue::Image(img) => img.call_method(method_name, args),
         QValue::StringIO(sio) => {
             let mut stringio = sio.borrow_mut();
             stringio.call_method(method_name, args)
@@ -453,7 +582,7 @@ fn parse_call_arguments(
     use crate::arg_err;
     
     let mut positional = Vec::new();
-    let mut keyword = HashMap::new();
+    let mut keyword = IndexMap::new();
     let mut explicit_keywords = std::collections::HashSet::new();
     let mut seen_named = false;
     
@@ -540,7 +669,7 @@ fn call_user_function_compat(
     scope: &mut Scope
 ) -> Result<QValue, EvalError> {
     // QEP-057: Pass current line for stack traces
-    call_user_function(user_fun, function_call::CallArguments::positional_only(args), scope, scope.current_line).map_err(|e| e.into())
+    call_user_function(user_fun, function_call::CallArguments::positional_only(args), scope, scope.current_line)
 }
 
 fn apply_decorator(
@@ -575,8 +704,8 @@ fn apply_decorator(
         }
     }
     
-    // Look up the decorator type
-    let decorator_type = scope.get(&decorator_name)
+    // Look up the decorator type (may be module-qualified: @mod.Type)
+    let decorator_type = resolve_qualified(&decorator_name, scope)
     .ok_or_else(|| format!("Decorator '{}' not found", decorator_name))?;
     
     // Verify it's a type
@@ -619,8 +748,8 @@ fn apply_decorator(
                 // No arguments - just pass the function
                 construct_struct(&qtype, vec![func.clone()], None, scope)?
             } else if args_inner[0].as_rule() == Rule::named_arg {
-                // Named arguments - collect into HashMap
-                let mut named_args = HashMap::new();
+                // Named arguments - collect into IndexMap
+                let mut named_args = IndexMap::new();
                 // First positional arg is always the function being decorated
                 named_args.insert("func".to_string(), func.clone());
 
@@ -682,6 +811,9 @@ fn eval_assignment(
     for part in target_parts {
         match part.as_rule() {
             Rule::index_access => {
+                if is_extended_index(&part) {
+                    return type_err!("Cannot assign to a slice or multi-dimensional index");
+                }
                 let index_expr = part.into_inner().next().unwrap();
                 let index_value = eval_pair(index_expr, scope)?;
                 postfix_ops.push(("index".to_string(), Some(index_value)));
@@ -710,7 +842,7 @@ fn eval_assignment(
         } else {
             let current = match scope.get(&identifier) {
                 Some(v) => v,
-                None => return name_err!("Undefined variable: {}", identifier),
+                None => return name_err!("Undefined variable: {}{}", identifier, suggest::for_variable(&identifier, scope)),
             };
             apply_compound_op(&current, op_str, &rhs)?
         };
@@ -735,7 +867,7 @@ fn eval_assignment(
             rhs
         } else {
             // Get current value for compound ops
-            let current = get_indexed_value(&parent, index)?;
+            let current = get_indexed_value(&parent, index, scope)?;
             apply_compound_op(&current, op_str, &rhs)?
         };
 
@@ -764,12 +896,16 @@ fn eval_assignment(
                         qstruct.borrow_mut().set_field(field_name, value);
                         Ok(())
                     } else {
-                        attr_err!("Type {} has no field '{}'", type_name, field_name)
+                        attr_err!("Type {} has no field '{}'{}", type_name, field_name, suggest::for_field(&field_name, &qtype))
                     }
                 } else {
                     name_err!("Type {} not found", type_name)
                 }
             }
+            QValue::Module(module) => {
+                // Module members are read-only; tests replace them with test.mock()
+                attr_err!("Cannot assign to member '{}' of module {}", field_name, module.name)
+            }
             _ => attr_err!("Cannot assign to field of non-struct type")
         }
     }
@@ -779,17 +915,17 @@ fn eval_assignment(
 fn navigate_to_parent(
     identifier: &str,
     ops: &[(String, Option<QValue>)],
-    scope: &Scope
+    scope: &mut Scope
 ) -> Result<QValue, EvalError> {
     let mut current = match scope.get(identifier) {
         Some(v) => v,
-        None => return Err(format!("NameErr: Undefined variable: {}", identifier).into()),
+        None => return Err(format!("NameErr: Undefined variable: {}{}", identifier, suggest::for_variable(identifier, scope)).into()),
     };
 
     for (op_type, op_value) in ops {
         if let Some(index) = op_value {
             // Index access
-            current = get_indexed_value(&current, index)?;
+            current = get_indexed_value(&current, index, scope)?;
         } else {
             // Member access
             let field_name = op_type;
@@ -809,7 +945,7 @@ fn navigate_to_parent(
 }
 
 // Get value from indexed access (helper)
-fn get_indexed_value(container: &QValue, index: &QValue) -> Result<QValue, EvalError> {
+fn get_indexed_value(container: &QValue, index: &QValue, scope: &mut Scope) -> Result<QValue, EvalError> {
     match container {
         QValue::Array(arr) => {
             let idx = index.as_num()? as isize;
@@ -827,10 +963,10 @@ fn get_indexed_value(container: &QValue, index: &QValue) -> Result<QValue, EvalE
         }
         QValue::Dict(dict) => {
             let key = index.as_str();
-            dict.map.borrow().get(&key)
+            dict.get_or_default(&key, scope)?
                 .ok_or_else(|| format!("Key '{}' not found in dict", key).into())
-                .map(|v| v.clone())
         }
+        QValue::Counter(counter) => Ok(counter.count(index)?),
         QValue::Str(_) | QValue::Bytes(_) => {
             type_err!("Strings and Bytes are immutable - cannot assign to index")
         }
@@ -861,6 +997,7 @@ fn set_indexed_value(container: QValue, index: QValue, value: QValue) -> Result<
             dict.map.borrow_mut().insert(key, value);
             Ok(())
         }
+        QValue::Counter(counter) => counter.set(&index, &value),
         QValue::Str(_) | QValue::Bytes(_) => {
             type_err!("Strings and Bytes are immutable - cannot assign to index")
         }
@@ -885,13 +1022,14 @@ fn eval_simple_condition(condition: &pest::iterators::Pair<Rule>, scope: &mut Sc
     // The optimization is mainly in avoiding the .clone() overhead through the fast path detection
     // Future: Could add more aggressive inlining here for specific patterns like "i < limit"
     let result = eval_pair(condition.clone(), scope)?;
-    Ok(result.as_bool())
+    strict::condition(&result, scope, "while")
 }
 
 /// Helper function to handle lambda expression parsing
 /// Extracted to avoid code duplication between expression and expression_statement handlers
 fn handle_lambda_expression(
     pair_str: String,
+    start: pest::Position,
     first: pest::iterators::Pair<Rule>,
     _inner: pest::iterators::Pairs<Rule>,
     scope: &mut Scope
@@ -902,15 +1040,19 @@ fn handle_lambda_expression(
 
     let mut varargs_name = None;
     let mut varargs_type = None;
+    let mut kwargs_name = None;
+    let mut kwargs_type = None;
 
     // Collect parameters if first was parameter_list
     if first.as_rule() == Rule::parameter_list {
-        let (p, pd, pt, va, vat, _kw, _kwt) = parse_parameters(first);
+        let (p, pd, pt, va, vat, kw, kwt) = parse_parameters(first);
         params = p;
         param_defaults = pd;
         param_types = pt;
         varargs_name = va;
         varargs_type = vat;
+        kwargs_name = kw;
+        kwargs_type = kwt;
     }
 
     // Extract body from the original string
@@ -941,7 +1083,12 @@ fn handle_lambda_expression(
 
     // Capture current scope for closure support
     let captured = function_call::capture_current_scope(scope);
-    let func = if varargs_name.is_some() {
+    let mut func = if kwargs_name.is_some() {
+        QValue::UserFun(Box::new(QUserFun::new_with_variadics(
+            None, params, param_defaults, param_types, body, None, captured,
+            varargs_name, varargs_type, kwargs_name, kwargs_type, None
+        )))
+    } else if varargs_name.is_some() {
         QValue::UserFun(Box::new(QUserFun::new_with_varargs(
             None, params, param_defaults, param_types, body, None, captured,
             varargs_name, varargs_type
@@ -951,6 +1098,12 @@ fn handle_lambda_expression(
             None, params, param_defaults, param_types, body, None, captured
         )))
     };
+    if let QValue::UserFun(f) = &mut func {
+        f.source = Some(Rc::new(pair_str.trim().to_string()));
+        // line_col() scans the source from the start, so it's only worked out
+        // here rather than for every expression
+        coverage::tag_function(f, scope, &pair_str, start.line_col().0);
+    }
     Ok(func)
 }
 
@@ -1307,8 +1460,21 @@ pub fn eval_pair(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> EvalRe
 pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> EvalResult<QValue> {
     match pair.as_rule() {
         Rule::statement => {
+            if coverage::is_enabled() {
+                coverage::record_line(scope, pair.as_span().start_pos().line_col().0);
+            }
+            // line_col() scans the source from the start, so only when it's used
+            let line = (debugger::is_enabled() || trace::is_enabled())
+                .then(|| pair.as_span().start_pos().line_col().0);
+            if let Some(line) = line {
+                debugger::on_statement(scope, line);
+            }
             // A statement can be various things, just evaluate the inner
             let inner = pair.into_inner().next().unwrap();
+            if let Some(line) = line {
+                trace::statement(scope, line, inner.as_rule(), inner.as_str());
+            }
+            profiler::on_statement(scope);
             eval_pair(inner, scope)
         }
         Rule::pub_statement => {
@@ -1405,6 +1571,13 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 }
             }
             
+            // Flat encoding module names from before std/encoding/
+            if let Some(name) = path_str.strip_prefix("std/").filter(|name| module_loader::ENCODING_MODULES.contains(name)) {
+                let new_path = format!("std/encoding/{}", name);
+                warnings::warn(scope, &format!("use \"{}\" is deprecated; use \"{}\" instead", path_str, new_path), warnings::DEPRECATION_WARNING, 1)?;
+                path_str = new_path;
+            }
+
             // Check if this is a built-in module (std/* namespace)
             if let Some(builtin_name) = path_str.strip_prefix("std/") {
                 // Try to resolve as built-in module first
@@ -1415,27 +1588,57 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     "hash" => Some(create_hash_module()),
                     "io" => Some(create_io_module()),
                     "crypto" => Some(create_crypto_module()),
+                    "crypto/jwt" => Some(create_jwt_module()),
                     "time" => Some(create_time_module()),
                     "serial" => Some(create_serial_module()),
+                    "serial/modbus" => Some(create_modbus_module()),
+                    "serial/can" => Some(create_can_module()),
                     "regex" => Some(create_regex_module()),
                     "uuid" => Some(create_uuid_module()),
                     "ndarray" => Some(create_ndarray_module()),
+                    "collections" => Some(create_collections_module()),
+                    "inspect" => Some(create_inspect_module()),
+                    "unicode" => Some(create_unicode_module()),
+                    "locale" => Some(create_locale_module()),
+                    "collate" => Some(create_collate_module()),
+                    "cache" => Some(create_cache_module()),
+                    "jobs" => Some(create_jobs_module()),
+                    "image" => Some(create_image_module()),
                     "settings" => Some(create_settings_module()),
+                    "config" => Some(create_config_module()),
                     "toml" => Some(create_toml_module()),
                     "rand" => Some(create_rand_module()),
                     "web" => Some(create_web_module()),
                     "sys" => Some(create_sys_module(get_script_args(), get_script_path())),
-                    // Encoding modules (only new nested paths)
+                    // Encoding modules (flat std/json etc. are mapped here above)
                     "encoding/b64" => Some(create_b64_module()),
                     "encoding/json" => Some(create_encoding_json_module()),
                     "encoding/struct" => Some(create_struct_module()),
                     "encoding/hex" => Some(create_hex_module()),
                     "encoding/url" => Some(create_url_module()),
                     "encoding/csv" => Some(create_csv_module()),
+                    "encoding/yaml" => Some(create_yaml_module()),
+                    "encoding/xml" => Some(create_xml_module()),
+                    "encoding/proto" => Some(create_proto_module()),
+                    "encoding/jsonschema" => Some(create_jsonschema_module()),
+                    "encoding/qr" => Some(create_qr_module()),
                     // Database modules
                     "db/sqlite" => Some(create_sqlite_module()),
                     "db/postgres" => Some(create_postgres_module()),
                     "db/mysql" => Some(create_mysql_module()),
+                    // Mail modules
+                    "email/imap" => Some(create_imap_module()),
+                    "email/pop3" => Some(create_pop3_module()),
+                    // File transfer modules
+                    "net/ftp" => Some(create_ftp_module()),
+                    "net/sftp" => Some(create_sftp_module()),
+                    "net/ip" => Some(create_ip_module()),
+                    // Text modules
+                    "text/diff" => Some(create_diff_module()),
+                    "text/fuzzy" => Some(create_fuzzy_module()),
+                    "text/shlex" => Some(create_shlex_module()),
+                    // Messaging modules
+                    "msg/nats" => Some(create_nats_module()),
                     // HTML modules
                     "html/templates" => Some(create_templates_module()),
                     "markdown" => Some(create_markdown_module()),
@@ -1445,6 +1648,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     // Compression modules
                     "compress/gzip" => Some(create_gzip_module()),
                     "compress/bzip2" => Some(create_bzip2_module()),
+                    "compress/tar" => Some(create_tar_module()),
                     "compress/deflate" => Some(create_deflate_module()),
                     "compress/zlib" => Some(create_zlib_module()),
                     // Process module
@@ -1452,10 +1656,16 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     "test.q" | "test" => None, // std/test.q is a file, not built-in
                     _ => None, // Not a built-in, try filesystem
                 };
-                
+
                 if let Some(module) = module_opt {
                     // QEP-002: Apply Quest overlay if lib/{module_path}.q exists
-                    let final_module = module_loader::apply_module_overlay(module, &path_str, scope)?;
+                    let mut final_module = module_loader::apply_module_overlay(module, &path_str, scope)?;
+
+                    // Each import gets its own instance; the path identifies them all
+                    // for members patched with sys.patch_member (test.mock)
+                    if let QValue::Module(m) = &mut final_module {
+                        m.source_path.get_or_insert_with(|| path_str.clone());
+                    }
 
                     // QEP-043: Handle selective imports
                     if !selective_imports.is_empty() {
@@ -1573,6 +1783,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
         Rule::function_declaration => {
             // decorator* fun name(params) statements end
             let pair_str = pair.as_str();
+            let def_line = pair.as_span().start_pos().line_col().0;
             let mut inner = pair.into_inner();
             
             // Collect decorators first
@@ -1717,6 +1928,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     captured
                 )))
             };
+            if let QValue::UserFun(f) = &mut func {
+                f.source = Some(Rc::new(pair_str.trim().to_string()));
+                coverage::tag_function(f, scope, pair_str, def_line);
+            }
             
             // Apply decorators in reverse order (bottom to top)
             for decorator in decorators.iter().rev() {
@@ -1882,6 +2097,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                             Rule::function_declaration => {
                                 // Method definition - extract and store
                                 let func_str = first.as_str();
+                                let def_line = first.as_span().start_pos().line_col().0;
                                 let mut func_inner = first.into_inner();
 
                                 // Collect decorators first (QEP-003)
@@ -1997,6 +2213,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                         captured
                                     )))
                                 };
+                                if let QValue::UserFun(f) = &mut func_value {
+                                    f.source = Some(Rc::new(func_str.trim().to_string()));
+                                    coverage::tag_function(f, scope, func_str, def_line);
+                                }
 
                                 // Apply decorators in reverse order (bottom to top) - QEP-003
                                 for decorator in decorators.iter().rev() {
@@ -2039,6 +2259,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                 for func in impl_inner {
                                     if func.as_rule() == Rule::function_declaration {
                                         let func_str = func.as_str();
+                                        let def_line = func.as_span().start_pos().line_col().0;
                                         let mut func_inner = func.into_inner();
                                         let method_name = func_inner.next().unwrap().as_str().to_string();
                                         
@@ -2090,7 +2311,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                         
                                         // Capture current scope for closure support
                                         let captured = function_call::capture_current_scope(scope);
-                                        let method_func = if varargs_name.is_some() || kwargs_name.is_some() {
+                                        let mut method_func = if varargs_name.is_some() || kwargs_name.is_some() {
                                             QUserFun::new_with_variadics(
                                                 Some(method_name.clone()),
                                                 params,
@@ -2116,6 +2337,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                                 captured
                                             )
                                         };
+                                        method_func.source = Some(Rc::new(func_str.trim().to_string()));
+                                        coverage::tag_function(&mut method_func, scope, func_str, def_line);
                                         methods.insert(method_name, method_func);
                                     }
                                 }
@@ -2169,6 +2392,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
             }
             
             // Store the type in scope
+            DECLARED_TYPES.with(|types| types.borrow_mut().insert(type_name.clone(), qtype.clone()));
             scope.declare(&type_name, QValue::Type(Box::new(qtype)))?;
             Ok(QValue::Nil(QNil))
         }
@@ -2265,14 +2489,15 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
         }
         Rule::expression_statement => {
             // Flattened: now contains lambda or elvis_expr directly
-            let pair_str = pair.as_str().to_string();
+            let pair_str = pair.as_str();
+            let start = pair.as_span().start_pos();
             let mut inner = pair.into_inner();
             let first = inner.next().unwrap();
 
             // Check if this is a lambda or elvis_expr
             if first.as_rule() == Rule::parameter_list || first.as_rule() == Rule::statement {
                 // This is a lambda: fun (params) body end
-                handle_lambda_expression(pair_str, first, inner, scope)
+                handle_lambda_expression(pair_str.to_string(), start, first, inner, scope)
             } else {
                 // This is an elvis_expr
                 eval_pair(first, scope)
@@ -2283,7 +2508,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
             let mut iter = pair.into_inner();
             let condition = eval_pair(iter.next().unwrap(), scope)?;
             
-            if condition.as_bool() {
+            if strict::condition(&condition, scope, "if")? {
                 // Execute the if block statements in a new scope
                 scope.push();
                 let mut result = QValue::Nil(QNil);
@@ -2309,7 +2534,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     Rule::elif_clause => {
                         let mut elif_inner = pair.into_inner();
                         let elif_condition = eval_pair(elif_inner.next().unwrap(), scope)?;
-                        if elif_condition.as_bool() {
+                        if strict::condition(&elif_condition, scope, "elif")? {
                             scope.push();
                             let mut result = QValue::Nil(QNil);
                             for stmt in elif_inner {
@@ -2499,7 +2724,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
             
             if range_parts.len() == 1 {
                 // Single expression - collection iteration
-                let collection = eval_pair(range_parts[0].clone(), scope)?;
+                let collection = match eval_pair(range_parts[0].clone(), scope)? {
+                    QValue::Iterator(it) => QValue::Array(QArray::new(it.collect(scope)?)),
+                    collection => collection,
+                };
                 
                 let mut result = QValue::Nil(QNil);
                 
@@ -2698,7 +2926,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 let condition_met = if fast_path {
                     eval_simple_condition(&condition_expr, scope)?
                 } else {
-                    eval_pair(condition_expr.clone(), scope)?.as_bool()
+                    let condition = eval_pair(condition_expr.clone(), scope)?;
+                    strict::condition(&condition, scope, "while")?
                 };
 
                 if !condition_met {
@@ -2745,14 +2974,15 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
         }
         Rule::expression => {
             // Flattened: now contains lambda or elvis_expr directly
-            let pair_str = pair.as_str().to_string();
+            let pair_str = pair.as_str();
+            let start = pair.as_span().start_pos();
             let mut inner = pair.into_inner();
             let first = inner.next().unwrap();
 
             // Check if this is a lambda or elvis_expr
             if first.as_rule() == Rule::parameter_list || first.as_rule() == Rule::statement {
                 // This is a lambda: fun (params) body end
-                handle_lambda_expression(pair_str, first, inner, scope)
+                handle_lambda_expression(pair_str.to_string(), start, first, inner, scope)
             } else {
                 // This is an elvis_expr
                 eval_pair(first, scope)
@@ -2852,10 +3082,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 Ok(result)
             } else {
                 // Do bitwise operations with i64
-                let mut int_result = result.as_num()? as i64;
+                let mut int_result = strict::bitwise_operand(&result, scope, "|")?;
                 for next in remaining {
-                    let right = eval_pair(next, scope)?.as_num()? as i64;
-                    int_result |= right;
+                    let right = eval_pair(next, scope)?;
+                    int_result |= strict::bitwise_operand(&right, scope, "|")?;
                 }
                 Ok(QValue::Int(QInt::new(int_result)))
             }
@@ -2871,10 +3101,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 Ok(result)
             } else {
                 // Do bitwise operations with i64
-                let mut int_result = result.as_num()? as i64;
+                let mut int_result = strict::bitwise_operand(&result, scope, "^")?;
                 for next in remaining {
-                    let right = eval_pair(next, scope)?.as_num()? as i64;
-                    int_result ^= right;
+                    let right = eval_pair(next, scope)?;
+                    int_result ^= strict::bitwise_operand(&right, scope, "^")?;
                 }
                 Ok(QValue::Int(QInt::new(int_result)))
             }
@@ -2890,10 +3120,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 Ok(result)
             } else {
                 // Do bitwise operations with i64
-                let mut int_result = result.as_num()? as i64;
+                let mut int_result = strict::bitwise_operand(&result, scope, "&")?;
                 for next in remaining {
-                    let right = eval_pair(next, scope)?.as_num()? as i64;
-                    int_result &= right;
+                    let right = eval_pair(next, scope)?;
+                    int_result &= strict::bitwise_operand(&right, scope, "&")?;
                 }
                 Ok(QValue::Int(QInt::new(int_result)))
             }
@@ -2906,8 +3136,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 let operator = op_pair.as_str();
                 let right = eval_pair(inner.next().unwrap(), scope)?;
                 
-                let left_val = result.as_num()? as i64;
-                let right_val = right.as_num()? as i64;
+                let left_val = strict::bitwise_operand(&result, scope, operator)?;
+                let right_val = strict::bitwise_operand(&right, scope, operator)?;
                 
                 let shifted = match operator {
                     "<<" => left_val.checked_shl(right_val as u32)
@@ -2930,6 +3160,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 if pair.as_rule() == Rule::comparison_op {
                     let op = pair.as_str();
                     let right = eval_pair(inner.next().unwrap(), scope)?;
+                    strict::check_comparison(&result, &right, op, scope)?;
                     
                     // Type-aware comparison with fast path for Int comparisons (QEP-042)
                     let cmp_result = match op {
@@ -3030,6 +3261,16 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     let right = eval_pair(inner.next().unwrap(), scope)?;
                     
                     // Use method calls to preserve types (Int + Int = Int, Int + Num = Num)
+                    // NDArray arithmetic broadcasts against numbers and other arrays
+                    if matches!(result, QValue::NDArray(_)) || matches!(right, QValue::NDArray(_)) {
+                        result = crate::types::QNDArray::apply_operator(op, &result, &right)?;
+                        continue;
+                    }
+                    if let (QValue::Counter(l), QValue::Counter(r)) = (&result, &right) {
+                        result = QValue::Counter(l.combine(op, r)?);
+                        continue;
+                    }
+
                     result = match op {
                         "+" => {
                             // Fast path for Int + Int (QEP-042 optimization #3)
@@ -3093,6 +3334,12 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     let right = eval_pair(inner.next().unwrap(), scope)?;
                     
                     // Use method calls to preserve types (Int * Int = Int, Int * Num = Num)
+                    // NDArray arithmetic broadcasts against numbers and other arrays
+                    if matches!(result, QValue::NDArray(_)) || matches!(right, QValue::NDArray(_)) {
+                        result = crate::types::QNDArray::apply_operator(op, &result, &right)?;
+                        continue;
+                    }
+
                     result = match op {
                         "*" => {
                             // Fast path for Int * Int (QEP-042 optimization #3)
@@ -3315,6 +3562,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                 // Check for built-in module methods first
                                 if method_name == "_doc" {
                                     result = QValue::Str(QString::new(module._doc()));
+                                } else if method_name == "cls" {
+                                    result = QValue::Str(QString::new(module.cls()));
                                 } else if method_name == "str" {
                                     result = QValue::Str(QString::new(module.str()));
                                 } else if method_name == "_rep" {
@@ -3324,7 +3573,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                 } else {
                                     // Calling a method on a module (e.g., test.it())
                                     let func = module.get_member(method_name)
-                                    .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'", module.name, method_name))?;
+                                    .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'{}", module.name, method_name, suggest::for_member(method_name, module)))?;
                                     
                                     match func {
                                         QValue::Fun(f) => {
@@ -3334,7 +3583,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                             } else {
                                                 format!("{}.{}", f.parent_type, f.name)
                                             };
-                                            result = call_builtin_function(&namespaced_name, args, scope)?;
+                                            result = call_builtin_function(&namespaced_name, call_args.clone().into_builtin_args(), scope)?;
                                         }
                                         QValue::UserFun(user_fn) => {
                                             let mut module_scope = Scope::with_shared_base(
@@ -3354,7 +3603,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                             module_scope.stdout_target = scope.stdout_target.clone();
                                             module_scope.stderr_target = scope.stderr_target.clone();
                                             
-                                            let ret = call_user_function(&user_fn, call_args.clone(), &mut module_scope, scope.current_line)?;
+                                            let caller = debugger::enter_module_call(scope);
+                                            let ret = call_user_function(&user_fn, call_args.clone(), &mut module_scope, scope.current_line);
+                                            drop(caller);
+                                            let ret = ret?;
                                             
                                             // No need to sync back module members - they're shared via Rc<RefCell<>>
                                             // Changes to module variables go directly to module.members
@@ -3390,21 +3642,23 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                 } else if let QValue::Array(arr) = &result {
                                     // Special handling for array higher-order functions
                                     match method_name {
-                                        "map" | "filter" | "each" | "reduce" | "any" | "all" | "find" | "find_index" => {
+                                        "map" | "filter" | "each" | "reduce" | "any" | "all" | "find" | "find_index" | "sort_by" | "group_by" | "unique" => {
                                             result = call_array_higher_order_method(arr, method_name, args, scope, call_user_function_compat)?;
                                         }
                                         _ => {
-                                            result = arr.call_method(method_name, args)?;
+                                            // Named arguments arrive as a trailing options Dict
+                                            result = arr.call_method(method_name, call_args.clone().into_builtin_args())?;
                                         }
                                     }
                                 } else if let QValue::Dict(dict) = &result {
                                     // Special handling for dict higher-order functions
                                     match method_name {
-                                        "each" => {
+                                        "each" | "map_values" | "filter" => {
                                             result = call_dict_higher_order_method(dict, method_name, args, scope, call_user_function_compat)?;
                                         }
                                         _ => {
-                                            result = dict.call_method(method_name, args)?;
+                                            // Named arguments arrive as a trailing options Dict
+                                            result = dict.call_method(method_name, call_args.clone().into_builtin_args())?;
                                         }
                                     }
                                 } else if let QValue::Type(qtype) = &result {
@@ -3417,7 +3671,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                             result = types::decimal::call_decimal_static_method("new", args)?;
                                         } else if qtype.name == "BigInt" {
                                             result = types::bigint::call_bigint_static_method("new", args)?;
-                                        } else if matches!(qtype.name.as_str(), "Err" | "SyntaxErr" |  "IndexErr" | "TypeErr" | "ValueErr" | "ArgErr" | "AttrErr" | "NameErr" | "RuntimeErr" | "IOErr" | "ImportErr" | "KeyErr" | "ConfigurationErr") {
+                                        } else if matches!(qtype.name.as_str(), "Err" | "SyntaxErr" |  "IndexErr" | "TypeErr" | "ValueErr" | "ArgErr" | "AttrErr" | "NameErr" | "RuntimeErr" | "IOErr" | "ImportErr" | "KeyErr" | "ConfigurationErr" | "WarningErr") {
                                             // QEP-037: Exception types
                                             // QEP-057: Pass scope to capture context
                                             result = exception_types::call_exception_static_method(&qtype.name, "new", args, scope)?;
@@ -3515,6 +3769,12 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                         } else {
                                             return Err(".does() argument must be a trait".to_string().into());
                                         }
+                                    } else if method_name == "cls" && args.is_empty() {
+                                        let type_name = qstruct.borrow().type_name.clone();
+                                        result = QValue::Str(QString::new(type_name));
+                                    } else if method_name == "_id" && args.is_empty() {
+                                        let id = qstruct.borrow().id;
+                                        result = QValue::Int(QInt::new(id as i64));
                                     } else {
                                         // Handle user-defined instance methods
                                         // First, look up the type to find the method
@@ -3574,7 +3834,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                                         }
                                                     }
                                                 } else {
-                                                    return attr_err!("Struct {} has no method or callable field '{}'", type_name, method_name);
+                                                    return attr_err!("Struct {} has no method or callable field '{}'{}", type_name, method_name, suggest::for_method(method_name, &qtype));
                                                 }
                                             }
                                         } else {
@@ -3598,6 +3858,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                         let expected_type = type_name.to_lowercase();
                                         result = QValue::Bool(QBool::new(actual_type == expected_type));
                                     } else {
+                                        // Rust-backed objects receive named arguments as a trailing options Dict
+                                        let args = call_args.clone().into_builtin_args();
                                         result = match &result {
                                             QValue::Int(i) => i.call_method(method_name, args)?,
                                             QValue::Float(f) => f.call_method(method_name, args)?,
@@ -3611,6 +3873,12 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                             QValue::UserFun(uf) => uf.call_method(method_name, args)?,
                                             QValue::Dict(d) => d.call_method(method_name, args)?,
                                             QValue::Set(s) => s.call_method(method_name, args)?,
+                                            QValue::Iterator(it) => it.call_method(method_name, args, scope)?,
+                                            QValue::BytesBuffer(b) => b.call_method(method_name, args)?,
+                                            QValue::Deque(d) => d.call_method(method_name, args)?,
+                                            QValue::Heap(h) => h.call_method(method_name, args, scope)?,
+                                            QValue::PriorityQueue(pq) => pq.call_method(method_name, args, scope)?,
+                                            QValue::Counter(c) => c.call_method(method_name, args)?,
                                             QValue::Exception(e) => e.call_method(method_name, args)?,
                                             QValue::Uuid(u) => u.call_method(method_name, args)?,
                                             QValue::Timestamp(ts) => ts.call_method(method_name, args)?,
@@ -3619,22 +3887,58 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                             QValue::Time(t) => t.call_method(method_name, args)?,
                                             QValue::Span(s) => s.call_method(method_name, args)?,
                                             QValue::DateRange(dr) => dr.call_method(method_name, args)?,
+                                            QValue::Stopwatch(sw) => sw.call_method(method_name, args)?,
                                             QValue::SerialPort(sp) => sp.call_method(method_name, args)?,
+                                            QValue::ModbusClient(c) => c.call_method(method_name, args)?,
+                                            QValue::CanBus(b) => b.call_method(method_name, args)?,
                                             QValue::SqliteConnection(conn) => conn.call_method(method_name, args)?,
                                             QValue::SqliteCursor(cursor) => cursor.call_method(method_name, args)?,
                                             QValue::PostgresConnection(conn) => conn.call_method(method_name, args)?,
                                             QValue::PostgresCursor(cursor) => cursor.call_method(method_name, args)?,
                                             QValue::MysqlConnection(conn) => conn.call_method(method_name, args)?,
                                             QValue::MysqlCursor(cursor) => cursor.call_method(method_name, args)?,
-                                            QValue::HtmlTemplate(tmpl) => tmpl.call_method(method_name, args)?,
+                                            QValue::ConnectionPool(pool) => pool.call_method(method_name, args)?,
+                                            QValue::PoolLease(lease) => lease.call_method(method_name, args)?,
+                                            QValue::Transaction(tx) => tx.call_method(method_name, args)?,
+                                            QValue::RowIterator(it) => it.call_method(method_name, args)?,
+                                            QValue::ImapClient(c) => c.call_method(method_name, args)?,
+                                            QValue::Pop3Client(c) => c.call_method(method_name, args)?,
+                                            QValue::FtpClient(c) => c.call_method(method_name, args)?,
+                                            QValue::SftpClient(c) => c.call_method(method_name, args)?,
+                                            QValue::NatsClient(c) => c.call_method(method_name, args)?,
+                                            QValue::NatsSubscription(s) => s.call_method(method_name, args)?,
+                                            QValue::HtmlTemplate(tmpl) => tmpl.call_method(method_name, args, scope)?,
+                                            QValue::SafeString(s) => s.call_method(method_name, args)?,
+                                            QValue::RegexPattern(p) => p.call_method(method_name, args, scope)?,
+                                            QValue::RegexMatch(m) => m.call_method(method_name, args)?,
                                             QValue::HttpClient(client) => client.call_method(method_name, args)?,
                                             QValue::HttpRequest(req) => req.call_method(method_name, args)?,
                                             QValue::HttpResponse(resp) => resp.call_method(method_name, args)?,
                                             QValue::ProcessResult(pr) => pr.call_method(method_name, args)?,
-                                            QValue::Process(p) => p.call_method(method_name, args)?,
+                                            QValue::PipelineResult(pr) => pr.call_method(method_name, args)?,
+                                            QValue::Process(p) => p.call_method(method_name, args, scope)?,
                                             QValue::WritableStream(ws) => ws.call_method(method_name, args)?,
                                             QValue::ReadableStream(rs) => rs.call_method(method_name, args)?,
+                                            QValue::XmlElement(el) => el.call_method(method_name, args)?,
+                                            QValue::XmlReader(reader) => reader.call_method(method_name, args)?,
+                                            QValue::CsvReader(reader) => reader.call_method(method_name, args)?,
+                                            QValue::CsvWriter(writer) => writer.call_method(method_name, args)?,
+                                            QValue::JsonStream(stream) => stream.call_method(method_name, args)?,
+                                            QValue::JsonSchema(schema) => schema.call_method(method_name, args)?,
+                                            QValue::ProtoSchema(v) => v.call_method(method_name, args)?,
+                                            QValue::TarReader(v) => v.call_method(method_name, args)?,
+                                            QValue::TarWriter(v) => v.call_method(method_name, args)?,
+                                            QValue::Compressor(v) => v.call_method(method_name, args)?,
+                                            QValue::Decompressor(v) => v.call_method(method_name, args)?,
+                                            QValue::CompressedReader(v) => v.call_method(method_name, args)?,
+                                            QValue::CompressedWriter(v) => v.call_method(method_name, args)?,
+                                            QValue::CryptoKey(v) => v.call_method(method_name, args)?,
                                             QValue::Rng(rng) => modules::call_rng_method(rng, method_name, args)?,
+                                            QValue::Cache(c) => c.call_method(method_name, args, scope)?,
+                                            QValue::Url(u) => u.call_method(method_name, args)?,
+                                            QValue::IpAddr(a) => a.call_method(method_name, args)?,
+                                            QValue::Cidr(n) => n.call_method(method_name, args)?,
+                                            QValue::Image(img) => img.call_method(method_name, args)?,
                                             QValue::StringIO(sio) => {
                                                 let mut stringio = sio.borrow_mut();
                                                 stringio.call_method(method_name, args)?
@@ -3703,7 +4007,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                             if let QValue::Module(module) = &result {
                                 // Access module member - functions already have module_scope set
                                 result = module.get_member(method_name)
-                                .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'", module.name, method_name))?;
+                                .ok_or_else(|| format!("AttrErr: Module {} has no member '{}'{}", module.name, method_name, suggest::for_member(method_name, module)))?;
                                 i += 1;
                             } else if let QValue::Process(proc) = &result {
                                 // Access process stdin/stdout/stderr streams
@@ -3756,7 +4060,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                     result = field_value;
                                     i += 1;
                                 } else {
-                                    return attr_err!("Struct {} has no field '{}'", type_name, method_name);
+                                    let hint = find_type_definition(&type_name, scope)
+                                        .map(|qtype| suggest::for_field(method_name, &qtype))
+                                        .unwrap_or_default();
+                                    return attr_err!("Struct {} has no field '{}'{}", type_name, method_name, hint);
                                 }
                             } else {
                                 // Return a QFun object representing the method
@@ -3770,17 +4077,22 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                         }
                     }
                     Rule::index_access => {
-                        // Array, dict, string, or bytes index access
-                        let mut index_inner = current.clone().into_inner();
-                        let index_expr = index_inner.next().unwrap();
-                        let index_value = eval_pair(index_expr, scope)?;
-
-                        // Check if there are multiple indices (for 2D arrays)
-                        if index_inner.next().is_some() {
-                            return Err("Multi-dimensional array access not yet implemented".to_string().into());
+                        // Slices and multi-dimensional indices: a[1:3], nd[0, :]
+                        if is_extended_index(current) {
+                            let entries = eval_index_entries(current.clone(), scope)?;
+                            result = index_with_entries(&result, &entries)?;
+                            i += 1;
+                            continue;
                         }
 
+                        // Array, dict, string, bytes or NDArray index access
+                        let index_expr = current.clone().into_inner().next().unwrap();
+                        let index_value = eval_pair(index_expr, scope)?;
+
                         match &result {
+                            QValue::NDArray(nda) => {
+                                result = nda.index(&[IndexEntry::At(index_value)])?;
+                            }
                             QValue::Array(arr) => {
                                 let index = index_value.as_num()? as i64;
                                 let len = arr.len() as i64;
@@ -3798,9 +4110,12 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                             }
                             QValue::Dict(dict) => {
                                 let key = index_value.as_str();
-                                result = dict.get(&key)
+                                result = dict.get_or_default(&key, scope)?
                                 .unwrap_or(QValue::Nil(QNil));
                             }
+                            QValue::Counter(counter) => {
+                                result = counter.count(&index_value)?;
+                            }
                             QValue::Str(s) => {
                                 // Validate index type (Int or BigInt that fits in i64)
                                 let index = match &index_value {
@@ -3906,14 +4221,16 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
             
             // If we started with a variable and the result is a modified struct, update the variable
             // Bug #008 fix: Never update 'self' this way - it should only be modified explicitly
-            if let (Some(var_name), Some(_orig_id)) = (original_identifier, original_result_id) {
+            if let (Some(var_name), Some(orig_id)) = (original_identifier, original_result_id) {
                 if var_name != "self" {  // Don't auto-update self!
-                if let QValue::Struct(_s) = &result {
+                if let QValue::Struct(s) = &result {
                     // Check if struct was modified (different ID than original means it's been cloned/modified)
                     // Actually, the ID should be the same if it's the same struct, but fields might have changed
                     // We need a better heuristic: if any method was called, assume it might have mutated
-                    // For now, always update if we called methods on a struct
-                    if !pairs.is_empty() {
+                    // For now, always update if we called methods on a struct - unless the chain
+                    // returned some other struct (e.g. one the method created), which must not
+                    // replace the receiver
+                    if !pairs.is_empty() && s.borrow().id == orig_id {
                         scope.set(&var_name, result.clone());
                     }
                 }
@@ -3977,24 +4294,32 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 }
                 
                 // Check if this is a module (module.method() calls need special handling)
-                if let Some(QValue::Module(_)) = scope.get(func_name) {
+                if let Some(QValue::Module(module)) = scope.get(func_name) {
                     // This is module.new() - treat as module function call
                     let function_name = format!("{}.new", func_name);
-                    let args = if let Some(args_pair) = inner.next() {
+                    let call_args = if let Some(args_pair) = inner.next() {
                         if args_pair.as_rule() == Rule::argument_list {
-                            parse_call_arguments(args_pair, scope)?.positional
+                            parse_call_arguments(args_pair, scope)?
                         } else {
-                            Vec::new()
+                            function_call::CallArguments::positional_only(Vec::new())
                         }
                     } else {
-                        Vec::new()
+                        function_call::CallArguments::positional_only(Vec::new())
                     };
+                    let args = call_args.positional.clone();
                     // Dispatch to appropriate module function handler
                     return match func_name {
                         "ndarray" => modules::call_ndarray_function(&function_name, args).map_err(|e| e.into()),
                         "uuid" => modules::call_uuid_function(&function_name, args, scope).map_err(|e| e.into()),
                         "http" => modules::call_http_client_function(&function_name, args, scope).map_err(|e| e.into()),
-                        _ => attr_err!("Unknown module function: {}", function_name),
+                        // Nested type objects imported by name, e.g. `use "std/io" {StringIO}`
+                        _ => match module.get_member("new") {
+                            Some(QValue::Fun(f)) if !f.parent_type.is_empty() => {
+                                let namespaced_name = format!("{}.{}", f.parent_type, f.name);
+                                call_builtin_function(&namespaced_name, call_args.into_builtin_args(), scope)
+                            }
+                            _ => attr_err!("Unknown module function: {}", function_name),
+                        },
                     };
                 } else if let Some(QValue::Type(qtype)) = scope.get(func_name) {
                     // This is TypeName.new(...) constructor
@@ -4032,7 +4357,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                             Vec::new()
                         };
                         return types::bigint::call_bigint_static_method("new", args).map_err(|e| e.into());
-                    } else if matches!(qtype.name.as_str(), "Err" | "IndexErr" | "TypeErr" | "ValueErr" | "ArgErr" | "AttrErr" | "NameErr" | "RuntimeErr" | "IOErr" | "ImportErr" | "KeyErr" | "ConfigurationErr") {
+                    } else if matches!(qtype.name.as_str(), "Err" | "IndexErr" | "TypeErr" | "ValueErr" | "ArgErr" | "AttrErr" | "NameErr" | "RuntimeErr" | "IOErr" | "ImportErr" | "KeyErr" | "ConfigurationErr" | "WarningErr") {
                         // QEP-037: Exception types
                         let args = if let Some(args_pair) = inner.next() {
                             if args_pair.as_rule() == Rule::argument_list {
@@ -4097,10 +4422,10 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                             } else {
                                 format!("{}.{}", qfun.parent_type, qfun.name)
                             };
-                            return call_builtin_function(&namespaced_name, call_args.positional, scope);
+                            return call_builtin_function(&namespaced_name, call_args.into_builtin_args(), scope);
                         }
                         QValue::UserFun(user_fun) => {
-                            return call_user_function(&user_fun, call_args, scope, Some(call_site_line)).map_err(|e| e.into());
+                            return call_user_function(&user_fun, call_args, scope, Some(call_site_line));
                         }
                         QValue::Type(qtype) => {
                             // Trying to call a type directly - provide helpful error
@@ -4134,9 +4459,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     }
                 }
                 
-                // For builtin functions, extract positional args
-                // (builtin functions don't yet support named arguments - future enhancement)
-                return call_builtin_function(func_name, call_args.positional, scope);
+                // Builtin functions receive named arguments as a trailing options Dict
+                return call_builtin_function(func_name, call_args.into_builtin_args(), scope);
             }
             
             // Just a bare identifier (variable reference)
@@ -4146,11 +4470,11 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 None => {
                     // Check if it's a builtin function - return a Fun object for it
                     match func_name {
-                        "puts" | "print" | "is_array" | "is_dict" | "is_str" | "is_int" | "is_float" | "chr" | "ord" | "exit" => {
+                        name if BUILTIN_FUNCTIONS.contains(&name) => {
                             // Return a Fun object representing the builtin function
                             Ok(QValue::Fun(QFun::new(func_name.to_string(), String::new())))
                         }
-                        _ => name_err!("Undefined variable: {}", func_name),
+                        _ => name_err!("Undefined variable: {}{}", func_name, suggest::for_variable(func_name, scope)),
                     }
                 }
             };
@@ -4262,7 +4586,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
     Rule::dict_literal => {
         // {key: value, key: value, ...}
         let inner = pair.into_inner();
-        let mut map = std::collections::HashMap::new();
+        let mut map = IndexMap::new();
         
         for dict_pair in inner {
             if dict_pair.as_rule() == Rule::dict_pair {
@@ -4405,7 +4729,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                                 // Look up variable in scope
                                 let value = match scope.get(var_name) {
                                     Some(v) => v,
-                                    None => return name_err!("Undefined variable: {}", var_name),
+                                    None => return name_err!("Undefined variable: {}{}", var_name, suggest::for_variable(var_name, scope)),
                                 };
                                 
                                 // Format the value
@@ -4487,7 +4811,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                     // QEP-057: Enrich with context from scope
                     let enriched = e.enrich_with_context(scope);
                     scope.current_exception = Some(enriched.clone());
-                    return Err(format!("{}: {}", enriched.exception_type, enriched.message).into());
+                    return Err(EvalError::raised(enriched));
                 }
                 QValue::Struct(ref s) => {
                     // Custom exception type (user-defined struct)
@@ -4531,7 +4855,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
 
                     // Store in scope and return error
                     scope.current_exception = Some(exc.clone());
-                    return Err(format!("{}: {}", type_name, exc.message).into());
+                    return Err(EvalError::raised(exc));
                 }
                 _ => {
                     return runtime_err!("Cannot raise type '{}' - must implement Error trait", value.q_type());
@@ -4540,7 +4864,7 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
         } else {
             // Bare raise - re-raise current exception
             if let Some(exc) = &scope.current_exception {
-                return Err(format!("{}: {}", exc.exception_type, exc.message).into());
+                return Err(EvalError::raised(exc.clone()));
             } else {
                 return runtime_err!("No active exception to re-raise");
             }
@@ -4636,7 +4960,19 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
         let mut suppress_exception = false;
         
         for item in items.iter().rev() {
-            let exit_result = call_method_on_value(&item.ctx_manager, "_exit", vec![], scope);
+            // Database transactions need to know whether to commit or roll back.
+            // Return/break/continue are clean exits; so is an exception that an
+            // inner _exit() already suppressed.
+            let failed = exception.as_ref().is_some_and(EvalError::is_runtime) && !suppress_exception;
+            let exit_args = match &item.ctx_manager {
+                QValue::Transaction(_) if failed => vec![QValue::Bool(QBool::new(true))],
+                // User types opt in by declaring a parameter: _exit(failed)
+                QValue::Struct(s) if exit_takes_failure_flag(&s.borrow().type_name, scope) => {
+                    vec![QValue::Bool(QBool::new(failed))]
+                }
+                _ => vec![],
+            };
+            let exit_result = call_method_on_value(&item.ctx_manager, "_exit", exit_args, scope);
             
             // If _exit() raises, that takes precedence
             if let Err(exit_err) = exit_result {
@@ -4740,7 +5076,11 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
 
                 if !is_builtin {
                     // Custom type - must implement Error trait
-                    if let Some(qtype) = find_type_definition(type_name, scope) {
+                    let type_found = match resolve_qualified(type_name, scope) {
+                        Some(QValue::Type(qtype)) => Some((*qtype).clone()),
+                        _ => find_type_definition(type_name, scope),
+                    };
+                    if let Some(qtype) = type_found {
                         if !qtype.implemented_traits.contains(&"Error".to_string()) {
                             return type_err!(
                                 "Cannot catch type '{}' that doesn't implement Error trait",
@@ -4775,9 +5115,9 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 let error_msg_clone = error_msg.clone();
                 // QEP-056: Convert EvalError to String for exception handling
                 let error_str: String = error_msg_clone.into();
-                // QEP-037: Use current_exception from scope if available
-                // (preserves original_value for user-defined exceptions)
-                let exception = if let Some(exc) = scope.current_exception.clone() {
+                // QEP-037: Use the raised exception, or current_exception from scope
+                // if available (preserves original_value for user-defined exceptions)
+                let exception = if let Some(exc) = error_msg.as_raised().cloned().or_else(|| scope.current_exception.clone()) {
                     // Exception was set by raise statement - use it directly
                     let mut exc = exc;
                     if exc.stack.is_empty() {
@@ -4812,7 +5152,8 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
                 for (var_name, exception_type_filter, body) in catch_clauses {
                     // Check if this catch clause matches the exception type (QEP-037)
                     let matches = if let Some(ref expected_type_str) = exception_type_filter {
-                        let expected_type = ExceptionType::from_str(expected_type_str);
+                        // A module-qualified filter (mod.Type) matches on the type's own name
+                        let expected_type = ExceptionType::from_str(expected_type_str.rsplit('.').next().unwrap_or(expected_type_str));
                         // Use subtype checking (enables catching Err to match all exceptions)
                         exception.exception_type.is_subtype_of(&expected_type)
                     } else {
@@ -4883,6 +5224,27 @@ pub fn eval_pair_impl(pair: pest::iterators::Pair<Rule>, scope: &mut Scope) -> E
 }
 }
 
+/// Whether a user type's `_exit` declares a parameter, in which case `with`
+/// passes it a flag saying whether the block raised
+fn exit_takes_failure_flag(type_name: &str, scope: &Scope) -> bool {
+    find_type_definition(type_name, scope)
+        .and_then(|qtype| qtype.get_method("_exit").map(|m| !m.params.is_empty() || m.varargs.is_some()))
+        .unwrap_or(false)
+}
+
+/// Look up a possibly module-qualified name (`mod.Type`), walking module members
+pub(crate) fn resolve_qualified(name: &str, scope: &Scope) -> Option<QValue> {
+    let mut path = name.split('.');
+    let mut value = scope.get(path.next()?);
+    for member in path {
+        value = match value {
+            Some(QValue::Module(module)) => module.get_member(member),
+            _ => None,
+        };
+    }
+    value
+}
+
 /// Helper function to find a type definition by name
 /// Checks local scope first, then searches through all modules
 fn find_type_definition(type_name: &str, scope: &Scope) -> Option<QType> {
@@ -4900,8 +5262,14 @@ fn find_type_definition(type_name: &str, scope: &Scope) -> Option<QType> {
             }
         }
     }
-    
-    None
+
+    // Finally, any type declared so far (e.g. a script's type handled inside a module)
+    DECLARED_TYPES.with(|types| types.borrow().get(type_name).cloned())
+}
+
+thread_local! {
+    // Every type declared so far, by name (last-resort lookup for find_type_definition)
+    static DECLARED_TYPES: RefCell<HashMap<String, QType>> = RefCell::new(HashMap::new());
 }
 
 /// Recursively deep clone a QValue, creating fresh Rc wrappers for mutable types
@@ -4917,7 +5285,7 @@ fn deep_clone_value(value: &QValue) -> QValue {
         }
         QValue::Dict(dict) => {
             // Recursively deep clone each value in the dict
-            let entries: HashMap<String, QValue> = dict.map.borrow()
+            let entries: IndexMap<String, QValue> = dict.map.borrow()
                 .iter()
                 .map(|(k, v)| (k.clone(), deep_clone_value(v)))
                 .collect();
@@ -4954,7 +5322,7 @@ fn get_field_value(field_def: &FieldDef, provided_value: Option<QValue>, _scope:
 
 // Format a value according to a Rust-style format specification
 /// Construct a struct instance from a type
-fn construct_struct(qtype: &QType, args: Vec<QValue>, named_args: Option<HashMap<String, QValue>>, scope: &mut Scope) -> Result<QValue, EvalError> {
+fn construct_struct(qtype: &QType, args: Vec<QValue>, named_args: Option<IndexMap<String, QValue>>, scope: &mut Scope) -> Result<QValue, EvalError> {
     let mut fields = HashMap::new();
     
     // Handle named arguments if provided
@@ -5004,7 +5372,7 @@ fn construct_struct(qtype: &QType, args: Vec<QValue>, named_args: Option<HashMap
             }
             
             // Check if we have exactly 1 required field, or 1+ fields where only first is required
-            let required_count = qtype.fields.iter().filter(|f| !f.optional).count();
+            let required_count = qtype.fields.iter().filter(|f| !f.optional && f.default_value.is_none()).count();
             if required_count > 1 {
                 return arg_err!("Type {} requires {} arguments, got 1", qtype.name, required_count);
             }
@@ -5025,7 +5393,7 @@ fn construct_struct(qtype: &QType, args: Vec<QValue>, named_args: Option<HashMap
         // Multiple positional arguments
         if args.len() != qtype.fields.len() {
             // Check if extra args can be skipped (optional fields)
-            let required_count = qtype.fields.iter().filter(|f| !f.optional).count();
+            let required_count = qtype.fields.iter().filter(|f| !f.optional && f.default_value.is_none()).count();
             if args.len() < required_count {
                 return arg_err!("Type {} requires at least {} arguments, got {}", qtype.name, required_count, args.len());
             }
@@ -5053,6 +5421,11 @@ fn construct_struct(qtype: &QType, args: Vec<QValue>, named_args: Option<HashMap
 }
 
 
+/// Functions callable by bare name without being declared in any scope
+pub const BUILTIN_FUNCTIONS: &[&str] = &[
+    "puts", "print", "is_array", "is_dict", "is_str", "is_int", "is_float", "chr", "ord", "exit",
+];
+
 fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
         // Delegate sys.* functions to sys module
@@ -5067,6 +5440,10 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("crypto.") => {
             Ok(modules::call_crypto_function(name, args, scope)?)
         }
+        // Delegate jwt.* functions to crypto/jwt module
+        name if name.starts_with("jwt.") => {
+            Ok(modules::call_jwt_function(name, args, scope)?)
+        }
         // Delegate math.* functions to math module
         name if name.starts_with("math.") => {
             Ok(modules::call_math_function(name, args, scope)?)
@@ -5075,6 +5452,34 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("term.") => {
             Ok(modules::call_term_function(name, args, scope)?)
         }
+        // Delegate inspect.* functions to inspect module
+        name if name.starts_with("inspect.") => {
+            Ok(modules::call_inspect_function(name, args, scope)?)
+        }
+        // Delegate unicode.* functions to unicode module
+        name if name.starts_with("unicode.") => {
+            Ok(modules::call_unicode_function(name, args, scope)?)
+        }
+        // Delegate locale.* functions to locale module
+        name if name.starts_with("locale.") => {
+            Ok(modules::call_locale_function(name, args, scope)?)
+        }
+        // Delegate collate.* functions to collate module
+        name if name.starts_with("collate.") => {
+            Ok(modules::call_collate_function(name, args, scope)?)
+        }
+        // Delegate cache.* functions to cache module
+        name if name.starts_with("cache.") => {
+            Ok(modules::call_cache_function(name, args, scope)?)
+        }
+        // Delegate jobs.* functions to jobs module
+        name if name.starts_with("jobs.") => {
+            Ok(modules::call_jobs_function(name, args, scope)?)
+        }
+        // Delegate image.* functions to image module
+        name if name.starts_with("image.") => {
+            Ok(modules::call_image_function(name, args, scope)?)
+        }
         // Delegate os.* functions to os module
         name if name.starts_with("os.") => {
             Ok(modules::call_os_function(name, args, scope)?)
@@ -5099,6 +5504,13 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("serial.") => {
             Ok(modules::call_serial_function(name, args, scope)?)
         }
+        // Delegate modbus.* and can.* functions to the serial protocol modules
+        name if name.starts_with("modbus.") => {
+            Ok(modules::call_modbus_function(name, args, scope)?)
+        }
+        name if name.starts_with("can.") => {
+            Ok(modules::call_can_function(name, args, scope)?)
+        }
         // Delegate uuid.* functions to uuid module
         name if name.starts_with("uuid.") => {
             Ok(modules::call_uuid_function(name, args, scope)?)
@@ -5107,10 +5519,18 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("ndarray.") => {
             Ok(modules::call_ndarray_function(name, args)?)
         }
+        // Delegate collections.* functions to collections module
+        name if name.starts_with("collections.") => {
+            Ok(modules::call_collections_function(name, args, scope)?)
+        }
         // Delegate settings.* functions to settings module
         name if name.starts_with("settings.") => {
             Ok(modules::call_settings_function(name, args)?)
         }
+        // Delegate config.* functions to config module
+        name if name.starts_with("config.") => {
+            Ok(modules::call_config_function(name, args)?)
+        }
         // Delegate toml.* functions to toml module
         name if name.starts_with("toml.") => {
             Ok(modules::call_toml_function(name, args)?)
@@ -5135,6 +5555,26 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("csv.") => {
             Ok(modules::call_csv_function(name, args, scope)?)
         }
+        // Delegate yaml.* functions to encoding/yaml module
+        name if name.starts_with("yaml.") => {
+            Ok(modules::call_yaml_function(name, args, scope)?)
+        }
+        // Delegate xml.* functions to encoding/xml module
+        name if name.starts_with("xml.") => {
+            Ok(modules::call_xml_function(name, args, scope)?)
+        }
+        // Delegate proto.* functions to encoding/proto module
+        name if name.starts_with("proto.") => {
+            Ok(modules::call_proto_function(name, args, scope)?)
+        }
+        // Delegate jsonschema.* functions to encoding/jsonschema module
+        name if name.starts_with("jsonschema.") => {
+            Ok(modules::call_jsonschema_function(name, args, scope)?)
+        }
+        // Delegate qr.* functions to encoding/qr module
+        name if name.starts_with("qr.") => {
+            Ok(modules::call_qr_function(name, args, scope)?)
+        }
         // Delegate rand.* functions to rand module
         name if name.starts_with("rand.") => {
             Ok(modules::call_rand_function(name, args, scope)?)
@@ -5167,6 +5607,42 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("mysql.") => {
             Ok(modules::call_mysql_function(name, args, scope)?)
         }
+        // Delegate imap.* functions to email/imap module
+        name if name.starts_with("imap.") => {
+            Ok(modules::call_imap_function(name, args, scope)?)
+        }
+        // Delegate pop3.* functions to email/pop3 module
+        name if name.starts_with("pop3.") => {
+            Ok(modules::call_pop3_function(name, args, scope)?)
+        }
+        // Delegate ftp.* functions to net/ftp module
+        name if name.starts_with("ftp.") => {
+            Ok(modules::call_ftp_function(name, args, scope)?)
+        }
+        // Delegate sftp.* functions to net/sftp module
+        name if name.starts_with("sftp.") => {
+            Ok(modules::call_sftp_function(name, args, scope)?)
+        }
+        // Delegate ip.* functions to net/ip module
+        name if name.starts_with("ip.") => {
+            Ok(modules::call_ip_function(name, args, scope)?)
+        }
+        // Delegate diff.* functions to text/diff module
+        name if name.starts_with("diff.") => {
+            Ok(modules::call_diff_function(name, args, scope)?)
+        }
+        // Delegate fuzzy.* functions to text/fuzzy module
+        name if name.starts_with("fuzzy.") => {
+            Ok(modules::call_fuzzy_function(name, args, scope)?)
+        }
+        // Delegate shlex.* functions to text/shlex module
+        name if name.starts_with("shlex.") => {
+            Ok(modules::call_shlex_function(name, args, scope)?)
+        }
+        // Delegate nats.* functions to msg/nats module
+        name if name.starts_with("nats.") => {
+            Ok(modules::call_nats_function(name, args, scope)?)
+        }
         // Delegate b64.* functions to encoding/b64 module
         name if name.starts_with("b64.") => {
             Ok(modules::call_b64_function(name, args, scope)?)
@@ -5179,6 +5655,10 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
         name if name.starts_with("bzip2.") => {
             Ok(modules::call_bzip2_function(name, args, scope)?)
         }
+        // Delegate tar.* functions to compress/tar module
+        name if name.starts_with("tar.") => {
+            Ok(modules::call_tar_function(name, args, scope)?)
+        }
         // Delegate deflate.* functions to compress/deflate module
         name if name.starts_with("deflate.") => {
             Ok(modules::call_deflate_function(name, args, scope)?)
@@ -5245,7 +5725,7 @@ fn call_builtin_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
             
             Ok(QValue::Int(QInt::new(ch as i64)))
         }
-        _ => attr_err!("Undefined function: {}", func_name),
+        _ => attr_err!("Undefined function: {}{}", func_name, suggest::for_variable(func_name, scope)),
     }
 }
 
@@ -5254,7 +5734,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(feature = "dhat-heap")]
     let _profiler = dhat::Profiler::new_heap();
 
-    let args: Vec<String> = env::args().collect();
+    // QUEST_CLONE_DEBUG reports object counts for the whole run
+    if alloc_counter::is_debug_enabled() {
+        alloc_counter::start_tracking();
+    }
+
+    let mut args: Vec<String> = env::args().collect();
+
+    // Warning filters: -W ACTION[:CATEGORY], repeatable, before anything else
+    while args.len() > 1 && args[1].starts_with("-W") {
+        let spec = if args[1] == "-W" {
+            if args.len() < 3 {
+                eprintln!("-W needs a filter, e.g. -W error or -W ignore:DeprecationWarning");
+                std::process::exit(1);
+            }
+            args.remove(2)
+        } else {
+            args[1][2..].to_string()
+        };
+        args.remove(1);
+        if let Err(e) = warnings::add_filter(&spec) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
 
     // Extract standard library on first run
     match embedded_lib::extract_stdlib() {
@@ -5294,37 +5797,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             return Ok(());
         }
         
+        // REPL without syntax highlighting
+        if first_arg == "--no-color" {
+            run_repl(false)?;
+            alloc_counter::print_stats();
+            return Ok(());
+        }
+
+        // One-liners: quest -e CODE, quest -p CODE, quest -r MODULE ...
+        if matches!(first_arg.as_str(), "-e" | "-p" | "-r") {
+            return handle_eval_command(&args[1..]);
+        }
+
         // Check for search path flag
         if first_arg == "--search-path" {
-            let mut search_paths = vec![];
-            
-            // 1. Development lib/ directory
-            if std::path::Path::new("lib/").exists() {
-                search_paths.push("lib/".to_string());
-            }
-            
-            // 2. QUEST_INCLUDE environment variable
-            let quest_include = env::var("QUEST_INCLUDE").unwrap_or_else(|_| String::new());
-            if !quest_include.is_empty() {
-                let separator = if cfg!(windows) { ';' } else { ':' };
-                for path_component in quest_include.split(separator) {
-                    if !path_component.is_empty() {
-                        search_paths.push(path_component.to_string());
-                    }
-                }
-            }
-            
-            // 3. Installed stdlib
-            let stdlib_dir = embedded_lib::get_stdlib_dir();
-            if stdlib_dir.exists() {
-                if let Some(stdlib_str) = stdlib_dir.to_str() {
-                    search_paths.push(stdlib_str.to_string());
-                }
-            }
-            
+            let search_dirs = module_loader::search_dirs(None, &[]);
+
             println!("Quest module search paths:");
-            for (i, path) in search_paths.iter().enumerate() {
-                println!("  {}: {}", i + 1, path);
+            for (i, (path, source)) in search_dirs.iter().enumerate() {
+                println!("  {}: {} ({})", i + 1, path, source);
             }
             return Ok(());
         }
@@ -5333,12 +5824,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         
         // Check if first argument is a COMMAND (case insensitive)
         if first_arg_lower == "run" {
-            // Handle 'run' command: quest run <script_name> [args...]
-            if args.len() < 3 {
-                eprintln!("Usage: quest run <script_name> [args...]");
+            // Handle 'run' command: quest run [--watch | --trace...] <script_name> [args...]
+            if args.len() < 3 || (args[2] == "--watch" && args.len() < 4) {
+                eprintln!("Usage: quest run [--watch | --trace...] <script_name> [args...]");
                 std::process::exit(1);
             }
 
+            if args[2] == "--watch" {
+                return handle_watch_command(&args[3], &args[4..]);
+            }
+
+            if args[2].starts_with("--trace") {
+                return handle_trace_command(&args[2..]);
+            }
+
             let script_name = &args[2];
             let remaining_args = if args.len() > 3 { &args[3..] } else { &[] };
 
@@ -5350,6 +5849,54 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
             return handle_test_command(remaining_args);
         }
+
+        if first_arg_lower == "bench" {
+            // Handle 'bench' command: quest bench [OPTIONS] [PATHS...]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_bench_command(remaining_args);
+        }
+
+        if first_arg_lower == "fmt" {
+            // Handle 'fmt' command: quest fmt [OPTIONS] [PATHS...]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_fmt_command(remaining_args);
+        }
+
+        if first_arg_lower == "eval" {
+            // Handle 'eval' command: quest eval [-r MODULE] -e CODE [ARGS...]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_eval_command(remaining_args);
+        }
+
+        if first_arg_lower == "profile" {
+            // Handle 'profile' command: quest profile [OPTIONS] <script> [args...]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_profile_command(remaining_args);
+        }
+
+        if first_arg_lower == "check" {
+            // Handle 'check' command: quest check [PATHS...]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_check_command(remaining_args);
+        }
+
+        if first_arg_lower == "debug" {
+            // Handle 'debug' command: quest debug [--dap [--port N]] <script> [args...]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_debug_command(remaining_args);
+        }
+
+        if first_arg_lower == "install" {
+            // Handle 'install' command: quest install [--update]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_install_command(remaining_args);
+        }
+
+        if first_arg_lower == "migrate" {
+            // Handle 'migrate' command: quest migrate [COMMAND] [OPTIONS]
+            let remaining_args = if args.len() > 2 { &args[2..] } else { &[] };
+            return handle_migrate_command(remaining_args);
+        }
         
         // Otherwise, treat the first positional argument as a file path
         let filename = &args[1];
@@ -5391,8 +5938,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
     
-    // Otherwise, run interactive REPL
-    run_repl()?;
+    // Otherwise, run interactive REPL (highlighted unless NO_COLOR is set)
+    run_repl(env::var_os("NO_COLOR").is_none())?;
     
     // Print debug stats if QUEST_CLONE_DEBUG is enabled
     alloc_counter::print_stats();
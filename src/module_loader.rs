@@ -22,7 +22,9 @@ use crate::embedded_lib;
 pub fn load_external_module(scope: &mut Scope, path: &str, alias: &str) -> Result<(), String> {
     // QEP-048: Track module loading depth
     scope.module_loading_depth += 1;
+    let caller = crate::debugger::enter_call(scope);
     let result = load_external_module_impl(scope, path, alias);
+    drop(caller);
     scope.module_loading_depth -= 1;
     result
 }
@@ -73,6 +75,8 @@ fn load_external_module_impl(scope: &mut Scope, path: &str, alias: &str) -> Resu
         module_scope.current_script_path = Rc::new(RefCell::new(Some(canonical_path.clone())));
         // QEP-057: Set current file for magic variables
         module_scope.current_file = Some(canonical_path.clone());
+        module_scope.source_file = Some(Rc::new(canonical_path.clone()));
+        crate::strict::check_pragma(&canonical_path, &file_content);
 
         // Parse and evaluate module
         let pairs = QuestParser::parse(Rule::program, &file_content)
@@ -114,15 +118,16 @@ fn load_external_module_impl(scope: &mut Scope, path: &str, alias: &str) -> Resu
         // Check if evaluation failed
         eval_result?;
 
-        // Get the complete module scope (contains both public and private)
-        let all_members = module_scope.to_flat_map();
+        // Share the module's top-level scope (contains both public and private) as its
+        // member table, so replacing a member (test.mock) also affects internal calls
+        let all_members = Rc::clone(&module_scope.scopes[0]);
         let public_items = module_scope.public_items.clone();
 
         // IMPORTANT: All functions in all_members have their captured_scope set to
         // the module's scope, so they can access private variables
 
         // Create module with public/private separation
-        let new_module = QValue::Module(Box::new(QModule::with_public_items(
+        let new_module = QValue::Module(Box::new(QModule::with_shared_members(
             alias.to_string(),
             all_members,
             public_items,
@@ -142,12 +147,34 @@ fn load_external_module_impl(scope: &mut Scope, path: &str, alias: &str) -> Resu
 
 /// Resolve module path with relative import support
 fn resolve_module_path_full(path: &str, scope: &Scope) -> Result<String, String> {
+    let current_script = scope.current_script_path.borrow().clone();
+
+    // Search paths added at runtime through os.search_path
+    let mut extra_paths = vec![];
+    if let Some(QValue::Module(os_module)) = scope.get("os") {
+        if let Some(QValue::Array(arr)) = os_module.get_member("search_path") {
+            let elements = arr.elements.borrow();
+            for elem in elements.iter() {
+                if let QValue::Str(s) = elem {
+                    extra_paths.push(s.value.as_ref().clone());
+                }
+            }
+        }
+    }
+
+    resolve_module_path_from(path, current_script.as_deref(), &extra_paths)
+}
+
+/// Resolve a `use` path the way the interpreter does, without needing a live scope
+///
+/// Relative paths are resolved against `script_path`; everything else goes through
+/// installed packages and then the directories from `search_dirs`.
+pub fn resolve_module_path_from(path: &str, script_path: Option<&str>, extra_paths: &[String]) -> Result<String, String> {
     // Check if this is a relative import (starts with ".")
     if path.starts_with('.') {
         // Relative import - resolve relative to current script
-        let current_script = scope.current_script_path.borrow().clone();
-        if let Some(script_path) = current_script {
-            let script_dir = std::path::Path::new(&script_path)
+        if let Some(script_path) = script_path {
+            let script_dir = std::path::Path::new(script_path)
                 .parent()
                 .ok_or_else(|| format!("Cannot determine parent directory of '{}'", script_path))?;
 
@@ -170,79 +197,116 @@ fn resolve_module_path_full(path: &str, scope: &Scope) -> Result<String, String>
         }
     }
 
-    // Absolute import - use search paths
-    let mut search_paths = vec![];
-
-    // 1. First priority: Development lib/ directory (if exists)
-    //    This allows developers to use local modifications during development
-    if std::path::Path::new("lib/").exists() {
-        search_paths.push("lib/".to_string());
+    // Installed packages (quest_modules/, listed in the nearest quest.lock)
+    if !path.starts_with("std/") {
+        if let Some(resolved) = crate::package::resolve_package_module(path, script_path) {
+            return Ok(resolved);
+        }
     }
 
-    // 2. Try to get search paths from os module if it exists
-    if let Some(QValue::Module(os_module)) = scope.get("os") {
-        if let Some(QValue::Array(arr)) = os_module.get_member("search_path") {
-            let elements = arr.elements.borrow();
-            for elem in elements.iter() {
-                if let QValue::Str(s) = elem {
-                    search_paths.push(s.value.as_ref().clone());
-                }
-            }
+    let dirs = search_dirs(script_path, extra_paths);
+    resolve_module_path(path, &dirs).map_err(|err| {
+        if crate::package::is_declared_dependency(path, script_path) {
+            let name = path.split('/').next().unwrap_or(path);
+            format!("{}\nPackage '{}' is listed in quest.toml [dependencies] but is not installed; run `quest install`",
+                err, name.strip_suffix(".q").unwrap_or(name))
+        } else {
+            err
         }
+    })
+}
+
+/// Built-in modules under std/encoding/. They used to be importable directly
+/// under std/ (`use "std/json"`), which still works with a DeprecationWarning.
+pub const ENCODING_MODULES: &[&str] = &["json", "b64", "csv", "hex", "url", "yaml", "xml", "struct", "proto"];
+
+/// A module search directory and where it came from (shown when a module isn't found)
+pub type SearchDir = (String, &'static str);
+
+/// Search directories that come from the environment rather than the script:
+/// lib/, QUEST_PATH, QUEST_INCLUDE and the extracted stdlib. These are also the
+/// initial contents of `os.search_path`.
+pub fn default_search_dirs() -> Vec<SearchDir> {
+    let mut dirs = vec![];
+
+    // Development lib/ directory, so local modifications win during development
+    if std::path::Path::new("lib/").exists() {
+        dirs.push(("lib/".to_string(), "development lib/"));
     }
 
-    // 3. QUEST_INCLUDE environment variable
-    let quest_include = env::var("QUEST_INCLUDE").unwrap_or_else(|_| String::new());
-    if !quest_include.is_empty() {
-        let separator = if cfg!(windows) { ';' } else { ':' };
-        for path_component in quest_include.split(separator) {
+    // QUEST_PATH, then the older QUEST_INCLUDE
+    let separator = if cfg!(windows) { ';' } else { ':' };
+    for var in ["QUEST_PATH", "QUEST_INCLUDE"] {
+        let value = env::var(var).unwrap_or_default();
+        for path_component in value.split(separator) {
             if !path_component.is_empty() {
-                search_paths.push(path_component.to_string());
+                dirs.push((path_component.to_string(), var));
             }
         }
     }
 
-    // 4. Fallback: Extracted stdlib in ~/.quest/lib (for installed binary)
+    // Fallback: Extracted stdlib in ~/.quest/lib (for installed binary)
     let stdlib_dir = embedded_lib::get_stdlib_dir();
     if stdlib_dir.exists() {
         if let Some(stdlib_str) = stdlib_dir.to_str() {
-            search_paths.push(stdlib_str.to_string());
+            dirs.push((stdlib_str.to_string(), "installed stdlib"));
         }
     }
 
-    resolve_module_path(path, &search_paths)
+    dirs
 }
 
-/// Resolve a module path using search paths
-pub fn resolve_module_path(relative_path: &str, search_paths: &[String]) -> Result<String, String> {
+/// Every directory searched for a non-relative `use` path, in order
+///
+/// The current directory, lib/, directories added to `os.search_path`, the
+/// `search_path` of the nearest quest.toml, QUEST_PATH, QUEST_INCLUDE and
+/// finally the extracted stdlib.
+pub fn search_dirs(script_path: Option<&str>, extra_paths: &[String]) -> Vec<SearchDir> {
+    let defaults = default_search_dirs();
+    let (lib, rest): (Vec<SearchDir>, Vec<SearchDir>) = defaults.iter().cloned().partition(|(_, source)| *source == "development lib/");
+
+    let mut dirs = vec![];
+    if let Ok(cwd) = env::current_dir() {
+        dirs.push((cwd.to_string_lossy().to_string(), "current directory"));
+    }
+    dirs.extend(lib);
+    // os.search_path starts out as the defaults; only what was added to it counts here
+    for path in extra_paths {
+        if !defaults.iter().any(|(dir, _)| dir == path) {
+            dirs.push((path.clone(), "os.search_path"));
+        }
+    }
+    for path in crate::package::project_search_paths(script_path) {
+        dirs.push((path, "quest.toml search_path"));
+    }
+    dirs.extend(rest);
+
+    let mut seen = HashSet::new();
+    dirs.retain(|(dir, _)| seen.insert(dir.clone()));
+    dirs
+}
+
+/// Resolve a module path against search directories, first match wins
+pub fn resolve_module_path(relative_path: &str, search_dirs: &[SearchDir]) -> Result<String, String> {
     let with_extension = if relative_path.ends_with(".q") {
         relative_path.to_string()
     } else {
         format!("{}.q", relative_path)
     };
 
-    // Try current directory first
-    let cwd_path = env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?
-        .join(&with_extension);
-
-    if cwd_path.exists() {
-        return Ok(cwd_path.to_string_lossy().to_string());
-    }
-
-    // Try each search path
-    for search_dir in search_paths {
+    for (search_dir, _) in search_dirs {
         let candidate = std::path::Path::new(search_dir).join(&with_extension);
         if candidate.exists() {
             return Ok(candidate.to_string_lossy().to_string());
         }
     }
 
-    import_err!(
-        "Module '{}' not found in current directory or search paths: [{}]",
-        relative_path,
-        search_paths.join(", ")
-    )
+    let searched: Vec<String> = search_dirs.iter().enumerate()
+        .map(|(i, (dir, source))| {
+            format!("  {}. {} ({})", i + 1, std::path::Path::new(dir).join(&with_extension).display(), source)
+        })
+        .collect();
+    import_err!("Module '{}' not found. Tried, in order:\n{}", relative_path, searched.join("\n"))
 }
 
 /// Extract docstring from the beginning of a file or function body
@@ -374,6 +438,8 @@ pub fn apply_module_overlay(
         .unwrap_or_else(|| path.clone());
     overlay_scope.current_script_path = Rc::new(RefCell::new(Some(canonical_path.clone())));
     // QEP-057: Set current file for magic variables
+    overlay_scope.source_file = Some(Rc::new(canonical_path.clone()));
+    crate::strict::check_pragma(&canonical_path, &overlay_source);
     overlay_scope.current_file = Some(canonical_path);
 
     // Set __builtin__ to the Rust module (for overlay code to access)
@@ -0,0 +1,45 @@
+// Argument helpers shared by builtin modules
+//
+// Keyword arguments reach builtins as a trailing options Dict (see
+// CallArguments::into_builtin_args), so modules peel that Dict off before
+// checking positional arguments.
+
+use crate::type_err;
+use crate::types::*;
+
+/// Remove and return the trailing options Dict, if there is one
+pub fn take_options(args: &mut Vec<QValue>) -> Option<Box<QDict>> {
+    match args.last() {
+        Some(QValue::Dict(_)) => match args.pop() {
+            Some(QValue::Dict(options)) => Some(options),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The positional arguments and the trailing options Dict, without copying
+pub fn split_options(args: &[QValue]) -> (&[QValue], Option<&QDict>) {
+    match args {
+        [rest @ .., QValue::Dict(d)] => (rest, Some(d.as_ref())),
+        _ => (args, None),
+    }
+}
+
+/// The option `name`; nil counts as missing
+pub fn option(options: &Option<Box<QDict>>, name: &str) -> Option<QValue> {
+    get_option(options.as_deref(), name)
+}
+
+/// option() for a Dict borrowed through split_options()
+pub fn get_option(options: Option<&QDict>, name: &str) -> Option<QValue> {
+    options.and_then(|o| o.get(name)).filter(|v| !matches!(v, QValue::Nil(_)))
+}
+
+/// A Str argument, reported as "<func_name> <name> must be a Str" otherwise
+pub fn str_arg(func_name: &str, name: &str, value: &QValue) -> Result<String, String> {
+    match value {
+        QValue::Str(s) => Ok(s.value.to_string()),
+        other => type_err!("{} {} must be a Str, got {}", func_name, name, other.as_obj().cls()),
+    }
+}
@@ -0,0 +1,488 @@
+// std/cache - in-memory LRU and TTL caches
+//
+// A cache maps hashable Quest values (scalars, and Arrays/Dicts of them) to
+// arbitrary values. Recency is tracked with a tick counter: every hit or
+// write stamps the entry with the next tick, and a BTreeMap from tick to key
+// gives the least recently used entry in O(log n). Expiry uses the monotonic
+// clock, so wall-clock changes never expire (or revive) entries.
+//
+// The memoize decorator in lib/std/cache.q keeps one of these per function
+// and routes every call through memo_call(), so argument hashing, lookup and
+// the call itself stay in Rust.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+use ordered_float::OrderedFloat;
+
+use crate::control_flow::EvalError;
+use crate::function_call::{call_value, CallArguments};
+use crate::types::*;
+use crate::modules::args::take_options;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+/// Default max_size for cache.lru()
+const DEFAULT_MAX_SIZE: usize = 128;
+
+/// An unbounded TTL cache sweeps expired entries once it has grown past this
+/// many entries since the last sweep
+const MIN_SWEEP: usize = 64;
+
+/// Hashable form of a cache key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(OrderedFloat<f64>),
+    Str(String),
+    Bytes(Vec<u8>),
+    Seq(Vec<CacheKey>),
+    /// Dict entries sorted by key, so {a: 1, b: 2} and {b: 2, a: 1} match
+    Map(Vec<(String, CacheKey)>),
+}
+
+impl CacheKey {
+    fn from_qvalue(value: &QValue) -> Result<Self, String> {
+        match value {
+            QValue::Nil(_) => Ok(CacheKey::Nil),
+            QValue::Bool(b) => Ok(CacheKey::Bool(b.value)),
+            QValue::Int(i) => Ok(CacheKey::Int(i.value)),
+            QValue::Float(f) => Ok(CacheKey::Float(OrderedFloat(f.value))),
+            QValue::Str(s) => Ok(CacheKey::Str(s.value.as_ref().clone())),
+            QValue::Bytes(b) => Ok(CacheKey::Bytes(b.data.clone())),
+            QValue::Array(a) => Ok(CacheKey::Seq(Self::from_values(&a.elements.borrow())?)),
+            QValue::Dict(d) => Self::from_dict(&d.map.borrow()),
+            _ => type_err!("Cache keys must be Nil, Bool, Int, Float, Str, Bytes, or Arrays and Dicts of those, got {}", value.as_obj().cls()),
+        }
+    }
+
+    fn from_values(values: &[QValue]) -> Result<Vec<Self>, String> {
+        values.iter().map(Self::from_qvalue).collect()
+    }
+
+    fn from_dict(map: &IndexMap<String, QValue>) -> Result<Self, String> {
+        let mut entries = map.iter()
+            .map(|(k, v)| Ok((k.clone(), Self::from_qvalue(v)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(CacheKey::Map(entries))
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// The key as the caller passed it, for keys()
+    key: QValue,
+    value: QValue,
+    tick: u64,
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    fn expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|at| at <= now)
+    }
+}
+
+#[derive(Debug)]
+struct CacheState {
+    entries: HashMap<CacheKey, Entry>,
+    /// Last-use tick -> key, oldest first
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    max_size: Option<usize>,
+    ttl: Option<Duration>,
+    next_sweep: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+}
+
+impl CacheState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Look up a live entry, counting the hit or miss and marking it as
+    /// recently used
+    fn lookup(&mut self, key: &CacheKey) -> Option<QValue> {
+        let now = Instant::now();
+        let old_tick = match self.entries.get(key) {
+            Some(entry) if entry.expired(now) => {
+                self.remove(key);
+                self.expirations += 1;
+                None
+            }
+            Some(entry) => Some(entry.tick),
+            None => None,
+        };
+        let Some(old_tick) = old_tick else {
+            self.misses += 1;
+            return None;
+        };
+
+        self.hits += 1;
+        let tick = self.next_tick();
+        self.order.remove(&old_tick);
+        self.order.insert(tick, key.clone());
+        let entry = self.entries.get_mut(key)?;
+        entry.tick = tick;
+        Some(entry.value.clone())
+    }
+
+    fn live(&self, key: &CacheKey) -> bool {
+        self.entries.get(key).is_some_and(|e| !e.expired(Instant::now()))
+    }
+
+    fn insert(&mut self, key: CacheKey, key_value: QValue, value: QValue, ttl: Option<Duration>) {
+        let expires = ttl.or(self.ttl).map(|ttl| Instant::now() + ttl);
+        self.remove(&key);
+
+        if self.max_size.is_none() && self.ttl.is_some() && self.entries.len() >= self.next_sweep {
+            self.purge();
+            self.next_sweep = (self.entries.len() * 2).max(MIN_SWEEP);
+        }
+        if let Some(max) = self.max_size {
+            while self.entries.len() >= max {
+                let Some((_, oldest)) = self.order.pop_first() else { break };
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+            }
+        }
+
+        let tick = self.next_tick();
+        self.order.insert(tick, key.clone());
+        self.entries.insert(key, Entry { key: key_value, value, tick, expires });
+    }
+
+    fn remove(&mut self, key: &CacheKey) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        Some(entry)
+    }
+
+    /// Drop every expired entry, returning how many were removed
+    fn purge(&mut self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<CacheKey> = self.entries.iter()
+            .filter(|(_, e)| e.expired(now))
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        self.expirations += expired.len() as u64;
+        expired.len()
+    }
+}
+
+/// QCache - LRU/TTL cache; copies share the same entries
+#[derive(Debug, Clone)]
+pub struct QCache {
+    state: Rc<RefCell<CacheState>>,
+    pub id: u64,
+}
+
+impl QCache {
+    pub fn new(max_size: Option<usize>, ttl: Option<Duration>) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(CacheState {
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                tick: 0,
+                max_size,
+                ttl,
+                next_sweep: MIN_SWEEP,
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+                expirations: 0,
+            })),
+            id: next_object_id(),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+        // Try QObj trait methods first
+        use crate::types::try_call_qobj_method;
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "get" => {
+                // get(key, default = nil)
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("get expects 1 or 2 arguments (key, default), got {}", args.len());
+                }
+                let key = CacheKey::from_qvalue(&args[0])?;
+                let value = self.state.borrow_mut().lookup(&key);
+                Ok(value.unwrap_or_else(|| args.get(1).cloned().unwrap_or(QValue::Nil(QNil))))
+            }
+            "set" => {
+                // set(key, value, ttl: nil)
+                let (args, ttl) = split_ttl(args, 2)?;
+                if args.len() != 2 {
+                    return arg_err!("set expects 2 arguments (key, value), got {}", args.len());
+                }
+                let key = CacheKey::from_qvalue(&args[0])?;
+                self.state.borrow_mut().insert(key, args[0].clone(), args[1].clone(), ttl);
+                Ok(QValue::Nil(QNil))
+            }
+            "get_or_set" => {
+                // get_or_set(key, fn, ttl: nil) - fn() computes the value on a miss
+                let (args, ttl) = split_ttl(args, 2)?;
+                if args.len() != 2 {
+                    return arg_err!("get_or_set expects 2 arguments (key, fn), got {}", args.len());
+                }
+                let key = CacheKey::from_qvalue(&args[0])?;
+                if let Some(value) = self.state.borrow_mut().lookup(&key) {
+                    return Ok(value);
+                }
+                // No borrow is held while fn runs, so it may use the cache too
+                let value = call_value(&args[1], CallArguments::positional_only(Vec::new()), scope)?;
+                self.state.borrow_mut().insert(key, args[0].clone(), value.clone(), ttl);
+                Ok(value)
+            }
+            "memo_call" => {
+                // memo_call(fn, args, kwargs) - fn(*args, **kwargs), cached by the arguments
+                if args.len() != 3 {
+                    return arg_err!("memo_call expects 3 arguments (fn, args, kwargs), got {}", args.len());
+                }
+                let (QValue::Array(positional), QValue::Dict(keyword)) = (&args[1], &args[2]) else {
+                    return type_err!("memo_call expects an Array of arguments and a Dict of keyword arguments");
+                };
+                let positional = positional.elements.borrow().clone();
+                let keyword = keyword.map.borrow().clone();
+                // Keyed as [args] or [args, kwargs], so get/delete can find the entry
+                let mut parts = vec![CacheKey::Seq(CacheKey::from_values(&positional)?)];
+                let mut key_parts = vec![args[1].clone()];
+                if !keyword.is_empty() {
+                    parts.push(CacheKey::from_dict(&keyword)?);
+                    key_parts.push(args[2].clone());
+                }
+                let key = CacheKey::Seq(parts);
+                if let Some(value) = self.state.borrow_mut().lookup(&key) {
+                    return Ok(value);
+                }
+
+                let key_value = QValue::Array(QArray::new(key_parts));
+                let value = call_value(&args[0], CallArguments::new(positional, keyword), scope)?;
+                self.state.borrow_mut().insert(key, key_value, value.clone(), None);
+                Ok(value)
+            }
+            "contains" => {
+                check_count(method_name, &args, 1, "key")?;
+                let key = CacheKey::from_qvalue(&args[0])?;
+                Ok(QValue::Bool(QBool::new(self.state.borrow().live(&key))))
+            }
+            "delete" => {
+                // Returns true if the key was present
+                check_count(method_name, &args, 1, "key")?;
+                let key = CacheKey::from_qvalue(&args[0])?;
+                let removed = self.state.borrow_mut().remove(&key);
+                Ok(QValue::Bool(QBool::new(removed.is_some_and(|e| !e.expired(Instant::now())))))
+            }
+            "clear" => {
+                check_count(method_name, &args, 0, "")?;
+                let mut state = self.state.borrow_mut();
+                state.entries.clear();
+                state.order.clear();
+                Ok(QValue::Nil(QNil))
+            }
+            "purge" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(QValue::Int(QInt::new(self.state.borrow_mut().purge() as i64)))
+            }
+            "len" => {
+                check_count(method_name, &args, 0, "")?;
+                let state = self.state.borrow();
+                let now = Instant::now();
+                Ok(QValue::Int(QInt::new(state.entries.values().filter(|e| !e.expired(now)).count() as i64)))
+            }
+            "keys" => {
+                // Least recently used first
+                check_count(method_name, &args, 0, "")?;
+                let state = self.state.borrow();
+                let now = Instant::now();
+                let keys = state.order.values()
+                    .filter_map(|k| state.entries.get(k))
+                    .filter(|e| !e.expired(now))
+                    .map(|e| e.key.clone())
+                    .collect();
+                Ok(QValue::Array(QArray::new(keys)))
+            }
+            "stats" => {
+                check_count(method_name, &args, 0, "")?;
+                let state = self.state.borrow();
+                let mut stats = IndexMap::new();
+                let int = |n: u64| QValue::Int(QInt::new(n as i64));
+                stats.insert("hits".to_string(), int(state.hits));
+                stats.insert("misses".to_string(), int(state.misses));
+                stats.insert("evictions".to_string(), int(state.evictions));
+                stats.insert("expirations".to_string(), int(state.expirations));
+                stats.insert("size".to_string(), int(state.entries.len() as u64));
+                stats.insert("max_size".to_string(), match state.max_size {
+                    Some(max) => int(max as u64),
+                    None => QValue::Nil(QNil),
+                });
+                stats.insert("ttl".to_string(), match state.ttl {
+                    Some(ttl) => QValue::Float(QFloat::new(ttl.as_secs_f64())),
+                    None => QValue::Nil(QNil),
+                });
+                Ok(QValue::Dict(Box::new(QDict::new(stats))))
+            }
+            "reset_stats" => {
+                check_count(method_name, &args, 0, "")?;
+                let mut state = self.state.borrow_mut();
+                state.hits = 0;
+                state.misses = 0;
+                state.evictions = 0;
+                state.expirations = 0;
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on Cache", method_name),
+        }
+    }
+}
+
+impl QObj for QCache {
+    fn cls(&self) -> String {
+        "Cache".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Cache"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Cache"
+    }
+
+    fn str(&self) -> String {
+        self._rep()
+    }
+
+    fn _rep(&self) -> String {
+        let state = self.state.borrow();
+        let mut parts = vec![format!("size={}", state.entries.len())];
+        if let Some(max) = state.max_size {
+            parts.push(format!("max_size={}", max));
+        }
+        if let Some(ttl) = state.ttl {
+            parts.push(format!("ttl={}", ttl.as_secs_f64()));
+        }
+        format!("Cache({})", parts.join(", "))
+    }
+
+    fn _doc(&self) -> String {
+        "An in-memory LRU/TTL cache".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+fn check_count(method: &str, args: &[QValue], expected: usize, usage: &str) -> Result<(), String> {
+    if args.len() != expected {
+        if expected == 0 {
+            return arg_err!("{} expects 0 arguments, got {}", method, args.len());
+        }
+        return arg_err!("{} expects {} argument ({}), got {}", method, expected, usage, args.len());
+    }
+    Ok(())
+}
+
+/// Split a trailing `ttl:` keyword Dict off a call with `positional` arguments.
+/// A Dict in a positional slot is a value, not options.
+fn split_ttl(mut args: Vec<QValue>, positional: usize) -> Result<(Vec<QValue>, Option<Duration>), String> {
+    if args.len() <= positional {
+        return Ok((args, None));
+    }
+    let Some(QValue::Dict(options)) = args.pop() else {
+        return arg_err!("expected {} positional arguments, got {}", positional, args.len() + 1);
+    };
+    let ttl = match options.get("ttl") {
+        Some(value) => ttl_value(&value)?,
+        None => None,
+    };
+    Ok((args, ttl))
+}
+
+/// Seconds (Int/Float), a duration string like "5m" or "1h30m", or a Span.
+/// nil means no expiry.
+fn ttl_value(value: &QValue) -> Result<Option<Duration>, String> {
+    if matches!(value, QValue::Nil(_)) {
+        return Ok(None);
+    }
+    let secs = crate::modules::time::seconds_value("ttl", value)?;
+    if !secs.is_finite() || secs <= 0.0 {
+        return value_err!("ttl must be positive, got {}", secs);
+    }
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
+fn max_size_value(value: &QValue) -> Result<Option<usize>, String> {
+    match value {
+        QValue::Nil(_) => Ok(None),
+        QValue::Int(i) if i.value > 0 => Ok(Some(i.value as usize)),
+        QValue::Int(i) => value_err!("max_size must be positive, got {}", i.value),
+        _ => type_err!("max_size must be Int, got {}", value.as_obj().cls()),
+    }
+}
+
+pub fn create_cache_module() -> QValue {
+    let mut members = HashMap::new();
+    members.insert("lru".to_string(), create_fn("cache", "lru"));
+    members.insert("ttl".to_string(), create_fn("cache", "ttl"));
+    QValue::Module(Box::new(QModule::new("cache".to_string(), members)))
+}
+
+/// Handle cache.* function calls
+pub fn call_cache_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let mut args = args;
+    let options = take_options(&mut args);
+    // Not args::option(): an explicit nil (e.g. `max_size: nil`) is a value here
+    let option = |index: usize, key: &str| args.get(index).cloned().or_else(|| options.as_ref().and_then(|o| o.get(key)));
+
+    match func_name {
+        "cache.lru" => {
+            // lru(max_size = 128, ttl: nil)
+            if args.len() > 2 {
+                return arg_err!("cache.lru expects 0 to 2 arguments (max_size, ttl), got {}", args.len());
+            }
+            let max_size = match option(0, "max_size") {
+                Some(value) => max_size_value(&value)?,
+                None => Some(DEFAULT_MAX_SIZE),
+            };
+            let ttl = match option(1, "ttl") {
+                Some(value) => ttl_value(&value)?,
+                None => None,
+            };
+            Ok(QValue::Cache(QCache::new(max_size, ttl)))
+        }
+        "cache.ttl" => {
+            // ttl(ttl, max_size: nil)
+            if args.len() > 2 {
+                return arg_err!("cache.ttl expects 1 or 2 arguments (ttl, max_size), got {}", args.len());
+            }
+            let Some(ttl) = option(0, "ttl") else {
+                return arg_err!("cache.ttl expects 1 or 2 arguments (ttl, max_size), got 0");
+            };
+            let ttl = ttl_value(&ttl)?;
+            let max_size = match option(1, "max_size") {
+                Some(value) => max_size_value(&value)?,
+                None => None,
+            };
+            Ok(QValue::Cache(QCache::new(max_size, ttl)))
+        }
+        _ => attr_err!("Unknown cache function: {}", func_name),
+    }
+}
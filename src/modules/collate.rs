@@ -0,0 +1,184 @@
+// std/collate - locale-aware string comparison and sorting
+//
+// Plain string comparison orders by code point, so "Zebra" sorts before
+// "apple", "été" after "zoo", and "file10" before "file2". Collation uses
+// the Unicode Collation Algorithm with CLDR tailorings from ICU4X: "ä"
+// sorts with "a" in German and after "z" in Swedish. natural_cmp() is the
+// locale-independent ordering behind Array.sort(natural: true).
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use icu_collator::{AlternateHandling, CaseLevel, Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::Locale;
+
+use crate::control_flow::EvalError;
+use crate::function_call::{call_value, CallArguments};
+use crate::modules::args::{option, str_arg, take_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+pub fn create_collate_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("compare".to_string(), create_fn("collate", "compare"));
+    members.insert("equal".to_string(), create_fn("collate", "equal"));
+    members.insert("sort".to_string(), create_fn("collate", "sort"));
+    members.insert("natural_compare".to_string(), create_fn("collate", "natural_compare"));
+    members.insert("current".to_string(), create_fn("collate", "current"));
+
+    QValue::Module(Box::new(QModule::new("collate".to_string(), members)))
+}
+
+/// Compare strings with runs of ASCII digits ordered by their numeric value,
+/// so "file2" < "file10". Everything else compares by code point. Strings
+/// that differ only in leading zeros ("a01", "a1") fall back to a plain
+/// comparison, which keeps the order total.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a, b);
+    loop {
+        let (Some(cx), Some(cy)) = (x.chars().next(), y.chars().next()) else {
+            return x.len().cmp(&y.len()).then_with(|| a.cmp(b));
+        };
+        if cx.is_ascii_digit() && cy.is_ascii_digit() {
+            let (nx, rest_x) = split_digits(x);
+            let (ny, rest_y) = split_digits(y);
+            let (tx, ty) = (nx.trim_start_matches('0'), ny.trim_start_matches('0'));
+            match tx.len().cmp(&ty.len()).then_with(|| tx.cmp(ty)) {
+                Ordering::Equal => (x, y) = (rest_x, rest_y),
+                ordering => return ordering,
+            }
+        } else {
+            match cx.cmp(&cy) {
+                Ordering::Equal => (x, y) = (&x[cx.len_utf8()..], &y[cy.len_utf8()..]),
+                ordering => return ordering,
+            }
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn flag(options: &Option<Box<QDict>>, name: &str) -> bool {
+    option(options, name).is_some_and(|v| v.as_bool())
+}
+
+/// The collation locale from LC_ALL, LC_COLLATE or LANG; "und" (the CLDR
+/// root order) when unset or "C"/"POSIX"
+fn current_locale() -> String {
+    ["LC_ALL", "LC_COLLATE", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| parse_locale(&value).ok())
+        .map(|locale| locale.to_string())
+        .unwrap_or_else(|| "und".to_string())
+}
+
+/// Parse a BCP 47 or POSIX locale tag ("de-DE", "sv_SE.UTF-8", "C")
+fn parse_locale(tag: &str) -> Result<Locale, String> {
+    let cleaned = tag.split(['.', '@']).next().unwrap_or("").replace('_', "-");
+    if cleaned.is_empty() || cleaned == "C" || cleaned == "POSIX" {
+        return Ok(Locale::UND);
+    }
+    cleaned.parse::<Locale>().map_err(|_| format!("ValueErr: Invalid locale '{}'", tag))
+}
+
+/// Build a collator from locale: and the ignore_*/natural options. Locales
+/// without CLDR tailoring use the root order.
+fn collator(func_name: &str, options: &Option<Box<QDict>>) -> Result<Collator, String> {
+    let locale = match option(options, "locale") {
+        None => parse_locale(&current_locale())?,
+        Some(QValue::Str(s)) => parse_locale(&s.value)?,
+        Some(other) => return type_err!("{} locale must be a Str, got {}", func_name, other.as_obj().cls()),
+    };
+    let (ignore_case, ignore_accents) = (flag(options, "ignore_case"), flag(options, "ignore_accents"));
+
+    let mut collator_options = CollatorOptions::new();
+    // Primary strength ignores accents and case; the case level brings case back
+    collator_options.strength = Some(match (ignore_accents, ignore_case) {
+        (false, false) => Strength::Tertiary,
+        (false, true) => Strength::Secondary,
+        (true, _) => Strength::Primary,
+    });
+    if ignore_accents && !ignore_case {
+        collator_options.case_level = Some(CaseLevel::On);
+    }
+    if flag(options, "ignore_punctuation") {
+        collator_options.alternate_handling = Some(AlternateHandling::Shifted);
+    }
+    if flag(options, "natural") {
+        collator_options.numeric = Some(Numeric::On);
+    }
+    match Collator::try_new(&(&locale).into(), collator_options) {
+        Ok(collator) => Ok(collator),
+        Err(e) => value_err!("{}: no collation data for locale '{}': {}", func_name, locale, e),
+    }
+}
+
+fn ordering_value(ordering: Ordering) -> QValue {
+    QValue::Int(QInt::new(ordering as i64))
+}
+
+pub fn call_collate_function(func_name: &str, mut args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let options = take_options(&mut args);
+    match func_name {
+        "collate.compare" | "collate.equal" => {
+            // compare(a, b, locale:, natural:, ignore_case:, ignore_accents:, ignore_punctuation:) -> -1, 0 or 1
+            if args.len() != 2 {
+                return arg_err!("{} expects 2 arguments (a, b), got {}", func_name, args.len());
+            }
+            let (a, b) = (str_arg(func_name, "a", &args[0])?, str_arg(func_name, "b", &args[1])?);
+            let ordering = collator(func_name, &options)?.compare(&a, &b);
+            if func_name == "collate.equal" {
+                Ok(QValue::Bool(QBool::new(ordering == Ordering::Equal)))
+            } else {
+                Ok(ordering_value(ordering))
+            }
+        }
+        "collate.sort" => {
+            // sort(strings, key: nil, reverse: false, ...) -> new Array; a key function
+            // maps each element to the Str to compare
+            if args.len() != 1 {
+                return arg_err!("collate.sort expects 1 argument (array), got {}", args.len());
+            }
+            let QValue::Array(array) = &args[0] else {
+                return type_err!("collate.sort expects an Array, got {}", args[0].as_obj().cls());
+            };
+            let collator = collator(func_name, &options)?;
+            let key = option(&options, "key");
+            let elements = array.elements.borrow().clone();
+            let mut keyed = Vec::with_capacity(elements.len());
+            for element in elements {
+                let sort_key = match &key {
+                    Some(f) => call_value(f, CallArguments::positional_only(vec![element.clone()]), scope)?,
+                    None => element.clone(),
+                };
+                keyed.push((str_arg(func_name, "key", &sort_key)?, element));
+            }
+            // Stable, so equal keys keep their order in either direction
+            let reverse = flag(&options, "reverse");
+            keyed.sort_by(|a, b| {
+                let ordering = collator.compare(&a.0, &b.0);
+                if reverse { ordering.reverse() } else { ordering }
+            });
+            Ok(QValue::Array(QArray::new(keyed.into_iter().map(|(_, element)| element).collect())))
+        }
+        "collate.natural_compare" => {
+            if args.len() != 2 {
+                return arg_err!("collate.natural_compare expects 2 arguments (a, b), got {}", args.len());
+            }
+            let (a, b) = (str_arg(func_name, "a", &args[0])?, str_arg(func_name, "b", &args[1])?);
+            Ok(ordering_value(natural_cmp(&a, &b)))
+        }
+        "collate.current" => {
+            if !args.is_empty() {
+                return arg_err!("collate.current expects 0 arguments, got {}", args.len());
+            }
+            Ok(QValue::Str(QString::new(current_locale())))
+        }
+        _ => attr_err!("Unknown collate function: {}", func_name),
+    }
+}
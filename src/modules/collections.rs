@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::modules::args::{get_option, split_options};
+use crate::{arg_err, attr_err, type_err, value_err};
+
+pub fn create_collections_module() -> QValue {
+    let mut members = HashMap::new();
+
+    // Each collection type is a nested type object with a `new` constructor
+    for type_name in ["Deque", "Heap", "PriorityQueue", "Counter", "DefaultDict"] {
+        let mut type_members = HashMap::new();
+        type_members.insert("new".to_string(), create_fn(&format!("collections.{}", type_name), "new"));
+        members.insert(type_name.to_string(),
+            QValue::Module(Box::new(QModule::new(type_name.to_string(), type_members))));
+    }
+
+    QValue::Module(Box::new(QModule::new("collections".to_string(), members)))
+}
+
+pub fn call_collections_function(func_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let (positional, options) = split_options(&args);
+
+    match func_name {
+        "collections.Deque.new" => {
+            // Deque.new() / Deque.new([1, 2, 3], maxlen: 10)
+            let values = initial_values(positional, "Deque.new")?;
+            let maxlen = match get_option(options, "maxlen") {
+                None => None,
+                Some(QValue::Int(n)) if n.value >= 0 => Some(n.value as usize),
+                Some(other) => return value_err!("Deque.new maxlen must be a non-negative Int, got {}", other.as_str()),
+            };
+            Ok(QValue::Deque(QDeque::new(values, maxlen)))
+        }
+
+        "collections.Heap.new" => {
+            // Heap.new() / Heap.new([3, 1, 2], compare: fun (a, b) a > b end)
+            let values = initial_values(positional, "Heap.new")?;
+            let compare = compare_option(options, "Heap.new")?;
+            Ok(QValue::Heap(QHeap::new(compare, values, scope)?))
+        }
+
+        "collections.PriorityQueue.new" => {
+            // PriorityQueue.new() / PriorityQueue.new(compare: fun (a, b) a > b end)
+            if !positional.is_empty() {
+                return arg_err!("PriorityQueue.new expects no positional arguments, got {}", positional.len());
+            }
+            let compare = compare_option(options, "PriorityQueue.new")?;
+            Ok(QValue::PriorityQueue(QPriorityQueue::new(compare)))
+        }
+
+        "collections.Counter.new" => {
+            // Counter.new() / Counter.new(["a", "b", "a"]) / Counter.new("hello") / Counter.new({a: 2})
+            if let Some(counts) = options {
+                // A lone Dict argument is the source of counts, not named arguments
+                return Ok(QValue::Counter(QCounter::from_qvalue(&QValue::Dict(Box::new(counts.clone())))?));
+            }
+            match positional {
+                [] => Ok(QValue::Counter(QCounter::new(CounterState::default()))),
+                [source] => Ok(QValue::Counter(QCounter::from_qvalue(source)?)),
+                _ => arg_err!("Counter.new expects 0 or 1 arguments, got {}", positional.len()),
+            }
+        }
+
+        "collections.DefaultDict.new" => {
+            // DefaultDict.new(fun () [] end) / DefaultDict.new(fun () 0 end, {a: 1})
+            let factory = match positional.first() {
+                Some(QValue::UserFun(f)) => (**f).clone(),
+                Some(other) => return type_err!("DefaultDict.new expects a factory function, got {}", other.as_obj().cls()),
+                None => return arg_err!("DefaultDict.new expects a factory function"),
+            };
+            if positional.len() > 1 {
+                return arg_err!("DefaultDict.new expects 1 or 2 arguments (factory, [initial]), got {}", positional.len() + 1);
+            }
+            let initial = options.map(|d| d.map.borrow().clone()).unwrap_or_default();
+            Ok(QValue::Dict(Box::new(QDict::with_default(initial, factory))))
+        }
+
+        _ => attr_err!("Unknown collections function: {}", func_name),
+    }
+}
+
+fn initial_values(positional: &[QValue], func: &str) -> Result<Vec<QValue>, String> {
+    match positional {
+        [] => Ok(Vec::new()),
+        [QValue::Array(arr)] => Ok(arr.elements.borrow().clone()),
+        [other] => type_err!("{} expects an Array, got {}", func, other.as_obj().cls()),
+        _ => arg_err!("{} expects 0 or 1 positional arguments, got {}", func, positional.len()),
+    }
+}
+
+fn compare_option(options: Option<&QDict>, func: &str) -> Result<Option<QUserFun>, String> {
+    match get_option(options, "compare") {
+        None => Ok(None),
+        Some(QValue::UserFun(f)) => Ok(Some(*f)),
+        Some(other) => type_err!("{} compare must be a function, got {}", func, other.as_obj().cls()),
+    }
+}
@@ -7,6 +7,7 @@ use bzip2::read::BzDecoder;
 use bzip2::write::BzEncoder;
 use std::io::{Read, Write};
 use crate::{arg_err, attr_err};
+use super::stream::{call_stream_function, Format};
 
 /// Create the bzip2 module with compress and decompress functions
 pub fn create_bzip2_module() -> QValue {
@@ -15,6 +16,12 @@ pub fn create_bzip2_module() -> QValue {
     members.insert("compress".to_string(), create_fn("bzip2", "compress"));
     members.insert("decompress".to_string(), create_fn("bzip2", "decompress"));
 
+    // Streaming API
+    members.insert("compressor".to_string(), create_fn("bzip2", "compressor"));
+    members.insert("decompressor".to_string(), create_fn("bzip2", "decompressor"));
+    members.insert("reader".to_string(), create_fn("bzip2", "reader"));
+    members.insert("writer".to_string(), create_fn("bzip2", "writer"));
+
     QValue::Module(Box::new(QModule::new("bzip2".to_string(), members)))
 }
 
@@ -87,6 +94,10 @@ pub fn call_bzip2_function(func_name: &str, args: Vec<QValue>, _scope: &mut crat
             Ok(QValue::Bytes(QBytes::new(result)))
         }
 
+        "bzip2.compressor" | "bzip2.decompressor" | "bzip2.reader" | "bzip2.writer" => {
+            call_stream_function(Format::Bzip2, &func_name["bzip2.".len()..], &args)
+        }
+
         _ => attr_err!("Unknown bzip2 function: {}", func_name)
     }
 }
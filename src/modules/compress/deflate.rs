@@ -3,6 +3,7 @@ use crate::control_flow::EvalError;
 use std::collections::HashMap;
 use crate::types::*;
 use crate::{arg_err, attr_err};
+use super::stream::{call_stream_function, Format};
 use flate2::Compression;
 use flate2::read::DeflateDecoder;
 use flate2::write::DeflateEncoder;
@@ -15,6 +16,12 @@ pub fn create_deflate_module() -> QValue {
     members.insert("compress".to_string(), create_fn("deflate", "compress"));
     members.insert("decompress".to_string(), create_fn("deflate", "decompress"));
 
+    // Streaming API
+    members.insert("compressor".to_string(), create_fn("deflate", "compressor"));
+    members.insert("decompressor".to_string(), create_fn("deflate", "decompressor"));
+    members.insert("reader".to_string(), create_fn("deflate", "reader"));
+    members.insert("writer".to_string(), create_fn("deflate", "writer"));
+
     QValue::Module(Box::new(QModule::new("deflate".to_string(), members)))
 }
 
@@ -86,6 +93,10 @@ pub fn call_deflate_function(func_name: &str, args: Vec<QValue>, _scope: &mut cr
             Ok(QValue::Bytes(QBytes::new(result)))
         }
 
+        "deflate.compressor" | "deflate.decompressor" | "deflate.reader" | "deflate.writer" => {
+            call_stream_function(Format::Deflate, &func_name["deflate.".len()..], &args)
+        }
+
         _ => attr_err!("Unknown deflate function: {}", func_name)
     }
 }
@@ -3,6 +3,7 @@ use crate::control_flow::EvalError;
 use std::collections::HashMap;
 use crate::types::*;
 use crate::{arg_err, attr_err};
+use super::stream::{call_stream_function, Format};
 use flate2::Compression;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
@@ -15,6 +16,12 @@ pub fn create_gzip_module() -> QValue {
     members.insert("compress".to_string(), create_fn("gzip", "compress"));
     members.insert("decompress".to_string(), create_fn("gzip", "decompress"));
 
+    // Streaming API
+    members.insert("compressor".to_string(), create_fn("gzip", "compressor"));
+    members.insert("decompressor".to_string(), create_fn("gzip", "decompressor"));
+    members.insert("reader".to_string(), create_fn("gzip", "reader"));
+    members.insert("writer".to_string(), create_fn("gzip", "writer"));
+
     QValue::Module(Box::new(QModule::new("gzip".to_string(), members)))
 }
 
@@ -86,6 +93,10 @@ pub fn call_gzip_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
             Ok(QValue::Bytes(QBytes::new(result)))
         }
 
+        "gzip.compressor" | "gzip.decompressor" | "gzip.reader" | "gzip.writer" => {
+            call_stream_function(Format::Gzip, &func_name["gzip.".len()..], &args)
+        }
+
         _ => attr_err!("Unknown gzip function: {}", func_name)
     }
 }
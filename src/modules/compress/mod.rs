@@ -5,3 +5,5 @@ pub mod gzip;
 pub mod bzip2;
 pub mod deflate;
 pub mod zlib;
+pub mod tar;
+pub mod stream;
@@ -0,0 +1,702 @@
+// Streaming (incremental) compression shared by the gzip, bzip2, deflate and zlib modules
+// Compressor/Decompressor work chunk by chunk in memory; CompressedReader/CompressedWriter
+// wrap files, byte buffers and process pipes so data never has to fit in memory at once.
+use crate::control_flow::EvalError;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::rc::Rc;
+use crate::types::*;
+use crate::{arg_err, attr_err, io_err, runtime_err, type_err, value_err};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Gzip,
+    Bzip2,
+    Deflate,
+    Zlib,
+}
+
+impl Format {
+    fn name(&self) -> &'static str {
+        match self {
+            Format::Gzip => "gzip",
+            Format::Bzip2 => "bzip2",
+            Format::Deflate => "deflate",
+            Format::Zlib => "zlib",
+        }
+    }
+
+    /// Parse an optional compression level argument (default 6)
+    fn level(&self, func: &str, value: Option<&QValue>) -> Result<u32, EvalError> {
+        let level = match value {
+            None | Some(QValue::Nil(_)) => return Ok(6),
+            Some(QValue::Int(n)) => n.value,
+            Some(QValue::Float(f)) => f.value as i64,
+            Some(other) => return type_err!("{} level must be a number, got {}", func, other.as_obj().cls()),
+        };
+        // Bzip2 uses levels 1-9, the deflate family 0-9
+        let min = if *self == Format::Bzip2 { 1 } else { 0 };
+        if level < min || level > 9 {
+            return value_err!("Compression level must be between {} and 9", min);
+        }
+        Ok(level as u32)
+    }
+}
+
+/// Handle the streaming functions every compress module exposes:
+/// `compressor(level?)`, `decompressor()`, `reader(source)` and `writer(sink, level?)`
+pub fn call_stream_function(format: Format, name: &str, args: &[QValue]) -> Result<QValue, EvalError> {
+    let func = format!("{}.{}", format.name(), name);
+    match name {
+        "compressor" => {
+            if args.len() > 1 {
+                return arg_err!("{} expects 0 or 1 arguments (level?), got {}", func, args.len());
+            }
+            let level = format.level(&func, args.first())?;
+            Ok(QValue::Compressor(QCompressor::new(format, level)))
+        }
+        "decompressor" => {
+            if !args.is_empty() {
+                return arg_err!("{} expects 0 arguments, got {}", func, args.len());
+            }
+            Ok(QValue::Decompressor(QDecompressor::new(format)))
+        }
+        "reader" => {
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument (path, bytes or stream), got {}", func, args.len());
+            }
+            let source = open_source(&func, &args[0])?;
+            Ok(QValue::CompressedReader(QCompressedReader::new(format, source)))
+        }
+        "writer" => {
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("{} expects 1 or 2 arguments (path or stream, level?), got {}", func, args.len());
+            }
+            let level = format.level(&func, args.get(1))?;
+            let sink = open_sink(&func, &args[0])?;
+            Ok(QValue::CompressedWriter(QCompressedWriter::new(format, sink, level)))
+        }
+        _ => attr_err!("Unknown {} function: {}", format.name(), func),
+    }
+}
+
+fn data_arg(method: &str, args: &[QValue]) -> Result<Vec<u8>, EvalError> {
+    if args.len() != 1 {
+        return arg_err!("{} expects 1 argument (data), got {}", method, args.len());
+    }
+    match &args[0] {
+        QValue::Bytes(b) => Ok(b.data.clone()),
+        QValue::Str(s) => Ok(s.value.as_bytes().to_vec()),
+        other => type_err!("{} expects Str or Bytes, got {}", method, other.as_obj().cls()),
+    }
+}
+
+fn no_args(method: &str, args: &[QValue]) -> Result<(), EvalError> {
+    if !args.is_empty() {
+        return arg_err!("{} expects 0 arguments, got {}", method, args.len());
+    }
+    Ok(())
+}
+
+fn bytes(data: Vec<u8>) -> QValue {
+    QValue::Bytes(QBytes::new(data))
+}
+
+// ============================================================================
+// Codecs
+// ============================================================================
+
+enum Encoder<W: Write> {
+    Gzip(flate2::write::GzEncoder<W>),
+    Bzip2(bzip2::write::BzEncoder<W>),
+    Deflate(flate2::write::DeflateEncoder<W>),
+    Zlib(flate2::write::ZlibEncoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    fn new(format: Format, inner: W, level: u32) -> Self {
+        match format {
+            Format::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(inner, flate2::Compression::new(level))),
+            Format::Bzip2 => Encoder::Bzip2(bzip2::write::BzEncoder::new(inner, bzip2::Compression::new(level))),
+            Format::Deflate => Encoder::Deflate(flate2::write::DeflateEncoder::new(inner, flate2::Compression::new(level))),
+            Format::Zlib => Encoder::Zlib(flate2::write::ZlibEncoder::new(inner, flate2::Compression::new(level))),
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        match self {
+            Encoder::Gzip(e) => e.get_mut(),
+            Encoder::Bzip2(e) => e.get_mut(),
+            Encoder::Deflate(e) => e.get_mut(),
+            Encoder::Zlib(e) => e.get_mut(),
+        }
+    }
+
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Gzip(e) => e.finish(),
+            Encoder::Bzip2(e) => e.finish(),
+            Encoder::Deflate(e) => e.finish(),
+            Encoder::Zlib(e) => e.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Gzip(e) => e.write(buf),
+            Encoder::Bzip2(e) => e.write(buf),
+            Encoder::Deflate(e) => e.write(buf),
+            Encoder::Zlib(e) => e.write(buf),
+        }
+    }
+
+    /// Sync flush: everything written so far becomes decodable by the receiver
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Gzip(e) => e.flush(),
+            Encoder::Bzip2(e) => e.flush(),
+            Encoder::Deflate(e) => e.flush(),
+            Encoder::Zlib(e) => e.flush(),
+        }
+    }
+}
+
+enum Decoder {
+    Gzip(flate2::write::MultiGzDecoder<Vec<u8>>),
+    Bzip2(bzip2::write::BzDecoder<Vec<u8>>),
+    Deflate(flate2::write::DeflateDecoder<Vec<u8>>),
+    Zlib(flate2::write::ZlibDecoder<Vec<u8>>),
+}
+
+impl Decoder {
+    fn new(format: Format) -> Self {
+        match format {
+            Format::Gzip => Decoder::Gzip(flate2::write::MultiGzDecoder::new(Vec::new())),
+            Format::Bzip2 => Decoder::Bzip2(bzip2::write::BzDecoder::new(Vec::new())),
+            Format::Deflate => Decoder::Deflate(flate2::write::DeflateDecoder::new(Vec::new())),
+            Format::Zlib => Decoder::Zlib(flate2::write::ZlibDecoder::new(Vec::new())),
+        }
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Decoder::Gzip(d) => d.write_all(data),
+            Decoder::Bzip2(d) => d.write_all(data),
+            Decoder::Deflate(d) => d.write_all(data),
+            Decoder::Zlib(d) => d.write_all(data),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Decoder::Gzip(d) => d.flush(),
+            Decoder::Bzip2(d) => d.flush(),
+            Decoder::Deflate(d) => d.flush(),
+            Decoder::Zlib(d) => d.flush(),
+        }
+    }
+
+    fn take_output(&mut self) -> Vec<u8> {
+        let out = match self {
+            Decoder::Gzip(d) => d.get_mut(),
+            Decoder::Bzip2(d) => d.get_mut(),
+            Decoder::Deflate(d) => d.get_mut(),
+            Decoder::Zlib(d) => d.get_mut(),
+        };
+        std::mem::take(out)
+    }
+
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(d) => d.finish(),
+            Decoder::Bzip2(mut d) => d.finish(),
+            Decoder::Deflate(d) => d.finish(),
+            Decoder::Zlib(d) => d.finish(),
+        }
+    }
+}
+
+fn decoder_for_read(format: Format, source: Box<dyn Read>) -> Box<dyn Read> {
+    match format {
+        Format::Gzip => Box::new(flate2::read::MultiGzDecoder::new(source)),
+        Format::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(source)),
+        Format::Deflate => Box::new(flate2::read::DeflateDecoder::new(source)),
+        Format::Zlib => Box::new(flate2::read::ZlibDecoder::new(source)),
+    }
+}
+
+/// Open the compressed input of a reader: a file path, in-memory Bytes or a process stream
+fn open_source(func: &str, value: &QValue) -> Result<Box<dyn Read>, EvalError> {
+    match value {
+        QValue::Str(s) => {
+            let file = File::open(s.value.as_str())
+                .map_err(|e| format!("IOErr: Failed to open '{}': {}", s.value, e))?;
+            Ok(Box::new(BufReader::with_capacity(CHUNK_SIZE, file)))
+        }
+        QValue::Bytes(b) => Ok(Box::new(Cursor::new(b.data.clone()))),
+        QValue::ReadableStream(stream) => Ok(Box::new(stream.clone())),
+        other => type_err!("{} expects a path, Bytes or ReadableStream, got {}", func, other.as_obj().cls()),
+    }
+}
+
+/// Open the destination of a writer: a file path or a process stdin stream
+fn open_sink(func: &str, value: &QValue) -> Result<Box<dyn Write>, EvalError> {
+    match value {
+        QValue::Str(s) => {
+            let file = File::create(s.value.as_str())
+                .map_err(|e| format!("IOErr: Failed to create '{}': {}", s.value, e))?;
+            Ok(Box::new(BufWriter::with_capacity(CHUNK_SIZE, file)))
+        }
+        QValue::WritableStream(stream) => Ok(Box::new(stream.clone())),
+        other => type_err!("{} expects a path or WritableStream, got {}", func, other.as_obj().cls()),
+    }
+}
+
+// ============================================================================
+// Compressor / Decompressor
+// ============================================================================
+
+#[derive(Clone)]
+pub struct QCompressor {
+    format: Format,
+    encoder: Rc<RefCell<Option<Encoder<Vec<u8>>>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCompressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCompressor").field("format", &self.format).field("id", &self.id).finish()
+    }
+}
+
+impl QCompressor {
+    fn new(format: Format, level: u32) -> Self {
+        QCompressor {
+            format,
+            encoder: Rc::new(RefCell::new(Some(Encoder::new(format, Vec::new(), level)))),
+            id: next_object_id(),
+        }
+    }
+
+    /// Run `op` on the live encoder and return the compressed output it produced
+    fn with_encoder(&self, op: impl FnOnce(&mut Encoder<Vec<u8>>) -> io::Result<()>) -> Result<QValue, EvalError> {
+        let mut guard = self.encoder.borrow_mut();
+        let Some(encoder) = guard.as_mut() else {
+            return runtime_err!("{} compressor is already finished", self.format.name());
+        };
+        op(encoder).map_err(|e| format!("Failed to compress: {}", e))?;
+        Ok(bytes(std::mem::take(encoder.get_mut())))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "write" => {
+                let data = data_arg("write", &args)?;
+                self.with_encoder(|e| e.write_all(&data))
+            }
+            "flush" => {
+                no_args("flush", &args)?;
+                self.with_encoder(|e| e.flush())
+            }
+            "finish" => {
+                no_args("finish", &args)?;
+                let Some(encoder) = self.encoder.borrow_mut().take() else {
+                    return runtime_err!("{} compressor is already finished", self.format.name());
+                };
+                let out = encoder.finish().map_err(|e| format!("Failed to finish compression: {}", e))?;
+                Ok(bytes(out))
+            }
+            "is_finished" => {
+                no_args("is_finished", &args)?;
+                Ok(QValue::Bool(QBool::new(self.encoder.borrow().is_none())))
+            }
+            "format" => {
+                no_args("format", &args)?;
+                Ok(QValue::Str(QString::new(self.format.name().to_string())))
+            }
+            _ => attr_err!("Unknown method '{}' on Compressor", method_name),
+        }
+    }
+}
+
+impl QObj for QCompressor {
+    fn cls(&self) -> String {
+        "Compressor".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Compressor"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Compressor"
+    }
+
+    fn str(&self) -> String {
+        format!("<Compressor {}>", self.format.name())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Incremental compressor: write() chunks, finish() to end the stream".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+#[derive(Clone)]
+pub struct QDecompressor {
+    format: Format,
+    decoder: Rc<RefCell<Option<Decoder>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QDecompressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QDecompressor").field("format", &self.format).field("id", &self.id).finish()
+    }
+}
+
+impl QDecompressor {
+    fn new(format: Format) -> Self {
+        QDecompressor {
+            format,
+            decoder: Rc::new(RefCell::new(Some(Decoder::new(format)))),
+            id: next_object_id(),
+        }
+    }
+
+    fn with_decoder(&self, op: impl FnOnce(&mut Decoder) -> io::Result<()>) -> Result<QValue, EvalError> {
+        let mut guard = self.decoder.borrow_mut();
+        let Some(decoder) = guard.as_mut() else {
+            return runtime_err!("{} decompressor is already finished", self.format.name());
+        };
+        if let Err(e) = op(decoder) {
+            return value_err!("Invalid {} data: {}", self.format.name(), e);
+        }
+        Ok(bytes(decoder.take_output()))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "write" => {
+                let data = data_arg("write", &args)?;
+                self.with_decoder(|d| {
+                    d.write_all(&data)?;
+                    d.flush()
+                })
+            }
+            "flush" => {
+                no_args("flush", &args)?;
+                self.with_decoder(|d| d.flush())
+            }
+            "finish" => {
+                no_args("finish", &args)?;
+                let Some(decoder) = self.decoder.borrow_mut().take() else {
+                    return runtime_err!("{} decompressor is already finished", self.format.name());
+                };
+                match decoder.finish() {
+                    Ok(out) => Ok(bytes(out)),
+                    Err(e) => value_err!("Invalid {} data: {}", self.format.name(), e),
+                }
+            }
+            "is_finished" => {
+                no_args("is_finished", &args)?;
+                Ok(QValue::Bool(QBool::new(self.decoder.borrow().is_none())))
+            }
+            "format" => {
+                no_args("format", &args)?;
+                Ok(QValue::Str(QString::new(self.format.name().to_string())))
+            }
+            _ => attr_err!("Unknown method '{}' on Decompressor", method_name),
+        }
+    }
+}
+
+impl QObj for QDecompressor {
+    fn cls(&self) -> String {
+        "Decompressor".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Decompressor"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Decompressor"
+    }
+
+    fn str(&self) -> String {
+        format!("<Decompressor {}>", self.format.name())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Incremental decompressor: write() compressed chunks, get decompressed bytes back".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+// ============================================================================
+// Stream adapters
+// ============================================================================
+
+type DecodedReader = BufReader<Box<dyn Read>>;
+
+#[derive(Clone)]
+pub struct QCompressedReader {
+    format: Format,
+    reader: Rc<RefCell<Option<DecodedReader>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCompressedReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCompressedReader").field("format", &self.format).field("id", &self.id).finish()
+    }
+}
+
+impl QCompressedReader {
+    fn new(format: Format, source: Box<dyn Read>) -> Self {
+        QCompressedReader {
+            format,
+            reader: Rc::new(RefCell::new(Some(BufReader::with_capacity(CHUNK_SIZE, decoder_for_read(format, source))))),
+            id: next_object_id(),
+        }
+    }
+
+    fn read_error(&self, e: io::Error) -> EvalError {
+        match e.kind() {
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                format!("ValueErr: Invalid {} data: {}", self.format.name(), e).into()
+            }
+            _ => format!("IOErr: Failed to read {} stream: {}", self.format.name(), e).into(),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "read" => {
+                if args.len() > 1 {
+                    return arg_err!("read expects 0 or 1 arguments (size?), got {}", args.len());
+                }
+                let mut guard = self.reader.borrow_mut();
+                let Some(reader) = guard.as_mut() else {
+                    return io_err!("{} reader is closed", self.format.name());
+                };
+                let mut buffer = Vec::new();
+                match args.first() {
+                    Some(n) => {
+                        let n = n.as_num()? as i64;
+                        if n < 0 {
+                            return value_err!("read size must be non-negative");
+                        }
+                        reader.by_ref().take(n as u64).read_to_end(&mut buffer)
+                    }
+                    None => reader.read_to_end(&mut buffer),
+                }
+                .map_err(|e| self.read_error(e))?;
+                Ok(bytes(buffer))
+            }
+            "readline" => {
+                no_args("readline", &args)?;
+                let mut guard = self.reader.borrow_mut();
+                let Some(reader) = guard.as_mut() else {
+                    return io_err!("{} reader is closed", self.format.name());
+                };
+                let mut line = Vec::new();
+                reader.read_until(b'\n', &mut line).map_err(|e| self.read_error(e))?;
+                Ok(QValue::Str(QString::new(String::from_utf8_lossy(&line).to_string())))
+            }
+            "close" => {
+                no_args("close", &args)?;
+                self.reader.borrow_mut().take();
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => {
+                no_args("closed", &args)?;
+                Ok(QValue::Bool(QBool::new(self.reader.borrow().is_none())))
+            }
+            "_enter" => {
+                no_args("_enter", &args)?;
+                Ok(QValue::CompressedReader(self.clone()))
+            }
+            "_exit" => {
+                self.reader.borrow_mut().take();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on CompressedReader", method_name),
+        }
+    }
+}
+
+impl QObj for QCompressedReader {
+    fn cls(&self) -> String {
+        "CompressedReader".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "CompressedReader"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "CompressedReader"
+    }
+
+    fn str(&self) -> String {
+        format!("<CompressedReader {}>", self.format.name())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Reads decompressed data from a compressed file or stream".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+type SinkEncoder = Encoder<Box<dyn Write>>;
+
+#[derive(Clone)]
+pub struct QCompressedWriter {
+    format: Format,
+    encoder: Rc<RefCell<Option<SinkEncoder>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCompressedWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCompressedWriter").field("format", &self.format).field("id", &self.id).finish()
+    }
+}
+
+impl QCompressedWriter {
+    fn new(format: Format, sink: Box<dyn Write>, level: u32) -> Self {
+        QCompressedWriter {
+            format,
+            encoder: Rc::new(RefCell::new(Some(Encoder::new(format, sink, level)))),
+            id: next_object_id(),
+        }
+    }
+
+    /// Write the stream trailer and flush the underlying file or pipe
+    fn close(&self) -> Result<(), EvalError> {
+        if let Some(encoder) = self.encoder.borrow_mut().take() {
+            let mut sink = encoder.finish()
+                .map_err(|e| format!("IOErr: Failed to finish {} stream: {}", self.format.name(), e))?;
+            sink.flush()
+                .map_err(|e| format!("IOErr: Failed to flush {} stream: {}", self.format.name(), e))?;
+        }
+        Ok(())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "write" => {
+                let data = data_arg("write", &args)?;
+                let mut guard = self.encoder.borrow_mut();
+                let Some(encoder) = guard.as_mut() else {
+                    return io_err!("{} writer is closed", self.format.name());
+                };
+                encoder.write_all(&data)
+                    .map_err(|e| format!("IOErr: Failed to write {} stream: {}", self.format.name(), e))?;
+                Ok(QValue::Int(QInt::new(data.len() as i64)))
+            }
+            "flush" => {
+                no_args("flush", &args)?;
+                let mut guard = self.encoder.borrow_mut();
+                let Some(encoder) = guard.as_mut() else {
+                    return io_err!("{} writer is closed", self.format.name());
+                };
+                encoder.flush()
+                    .map_err(|e| format!("IOErr: Failed to flush {} stream: {}", self.format.name(), e))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "close" => {
+                no_args("close", &args)?;
+                self.close()?;
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => {
+                no_args("closed", &args)?;
+                Ok(QValue::Bool(QBool::new(self.encoder.borrow().is_none())))
+            }
+            "_enter" => {
+                no_args("_enter", &args)?;
+                Ok(QValue::CompressedWriter(self.clone()))
+            }
+            "_exit" => {
+                self.close()?;
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on CompressedWriter", method_name),
+        }
+    }
+}
+
+impl QObj for QCompressedWriter {
+    fn cls(&self) -> String {
+        "CompressedWriter".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "CompressedWriter"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "CompressedWriter"
+    }
+
+    fn str(&self) -> String {
+        format!("<CompressedWriter {}>", self.format.name())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Compresses data written to it into a file or stream".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
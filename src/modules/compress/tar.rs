@@ -0,0 +1,770 @@
+// TAR archive module (.tar, .tar.gz, .tar.bz2)
+// Entries are streamed: neither the archive nor individual entries need to fit in memory.
+use crate::control_flow::EvalError;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use crate::types::*;
+use crate::{arg_err, attr_err, io_err, runtime_err, type_err, value_err};
+
+const BLOCK_SIZE: u64 = 512;
+
+/// Create the tar module
+pub fn create_tar_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("open".to_string(), create_fn("tar", "open"));
+    members.insert("create".to_string(), create_fn("tar", "create"));
+    members.insert("list".to_string(), create_fn("tar", "list"));
+    members.insert("extract".to_string(), create_fn("tar", "extract"));
+
+    QValue::Module(Box::new(QModule::new("tar".to_string(), members)))
+}
+
+/// Handle tar.* function calls
+pub fn call_tar_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "tar.open" => {
+            if args.len() != 1 {
+                return arg_err!("tar.open expects 1 argument (path or bytes), got {}", args.len());
+            }
+            Ok(QValue::TarReader(QTarReader::new(TarStream::open(&args[0])?)))
+        }
+
+        "tar.create" => {
+            // tar.create(path?, options?) - without a path the archive is built in memory
+            let (path, options) = match args.as_slice() {
+                [] => (None, None),
+                [QValue::Dict(options)] => (None, Some(options)),
+                [path] => (Some(path.as_str()), None),
+                [path, QValue::Dict(options)] => (Some(path.as_str()), Some(options)),
+                [_, other] => return type_err!("tar.create options must be Dict, got {}", other.as_obj().cls()),
+                _ => return arg_err!("tar.create expects 0-2 arguments (path?, options?), got {}", args.len()),
+            };
+
+            let compression = match options.and_then(|o| o.get("compression")) {
+                Some(value) => Codec::parse(&value.as_str())?,
+                None => path.as_deref().map(Codec::from_extension).unwrap_or(Codec::None),
+            };
+            let level = match options.and_then(|o| o.get("level")) {
+                Some(value) => {
+                    let level = value.as_num()? as i64;
+                    if !(0..=9).contains(&level) {
+                        return value_err!("Compression level must be between 0 and 9");
+                    }
+                    level as u32
+                }
+                None => 6,
+            };
+
+            let sink = match &path {
+                Some(path) => Sink::File(BufWriter::new(File::create(path)
+                    .map_err(|e| format!("IOErr: Failed to create '{}': {}", path, e))?)),
+                None => Sink::Memory(Vec::new()),
+            };
+            Ok(QValue::TarWriter(QTarWriter::new(TarOutput::new(sink, compression, level))))
+        }
+
+        "tar.list" => {
+            if args.len() != 1 {
+                return arg_err!("tar.list expects 1 argument (path or bytes), got {}", args.len());
+            }
+            let mut stream = TarStream::open(&args[0])?;
+            let mut entries = Vec::new();
+            while let Some(entry) = stream.next_entry()? {
+                entries.push(entry.to_dict());
+            }
+            Ok(QValue::Array(QArray::new(entries)))
+        }
+
+        "tar.extract" => {
+            if args.len() != 2 {
+                return arg_err!("tar.extract expects 2 arguments (path or bytes, dest_dir), got {}", args.len());
+            }
+            let mut stream = TarStream::open(&args[0])?;
+            let dest = PathBuf::from(args[1].as_str());
+            std::fs::create_dir_all(&dest)
+                .map_err(|e| format!("IOErr: Failed to create '{}': {}", dest.display(), e))?;
+
+            let mut extracted = Vec::new();
+            while let Some(entry) = stream.next_entry()? {
+                if stream.extract_current(&entry, &dest)?.is_some() {
+                    extracted.push(QValue::Str(QString::new(entry.name)));
+                }
+            }
+            Ok(QValue::Array(QArray::new(extracted)))
+        }
+
+        _ => attr_err!("Unknown tar function: {}", func_name)
+    }
+}
+
+fn read_err(e: io::Error) -> EvalError {
+    format!("IOErr: Failed to read tar archive: {}", e).into()
+}
+
+fn write_err(e: io::Error) -> EvalError {
+    format!("IOErr: Failed to write tar archive: {}", e).into()
+}
+
+/// Outer compression of the archive
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Codec {
+    None,
+    Gzip,
+    Bzip2,
+}
+
+impl Codec {
+    fn parse(name: &str) -> Result<Self, EvalError> {
+        match name {
+            "none" => Ok(Codec::None),
+            "gzip" | "gz" => Ok(Codec::Gzip),
+            "bzip2" | "bz2" => Ok(Codec::Bzip2),
+            _ => value_err!("Unknown compression '{}' (expected none, gzip, bzip2)", name),
+        }
+    }
+
+    fn from_extension(path: &str) -> Self {
+        let path = path.to_lowercase();
+        if path.ends_with(".gz") || path.ends_with(".tgz") {
+            Codec::Gzip
+        } else if path.ends_with(".bz2") || path.ends_with(".tbz2") || path.ends_with(".tbz") {
+            Codec::Bzip2
+        } else {
+            Codec::None
+        }
+    }
+
+    fn from_magic(head: &[u8]) -> Self {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Codec::Gzip
+        } else if head.starts_with(b"BZh") {
+            Codec::Bzip2
+        } else {
+            Codec::None
+        }
+    }
+}
+
+// ============================================================================
+// Reading
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    Hardlink,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct TarEntry {
+    name: String,
+    kind: EntryKind,
+    size: u64,
+    mode: u32,
+    mtime: u64,
+    link: Option<String>,
+}
+
+impl TarEntry {
+    fn to_dict(&self) -> QValue {
+        let mut map = IndexMap::new();
+        let kind = match self.kind {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Hardlink => "hardlink",
+            EntryKind::Other => "other",
+        };
+        map.insert("name".to_string(), QValue::Str(QString::new(self.name.clone())));
+        map.insert("type".to_string(), QValue::Str(QString::new(kind.to_string())));
+        map.insert("size".to_string(), QValue::Int(QInt::new(self.size as i64)));
+        map.insert("mode".to_string(), QValue::Int(QInt::new(self.mode as i64)));
+        map.insert("mtime".to_string(), QValue::Int(QInt::new(self.mtime as i64)));
+        map.insert("link".to_string(), match &self.link {
+            Some(link) => QValue::Str(QString::new(link.clone())),
+            None => QValue::Nil(QNil),
+        });
+        QValue::Dict(Box::new(QDict::new(map)))
+    }
+}
+
+/// Sequential reader over tar headers and entry data.
+/// Handles ustar, GNU long names, and pax extended headers.
+struct TarStream {
+    source: Box<dyn Read>,
+    /// Unread data bytes of the current entry
+    remaining: u64,
+    /// Padding after the current entry's data
+    padding: u64,
+    done: bool,
+}
+
+fn padding_for(size: u64) -> u64 {
+    (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE
+}
+
+impl TarStream {
+    /// Open a path or in-memory Bytes, detecting gzip/bzip2 from the leading magic bytes
+    fn open(source: &QValue) -> Result<Self, EvalError> {
+        let raw: Box<dyn Read> = match source {
+            QValue::Bytes(b) => Box::new(Cursor::new(b.data.clone())),
+            other => {
+                let path = other.as_str();
+                Box::new(File::open(&path).map_err(|e| format!("IOErr: Failed to open '{}': {}", path, e))?)
+            }
+        };
+
+        let mut reader = BufReader::new(raw);
+        let codec = Codec::from_magic(reader.fill_buf().map_err(read_err)?);
+        let source: Box<dyn Read> = match codec {
+            Codec::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Codec::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Codec::None => Box::new(reader),
+        };
+        Ok(TarStream { source, remaining: 0, padding: 0, done: false })
+    }
+
+    /// Discard whatever is left of the current entry
+    fn skip_current(&mut self) -> Result<(), EvalError> {
+        let skip = self.remaining + self.padding;
+        if skip > 0 {
+            let copied = io::copy(&mut (&mut self.source).take(skip), &mut io::sink()).map_err(read_err)?;
+            if copied < skip {
+                return value_err!("Truncated tar archive");
+            }
+        }
+        self.remaining = 0;
+        self.padding = 0;
+        Ok(())
+    }
+
+    /// Read a whole block; Ok(false) on a clean end of input
+    fn read_block(&mut self, block: &mut [u8; BLOCK_SIZE as usize]) -> Result<bool, EvalError> {
+        let mut filled = 0;
+        while filled < block.len() {
+            match self.source.read(&mut block[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return value_err!("Truncated tar archive"),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(read_err(e)),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Read the data of a metadata entry (long name, pax header) including its padding
+    fn read_meta_data(&mut self, size: u64) -> Result<Vec<u8>, EvalError> {
+        if size > 1024 * 1024 {
+            return value_err!("Tar extended header is too large ({} bytes)", size);
+        }
+        let mut data = vec![0u8; size as usize];
+        self.source.read_exact(&mut data).map_err(read_err)?;
+        self.remaining = 0;
+        self.padding = padding_for(size);
+        self.skip_current()?;
+        Ok(data)
+    }
+
+    fn next_entry(&mut self) -> Result<Option<TarEntry>, EvalError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.skip_current()?;
+
+        let mut long_name: Option<String> = None;
+        let mut long_link: Option<String> = None;
+        let mut pax: HashMap<String, String> = HashMap::new();
+
+        loop {
+            let mut header = tar::Header::new_old();
+            if !self.read_block(header.as_mut_bytes())? || header.as_bytes().iter().all(|&b| b == 0) {
+                self.done = true;
+                return Ok(None);
+            }
+
+            let expected = header.cksum().map_err(|_| "ValueErr: Invalid tar header".to_string())?;
+            let actual: u32 = header.as_bytes().iter().enumerate()
+                .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u32 } else { b as u32 })
+                .sum();
+            if expected != actual {
+                return value_err!("Invalid tar header checksum");
+            }
+
+            let size = header.entry_size().map_err(|e| format!("ValueErr: Invalid tar header: {}", e))?;
+            let entry_type = header.entry_type();
+            if entry_type.is_gnu_longname() {
+                long_name = Some(nul_terminated(&self.read_meta_data(size)?));
+                continue;
+            }
+            if entry_type.is_gnu_longlink() {
+                long_link = Some(nul_terminated(&self.read_meta_data(size)?));
+                continue;
+            }
+            if entry_type.is_pax_local_extensions() {
+                pax = parse_pax(&self.read_meta_data(size)?);
+                continue;
+            }
+            if entry_type.is_pax_global_extensions() {
+                self.read_meta_data(size)?;
+                continue;
+            }
+
+            let size = match pax.get("size") {
+                Some(s) => s.parse().map_err(|_| "ValueErr: Invalid pax size".to_string())?,
+                None => size,
+            };
+            let name = pax.remove("path").or(long_name)
+                .unwrap_or_else(|| String::from_utf8_lossy(&header.path_bytes()).to_string());
+            let link = pax.remove("linkpath").or(long_link)
+                .or_else(|| header.link_name_bytes().map(|l| String::from_utf8_lossy(&l).to_string()));
+            let kind = if entry_type.is_file() || entry_type.is_contiguous() {
+                EntryKind::File
+            } else if entry_type.is_dir() {
+                EntryKind::Dir
+            } else if entry_type.is_symlink() {
+                EntryKind::Symlink
+            } else if entry_type.is_hard_link() {
+                EntryKind::Hardlink
+            } else {
+                EntryKind::Other
+            };
+
+            self.remaining = size;
+            self.padding = padding_for(size);
+            return Ok(Some(TarEntry {
+                name,
+                kind,
+                size,
+                mode: header.mode().unwrap_or(0o644),
+                mtime: pax.get("mtime")
+                    .and_then(|m| m.split('.').next().and_then(|s| s.parse().ok()))
+                    .unwrap_or_else(|| header.mtime().unwrap_or(0)),
+                link,
+            }));
+        }
+    }
+
+    /// Read up to `max` bytes of the current entry's data
+    fn read_data(&mut self, max: u64) -> Result<Vec<u8>, EvalError> {
+        let len = max.min(self.remaining) as usize;
+        let mut data = vec![0u8; len];
+        self.source.read_exact(&mut data).map_err(read_err)?;
+        self.remaining -= len as u64;
+        Ok(data)
+    }
+
+    /// Write the current entry below `dest`. Returns the written path, or None for
+    /// entries that are not extracted (links and special files).
+    fn extract_current(&mut self, entry: &TarEntry, dest: &Path) -> Result<Option<PathBuf>, EvalError> {
+        let target = dest.join(safe_relative_path(&entry.name)?);
+        match entry.kind {
+            EntryKind::Dir => {
+                std::fs::create_dir_all(&target)
+                    .map_err(|e| format!("IOErr: Failed to create '{}': {}", target.display(), e))?;
+            }
+            EntryKind::File => {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|e| format!("IOErr: Failed to create '{}': {}", parent.display(), e))?;
+                }
+                let mut file = BufWriter::new(File::create(&target)
+                    .map_err(|e| format!("IOErr: Failed to create '{}': {}", target.display(), e))?);
+                let expected = self.remaining;
+                let copied = io::copy(&mut (&mut self.source).take(expected), &mut file).map_err(read_err)?;
+                self.remaining -= copied;
+                if copied < expected {
+                    return value_err!("Truncated tar archive");
+                }
+                file.flush().map_err(|e| format!("IOErr: Failed to write '{}': {}", target.display(), e))?;
+                set_mode(&target, entry.mode);
+            }
+            _ => return Ok(None),
+        }
+        Ok(Some(target))
+    }
+}
+
+fn nul_terminated(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).to_string()
+}
+
+/// Parse pax records of the form "<len> <key>=<value>\n"
+fn parse_pax(data: &[u8]) -> HashMap<String, String> {
+    let mut records = HashMap::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let Some(space) = rest.iter().position(|&b| b == b' ') else { break };
+        let Some(len) = std::str::from_utf8(&rest[..space]).ok().and_then(|s| s.parse::<usize>().ok()) else { break };
+        if len <= space || len > rest.len() {
+            break;
+        }
+        let record = String::from_utf8_lossy(&rest[space + 1..len]).trim_end_matches('\n').to_string();
+        if let Some((key, value)) = record.split_once('=') {
+            records.insert(key.to_string(), value.to_string());
+        }
+        rest = &rest[len..];
+    }
+    records
+}
+
+/// Reject entry names that would escape the destination directory
+fn safe_relative_path(name: &str) -> Result<PathBuf, EvalError> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return value_err!("Refusing to extract unsafe path '{}'", name),
+        }
+    }
+    Ok(path)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o777));
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) {}
+
+#[derive(Clone)]
+pub struct QTarReader {
+    stream: Rc<RefCell<TarStream>>,
+    current: Rc<RefCell<Option<TarEntry>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QTarReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QTarReader").field("id", &self.id).finish()
+    }
+}
+
+impl QTarReader {
+    fn new(stream: TarStream) -> Self {
+        QTarReader {
+            stream: Rc::new(RefCell::new(stream)),
+            current: Rc::new(RefCell::new(None)),
+            id: next_object_id(),
+        }
+    }
+
+    fn current_entry(&self) -> Result<TarEntry, EvalError> {
+        match self.current.borrow().as_ref() {
+            Some(entry) => Ok(entry.clone()),
+            None => runtime_err!("No current entry, call next() first"),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "next" => {
+                if !args.is_empty() {
+                    return arg_err!("next expects 0 arguments, got {}", args.len());
+                }
+                let entry = self.stream.borrow_mut().next_entry()?;
+                let result = entry.as_ref().map(|e| e.to_dict()).unwrap_or(QValue::Nil(QNil));
+                *self.current.borrow_mut() = entry;
+                Ok(result)
+            }
+            "read" => {
+                if args.len() > 1 {
+                    return arg_err!("read expects 0-1 arguments ([size]), got {}", args.len());
+                }
+                self.current_entry()?;
+                let max = match args.first() {
+                    Some(n) => {
+                        let n = n.as_num()? as i64;
+                        if n < 0 {
+                            return value_err!("read size must be non-negative");
+                        }
+                        n as u64
+                    }
+                    None => u64::MAX,
+                };
+                Ok(QValue::Bytes(QBytes::new(self.stream.borrow_mut().read_data(max)?)))
+            }
+            "extract_to" => {
+                if args.len() != 1 {
+                    return arg_err!("extract_to expects 1 argument (dest_dir), got {}", args.len());
+                }
+                let entry = self.current_entry()?;
+                let dest = PathBuf::from(args[0].as_str());
+                match self.stream.borrow_mut().extract_current(&entry, &dest)? {
+                    Some(path) => Ok(QValue::Str(QString::new(path.to_string_lossy().to_string()))),
+                    None => Ok(QValue::Nil(QNil)),
+                }
+            }
+            _ => attr_err!("Unknown method '{}' on TarReader", method_name),
+        }
+    }
+}
+
+impl QObj for QTarReader {
+    fn cls(&self) -> String {
+        "TarReader".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "TarReader"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "TarReader"
+    }
+
+    fn str(&self) -> String {
+        "<TarReader>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Streaming TAR archive reader".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+// ============================================================================
+// Writing
+// ============================================================================
+
+enum Sink {
+    File(BufWriter<File>),
+    Memory(Vec<u8>),
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::File(f) => f.write(buf),
+            Sink::Memory(v) => v.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::File(f) => f.flush(),
+            Sink::Memory(v) => v.flush(),
+        }
+    }
+}
+
+enum TarOutput {
+    Plain(Sink),
+    Gzip(flate2::write::GzEncoder<Sink>),
+    Bzip2(bzip2::write::BzEncoder<Sink>),
+}
+
+impl TarOutput {
+    fn new(sink: Sink, codec: Codec, level: u32) -> Self {
+        match codec {
+            Codec::None => TarOutput::Plain(sink),
+            Codec::Gzip => TarOutput::Gzip(flate2::write::GzEncoder::new(sink, flate2::Compression::new(level))),
+            Codec::Bzip2 => TarOutput::Bzip2(bzip2::write::BzEncoder::new(sink, bzip2::Compression::new(level.max(1)))),
+        }
+    }
+
+    fn finish(self) -> io::Result<Sink> {
+        let mut sink = match self {
+            TarOutput::Plain(sink) => sink,
+            TarOutput::Gzip(encoder) => encoder.finish()?,
+            TarOutput::Bzip2(encoder) => encoder.finish()?,
+        };
+        sink.flush()?;
+        Ok(sink)
+    }
+}
+
+impl Write for TarOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TarOutput::Plain(w) => w.write(buf),
+            TarOutput::Gzip(w) => w.write(buf),
+            TarOutput::Bzip2(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TarOutput::Plain(w) => w.flush(),
+            TarOutput::Gzip(w) => w.flush(),
+            TarOutput::Bzip2(w) => w.flush(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct QTarWriter {
+    builder: Rc<RefCell<Option<tar::Builder<TarOutput>>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QTarWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QTarWriter").field("id", &self.id).finish()
+    }
+}
+
+impl QTarWriter {
+    fn new(output: TarOutput) -> Self {
+        let mut builder = tar::Builder::new(output);
+        builder.follow_symlinks(false);
+        QTarWriter { builder: Rc::new(RefCell::new(Some(builder))), id: next_object_id() }
+    }
+
+    fn with_builder<T>(&self, f: impl FnOnce(&mut tar::Builder<TarOutput>) -> Result<T, EvalError>) -> Result<T, EvalError> {
+        match self.builder.borrow_mut().as_mut() {
+            Some(builder) => f(builder),
+            None => runtime_err!("TarWriter is closed"),
+        }
+    }
+
+    /// Archive name for a source path: the explicit name, or the path's file name
+    fn archive_name(args: &[QValue], src: &str) -> Result<String, EvalError> {
+        let name = match args.get(1) {
+            Some(name) => name.as_str(),
+            None => match Path::new(src).file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => return value_err!("Cannot derive an archive name from '{}'", src),
+            },
+        };
+        safe_relative_path(&name)?;
+        Ok(name)
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "add_file" => {
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("add_file expects 1-2 arguments (src_path, [name]), got {}", args.len());
+                }
+                let src = args[0].as_str();
+                let name = Self::archive_name(&args, &src)?;
+                if !Path::new(&src).is_file() {
+                    return io_err!("Not a file: {}", src);
+                }
+                self.with_builder(|b| b.append_path_with_name(&src, &name).map_err(write_err))?;
+                Ok(QValue::TarWriter(self.clone()))
+            }
+            "add_dir" => {
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("add_dir expects 1-2 arguments (src_dir, [name]), got {}", args.len());
+                }
+                let src = args[0].as_str();
+                let name = Self::archive_name(&args, &src)?;
+                if !Path::new(&src).is_dir() {
+                    return io_err!("Not a directory: {}", src);
+                }
+                self.with_builder(|b| b.append_dir_all(&name, &src).map_err(write_err))?;
+                Ok(QValue::TarWriter(self.clone()))
+            }
+            "add_bytes" => {
+                if args.len() < 2 || args.len() > 3 {
+                    return arg_err!("add_bytes expects 2-3 arguments (name, data, [options]), got {}", args.len());
+                }
+                let name = args[0].as_str();
+                safe_relative_path(&name)?;
+                let data = match &args[1] {
+                    QValue::Bytes(b) => b.data.clone(),
+                    QValue::Str(s) => s.value.as_bytes().to_vec(),
+                    other => return type_err!("add_bytes expects Str or Bytes, got {}", other.as_obj().cls()),
+                };
+                let (mode, mtime) = match args.get(2) {
+                    Some(QValue::Dict(options)) => (
+                        match options.get("mode") { Some(v) => v.as_num()? as u32, None => 0o644 },
+                        match options.get("mtime") { Some(v) => v.as_num()? as u64, None => now_secs() },
+                    ),
+                    Some(other) => return type_err!("add_bytes options must be Dict, got {}", other.as_obj().cls()),
+                    None => (0o644, now_secs()),
+                };
+
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(mode);
+                header.set_mtime(mtime);
+                header.set_entry_type(tar::EntryType::Regular);
+                self.with_builder(|b| b.append_data(&mut header, &name, data.as_slice()).map_err(write_err))?;
+                Ok(QValue::TarWriter(self.clone()))
+            }
+            "close" => {
+                if !args.is_empty() {
+                    return arg_err!("close expects 0 arguments, got {}", args.len());
+                }
+                let builder = match self.builder.borrow_mut().take() {
+                    Some(builder) => builder,
+                    None => return Ok(QValue::Nil(QNil)),
+                };
+                let sink = builder.into_inner().and_then(TarOutput::finish).map_err(write_err)?;
+                match sink {
+                    Sink::Memory(data) => Ok(QValue::Bytes(QBytes::new(data))),
+                    Sink::File(_) => Ok(QValue::Nil(QNil)),
+                }
+            }
+            _ => attr_err!("Unknown method '{}' on TarWriter", method_name),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl QObj for QTarWriter {
+    fn cls(&self) -> String {
+        "TarWriter".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "TarWriter"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "TarWriter"
+    }
+
+    fn str(&self) -> String {
+        "<TarWriter>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "TAR archive writer".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
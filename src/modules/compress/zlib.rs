@@ -3,6 +3,7 @@ use crate::control_flow::EvalError;
 use std::collections::HashMap;
 use crate::types::*;
 use crate::{arg_err, attr_err};
+use super::stream::{call_stream_function, Format};
 use flate2::Compression;
 use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
@@ -15,6 +16,12 @@ pub fn create_zlib_module() -> QValue {
     members.insert("compress".to_string(), create_fn("zlib", "compress"));
     members.insert("decompress".to_string(), create_fn("zlib", "decompress"));
 
+    // Streaming API
+    members.insert("compressor".to_string(), create_fn("zlib", "compressor"));
+    members.insert("decompressor".to_string(), create_fn("zlib", "decompressor"));
+    members.insert("reader".to_string(), create_fn("zlib", "reader"));
+    members.insert("writer".to_string(), create_fn("zlib", "writer"));
+
     QValue::Module(Box::new(QModule::new("zlib".to_string(), members)))
 }
 
@@ -86,6 +93,10 @@ pub fn call_zlib_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
             Ok(QValue::Bytes(QBytes::new(result)))
         }
 
+        "zlib.compressor" | "zlib.decompressor" | "zlib.reader" | "zlib.writer" => {
+            call_stream_function(Format::Zlib, &func_name["zlib.".len()..], &args)
+        }
+
         _ => attr_err!("Unknown zlib function: {}", func_name)
     }
 }
@@ -0,0 +1,352 @@
+// JSON Web Tokens (RFC 7519): HS256/HS384/HS512, RS256 and EdDSA
+use crate::control_flow::EvalError;
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use sha2::{Sha256, Sha384, Sha512};
+use crate::types::*;
+use crate::encoding::json_utils::{json_to_qvalue, qvalue_to_json};
+use crate::{arg_err, attr_err, type_err, value_err};
+use super::keys::{KeyMaterial, QCryptoKey};
+
+pub fn create_jwt_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("sign".to_string(), create_fn("jwt", "sign"));
+    members.insert("verify".to_string(), create_fn("jwt", "verify"));
+    members.insert("decode".to_string(), create_fn("jwt", "decode"));
+    members.insert("header".to_string(), create_fn("jwt", "header"));
+
+    QValue::Module(Box::new(QModule::new("jwt".to_string(), members)))
+}
+
+/// Handle jwt.* function calls
+pub fn call_jwt_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "jwt.sign" => {
+            // jwt.sign(claims, key, alg: "HS256", header: {...}, expires_in: seconds)
+            if args.len() < 2 || args.len() > 3 {
+                return arg_err!("jwt.sign expects 2 or 3 arguments (claims, key, options?), got {}", args.len());
+            }
+            let mut claims = match qvalue_to_json(&args[0])? {
+                serde_json::Value::Object(map) => map,
+                _ => return type_err!("jwt.sign claims must be Dict, got {}", args[0].as_obj().cls()),
+            };
+            let key = SigningKey::from_value(&args[1])?;
+            let options = options_arg("jwt.sign", args.get(2))?;
+
+            let alg = match options.and_then(|o| o.get("alg")) {
+                Some(alg) => Algorithm::parse(&alg.as_str())?,
+                None => key.default_algorithm(),
+            };
+
+            let mut header = serde_json::Map::new();
+            if let Some(extra) = options.and_then(|o| o.get("header")) {
+                match qvalue_to_json(&extra)? {
+                    serde_json::Value::Object(extra) => header.extend(extra),
+                    _ => return type_err!("jwt.sign header must be Dict"),
+                }
+            }
+            header.insert("alg".to_string(), alg.name().into());
+            header.entry("typ").or_insert_with(|| "JWT".into());
+
+            if let Some(expires_in) = options.and_then(|o| o.get("expires_in")) {
+                let now = now_seconds();
+                claims.insert("iat".to_string(), now.into());
+                claims.insert("exp".to_string(), (now + expires_in.as_num()? as i64).into());
+            }
+
+            let signing_input = format!("{}.{}", encode_segment(&header.into())?, encode_segment(&claims.into())?);
+            let signature = key.sign(alg, signing_input.as_bytes())?;
+            Ok(QValue::Str(QString::new(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature)))))
+        }
+
+        "jwt.verify" => {
+            // jwt.verify(token, key, audience:, issuer:, algorithms: [...], leeway: seconds)
+            if args.len() < 2 || args.len() > 3 {
+                return arg_err!("jwt.verify expects 2 or 3 arguments (token, key, options?), got {}", args.len());
+            }
+            let token = Token::parse(&args[0])?;
+            let key = SigningKey::from_value(&args[1])?;
+            let options = options_arg("jwt.verify", args.get(2))?;
+
+            let alg = match token.header.get("alg").and_then(|a| a.as_str()) {
+                Some(alg) => Algorithm::parse(alg)?,
+                None => return value_err!("Invalid JWT: header has no 'alg'"),
+            };
+            if let Some(allowed) = options.and_then(|o| o.get("algorithms")) {
+                let allowed = match allowed {
+                    QValue::Array(arr) => arr.elements.borrow().iter().map(|a| a.as_str()).collect::<Vec<_>>(),
+                    other => vec![other.as_str()],
+                };
+                if !allowed.iter().any(|a| a == alg.name()) {
+                    return value_err!("JWT algorithm {} is not allowed", alg.name());
+                }
+            }
+            if !key.verify(alg, token.signing_input.as_bytes(), &token.signature)? {
+                return value_err!("JWT signature verification failed");
+            }
+
+            validate_claims(&token.claims, options)?;
+            json_to_qvalue(serde_json::Value::Object(token.claims))
+        }
+
+        "jwt.decode" => {
+            // Inspect a token without verifying it
+            if args.len() != 1 {
+                return arg_err!("jwt.decode expects 1 argument (token), got {}", args.len());
+            }
+            let token = Token::parse(&args[0])?;
+            let mut result = IndexMap::new();
+            result.insert("header".to_string(), json_to_qvalue(serde_json::Value::Object(token.header))?);
+            result.insert("claims".to_string(), json_to_qvalue(serde_json::Value::Object(token.claims))?);
+            result.insert("signature".to_string(), QValue::Bytes(QBytes::new(token.signature)));
+            Ok(QValue::Dict(Box::new(QDict::new(result))))
+        }
+
+        "jwt.header" => {
+            if args.len() != 1 {
+                return arg_err!("jwt.header expects 1 argument (token), got {}", args.len());
+            }
+            let token = Token::parse(&args[0])?;
+            json_to_qvalue(serde_json::Value::Object(token.header))
+        }
+
+        _ => attr_err!("Unknown jwt function: {}", func_name)
+    }
+}
+
+fn options_arg<'a>(func: &str, value: Option<&'a QValue>) -> Result<Option<&'a QDict>, EvalError> {
+    match value {
+        Some(QValue::Dict(options)) => Ok(Some(options)),
+        Some(QValue::Nil(_)) | None => Ok(None),
+        Some(other) => type_err!("{} options must be Dict, got {}", func, other.as_obj().cls()),
+    }
+}
+
+fn now_seconds() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn encode_segment(value: &serde_json::Value) -> Result<String, EvalError> {
+    let json = serde_json::to_vec(value).map_err(|e| format!("JWT encoding error: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(json))
+}
+
+/// Check registered claims: exp, nbf, and optionally aud / iss
+fn validate_claims(claims: &serde_json::Map<String, serde_json::Value>, options: Option<&QDict>) -> Result<(), EvalError> {
+    let leeway = match options.and_then(|o| o.get("leeway")) {
+        Some(leeway) => leeway.as_num()?,
+        None => 0.0,
+    };
+    let now = now_seconds() as f64;
+    let time_claim = |name: &str| -> Result<Option<f64>, EvalError> {
+        match claims.get(name) {
+            None => Ok(None),
+            Some(value) => match value.as_f64() {
+                Some(t) => Ok(Some(t)),
+                None => value_err!("Invalid JWT: '{}' claim must be a number", name),
+            },
+        }
+    };
+
+    if let Some(exp) = time_claim("exp")? {
+        if now >= exp + leeway {
+            return value_err!("JWT has expired");
+        }
+    }
+    if let Some(nbf) = time_claim("nbf")? {
+        if now + leeway < nbf {
+            return value_err!("JWT is not valid yet");
+        }
+    }
+
+    if let Some(expected) = options.and_then(|o| o.get("issuer")) {
+        let expected = expected.as_str();
+        if claims.get("iss").and_then(|i| i.as_str()) != Some(expected.as_str()) {
+            return value_err!("JWT issuer does not match '{}'", expected);
+        }
+    }
+    if let Some(expected) = options.and_then(|o| o.get("audience")) {
+        let expected = expected.as_str();
+        let matches = match claims.get("aud") {
+            Some(serde_json::Value::String(aud)) => *aud == expected,
+            Some(serde_json::Value::Array(auds)) => auds.iter().any(|a| a.as_str() == Some(expected.as_str())),
+            _ => false,
+        };
+        if !matches {
+            return value_err!("JWT audience does not include '{}'", expected);
+        }
+    }
+    Ok(())
+}
+
+struct Token {
+    header: serde_json::Map<String, serde_json::Value>,
+    claims: serde_json::Map<String, serde_json::Value>,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+impl Token {
+    fn parse(value: &QValue) -> Result<Self, EvalError> {
+        let token = match value {
+            QValue::Str(s) => s.value.trim().to_string(),
+            other => return type_err!("JWT must be Str, got {}", other.as_obj().cls()),
+        };
+        let parts: Vec<&str> = token.split('.').collect();
+        if parts.len() != 3 {
+            return value_err!("Invalid JWT: expected 3 segments, got {}", parts.len());
+        }
+        let signature = URL_SAFE_NO_PAD.decode(parts[2])
+            .map_err(|e| format!("ValueErr: Invalid JWT signature encoding: {}", e))?;
+        Ok(Token {
+            header: Self::decode_segment("header", parts[0])?,
+            claims: Self::decode_segment("claims", parts[1])?,
+            signing_input: format!("{}.{}", parts[0], parts[1]),
+            signature,
+        })
+    }
+
+    fn decode_segment(name: &str, segment: &str) -> Result<serde_json::Map<String, serde_json::Value>, EvalError> {
+        let bytes = URL_SAFE_NO_PAD.decode(segment)
+            .map_err(|e| format!("ValueErr: Invalid JWT {} encoding: {}", name, e))?;
+        match serde_json::from_slice(&bytes) {
+            Ok(serde_json::Value::Object(map)) => Ok(map),
+            Ok(_) => value_err!("Invalid JWT: {} must be a JSON object", name),
+            Err(e) => value_err!("Invalid JWT {} JSON: {}", name, e),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Algorithm {
+    HS256,
+    HS384,
+    HS512,
+    RS256,
+    EdDSA,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Result<Self, EvalError> {
+        match name {
+            "HS256" => Ok(Algorithm::HS256),
+            "HS384" => Ok(Algorithm::HS384),
+            "HS512" => Ok(Algorithm::HS512),
+            "RS256" => Ok(Algorithm::RS256),
+            "EdDSA" => Ok(Algorithm::EdDSA),
+            // "none" is deliberately unsupported
+            _ => value_err!("Unsupported JWT algorithm '{}' (expected HS256, HS384, HS512, RS256 or EdDSA)", name),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::HS256 => "HS256",
+            Algorithm::HS384 => "HS384",
+            Algorithm::HS512 => "HS512",
+            Algorithm::RS256 => "RS256",
+            Algorithm::EdDSA => "EdDSA",
+        }
+    }
+}
+
+/// HMAC secrets are Str/Bytes; RS256 and EdDSA use CryptoKey objects from std/crypto
+enum SigningKey {
+    Secret(Vec<u8>),
+    Key(QCryptoKey),
+}
+
+impl SigningKey {
+    fn from_value(value: &QValue) -> Result<Self, EvalError> {
+        match value {
+            QValue::Str(s) => Ok(SigningKey::Secret(s.value.as_bytes().to_vec())),
+            QValue::Bytes(b) => Ok(SigningKey::Secret(b.data.clone())),
+            QValue::CryptoKey(k) => Ok(SigningKey::Key(k.clone())),
+            other => type_err!("JWT key must be a secret (Str or Bytes) or CryptoKey, got {}", other.as_obj().cls()),
+        }
+    }
+
+    fn default_algorithm(&self) -> Algorithm {
+        match self {
+            SigningKey::Key(k) if k.algorithm() == "rsa" => Algorithm::RS256,
+            SigningKey::Key(_) => Algorithm::EdDSA,
+            SigningKey::Secret(_) => Algorithm::HS256,
+        }
+    }
+
+    fn mismatch<T>(&self, alg: Algorithm) -> Result<T, EvalError> {
+        let key = match self {
+            SigningKey::Secret(_) => "a secret".to_string(),
+            SigningKey::Key(k) => format!("an {} key", k.algorithm()),
+        };
+        type_err!("JWT algorithm {} cannot be used with {}", alg.name(), key)
+    }
+
+    fn sign(&self, alg: Algorithm, data: &[u8]) -> Result<Vec<u8>, EvalError> {
+        match (alg, self) {
+            (Algorithm::HS256, SigningKey::Secret(secret)) => Ok(hmac_sign::<Hmac<Sha256>>(secret, data)),
+            (Algorithm::HS384, SigningKey::Secret(secret)) => Ok(hmac_sign::<Hmac<Sha384>>(secret, data)),
+            (Algorithm::HS512, SigningKey::Secret(secret)) => Ok(hmac_sign::<Hmac<Sha512>>(secret, data)),
+            (Algorithm::RS256, SigningKey::Key(key)) => match &*key.key {
+                KeyMaterial::RsaPrivate(k) => {
+                    let signer = rsa::pkcs1v15::SigningKey::<Sha256>::new(k.clone());
+                    Ok(signer.try_sign(data).map_err(|e| format!("RuntimeErr: RS256 signing failed: {}", e))?.to_vec())
+                }
+                KeyMaterial::RsaPublic(_) => value_err!("Cannot sign a JWT with a public key"),
+                _ => self.mismatch(alg),
+            },
+            (Algorithm::EdDSA, SigningKey::Key(key)) => match &*key.key {
+                KeyMaterial::Ed25519Private(k) => Ok(k.sign(data).to_bytes().to_vec()),
+                KeyMaterial::Ed25519Public(_) => value_err!("Cannot sign a JWT with a public key"),
+                _ => self.mismatch(alg),
+            },
+            _ => self.mismatch(alg),
+        }
+    }
+
+    fn verify(&self, alg: Algorithm, data: &[u8], signature: &[u8]) -> Result<bool, EvalError> {
+        match (alg, self) {
+            (Algorithm::HS256, SigningKey::Secret(secret)) => Ok(hmac_verify::<Hmac<Sha256>>(secret, data, signature)),
+            (Algorithm::HS384, SigningKey::Secret(secret)) => Ok(hmac_verify::<Hmac<Sha384>>(secret, data, signature)),
+            (Algorithm::HS512, SigningKey::Secret(secret)) => Ok(hmac_verify::<Hmac<Sha512>>(secret, data, signature)),
+            (Algorithm::RS256, SigningKey::Key(key)) => match key.public_material() {
+                KeyMaterial::RsaPublic(k) => {
+                    let Ok(signature) = rsa::pkcs1v15::Signature::try_from(signature) else {
+                        return Ok(false);
+                    };
+                    Ok(rsa::pkcs1v15::VerifyingKey::<Sha256>::new(k).verify(data, &signature).is_ok())
+                }
+                _ => self.mismatch(alg),
+            },
+            (Algorithm::EdDSA, SigningKey::Key(key)) => match key.public_material() {
+                KeyMaterial::Ed25519Public(k) => {
+                    let Ok(signature) = ed25519_dalek::Signature::from_slice(signature) else {
+                        return Ok(false);
+                    };
+                    Ok(k.verify_strict(data, &signature).is_ok())
+                }
+                _ => self.mismatch(alg),
+            },
+            _ => self.mismatch(alg),
+        }
+    }
+}
+
+fn hmac_sign<M: Mac + hmac::digest::KeyInit>(secret: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hmac_verify<M: Mac + hmac::digest::KeyInit>(secret: &[u8], data: &[u8], signature: &[u8]) -> bool {
+    let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    // Constant-time comparison
+    mac.verify_slice(signature).is_ok()
+}
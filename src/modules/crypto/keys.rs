@@ -0,0 +1,330 @@
+// Public-key signatures: RSA-PSS (SHA-256) and Ed25519
+use crate::control_flow::EvalError;
+use std::rc::Rc;
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+use ed25519_dalek::Signer;
+use rand::rngs::OsRng;
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::signature::{RandomizedSigner, SignatureEncoding, Verifier};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::Sha256;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+const DEFAULT_RSA_BITS: usize = 2048;
+
+pub enum KeyMaterial {
+    RsaPrivate(RsaPrivateKey),
+    RsaPublic(RsaPublicKey),
+    Ed25519Private(ed25519_dalek::SigningKey),
+    Ed25519Public(ed25519_dalek::VerifyingKey),
+}
+
+/// crypto.generate_keypair(algorithm, options?) - "rsa" (options: bits) or "ed25519"
+pub fn generate_keypair(args: &[QValue]) -> Result<QValue, EvalError> {
+    if args.is_empty() || args.len() > 2 {
+        return arg_err!("generate_keypair expects 1 or 2 arguments (algorithm, options?), got {}", args.len());
+    }
+    let algorithm = match &args[0] {
+        QValue::Str(s) => s.value.to_lowercase(),
+        other => return type_err!("generate_keypair algorithm must be Str, got {}", other.as_obj().cls()),
+    };
+    let bits = match args.get(1) {
+        Some(QValue::Dict(options)) => match options.get("bits") {
+            Some(bits) => bits.as_num()? as i64,
+            None => DEFAULT_RSA_BITS as i64,
+        },
+        Some(other) => return type_err!("generate_keypair options must be Dict, got {}", other.as_obj().cls()),
+        None => DEFAULT_RSA_BITS as i64,
+    };
+
+    let material = match algorithm.as_str() {
+        "rsa" => {
+            if !(2048..=8192).contains(&bits) || bits % 8 != 0 {
+                return value_err!("RSA key size must be a multiple of 8 between 2048 and 8192 bits, got {}", bits);
+            }
+            let key = RsaPrivateKey::new(&mut OsRng, bits as usize)
+                .map_err(|e| format!("RuntimeErr: RSA key generation failed: {}", e))?;
+            KeyMaterial::RsaPrivate(key)
+        }
+        "ed25519" => KeyMaterial::Ed25519Private(ed25519_dalek::SigningKey::generate(&mut OsRng)),
+        _ => return value_err!("Unknown key algorithm '{}' (expected rsa or ed25519)", algorithm),
+    };
+    Ok(QValue::CryptoKey(QCryptoKey::new(material)))
+}
+
+/// crypto.load_private_key(pem) - PKCS#8 ("PRIVATE KEY") or PKCS#1 ("RSA PRIVATE KEY")
+pub fn load_private_key(args: &[QValue]) -> Result<QValue, EvalError> {
+    let pem = pem_arg("load_private_key", args)?;
+    let material = if let Ok(key) = ed25519_dalek::SigningKey::from_pkcs8_pem(&pem) {
+        KeyMaterial::Ed25519Private(key)
+    } else if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(&pem) {
+        KeyMaterial::RsaPrivate(key)
+    } else if let Ok(key) = RsaPrivateKey::from_pkcs1_pem(&pem) {
+        KeyMaterial::RsaPrivate(key)
+    } else {
+        return value_err!("Invalid private key: expected a PEM-encoded RSA or Ed25519 private key");
+    };
+    Ok(QValue::CryptoKey(QCryptoKey::new(material)))
+}
+
+/// crypto.load_public_key(pem) - SubjectPublicKeyInfo ("PUBLIC KEY") or PKCS#1 ("RSA PUBLIC KEY").
+/// A raw 32-byte Ed25519 key (as published by many webhook providers) is also accepted as Bytes,
+/// and a JWK (as published in OpenID Connect JWKS documents) as a Dict.
+pub fn load_public_key(args: &[QValue]) -> Result<QValue, EvalError> {
+    if let [QValue::Bytes(raw)] = args {
+        if let Ok(raw) = <[u8; 32]>::try_from(raw.data.as_slice()) {
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&raw)
+                .map_err(|e| format!("ValueErr: Invalid Ed25519 public key: {}", e))?;
+            return Ok(QValue::CryptoKey(QCryptoKey::new(KeyMaterial::Ed25519Public(key))));
+        }
+    }
+    if let [QValue::Dict(jwk)] = args {
+        return Ok(QValue::CryptoKey(QCryptoKey::new(jwk_material(jwk)?)));
+    }
+    let pem = pem_arg("load_public_key", args)?;
+    let material = if let Ok(key) = ed25519_dalek::VerifyingKey::from_public_key_pem(&pem) {
+        KeyMaterial::Ed25519Public(key)
+    } else if let Ok(key) = RsaPublicKey::from_public_key_pem(&pem) {
+        KeyMaterial::RsaPublic(key)
+    } else if let Ok(key) = RsaPublicKey::from_pkcs1_pem(&pem) {
+        KeyMaterial::RsaPublic(key)
+    } else {
+        return value_err!("Invalid public key: expected a PEM-encoded RSA or Ed25519 public key");
+    };
+    Ok(QValue::CryptoKey(QCryptoKey::new(material)))
+}
+
+/// Public key from an RSA or Ed25519 (OKP) JSON Web Key (RFC 7517, RFC 8037)
+fn jwk_material(jwk: &QDict) -> Result<KeyMaterial, EvalError> {
+    let field = |name: &str| -> Result<Vec<u8>, EvalError> {
+        let value = match jwk.get(name) {
+            Some(value) => value.as_str(),
+            None => return value_err!("Invalid JWK: missing '{}'", name),
+        };
+        URL_SAFE_NO_PAD.decode(value.trim_end_matches('='))
+            .map_err(|e| format!("ValueErr: Invalid JWK '{}': {}", name, e).into())
+    };
+    let kty = jwk.get("kty").map(|k| k.as_str()).unwrap_or_default();
+    match kty.as_str() {
+        "RSA" => {
+            let n = rsa::BigUint::from_bytes_be(&field("n")?);
+            let e = rsa::BigUint::from_bytes_be(&field("e")?);
+            let key = RsaPublicKey::new(n, e)
+                .map_err(|e| format!("ValueErr: Invalid RSA JWK: {}", e))?;
+            Ok(KeyMaterial::RsaPublic(key))
+        }
+        "OKP" => {
+            let crv = jwk.get("crv").map(|c| c.as_str()).unwrap_or_default();
+            if crv != "Ed25519" {
+                return value_err!("Unsupported JWK curve '{}' (expected Ed25519)", crv);
+            }
+            let raw = <[u8; 32]>::try_from(field("x")?.as_slice())
+                .map_err(|_| "ValueErr: Invalid Ed25519 JWK: 'x' must be 32 bytes".to_string())?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&raw)
+                .map_err(|e| format!("ValueErr: Invalid Ed25519 public key: {}", e))?;
+            Ok(KeyMaterial::Ed25519Public(key))
+        }
+        _ => value_err!("Unsupported JWK key type '{}' (expected RSA or OKP)", kty),
+    }
+}
+
+fn pem_arg(func: &str, args: &[QValue]) -> Result<String, EvalError> {
+    if args.len() != 1 {
+        return arg_err!("{} expects 1 argument (pem), got {}", func, args.len());
+    }
+    match &args[0] {
+        QValue::Str(s) => Ok(s.value.trim().to_string()),
+        QValue::Bytes(b) => Ok(String::from_utf8_lossy(&b.data).trim().to_string()),
+        other => type_err!("{} expects PEM Str, got {}", func, other.as_obj().cls()),
+    }
+}
+
+fn data_arg(method: &str, what: &str, value: &QValue) -> Result<Vec<u8>, EvalError> {
+    match value {
+        QValue::Bytes(b) => Ok(b.data.clone()),
+        QValue::Str(s) => Ok(s.value.as_bytes().to_vec()),
+        other => type_err!("{} {} must be Str or Bytes, got {}", method, what, other.as_obj().cls()),
+    }
+}
+
+#[derive(Clone)]
+pub struct QCryptoKey {
+    pub key: Rc<KeyMaterial>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCryptoKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCryptoKey").field("id", &self.id).finish()
+    }
+}
+
+impl QCryptoKey {
+    pub fn new(key: KeyMaterial) -> Self {
+        QCryptoKey {
+            key: Rc::new(key),
+            id: next_object_id(),
+        }
+    }
+
+    pub fn algorithm(&self) -> &'static str {
+        match *self.key {
+            KeyMaterial::RsaPrivate(_) | KeyMaterial::RsaPublic(_) => "rsa",
+            KeyMaterial::Ed25519Private(_) | KeyMaterial::Ed25519Public(_) => "ed25519",
+        }
+    }
+
+    pub fn is_private(&self) -> bool {
+        matches!(*self.key, KeyMaterial::RsaPrivate(_) | KeyMaterial::Ed25519Private(_))
+    }
+
+    pub(crate) fn public_material(&self) -> KeyMaterial {
+        match &*self.key {
+            KeyMaterial::RsaPrivate(k) => KeyMaterial::RsaPublic(k.to_public_key()),
+            KeyMaterial::RsaPublic(k) => KeyMaterial::RsaPublic(k.clone()),
+            KeyMaterial::Ed25519Private(k) => KeyMaterial::Ed25519Public(k.verifying_key()),
+            KeyMaterial::Ed25519Public(k) => KeyMaterial::Ed25519Public(*k),
+        }
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, EvalError> {
+        match &*self.key {
+            KeyMaterial::RsaPrivate(k) => {
+                let signer = rsa::pss::BlindedSigningKey::<Sha256>::new(k.clone());
+                let signature = signer.try_sign_with_rng(&mut OsRng, data)
+                    .map_err(|e| format!("RuntimeErr: RSA-PSS signing failed: {}", e))?;
+                Ok(signature.to_vec())
+            }
+            KeyMaterial::Ed25519Private(k) => Ok(k.sign(data).to_bytes().to_vec()),
+            _ => value_err!("Cannot sign with a public key"),
+        }
+    }
+
+    fn verify(&self, signature: &[u8], data: &[u8]) -> bool {
+        match self.public_material() {
+            KeyMaterial::RsaPublic(k) => {
+                let Ok(signature) = rsa::pss::Signature::try_from(signature) else {
+                    return false;
+                };
+                rsa::pss::VerifyingKey::<Sha256>::new(k).verify(data, &signature).is_ok()
+            }
+            KeyMaterial::Ed25519Public(k) => {
+                let Ok(signature) = ed25519_dalek::Signature::from_slice(signature) else {
+                    return false;
+                };
+                k.verify_strict(data, &signature).is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    fn to_pem(&self) -> Result<String, EvalError> {
+        let pem = match &*self.key {
+            KeyMaterial::RsaPrivate(k) => k.to_pkcs8_pem(LineEnding::LF).map(|p| p.to_string()),
+            KeyMaterial::Ed25519Private(k) => k.to_pkcs8_pem(LineEnding::LF).map(|p| p.to_string()),
+            KeyMaterial::RsaPublic(k) => return k.to_public_key_pem(LineEnding::LF)
+                .map_err(|e| format!("RuntimeErr: Failed to encode public key: {}", e).into()),
+            KeyMaterial::Ed25519Public(k) => return k.to_public_key_pem(LineEnding::LF)
+                .map_err(|e| format!("RuntimeErr: Failed to encode public key: {}", e).into()),
+        };
+        pem.map_err(|e| format!("RuntimeErr: Failed to encode private key: {}", e).into())
+    }
+
+    fn bits(&self) -> usize {
+        match &*self.key {
+            KeyMaterial::RsaPrivate(k) => k.size() * 8,
+            KeyMaterial::RsaPublic(k) => k.size() * 8,
+            KeyMaterial::Ed25519Private(_) | KeyMaterial::Ed25519Public(_) => 256,
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "sign" => {
+                if args.len() != 1 {
+                    return arg_err!("sign expects 1 argument (data), got {}", args.len());
+                }
+                let data = data_arg("sign", "data", &args[0])?;
+                Ok(QValue::Bytes(QBytes::new(self.sign(&data)?)))
+            }
+            "verify" => {
+                if args.len() != 2 {
+                    return arg_err!("verify expects 2 arguments (signature, data), got {}", args.len());
+                }
+                let signature = data_arg("verify", "signature", &args[0])?;
+                let data = data_arg("verify", "data", &args[1])?;
+                Ok(QValue::Bool(QBool::new(self.verify(&signature, &data))))
+            }
+            "public_key" => {
+                if !args.is_empty() {
+                    return arg_err!("public_key expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::CryptoKey(QCryptoKey::new(self.public_material())))
+            }
+            "to_pem" => {
+                if !args.is_empty() {
+                    return arg_err!("to_pem expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Str(QString::new(self.to_pem()?)))
+            }
+            "algorithm" => {
+                if !args.is_empty() {
+                    return arg_err!("algorithm expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Str(QString::new(self.algorithm().to_string())))
+            }
+            "is_private" => {
+                if !args.is_empty() {
+                    return arg_err!("is_private expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Bool(QBool::new(self.is_private())))
+            }
+            "bits" => {
+                if !args.is_empty() {
+                    return arg_err!("bits expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Int(QInt::new(self.bits() as i64)))
+            }
+            _ => attr_err!("Unknown method '{}' on CryptoKey", method_name),
+        }
+    }
+}
+
+impl QObj for QCryptoKey {
+    fn cls(&self) -> String {
+        "CryptoKey".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "CryptoKey"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "CryptoKey"
+    }
+
+    fn str(&self) -> String {
+        let kind = if self.is_private() { "private" } else { "public" };
+        format!("<CryptoKey {} {} {} bits>", self.algorithm(), kind, self.bits())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "RSA or Ed25519 key for signing and verification".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
@@ -0,0 +1,322 @@
+// Cryptography: HMAC, authenticated encryption, public-key signatures, password
+// hashing and secure random tokens
+pub mod keys;
+pub mod password;
+pub mod jwt;
+
+use std::collections::HashMap;
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+use aes_gcm::aead::{Aead, AeadCore, OsRng, Payload};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+
+pub fn create_crypto_module() -> QValue {
+    let mut members = HashMap::new();
+    members.insert("hmac_sha256".to_string(), create_fn("crypto", "hmac_sha256"));
+    members.insert("hmac_sha512".to_string(), create_fn("crypto", "hmac_sha512"));
+
+    // Authenticated encryption (AEAD)
+    for name in [
+        "aes_gcm_encrypt", "aes_gcm_decrypt",
+        "chacha20_poly1305_encrypt", "chacha20_poly1305_decrypt",
+        "xchacha20_poly1305_encrypt", "xchacha20_poly1305_decrypt",
+        "generate_key", "generate_nonce",
+        // Secure random tokens (OS entropy, unlike std/rand's seeded generators)
+        "random_bytes", "token_hex", "token_urlsafe", "compare",
+        // Public-key signatures
+        "generate_keypair", "load_private_key", "load_public_key",
+        // Password hashing and key derivation
+        "password_hash", "password_verify", "pbkdf2", "hkdf",
+    ] {
+        members.insert(name.to_string(), create_fn("crypto", name));
+    }
+    QValue::Module(Box::new(QModule::new("crypto".to_string(), members)))
+}
+
+/// Handle crypto.* function calls
+pub fn call_crypto_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "crypto.hmac_sha256" => {
+            if args.len() != 2 {
+                return arg_err!("hmac_sha256 expects 2 arguments (message, key), got {}", args.len());
+            }
+            let message = bytes_arg("hmac_sha256", "message", &args[0])?;
+            let key = bytes_arg("hmac_sha256", "key", &args[1])?;
+
+            use hmac::{Hmac, Mac};
+            use sha2::Sha256;
+            type HmacSha256 = Hmac<Sha256>;
+
+            let mut mac = HmacSha256::new_from_slice(&key)
+                .map_err(|e| format!("HMAC key error: {}", e))?;
+            mac.update(&message);
+            let result = mac.finalize();
+            let code_bytes = result.into_bytes();
+
+            Ok(QValue::Str(QString::new(format!("{:x}", code_bytes))))
+        }
+        "crypto.hmac_sha512" => {
+            if args.len() != 2 {
+                return arg_err!("hmac_sha512 expects 2 arguments (message, key), got {}", args.len());
+            }
+            let message = bytes_arg("hmac_sha512", "message", &args[0])?;
+            let key = bytes_arg("hmac_sha512", "key", &args[1])?;
+
+            use hmac::{Hmac, Mac};
+            use sha2::Sha512;
+            type HmacSha512 = Hmac<Sha512>;
+
+            let mut mac = HmacSha512::new_from_slice(&key)
+                .map_err(|e| format!("HMAC key error: {}", e))?;
+            mac.update(&message);
+            let result = mac.finalize();
+            let code_bytes = result.into_bytes();
+
+            Ok(QValue::Str(QString::new(format!("{:x}", code_bytes))))
+        }
+        "crypto.aes_gcm_encrypt" | "crypto.chacha20_poly1305_encrypt" | "crypto.xchacha20_poly1305_encrypt" => {
+            let cipher = AeadCipher::from_func(func_name);
+            let name = &func_name["crypto.".len()..];
+            if args.len() < 2 || args.len() > 3 {
+                return arg_err!("{} expects 2 or 3 arguments (key, plaintext, aad?), got {}", name, args.len());
+            }
+            let key = bytes_arg(name, "key", &args[0])?;
+            let plaintext = bytes_arg(name, "plaintext", &args[1])?;
+            let (aad, nonce) = aead_options(name, args.get(2))?;
+            let sealed = cipher.encrypt(&key, nonce, &plaintext, &aad)?;
+            Ok(QValue::Bytes(QBytes::new(sealed)))
+        }
+        "crypto.aes_gcm_decrypt" | "crypto.chacha20_poly1305_decrypt" | "crypto.xchacha20_poly1305_decrypt" => {
+            let cipher = AeadCipher::from_func(func_name);
+            let name = &func_name["crypto.".len()..];
+            if args.len() < 2 || args.len() > 3 {
+                return arg_err!("{} expects 2 or 3 arguments (key, ciphertext, aad?), got {}", name, args.len());
+            }
+            let key = bytes_arg(name, "key", &args[0])?;
+            let sealed = bytes_arg(name, "ciphertext", &args[1])?;
+            let (aad, nonce) = aead_options(name, args.get(2))?;
+            if nonce.is_some() {
+                return arg_err!("{} reads the nonce from the ciphertext, 'nonce' option is not allowed", name);
+            }
+            let plaintext = cipher.decrypt(&key, &sealed, &aad)?;
+            Ok(QValue::Bytes(QBytes::new(plaintext)))
+        }
+        "crypto.generate_key" => {
+            // generate_key(size?) - random key, 32 bytes (256 bits) by default
+            if args.len() > 1 {
+                return arg_err!("generate_key expects 0 or 1 arguments (size?), got {}", args.len());
+            }
+            let size = match args.first() {
+                Some(size) => size.as_num()? as i64,
+                None => 32,
+            };
+            if size <= 0 || size > 1024 {
+                return value_err!("generate_key size must be between 1 and 1024 bytes, got {}", size);
+            }
+            Ok(QValue::Bytes(QBytes::new(random_bytes(size as usize))))
+        }
+        "crypto.generate_nonce" => {
+            // generate_nonce(algorithm?) - random nonce sized for the cipher (default aes_gcm)
+            if args.len() > 1 {
+                return arg_err!("generate_nonce expects 0 or 1 arguments (algorithm?), got {}", args.len());
+            }
+            let size = match args.first() {
+                Some(QValue::Int(n)) if n.value > 0 && n.value <= 1024 => n.value as usize,
+                Some(QValue::Int(n)) => return value_err!("generate_nonce size must be between 1 and 1024 bytes, got {}", n.value),
+                Some(QValue::Str(s)) => AeadCipher::from_name(&s.value)?.nonce_size(),
+                Some(other) => return type_err!("generate_nonce expects algorithm name or size, got {}", other.as_obj().cls()),
+                None => AeadCipher::AesGcm.nonce_size(),
+            };
+            Ok(QValue::Bytes(QBytes::new(random_bytes(size))))
+        }
+        "crypto.random_bytes" | "crypto.token_hex" | "crypto.token_urlsafe" => {
+            // random_bytes(n), token_hex(n = 32), token_urlsafe(n = 32) - n bytes of OS randomness
+            let name = &func_name["crypto.".len()..];
+            if args.len() > 1 || (name == "random_bytes" && args.is_empty()) {
+                return arg_err!("{} expects {} argument (n), got {}", name, if name == "random_bytes" { "1" } else { "0 or 1" }, args.len());
+            }
+            let n = match args.first() {
+                Some(QValue::Int(n)) if (0..=MAX_RANDOM_BYTES).contains(&n.value) => n.value as usize,
+                Some(QValue::Int(n)) => return value_err!("{} n must be between 0 and {} bytes, got {}", name, MAX_RANDOM_BYTES, n.value),
+                Some(other) => return type_err!("{} expects Int, got {}", name, other.as_obj().cls()),
+                None => 32,
+            };
+            let bytes = random_bytes(n);
+            Ok(match name {
+                "token_hex" => QValue::Str(QString::new(bytes.iter().map(|b| format!("{:02x}", b)).collect())),
+                "token_urlsafe" => {
+                    use base64::Engine;
+                    QValue::Str(QString::new(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes)))
+                }
+                _ => QValue::Bytes(QBytes::new(bytes)),
+            })
+        }
+        "crypto.compare" => {
+            // compare(a, b) - equality in time independent of where the values differ
+            if args.len() != 2 {
+                return arg_err!("compare expects 2 arguments (a, b), got {}", args.len());
+            }
+            let a = bytes_arg("compare", "a", &args[0])?;
+            let b = bytes_arg("compare", "b", &args[1])?;
+            use subtle::ConstantTimeEq;
+            Ok(QValue::Bool(QBool::new(a.ct_eq(&b).into())))
+        }
+        "crypto.generate_keypair" => keys::generate_keypair(&args),
+        "crypto.load_private_key" => keys::load_private_key(&args),
+        "crypto.load_public_key" => keys::load_public_key(&args),
+        "crypto.password_hash" => password::password_hash(&args),
+        "crypto.password_verify" => password::password_verify(&args),
+        "crypto.pbkdf2" => password::derive_pbkdf2(&args),
+        "crypto.hkdf" => password::derive_hkdf(&args),
+        _ => attr_err!("Unknown crypto function: {}", func_name)
+    }
+}
+
+// ============================================================================
+// Authenticated encryption (AEAD)
+// ============================================================================
+
+/// AEAD ciphers. Encrypted output is `nonce || ciphertext || tag` so decryption
+/// only needs the key (and the same associated data).
+#[derive(Clone, Copy)]
+enum AeadCipher {
+    AesGcm,
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
+}
+
+const TAG_SIZE: usize = 16;
+
+impl AeadCipher {
+    fn from_func(func_name: &str) -> Self {
+        if func_name.starts_with("crypto.aes_gcm") {
+            AeadCipher::AesGcm
+        } else if func_name.starts_with("crypto.xchacha20") {
+            AeadCipher::XChaCha20Poly1305
+        } else {
+            AeadCipher::ChaCha20Poly1305
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, EvalError> {
+        match name {
+            "aes_gcm" => Ok(AeadCipher::AesGcm),
+            "chacha20_poly1305" => Ok(AeadCipher::ChaCha20Poly1305),
+            "xchacha20_poly1305" => Ok(AeadCipher::XChaCha20Poly1305),
+            _ => value_err!("Unknown AEAD algorithm '{}' (expected aes_gcm, chacha20_poly1305 or xchacha20_poly1305)", name),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            AeadCipher::AesGcm => "aes_gcm",
+            AeadCipher::ChaCha20Poly1305 => "chacha20_poly1305",
+            AeadCipher::XChaCha20Poly1305 => "xchacha20_poly1305",
+        }
+    }
+
+    fn nonce_size(&self) -> usize {
+        match self {
+            AeadCipher::AesGcm | AeadCipher::ChaCha20Poly1305 => 12,
+            AeadCipher::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    fn encrypt(&self, key: &[u8], nonce: Option<Vec<u8>>, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, EvalError> {
+        let nonce = match nonce {
+            Some(nonce) if nonce.len() != self.nonce_size() => {
+                return value_err!("{} nonce must be {} bytes, got {}", self.name(), self.nonce_size(), nonce.len());
+            }
+            Some(nonce) => nonce,
+            None => random_bytes(self.nonce_size()),
+        };
+        let payload = Payload { msg: plaintext, aad };
+        let ciphertext = match (self, key.len()) {
+            (AeadCipher::AesGcm, 16) => seal::<Aes128Gcm>(key, &nonce, payload),
+            (AeadCipher::AesGcm, 32) => seal::<Aes256Gcm>(key, &nonce, payload),
+            (AeadCipher::ChaCha20Poly1305, 32) => seal::<ChaCha20Poly1305>(key, &nonce, payload),
+            (AeadCipher::XChaCha20Poly1305, 32) => seal::<XChaCha20Poly1305>(key, &nonce, payload),
+            (_, len) => return self.key_size_error(len),
+        };
+        let ciphertext = ciphertext.ok_or_else(|| format!("RuntimeErr: {} encryption failed", self.name()))?;
+
+        let mut sealed = nonce;
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    fn decrypt(&self, key: &[u8], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, EvalError> {
+        if sealed.len() < self.nonce_size() + TAG_SIZE {
+            return value_err!("{} ciphertext is too short ({} bytes)", self.name(), sealed.len());
+        }
+        let (nonce, ciphertext) = sealed.split_at(self.nonce_size());
+        let payload = Payload { msg: ciphertext, aad };
+        let plaintext = match (self, key.len()) {
+            (AeadCipher::AesGcm, 16) => open::<Aes128Gcm>(key, nonce, payload),
+            (AeadCipher::AesGcm, 32) => open::<Aes256Gcm>(key, nonce, payload),
+            (AeadCipher::ChaCha20Poly1305, 32) => open::<ChaCha20Poly1305>(key, nonce, payload),
+            (AeadCipher::XChaCha20Poly1305, 32) => open::<XChaCha20Poly1305>(key, nonce, payload),
+            (_, len) => return self.key_size_error(len),
+        };
+        match plaintext {
+            Some(plaintext) => Ok(plaintext),
+            // Wrong key, wrong associated data and tampering are indistinguishable by design
+            None => value_err!("{} decryption failed: authentication failed", self.name()),
+        }
+    }
+
+    fn key_size_error<T>(&self, len: usize) -> Result<T, EvalError> {
+        match self {
+            AeadCipher::AesGcm => value_err!("aes_gcm key must be 16 or 32 bytes, got {}", len),
+            _ => value_err!("{} key must be 32 bytes, got {}", self.name(), len),
+        }
+    }
+}
+
+fn seal<C: Aead + AeadCore + aes_gcm::KeyInit>(key: &[u8], nonce: &[u8], payload: Payload) -> Option<Vec<u8>> {
+    C::new_from_slice(key).ok()?.encrypt(aes_gcm::aead::Nonce::<C>::from_slice(nonce), payload).ok()
+}
+
+fn open<C: Aead + AeadCore + aes_gcm::KeyInit>(key: &[u8], nonce: &[u8], payload: Payload) -> Option<Vec<u8>> {
+    C::new_from_slice(key).ok()?.decrypt(aes_gcm::aead::Nonce::<C>::from_slice(nonce), payload).ok()
+}
+
+/// Upper bound for random_bytes/token_* sizes
+const MAX_RANDOM_BYTES: i64 = 1 << 20;
+
+fn random_bytes(size: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; size];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn bytes_arg(func: &str, what: &str, value: &QValue) -> Result<Vec<u8>, EvalError> {
+    match value {
+        QValue::Bytes(b) => Ok(b.data.clone()),
+        QValue::Str(s) => Ok(s.value.as_bytes().to_vec()),
+        other => type_err!("{} {} must be Str or Bytes, got {}", func, what, other.as_obj().cls()),
+    }
+}
+
+/// The optional third argument is either the associated data itself or an
+/// options Dict with `aad` and/or an explicit `nonce`
+fn aead_options(func: &str, value: Option<&QValue>) -> Result<(Vec<u8>, Option<Vec<u8>>), EvalError> {
+    match value {
+        None | Some(QValue::Nil(_)) => Ok((Vec::new(), None)),
+        Some(QValue::Dict(options)) => {
+            let aad = match options.get("aad") {
+                Some(QValue::Nil(_)) | None => Vec::new(),
+                Some(aad) => bytes_arg(func, "aad", &aad)?,
+            };
+            let nonce = match options.get("nonce") {
+                Some(QValue::Nil(_)) | None => None,
+                Some(nonce) => Some(bytes_arg(func, "nonce", &nonce)?),
+            };
+            Ok((aad, nonce))
+        }
+        Some(aad) => Ok((bytes_arg(func, "aad", aad)?, None)),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,178 @@
+// Password hashing (Argon2, PBKDF2) and key derivation (PBKDF2, HKDF)
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, type_err, value_err};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use rand::rngs::OsRng;
+use sha2::{Sha256, Sha512};
+
+/// Hash/derive options shared by the functions below: a trailing Dict (or keyword arguments)
+fn options(args: &[QValue], positional: usize) -> Result<Option<&QDict>, EvalError> {
+    match args.get(positional) {
+        Some(QValue::Dict(options)) => Ok(Some(options)),
+        Some(QValue::Nil(_)) | None => Ok(None),
+        Some(other) => type_err!("Expected options Dict, got {}", other.as_obj().cls()),
+    }
+}
+
+fn int_option(options: Option<&QDict>, name: &str, default: u32) -> Result<u32, EvalError> {
+    match options.and_then(|o| o.get(name)) {
+        Some(value) => {
+            let n = value.as_num()? as i64;
+            if n <= 0 || n > u32::MAX as i64 {
+                return value_err!("{} must be a positive integer, got {}", name, n);
+            }
+            Ok(n as u32)
+        }
+        None => Ok(default),
+    }
+}
+
+fn bytes_arg(func: &str, what: &str, value: &QValue) -> Result<Vec<u8>, EvalError> {
+    match value {
+        QValue::Bytes(b) => Ok(b.data.clone()),
+        QValue::Str(s) => Ok(s.value.as_bytes().to_vec()),
+        other => type_err!("{} {} must be Str or Bytes, got {}", func, what, other.as_obj().cls()),
+    }
+}
+
+/// crypto.password_hash(password, algorithm?: "argon2id", ...) -> PHC string
+///
+/// Options: `algorithm` ("argon2id", "argon2i", "argon2d", "pbkdf2"), Argon2 costs
+/// `memory_cost` (KiB), `time_cost`, `parallelism`, and PBKDF2 `iterations`.
+pub fn password_hash(args: &[QValue]) -> Result<QValue, EvalError> {
+    if args.is_empty() || args.len() > 2 {
+        return arg_err!("password_hash expects 1 or 2 arguments (password, options?), got {}", args.len());
+    }
+    let password = bytes_arg("password_hash", "password", &args[0])?;
+    // Accept password_hash(pw, "pbkdf2") as shorthand for password_hash(pw, algorithm: "pbkdf2")
+    let (algorithm, options) = match args.get(1) {
+        Some(QValue::Str(s)) => (s.value.to_lowercase(), None),
+        _ => {
+            let options = options(args, 1)?;
+            let algorithm = options.and_then(|o| o.get("algorithm"))
+                .map(|a| a.as_str().to_lowercase())
+                .unwrap_or_else(|| "argon2id".to_string());
+            (algorithm, options)
+        }
+    };
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = match algorithm.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => {
+            let variant = match algorithm.as_str() {
+                "argon2id" => argon2::Algorithm::Argon2id,
+                "argon2i" => argon2::Algorithm::Argon2i,
+                _ => argon2::Algorithm::Argon2d,
+            };
+            let params = argon2::Params::new(
+                int_option(options, "memory_cost", argon2::Params::DEFAULT_M_COST)?,
+                int_option(options, "time_cost", argon2::Params::DEFAULT_T_COST)?,
+                int_option(options, "parallelism", argon2::Params::DEFAULT_P_COST)?,
+                None,
+            ).map_err(|e| format!("ValueErr: Invalid Argon2 parameters: {}", e))?;
+            argon2::Argon2::new(variant, argon2::Version::V0x13, params)
+                .hash_password(&password, &salt)
+                .map_err(|e| format!("RuntimeErr: Password hashing failed: {}", e))?
+                .to_string()
+        }
+        "pbkdf2" => {
+            let params = pbkdf2::Params {
+                rounds: int_option(options, "iterations", pbkdf2::Params::default().rounds)?,
+                output_length: 32,
+            };
+            pbkdf2::Pbkdf2.hash_password_customized(&password, Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()), None, params, &salt)
+                .map_err(|e| format!("RuntimeErr: Password hashing failed: {}", e))?
+                .to_string()
+        }
+        _ => return value_err!("Unknown password hash algorithm '{}' (expected argon2id, argon2i, argon2d or pbkdf2)", algorithm),
+    };
+    Ok(QValue::Str(QString::new(hash)))
+}
+
+/// crypto.password_verify(password, hash) -> Bool. The algorithm and parameters come from the hash.
+pub fn password_verify(args: &[QValue]) -> Result<QValue, EvalError> {
+    if args.len() != 2 {
+        return arg_err!("password_verify expects 2 arguments (password, hash), got {}", args.len());
+    }
+    let password = bytes_arg("password_verify", "password", &args[0])?;
+    let encoded = match &args[1] {
+        QValue::Str(s) => s.value.clone(),
+        other => return type_err!("password_verify hash must be Str, got {}", other.as_obj().cls()),
+    };
+    let hash = PasswordHash::new(&encoded)
+        .map_err(|e| format!("ValueErr: Invalid password hash: {}", e))?;
+
+    let verified = match hash.algorithm.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => argon2::Argon2::default().verify_password(&password, &hash).is_ok(),
+        "pbkdf2-sha256" | "pbkdf2-sha512" => pbkdf2::Pbkdf2.verify_password(&password, &hash).is_ok(),
+        other => return value_err!("Unsupported password hash algorithm '{}'", other),
+    };
+    Ok(QValue::Bool(QBool::new(verified)))
+}
+
+/// crypto.pbkdf2(password, salt, iterations, length?: 32, hash?: "sha256") -> Bytes
+pub fn derive_pbkdf2(args: &[QValue]) -> Result<QValue, EvalError> {
+    if args.len() < 3 || args.len() > 4 {
+        return arg_err!("pbkdf2 expects 3 or 4 arguments (password, salt, iterations, options?), got {}", args.len());
+    }
+    let password = bytes_arg("pbkdf2", "password", &args[0])?;
+    let salt = bytes_arg("pbkdf2", "salt", &args[1])?;
+    let iterations = args[2].as_num()? as i64;
+    if iterations <= 0 || iterations > u32::MAX as i64 {
+        return value_err!("pbkdf2 iterations must be a positive integer, got {}", iterations);
+    }
+    let (length, hash) = length_and_hash("pbkdf2", args, 3)?;
+
+    let mut out = vec![0u8; length];
+    match hash.as_str() {
+        "sha256" => pbkdf2::pbkdf2_hmac::<Sha256>(&password, &salt, iterations as u32, &mut out),
+        _ => pbkdf2::pbkdf2_hmac::<Sha512>(&password, &salt, iterations as u32, &mut out),
+    }
+    Ok(QValue::Bytes(QBytes::new(out)))
+}
+
+/// crypto.hkdf(key_material, salt?, info?, length?: 32, hash?: "sha256") -> Bytes
+pub fn derive_hkdf(args: &[QValue]) -> Result<QValue, EvalError> {
+    if args.is_empty() || args.len() > 4 {
+        return arg_err!("hkdf expects 1-4 arguments (key_material, salt?, info?, options?), got {}", args.len());
+    }
+    let ikm = bytes_arg("hkdf", "key_material", &args[0])?;
+    let optional = |i: usize, what: &str| -> Result<Vec<u8>, EvalError> {
+        match args.get(i) {
+            None | Some(QValue::Nil(_)) | Some(QValue::Dict(_)) => Ok(Vec::new()),
+            Some(value) => bytes_arg("hkdf", what, value),
+        }
+    };
+    let salt = optional(1, "salt")?;
+    let info = optional(2, "info")?;
+    let options_at = args.iter().position(|a| matches!(a, QValue::Dict(_))).unwrap_or(args.len());
+    let (length, hash) = length_and_hash("hkdf", args, options_at)?;
+
+    let salt = if salt.is_empty() { None } else { Some(salt.as_slice()) };
+    let mut out = vec![0u8; length];
+    let expanded = match hash.as_str() {
+        "sha256" => hkdf::Hkdf::<Sha256>::new(salt, &ikm).expand(&info, &mut out),
+        _ => hkdf::Hkdf::<Sha512>::new(salt, &ikm).expand(&info, &mut out),
+    };
+    if expanded.is_err() {
+        return value_err!("hkdf length {} is too large for {}", length, hash);
+    }
+    Ok(QValue::Bytes(QBytes::new(out)))
+}
+
+/// Parse `length` and `hash` options (defaults 32 and "sha256")
+fn length_and_hash(func: &str, args: &[QValue], at: usize) -> Result<(usize, String), EvalError> {
+    let options = options(args, at)?;
+    let length = int_option(options, "length", 32)? as usize;
+    if length > 1024 {
+        return value_err!("{} length must be at most 1024 bytes, got {}", func, length);
+    }
+    let hash = options.and_then(|o| o.get("hash"))
+        .map(|h| h.as_str().to_lowercase())
+        .unwrap_or_else(|| "sha256".to_string());
+    if hash != "sha256" && hash != "sha512" {
+        return value_err!("{} hash must be sha256 or sha512, got '{}'", func, hash);
+    }
+    Ok((length, hash))
+}
@@ -1,6 +1,10 @@
 pub mod sqlite;
 pub mod postgres;
 pub mod mysql;
+pub mod pool;
+pub mod stream;
+pub mod transaction;
+mod params;
 
 pub use sqlite::{create_sqlite_module, call_sqlite_function};
 pub use postgres::{create_postgres_module, call_postgres_function};
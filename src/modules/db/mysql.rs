@@ -1,17 +1,24 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::{arg_err, attr_err};
 use std::sync::{Arc, Mutex};
-use mysql::{Conn, Row, Params, Value, prelude::*};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use mysql::{Conn, QueryResult, Row, Params, Value, prelude::*};
 use crate::types::*;
 use crate::scope::Scope;
+use super::params::{bind_params, Placeholder};
+use super::stream::{lock_conn, pull_rows, stream_option, QRowIterator, RowStream, SharedConn, StreamSender};
+use super::transaction::QTransaction;
 use chrono::{DateTime, Utc, NaiveDate, NaiveTime, NaiveDateTime};
 use rust_decimal::Decimal;
 
 /// Wrapper for MySQL Connection that implements QObj
 #[derive(Clone)]
 pub struct QMysqlConnection {
-    conn: Arc<Mutex<Conn>>,
+    conn: Arc<SharedConn<Conn>>,
+    /// Open `transaction()` blocks, shared by clones of this connection
+    tx_depth: Arc<AtomicUsize>,
     id: u64,
 }
 
@@ -26,11 +33,30 @@ impl std::fmt::Debug for QMysqlConnection {
 impl QMysqlConnection {
     pub fn new(conn: Conn) -> Self {
         QMysqlConnection {
-            conn: Arc::new(Mutex::new(conn)),
+            conn: Arc::new(SharedConn::new(conn)),
+            tx_depth: Arc::new(AtomicUsize::new(0)),
             id: next_object_id(),
         }
     }
 
+    /// Roll back any open transaction before the connection is reused
+    pub fn reset(&self) -> Result<(), String> {
+        self.tx_depth.store(0, Ordering::SeqCst);
+        let mut conn = lock_conn(&self.conn)?;
+        conn.query_drop("ROLLBACK")
+            .map_err(|e| format!("DatabaseError: {}", e))
+    }
+
+    /// Run one or more statements that return no rows
+    pub fn run_batch(&self, sql: &str) -> Result<(), String> {
+        let mut conn = lock_conn(&self.conn)?;
+        conn.query_drop(sql).map_err(map_mysql_error)
+    }
+
+    pub fn tx_depth(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.tx_depth)
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "close" => {
@@ -39,21 +65,39 @@ impl QMysqlConnection {
             }
 
             "commit" => {
-                let mut conn = self.conn.lock().unwrap();
+                let mut conn = lock_conn(&self.conn)?;
                 conn.query_drop("COMMIT")
                     .map_err(|e| format!("DatabaseError: {}", e))?;
                 Ok(QValue::Nil(QNil))
             }
 
             "rollback" => {
-                let mut conn = self.conn.lock().unwrap();
+                let mut conn = lock_conn(&self.conn)?;
                 conn.query_drop("ROLLBACK")
                     .map_err(|e| format!("DatabaseError: {}", e))?;
                 Ok(QValue::Nil(QNil))
             }
 
+            "transaction" => {
+                if !args.is_empty() {
+                    return arg_err!("transaction expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Transaction(QTransaction::new(QValue::MysqlConnection(self.clone()))))
+            }
+
+            "execute_batch" => {
+                // Several `;`-separated statements, e.g. a migration or schema file
+                if args.len() != 1 {
+                    return arg_err!("execute_batch expects 1 argument (sql), got {}", args.len());
+                }
+                self.run_batch(&args[0].as_str())?;
+                Ok(QValue::Nil(QNil))
+            }
+
             "cursor" => {
-                Ok(QValue::MysqlCursor(QMysqlCursor::new(self.conn.clone())))
+                // cursor() / cursor(stream: true)
+                let stream = stream_option(&args)?;
+                Ok(QValue::MysqlCursor(QMysqlCursor::new(self.conn.clone(), stream)))
             }
 
             "execute" => {
@@ -67,7 +111,7 @@ impl QMysqlConnection {
                     None
                 };
 
-                let mut conn = self.conn.lock().unwrap();
+                let mut conn = lock_conn(&self.conn)?;
                 let count = execute_with_params(&mut conn, &sql, params)?;
                 Ok(QValue::Int(QInt::new(count as i64)))
             }
@@ -121,11 +165,14 @@ struct ColumnDescription {
 /// Wrapper for MySQL cursor (connection + results)
 #[derive(Clone)]
 pub struct QMysqlCursor {
-    conn: Arc<Mutex<Conn>>,
-    current_results: Arc<Mutex<Vec<HashMap<String, QValue>>>>,
+    conn: Arc<SharedConn<Conn>>,
+    current_results: Arc<Mutex<Vec<IndexMap<String, QValue>>>>,
     position: Arc<Mutex<usize>>,
     row_count: Arc<Mutex<i64>>,
     description: Arc<Mutex<Option<Vec<ColumnDescription>>>>,
+    /// Stream SELECT results instead of buffering them (`cursor(stream: true)`)
+    streaming: bool,
+    stream: Arc<Mutex<Option<RowStream<Row>>>>,
     id: u64,
 }
 
@@ -139,17 +186,24 @@ impl std::fmt::Debug for QMysqlCursor {
 }
 
 impl QMysqlCursor {
-    pub fn new(conn: Arc<Mutex<Conn>>) -> Self {
+    pub fn new(conn: Arc<SharedConn<Conn>>, streaming: bool) -> Self {
         QMysqlCursor {
             conn,
             current_results: Arc::new(Mutex::new(Vec::new())),
             position: Arc::new(Mutex::new(0)),
             row_count: Arc::new(Mutex::new(-1)),
             description: Arc::new(Mutex::new(None)),
+            streaming,
+            stream: Arc::new(Mutex::new(None)),
             id: next_object_id(),
         }
     }
 
+    /// Rows from the open stream, or None when results are buffered
+    fn pull_stream(&self, limit: Option<usize>) -> Result<Option<Vec<QValue>>, String> {
+        pull_rows(&self.stream, limit, |row| row_to_dict(&row))
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "execute" => {
@@ -180,7 +234,7 @@ impl QMysqlCursor {
                 let mut total_count = 0;
                 let params_elements = params_seq.elements.borrow();
                 for params in params_elements.iter() {
-                    let mut conn = self.conn.lock().unwrap();
+                    let mut conn = lock_conn(&self.conn)?;
                     let count = execute_with_params(&mut conn, &sql, Some(params))?;
                     total_count += count;
                 }
@@ -190,6 +244,10 @@ impl QMysqlCursor {
             }
 
             "fetch_one" => {
+                if let Some(mut rows) = self.pull_stream(Some(1))? {
+                    return Ok(rows.pop().unwrap_or(QValue::Nil(QNil)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -209,6 +267,10 @@ impl QMysqlCursor {
                     args[0].as_num()? as usize
                 };
 
+                if let Some(rows) = self.pull_stream(Some(size))? {
+                    return Ok(QValue::Array(QArray::new(rows)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -223,6 +285,10 @@ impl QMysqlCursor {
             }
 
             "fetch_all" => {
+                if let Some(rows) = self.pull_stream(None)? {
+                    return Ok(QValue::Array(QArray::new(rows)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -235,8 +301,16 @@ impl QMysqlCursor {
                 Ok(QValue::Array(QArray::new(rows)))
             }
 
+            "iter" => {
+                if !args.is_empty() {
+                    return arg_err!("iter expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::RowIterator(QRowIterator::new(QValue::MysqlCursor(self.clone()))))
+            }
+
             "close" => {
-                // Clear results
+                // Clear results and release the connection if streaming
+                *self.stream.lock().unwrap() = None;
                 self.current_results.lock().unwrap().clear();
                 *self.position.lock().unwrap() = 0;
                 *self.row_count.lock().unwrap() = -1;
@@ -249,7 +323,7 @@ impl QMysqlCursor {
                 match &*desc {
                     Some(columns) => {
                         let result: Vec<QValue> = columns.iter().map(|col| {
-                            let mut map = HashMap::new();
+                            let mut map = IndexMap::new();
                             map.insert("name".to_string(), QValue::Str(QString::new(col.name.clone())));
                             map.insert("type_code".to_string(), QValue::Str(QString::new(col.type_code.clone())));
                             map.insert("display_size".to_string(), QValue::Nil(QNil));
@@ -279,11 +353,18 @@ impl QMysqlCursor {
     }
 
     fn execute_internal(&self, sql: &str, params: Option<&QValue>) -> Result<(), String> {
-        let mut conn = self.conn.lock().unwrap();
+        // A new statement ends any result set this cursor is still streaming
+        *self.stream.lock().unwrap() = None;
 
         // Check if this is a SELECT query
         let is_query = sql.trim().to_uppercase().starts_with("SELECT");
 
+        if is_query && self.streaming {
+            return self.start_stream(sql, params);
+        }
+
+        let mut conn = lock_conn(&self.conn)?;
+
         if is_query {
             // Execute query and fetch all results with column metadata
             let (rows, columns) = query_with_params_and_metadata(&mut conn, sql, params)?;
@@ -305,6 +386,53 @@ impl QMysqlCursor {
 
         Ok(())
     }
+
+    /// Run a query on a worker thread that keeps its result set open
+    fn start_stream(&self, sql: &str, params: Option<&QValue>) -> Result<(), String> {
+        let (sql, params) = bind_params(sql, params, Placeholder::Question)?;
+        let mysql_params: Option<Vec<Value>> = params.map(|values| values.iter().map(qvalue_to_mysql_param).collect());
+        let (columns, stream) = RowStream::start(&self.conn, move |conn, sender| {
+            // Rows are read off the socket as the result is iterated
+            match mysql_params {
+                Some(values) => {
+                    let result = conn.exec_iter(sql, Params::Positional(values))
+                        .map_err(map_mysql_error)?;
+                    send_result_rows(result, sender)
+                }
+                None => {
+                    let result = conn.query_iter(sql)
+                        .map_err(map_mysql_error)?;
+                    send_result_rows(result, sender)
+                }
+            }
+        })?;
+
+        // The row count isn't known until every row has been read
+        *self.row_count.lock().unwrap() = -1;
+        *self.description.lock().unwrap() = Some(columns);
+        self.current_results.lock().unwrap().clear();
+        *self.position.lock().unwrap() = 0;
+        *self.stream.lock().unwrap() = Some(stream);
+        Ok(())
+    }
+}
+
+/// Hand the first result set's rows to a streaming cursor
+fn send_result_rows<P: Protocol>(mut result: QueryResult<'_, '_, '_, P>, sender: &mut StreamSender<Vec<ColumnDescription>, Row>) -> Result<(), String> {
+    let columns = result.columns().as_ref().iter().map(|col| ColumnDescription {
+        name: col.name_str().to_string(),
+        type_code: format!("{:?}", col.column_type()),
+    }).collect();
+    sender.ready(columns);
+
+    if let Some(rows) = result.iter() {
+        for row in rows {
+            if !sender.send(row.map_err(map_mysql_error)?) {
+                break;
+            }
+        }
+    }
+    Ok(())
 }
 
 impl QObj for QMysqlCursor {
@@ -552,47 +680,42 @@ fn qvalue_to_mysql_param(value: &QValue) -> Value {
 }
 
 /// Execute statement with parameters
+///
+/// `Conn` caches prepared statements per connection, keyed by SQL text.
 fn execute_with_params(conn: &mut Conn, sql: &str, params: Option<&QValue>) -> Result<u64, String> {
-    if let Some(params_value) = params {
-        match params_value {
-            QValue::Array(arr) => {
-                // Positional parameters (?)
-                let mysql_params: Vec<Value> = arr.elements.borrow().iter()
-                    .map(qvalue_to_mysql_param)
-                    .collect();
-
-                conn.exec_drop(sql, Params::Positional(mysql_params))
-                    .map_err(|e| map_mysql_error(e))?;
-
-                Ok(conn.affected_rows())
-            }
-            _ => Err("MySQL only supports positional parameters (arrays)".into())
+    // Positional parameters (?) or a dict for :name placeholders
+    match bind_params(sql, params, Placeholder::Question)? {
+        (sql, Some(values)) => {
+            let mysql_params: Vec<Value> = values.iter()
+                .map(qvalue_to_mysql_param)
+                .collect();
+
+            conn.exec_drop(sql, Params::Positional(mysql_params))
+                .map_err(|e| map_mysql_error(e))?;
+        }
+        (sql, None) => {
+            conn.query_drop(sql)
+                .map_err(|e| map_mysql_error(e))?;
         }
-    } else {
-        conn.query_drop(sql)
-            .map_err(|e| map_mysql_error(e))?;
-        Ok(conn.affected_rows())
     }
+    Ok(conn.affected_rows())
 }
 
 /// Query with parameters and return rows with column metadata
-fn query_with_params_and_metadata(conn: &mut Conn, sql: &str, params: Option<&QValue>) -> Result<(Vec<HashMap<String, QValue>>, Vec<ColumnDescription>), String> {
-    let rows: Vec<Row> = if let Some(params_value) = params {
-        match params_value {
-            QValue::Array(arr) => {
-                // Positional parameters
-                let mysql_params: Vec<Value> = arr.elements.borrow().iter()
-                    .map(qvalue_to_mysql_param)
-                    .collect();
-
-                conn.exec(sql, Params::Positional(mysql_params))
-                    .map_err(|e| map_mysql_error(e))?
-            }
-            _ => return Err("MySQL only supports positional parameters (arrays)".into())
+fn query_with_params_and_metadata(conn: &mut Conn, sql: &str, params: Option<&QValue>) -> Result<(Vec<IndexMap<String, QValue>>, Vec<ColumnDescription>), String> {
+    let rows: Vec<Row> = match bind_params(sql, params, Placeholder::Question)? {
+        (sql, Some(values)) => {
+            let mysql_params: Vec<Value> = values.iter()
+                .map(qvalue_to_mysql_param)
+                .collect();
+
+            conn.exec(sql, Params::Positional(mysql_params))
+                .map_err(|e| map_mysql_error(e))?
+        }
+        (sql, None) => {
+            conn.query(sql)
+                .map_err(|e| map_mysql_error(e))?
         }
-    } else {
-        conn.query(sql)
-            .map_err(|e| map_mysql_error(e))?
     };
 
     // Get column metadata
@@ -615,8 +738,8 @@ fn query_with_params_and_metadata(conn: &mut Conn, sql: &str, params: Option<&QV
 }
 
 /// Convert MySQL row to Quest dict
-fn row_to_dict(row: &Row) -> Result<HashMap<String, QValue>, String> {
-    let mut dict = HashMap::new();
+fn row_to_dict(row: &Row) -> Result<IndexMap<String, QValue>, String> {
+    let mut dict = IndexMap::new();
 
     for (idx, column) in row.columns().iter().enumerate() {
         let col_name = column.name_str().to_string();
@@ -733,9 +856,27 @@ pub fn create_mysql_module() -> QValue {
         id: next_object_id(),
     }));
 
+    members.insert("pool".to_string(), QValue::Fun(QFun {
+        name: "pool".to_string(),
+        parent_type: "mysql".to_string(),
+        id: next_object_id(),
+    }));
+
     QValue::Module(Box::new(QModule::new("mysql".to_string(), members)))
 }
 
+/// Connect using a mysql:// URL
+pub fn connect(conn_str: &str) -> Result<QValue, String> {
+    let mut conn = Conn::new(conn_str)
+        .map_err(|e| format!("DatabaseError: Failed to connect to database: {}", e))?;
+
+    // Disable autocommit for proper transaction support
+    conn.query_drop("SET autocommit=0")
+        .map_err(|e| format!("DatabaseError: Failed to disable autocommit: {}", e))?;
+
+    Ok(QValue::MysqlConnection(QMysqlConnection::new(conn)))
+}
+
 /// Call mysql module functions
 pub fn call_mysql_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
@@ -743,16 +884,12 @@ pub fn call_mysql_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scop
             if args.len() != 1 {
                 return arg_err!("mysql.connect expects 1 argument (connection_string), got {}", args.len());
             }
-            let conn_str = args[0].as_str();
-
-            let mut conn = Conn::new(&*conn_str)
-                .map_err(|e| format!("DatabaseError: Failed to connect to database: {}", e))?;
-
-            // Disable autocommit for proper transaction support
-            conn.query_drop("SET autocommit=0")
-                .map_err(|e| format!("DatabaseError: Failed to disable autocommit: {}", e))?;
+            Ok(connect(&args[0].as_str())?)
+        }
 
-            Ok(QValue::MysqlConnection(QMysqlConnection::new(conn)))
+        "mysql.pool" => {
+            // mysql.pool(url, max: 10)
+            Ok(super::pool::create_pool(super::pool::Driver::Mysql, args)?)
         }
 
         _ => attr_err!("Unknown function: {}", func_name)
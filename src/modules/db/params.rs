@@ -0,0 +1,167 @@
+// Parameter handling shared by the database drivers
+//
+// Every driver accepts `execute(sql, [values])` for its native positional
+// placeholders and `execute(sql, {name: value})` for `:name` placeholders.
+// Named placeholders are rewritten into the driver's positional style, so the
+// same SQL text works against SQLite, PostgreSQL and MySQL.
+
+use crate::types::*;
+
+/// Positional placeholder style used by a driver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placeholder {
+    /// `?1`, `?2`, ... (SQLite). A repeated name reuses its number.
+    Numbered,
+    /// `$1`, `$2`, ... (PostgreSQL). A repeated name reuses its number.
+    Dollar,
+    /// `?` (MySQL). A repeated name is bound once per occurrence.
+    Question,
+}
+
+/// Resolve `params` for `sql`: an Array is used as-is, a Dict is matched
+/// against `:name` placeholders and the SQL rewritten to positional form.
+pub fn bind_params(sql: &str, params: Option<&QValue>, style: Placeholder) -> Result<(String, Option<Vec<QValue>>), String> {
+    match params {
+        None => Ok((sql.to_string(), None)),
+        Some(QValue::Array(arr)) => Ok((sql.to_string(), Some(arr.elements.borrow().clone()))),
+        Some(QValue::Dict(dict)) => {
+            let (rewritten, names) = rewrite_named(sql, style);
+            let mut values = Vec::with_capacity(names.len());
+            for name in &names {
+                // Keys may be written with or without the leading colon
+                let value = dict.get(name).or_else(|| dict.get(&format!(":{}", name)));
+                match value {
+                    Some(v) => values.push(v),
+                    None => return Err(format!("ProgrammingError: No value supplied for named parameter ':{}'", name)),
+                }
+            }
+            Ok((rewritten, Some(values)))
+        }
+        Some(other) => Err(format!("ProgrammingError: Parameters must be an array or dict, got {}", other.as_obj().cls())),
+    }
+}
+
+/// Replace `:name` placeholders with positional ones. Returns the new SQL and
+/// the parameter names in binding order. String literals, quoted identifiers,
+/// comments, PostgreSQL dollar-quoted strings and `::` casts are left alone.
+pub fn rewrite_named(sql: &str, style: Placeholder) -> (String, Vec<String>) {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' | '`' => {
+                // Quoted text runs to the matching quote; doubled quotes ('') re-enter here
+                let end = chars[i + 1..].iter().position(|&ch| ch == c).map_or(chars.len(), |p| i + 1 + p + 1);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                let end = chars[i..].iter().position(|&ch| ch == '\n').map_or(chars.len(), |p| i + p);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let end = find_seq(&chars, i + 2, &['*', '/']).map_or(chars.len(), |p| p + 2);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '$' if style == Placeholder::Dollar => {
+                // $$...$$ or $tag$...$tag$
+                let tag_len = chars[i + 1..].iter().take_while(|ch| ch.is_ascii_alphabetic() || **ch == '_').count();
+                if chars.get(i + 1 + tag_len) == Some(&'$') {
+                    let tag: Vec<char> = chars[i..i + tag_len + 2].to_vec();
+                    let end = find_seq(&chars, i + tag.len(), &tag).map_or(chars.len(), |p| p + tag.len());
+                    out.extend(&chars[i..end]);
+                    i = end;
+                } else {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+            ':' if chars.get(i + 1) == Some(&':') => {
+                // PostgreSQL cast: value::type
+                out.push_str("::");
+                i += 2;
+            }
+            ':' if is_name_start(chars.get(i + 1)) && !is_name_char(i.checked_sub(1).map(|p| chars[p])) => {
+                let len = chars[i + 1..].iter().take_while(|ch| is_name_char(Some(**ch))).count();
+                let name: String = chars[i + 1..i + 1 + len].iter().collect();
+                match style {
+                    Placeholder::Question => {
+                        names.push(name);
+                        out.push('?');
+                    }
+                    Placeholder::Numbered | Placeholder::Dollar => {
+                        let index = match names.iter().position(|n| *n == name) {
+                            Some(p) => p + 1,
+                            None => {
+                                names.push(name);
+                                names.len()
+                            }
+                        };
+                        out.push(if style == Placeholder::Dollar { '$' } else { '?' });
+                        out.push_str(&index.to_string());
+                    }
+                }
+                i += 1 + len;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    (out, names)
+}
+
+fn is_name_start(c: Option<&char>) -> bool {
+    matches!(c, Some(ch) if ch.is_alphabetic() || *ch == '_')
+}
+
+fn is_name_char(c: Option<char>) -> bool {
+    // Also excludes array slices such as arr[1:n] from being read as placeholders
+    matches!(c, Some(ch) if ch.is_alphanumeric() || ch == '_')
+}
+
+/// Index of the next occurrence of `seq` at or after `from`
+fn find_seq(chars: &[char], from: usize, seq: &[char]) -> Option<usize> {
+    if from > chars.len() {
+        return None;
+    }
+    chars[from..].windows(seq.len()).position(|w| w == seq).map(|p| from + p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_styles() {
+        let sql = "SELECT * FROM t WHERE a = :a AND b = :b OR a = :a";
+        assert_eq!(rewrite_named(sql, Placeholder::Dollar),
+            ("SELECT * FROM t WHERE a = $1 AND b = $2 OR a = $1".to_string(), vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(rewrite_named(sql, Placeholder::Numbered).0, "SELECT * FROM t WHERE a = ?1 AND b = ?2 OR a = ?1");
+        assert_eq!(rewrite_named(sql, Placeholder::Question),
+            ("SELECT * FROM t WHERE a = ? AND b = ? OR a = ?".to_string(), vec!["a".to_string(), "b".to_string(), "a".to_string()]));
+    }
+
+    #[test]
+    fn test_rewrite_skips_literals_and_casts() {
+        let sql = "SELECT ':x', \"col:y\", id::text, $$ :z $$ FROM t -- :c\nWHERE id = :id /* :d */";
+        let (out, names) = rewrite_named(sql, Placeholder::Dollar);
+        assert_eq!(out, "SELECT ':x', \"col:y\", id::text, $$ :z $$ FROM t -- :c\nWHERE id = $1 /* :d */");
+        assert_eq!(names, vec!["id".to_string()]);
+    }
+
+    #[test]
+    fn test_rewrite_skips_array_slices() {
+        let (out, names) = rewrite_named("SELECT arr[1:n] FROM t WHERE x = :x", Placeholder::Dollar);
+        assert_eq!(out, "SELECT arr[1:n] FROM t WHERE x = $1");
+        assert_eq!(names, vec!["x".to_string()]);
+    }
+}
@@ -0,0 +1,284 @@
+// Connection pools shared by the database drivers
+//
+// `postgres.pool(url, max: 10)` (and the sqlite/mysql equivalents) keeps idle
+// connections around so code that runs per request - such as `quest serve`
+// handlers - can borrow one instead of reconnecting every time:
+//
+//     let pool = postgres.pool(url, max: 5)
+//     with pool.acquire() as conn
+//         conn.cursor().execute("SELECT 1")
+//     end
+//
+// A lease hands its connection back when released, when the `with` block
+// exits, or when the lease itself is dropped. Returned connections are rolled
+// back first so an uncommitted transaction never leaks into the next borrower.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, value_err};
+use crate::types::*;
+
+const DEFAULT_MAX_CONNECTIONS: usize = 10;
+
+/// Driver a pool opens connections with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Driver {
+    Sqlite,
+    Postgres,
+    Mysql,
+}
+
+impl Driver {
+    fn name(&self) -> &'static str {
+        match self {
+            Driver::Sqlite => "sqlite",
+            Driver::Postgres => "postgres",
+            Driver::Mysql => "mysql",
+        }
+    }
+
+    fn connect(&self, url: &str) -> Result<QValue, String> {
+        match self {
+            Driver::Sqlite => super::sqlite::connect(url),
+            Driver::Postgres => super::postgres::connect(url),
+            Driver::Mysql => super::mysql::connect(url),
+        }
+    }
+
+    /// Roll back any open transaction so the connection can be reused
+    fn reset(&self, conn: &QValue) -> Result<(), String> {
+        match conn {
+            QValue::SqliteConnection(c) => c.reset(),
+            QValue::PostgresConnection(c) => c.reset(),
+            QValue::MysqlConnection(c) => c.reset(),
+            other => Err(format!("ProgrammingError: Not a {} connection: {}", self.name(), other.as_obj().cls())),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PoolState {
+    driver: Driver,
+    url: String,
+    max: usize,
+    idle: Vec<QValue>,
+    in_use: usize,
+    closed: bool,
+}
+
+impl PoolState {
+    fn release(&mut self, conn: QValue) {
+        self.in_use -= 1;
+        // A connection that can't be reset is dropped; the pool opens a new one later
+        if !self.closed && self.driver.reset(&conn).is_ok() {
+            self.idle.push(conn);
+        }
+    }
+}
+
+/// Build a pool from `X.pool(url, max: n)` arguments. No connection is opened
+/// until the first acquire().
+pub fn create_pool(driver: Driver, mut args: Vec<QValue>) -> Result<QValue, String> {
+    let options = match args.last() {
+        Some(QValue::Dict(_)) if args.len() > 1 => args.pop(),
+        _ => None,
+    };
+    if args.len() != 1 {
+        return arg_err!("{}.pool expects 1 argument (url), got {}", driver.name(), args.len());
+    }
+    let max = match options.as_ref().and_then(|o| match o { QValue::Dict(d) => d.get("max"), _ => None }) {
+        None | Some(QValue::Nil(_)) => DEFAULT_MAX_CONNECTIONS,
+        Some(QValue::Int(n)) if n.value > 0 => n.value as usize,
+        Some(other) => return value_err!("{}.pool max must be a positive Int, got {}", driver.name(), other.as_str()),
+    };
+
+    Ok(QValue::ConnectionPool(QConnectionPool {
+        state: Rc::new(RefCell::new(PoolState {
+            driver,
+            url: args[0].as_str(),
+            max,
+            idle: Vec::new(),
+            in_use: 0,
+            closed: false,
+        })),
+        id: next_object_id(),
+    }))
+}
+
+#[derive(Debug, Clone)]
+pub struct QConnectionPool {
+    state: Rc<RefCell<PoolState>>,
+    pub id: u64,
+}
+
+impl QConnectionPool {
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        if !args.is_empty() {
+            return arg_err!("{}() expects 0 arguments, got {}", method_name, args.len());
+        }
+
+        match method_name {
+            "acquire" => {
+                let mut state = self.state.borrow_mut();
+                if state.closed {
+                    return Err("OperationalError: Connection pool is closed".to_string().into());
+                }
+                let conn = match state.idle.pop() {
+                    Some(conn) => conn,
+                    // Requests are handled one at a time per thread, so waiting
+                    // for a connection to come back would never finish
+                    None if state.in_use >= state.max => {
+                        return Err(format!("OperationalError: Connection pool exhausted ({} connections in use)", state.in_use).into());
+                    }
+                    None => state.driver.connect(&state.url)?,
+                };
+                state.in_use += 1;
+                drop(state);
+
+                Ok(QValue::PoolLease(QPoolLease {
+                    inner: Rc::new(LeaseInner {
+                        conn: RefCell::new(Some(conn)),
+                        pool: Rc::clone(&self.state),
+                    }),
+                    id: next_object_id(),
+                }))
+            }
+            "max" => Ok(QValue::Int(QInt::new(self.state.borrow().max as i64))),
+            "size" => {
+                let state = self.state.borrow();
+                Ok(QValue::Int(QInt::new((state.idle.len() + state.in_use) as i64)))
+            }
+            "idle" => Ok(QValue::Int(QInt::new(self.state.borrow().idle.len() as i64))),
+            "in_use" => Ok(QValue::Int(QInt::new(self.state.borrow().in_use as i64))),
+            "closed" => Ok(QValue::Bool(QBool::new(self.state.borrow().closed))),
+            "close" => {
+                // Idle connections close now, leased ones when they are returned
+                let mut state = self.state.borrow_mut();
+                state.closed = true;
+                state.idle.clear();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on ConnectionPool", method_name),
+        }
+    }
+}
+
+impl QObj for QConnectionPool {
+    fn cls(&self) -> String {
+        "ConnectionPool".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "ConnectionPool"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "ConnectionPool"
+    }
+
+    fn str(&self) -> String {
+        let state = self.state.borrow();
+        format!("<ConnectionPool {} {}/{}>", state.driver.name(), state.idle.len() + state.in_use, state.max)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Database connection pool".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+#[derive(Debug)]
+struct LeaseInner {
+    conn: RefCell<Option<QValue>>,
+    pool: Rc<RefCell<PoolState>>,
+}
+
+impl LeaseInner {
+    fn release(&self) {
+        if let Some(conn) = self.conn.borrow_mut().take() {
+            self.pool.borrow_mut().release(conn);
+        }
+    }
+}
+
+impl Drop for LeaseInner {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// A connection borrowed from a pool
+#[derive(Debug, Clone)]
+pub struct QPoolLease {
+    inner: Rc<LeaseInner>,
+    pub id: u64,
+}
+
+impl QPoolLease {
+    fn connection(&self) -> Result<QValue, EvalError> {
+        self.inner.conn.borrow().clone()
+            .ok_or_else(|| "OperationalError: Connection has already been returned to the pool".to_string().into())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        if !args.is_empty() {
+            return arg_err!("{}() expects 0 arguments, got {}", method_name, args.len());
+        }
+
+        match method_name {
+            "_enter" | "connection" => self.connection(),
+            "_exit" | "release" => {
+                self.inner.release();
+                Ok(QValue::Nil(QNil))
+            }
+            "released" => Ok(QValue::Bool(QBool::new(self.inner.conn.borrow().is_none()))),
+            _ => attr_err!("Unknown method '{}' on PoolLease", method_name),
+        }
+    }
+}
+
+impl QObj for QPoolLease {
+    fn cls(&self) -> String {
+        "PoolLease".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "PoolLease"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "PoolLease"
+    }
+
+    fn str(&self) -> String {
+        format!("<PoolLease {}>", self.id)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Connection borrowed from a ConnectionPool".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
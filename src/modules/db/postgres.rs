@@ -1,19 +1,62 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::{arg_err, attr_err, value_err};
 use std::sync::{Arc, Mutex};
-use postgres::{Client, Row, types::ToSql};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use postgres::{Client, Row, Statement, fallible_iterator::FallibleIterator, types::ToSql};
 use crate::types::*;
 use crate::scope::Scope;
+use super::params::{bind_params, Placeholder};
+use super::stream::{lock_conn, pull_rows, stream_option, QRowIterator, RowStream, SharedConn};
+use super::transaction::QTransaction;
 use chrono::{DateTime, Utc, NaiveDate, NaiveTime, NaiveDateTime};
 use pg_interval::Interval;
 use serde_json;
 use rust_decimal::prelude::*;
 
+/// Prepared statements kept per connection (same as the MySQL driver's default)
+const STATEMENT_CACHE_CAPACITY: usize = 32;
+
+/// PostgreSQL client plus its prepared statements, keyed by SQL text
+pub struct PgSession {
+    client: Client,
+    statements: IndexMap<String, Statement>,
+}
+
+impl PgSession {
+    fn new(client: Client) -> Self {
+        PgSession { client, statements: IndexMap::new() }
+    }
+
+    /// Prepare `sql`, reusing a cached statement. The least recently used
+    /// statement is evicted when the cache is full.
+    fn prepare(&mut self, sql: &str) -> Result<Statement, String> {
+        if let Some(stmt) = self.statements.shift_remove(sql) {
+            self.statements.insert(sql.to_string(), stmt.clone());
+            return Ok(stmt);
+        }
+        let stmt = self.client.prepare(sql)
+            .map_err(|e| map_postgres_error(e))?;
+        if self.statements.len() >= STATEMENT_CACHE_CAPACITY {
+            self.statements.shift_remove_index(0);
+        }
+        self.statements.insert(sql.to_string(), stmt.clone());
+        Ok(stmt)
+    }
+
+    /// Drop a cached statement that failed, e.g. after the schema changed
+    fn forget(&mut self, sql: &str) {
+        self.statements.shift_remove(sql);
+    }
+}
+
 /// Wrapper for PostgreSQL Client that implements QObj
 #[derive(Clone)]
 pub struct QPostgresConnection {
-    conn: Arc<Mutex<Client>>,
+    conn: Arc<SharedConn<PgSession>>,
+    /// Open `transaction()` blocks, shared by clones of this connection
+    tx_depth: Arc<AtomicUsize>,
     id: u64,
 }
 
@@ -28,11 +71,33 @@ impl std::fmt::Debug for QPostgresConnection {
 impl QPostgresConnection {
     pub fn new(conn: Client) -> Self {
         QPostgresConnection {
-            conn: Arc::new(Mutex::new(conn)),
+            conn: Arc::new(SharedConn::new(PgSession::new(conn))),
+            tx_depth: Arc::new(AtomicUsize::new(0)),
             id: next_object_id(),
         }
     }
 
+    /// Roll back any open transaction before the connection is reused
+    pub fn reset(&self) -> Result<(), String> {
+        self.tx_depth.store(0, Ordering::SeqCst);
+        let mut conn = lock_conn(&self.conn)?;
+        if conn.client.is_closed() {
+            return Err("OperationalError: Connection is closed".to_string());
+        }
+        conn.client.batch_execute("ROLLBACK")
+            .map_err(|e| format!("DatabaseError: {}", e))
+    }
+
+    /// Run one or more statements that return no rows
+    pub fn run_batch(&self, sql: &str) -> Result<(), String> {
+        let mut conn = lock_conn(&self.conn)?;
+        conn.client.batch_execute(sql).map_err(map_postgres_error)
+    }
+
+    pub fn tx_depth(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.tx_depth)
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "close" => {
@@ -41,21 +106,39 @@ impl QPostgresConnection {
             }
 
             "commit" => {
-                let mut conn = self.conn.lock().unwrap();
-                conn.batch_execute("COMMIT")
+                let mut conn = lock_conn(&self.conn)?;
+                conn.client.batch_execute("COMMIT")
                     .map_err(|e| format!("DatabaseError: {}", e))?;
                 Ok(QValue::Nil(QNil))
             }
 
             "rollback" => {
-                let mut conn = self.conn.lock().unwrap();
-                conn.batch_execute("ROLLBACK")
+                let mut conn = lock_conn(&self.conn)?;
+                conn.client.batch_execute("ROLLBACK")
                     .map_err(|e| format!("DatabaseError: {}", e))?;
                 Ok(QValue::Nil(QNil))
             }
 
+            "transaction" => {
+                if !args.is_empty() {
+                    return arg_err!("transaction expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Transaction(QTransaction::new(QValue::PostgresConnection(self.clone()))))
+            }
+
+            "execute_batch" => {
+                // Several `;`-separated statements, e.g. a migration or schema file
+                if args.len() != 1 {
+                    return arg_err!("execute_batch expects 1 argument (sql), got {}", args.len());
+                }
+                self.run_batch(&args[0].as_str())?;
+                Ok(QValue::Nil(QNil))
+            }
+
             "cursor" => {
-                Ok(QValue::PostgresCursor(QPostgresCursor::new(self.conn.clone())))
+                // cursor() / cursor(stream: true)
+                let stream = stream_option(&args)?;
+                Ok(QValue::PostgresCursor(QPostgresCursor::new(self.conn.clone(), stream)))
             }
 
             "execute" => {
@@ -69,7 +152,7 @@ impl QPostgresConnection {
                     None
                 };
 
-                let mut conn = self.conn.lock().unwrap();
+                let mut conn = lock_conn(&self.conn)?;
                 let count = execute_with_params(&mut conn, &sql, params)?;
                 Ok(QValue::Int(QInt::new(count as i64)))
             }
@@ -123,11 +206,14 @@ struct ColumnDescription {
 /// Wrapper for PostgreSQL cursor (connection + results)
 #[derive(Clone)]
 pub struct QPostgresCursor {
-    conn: Arc<Mutex<Client>>,
-    current_results: Arc<Mutex<Vec<HashMap<String, QValue>>>>,
+    conn: Arc<SharedConn<PgSession>>,
+    current_results: Arc<Mutex<Vec<IndexMap<String, QValue>>>>,
     position: Arc<Mutex<usize>>,
     row_count: Arc<Mutex<i64>>,
     description: Arc<Mutex<Option<Vec<ColumnDescription>>>>,
+    /// Stream SELECT results instead of buffering them (`cursor(stream: true)`)
+    streaming: bool,
+    stream: Arc<Mutex<Option<RowStream<Row>>>>,
     id: u64,
 }
 
@@ -141,17 +227,24 @@ impl std::fmt::Debug for QPostgresCursor {
 }
 
 impl QPostgresCursor {
-    pub fn new(conn: Arc<Mutex<Client>>) -> Self {
+    pub fn new(conn: Arc<SharedConn<PgSession>>, streaming: bool) -> Self {
         QPostgresCursor {
             conn,
             current_results: Arc::new(Mutex::new(Vec::new())),
             position: Arc::new(Mutex::new(0)),
             row_count: Arc::new(Mutex::new(-1)),
             description: Arc::new(Mutex::new(None)),
+            streaming,
+            stream: Arc::new(Mutex::new(None)),
             id: next_object_id(),
         }
     }
 
+    /// Rows from the open stream, or None when results are buffered
+    fn pull_stream(&self, limit: Option<usize>) -> Result<Option<Vec<QValue>>, String> {
+        pull_rows(&self.stream, limit, |row| row_to_dict(&row))
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "execute" => {
@@ -183,7 +276,7 @@ impl QPostgresCursor {
                 let params_elements = params_seq.elements.borrow();
                 for params in params_elements.iter() {
                     let count = {
-                        let mut conn = self.conn.lock().unwrap();
+                        let mut conn = lock_conn(&self.conn)?;
                         execute_with_params(&mut conn, &sql, Some(params))?
                     };
                     total_count += count;
@@ -194,6 +287,10 @@ impl QPostgresCursor {
             }
 
             "fetch_one" => {
+                if let Some(mut rows) = self.pull_stream(Some(1))? {
+                    return Ok(rows.pop().unwrap_or(QValue::Nil(QNil)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -213,6 +310,10 @@ impl QPostgresCursor {
                     args[0].as_num()? as usize
                 };
 
+                if let Some(rows) = self.pull_stream(Some(size))? {
+                    return Ok(QValue::Array(QArray::new(rows)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -227,6 +328,10 @@ impl QPostgresCursor {
             }
 
             "fetch_all" => {
+                if let Some(rows) = self.pull_stream(None)? {
+                    return Ok(QValue::Array(QArray::new(rows)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -239,8 +344,16 @@ impl QPostgresCursor {
                 Ok(QValue::Array(QArray::new(rows)))
             }
 
+            "iter" => {
+                if !args.is_empty() {
+                    return arg_err!("iter expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::RowIterator(QRowIterator::new(QValue::PostgresCursor(self.clone()))))
+            }
+
             "close" => {
-                // Clear results
+                // Clear results and release the connection if streaming
+                *self.stream.lock().unwrap() = None;
                 self.current_results.lock().unwrap().clear();
                 *self.position.lock().unwrap() = 0;
                 *self.row_count.lock().unwrap() = -1;
@@ -253,7 +366,7 @@ impl QPostgresCursor {
                 match &*desc {
                     Some(columns) => {
                         let result: Vec<QValue> = columns.iter().map(|col| {
-                            let mut map = HashMap::new();
+                            let mut map = IndexMap::new();
                             map.insert("name".to_string(), QValue::Str(QString::new(col.name.clone())));
                             map.insert("type_code".to_string(), QValue::Str(QString::new(col.type_code.clone())));
                             map.insert("display_size".to_string(), QValue::Nil(QNil));
@@ -283,11 +396,18 @@ impl QPostgresCursor {
     }
 
     fn execute_internal(&self, sql: &str, params: Option<&QValue>) -> Result<(), String> {
-        let mut conn = self.conn.lock().unwrap();
+        // A new statement ends any result set this cursor is still streaming
+        *self.stream.lock().unwrap() = None;
 
         // Check if this is a SELECT query
         let is_query = sql.trim().to_uppercase().starts_with("SELECT");
 
+        if is_query && self.streaming {
+            return self.start_stream(sql, params);
+        }
+
+        let mut conn = lock_conn(&self.conn)?;
+
         if is_query {
             // Execute query and fetch all results with column metadata
             let (rows, columns) = query_with_params_and_metadata(&mut conn, sql, params)?;
@@ -309,6 +429,41 @@ impl QPostgresCursor {
 
         Ok(())
     }
+
+    /// Run a query on a worker thread that keeps its result set open
+    fn start_stream(&self, sql: &str, params: Option<&QValue>) -> Result<(), String> {
+        let (sql, params) = bind_params(sql, params, Placeholder::Dollar)?;
+        let pg_params = to_pg_params(params.as_deref().unwrap_or(&[]))?;
+        let (columns, stream) = RowStream::start(&self.conn, move |conn, sender| {
+            let stmt = conn.prepare(&sql)?;
+            let columns = column_descriptions(&stmt);
+
+            // Rows arrive from the server as the iterator advances
+            let params = pg_params.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync));
+            let err = match conn.client.query_raw(&stmt, params) {
+                Ok(mut rows) => {
+                    sender.ready(columns);
+                    while let Some(row) = rows.next().map_err(map_postgres_error)? {
+                        if !sender.send(row) {
+                            break;
+                        }
+                    }
+                    return Ok(());
+                }
+                Err(e) => map_postgres_error(e),
+            };
+            conn.forget(&sql);
+            Err(err)
+        })?;
+
+        // The row count isn't known until every row has been read
+        *self.row_count.lock().unwrap() = -1;
+        *self.description.lock().unwrap() = Some(columns);
+        self.current_results.lock().unwrap().clear();
+        *self.position.lock().unwrap() = 0;
+        *self.stream.lock().unwrap() = Some(stream);
+        Ok(())
+    }
 }
 
 impl QObj for QPostgresCursor {
@@ -563,7 +718,7 @@ fn json_to_qvalue(value: &serde_json::Value) -> QValue {
             QValue::Array(QArray::new(elements))
         }
         serde_json::Value::Object(obj) => {
-            let mut dict = HashMap::new();
+            let mut dict = IndexMap::new();
             for (key, val) in obj {
                 dict.insert(key.clone(), json_to_qvalue(val));
             }
@@ -597,7 +752,7 @@ fn array_element_type(arr: &QArray) -> Option<&'static str> {
 // =============================================================================
 
 /// Convert QValue to PostgreSQL parameter
-fn qvalue_to_pg_param(value: &QValue) -> Result<Box<dyn ToSql + Sync>, String> {
+fn qvalue_to_pg_param(value: &QValue) -> Result<Box<dyn ToSql + Sync + Send>, String> {
     match value {
         // For NULL, we use Option<String> as it's the most flexible type for PostgreSQL's implicit casting
         QValue::Nil(_) => Ok(Box::new(None::<String>)),
@@ -681,71 +836,39 @@ fn qvalue_to_pg_param(value: &QValue) -> Result<Box<dyn ToSql + Sync>, String> {
 }
 
 /// Execute statement with parameters
-fn execute_with_params(conn: &mut Client, sql: &str, params: Option<&QValue>) -> Result<u64, String> {
-    if let Some(params_value) = params {
-        match params_value {
-            QValue::Array(arr) => {
-                // Positional parameters ($1, $2, etc)
-                let pg_params: Result<Vec<Box<dyn ToSql + Sync>>, String> = arr.elements.borrow().iter()
-                    .map(qvalue_to_pg_param)
-                    .collect();
-                let pg_params = pg_params?;
-                let params_refs: Vec<&(dyn ToSql + Sync)> = pg_params.iter()
-                    .map(|p| p.as_ref())
-                    .collect();
-
-                conn.execute(sql, params_refs.as_slice())
-                    .map_err(|e| map_postgres_error(e))
-            }
-            _ => Err("PostgreSQL only supports positional parameters (arrays)".into())
-        }
-    } else {
-        conn.execute(sql, &[])
-            .map_err(|e| map_postgres_error(e))
-    }
+fn execute_with_params(conn: &mut PgSession, sql: &str, params: Option<&QValue>) -> Result<u64, String> {
+    // Positional parameters ($1, $2, etc) or a dict for :name placeholders
+    let (sql, params) = bind_params(sql, params, Placeholder::Dollar)?;
+    let pg_params = to_pg_params(params.as_deref().unwrap_or(&[]))?;
+    let params_refs: Vec<&(dyn ToSql + Sync)> = pg_params.iter()
+        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+        .collect();
+
+    let stmt = conn.prepare(&sql)?;
+    conn.client.execute(&stmt, params_refs.as_slice())
+        .map_err(|e| {
+            conn.forget(&sql);
+            map_postgres_error(e)
+        })
 }
 
 /// Query with parameters and return rows with column metadata
-fn query_with_params_and_metadata(conn: &mut Client, sql: &str, params: Option<&QValue>) -> Result<(Vec<HashMap<String, QValue>>, Vec<ColumnDescription>), String> {
-    // Execute the query without prepared statement to allow type flexibility
-    let rows = if let Some(params_value) = params {
-        match params_value {
-            QValue::Array(arr) => {
-                // Positional parameters
-                let pg_params: Result<Vec<Box<dyn ToSql + Sync>>, String> = arr.elements.borrow().iter()
-                    .map(qvalue_to_pg_param)
-                    .collect();
-                let pg_params = pg_params?;
-                let params_refs: Vec<&(dyn ToSql + Sync)> = pg_params.iter()
-                    .map(|p| p.as_ref())
-                    .collect();
-
-                conn.query(sql, params_refs.as_slice())
-                    .map_err(|e| map_postgres_error(e))?
-            }
-            _ => return Err("PostgreSQL only supports positional parameters (arrays)".into())
-        }
-    } else {
-        conn.query(sql, &[])
-            .map_err(|e| map_postgres_error(e))?
-    };
-
-    // Get column metadata from the first row if available
-    // If no rows, we'll have empty column metadata
-    let columns: Vec<ColumnDescription> = if let Some(first_row) = rows.first() {
-        first_row.columns().iter().map(|col| ColumnDescription {
-            name: col.name().to_string(),
-            type_code: format!("{:?}", col.type_()),
-        }).collect()
-    } else {
-        // For empty result sets, prepare the statement to get column info
-        let stmt = conn.prepare(sql)
-            .map_err(|e| map_postgres_error(e))?;
-        stmt.columns().iter().map(|col| ColumnDescription {
-            name: col.name().to_string(),
-            type_code: format!("{:?}", col.type_()),
-        }).collect()
-    };
+fn query_with_params_and_metadata(conn: &mut PgSession, sql: &str, params: Option<&QValue>) -> Result<(Vec<IndexMap<String, QValue>>, Vec<ColumnDescription>), String> {
+    let (sql, params) = bind_params(sql, params, Placeholder::Dollar)?;
+    let pg_params = to_pg_params(params.as_deref().unwrap_or(&[]))?;
+    let params_refs: Vec<&(dyn ToSql + Sync)> = pg_params.iter()
+        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+        .collect();
+
+    let stmt = conn.prepare(&sql)?;
+    let rows = conn.client.query(&stmt, params_refs.as_slice())
+        .map_err(|e| {
+            conn.forget(&sql);
+            map_postgres_error(e)
+        })?;
+
+    // Column metadata comes from the prepared statement, so it is available for empty results too
+    let columns = column_descriptions(&stmt);
 
     let mut results = Vec::new();
     for row in rows {
@@ -754,9 +877,20 @@ fn query_with_params_and_metadata(conn: &mut Client, sql: &str, params: Option<&
     Ok((results, columns))
 }
 
+fn to_pg_params(values: &[QValue]) -> Result<Vec<Box<dyn ToSql + Sync + Send>>, String> {
+    values.iter().map(qvalue_to_pg_param).collect()
+}
+
+fn column_descriptions(stmt: &Statement) -> Vec<ColumnDescription> {
+    stmt.columns().iter().map(|col| ColumnDescription {
+        name: col.name().to_string(),
+        type_code: format!("{:?}", col.type_()),
+    }).collect()
+}
+
 /// Convert PostgreSQL row to Quest dict
-fn row_to_dict(row: &Row) -> Result<HashMap<String, QValue>, String> {
-    let mut dict = HashMap::new();
+fn row_to_dict(row: &Row) -> Result<IndexMap<String, QValue>, String> {
+    let mut dict = IndexMap::new();
 
     for (idx, column) in row.columns().iter().enumerate() {
         let col_name = column.name().to_string();
@@ -883,9 +1017,22 @@ pub fn create_postgres_module() -> QValue {
         id: next_object_id(),
     }));
 
+    members.insert("pool".to_string(), QValue::Fun(QFun {
+        name: "pool".to_string(),
+        parent_type: "postgres".to_string(),
+        id: next_object_id(),
+    }));
+
     QValue::Module(Box::new(QModule::new("postgres".to_string(), members)))
 }
 
+/// Connect using a libpq-style connection string
+pub fn connect(conn_str: &str) -> Result<QValue, String> {
+    let conn = Client::connect(conn_str, postgres::NoTls)
+        .map_err(|e| format!("DatabaseError: Failed to connect to database: {}", e))?;
+    Ok(QValue::PostgresConnection(QPostgresConnection::new(conn)))
+}
+
 /// Call postgres module functions
 pub fn call_postgres_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
@@ -893,12 +1040,12 @@ pub fn call_postgres_function(func_name: &str, args: Vec<QValue>, _scope: &mut S
             if args.len() != 1 {
                 return arg_err!("postgres.connect expects 1 argument (connection_string), got {}", args.len());
             }
-            let conn_str = args[0].as_str();
-
-            let conn = Client::connect(&conn_str, postgres::NoTls)
-                .map_err(|e| format!("DatabaseError: Failed to connect to database: {}", e))?;
+            Ok(connect(&args[0].as_str())?)
+        }
 
-            Ok(QValue::PostgresConnection(QPostgresConnection::new(conn)))
+        "postgres.pool" => {
+            // postgres.pool(url, max: 10)
+            Ok(super::pool::create_pool(super::pool::Driver::Postgres, args)?)
         }
 
         _ => attr_err!("Unknown function: {}", func_name)
@@ -1,26 +1,55 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::{arg_err, attr_err, value_err};
 use std::sync::{Arc, Mutex};
-use rusqlite::{Connection, Row, Statement, ToSql, types::ValueRef};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rusqlite::{Connection, Row, Statement, ToSql, types::{Value, ValueRef}};
 use crate::types::*;
 use crate::scope::Scope;
+use super::params::{bind_params, Placeholder};
+use super::stream::{lock_conn, pull_rows, stream_option, QRowIterator, RowStream, SharedConn};
+use super::transaction::QTransaction;
 
 /// Wrapper for SQLite Connection that implements QObj
 #[derive(Debug, Clone)]
 pub struct QSqliteConnection {
-    conn: Arc<Mutex<Connection>>,
+    conn: Arc<SharedConn<Connection>>,
+    /// Open `transaction()` blocks, shared by clones of this connection
+    tx_depth: Arc<AtomicUsize>,
     id: u64,
 }
 
 impl QSqliteConnection {
     pub fn new(conn: Connection) -> Self {
         QSqliteConnection {
-            conn: Arc::new(Mutex::new(conn)),
+            conn: Arc::new(SharedConn::new(conn)),
+            tx_depth: Arc::new(AtomicUsize::new(0)),
             id: next_object_id(),
         }
     }
 
+    /// Roll back any open transaction before the connection is reused
+    pub fn reset(&self) -> Result<(), String> {
+        self.tx_depth.store(0, Ordering::SeqCst);
+        let conn = lock_conn(&self.conn)?;
+        if !conn.is_autocommit() {
+            conn.execute_batch("ROLLBACK")
+                .map_err(|e| format!("DatabaseError: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Run one or more statements that return no rows
+    pub fn run_batch(&self, sql: &str) -> Result<(), String> {
+        let conn = lock_conn(&self.conn)?;
+        conn.execute_batch(sql).map_err(map_sqlite_error)
+    }
+
+    pub fn tx_depth(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.tx_depth)
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "close" => {
@@ -29,21 +58,39 @@ impl QSqliteConnection {
             }
 
             "commit" => {
-                let conn = self.conn.lock().unwrap();
+                let conn = lock_conn(&self.conn)?;
                 conn.execute_batch("COMMIT")
                     .map_err(|e| format!("DatabaseError: {}", e))?;
                 Ok(QValue::Nil(QNil))
             }
 
             "rollback" => {
-                let conn = self.conn.lock().unwrap();
+                let conn = lock_conn(&self.conn)?;
                 conn.execute_batch("ROLLBACK")
                     .map_err(|e| format!("DatabaseError: {}", e))?;
                 Ok(QValue::Nil(QNil))
             }
 
+            "transaction" => {
+                if !args.is_empty() {
+                    return arg_err!("transaction expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Transaction(QTransaction::new(QValue::SqliteConnection(self.clone()))))
+            }
+
+            "execute_batch" => {
+                // Several `;`-separated statements, e.g. a migration or schema file
+                if args.len() != 1 {
+                    return arg_err!("execute_batch expects 1 argument (sql), got {}", args.len());
+                }
+                self.run_batch(&args[0].as_str())?;
+                Ok(QValue::Nil(QNil))
+            }
+
             "cursor" => {
-                Ok(QValue::SqliteCursor(QSqliteCursor::new(self.conn.clone())))
+                // cursor() / cursor(stream: true)
+                let stream = stream_option(&args)?;
+                Ok(QValue::SqliteCursor(QSqliteCursor::new(self.conn.clone(), stream)))
             }
 
             "execute" => {
@@ -57,7 +104,7 @@ impl QSqliteConnection {
                     None
                 };
 
-                let mut conn = self.conn.lock().unwrap();
+                let mut conn = lock_conn(&self.conn)?;
                 let count = execute_with_params(&mut conn, &sql, params)?;
                 Ok(QValue::Int(QInt::new(count as i64)))
             }
@@ -111,26 +158,37 @@ struct ColumnDescription {
 /// Wrapper for SQLite cursor (statement + results)
 #[derive(Debug, Clone)]
 pub struct QSqliteCursor {
-    conn: Arc<Mutex<Connection>>,
-    current_results: Arc<Mutex<Vec<HashMap<String, QValue>>>>,
+    conn: Arc<SharedConn<Connection>>,
+    current_results: Arc<Mutex<Vec<IndexMap<String, QValue>>>>,
     position: Arc<Mutex<usize>>,
     row_count: Arc<Mutex<i64>>,
     description: Arc<Mutex<Option<Vec<ColumnDescription>>>>,
+    /// Stream SELECT results instead of buffering them (`cursor(stream: true)`)
+    streaming: bool,
+    stream: Arc<Mutex<Option<RowStream<Vec<Value>>>>>,
     id: u64,
 }
 
 impl QSqliteCursor {
-    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+    pub fn new(conn: Arc<SharedConn<Connection>>, streaming: bool) -> Self {
         QSqliteCursor {
             conn,
             current_results: Arc::new(Mutex::new(Vec::new())),
             position: Arc::new(Mutex::new(0)),
             row_count: Arc::new(Mutex::new(-1)),
             description: Arc::new(Mutex::new(None)),
+            streaming,
+            stream: Arc::new(Mutex::new(None)),
             id: next_object_id(),
         }
     }
 
+    /// Rows from the open stream, or None when results are buffered
+    fn pull_stream(&self, limit: Option<usize>) -> Result<Option<Vec<QValue>>, String> {
+        let columns = self.description.lock().unwrap().clone().unwrap_or_default();
+        pull_rows(&self.stream, limit, |values| values_to_dict(values, &columns))
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "execute" => {
@@ -175,7 +233,7 @@ impl QSqliteCursor {
                 let elements = params_seq.elements.borrow();
                 for params in elements.iter() {
                     let count = {
-                        let mut conn = self.conn.lock().unwrap();
+                        let mut conn = lock_conn(&self.conn)?;
                         execute_with_params(&mut conn, &sql, Some(params))?
                     };
                     total_count += count;
@@ -214,6 +272,10 @@ impl QSqliteCursor {
                     }
                 }
 
+                if let Some(mut rows) = self.pull_stream(Some(1))? {
+                    return Ok(rows.pop().unwrap_or(QValue::Nil(QNil)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -233,6 +295,10 @@ impl QSqliteCursor {
                     args[0].as_num()? as usize
                 };
 
+                if let Some(rows) = self.pull_stream(Some(size))? {
+                    return Ok(QValue::Array(QArray::new(rows)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -275,6 +341,10 @@ impl QSqliteCursor {
                     }
                 }
 
+                if let Some(rows) = self.pull_stream(None)? {
+                    return Ok(QValue::Array(QArray::new(rows)));
+                }
+
                 let mut pos = self.position.lock().unwrap();
                 let results = self.current_results.lock().unwrap();
 
@@ -287,8 +357,16 @@ impl QSqliteCursor {
                 Ok(QValue::Array(QArray::new(rows)))
             }
 
+            "iter" => {
+                if !args.is_empty() {
+                    return arg_err!("iter expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::RowIterator(QRowIterator::new(QValue::SqliteCursor(self.clone()))))
+            }
+
             "close" => {
-                // Clear results
+                // Clear results and release the connection if streaming
+                *self.stream.lock().unwrap() = None;
                 self.current_results.lock().unwrap().clear();
                 *self.position.lock().unwrap() = 0;
                 *self.row_count.lock().unwrap() = -1;
@@ -301,7 +379,7 @@ impl QSqliteCursor {
                 match &*desc {
                     Some(columns) => {
                         let result: Vec<QValue> = columns.iter().map(|col| {
-                            let mut map = HashMap::new();
+                            let mut map = IndexMap::new();
                             map.insert("name".to_string(), QValue::Str(QString::new(col.name.clone())));
                             map.insert("type_code".to_string(), QValue::Str(QString::new(col.type_code.clone())));
                             map.insert("display_size".to_string(), QValue::Nil(QNil));
@@ -331,35 +409,27 @@ impl QSqliteCursor {
     }
 
     fn execute_internal(&self, sql: &str, params: Option<&QValue>) -> Result<(), String> {
-        let mut conn = self.conn.lock().unwrap();
+        // A new statement ends any result set this cursor is still streaming
+        *self.stream.lock().unwrap() = None;
 
         // Check if this is a SELECT query
         let is_query = sql.trim().to_uppercase().starts_with("SELECT");
 
+        if is_query && self.streaming {
+            return self.start_stream(sql, params);
+        }
+
+        let mut conn = lock_conn(&self.conn)?;
+
         if is_query {
             // Execute query and fetch all results
-            let mut stmt = conn.prepare(sql)
+            let (sql, params) = bind_params(sql, params, Placeholder::Numbered)?;
+            let mut stmt = conn.prepare_cached(&sql)
                 .map_err(|e| format!("ProgrammingError: {}", e))?;
-
-            // Get column names and types
-            let column_count = stmt.column_count();
-            let mut columns = Vec::new();
-            for i in 0..column_count {
-                let name = stmt.column_name(i)
-                    .map_err(|e| format!("DatabaseError: {}", e))?
-                    .to_string();
-                columns.push(ColumnDescription {
-                    name,
-                    type_code: "TEXT".to_string(), // SQLite is dynamically typed
-                });
-            }
+            let columns = column_descriptions(&stmt)?;
 
             // Execute and collect results
-            let rows = if let Some(params_value) = params {
-                query_with_params(&mut stmt, params_value, &columns)?
-            } else {
-                query_without_params(&mut stmt, &columns)?
-            };
+            let rows = query_with_params(&mut stmt, params.as_deref().unwrap_or(&[]), &columns)?;
 
             let row_count = rows.len() as i64;
 
@@ -379,6 +449,43 @@ impl QSqliteCursor {
 
         Ok(())
     }
+
+    /// Run a query on a worker thread that keeps its result set open
+    fn start_stream(&self, sql: &str, params: Option<&QValue>) -> Result<(), String> {
+        let (sql, params) = bind_params(sql, params, Placeholder::Numbered)?;
+        let sql_params = to_sql_params(params.as_deref().unwrap_or(&[]))?;
+        let (columns, stream) = RowStream::start(&self.conn, move |conn, sender| {
+            let mut stmt = conn.prepare_cached(&sql)
+                .map_err(|e| format!("ProgrammingError: {}", e))?;
+            let columns = column_descriptions(&stmt)?;
+            let column_count = columns.len();
+            let params_refs: Vec<&dyn ToSql> = sql_params.iter()
+                .map(|p| p.as_ref() as &dyn ToSql)
+                .collect();
+
+            let mut rows = stmt.query(params_refs.as_slice())
+                .map_err(map_sqlite_error)?;
+            sender.ready(columns);
+            while let Some(row) = rows.next().map_err(map_sqlite_error)? {
+                let values = (0..column_count)
+                    .map(|i| row.get_ref(i).map(Value::from))
+                    .collect::<Result<Vec<Value>, _>>()
+                    .map_err(|e| format!("DatabaseError: {}", e))?;
+                if !sender.send(values) {
+                    break;
+                }
+            }
+            Ok(())
+        })?;
+
+        // The row count isn't known until every row has been read
+        *self.row_count.lock().unwrap() = -1;
+        *self.description.lock().unwrap() = Some(columns);
+        self.current_results.lock().unwrap().clear();
+        *self.position.lock().unwrap() = 0;
+        *self.stream.lock().unwrap() = Some(stream);
+        Ok(())
+    }
 }
 
 impl QObj for QSqliteCursor {
@@ -412,12 +519,12 @@ impl QObj for QSqliteCursor {
 }
 
 /// Convert QValue to SQLite parameter
-fn qvalue_to_sql_param(value: &QValue) -> Result<Box<dyn ToSql>, String> {
+fn qvalue_to_sql_param(value: &QValue) -> Result<Box<dyn ToSql + Send>, String> {
     match value {
         QValue::Nil(_) => Ok(Box::new(rusqlite::types::Null)),
         QValue::Int(i) => Ok(Box::new(i.value)),
         QValue::Float(f) => Ok(Box::new(f.value)),
-        QValue::Str(s) => Ok(Box::new(s.value.clone())),
+        QValue::Str(s) => Ok(Box::new(s.value.as_ref().clone())),
         QValue::Bool(b) => Ok(Box::new(if b.value { 1i64 } else { 0i64 })),
         QValue::Bytes(b) => Ok(Box::new(b.data.clone())),
         _ => value_err!("Cannot convert {} to SQL parameter", value.q_type())
@@ -426,108 +533,27 @@ fn qvalue_to_sql_param(value: &QValue) -> Result<Box<dyn ToSql>, String> {
 
 /// Execute statement with parameters
 fn execute_with_params(conn: &mut Connection, sql: &str, params: Option<&QValue>) -> Result<usize, String> {
-    if let Some(params_value) = params {
-        match params_value {
-            QValue::Array(arr) => {
-                // Positional parameters
-                let elements = arr.elements.borrow();
-                let sql_params: Result<Vec<Box<dyn ToSql>>, String> = elements.iter()
-                    .map(qvalue_to_sql_param)
-                    .collect();
-                let sql_params = sql_params?;
-                let params_refs: Vec<&dyn ToSql> = sql_params.iter()
-                    .map(|p| p.as_ref())
-                    .collect();
-
-                conn.execute(sql, params_refs.as_slice())
-                    .map_err(|e| map_sqlite_error(e))
-            }
-            QValue::Dict(dict) => {
-                // Named parameters
-                let mut stmt = conn.prepare(sql)
-                    .map_err(|e| format!("ProgrammingError: {}", e))?;
-
-                let mut named_params: Vec<(String, Box<dyn ToSql>)> = Vec::new();
-                for (key, value) in dict.map.borrow().iter() {
-                    let param_name = if key.starts_with(':') {
-                        key.clone()
-                    } else {
-                        format!(":{}", key)
-                    };
-                    named_params.push((param_name, qvalue_to_sql_param(value)?));
-                }
-
-                // Convert to &str and &dyn ToSql
-                let params_refs: Vec<(&str, &dyn ToSql)> = named_params.iter()
-                    .map(|(name, value)| (name.as_str(), value.as_ref()))
-                    .collect();
-
-                stmt.execute(params_refs.as_slice())
-                    .map_err(|e| map_sqlite_error(e))
-            }
-            _ => Err("Parameters must be an array or dict".into())
-        }
-    } else {
-        conn.execute(sql, [])
-            .map_err(|e| map_sqlite_error(e))
-    }
+    let (sql, params) = bind_params(sql, params, Placeholder::Numbered)?;
+    let sql_params = to_sql_params(params.as_deref().unwrap_or(&[]))?;
+    let params_refs: Vec<&dyn ToSql> = sql_params.iter()
+        .map(|p| p.as_ref() as &dyn ToSql)
+        .collect();
+
+    // Statements are cached per connection, keyed by SQL text
+    let mut stmt = conn.prepare_cached(&sql)
+        .map_err(|e| format!("ProgrammingError: {}", e))?;
+    stmt.execute(params_refs.as_slice())
+        .map_err(|e| map_sqlite_error(e))
 }
 
-/// Query with parameters and return rows
-fn query_with_params(stmt: &mut Statement, params: &QValue, columns: &[ColumnDescription]) -> Result<Vec<HashMap<String, QValue>>, String> {
-    match params {
-        QValue::Array(arr) => {
-            // Positional parameters
-            let elements = arr.elements.borrow();
-            let sql_params: Result<Vec<Box<dyn ToSql>>, String> = elements.iter()
-                .map(qvalue_to_sql_param)
-                .collect();
-            let sql_params = sql_params?;
-            let params_refs: Vec<&dyn ToSql> = sql_params.iter()
-                .map(|p| p.as_ref())
-                .collect();
-
-            let mut rows_result = stmt.query(params_refs.as_slice())
-                .map_err(|e| map_sqlite_error(e))?;
+/// Query with positional parameters and return rows
+fn query_with_params(stmt: &mut Statement, params: &[QValue], columns: &[ColumnDescription]) -> Result<Vec<IndexMap<String, QValue>>, String> {
+    let sql_params = to_sql_params(params)?;
+    let params_refs: Vec<&dyn ToSql> = sql_params.iter()
+        .map(|p| p.as_ref() as &dyn ToSql)
+        .collect();
 
-            let mut results = Vec::new();
-            while let Some(row) = rows_result.next().map_err(|e| map_sqlite_error(e))? {
-                results.push(row_to_dict(row, columns)?);
-            }
-            Ok(results)
-        }
-        QValue::Dict(dict) => {
-            // Named parameters
-            let mut named_params: Vec<(String, Box<dyn ToSql>)> = Vec::new();
-            for (key, value) in dict.map.borrow().iter() {
-                let param_name = if key.starts_with(':') {
-                    key.clone()
-                } else {
-                    format!(":{}", key)
-                };
-                named_params.push((param_name, qvalue_to_sql_param(value)?));
-            }
-
-            let params_refs: Vec<(&str, &dyn ToSql)> = named_params.iter()
-                .map(|(name, value)| (name.as_str(), value.as_ref()))
-                .collect();
-
-            let mut rows_result = stmt.query(params_refs.as_slice())
-                .map_err(|e| map_sqlite_error(e))?;
-
-            let mut results = Vec::new();
-            while let Some(row) = rows_result.next().map_err(|e| map_sqlite_error(e))? {
-                results.push(row_to_dict(row, columns)?);
-            }
-            Ok(results)
-        }
-        _ => Err("Parameters must be an array or dict".into())
-    }
-}
-
-/// Query without parameters and return rows
-fn query_without_params(stmt: &mut Statement, columns: &[ColumnDescription]) -> Result<Vec<HashMap<String, QValue>>, String> {
-    let mut rows_result = stmt.query([])
+    let mut rows_result = stmt.query(params_refs.as_slice())
         .map_err(|e| map_sqlite_error(e))?;
 
     let mut results = Vec::new();
@@ -537,28 +563,60 @@ fn query_without_params(stmt: &mut Statement, columns: &[ColumnDescription]) ->
     Ok(results)
 }
 
+fn to_sql_params(values: &[QValue]) -> Result<Vec<Box<dyn ToSql + Send>>, String> {
+    values.iter().map(qvalue_to_sql_param).collect()
+}
+
+/// Column names of a prepared statement
+fn column_descriptions(stmt: &Statement) -> Result<Vec<ColumnDescription>, String> {
+    let mut columns = Vec::new();
+    for i in 0..stmt.column_count() {
+        let name = stmt.column_name(i)
+            .map_err(|e| format!("DatabaseError: {}", e))?
+            .to_string();
+        columns.push(ColumnDescription {
+            name,
+            type_code: "TEXT".to_string(), // SQLite is dynamically typed
+        });
+    }
+    Ok(columns)
+}
+
 /// Convert SQLite row to Quest dict
-fn row_to_dict(row: &Row, columns: &[ColumnDescription]) -> Result<HashMap<String, QValue>, String> {
-    let mut dict = HashMap::new();
+fn row_to_dict(row: &Row, columns: &[ColumnDescription]) -> Result<IndexMap<String, QValue>, String> {
+    let mut dict = IndexMap::new();
 
     for (idx, col) in columns.iter().enumerate() {
-        let value = match row.get_ref(idx).map_err(|e| format!("DatabaseError: {}", e))? {
-            ValueRef::Null => QValue::Nil(QNil),
-            ValueRef::Integer(i) => QValue::Int(QInt::new(i)),
-            ValueRef::Real(f) => QValue::Float(QFloat::new(f)),
-            ValueRef::Text(s) => {
-                let string = String::from_utf8(s.to_vec())
-                    .map_err(|e| format!("UTF-8 error: {}", e))?;
-                QValue::Str(QString::new(string))
-            }
-            ValueRef::Blob(b) => QValue::Bytes(QBytes::new(b.to_vec())),
-        };
-        dict.insert(col.name.clone(), value);
+        let value = row.get_ref(idx).map_err(|e| format!("DatabaseError: {}", e))?;
+        dict.insert(col.name.clone(), value_to_qvalue(value)?);
     }
 
     Ok(dict)
 }
 
+/// Convert a row read by a streaming cursor to a Quest dict
+fn values_to_dict(values: Vec<Value>, columns: &[ColumnDescription]) -> Result<IndexMap<String, QValue>, String> {
+    let mut dict = IndexMap::new();
+    for (value, col) in values.iter().zip(columns) {
+        dict.insert(col.name.clone(), value_to_qvalue(ValueRef::from(value))?);
+    }
+    Ok(dict)
+}
+
+fn value_to_qvalue(value: ValueRef) -> Result<QValue, String> {
+    Ok(match value {
+        ValueRef::Null => QValue::Nil(QNil),
+        ValueRef::Integer(i) => QValue::Int(QInt::new(i)),
+        ValueRef::Real(f) => QValue::Float(QFloat::new(f)),
+        ValueRef::Text(s) => {
+            let string = String::from_utf8(s.to_vec())
+                .map_err(|e| format!("UTF-8 error: {}", e))?;
+            QValue::Str(QString::new(string))
+        }
+        ValueRef::Blob(b) => QValue::Bytes(QBytes::new(b.to_vec())),
+    })
+}
+
 /// Map rusqlite errors to QEP-001 exception hierarchy
 fn map_sqlite_error(err: rusqlite::Error) -> String {
     match err {
@@ -599,9 +657,22 @@ pub fn create_sqlite_module() -> QValue {
         id: next_object_id(),
     }));
 
+    members.insert("pool".to_string(), QValue::Fun(QFun {
+        name: "pool".to_string(),
+        parent_type: "sqlite".to_string(),
+        id: next_object_id(),
+    }));
+
     QValue::Module(Box::new(QModule::new("sqlite".to_string(), members)))
 }
 
+/// Open a database file (or ":memory:")
+pub fn connect(path: &str) -> Result<QValue, String> {
+    let conn = Connection::open(path)
+        .map_err(|e| format!("DatabaseError: Failed to open database: {}", e))?;
+    Ok(QValue::SqliteConnection(QSqliteConnection::new(conn)))
+}
+
 /// Call sqlite module functions
 pub fn call_sqlite_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
@@ -609,12 +680,12 @@ pub fn call_sqlite_function(func_name: &str, args: Vec<QValue>, _scope: &mut Sco
             if args.len() != 1 {
                 return arg_err!("sqlite.connect expects 1 argument (path), got {}", args.len());
             }
-            let path = args[0].as_str();
-
-            let conn = Connection::open(&path)
-                .map_err(|e| format!("DatabaseError: Failed to open database: {}", e))?;
+            Ok(connect(&args[0].as_str())?)
+        }
 
-            Ok(QValue::SqliteConnection(QSqliteConnection::new(conn)))
+        "sqlite.pool" => {
+            // sqlite.pool(path, max: 10)
+            Ok(super::pool::create_pool(super::pool::Driver::Sqlite, args)?)
         }
 
         "sqlite.version" => {
@@ -0,0 +1,291 @@
+// Streaming result sets shared by the database drivers
+//
+// A cursor opened with `conn.cursor(stream: true)` doesn't load a SELECT's
+// rows up front. The query runs on a worker thread that hands rows over
+// through a small bounded channel, so only a few hundred rows are in memory at
+// a time. fetch_one/fetch_many/fetch_all and `for row in cursor.iter()` pull
+// from it. The stream claims its connection until a fetch reaches the end of
+// the result set or the cursor is closed; anything else run on the connection
+// meanwhile raises OperationalError rather than waiting on rows nobody reads.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::thread::{self, JoinHandle};
+use indexmap::IndexMap;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err};
+use crate::types::*;
+
+/// Rows buffered between the worker and the interpreter
+const STREAM_BUFFER_ROWS: usize = 256;
+
+const BUSY_ERROR: &str = "OperationalError: Connection is busy with a streaming cursor; fetch its remaining rows or close it first";
+
+/// A driver connection shared by its cursors and their streaming workers
+#[derive(Debug)]
+pub struct SharedConn<T> {
+    conn: Mutex<T>,
+    streaming: Arc<AtomicBool>,
+}
+
+impl<T> SharedConn<T> {
+    pub fn new(conn: T) -> Self {
+        SharedConn { conn: Mutex::new(conn), streaming: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Claim the connection for a stream, failing if another stream has it
+    fn claim(&self) -> Result<StreamClaim, String> {
+        match self.streaming.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => Ok(StreamClaim(Arc::clone(&self.streaming))),
+            Err(_) => Err(BUSY_ERROR.to_string()),
+        }
+    }
+}
+
+/// Lock a connection from the interpreter thread. Fails instead of waiting
+/// while a streaming cursor still has rows to read from the connection.
+pub fn lock_conn<T>(conn: &SharedConn<T>) -> Result<MutexGuard<'_, T>, String> {
+    if conn.streaming.load(Ordering::Acquire) {
+        return Err(BUSY_ERROR.to_string());
+    }
+    Ok(conn.conn.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Marks a connection as streaming until dropped
+#[derive(Debug)]
+struct StreamClaim(Arc<AtomicBool>);
+
+impl Drop for StreamClaim {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Parse the options of `conn.cursor()` / `conn.cursor(stream: true)`
+pub fn stream_option(args: &[QValue]) -> Result<bool, String> {
+    match args {
+        [] => Ok(false),
+        [QValue::Dict(opts)] => Ok(opts.get("stream").is_some_and(|v| v.as_bool())),
+        _ => arg_err!("cursor expects 0 arguments (or stream: true), got {}", args.len()),
+    }
+}
+
+/// Take up to `limit` rows (all remaining when None) from a cursor's open
+/// stream as Dicts. Returns None when no stream is open, i.e. the cursor's
+/// results are buffered.
+pub fn pull_rows<R>(
+    stream: &Mutex<Option<RowStream<R>>>,
+    limit: Option<usize>,
+    mut to_dict: impl FnMut(R) -> Result<IndexMap<String, QValue>, String>,
+) -> Result<Option<Vec<QValue>>, String> {
+    let mut guard = stream.lock().unwrap();
+    let stream = match guard.as_mut() {
+        Some(stream) => stream,
+        None => return Ok(None),
+    };
+    let mut rows = Vec::new();
+    while limit.is_none_or(|n| rows.len() < n) {
+        match stream.next()? {
+            Some(row) => rows.push(QValue::Dict(Box::new(QDict::new(to_dict(row)?)))),
+            None => break,
+        }
+    }
+    Ok(Some(rows))
+}
+
+/// Worker side of a stream
+pub struct StreamSender<H, R> {
+    ready: Option<Sender<Result<H, String>>>,
+    rows: SyncSender<Result<R, String>>,
+}
+
+impl<H, R> StreamSender<H, R> {
+    /// Report that the query started, along with its column metadata
+    pub fn ready(&mut self, header: H) {
+        if let Some(tx) = self.ready.take() {
+            let _ = tx.send(Ok(header));
+        }
+    }
+
+    /// Hand over one row. Returns false once the cursor has stopped reading.
+    pub fn send(&self, row: R) -> bool {
+        self.rows.send(Ok(row)).is_ok()
+    }
+}
+
+/// Interpreter side of a stream
+pub struct RowStream<R> {
+    rows: Option<Receiver<Result<R, String>>>,
+    worker: Option<JoinHandle<()>>,
+    claim: Option<StreamClaim>,
+}
+
+impl<R: Send + 'static> RowStream<R> {
+    /// Claim `conn`, run `work` with it on a worker thread and wait until it
+    /// has called ready() or failed, so SQL errors surface from execute()
+    /// rather than the first fetch. The claim is checked here, on the caller's
+    /// thread, so a second stream fails at once instead of waiting on the first.
+    pub fn start<T, H, F>(conn: &Arc<SharedConn<T>>, work: F) -> Result<(H, Self), String>
+    where
+        T: Send + 'static,
+        H: Send + 'static,
+        F: FnOnce(&mut T, &mut StreamSender<H, R>) -> Result<(), String> + Send + 'static,
+    {
+        let claim = conn.claim()?;
+        let conn = Arc::clone(conn);
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (rows_tx, rows_rx) = mpsc::sync_channel(STREAM_BUFFER_ROWS);
+
+        let worker = thread::spawn(move || {
+            let mut sender = StreamSender { ready: Some(ready_tx), rows: rows_tx };
+            // The claim keeps the interpreter off the connection, so the lock
+            // is free; never wait on it here
+            let result = match conn.conn.try_lock() {
+                Ok(mut guard) => work(&mut guard, &mut sender),
+                Err(TryLockError::Poisoned(e)) => work(&mut e.into_inner(), &mut sender),
+                Err(TryLockError::WouldBlock) => Err(BUSY_ERROR.to_string()),
+            };
+            if let Err(e) = result {
+                match sender.ready.take() {
+                    Some(tx) => { let _ = tx.send(Err(e)); }
+                    None => { let _ = sender.rows.send(Err(e)); }
+                }
+            }
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(header)) => Ok((header, RowStream { rows: Some(rows_rx), worker: Some(worker), claim: Some(claim) })),
+            Ok(Err(e)) => {
+                let _ = worker.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = worker.join();
+                Err("DatabaseError: Query worker stopped unexpectedly".to_string())
+            }
+        }
+    }
+}
+
+impl<R> RowStream<R> {
+    /// Next row, or None once the result set is exhausted
+    pub fn next(&mut self) -> Result<Option<R>, String> {
+        let received = match &self.rows {
+            Some(rx) => rx.recv(),
+            None => return Ok(None),
+        };
+        match received {
+            Ok(Ok(row)) => Ok(Some(row)),
+            Ok(Err(e)) => {
+                self.close();
+                Err(e)
+            }
+            Err(_) => {
+                self.close();
+                Ok(None)
+            }
+        }
+    }
+
+    /// Stop reading. The worker notices on its next row and releases the
+    /// connection, and then the claim on it is dropped.
+    pub fn close(&mut self) {
+        self.rows = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        self.claim = None;
+    }
+}
+
+impl<R> Drop for RowStream<R> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl<R> std::fmt::Debug for RowStream<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowStream")
+            .field("open", &self.rows.is_some())
+            .finish()
+    }
+}
+
+/// `cursor.iter()`: yields the cursor's rows one at a time, and works directly
+/// in `for` loops without collecting them into an Array first
+#[derive(Debug, Clone)]
+pub struct QRowIterator {
+    cursor: Box<QValue>,
+    pub id: u64,
+}
+
+impl QRowIterator {
+    pub fn new(cursor: QValue) -> Self {
+        QRowIterator {
+            cursor: Box::new(cursor),
+            id: next_object_id(),
+        }
+    }
+
+    /// Next row as a Dict, or None when the cursor has no more rows
+    pub fn next_row(&self) -> Result<Option<QValue>, EvalError> {
+        let row = match self.cursor.as_ref() {
+            QValue::SqliteCursor(c) => c.call_method("fetch_one", vec![])?,
+            QValue::PostgresCursor(c) => c.call_method("fetch_one", vec![])?,
+            QValue::MysqlCursor(c) => c.call_method("fetch_one", vec![])?,
+            _ => unreachable!("row iterators are only created by db cursors"),
+        };
+        Ok(match row {
+            QValue::Nil(_) => None,
+            row => Some(row),
+        })
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        if !args.is_empty() {
+            return arg_err!("{}() expects 0 arguments, got {}", method_name, args.len());
+        }
+
+        match method_name {
+            "next" => Ok(self.next_row()?.unwrap_or(QValue::Nil(QNil))),
+            "cursor" => Ok((*self.cursor).clone()),
+            _ => attr_err!("Unknown method '{}' on RowIterator", method_name),
+        }
+    }
+}
+
+impl QObj for QRowIterator {
+    fn cls(&self) -> String {
+        "RowIterator".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "RowIterator"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "RowIterator"
+    }
+
+    fn str(&self) -> String {
+        format!("<RowIterator {}>", self.id)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Iterator over a database cursor's rows".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
@@ -0,0 +1,173 @@
+// Transaction context managers shared by the database drivers
+//
+//     with conn.transaction()
+//         cursor.execute("UPDATE accounts SET balance = balance - 100 WHERE id = 1")
+//         cursor.execute("UPDATE accounts SET balance = balance + 100 WHERE id = 2")
+//     end
+//
+// The outermost block begins a transaction, commits it on a clean exit and
+// rolls it back if the body raised. Blocks nested on the same connection use
+// savepoints, so a failing inner block only undoes its own work.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err};
+use crate::types::*;
+
+#[derive(Debug, Clone)]
+pub struct QTransaction {
+    conn: Box<QValue>,
+    /// Nesting level while the block is open (0 = outermost), None otherwise
+    level: Rc<Cell<Option<usize>>>,
+    pub id: u64,
+}
+
+impl QTransaction {
+    pub fn new(conn: QValue) -> Self {
+        QTransaction {
+            conn: Box::new(conn),
+            level: Rc::new(Cell::new(None)),
+            id: next_object_id(),
+        }
+    }
+
+    fn depth_counter(&self) -> Arc<AtomicUsize> {
+        match self.conn.as_ref() {
+            QValue::SqliteConnection(c) => c.tx_depth(),
+            QValue::PostgresConnection(c) => c.tx_depth(),
+            QValue::MysqlConnection(c) => c.tx_depth(),
+            _ => unreachable!("transactions are only created by db connections"),
+        }
+    }
+
+    fn run(&self, sql: &str) -> Result<(), String> {
+        match self.conn.as_ref() {
+            QValue::SqliteConnection(c) => c.run_batch(sql),
+            QValue::PostgresConnection(c) => c.run_batch(sql),
+            QValue::MysqlConnection(c) => c.run_batch(sql),
+            _ => unreachable!("transactions are only created by db connections"),
+        }
+    }
+
+    fn begin(&self) -> Result<(), String> {
+        if self.level.get().is_some() {
+            return Err("ProgrammingError: Transaction has already begun".to_string());
+        }
+        let depth = self.depth_counter();
+        let level = depth.load(Ordering::SeqCst);
+        if level == 0 {
+            self.run(match *self.conn {
+                QValue::MysqlConnection(_) => "START TRANSACTION",
+                _ => "BEGIN",
+            })?;
+        } else {
+            self.run(&format!("SAVEPOINT {}", savepoint_name(level)))?;
+        }
+        depth.store(level + 1, Ordering::SeqCst);
+        self.level.set(Some(level));
+        Ok(())
+    }
+
+    /// Commit (or release the savepoint) unless `failed`, in which case roll
+    /// back. Finishing a transaction that is not open does nothing.
+    fn finish(&self, failed: bool) -> Result<(), String> {
+        let level = match self.level.take() {
+            Some(level) => level,
+            None => return Ok(()),
+        };
+        self.depth_counter().store(level, Ordering::SeqCst);
+
+        if level == 0 {
+            if failed {
+                return self.run("ROLLBACK");
+            }
+            // A failed COMMIT can leave the transaction open (e.g. SQLite busy)
+            return self.run("COMMIT").inspect_err(|_| {
+                let _ = self.run("ROLLBACK");
+            });
+        }
+
+        let name = savepoint_name(level);
+        if failed {
+            self.run(&format!("ROLLBACK TO SAVEPOINT {}", name))?;
+        }
+        self.run(&format!("RELEASE SAVEPOINT {}", name))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "_enter" => {
+                if !args.is_empty() {
+                    return arg_err!("_enter expects 0 arguments, got {}", args.len());
+                }
+                self.begin()?;
+                Ok(QValue::Transaction(self.clone()))
+            }
+            "_exit" => {
+                // The with statement passes `true` when the block raised
+                let failed = match args.as_slice() {
+                    [] => false,
+                    [failed] => failed.as_bool(),
+                    _ => return arg_err!("_exit expects 0 or 1 arguments, got {}", args.len()),
+                };
+                self.finish(failed)?;
+                Ok(QValue::Nil(QNil))
+            }
+            "commit" | "rollback" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                self.finish(method_name == "rollback")?;
+                Ok(QValue::Nil(QNil))
+            }
+            "active" => Ok(QValue::Bool(QBool::new(self.level.get().is_some()))),
+            "depth" => Ok(match self.level.get() {
+                Some(level) => QValue::Int(QInt::new(level as i64 + 1)),
+                None => QValue::Nil(QNil),
+            }),
+            "connection" => Ok((*self.conn).clone()),
+            _ => attr_err!("Unknown method '{}' on Transaction", method_name),
+        }
+    }
+}
+
+fn savepoint_name(level: usize) -> String {
+    format!("quest_sp_{}", level)
+}
+
+impl QObj for QTransaction {
+    fn cls(&self) -> String {
+        "Transaction".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Transaction"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Transaction"
+    }
+
+    fn str(&self) -> String {
+        format!("<Transaction {}>", self.id)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Database transaction (use with `with`)".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
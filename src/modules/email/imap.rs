@@ -0,0 +1,839 @@
+// IMAP client (std/email/imap)
+//
+//     use "std/email/imap"
+//     let mail = imap.connect("imap.example.com", user: "me", password: pw)
+//     mail.select("INBOX")
+//     for msg in mail.fetch(mail.search(unseen: true))
+//         puts(msg["from"], ": ", msg["subject"])
+//     end
+//     mail.logout()
+//
+// Messages are addressed by UID, so the numbers stay valid across sessions as
+// long as the folder's UIDVALIDITY doesn't change. Fetching uses BODY.PEEK so
+// reading a message doesn't mark it as seen; mark_read() does that explicitly.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use indexmap::IndexMap;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+use crate::types::*;
+use crate::scope::Scope;
+use crate::modules::net::stream::{ConnectOptions, LineStream};
+use super::{message_bytes, mime};
+
+/// One piece of a server response. Quoted strings and literals are both Str.
+#[derive(Debug, Clone)]
+enum Token {
+    Atom(String),
+    Str(Vec<u8>),
+    List(Vec<Token>),
+    Nil,
+}
+
+impl Token {
+    fn atom(&self) -> Option<&str> {
+        match self {
+            Token::Atom(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    fn is_atom(&self, name: &str) -> bool {
+        self.atom().is_some_and(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Token::Atom(a) => a.clone(),
+            Token::Str(s) => String::from_utf8_lossy(s).into_owned(),
+            Token::List(items) => format!("({})", items.iter().map(Token::text).collect::<Vec<_>>().join(" ")),
+            Token::Nil => "NIL".to_string(),
+        }
+    }
+
+    fn number(&self) -> Option<i64> {
+        self.atom().and_then(|a| a.parse().ok())
+    }
+}
+
+/// Tokenizes one response, pulling `{n}` literals and the lines after them
+/// from the stream as it goes
+struct ResponseReader<'a> {
+    stream: &'a mut LineStream,
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl<'a> ResponseReader<'a> {
+    fn read(stream: &'a mut LineStream) -> Result<Vec<Token>, String> {
+        let line = stream.read_line()?;
+        let mut reader = ResponseReader { stream, line, pos: 0 };
+        reader.tokens(0)
+    }
+
+    fn tokens(&mut self, depth: usize) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        loop {
+            while self.pos < self.line.len() && self.line[self.pos] == b' ' {
+                self.pos += 1;
+            }
+            let Some(&c) = self.line.get(self.pos) else {
+                return Ok(tokens);
+            };
+            match c {
+                b'(' => {
+                    self.pos += 1;
+                    tokens.push(Token::List(self.tokens(depth + 1)?));
+                }
+                b')' => {
+                    self.pos += 1;
+                    if depth > 0 {
+                        return Ok(tokens);
+                    }
+                }
+                b'"' => tokens.push(Token::Str(self.quoted())),
+                b'{' => match self.literal_len() {
+                    Some(len) => {
+                        let data = self.stream.read_exact(len)?;
+                        self.line = self.stream.read_line()?;
+                        self.pos = 0;
+                        tokens.push(Token::Str(data));
+                    }
+                    None => tokens.push(self.atom()),
+                },
+                _ => tokens.push(self.atom()),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Vec<u8> {
+        let mut value = Vec::new();
+        self.pos += 1;
+        while let Some(&c) = self.line.get(self.pos) {
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    if let Some(&next) = self.line.get(self.pos) {
+                        value.push(next);
+                        self.pos += 1;
+                    }
+                }
+                _ => value.push(c),
+            }
+        }
+        value
+    }
+
+    /// Length of a `{n}` literal, which always ends the line
+    fn literal_len(&self) -> Option<usize> {
+        let rest = &self.line[self.pos + 1..];
+        let digits = rest.strip_suffix(b"}")?;
+        let digits = digits.strip_suffix(b"+").unwrap_or(digits);
+        std::str::from_utf8(digits).ok()?.parse().ok()
+    }
+
+    /// An atom, keeping `[...]` sections (BODY[HEADER], [UIDNEXT 5]) whole
+    fn atom(&mut self) -> Token {
+        let start = self.pos;
+        let mut brackets = 0;
+        while let Some(&c) = self.line.get(self.pos) {
+            match c {
+                b'[' => brackets += 1,
+                b']' if brackets > 0 => brackets -= 1,
+                b' ' | b'(' | b')' if brackets == 0 => break,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // A stray character that starts nothing; skip it
+            self.pos += 1;
+        }
+        let atom = String::from_utf8_lossy(&self.line[start..self.pos]).into_owned();
+        if atom.eq_ignore_ascii_case("NIL") {
+            Token::Nil
+        } else {
+            Token::Atom(atom)
+        }
+    }
+}
+
+/// A command argument
+enum Arg {
+    /// Sent as it is (numbers, flags, search keys)
+    Raw(String),
+    /// Sent as a quoted string, or as a literal when it can't be quoted
+    Str(String),
+}
+
+fn raw(s: impl Into<String>) -> Arg {
+    Arg::Raw(s.into())
+}
+
+struct Session {
+    stream: LineStream,
+    next_tag: u32,
+    selected: Option<String>,
+}
+
+impl Session {
+    fn greet(stream: LineStream) -> Result<(Self, bool), String> {
+        let mut session = Session { stream, next_tag: 1, selected: None };
+        let greeting = ResponseReader::read(&mut session.stream)?;
+        let preauth = match greeting.get(1) {
+            Some(t) if t.is_atom("OK") => false,
+            Some(t) if t.is_atom("PREAUTH") => true,
+            _ => return io_err!("IMAP server refused the connection: {}", join_text(&greeting)),
+        };
+        Ok((session, preauth))
+    }
+
+    /// Run a tagged command and return its untagged responses (without the `*`)
+    fn command(&mut self, args: Vec<Arg>) -> Result<Vec<Vec<Token>>, String> {
+        let tag = format!("Q{:04}", self.next_tag);
+        self.next_tag += 1;
+        let verb = match args.first() {
+            Some(Arg::Raw(verb)) => verb.clone(),
+            _ => "command".to_string(),
+        };
+
+        let mut buf = tag.clone().into_bytes();
+        for arg in args {
+            buf.push(b' ');
+            match arg {
+                Arg::Raw(s) => {
+                    if s.contains(['\r', '\n']) {
+                        return value_err!("IMAP arguments cannot contain line breaks");
+                    }
+                    buf.extend_from_slice(s.as_bytes());
+                }
+                Arg::Str(s) if s.bytes().all(|b| (0x20..0x7f).contains(&b)) => {
+                    buf.push(b'"');
+                    for b in s.bytes() {
+                        if b == b'"' || b == b'\\' {
+                            buf.push(b'\\');
+                        }
+                        buf.push(b);
+                    }
+                    buf.push(b'"');
+                }
+                Arg::Str(s) => {
+                    // Synchronizing literal: wait for the server's go-ahead
+                    buf.extend_from_slice(format!("{{{}}}\r\n", s.len()).as_bytes());
+                    self.stream.write_all(&buf)?;
+                    buf.clear();
+                    self.await_continuation(&tag, &verb)?;
+                    buf.extend_from_slice(s.as_bytes());
+                }
+            }
+        }
+        buf.extend_from_slice(b"\r\n");
+        self.stream.write_all(&buf)?;
+
+        let mut untagged = Vec::new();
+        loop {
+            let mut tokens = ResponseReader::read(&mut self.stream)?;
+            if tokens.is_empty() {
+                continue;
+            }
+            if tokens[0].is_atom(&tag) {
+                return check_status(&verb, &tokens).map(|_| untagged);
+            }
+            if tokens[0].is_atom("*") {
+                tokens.remove(0);
+                untagged.push(tokens);
+            }
+        }
+    }
+
+    fn await_continuation(&mut self, tag: &str, verb: &str) -> Result<(), String> {
+        loop {
+            let tokens = ResponseReader::read(&mut self.stream)?;
+            match tokens.first() {
+                Some(t) if t.is_atom("+") => return Ok(()),
+                Some(t) if t.is_atom(tag) => {
+                    check_status(verb, &tokens)?;
+                    return io_err!("IMAP {} failed: server did not accept a literal", verb);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn login(&mut self, user: &str, password: &str) -> Result<(), String> {
+        self.command(vec![raw("LOGIN"), Arg::Str(user.to_string()), Arg::Str(password.to_string())])?;
+        Ok(())
+    }
+
+    fn select(&mut self, folder: &str, readonly: bool) -> Result<IndexMap<String, QValue>, String> {
+        let verb = if readonly { "EXAMINE" } else { "SELECT" };
+        let responses = self.command(vec![raw(verb), Arg::Str(encode_folder(folder))])?;
+        self.selected = Some(folder.to_string());
+
+        let mut info = IndexMap::new();
+        info.insert("exists".to_string(), QValue::Int(QInt::new(0)));
+        info.insert("recent".to_string(), QValue::Int(QInt::new(0)));
+        for tokens in &responses {
+            match tokens.as_slice() {
+                [n, kind, ..] if kind.is_atom("EXISTS") || kind.is_atom("RECENT") => {
+                    let key = kind.text().to_lowercase();
+                    info.insert(key, QValue::Int(QInt::new(n.number().unwrap_or(0))));
+                }
+                [kind, Token::List(flags), ..] if kind.is_atom("FLAGS") => {
+                    info.insert("flags".to_string(), flag_array(flags));
+                }
+                [status, Token::Atom(code), ..] if status.is_atom("OK") && code.starts_with('[') => {
+                    let code = code.trim_start_matches('[').trim_end_matches(']');
+                    let mut parts = code.splitn(2, ' ');
+                    let key = parts.next().unwrap_or_default().to_ascii_uppercase();
+                    if let (Some(name), Some(Ok(n))) = (status_key(&key), parts.next().map(str::parse::<i64>)) {
+                        info.insert(name.to_string(), QValue::Int(QInt::new(n)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        info.insert("readonly".to_string(), QValue::Bool(QBool::new(readonly)));
+        Ok(info)
+    }
+
+    /// Select INBOX when a mailbox command is used before select()
+    fn ensure_selected(&mut self) -> Result<(), String> {
+        if self.selected.is_none() {
+            self.select("INBOX", false)?;
+        }
+        Ok(())
+    }
+
+    fn fetch(&mut self, uids: &[i64], headers_only: bool) -> Result<HashMap<i64, QValue>, String> {
+        self.ensure_selected()?;
+        let section = if headers_only { "BODY.PEEK[HEADER]" } else { "BODY.PEEK[]" };
+        let items = format!("(UID FLAGS RFC822.SIZE INTERNALDATE {})", section);
+        let responses = self.command(vec![raw("UID FETCH"), raw(uid_set(uids)), raw(items)])?;
+
+        let mut messages = HashMap::new();
+        for tokens in responses {
+            let [_, kind, Token::List(items)] = tokens.as_slice() else { continue };
+            if !kind.is_atom("FETCH") {
+                continue;
+            }
+            let mut uid = None;
+            let mut flags = QValue::Array(QArray::new(Vec::new()));
+            let mut size = None;
+            let mut internal_date = QValue::Nil(QNil);
+            let mut body = None;
+            for pair in items.chunks(2) {
+                let [key, value] = pair else { continue };
+                let key = key.text().to_ascii_uppercase();
+                match (key.as_str(), value) {
+                    ("UID", v) => uid = v.number(),
+                    ("FLAGS", Token::List(list)) => flags = flag_array(list),
+                    ("RFC822.SIZE", v) => size = v.number(),
+                    ("INTERNALDATE", v) => internal_date = QValue::Str(QString::new(v.text())),
+                    (k, Token::Str(data)) if k.starts_with("BODY[") => body = Some(data.clone()),
+                    _ => {}
+                }
+            }
+            // Servers may slip in unsolicited FETCH responses for flag changes
+            let (Some(uid), Some(body)) = (uid, body) else { continue };
+
+            let mut message = if headers_only {
+                mime::parse_headers_only(&body)
+            } else {
+                mime::parse_message(&body)
+            };
+            message.insert("uid".to_string(), QValue::Int(QInt::new(uid)));
+            message.insert("flags".to_string(), flags);
+            message.insert("size".to_string(), QValue::Int(QInt::new(size.unwrap_or(body.len() as i64))));
+            message.insert("internal_date".to_string(), internal_date);
+            if !headers_only {
+                message.insert("raw".to_string(), QValue::Bytes(QBytes::new(body)));
+            }
+            messages.insert(uid, QValue::Dict(Box::new(QDict::new(message))));
+        }
+        Ok(messages)
+    }
+
+    fn store(&mut self, uids: &[i64], change: &str, flag: &str) -> Result<(), String> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+        self.ensure_selected()?;
+        self.command(vec![
+            raw("UID STORE"),
+            raw(uid_set(uids)),
+            raw(format!("{}FLAGS.SILENT", change)),
+            raw(format!("({})", flag)),
+        ])?;
+        Ok(())
+    }
+}
+
+fn check_status(verb: &str, tokens: &[Token]) -> Result<(), String> {
+    match tokens.get(1) {
+        Some(t) if t.is_atom("OK") => Ok(()),
+        _ => io_err!("IMAP {} failed: {}", verb, join_text(tokens.get(1..).unwrap_or_default())),
+    }
+}
+
+fn join_text(tokens: &[Token]) -> String {
+    tokens.iter().map(Token::text).collect::<Vec<_>>().join(" ")
+}
+
+fn status_key(key: &str) -> Option<&'static str> {
+    match key {
+        "MESSAGES" => Some("messages"),
+        "RECENT" => Some("recent"),
+        "UNSEEN" => Some("unseen"),
+        "UIDNEXT" => Some("uidnext"),
+        "UIDVALIDITY" => Some("uidvalidity"),
+        _ => None,
+    }
+}
+
+fn flag_array(flags: &[Token]) -> QValue {
+    QValue::Array(QArray::new(flags.iter().map(|f| QValue::Str(QString::new(f.text()))).collect()))
+}
+
+/// Comma separated UID set; the caller guarantees it isn't empty
+fn uid_set(uids: &[i64]) -> String {
+    uids.iter().map(|u| u.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Folder names travel in IMAP's modified UTF-7 (RFC 3501 section 5.1.3)
+fn encode_folder(name: &str) -> String {
+    use base64::Engine;
+    let mut out = String::new();
+    let mut pending: Vec<u16> = Vec::new();
+    let flush = |pending: &mut Vec<u16>, out: &mut String| {
+        if pending.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = pending.iter().flat_map(|u| u.to_be_bytes()).collect();
+        let encoded = base64::engine::general_purpose::STANDARD_NO_PAD.encode(bytes);
+        out.push('&');
+        out.push_str(&encoded.replace('/', ","));
+        out.push('-');
+        pending.clear();
+    };
+    for c in name.chars() {
+        if (' '..='~').contains(&c) {
+            flush(&mut pending, &mut out);
+            if c == '&' {
+                out.push_str("&-");
+            } else {
+                out.push(c);
+            }
+        } else {
+            let mut units = [0u16; 2];
+            pending.extend_from_slice(c.encode_utf16(&mut units));
+        }
+    }
+    flush(&mut pending, &mut out);
+    out
+}
+
+fn decode_folder(name: &str) -> String {
+    use base64::Engine;
+    let mut out = String::new();
+    let mut rest = name;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('-') else {
+            // Not valid modified UTF-7; leave the remainder alone
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let encoded = &after[..end];
+        if encoded.is_empty() {
+            out.push('&');
+        } else {
+            let decoded = base64::engine::general_purpose::STANDARD_NO_PAD
+                .decode(encoded.replace(',', "/"))
+                .ok()
+                .map(|bytes| {
+                    let units: Vec<u16> = bytes.chunks_exact(2).map(|p| u16::from_be_bytes([p[0], p[1]])).collect();
+                    String::from_utf16_lossy(&units)
+                });
+            match decoded {
+                Some(text) => out.push_str(&text),
+                None => out.push_str(&rest[start..start + end + 2]),
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Build UID SEARCH criteria from `search(raw, unseen: true, from: "...", ...)`
+fn search_criteria(args: &[QValue]) -> Result<Vec<Arg>, String> {
+    let (positional, kwargs) = match args {
+        [rest @ .., QValue::Dict(d)] => (rest, Some(d)),
+        _ => (args, None),
+    };
+    let mut criteria = Vec::new();
+    match positional {
+        [] => {}
+        [QValue::Str(s)] => criteria.push(raw(s.value.to_string())),
+        [other] => return type_err!("search criteria must be Str, got {}", other.as_obj().cls()),
+        _ => return arg_err!("search expects 0 or 1 arguments, got {}", positional.len()),
+    }
+
+    if let Some(kwargs) = kwargs {
+        for (key, value) in kwargs.map.borrow().iter() {
+            if matches!(value, QValue::Nil(_)) {
+                continue;
+            }
+            let key = key.as_str();
+            match key {
+                "all" | "new" | "old" | "recent" => {
+                    if value.as_bool() {
+                        criteria.push(raw(key.to_uppercase()));
+                    }
+                }
+                "seen" | "flagged" | "answered" | "deleted" | "draft" => {
+                    let negated = if value.as_bool() { "" } else { "UN" };
+                    criteria.push(raw(format!("{}{}", negated, key.to_uppercase())));
+                }
+                "unseen" | "unflagged" | "unanswered" | "undeleted" | "undraft" => {
+                    let flag = key.trim_start_matches("un").to_uppercase();
+                    let negated = if value.as_bool() { "UN" } else { "" };
+                    criteria.push(raw(format!("{}{}", negated, flag)));
+                }
+                "from" | "to" | "cc" | "bcc" | "subject" | "body" | "text" => {
+                    criteria.push(raw(key.to_uppercase()));
+                    criteria.push(Arg::Str(value.as_str()));
+                }
+                "since" | "before" | "on" | "sent_since" | "sent_before" | "sent_on" => {
+                    let date = value.as_str();
+                    if !is_search_date(&date) {
+                        return value_err!("search {} expects a date like \"1-Jan-2025\", got \"{}\"", key, date);
+                    }
+                    criteria.push(raw(key.replace('_', "").to_uppercase()));
+                    criteria.push(raw(date));
+                }
+                "larger" | "smaller" => match value {
+                    QValue::Int(n) if n.value >= 0 => {
+                        criteria.push(raw(key.to_uppercase()));
+                        criteria.push(raw(n.value.to_string()));
+                    }
+                    _ => return value_err!("search {} expects a size in bytes, got {}", key, value.as_str()),
+                },
+                _ => return arg_err!("Unknown search criterion '{}'", key),
+            }
+        }
+    }
+
+    if criteria.is_empty() {
+        criteria.push(raw("ALL"));
+    }
+    Ok(criteria)
+}
+
+/// IMAP dates are day-Mon-year, e.g. 1-Jan-2025
+fn is_search_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    matches!(parts.as_slice(), [day, month, year]
+        if day.len() <= 2 && day.parse::<u8>().is_ok()
+        && month.len() == 3 && month.chars().all(|c| c.is_ascii_alphabetic())
+        && year.len() == 4 && year.parse::<u16>().is_ok())
+}
+
+/// UIDs from an Int or an Array of Ints
+fn uid_list(method: &str, value: &QValue) -> Result<Vec<i64>, String> {
+    match value {
+        QValue::Int(n) => Ok(vec![n.value]),
+        QValue::Array(arr) => arr.elements.borrow().iter()
+            .map(|v| match v {
+                QValue::Int(n) => Ok(n.value),
+                other => type_err!("{} expects Int UIDs, got {}", method, other.as_obj().cls()),
+            })
+            .collect(),
+        other => type_err!("{} expects a UID or an Array of UIDs, got {}", method, other.as_obj().cls()),
+    }
+}
+
+#[derive(Clone)]
+pub struct QImapClient {
+    session: Rc<RefCell<Option<Session>>>,
+    host: String,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QImapClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QImapClient").field("host", &self.host).field("id", &self.id).finish()
+    }
+}
+
+impl QImapClient {
+    pub fn connect(opts: &ConnectOptions) -> Result<Self, String> {
+        let stream = LineStream::connect(opts)?;
+        let (mut session, preauth) = Session::greet(stream)?;
+
+        if opts.starttls {
+            session.command(vec![raw("STARTTLS")])?;
+            let stream = session.stream.start_tls(opts)?;
+            session = Session { stream, next_tag: session.next_tag, selected: None };
+        }
+        if let (Some(user), false) = (&opts.user, preauth) {
+            session.login(user, opts.password.as_deref().unwrap_or(""))?;
+        }
+
+        Ok(QImapClient {
+            session: Rc::new(RefCell::new(Some(session))),
+            host: opts.host.clone(),
+            id: next_object_id(),
+        })
+    }
+
+    fn with_session<T>(&self, f: impl FnOnce(&mut Session) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.session.borrow_mut();
+        match guard.as_mut() {
+            Some(session) => f(session),
+            None => io_err!("IMAP connection is closed"),
+        }
+    }
+
+    fn logout(&self) -> Result<(), String> {
+        let Some(mut session) = self.session.borrow_mut().take() else {
+            return Ok(());
+        };
+        session.command(vec![raw("LOGOUT")]).map(|_| ())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "login" => {
+                let [user, password] = args.as_slice() else {
+                    return arg_err!("login expects 2 arguments (user, password), got {}", args.len());
+                };
+                self.with_session(|s| s.login(&user.as_str(), &password.as_str()))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "folders" => {
+                // folders(pattern = "*")
+                let pattern = match args.as_slice() {
+                    [] => "*".to_string(),
+                    [pattern] => pattern.as_str(),
+                    _ => return arg_err!("folders expects 0 or 1 arguments, got {}", args.len()),
+                };
+                let responses = self.with_session(|s| {
+                    s.command(vec![raw("LIST"), Arg::Str(String::new()), Arg::Str(encode_folder(&pattern))])
+                })?;
+                let folders = responses.into_iter()
+                    .filter_map(|tokens| match tokens.as_slice() {
+                        [kind, Token::List(flags), delimiter, name] if kind.is_atom("LIST") => {
+                            let mut folder = IndexMap::new();
+                            folder.insert("name".to_string(), QValue::Str(QString::new(decode_folder(&name.text()))));
+                            folder.insert("delimiter".to_string(), match delimiter {
+                                Token::Nil => QValue::Nil(QNil),
+                                d => QValue::Str(QString::new(d.text())),
+                            });
+                            folder.insert("flags".to_string(), flag_array(flags));
+                            Some(QValue::Dict(Box::new(QDict::new(folder))))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                Ok(QValue::Array(QArray::new(folders)))
+            }
+            "select" => {
+                // select(folder = "INBOX", readonly: false)
+                let (positional, readonly) = match args.as_slice() {
+                    [rest @ .., QValue::Dict(d)] => (rest, d.get("readonly").is_some_and(|v| v.as_bool())),
+                    _ => (args.as_slice(), false),
+                };
+                let folder = match positional {
+                    [] => "INBOX".to_string(),
+                    [folder] => folder.as_str(),
+                    _ => return arg_err!("select expects 0 or 1 arguments, got {}", positional.len()),
+                };
+                let info = self.with_session(|s| s.select(&folder, readonly))?;
+                Ok(QValue::Dict(Box::new(QDict::new(info))))
+            }
+            "selected" => Ok(match self.with_session(|s| Ok(s.selected.clone()))? {
+                Some(folder) => QValue::Str(QString::new(folder)),
+                None => QValue::Nil(QNil),
+            }),
+            "status" => {
+                // status(folder = "INBOX") counts without selecting
+                let folder = match args.as_slice() {
+                    [] => "INBOX".to_string(),
+                    [folder] => folder.as_str(),
+                    _ => return arg_err!("status expects 0 or 1 arguments, got {}", args.len()),
+                };
+                let responses = self.with_session(|s| s.command(vec![
+                    raw("STATUS"),
+                    Arg::Str(encode_folder(&folder)),
+                    raw("(MESSAGES RECENT UNSEEN UIDNEXT UIDVALIDITY)"),
+                ]))?;
+                let mut info = IndexMap::new();
+                for tokens in responses {
+                    if let [kind, _, Token::List(items)] = tokens.as_slice() {
+                        if !kind.is_atom("STATUS") {
+                            continue;
+                        }
+                        for pair in items.chunks(2) {
+                            if let [key, value] = pair {
+                                if let (Some(name), Some(n)) = (status_key(&key.text().to_ascii_uppercase()), value.number()) {
+                                    info.insert(name.to_string(), QValue::Int(QInt::new(n)));
+                                }
+                            }
+                        }
+                    }
+                }
+                Ok(QValue::Dict(Box::new(QDict::new(info))))
+            }
+            "search" => {
+                let mut command = vec![raw("UID SEARCH")];
+                let criteria = search_criteria(&args)?;
+                if criteria.iter().any(|c| matches!(c, Arg::Str(s) if !s.is_ascii())) {
+                    command.push(raw("CHARSET UTF-8"));
+                }
+                command.extend(criteria);
+                let responses = self.with_session(|s| {
+                    s.ensure_selected()?;
+                    s.command(command)
+                })?;
+                let uids = responses.iter()
+                    .filter(|tokens| tokens.first().is_some_and(|t| t.is_atom("SEARCH")))
+                    .flat_map(|tokens| tokens[1..].iter().filter_map(Token::number))
+                    .map(|uid| QValue::Int(QInt::new(uid)))
+                    .collect();
+                Ok(QValue::Array(QArray::new(uids)))
+            }
+            "fetch" => {
+                // fetch(uid | [uids], headers_only: false)
+                let (positional, headers_only) = match args.as_slice() {
+                    [rest @ .., QValue::Dict(d)] => (rest, d.get("headers_only").is_some_and(|v| v.as_bool())),
+                    _ => (args.as_slice(), false),
+                };
+                let [target] = positional else {
+                    return arg_err!("fetch expects 1 argument (uid or array of uids), got {}", positional.len());
+                };
+                let uids = uid_list("fetch", target)?;
+                if uids.is_empty() {
+                    return Ok(QValue::Array(QArray::new(Vec::new())));
+                }
+                let mut messages = self.with_session(|s| s.fetch(&uids, headers_only))?;
+                if let QValue::Int(n) = target {
+                    return Ok(messages.remove(&n.value).unwrap_or(QValue::Nil(QNil)));
+                }
+                // Requested order; UIDs that no longer exist are left out
+                let found = uids.iter().filter_map(|uid| messages.remove(uid)).collect();
+                Ok(QValue::Array(QArray::new(found)))
+            }
+            "mark_read" | "mark_unread" | "mark_deleted" | "mark_undeleted" | "mark_flagged" | "mark_unflagged" => {
+                let [target] = args.as_slice() else {
+                    return arg_err!("{} expects 1 argument (uid or array of uids), got {}", method_name, args.len());
+                };
+                let uids = uid_list(method_name, target)?;
+                let (change, flag) = match method_name {
+                    "mark_read" => ("+", "\\Seen"),
+                    "mark_unread" => ("-", "\\Seen"),
+                    "mark_deleted" => ("+", "\\Deleted"),
+                    "mark_undeleted" => ("-", "\\Deleted"),
+                    "mark_flagged" => ("+", "\\Flagged"),
+                    _ => ("-", "\\Flagged"),
+                };
+                self.with_session(|s| s.store(&uids, change, flag))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "expunge" => {
+                if !args.is_empty() {
+                    return arg_err!("expunge expects 0 arguments, got {}", args.len());
+                }
+                self.with_session(|s| {
+                    s.ensure_selected()?;
+                    s.command(vec![raw("EXPUNGE")])
+                })?;
+                Ok(QValue::Nil(QNil))
+            }
+            "noop" => {
+                self.with_session(|s| s.command(vec![raw("NOOP")]))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "logout" | "close" => {
+                self.logout()?;
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.session.borrow().is_none()))),
+            "_enter" => Ok(QValue::ImapClient(self.clone())),
+            "_exit" => {
+                // Don't mask an exception from the block with a failed LOGOUT
+                let _ = self.logout();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on ImapClient", method_name),
+        }
+    }
+}
+
+impl QObj for QImapClient {
+    fn cls(&self) -> String {
+        "ImapClient".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "ImapClient"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "ImapClient"
+    }
+
+    fn str(&self) -> String {
+        format!("<ImapClient {}>", self.host)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "IMAP mailbox connection".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_imap_module() -> QValue {
+    let mut members = HashMap::new();
+
+    for name in ["connect", "parse_message"] {
+        members.insert(name.to_string(), QValue::Fun(QFun {
+            name: name.to_string(),
+            parent_type: "imap".to_string(),
+            id: next_object_id(),
+        }));
+    }
+
+    QValue::Module(Box::new(QModule::new("imap".to_string(), members)))
+}
+
+pub fn call_imap_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "imap.connect" => {
+            // imap.connect(host, port: 993, tls: true, starttls: false, user:, password:)
+            let opts = ConnectOptions::parse("imap.connect", args, 993, 143)?;
+            Ok(QValue::ImapClient(QImapClient::connect(&opts)?))
+        }
+        "imap.parse_message" => {
+            let raw = message_bytes("imap.parse_message", &args)?;
+            Ok(QValue::Dict(Box::new(QDict::new(mime::parse_message(&raw)))))
+        }
+        _ => attr_err!("Unknown function: {}", func_name),
+    }
+}
@@ -0,0 +1,420 @@
+// Parsing of RFC 822 / MIME messages into Quest Dicts
+//
+// Both mail readers hand fetched messages to parse_message(), which returns:
+//
+//     {headers: {subject: "...", ...}, subject, from, to: [...], cc: [...],
+//      date, message_id, text, html, attachments: [{filename, content_type,
+//      size, data, inline, content_id}]}
+//
+// Header names are lowercased. Encoded words (=?UTF-8?B?...?=) are decoded,
+// and bodies are decoded from base64 / quoted-printable and their charset.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use indexmap::IndexMap;
+use crate::types::*;
+
+/// One MIME entity: unfolded headers in order, plus the undecoded body
+struct Part<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    fn parse(raw: &'a [u8]) -> Self {
+        let (head, body) = split_head(raw);
+        Part { headers: parse_headers(head), body }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Lowercased media type and its parameters; text/plain when missing
+    fn content_type(&self) -> (String, IndexMap<String, String>) {
+        match self.header("content-type") {
+            Some(value) => {
+                let (value, params) = parse_params(value);
+                let media = if value.contains('/') { value.to_lowercase() } else { "text/plain".to_string() };
+                (media, params)
+            }
+            None => ("text/plain".to_string(), IndexMap::new()),
+        }
+    }
+
+    fn disposition(&self) -> (Option<String>, IndexMap<String, String>) {
+        match self.header("content-disposition") {
+            Some(value) => {
+                let (value, params) = parse_params(value);
+                (Some(value.to_lowercase()), params)
+            }
+            None => (None, IndexMap::new()),
+        }
+    }
+
+    /// Body with its Content-Transfer-Encoding undone
+    fn decoded_body(&self) -> Vec<u8> {
+        let encoding = self.header("content-transfer-encoding")
+            .map(|e| e.trim().to_lowercase())
+            .unwrap_or_default();
+        match encoding.as_str() {
+            "base64" => {
+                let compact: Vec<u8> = self.body.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+                decode_base64(&compact).unwrap_or_else(|| self.body.to_vec())
+            }
+            "quoted-printable" => decode_quoted_printable(self.body),
+            _ => self.body.to_vec(),
+        }
+    }
+}
+
+/// Collected while walking a message's parts
+#[derive(Default)]
+struct Contents {
+    text: Option<String>,
+    html: Option<String>,
+    attachments: Vec<QValue>,
+}
+
+/// Parse a complete message (headers and body) into a Dict
+pub fn parse_message(raw: &[u8]) -> IndexMap<String, QValue> {
+    let message = Part::parse(raw);
+    let mut fields = parse_header_fields(&message);
+
+    let mut contents = Contents::default();
+    walk(&message, &mut contents, 0);
+
+    fields.insert("text".to_string(), opt_str(contents.text));
+    fields.insert("html".to_string(), opt_str(contents.html));
+    fields.insert("attachments".to_string(), QValue::Array(QArray::new(contents.attachments)));
+    fields
+}
+
+/// Parse only the header block, for header-only fetches
+pub fn parse_headers_only(raw: &[u8]) -> IndexMap<String, QValue> {
+    parse_header_fields(&Part::parse(raw))
+}
+
+fn parse_header_fields(message: &Part) -> IndexMap<String, QValue> {
+    // Repeated headers such as Received keep their first value
+    let mut headers = IndexMap::new();
+    for (name, value) in &message.headers {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), QValue::Str(QString::new(decode_words(value))));
+        }
+    }
+
+    let decoded = |name: &str| message.header(name).map(decode_words);
+    let addresses = |name: &str| {
+        let list = message.headers.iter()
+            .filter(|(n, _)| n == name)
+            .flat_map(|(_, v)| split_addresses(&decode_words(v)))
+            .map(|a| QValue::Str(QString::new(a)))
+            .collect();
+        QValue::Array(QArray::new(list))
+    };
+
+    let mut fields = IndexMap::new();
+    fields.insert("subject".to_string(), opt_str(decoded("subject")));
+    fields.insert("from".to_string(), opt_str(decoded("from")));
+    fields.insert("to".to_string(), addresses("to"));
+    fields.insert("cc".to_string(), addresses("cc"));
+    fields.insert("date".to_string(), opt_str(decoded("date")));
+    fields.insert("message_id".to_string(), opt_str(decoded("message-id").map(|id| id.trim().to_string())));
+    fields.insert("headers".to_string(), QValue::Dict(Box::new(QDict::new(headers))));
+    fields
+}
+
+fn walk(part: &Part, contents: &mut Contents, depth: usize) {
+    let (media, params) = part.content_type();
+
+    // Nesting this deep only happens in malformed or hostile messages
+    if media.starts_with("multipart/") && depth < 20 {
+        if let Some(boundary) = params.get("boundary") {
+            for sub in split_multipart(part.body, boundary) {
+                walk(&Part::parse(sub), contents, depth + 1);
+            }
+            return;
+        }
+    }
+
+    let (disposition, disp_params) = part.disposition();
+    let filename = disp_params.get("filename")
+        .or_else(|| params.get("name"))
+        .map(|f| decode_words(f));
+    let is_attachment = disposition.as_deref() == Some("attachment") || filename.is_some();
+
+    if !is_attachment && (media == "text/plain" || media == "text/html") {
+        let text = decode_charset(&part.decoded_body(), params.get("charset").map(String::as_str));
+        let slot = if media == "text/plain" { &mut contents.text } else { &mut contents.html };
+        // Bodies split around attachments are joined back together
+        match slot {
+            Some(existing) => existing.push_str(&text),
+            None => *slot = Some(text),
+        }
+        return;
+    }
+
+    let data = part.decoded_body();
+    let filename = filename.unwrap_or_else(|| match media.as_str() {
+        "message/rfc822" => "message.eml".to_string(),
+        _ => String::new(),
+    });
+    let content_id = part.header("content-id")
+        .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string());
+
+    let mut attachment = IndexMap::new();
+    attachment.insert("filename".to_string(), QValue::Str(QString::new(filename)));
+    attachment.insert("content_type".to_string(), QValue::Str(QString::new(media)));
+    attachment.insert("size".to_string(), QValue::Int(QInt::new(data.len() as i64)));
+    attachment.insert("data".to_string(), QValue::Bytes(QBytes::new(data)));
+    attachment.insert("inline".to_string(), QValue::Bool(QBool::new(disposition.as_deref() == Some("inline"))));
+    attachment.insert("content_id".to_string(), opt_str(content_id));
+    contents.attachments.push(QValue::Dict(Box::new(QDict::new(attachment))));
+}
+
+fn opt_str(value: Option<String>) -> QValue {
+    match value {
+        Some(s) => QValue::Str(QString::new(s)),
+        None => QValue::Nil(QNil),
+    }
+}
+
+/// Split at the blank line ending the headers (CRLF or bare LF)
+fn split_head(raw: &[u8]) -> (&[u8], &[u8]) {
+    let mut i = 0;
+    while i < raw.len() {
+        // A message without headers starts with the blank line
+        if raw[i..].starts_with(b"\r\n") && (i == 0 || raw[i - 1] == b'\n') {
+            return (&raw[..i], &raw[i + 2..]);
+        }
+        if raw[i] == b'\n' && (i == 0 || raw[i - 1] == b'\n') {
+            return (&raw[..i], &raw[i + 1..]);
+        }
+        i += 1;
+    }
+    (raw, &[])
+}
+
+/// Unfold and split "Name: value" lines; names are lowercased
+fn parse_headers(head: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(head);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+/// Split `value; key=val; key="quoted val"` into the value and its parameters.
+/// RFC 2231 `key*=charset''percent%20encoded` parameters are decoded.
+fn parse_params(header: &str) -> (String, IndexMap<String, String>) {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in header.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ';' if !quoted => pieces.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    pieces.push(current);
+
+    let value = pieces[0].trim().to_string();
+    let mut params = IndexMap::new();
+    for piece in &pieces[1..] {
+        if let Some((key, val)) = piece.split_once('=') {
+            let key = key.trim().to_lowercase();
+            let val = val.trim();
+            let val = val.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+                .map(|v| v.replace("\\\"", "\"").replace("\\\\", "\\"))
+                .unwrap_or_else(|| val.to_string());
+            match key.strip_suffix('*') {
+                Some(base) => {
+                    let (charset, text) = match val.splitn(3, '\'').collect::<Vec<_>>().as_slice() {
+                        [charset, _lang, text] => (Some(charset.to_string()), text.to_string()),
+                        _ => (None, val.clone()),
+                    };
+                    let bytes = urlencoding::decode_binary(text.as_bytes()).into_owned();
+                    params.insert(base.to_string(), decode_charset(&bytes, charset.as_deref()));
+                }
+                None => {
+                    params.entry(key).or_insert(val);
+                }
+            }
+        }
+    }
+    (value, params)
+}
+
+/// Bodies of the parts of a multipart entity, without the delimiter lines
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut pos = 0;
+
+    while pos < body.len() {
+        let end = body[pos..].iter().position(|&b| b == b'\n').map_or(body.len(), |p| pos + p + 1);
+        let line = body[pos..end].trim_ascii_end();
+        if let Some(rest) = line.strip_prefix(delimiter.as_bytes()) {
+            if rest.is_empty() || rest == b"--" {
+                if let Some(s) = start {
+                    // The line break before a delimiter belongs to the delimiter
+                    let mut part_end = pos;
+                    if body[..part_end].ends_with(b"\n") { part_end -= 1; }
+                    if body[..part_end].ends_with(b"\r") { part_end -= 1; }
+                    parts.push(&body[s..part_end.max(s)]);
+                }
+                if rest == b"--" {
+                    return parts;
+                }
+                start = Some(end);
+            }
+        }
+        pos = end;
+    }
+
+    // Unterminated multipart: keep the last part
+    if let Some(s) = start {
+        parts.push(&body[s..]);
+    }
+    parts
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        if body[i] == b'=' {
+            // Soft line break
+            if body[i + 1..].starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if body[i + 1..].starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            if let Some(byte) = body.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(body[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Decode text in `charset` (UTF-8 when missing or unknown)
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|c| encoding_rs::Encoding::for_label(c.trim().as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Decode RFC 2047 encoded words (=?charset?B?...?= and =?charset?Q?...?=).
+/// Whitespace between two encoded words is dropped.
+pub fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut pending_space = String::new();
+    let mut last_was_word = false;
+
+    while !rest.is_empty() {
+        if let Some((decoded, len)) = rest.strip_prefix("=?").and_then(|_| decode_word(rest)) {
+            if !last_was_word {
+                out.push_str(&pending_space);
+            }
+            pending_space.clear();
+            out.push_str(&decoded);
+            rest = &rest[len..];
+            last_was_word = true;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            pending_space.push(c);
+        } else {
+            out.push_str(&pending_space);
+            pending_space.clear();
+            out.push(c);
+            last_was_word = false;
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out.push_str(&pending_space);
+    out
+}
+
+/// Decode one encoded word at the start of `s`, returning it and its length
+fn decode_word(s: &str) -> Option<(String, usize)> {
+    let (charset, rest) = s[2..].split_once('?')?;
+    let (encoding, rest) = rest.split_once('?')?;
+    let text = &rest[..rest.find("?=")?];
+    if text.contains(char::is_whitespace) || charset.contains(char::is_whitespace) {
+        return None;
+    }
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + text.len() + 2;
+
+    let bytes = match encoding.to_ascii_uppercase().as_str() {
+        "B" => decode_base64(text.as_bytes())?,
+        "Q" => decode_quoted_printable(text.replace('_', " ").as_bytes()),
+        _ => return None,
+    };
+    // Drop an RFC 2231 language suffix (utf-8*en)
+    let charset = charset.split('*').next().unwrap_or(charset);
+    Some((decode_charset(&bytes, Some(charset)), len))
+}
+
+/// Standard base64, tolerating missing padding
+fn decode_base64(data: &[u8]) -> Option<Vec<u8>> {
+    BASE64.decode(data).ok().or_else(|| {
+        let unpadded = &data[..data.iter().rposition(|&b| b != b'=').map_or(0, |p| p + 1)];
+        base64::engine::general_purpose::STANDARD_NO_PAD.decode(unpadded).ok()
+    })
+}
+
+/// Split an address list at commas outside quotes and angle brackets
+fn split_addresses(value: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut angle = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                out.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    out.push(current);
+    out.into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
@@ -0,0 +1,25 @@
+// Mailbox readers: std/email/imap and std/email/pop3
+//
+// Both protocols are line based and connect through net/stream.rs: TLS from
+// the start (ports 993 / 995), plain, or upgraded with STARTTLS / STLS.
+// Fetched messages are parsed by mime.rs.
+
+pub mod imap;
+pub mod mime;
+pub mod pop3;
+
+pub use imap::{create_imap_module, call_imap_function};
+pub use pop3::{create_pop3_module, call_pop3_function};
+
+use crate::{arg_err, type_err};
+use crate::types::*;
+
+/// Raw message bytes from a Str or Bytes argument (`parse_message(raw)`)
+pub fn message_bytes(func: &str, args: &[QValue]) -> Result<Vec<u8>, String> {
+    match args {
+        [QValue::Bytes(b)] => Ok(b.data.clone()),
+        [QValue::Str(s)] => Ok(s.value.as_bytes().to_vec()),
+        [other] => type_err!("{} expects Str or Bytes, got {}", func, other.as_obj().cls()),
+        _ => arg_err!("{} expects 1 argument (raw message), got {}", func, args.len()),
+    }
+}
@@ -0,0 +1,290 @@
+// POP3 client (std/email/pop3)
+//
+//     use "std/email/pop3"
+//     let mail = pop3.connect("pop.example.com", user: "me", password: pw)
+//     for entry in mail.list()
+//         let msg = mail.fetch(entry["number"])
+//         puts(msg["subject"])
+//         mail.delete(entry["number"])
+//     end
+//     mail.quit()
+//
+// Messages are numbered from 1 within the session. Deletions only take effect
+// when the session ends with quit(); reset() undoes them before that.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use indexmap::IndexMap;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+use crate::types::*;
+use crate::scope::Scope;
+use crate::modules::net::stream::{ConnectOptions, LineStream};
+use super::{message_bytes, mime};
+
+struct Session {
+    stream: LineStream,
+}
+
+impl Session {
+    /// Read a `+OK` / `-ERR` status line and return the text after `+OK`
+    fn status(&mut self, verb: &str) -> Result<String, String> {
+        let line = String::from_utf8_lossy(&self.stream.read_line()?).into_owned();
+        if let Some(rest) = line.strip_prefix("+OK") {
+            return Ok(rest.trim().to_string());
+        }
+        let reason = line.strip_prefix("-ERR").unwrap_or(&line).trim();
+        io_err!("POP3 {} failed: {}", verb, reason)
+    }
+
+    fn command(&mut self, line: &str) -> Result<String, String> {
+        self.stream.send_line(line)?;
+        self.status(line.split(' ').next().unwrap_or(line))
+    }
+
+    /// A command whose reply continues with lines up to a lone "."
+    fn multiline(&mut self, line: &str) -> Result<Vec<Vec<u8>>, String> {
+        self.command(line)?;
+        let mut lines = Vec::new();
+        loop {
+            let mut line = self.stream.read_line()?;
+            if line == b"." {
+                return Ok(lines);
+            }
+            // Lines starting with "." are sent with an extra one
+            if line.starts_with(b".") {
+                line.remove(0);
+            }
+            lines.push(line);
+        }
+    }
+
+    fn login(&mut self, user: &str, password: &str) -> Result<(), String> {
+        self.command(&format!("USER {}", user))?;
+        self.command(&format!("PASS {}", password))?;
+        Ok(())
+    }
+
+    /// `n value` pairs from LIST and UIDL
+    fn listing(&mut self, command: &str) -> Result<Vec<(i64, String)>, String> {
+        Ok(self.multiline(command)?
+            .iter()
+            .filter_map(|line| {
+                let line = String::from_utf8_lossy(line);
+                let mut parts = line.split_whitespace();
+                let number = parts.next()?.parse().ok()?;
+                Some((number, parts.next()?.to_string()))
+            })
+            .collect())
+    }
+}
+
+fn message_number(method: &str, args: &[QValue]) -> Result<i64, String> {
+    match args {
+        [QValue::Int(n)] if n.value > 0 => Ok(n.value),
+        [QValue::Int(n)] => value_err!("{} expects a message number from 1, got {}", method, n.value),
+        [other] => type_err!("{} expects Int message number, got {}", method, other.as_obj().cls()),
+        _ => arg_err!("{} expects 1 argument (message number), got {}", method, args.len()),
+    }
+}
+
+#[derive(Clone)]
+pub struct QPop3Client {
+    session: Rc<RefCell<Option<Session>>>,
+    host: String,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QPop3Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QPop3Client").field("host", &self.host).field("id", &self.id).finish()
+    }
+}
+
+impl QPop3Client {
+    pub fn connect(opts: &ConnectOptions) -> Result<Self, String> {
+        let mut session = Session { stream: LineStream::connect(opts)? };
+        session.status("greeting")?;
+
+        if opts.starttls {
+            session.command("STLS")?;
+            session = Session { stream: session.stream.start_tls(opts)? };
+        }
+        if let Some(user) = &opts.user {
+            session.login(user, opts.password.as_deref().unwrap_or(""))?;
+        }
+
+        Ok(QPop3Client {
+            session: Rc::new(RefCell::new(Some(session))),
+            host: opts.host.clone(),
+            id: next_object_id(),
+        })
+    }
+
+    fn with_session<T>(&self, f: impl FnOnce(&mut Session) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.session.borrow_mut();
+        match guard.as_mut() {
+            Some(session) => f(session),
+            None => io_err!("POP3 connection is closed"),
+        }
+    }
+
+    fn quit(&self) -> Result<(), String> {
+        let Some(mut session) = self.session.borrow_mut().take() else {
+            return Ok(());
+        };
+        session.command("QUIT").map(|_| ())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "login" => {
+                let [user, password] = args.as_slice() else {
+                    return arg_err!("login expects 2 arguments (user, password), got {}", args.len());
+                };
+                self.with_session(|s| s.login(&user.as_str(), &password.as_str()))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "stat" => {
+                let reply = self.with_session(|s| s.command("STAT"))?;
+                let mut numbers = reply.split_whitespace().map(|n| n.parse::<i64>().unwrap_or(0));
+                let mut info = IndexMap::new();
+                info.insert("count".to_string(), QValue::Int(QInt::new(numbers.next().unwrap_or(0))));
+                info.insert("size".to_string(), QValue::Int(QInt::new(numbers.next().unwrap_or(0))));
+                Ok(QValue::Dict(Box::new(QDict::new(info))))
+            }
+            "list" => {
+                // [{number, size, uid}], with uid nil if the server lacks UIDL
+                let (sizes, uids) = self.with_session(|s| {
+                    let sizes = s.listing("LIST")?;
+                    let uids: HashMap<i64, String> = s.listing("UIDL").unwrap_or_default().into_iter().collect();
+                    Ok((sizes, uids))
+                })?;
+                let entries = sizes.into_iter()
+                    .map(|(number, size)| {
+                        let mut entry = IndexMap::new();
+                        entry.insert("number".to_string(), QValue::Int(QInt::new(number)));
+                        entry.insert("size".to_string(), QValue::Int(QInt::new(size.parse().unwrap_or(0))));
+                        entry.insert("uid".to_string(), match uids.get(&number) {
+                            Some(uid) => QValue::Str(QString::new(uid.clone())),
+                            None => QValue::Nil(QNil),
+                        });
+                        QValue::Dict(Box::new(QDict::new(entry)))
+                    })
+                    .collect();
+                Ok(QValue::Array(QArray::new(entries)))
+            }
+            "fetch" => {
+                // fetch(number, headers_only: false)
+                let (positional, headers_only) = match args.as_slice() {
+                    [rest @ .., QValue::Dict(d)] => (rest, d.get("headers_only").is_some_and(|v| v.as_bool())),
+                    _ => (args.as_slice(), false),
+                };
+                let number = message_number("fetch", positional)?;
+                let command = if headers_only { format!("TOP {} 0", number) } else { format!("RETR {}", number) };
+                let lines = self.with_session(|s| s.multiline(&command))?;
+                let raw = lines.join(&b"\r\n"[..]);
+
+                let mut message = if headers_only {
+                    mime::parse_headers_only(&raw)
+                } else {
+                    mime::parse_message(&raw)
+                };
+                message.insert("number".to_string(), QValue::Int(QInt::new(number)));
+                message.insert("size".to_string(), QValue::Int(QInt::new(raw.len() as i64)));
+                if !headers_only {
+                    message.insert("raw".to_string(), QValue::Bytes(QBytes::new(raw)));
+                }
+                Ok(QValue::Dict(Box::new(QDict::new(message))))
+            }
+            "delete" => {
+                let number = message_number("delete", &args)?;
+                self.with_session(|s| s.command(&format!("DELE {}", number)))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "reset" => {
+                self.with_session(|s| s.command("RSET"))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "noop" => {
+                self.with_session(|s| s.command("NOOP"))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "quit" | "close" => {
+                self.quit()?;
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.session.borrow().is_none()))),
+            "_enter" => Ok(QValue::Pop3Client(self.clone())),
+            "_exit" => {
+                let _ = self.quit();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on Pop3Client", method_name),
+        }
+    }
+}
+
+impl QObj for QPop3Client {
+    fn cls(&self) -> String {
+        "Pop3Client".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Pop3Client"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Pop3Client"
+    }
+
+    fn str(&self) -> String {
+        format!("<Pop3Client {}>", self.host)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "POP3 mailbox connection".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_pop3_module() -> QValue {
+    let mut members = HashMap::new();
+
+    for name in ["connect", "parse_message"] {
+        members.insert(name.to_string(), QValue::Fun(QFun {
+            name: name.to_string(),
+            parent_type: "pop3".to_string(),
+            id: next_object_id(),
+        }));
+    }
+
+    QValue::Module(Box::new(QModule::new("pop3".to_string(), members)))
+}
+
+pub fn call_pop3_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "pop3.connect" => {
+            // pop3.connect(host, port: 995, tls: true, starttls: false, user:, password:)
+            let opts = ConnectOptions::parse("pop3.connect", args, 995, 110)?;
+            Ok(QValue::Pop3Client(QPop3Client::connect(&opts)?))
+        }
+        "pop3.parse_message" => {
+            let raw = message_bytes("pop3.parse_message", &args)?;
+            Ok(QValue::Dict(Box::new(QDict::new(mime::parse_message(&raw)))))
+        }
+        _ => attr_err!("Unknown function: {}", func_name),
+    }
+}
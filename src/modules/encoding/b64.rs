@@ -28,8 +28,10 @@ pub fn call_b64_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate:
             if args.len() != 1 {
                 return arg_err!("b64.encode expects 1 argument, got {}", args.len());
             }
-            let data = args[0].as_str();
-            let encoded = general_purpose::STANDARD.encode(data.as_bytes());
+            let encoded = match &args[0] {
+                QValue::Bytes(b) => general_purpose::STANDARD.encode(&b.data),
+                other => general_purpose::STANDARD.encode(other.as_str().as_bytes()),
+            };
             Ok(QValue::Str(QString::new(encoded)))
         }
         "b64.decode" => {
@@ -47,8 +49,10 @@ pub fn call_b64_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate:
             if args.len() != 1 {
                 return arg_err!("b64.encode_url expects 1 argument, got {}", args.len());
             }
-            let data = args[0].as_str();
-            let encoded = general_purpose::URL_SAFE_NO_PAD.encode(data.as_bytes());
+            let encoded = match &args[0] {
+                QValue::Bytes(b) => general_purpose::URL_SAFE_NO_PAD.encode(&b.data),
+                other => general_purpose::URL_SAFE_NO_PAD.encode(other.as_str().as_bytes()),
+            };
             Ok(QValue::Str(QString::new(encoded)))
         }
         "b64.decode_url" => {
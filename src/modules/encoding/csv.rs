@@ -1,14 +1,21 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Write};
+use std::rc::Rc;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
-use csv::{ReaderBuilder, WriterBuilder};
+use csv::{QuoteStyle, ReaderBuilder, StringRecord, Terminator, WriterBuilder};
 use crate::types::*;
-use crate::{arg_err, attr_err, type_err};
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
 
 pub fn create_csv_module() -> QValue {
     let mut members = HashMap::new();
 
     members.insert("parse".to_string(), create_fn("csv", "parse"));
     members.insert("stringify".to_string(), create_fn("csv", "stringify"));
+    members.insert("reader".to_string(), create_fn("csv", "reader"));
+    members.insert("writer".to_string(), create_fn("csv", "writer"));
 
     QValue::Module(Box::new(QModule::new("csv".to_string(), members)))
 }
@@ -17,6 +24,8 @@ pub fn call_csv_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate:
     match func_name {
         "csv.parse" => csv_parse(args),
         "csv.stringify" => csv_stringify(args),
+        "csv.reader" => Ok(csv_reader(args)?),
+        "csv.writer" => Ok(csv_writer(args)?),
         _ => attr_err!("Unknown csv function: {}", func_name)
     }
 }
@@ -73,7 +82,7 @@ fn csv_parse(args: Vec<QValue>) -> Result<QValue, EvalError> {
 
         for result in reader.records() {
             let record = result.map_err(|e| format!("Failed to read record: {}", e))?;
-            let mut row_dict = HashMap::new();
+            let mut row_dict = IndexMap::new();
 
             for (i, field) in record.iter().enumerate() {
                 if let Some(header) = headers.get(i) {
@@ -257,3 +266,525 @@ fn qvalue_to_csv_string(value: &QValue) -> String {
         _ => value.as_str(),
     }
 }
+
+// ============================================================================
+// Streaming reader and writer
+// ============================================================================
+
+/// Split `(source, options)` arguments where options come from keyword
+/// arguments or a trailing Dict
+fn stream_args<'a>(func: &str, args: &'a [QValue]) -> Result<(&'a QValue, IndexMap<String, QValue>), String> {
+    match args {
+        [source] => Ok((source, IndexMap::new())),
+        [source, QValue::Dict(options)] => Ok((source, options.map.borrow().clone())),
+        [_, other] => type_err!("{} options must be Dict, got {}", func, other.as_obj().cls()),
+        _ => arg_err!("{} expects 1-2 arguments (file, [options]), got {}", func, args.len()),
+    }
+}
+
+/// A single-byte option such as `delimiter` or `quote`
+fn byte_option(func: &str, name: &str, value: &QValue) -> Result<u8, String> {
+    match value {
+        QValue::Str(s) if s.value.len() == 1 => Ok(s.value.as_bytes()[0]),
+        QValue::Str(s) => value_err!("{} {} must be a single ASCII character, got '{}'", func, name, s.value),
+        other => type_err!("{} {} must be Str, got {}", func, name, other.as_obj().cls()),
+    }
+}
+
+fn bool_option(func: &str, name: &str, value: &QValue) -> Result<bool, String> {
+    match value {
+        QValue::Bool(b) => Ok(b.value),
+        other => type_err!("{} {} must be Bool, got {}", func, name, other.as_obj().cls()),
+    }
+}
+
+fn header_names(func: &str, names: &QArray) -> Result<Vec<String>, String> {
+    names.elements.borrow().iter().map(|name| match name {
+        QValue::Str(s) => Ok(s.value.to_string()),
+        other => type_err!("{} headers must be an Array of Str, got {}", func, other.as_obj().cls()),
+    }).collect()
+}
+
+/// Map a csv crate error to an IOErr or a ValueErr naming the line
+fn csv_error(action: &str, e: csv::Error) -> String {
+    if let csv::ErrorKind::Io(io_error) = e.kind() {
+        return format!("IOErr: Failed to {} CSV: {}", action, io_error);
+    }
+    match e.position() {
+        Some(pos) => format!("ValueErr: Invalid CSV on line {}: {}", pos.line(), e),
+        None => format!("ValueErr: Invalid CSV: {}", e),
+    }
+}
+
+/// Reads a StringIO from its current position, advancing it as the CSV
+/// reader pulls data
+struct StringIOSource(Rc<RefCell<QStringIO>>);
+
+impl Read for StringIOSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut sio = self.0.borrow_mut();
+        let start = sio.position.min(sio.buffer.len());
+        let n = buf.len().min(sio.buffer.len() - start);
+        buf[..n].copy_from_slice(&sio.buffer.as_bytes()[start..start + n]);
+        sio.position = start + n;
+        Ok(n)
+    }
+}
+
+/// Appends to a StringIO, holding back a multi-byte character split across
+/// two writes until it is complete
+struct StringIOSink {
+    target: Rc<RefCell<QStringIO>>,
+    pending: Vec<u8>,
+}
+
+impl Write for StringIOSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = String::from_utf8(self.pending.drain(..valid).collect()).unwrap_or_default();
+        self.target.borrow_mut().write(&text);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Open the input of a streaming reader (`csv.reader`, `json.lines`, ...): a
+/// file path, in-memory Bytes, a StringIO or a process stdout stream
+pub(crate) fn open_source(func: &str, value: &QValue) -> Result<Box<dyn Read>, String> {
+    match value {
+        QValue::Str(s) => {
+            let file = File::open(s.value.as_str())
+                .map_err(|e| format!("IOErr: Failed to open '{}': {}", s.value, e))?;
+            Ok(Box::new(file))
+        }
+        QValue::Bytes(b) => Ok(Box::new(Cursor::new(b.data.clone()))),
+        QValue::StringIO(sio) => Ok(Box::new(StringIOSource(Rc::clone(sio)))),
+        QValue::ReadableStream(stream) => Ok(Box::new(stream.clone())),
+        other => type_err!("{} expects a path, Bytes, StringIO or ReadableStream, got {}", func, other.as_obj().cls()),
+    }
+}
+
+/// Open the output of `csv.writer`: a file path, a StringIO or a process
+/// stdin stream
+fn open_sink(value: &QValue) -> Result<Box<dyn Write>, String> {
+    match value {
+        QValue::Str(s) => {
+            let file = File::create(s.value.as_str())
+                .map_err(|e| format!("IOErr: Failed to create '{}': {}", s.value, e))?;
+            Ok(Box::new(file))
+        }
+        QValue::StringIO(sio) => Ok(Box::new(StringIOSink { target: Rc::clone(sio), pending: Vec::new() })),
+        QValue::WritableStream(stream) => Ok(Box::new(stream.clone())),
+        other => type_err!("writer expects a path, StringIO or WritableStream, got {}", other.as_obj().cls()),
+    }
+}
+
+/// csv.reader(file, delimiter: ",", headers: true, trim: true, types: true, quote: "\"")
+fn csv_reader(args: Vec<QValue>) -> Result<QValue, String> {
+    let (source, options) = stream_args("reader", &args)?;
+
+    let mut builder = ReaderBuilder::new();
+    let mut headers = RowHeaders::FromFile;
+    let mut trim = true;
+    let mut types = true;
+    for (name, value) in &options {
+        match name.as_str() {
+            "delimiter" => { builder.delimiter(byte_option("reader", name, value)?); }
+            "quote" => { builder.quote(byte_option("reader", name, value)?); }
+            "headers" => headers = match value {
+                QValue::Bool(b) if b.value => RowHeaders::FromFile,
+                QValue::Bool(_) => RowHeaders::None,
+                QValue::Array(names) => RowHeaders::Given(header_names("reader", names)?),
+                other => return type_err!("reader headers must be Bool or Array, got {}", other.as_obj().cls()),
+            },
+            "trim" => trim = bool_option("reader", name, value)?,
+            "types" => types = bool_option("reader", name, value)?,
+            "flexible" => { builder.flexible(bool_option("reader", name, value)?); }
+            _ => return arg_err!("reader got unexpected option '{}' (expected delimiter, quote, headers, trim, types, flexible)", name),
+        }
+    }
+    builder.has_headers(false).trim(if trim { csv::Trim::All } else { csv::Trim::None });
+
+    let reader = builder.from_reader(open_source("reader", source)?);
+    Ok(QValue::CsvReader(QCsvReader {
+        state: Rc::new(RefCell::new(Some(ReaderState { reader, headers, types, record: StringRecord::new(), line: 0 }))),
+        id: next_object_id(),
+    }))
+}
+
+/// Where a reader's column names come from
+enum RowHeaders {
+    /// Read from the first record on first use
+    FromFile,
+    /// Known column names
+    Given(Vec<String>),
+    /// Rows are returned as Arrays
+    None,
+}
+
+struct ReaderState {
+    reader: csv::Reader<Box<dyn Read>>,
+    headers: RowHeaders,
+    types: bool,
+    record: StringRecord,
+    line: u64,
+}
+
+impl ReaderState {
+    /// Read the next record into `self.record`, returning false at the end
+    fn read_record(&mut self) -> Result<bool, String> {
+        let more = self.reader.read_record(&mut self.record).map_err(|e| csv_error("read", e))?;
+        if more {
+            self.line = self.record.position().map(|p| p.line()).unwrap_or(self.line + 1);
+        }
+        Ok(more)
+    }
+
+    /// The column names, reading the header row if it has not been read yet
+    fn headers(&mut self) -> Result<Option<&[String]>, String> {
+        if let RowHeaders::FromFile = self.headers {
+            let names = if self.read_record()? {
+                self.record.iter().map(str::to_string).collect()
+            } else {
+                Vec::new()
+            };
+            self.headers = RowHeaders::Given(names);
+        }
+        Ok(match &self.headers {
+            RowHeaders::Given(names) => Some(names),
+            _ => None,
+        })
+    }
+
+    fn value(&self, field: &str) -> QValue {
+        if self.types {
+            parse_csv_value(field, false)
+        } else {
+            QValue::Str(QString::new(field.to_string()))
+        }
+    }
+
+    fn next_row(&mut self) -> Result<Option<QValue>, String> {
+        if self.headers()?.is_some() && !self.read_record()? {
+            return Ok(None);
+        }
+        if let RowHeaders::None = self.headers {
+            if !self.read_record()? {
+                return Ok(None);
+            }
+            let row = self.record.iter().map(|field| self.value(field)).collect();
+            return Ok(Some(QValue::Array(QArray::new(row))));
+        }
+        let RowHeaders::Given(names) = &self.headers else { unreachable!() };
+        let mut row = IndexMap::new();
+        for (i, field) in self.record.iter().enumerate() {
+            // Extra fields of a flexible reader get positional names
+            let key = names.get(i).cloned().unwrap_or_else(|| i.to_string());
+            row.insert(key, self.value(field));
+        }
+        Ok(Some(QValue::Dict(Box::new(QDict::new(row)))))
+    }
+}
+
+/// Lazily reads rows from a file or stream, one record at a time
+#[derive(Clone)]
+pub struct QCsvReader {
+    state: Rc<RefCell<Option<ReaderState>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCsvReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCsvReader").field("id", &self.id).finish()
+    }
+}
+
+impl QCsvReader {
+    /// The next row, or None once the input is exhausted or the reader closed
+    pub fn next_row(&self) -> Result<Option<QValue>, String> {
+        match self.state.borrow_mut().as_mut() {
+            Some(state) => state.next_row(),
+            None => Ok(None),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "next" => {
+                if !args.is_empty() {
+                    return arg_err!("next expects 0 arguments, got {}", args.len());
+                }
+                Ok(self.next_row()?.unwrap_or(QValue::Nil(QNil)))
+            }
+            "read" => {
+                // read() -> all remaining rows, read(n) -> up to n rows
+                let limit = match args.as_slice() {
+                    [] => None,
+                    [QValue::Int(n)] if n.value >= 0 => Some(n.value as usize),
+                    [QValue::Int(n)] => return value_err!("read count must be non-negative, got {}", n.value),
+                    [other] => return type_err!("read expects Int, got {}", other.as_obj().cls()),
+                    _ => return arg_err!("read expects 0-1 arguments ([count]), got {}", args.len()),
+                };
+                let mut rows = Vec::new();
+                while limit.is_none_or(|n| rows.len() < n) {
+                    match self.next_row()? {
+                        Some(row) => rows.push(row),
+                        None => break,
+                    }
+                }
+                Ok(QValue::Array(QArray::new(rows)))
+            }
+            "headers" => {
+                if !args.is_empty() {
+                    return arg_err!("headers expects 0 arguments, got {}", args.len());
+                }
+                let mut guard = self.state.borrow_mut();
+                let names = match guard.as_mut() {
+                    Some(state) => state.headers()?.map(|names| names.to_vec()),
+                    None => None,
+                };
+                Ok(match names {
+                    Some(names) => QValue::Array(QArray::new(names.into_iter().map(|n| QValue::Str(QString::new(n))).collect())),
+                    None => QValue::Nil(QNil),
+                })
+            }
+            "line" => {
+                if !args.is_empty() {
+                    return arg_err!("line expects 0 arguments, got {}", args.len());
+                }
+                let line = self.state.borrow().as_ref().map(|state| state.line).unwrap_or(0);
+                Ok(QValue::Int(QInt::new(line as i64)))
+            }
+            "close" => {
+                if !args.is_empty() {
+                    return arg_err!("close expects 0 arguments, got {}", args.len());
+                }
+                self.state.borrow_mut().take();
+                Ok(QValue::Nil(QNil))
+            }
+            "is_closed" => Ok(QValue::Bool(QBool::new(self.state.borrow().is_none()))),
+            _ => attr_err!("Unknown method '{}' on CsvReader", method_name),
+        }
+    }
+}
+
+impl QObj for QCsvReader {
+    fn cls(&self) -> String {
+        "CsvReader".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "CsvReader"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "CsvReader"
+    }
+
+    fn str(&self) -> String {
+        "<CsvReader>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Streaming CSV reader yielding one row at a time".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// csv.writer(file, delimiter: ",", quote: "necessary", quote_char: "\"", headers: [...], line_terminator: "\n")
+fn csv_writer(args: Vec<QValue>) -> Result<QValue, String> {
+    let (sink, options) = stream_args("writer", &args)?;
+
+    let mut builder = WriterBuilder::new();
+    let mut headers = None;
+    for (name, value) in &options {
+        match name.as_str() {
+            "delimiter" => { builder.delimiter(byte_option("writer", name, value)?); }
+            "quote_char" => { builder.quote(byte_option("writer", name, value)?); }
+            "quote" => {
+                let style = match value.as_str().as_str() {
+                    "necessary" => QuoteStyle::Necessary,
+                    "always" => QuoteStyle::Always,
+                    "non_numeric" => QuoteStyle::NonNumeric,
+                    "never" => QuoteStyle::Never,
+                    other => return value_err!("writer quote must be 'necessary', 'always', 'non_numeric' or 'never', got '{}'", other),
+                };
+                builder.quote_style(style);
+            }
+            "line_terminator" => {
+                let terminator = match value.as_str().as_str() {
+                    "\n" => Terminator::Any(b'\n'),
+                    "\r\n" => Terminator::CRLF,
+                    other => return value_err!("writer line_terminator must be \"\\n\" or \"\\r\\n\", got {:?}", other),
+                };
+                builder.terminator(terminator);
+            }
+            "headers" => headers = match value {
+                QValue::Array(names) => Some(header_names("writer", names)?),
+                QValue::Nil(_) => None,
+                other => return type_err!("writer headers must be Array, got {}", other.as_obj().cls()),
+            },
+            _ => return arg_err!("writer got unexpected option '{}' (expected delimiter, quote, quote_char, headers, line_terminator)", name),
+        }
+    }
+
+    let writer = builder.from_writer(open_sink(sink)?);
+    Ok(QValue::CsvWriter(QCsvWriter {
+        state: Rc::new(RefCell::new(Some(WriterState { writer, headers, header_written: false, rows: 0 }))),
+        id: next_object_id(),
+    }))
+}
+
+struct WriterState {
+    writer: csv::Writer<Box<dyn Write>>,
+    headers: Option<Vec<String>>,
+    header_written: bool,
+    rows: usize,
+}
+
+impl WriterState {
+    fn write_header(&mut self) -> Result<(), String> {
+        if let (Some(headers), false) = (&self.headers, self.header_written) {
+            self.writer.write_record(headers).map_err(|e| csv_error("write", e))?;
+        }
+        self.header_written = true;
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &QValue) -> Result<(), String> {
+        let record: Vec<String> = match row {
+            QValue::Dict(dict) => {
+                let map = dict.map.borrow();
+                // Column order comes from the first Dict unless headers were given
+                let headers = self.headers.get_or_insert_with(|| map.keys().cloned().collect());
+                headers.iter()
+                    .map(|h| map.get(h).map(qvalue_to_csv_string).unwrap_or_default())
+                    .collect()
+            }
+            QValue::Array(values) => values.elements.borrow().iter().map(qvalue_to_csv_string).collect(),
+            other => return type_err!("write_row expects Dict or Array, got {}", other.as_obj().cls()),
+        };
+        self.write_header()?;
+        self.writer.write_record(&record).map_err(|e| csv_error("write", e))?;
+        self.rows += 1;
+        Ok(())
+    }
+}
+
+/// Writes rows to a file or stream as they are produced
+#[derive(Clone)]
+pub struct QCsvWriter {
+    state: Rc<RefCell<Option<WriterState>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCsvWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCsvWriter").field("id", &self.id).finish()
+    }
+}
+
+impl QCsvWriter {
+    fn with_state<T>(&self, op: impl FnOnce(&mut WriterState) -> Result<T, String>) -> Result<T, String> {
+        match self.state.borrow_mut().as_mut() {
+            Some(state) => op(state),
+            None => io_err!("CSV writer is closed"),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "write_row" => {
+                if args.len() != 1 {
+                    return arg_err!("write_row expects 1 argument (row), got {}", args.len());
+                }
+                self.with_state(|state| state.write_row(&args[0]))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "write_rows" => {
+                let rows = match args.as_slice() {
+                    [QValue::Array(rows)] => rows.elements.borrow().clone(),
+                    [other] => return type_err!("write_rows expects Array, got {}", other.as_obj().cls()),
+                    _ => return arg_err!("write_rows expects 1 argument (rows), got {}", args.len()),
+                };
+                self.with_state(|state| rows.iter().try_for_each(|row| state.write_row(row)))?;
+                Ok(QValue::Int(QInt::new(rows.len() as i64)))
+            }
+            "flush" => {
+                if !args.is_empty() {
+                    return arg_err!("flush expects 0 arguments, got {}", args.len());
+                }
+                self.with_state(|state| state.writer.flush()
+                    .map_err(|e| format!("IOErr: Failed to flush CSV: {}", e)))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "rows_written" => Ok(QValue::Int(QInt::new(self.with_state(|state| Ok(state.rows))? as i64))),
+            "close" => {
+                if !args.is_empty() {
+                    return arg_err!("close expects 0 arguments, got {}", args.len());
+                }
+                // Closing twice is a no-op; a header-only file still gets its header
+                if let Some(mut state) = self.state.borrow_mut().take() {
+                    state.write_header()?;
+                    state.writer.flush().map_err(|e| format!("IOErr: Failed to flush CSV: {}", e))?;
+                }
+                Ok(QValue::Nil(QNil))
+            }
+            "is_closed" => Ok(QValue::Bool(QBool::new(self.state.borrow().is_none()))),
+            _ => attr_err!("Unknown method '{}' on CsvWriter", method_name),
+        }
+    }
+}
+
+impl QObj for QCsvWriter {
+    fn cls(&self) -> String {
+        "CsvWriter".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "CsvWriter"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "CsvWriter"
+    }
+
+    fn str(&self) -> String {
+        "<CsvWriter>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Streaming CSV writer".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
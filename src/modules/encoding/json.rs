@@ -1,8 +1,12 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::{self, Read};
+use std::rc::Rc;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::types::*;
 use crate::encoding::json_utils::{qvalue_to_json, json_to_qvalue};
-use crate::{arg_err, attr_err};
+use crate::{arg_err, attr_err, type_err, value_err};
 
 pub fn create_json_module() -> QValue {
     // Create a wrapper for json functions
@@ -17,6 +21,10 @@ pub fn create_json_module() -> QValue {
     members.insert("try_parse".to_string(), create_json_fn("try_parse"));
     members.insert("is_valid".to_string(), create_json_fn("is_valid"));
 
+    // Streaming
+    members.insert("parse_stream".to_string(), create_json_fn("parse_stream"));
+    members.insert("lines".to_string(), create_json_fn("lines"));
+
     // Serialization functions
     members.insert("stringify".to_string(), create_json_fn("stringify"));
     members.insert("stringify_pretty".to_string(), create_json_fn("stringify_pretty"));
@@ -31,57 +39,599 @@ pub fn create_json_module() -> QValue {
 pub fn call_json_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
     match func_name {
         "json.parse" => {
-            if args.len() != 1 {
-                return arg_err!("parse expects 1 argument, got {}", args.len());
-            }
-            let json_str = args[0].as_str();
-            let json_value: serde_json::Value = serde_json::from_str(&json_str)
+            let (text, syntax) = text_and_syntax("parse", &args)?;
+            let json_value: serde_json::Value = serde_json::from_str(&syntax.prepare(&text)?)
                 .map_err(|e| format!("JSON parse error: {}", e))?;
             json_to_qvalue(json_value)
         }
 
         "json.try_parse" => {
-            if args.len() != 1 {
-                return arg_err!("try_parse expects 1 argument, got {}", args.len());
-            }
-            let json_str = args[0].as_str();
-            match serde_json::from_str::<serde_json::Value>(&json_str) {
+            let (text, syntax) = text_and_syntax("try_parse", &args)?;
+            let Ok(text) = syntax.prepare(&text) else {
+                return Ok(QValue::Nil(QNil));
+            };
+            match serde_json::from_str::<serde_json::Value>(&text) {
                 Ok(json_value) => json_to_qvalue(json_value),
                 Err(_) => Ok(QValue::Nil(QNil)),
             }
         }
 
         "json.is_valid" => {
-            if args.len() != 1 {
-                return arg_err!("is_valid expects 1 argument, got {}", args.len());
-            }
-            let json_str = args[0].as_str();
-            let is_valid = serde_json::from_str::<serde_json::Value>(&json_str).is_ok();
+            let (text, syntax) = text_and_syntax("is_valid", &args)?;
+            let is_valid = syntax.prepare(&text)
+                .is_ok_and(|text| serde_json::from_str::<serde_json::Value>(&text).is_ok());
             Ok(QValue::Bool(QBool::new(is_valid)))
         }
 
+        "json.parse_stream" | "json.lines" => {
+            let name = func_name.trim_start_matches("json.");
+            let (source, options) = match args.as_slice() {
+                [source] => (source, IndexMap::new()),
+                [source, QValue::Dict(options)] => (source, options.map.borrow().clone()),
+                [_, other] => return type_err!("{} options must be Dict, got {}", name, other.as_obj().cls()),
+                _ => return arg_err!("{} expects 1-2 arguments (file, [options]), got {}", name, args.len()),
+            };
+            let syntax = Syntax::from_options(name, &options)?;
+            let source = crate::modules::encoding::csv::open_source(name, source)?;
+            let mode = if name == "lines" { StreamMode::Lines } else { StreamMode::Start };
+            Ok(QValue::JsonStream(QJsonStream::new(source, mode, syntax)))
+        }
+
         "json.stringify" => {
             if args.is_empty() {
                 return arg_err!("stringify expects at least 1 argument, got 0");
             }
-            let value = &args[0];
-            let json_value = qvalue_to_json(value)?;
-            let json_str = serde_json::to_string(&json_value)
-                .map_err(|e| format!("JSON stringify error: {}", e))?;
-            Ok(QValue::Str(QString::new(json_str)))
+            let format = Format::from_args("stringify", &args[1..], None)?;
+            Ok(QValue::Str(QString::new(format.write(&args[0])?)))
         }
 
         "json.stringify_pretty" => {
             if args.is_empty() {
                 return arg_err!("stringify_pretty expects at least 1 argument, got 0");
             }
-            let value = &args[0];
-            let json_value = qvalue_to_json(value)?;
-            let json_str = serde_json::to_string_pretty(&json_value)
-                .map_err(|e| format!("JSON stringify error: {}", e))?;
-            Ok(QValue::Str(QString::new(json_str)))
+            let format = Format::from_args("stringify_pretty", &args[1..], Some(b"  ".to_vec()))?;
+            Ok(QValue::Str(QString::new(format.write(&args[0])?)))
         }
 
         _ => attr_err!("Unknown json function: {}", func_name)
     }
 }
+
+/// Output options of stringify: `indent` (spaces or a string, nil for
+/// compact output) and `sort_keys`
+struct Format {
+    indent: Option<Vec<u8>>,
+    sort_keys: bool,
+}
+
+impl Format {
+    fn from_args(func: &str, args: &[QValue], indent: Option<Vec<u8>>) -> Result<Self, String> {
+        let mut format = Format { indent, sort_keys: false };
+        let options = match args {
+            [] => return Ok(format),
+            [QValue::Dict(options)] => options.map.borrow().clone(),
+            [other] => return type_err!("{} options must be Dict, got {}", func, other.as_obj().cls()),
+            _ => return arg_err!("{} expects 1-2 arguments (value, [options]), got {}", func, args.len() + 1),
+        };
+        for (name, value) in &options {
+            match (name.as_str(), value) {
+                ("indent", QValue::Nil(_)) => format.indent = None,
+                ("indent", QValue::Int(n)) if (0..=16).contains(&n.value) => {
+                    format.indent = Some(vec![b' '; n.value as usize]);
+                }
+                ("indent", QValue::Int(n)) => return value_err!("{} indent must be between 0 and 16, got {}", func, n.value),
+                ("indent", QValue::Str(s)) => format.indent = Some(s.value.as_bytes().to_vec()),
+                ("indent", other) => return type_err!("{} indent must be Int, Str or nil, got {}", func, other.as_obj().cls()),
+                ("sort_keys", QValue::Bool(b)) => format.sort_keys = b.value,
+                ("sort_keys", other) => return type_err!("{} sort_keys must be Bool, got {}", func, other.as_obj().cls()),
+                _ => return arg_err!("{} got unexpected option '{}' (expected indent, sort_keys)", func, name),
+            }
+        }
+        Ok(format)
+    }
+
+    fn write(&self, value: &QValue) -> Result<String, String> {
+        let mut json_value = qvalue_to_json(value)?;
+        if self.sort_keys {
+            sort_keys(&mut json_value);
+        }
+        let out = match &self.indent {
+            None => serde_json::to_vec(&json_value),
+            Some(indent) => {
+                let mut out = Vec::new();
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(indent);
+                let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+                serde::Serialize::serialize(&json_value, &mut serializer).map(|_| out)
+            }
+        }.map_err(|e| format!("JSON stringify error: {}", e))?;
+        String::from_utf8(out).map_err(|e| format!("JSON stringify error: {}", e))
+    }
+}
+
+/// Sort object keys recursively (objects keep insertion order otherwise)
+fn sort_keys(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(sort_keys);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+/// Non-standard syntax a parse accepts: `//` and `/* */` comments, and a
+/// trailing comma before `]` or `}`
+#[derive(Clone, Copy, Default)]
+struct Syntax {
+    comments: bool,
+    trailing_commas: bool,
+}
+
+impl Syntax {
+    fn from_options(func: &str, options: &IndexMap<String, QValue>) -> Result<Self, String> {
+        let mut syntax = Syntax::default();
+        for (name, value) in options {
+            let flag = match name.as_str() {
+                "comments" => &mut syntax.comments,
+                "trailing_commas" => &mut syntax.trailing_commas,
+                _ => return arg_err!("{} got unexpected option '{}' (expected comments, trailing_commas)", func, name),
+            };
+            match value {
+                QValue::Bool(b) => *flag = b.value,
+                other => return type_err!("{} {} must be Bool, got {}", func, name, other.as_obj().cls()),
+            }
+        }
+        Ok(syntax)
+    }
+
+    /// Blank out comments and trailing commas with spaces so that serde_json
+    /// accepts the text and error positions still match the input
+    fn prepare<'a>(&self, text: &'a str) -> Result<std::borrow::Cow<'a, str>, String> {
+        if !self.comments && !self.trailing_commas {
+            return Ok(text.into());
+        }
+        let mut bytes = text.as_bytes().to_vec();
+        if self.comments {
+            blank_comments(&mut bytes)?;
+        }
+        if self.trailing_commas {
+            blank_trailing_commas(&mut bytes);
+        }
+        // Only ASCII bytes were replaced with spaces, so this stays UTF-8
+        Ok(String::from_utf8(bytes).map_err(|e| e.to_string())?.into())
+    }
+}
+
+/// Calls `visit(bytes, i)` for every byte index outside string literals;
+/// `visit` returns how many bytes to skip
+fn outside_strings(bytes: &mut [u8], mut visit: impl FnMut(&mut [u8], usize) -> Result<usize, String>) -> Result<(), String> {
+    let mut i = 0;
+    let mut in_string = false;
+    while i < bytes.len() {
+        if in_string {
+            match bytes[i] {
+                b'\\' => i += 1,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+        } else if bytes[i] == b'"' {
+            in_string = true;
+            i += 1;
+        } else {
+            i += visit(bytes, i)?.max(1);
+        }
+    }
+    Ok(())
+}
+
+fn blank_comments(bytes: &mut [u8]) -> Result<(), String> {
+    outside_strings(bytes, |bytes, i| {
+        let end = match (bytes[i], bytes.get(i + 1)) {
+            (b'/', Some(b'/')) => bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |n| i + n),
+            (b'/', Some(b'*')) => match bytes[i + 2..].windows(2).position(|w| w == b"*/") {
+                Some(n) => i + 2 + n + 2,
+                None => return value_err!("JSON parse error: unterminated /* comment"),
+            },
+            _ => return Ok(1),
+        };
+        for b in &mut bytes[i..end] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+        Ok(end - i)
+    })
+}
+
+fn blank_trailing_commas(bytes: &mut [u8]) {
+    let _ = outside_strings(bytes, |bytes, i| {
+        if bytes[i] == b',' {
+            let next = bytes[i + 1..].iter().find(|b| !b.is_ascii_whitespace());
+            if matches!(next, Some(b']') | Some(b'}')) {
+                bytes[i] = b' ';
+            }
+        }
+        Ok(1)
+    });
+}
+
+fn text_and_syntax(func: &str, args: &[QValue]) -> Result<(String, Syntax), String> {
+    match args {
+        [text] => Ok((text.as_str(), Syntax::default())),
+        [text, QValue::Dict(options)] => Ok((text.as_str(), Syntax::from_options(func, &options.map.borrow())?)),
+        [_, other] => type_err!("{} options must be Dict, got {}", func, other.as_obj().cls()),
+        _ => arg_err!("{} expects 1-2 arguments (text, [options]), got {}", func, args.len()),
+    }
+}
+
+// ============================================================================
+// Streaming parser
+// ============================================================================
+
+/// Where a stream is in its input
+#[derive(Clone, Copy, PartialEq)]
+enum StreamMode {
+    /// Nothing read yet; the first byte decides between Items and Values
+    Start,
+    /// Inside a top-level array, yielding its elements
+    Items { first: bool },
+    /// A sequence of whitespace-separated top-level values
+    Values,
+    /// JSON Lines: one value per non-blank line
+    Lines,
+    Done,
+}
+
+struct StreamState {
+    source: Box<dyn Read>,
+    buf: Vec<u8>,
+    pos: usize,
+    /// 1-based line of the next unread byte
+    line: usize,
+    mode: StreamMode,
+    syntax: Syntax,
+}
+
+impl StreamState {
+    fn io_error(e: io::Error) -> String {
+        format!("IOErr: Failed to read JSON: {}", e)
+    }
+
+    /// The byte `ahead` positions past the read position, reading more input as needed
+    fn peek_at(&mut self, ahead: usize) -> Result<Option<u8>, String> {
+        while self.pos + ahead >= self.buf.len() {
+            if self.pos > 0 {
+                self.buf.drain(..self.pos);
+                self.pos = 0;
+            }
+            let start = self.buf.len();
+            self.buf.resize(start + 64 * 1024, 0);
+            let n = loop {
+                match self.source.read(&mut self.buf[start..]) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(Self::io_error(e)),
+                }
+            };
+            self.buf.truncate(start + n);
+            if n == 0 {
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.buf[self.pos + ahead]))
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, String> {
+        self.peek_at(0)
+    }
+
+    fn bump(&mut self, out: Option<&mut Vec<u8>>) {
+        let b = self.buf[self.pos];
+        if b == b'\n' {
+            self.line += 1;
+        }
+        if let Some(out) = out {
+            out.push(b);
+        }
+        self.pos += 1;
+    }
+
+    /// Whether a comment starts at the read position
+    fn at_comment(&mut self) -> Result<bool, String> {
+        Ok(self.syntax.comments && self.peek()? == Some(b'/') && matches!(self.peek_at(1)?, Some(b'/') | Some(b'*')))
+    }
+
+    /// Consume a comment, appending spaces (and its newlines) to `out`
+    fn skip_comment(&mut self, mut out: Option<&mut Vec<u8>>) -> Result<(), String> {
+        let block = self.peek_at(1)? == Some(b'*');
+        self.pos += 2;
+        loop {
+            match self.peek()? {
+                None if block => return value_err!("Invalid JSON on line {}: unterminated /* comment", self.line),
+                None => return Ok(()),
+                Some(b'\n') if !block => return Ok(()),
+                Some(b'*') if block && self.peek_at(1)? == Some(b'/') => {
+                    self.pos += 2;
+                    return Ok(());
+                }
+                Some(b) => {
+                    let blank = if b == b'\n' { b'\n' } else { b' ' };
+                    self.bump(None);
+                    if let Some(out) = out.as_deref_mut() {
+                        out.push(blank);
+                    }
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), String> {
+        loop {
+            match self.peek()? {
+                Some(b) if b.is_ascii_whitespace() => self.bump(None),
+                Some(b'/') if self.at_comment()? => self.skip_comment(None)?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Copy the bytes of one JSON value, tracking strings and nesting to find its end
+    fn read_value(&mut self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        let mut depth = 0usize;
+        let mut in_string = false;
+        while let Some(b) = self.peek()? {
+            if in_string {
+                if b == b'\\' {
+                    self.bump(Some(&mut out));
+                    if self.peek()?.is_some() {
+                        self.bump(Some(&mut out));
+                    }
+                    continue;
+                }
+                self.bump(Some(&mut out));
+                if b == b'"' {
+                    in_string = false;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                continue;
+            }
+            match b {
+                b'"' => in_string = true,
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' if depth == 0 => break,
+                b']' | b'}' => {
+                    depth -= 1;
+                    self.bump(Some(&mut out));
+                    if depth == 0 {
+                        break;
+                    }
+                    continue;
+                }
+                b'/' if self.at_comment()? => {
+                    if depth == 0 {
+                        break;
+                    }
+                    self.skip_comment(Some(&mut out))?;
+                    continue;
+                }
+                b',' if depth == 0 => break,
+                _ if b.is_ascii_whitespace() && depth == 0 => break,
+                _ => {}
+            }
+            self.bump(Some(&mut out));
+        }
+        Ok(out)
+    }
+
+    fn parse(&self, raw: &[u8], line: usize) -> Result<QValue, String> {
+        let text = String::from_utf8_lossy(raw);
+        let text = self.syntax.prepare(&text)?;
+        let value: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| {
+                // serde_json's position is relative to this value, not the input
+                let message = e.to_string();
+                let message = message.rfind(" at line ").map_or(message.as_str(), |i| &message[..i]);
+                format!("ValueErr: Invalid JSON on line {}: {}", line + e.line().max(1) - 1, message)
+            })?;
+        json_to_qvalue(value).map_err(|e| e.to_string())
+    }
+
+    fn next_line(&mut self) -> Result<Option<QValue>, String> {
+        loop {
+            let line = self.line;
+            let mut raw = Vec::new();
+            while let Some(b) = self.peek()? {
+                self.bump(None);
+                if b == b'\n' {
+                    break;
+                }
+                raw.push(b);
+            }
+            if raw.is_empty() && self.peek()?.is_none() && line == self.line {
+                self.mode = StreamMode::Done;
+                return Ok(None);
+            }
+            if raw.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            return self.parse(&raw, line).map(Some);
+        }
+    }
+
+    fn next_value(&mut self) -> Result<Option<QValue>, String> {
+        loop {
+            self.skip_whitespace()?;
+            match self.mode {
+                StreamMode::Done => return Ok(None),
+                StreamMode::Lines => return self.next_line(),
+                StreamMode::Start => {
+                    if self.peek()? == Some(b'[') {
+                        self.bump(None);
+                        self.mode = StreamMode::Items { first: true };
+                    } else {
+                        self.mode = StreamMode::Values;
+                    }
+                }
+                StreamMode::Values => {
+                    if self.peek()?.is_none() {
+                        self.mode = StreamMode::Done;
+                        return Ok(None);
+                    }
+                    let line = self.line;
+                    let raw = self.read_value()?;
+                    if raw.is_empty() {
+                        let found = self.peek()?.map(char::from).unwrap_or(' ');
+                        return value_err!("Invalid JSON on line {}: unexpected '{}'", self.line, found);
+                    }
+                    return self.parse(&raw, line).map(Some);
+                }
+                StreamMode::Items { first } => {
+                    match self.peek()? {
+                        Some(b']') => {
+                            self.bump(None);
+                            self.skip_whitespace()?;
+                            if self.peek()?.is_some() {
+                                return value_err!("Invalid JSON on line {}: trailing characters after array", self.line);
+                            }
+                            self.mode = StreamMode::Done;
+                            return Ok(None);
+                        }
+                        None => return value_err!("Invalid JSON on line {}: unterminated array", self.line),
+                        Some(b',') if !first => {
+                            self.bump(None);
+                            self.skip_whitespace()?;
+                            if self.syntax.trailing_commas && self.peek()? == Some(b']') {
+                                continue;
+                            }
+                        }
+                        Some(b) if !first => {
+                            return value_err!("Invalid JSON on line {}: expected ',' or ']', found '{}'", self.line, char::from(b));
+                        }
+                        Some(_) => {}
+                    }
+                    self.mode = StreamMode::Items { first: false };
+                    let line = self.line;
+                    let raw = self.read_value()?;
+                    if raw.is_empty() {
+                        let found = self.peek()?.map(char::from).unwrap_or(' ');
+                        return value_err!("Invalid JSON on line {}: unexpected '{}'", self.line, found);
+                    }
+                    return self.parse(&raw, line).map(Some);
+                }
+            }
+        }
+    }
+}
+
+/// Lazily parses values from a file or stream: the elements of a top-level
+/// array, a sequence of concatenated values, or JSON Lines
+#[derive(Clone)]
+pub struct QJsonStream {
+    state: Rc<RefCell<Option<StreamState>>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QJsonStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QJsonStream").field("id", &self.id).finish()
+    }
+}
+
+impl QJsonStream {
+    fn new(source: Box<dyn Read>, mode: StreamMode, syntax: Syntax) -> Self {
+        let state = StreamState { source, buf: Vec::new(), pos: 0, line: 1, mode, syntax };
+        QJsonStream { state: Rc::new(RefCell::new(Some(state))), id: next_object_id() }
+    }
+
+    /// The next value, or None once the input is exhausted or the stream closed
+    pub fn next_value(&self) -> Result<Option<QValue>, String> {
+        match self.state.borrow_mut().as_mut() {
+            Some(state) => state.next_value(),
+            None => Ok(None),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "next" => {
+                if !args.is_empty() {
+                    return arg_err!("next expects 0 arguments, got {}", args.len());
+                }
+                Ok(self.next_value()?.unwrap_or(QValue::Nil(QNil)))
+            }
+            "read" => {
+                // read() -> all remaining values, read(n) -> up to n values
+                let limit = match args.as_slice() {
+                    [] => None,
+                    [QValue::Int(n)] if n.value >= 0 => Some(n.value as usize),
+                    [QValue::Int(n)] => return value_err!("read count must be non-negative, got {}", n.value),
+                    [other] => return type_err!("read expects Int, got {}", other.as_obj().cls()),
+                    _ => return arg_err!("read expects 0-1 arguments ([count]), got {}", args.len()),
+                };
+                let mut values = Vec::new();
+                while limit.is_none_or(|n| values.len() < n) {
+                    match self.next_value()? {
+                        Some(value) => values.push(value),
+                        None => break,
+                    }
+                }
+                Ok(QValue::Array(QArray::new(values)))
+            }
+            "line" => {
+                if !args.is_empty() {
+                    return arg_err!("line expects 0 arguments, got {}", args.len());
+                }
+                let line = self.state.borrow().as_ref().map(|state| state.line).unwrap_or(0);
+                Ok(QValue::Int(QInt::new(line as i64)))
+            }
+            "close" => {
+                if !args.is_empty() {
+                    return arg_err!("close expects 0 arguments, got {}", args.len());
+                }
+                self.state.borrow_mut().take();
+                Ok(QValue::Nil(QNil))
+            }
+            "is_closed" => Ok(QValue::Bool(QBool::new(self.state.borrow().is_none()))),
+            _ => attr_err!("Unknown method '{}' on JsonStream", method_name),
+        }
+    }
+}
+
+impl QObj for QJsonStream {
+    fn cls(&self) -> String {
+        "JsonStream".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "JsonStream"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "JsonStream"
+    }
+
+    fn str(&self) -> String {
+        "<JsonStream>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Streaming JSON parser yielding one value at a time".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
@@ -3,40 +3,12 @@ use crate::control_flow::EvalError;
 // Handles conversion between Quest values (QValue) and JSON (serde_json::Value)
 
 use crate::types::*;
-use std::collections::HashMap;
 use rust_decimal::prelude::*;
 
 /// Convert a serde_json::Value to a Quest QValue
 /// Supports all JSON types: null, bool, number, string, array, object
 pub fn json_to_qvalue(json: serde_json::Value) -> Result<QValue, EvalError> {
-    match json {
-        serde_json::Value::Null => Ok(QValue::Nil(QNil)),
-        serde_json::Value::Bool(b) => Ok(QValue::Bool(QBool::new(b))),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Ok(QValue::Int(QInt::new(i)))
-            } else if let Some(f) = n.as_f64() {
-                Ok(QValue::Float(QFloat::new(f)))
-            } else {
-                Err("Invalid JSON number".into())
-            }
-        }
-        serde_json::Value::String(s) => Ok(QValue::Str(QString::new(s))),
-        serde_json::Value::Array(arr) => {
-            let mut elements = Vec::new();
-            for item in arr {
-                elements.push(json_to_qvalue(item)?);
-            }
-            Ok(QValue::Array(QArray::new(elements)))
-        }
-        serde_json::Value::Object(obj) => {
-            let mut map = HashMap::new();
-            for (key, val) in obj {
-                map.insert(key, json_to_qvalue(val)?);
-            }
-            Ok(QValue::Dict(Box::new(QDict::new(map))))
-        }
-    }
+    Ok(QValue::from_serde(json))
 }
 
 /// Convert a Quest QValue to a serde_json::Value
@@ -71,6 +43,7 @@ pub fn qvalue_to_json(value: &QValue) -> Result<serde_json::Value, String> {
             Err("Cannot convert NDArray to JSON (not yet implemented)".into())
         }
         QValue::Str(s) => Ok(serde_json::Value::String(s.value.as_ref().clone())),
+        QValue::SafeString(s) => Ok(serde_json::Value::String(s.value.clone())),
         QValue::Bytes(b) => {
             // Convert bytes to base64 string for JSON representation
             use base64::{Engine as _, engine::general_purpose};
@@ -115,6 +88,19 @@ pub fn qvalue_to_json(value: &QValue) -> Result<serde_json::Value, String> {
             // Convert UUID to string
             Ok(serde_json::Value::String(u.value.to_string()))
         }
+        QValue::Url(u) => {
+            // URLs serialize as their string form
+            Ok(serde_json::Value::String(u.str()))
+        }
+        QValue::IpAddr(a) => {
+            Ok(serde_json::Value::String(a.str()))
+        }
+        QValue::Cidr(n) => {
+            Ok(serde_json::Value::String(n.str()))
+        }
+        QValue::Image(_) => {
+            Err("Cannot convert Image to JSON (use encode() first)".into())
+        }
         QValue::Struct(s) => {
             // Convert struct to JSON object with its fields
             let mut json_obj = serde_json::Map::new();
@@ -147,18 +133,89 @@ pub fn qvalue_to_json(value: &QValue) -> Result<serde_json::Value, String> {
             // Convert date range to string representation
             Ok(serde_json::Value::String(dr.str()))
         }
-        QValue::SerialPort(_) => {
+        QValue::Stopwatch(sw) => {
+            // Elapsed time as an ISO 8601 duration string, like a Span
+            Ok(serde_json::Value::String(sw.str()))
+        }
+        QValue::Deque(d) => {
+            // Serialize a deque as an array, front to back
+            let values: Result<Vec<_>, _> = d.elements.borrow().iter().map(qvalue_to_json).collect();
+            Ok(serde_json::Value::Array(values?))
+        }
+        QValue::Counter(c) => {
+            // Serialize a counter as an object of counts (keys become strings)
+            let map = c.items().into_iter()
+                .map(|(key, n)| (key.as_str(), serde_json::Value::Number(serde_json::Number::from(n))))
+                .collect();
+            Ok(serde_json::Value::Object(map))
+        }
+        QValue::Heap(_) | QValue::PriorityQueue(_) => {
+            Err("Cannot convert heap to JSON (use to_array() first)".into())
+        }
+        QValue::SerialPort(_) | QValue::ModbusClient(_) | QValue::CanBus(_) => {
             Err("Cannot convert serial port to JSON".into())
         }
-        QValue::SqliteConnection(_) | QValue::SqliteCursor(_) | QValue::PostgresConnection(_) | QValue::PostgresCursor(_) | QValue::MysqlConnection(_) | QValue::MysqlCursor(_) | QValue::HtmlTemplate(_) => {
+        QValue::SqliteConnection(_) | QValue::SqliteCursor(_) | QValue::PostgresConnection(_) | QValue::PostgresCursor(_) | QValue::MysqlConnection(_) | QValue::MysqlCursor(_) | QValue::ConnectionPool(_) | QValue::PoolLease(_) | QValue::Transaction(_) | QValue::RowIterator(_) | QValue::ImapClient(_) | QValue::Pop3Client(_) | QValue::FtpClient(_) | QValue::SftpClient(_) | QValue::NatsClient(_) | QValue::NatsSubscription(_) | QValue::HtmlTemplate(_) => {
             Err("Cannot convert database/template objects to JSON".into())
         }
+        QValue::RegexPattern(_) | QValue::RegexMatch(_) => {
+            Err("Cannot convert regex objects to JSON".into())
+        }
         QValue::HttpClient(_) | QValue::HttpRequest(_) | QValue::HttpResponse(_) => {
             Err("Cannot convert HTTP objects to JSON".into())
         }
+        QValue::XmlElement(el) => {
+            // Serialize element to its XML markup
+            Ok(serde_json::Value::String(el.str()))
+        }
+        QValue::XmlReader(_) => {
+            Err("Cannot convert XmlReader to JSON".into())
+        }
+        QValue::CsvReader(_) | QValue::CsvWriter(_) => {
+            Err("Cannot convert CSV streams to JSON".into())
+        }
+        QValue::JsonStream(_) => {
+            Err("Cannot convert JsonStream to JSON".into())
+        }
+        QValue::JsonSchema(_) => {
+            Err("Cannot convert JsonSchema to JSON".into())
+        }
+        QValue::ProtoSchema(_) => {
+            Err("Cannot convert ProtoSchema to JSON".into())
+        }
+        QValue::TarReader(_) => {
+            Err("Cannot convert TarReader to JSON".into())
+        }
+        QValue::TarWriter(_) => {
+            Err("Cannot convert TarWriter to JSON".into())
+        }
+        QValue::Compressor(_) => {
+            Err("Cannot convert Compressor to JSON".into())
+        }
+        QValue::Decompressor(_) => {
+            Err("Cannot convert Decompressor to JSON".into())
+        }
+        QValue::CompressedReader(_) => {
+            Err("Cannot convert CompressedReader to JSON".into())
+        }
+        QValue::CompressedWriter(_) => {
+            Err("Cannot convert CompressedWriter to JSON".into())
+        }
+        QValue::CryptoKey(_) => {
+            Err("Cannot convert CryptoKey to JSON".into())
+        }
         QValue::Rng(_) => {
             Err("Cannot convert RNG to JSON".into())
         }
+        QValue::Cache(_) => {
+            Err("Cannot convert Cache to JSON".into())
+        }
+        QValue::BytesBuffer(_) => {
+            Err("Cannot convert BytesBuffer to JSON (call to_bytes() first)".into())
+        }
+        QValue::Iterator(_) => {
+            Err("Cannot convert Iterator to JSON (call to_array() first)".into())
+        }
         QValue::StringIO(sio) => {
             // Convert StringIO to its string content
             Ok(serde_json::Value::String(sio.borrow().get_value()))
@@ -177,6 +234,14 @@ pub fn qvalue_to_json(value: &QValue) -> Result<serde_json::Value, String> {
             json_obj.insert("code".to_string(), serde_json::Value::Number(serde_json::Number::from(pr.code)));
             Ok(serde_json::Value::Object(json_obj))
         }
+        QValue::PipelineResult(pr) => {
+            // Same shape as ProcessResult: final output, all stderr, combined code
+            let mut json_obj = serde_json::Map::new();
+            json_obj.insert("stdout".to_string(), serde_json::Value::String(pr.stdout().to_string()));
+            json_obj.insert("stderr".to_string(), serde_json::Value::String(pr.stderr()));
+            json_obj.insert("code".to_string(), serde_json::Value::Number(serde_json::Number::from(pr.code)));
+            Ok(serde_json::Value::Object(json_obj))
+        }
         QValue::Process(_) | QValue::WritableStream(_) | QValue::ReadableStream(_) => {
             Err("Cannot convert Process/Stream objects to JSON".into())
         }
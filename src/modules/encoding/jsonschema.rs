@@ -0,0 +1,757 @@
+// JSON Schema validation (draft-07 and 2020-12 keywords)
+//
+// A schema is compiled once into a flat list of nodes. Subschemas refer to
+// each other by index, so local `$ref`s (including recursive ones) resolve to
+// the node compiled for the target location. Validation walks the nodes over
+// the instance converted to serde_json and collects every failure with the
+// JSON Pointer of the offending value and of the schema keyword.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use indexmap::IndexMap;
+use regex::Regex;
+use serde_json::Value;
+use crate::control_flow::EvalError;
+use crate::encoding::json_utils::{json_to_qvalue, qvalue_to_json};
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+/// Deepest subschema nesting followed while validating one value
+const MAX_DEPTH: usize = 256;
+
+pub fn create_jsonschema_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("compile".to_string(), create_fn("jsonschema", "compile"));
+    members.insert("validate".to_string(), create_fn("jsonschema", "validate"));
+    members.insert("is_valid".to_string(), create_fn("jsonschema", "is_valid"));
+
+    QValue::Module(Box::new(QModule::new("jsonschema".to_string(), members)))
+}
+
+/// Handle jsonschema.* function calls
+pub fn call_jsonschema_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "jsonschema.compile" => {
+            let (schema, options) = match args.as_slice() {
+                [schema] => (schema, IndexMap::new()),
+                [schema, QValue::Dict(options)] => (schema, options.map.borrow().clone()),
+                _ => return arg_err!("compile expects 1 argument (schema) and optional formats:, got {} arguments", args.len()),
+            };
+            let mut formats = true;
+            for (name, value) in &options {
+                match (name.as_str(), value) {
+                    ("formats", QValue::Bool(b)) => formats = b.value,
+                    ("formats", other) => return type_err!("compile formats must be Bool, got {}", other.as_obj().cls()),
+                    _ => return arg_err!("compile got unexpected option '{}' (expected formats)", name),
+                }
+            }
+            Ok(QValue::JsonSchema(QJsonSchema::compile(schema, formats)?))
+        }
+        "jsonschema.validate" | "jsonschema.is_valid" => {
+            let name = func_name.trim_start_matches("jsonschema.");
+            if args.len() != 2 {
+                return arg_err!("{} expects 2 arguments (schema, value), got {}", name, args.len());
+            }
+            let schema = match &args[0] {
+                QValue::JsonSchema(schema) => schema.clone(),
+                other => QJsonSchema::compile(other, true)?,
+            };
+            let errors = schema.errors(name, &args[1])?;
+            if name == "is_valid" {
+                return Ok(QValue::Bool(QBool::new(errors.is_empty())));
+            }
+            Ok(errors_array(errors))
+        }
+        _ => attr_err!("Unknown jsonschema function: {}", func_name),
+    }
+}
+
+// ============================================================================
+// Compiled schema
+// ============================================================================
+
+type NodeId = usize;
+
+enum Node {
+    /// `true` accepts everything, `false` nothing
+    Bool(bool),
+    Keywords(Box<Keywords>),
+    /// Being compiled; only seen while resolving a recursive `$ref`
+    Pending,
+}
+
+#[derive(Default)]
+struct Keywords {
+    reference: Option<NodeId>,
+    types: Option<Vec<String>>,
+    enum_values: Option<Vec<Value>>,
+    const_value: Option<Value>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    multiple_of: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    format: Option<String>,
+    prefix_items: Vec<NodeId>,
+    /// Applies to the elements after `prefix_items`
+    items: Option<NodeId>,
+    contains: Option<NodeId>,
+    min_contains: Option<usize>,
+    max_contains: Option<usize>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    unique_items: bool,
+    properties: Vec<(String, NodeId)>,
+    pattern_properties: Vec<(Regex, NodeId)>,
+    additional_properties: Option<NodeId>,
+    required: Vec<String>,
+    min_properties: Option<usize>,
+    max_properties: Option<usize>,
+    property_names: Option<NodeId>,
+    dependent_required: Vec<(String, Vec<String>)>,
+    dependent_schemas: Vec<(String, NodeId)>,
+    all_of: Vec<NodeId>,
+    any_of: Vec<NodeId>,
+    one_of: Vec<NodeId>,
+    not: Option<NodeId>,
+    if_schema: Option<NodeId>,
+    then_schema: Option<NodeId>,
+    else_schema: Option<NodeId>,
+}
+
+struct Compiled {
+    nodes: Vec<Node>,
+    /// JSON Pointer of each node within the schema document
+    locations: Vec<String>,
+    root: Value,
+    formats: bool,
+}
+
+/// Escape a key for use as a JSON Pointer segment
+fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+struct Compiler<'a> {
+    root: &'a Value,
+    nodes: Vec<Node>,
+    locations: Vec<String>,
+    by_location: HashMap<String, NodeId>,
+}
+
+impl Compiler<'_> {
+    fn invalid<T>(location: &str, message: impl std::fmt::Display) -> Result<T, String> {
+        let location = if location.is_empty() { "/" } else { location };
+        value_err!("Invalid schema at {}: {}", location, message)
+    }
+
+    fn compile(&mut self, schema: &Value, location: String) -> Result<NodeId, String> {
+        if let Some(&id) = self.by_location.get(&location) {
+            return Ok(id);
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Node::Pending);
+        self.locations.push(location.clone());
+        self.by_location.insert(location.clone(), id);
+
+        self.nodes[id] = match schema {
+            Value::Bool(b) => Node::Bool(*b),
+            Value::Object(map) => Node::Keywords(Box::new(self.keywords(map, &location)?)),
+            other => return Self::invalid(&location, format!("expected an object or boolean, got {}", json_type(other))),
+        };
+        Ok(id)
+    }
+
+    fn subschema(&mut self, value: &Value, location: &str, keyword: &str) -> Result<NodeId, String> {
+        self.compile(value, format!("{}/{}", location, pointer_segment(keyword)))
+    }
+
+    fn subschemas(&mut self, value: &Value, location: &str, keyword: &str) -> Result<Vec<NodeId>, String> {
+        let Value::Array(items) = value else {
+            return Self::invalid(location, format!("'{}' must be an array of schemas", keyword));
+        };
+        items.iter().enumerate()
+            .map(|(i, item)| self.compile(item, format!("{}/{}/{}", location, keyword, i)))
+            .collect()
+    }
+
+    fn schema_map(&mut self, value: &Value, location: &str, keyword: &str) -> Result<Vec<(String, NodeId)>, String> {
+        let Value::Object(map) = value else {
+            return Self::invalid(location, format!("'{}' must be an object of schemas", keyword));
+        };
+        map.iter()
+            .map(|(key, schema)| {
+                let id = self.compile(schema, format!("{}/{}/{}", location, keyword, pointer_segment(key)))?;
+                Ok((key.clone(), id))
+            })
+            .collect()
+    }
+
+    fn number(value: &Value, location: &str, keyword: &str) -> Result<f64, String> {
+        value.as_f64().map_or_else(|| Self::invalid(location, format!("'{}' must be a number", keyword)), Ok)
+    }
+
+    fn count(value: &Value, location: &str, keyword: &str) -> Result<usize, String> {
+        value.as_u64().map_or_else(|| Self::invalid(location, format!("'{}' must be a non-negative integer", keyword)), |n| Ok(n as usize))
+    }
+
+    fn strings(value: &Value, location: &str, keyword: &str) -> Result<Vec<String>, String> {
+        match value {
+            Value::Array(items) => items.iter()
+                .map(|item| item.as_str().map(str::to_string)
+                    .map_or_else(|| Self::invalid(location, format!("'{}' must be an array of strings", keyword)), Ok))
+                .collect(),
+            _ => Self::invalid(location, format!("'{}' must be an array of strings", keyword)),
+        }
+    }
+
+    fn regex(value: &Value, location: &str, keyword: &str) -> Result<Regex, String> {
+        let Some(pattern) = value.as_str() else {
+            return Self::invalid(location, format!("'{}' must be a string", keyword));
+        };
+        Regex::new(pattern).map_or_else(|e| Self::invalid(location, format!("bad pattern '{}': {}", pattern, e)), Ok)
+    }
+
+    fn keywords(&mut self, map: &serde_json::Map<String, Value>, location: &str) -> Result<Keywords, String> {
+        let mut k = Keywords::default();
+        for (keyword, value) in map {
+            let at = location;
+            match keyword.as_str() {
+                "$ref" => {
+                    let Some(reference) = value.as_str() else {
+                        return Self::invalid(at, "'$ref' must be a string");
+                    };
+                    let Some(pointer) = reference.strip_prefix('#') else {
+                        return Self::invalid(at, format!("only local references ('#...') are supported, got '{}'", reference));
+                    };
+                    let Some(target) = self.root.pointer(pointer) else {
+                        return Self::invalid(at, format!("unresolved reference '{}'", reference));
+                    };
+                    k.reference = Some(self.compile(target, pointer.to_string())?);
+                }
+                "type" => {
+                    let names = match value {
+                        Value::String(name) => vec![name.clone()],
+                        _ => Self::strings(value, at, "type")?,
+                    };
+                    for name in &names {
+                        if !["null", "boolean", "object", "array", "number", "string", "integer"].contains(&name.as_str()) {
+                            return Self::invalid(at, format!("unknown type '{}'", name));
+                        }
+                    }
+                    k.types = Some(names);
+                }
+                "enum" => match value {
+                    Value::Array(values) => k.enum_values = Some(values.clone()),
+                    _ => return Self::invalid(at, "'enum' must be an array"),
+                },
+                "const" => k.const_value = Some(value.clone()),
+                "minimum" => k.minimum = Some(Self::number(value, at, keyword)?),
+                "maximum" => k.maximum = Some(Self::number(value, at, keyword)?),
+                "exclusiveMinimum" => k.exclusive_minimum = Some(Self::number(value, at, keyword)?),
+                "exclusiveMaximum" => k.exclusive_maximum = Some(Self::number(value, at, keyword)?),
+                "multipleOf" => {
+                    let n = Self::number(value, at, keyword)?;
+                    if n <= 0.0 {
+                        return Self::invalid(at, "'multipleOf' must be greater than 0");
+                    }
+                    k.multiple_of = Some(n);
+                }
+                "minLength" => k.min_length = Some(Self::count(value, at, keyword)?),
+                "maxLength" => k.max_length = Some(Self::count(value, at, keyword)?),
+                "pattern" => k.pattern = Some(Self::regex(value, at, keyword)?),
+                "format" => match value.as_str() {
+                    Some(format) => k.format = Some(format.to_string()),
+                    None => return Self::invalid(at, "'format' must be a string"),
+                },
+                "prefixItems" => k.prefix_items = self.subschemas(value, at, keyword)?,
+                // Draft-07 tuple form: an array of schemas, with additionalItems for the rest
+                "items" if value.is_array() => k.prefix_items = self.subschemas(value, at, keyword)?,
+                "items" => k.items = Some(self.subschema(value, at, keyword)?),
+                "additionalItems" => {
+                    if matches!(map.get("items"), Some(Value::Array(_))) {
+                        k.items = Some(self.subschema(value, at, keyword)?);
+                    }
+                }
+                "contains" => k.contains = Some(self.subschema(value, at, keyword)?),
+                "minContains" => k.min_contains = Some(Self::count(value, at, keyword)?),
+                "maxContains" => k.max_contains = Some(Self::count(value, at, keyword)?),
+                "minItems" => k.min_items = Some(Self::count(value, at, keyword)?),
+                "maxItems" => k.max_items = Some(Self::count(value, at, keyword)?),
+                "uniqueItems" => match value {
+                    Value::Bool(b) => k.unique_items = *b,
+                    _ => return Self::invalid(at, "'uniqueItems' must be a boolean"),
+                },
+                "properties" => k.properties = self.schema_map(value, at, keyword)?,
+                "patternProperties" => {
+                    let Value::Object(patterns) = value else {
+                        return Self::invalid(at, "'patternProperties' must be an object of schemas");
+                    };
+                    for (pattern, schema) in patterns {
+                        let regex = Self::regex(&Value::String(pattern.clone()), at, keyword)?;
+                        let id = self.compile(schema, format!("{}/patternProperties/{}", at, pointer_segment(pattern)))?;
+                        k.pattern_properties.push((regex, id));
+                    }
+                }
+                "additionalProperties" => k.additional_properties = Some(self.subschema(value, at, keyword)?),
+                "required" => k.required = Self::strings(value, at, keyword)?,
+                "minProperties" => k.min_properties = Some(Self::count(value, at, keyword)?),
+                "maxProperties" => k.max_properties = Some(Self::count(value, at, keyword)?),
+                "propertyNames" => k.property_names = Some(self.subschema(value, at, keyword)?),
+                "dependentRequired" => {
+                    let Value::Object(deps) = value else {
+                        return Self::invalid(at, "'dependentRequired' must be an object");
+                    };
+                    for (name, required) in deps {
+                        k.dependent_required.push((name.clone(), Self::strings(required, at, keyword)?));
+                    }
+                }
+                "dependentSchemas" => k.dependent_schemas = self.schema_map(value, at, keyword)?,
+                // Draft-07 combined form of dependentRequired and dependentSchemas
+                "dependencies" => {
+                    let Value::Object(deps) = value else {
+                        return Self::invalid(at, "'dependencies' must be an object");
+                    };
+                    for (name, dep) in deps {
+                        if dep.is_array() {
+                            k.dependent_required.push((name.clone(), Self::strings(dep, at, keyword)?));
+                        } else {
+                            let id = self.compile(dep, format!("{}/dependencies/{}", at, pointer_segment(name)))?;
+                            k.dependent_schemas.push((name.clone(), id));
+                        }
+                    }
+                }
+                "allOf" => k.all_of = self.subschemas(value, at, keyword)?,
+                "anyOf" => k.any_of = self.subschemas(value, at, keyword)?,
+                "oneOf" => k.one_of = self.subschemas(value, at, keyword)?,
+                "not" => k.not = Some(self.subschema(value, at, keyword)?),
+                "if" => k.if_schema = Some(self.subschema(value, at, keyword)?),
+                "then" => k.then_schema = Some(self.subschema(value, at, keyword)?),
+                "else" => k.else_schema = Some(self.subschema(value, at, keyword)?),
+                // Annotations ($schema, $id, title, description, default, examples, ...)
+                // and unknown keywords are ignored, as the specification requires
+                _ => {}
+            }
+        }
+        Ok(k)
+    }
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+struct Failure {
+    path: String,
+    schema_path: String,
+    keyword: &'static str,
+    message: String,
+}
+
+/// JSON type name of a value, with integral numbers reported as "integer"
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("integer", Value::Number(n)) => n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0),
+        ("number", Value::Number(_)) => true,
+        _ => json_type(value) == name,
+    }
+}
+
+/// Equality where numbers compare by value (1 == 1.0)
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_equal(a, b)),
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| json_equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 { format!("{}", n as i64) } else { n.to_string() }
+}
+
+fn valid_format(format: &str, s: &str) -> Option<bool> {
+    Some(match format {
+        "email" => match s.split_once('@') {
+            Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.')
+                && !domain.ends_with('.') && !domain.contains('@') && !s.chars().any(char::is_whitespace),
+            None => false,
+        },
+        "uri" => s.split_once(':').is_some_and(|(scheme, _)| {
+            scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }) && !s.chars().any(char::is_whitespace),
+        "date" => s.len() == 10 && chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+        "date-time" => chrono::DateTime::parse_from_rfc3339(s).is_ok(),
+        "time" => chrono::DateTime::parse_from_rfc3339(&format!("1970-01-01T{}", s)).is_ok(),
+        "uuid" => s.len() == 36 && uuid::Uuid::parse_str(s).is_ok(),
+        "ipv4" => s.parse::<std::net::Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<std::net::Ipv6Addr>().is_ok(),
+        "hostname" => s.len() <= 253 && s.split('.').all(|label| {
+            !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }),
+        "regex" => Regex::new(s).is_ok(),
+        // Unknown formats are annotations only
+        _ => return None,
+    })
+}
+
+struct Validator<'a> {
+    compiled: &'a Compiled,
+    failures: Vec<Failure>,
+}
+
+impl Validator<'_> {
+    fn fail(&mut self, node: NodeId, keyword: &'static str, path: &str, message: String) {
+        let schema_path = format!("{}/{}", self.compiled.locations[node], keyword);
+        self.failures.push(Failure { path: path.to_string(), schema_path, keyword, message });
+    }
+
+    /// Whether `value` passes `node`, without recording failures
+    fn passes(&mut self, node: NodeId, value: &Value, path: &str, depth: usize) -> bool {
+        let before = self.failures.len();
+        self.check(node, value, path, depth);
+        let passed = self.failures.len() == before;
+        self.failures.truncate(before);
+        passed
+    }
+
+    fn check(&mut self, node: NodeId, value: &Value, path: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.fail(node, "$ref", path, "schema recursion is too deep".to_string());
+            return;
+        }
+        let compiled = self.compiled;
+        let k = match &compiled.nodes[node] {
+            Node::Bool(true) | Node::Pending => return,
+            Node::Bool(false) => {
+                let schema_path = compiled.locations[node].clone();
+                self.failures.push(Failure {
+                    path: path.to_string(), schema_path, keyword: "false", message: "no value is allowed here".to_string(),
+                });
+                return;
+            }
+            Node::Keywords(k) => k,
+        };
+        let depth = depth + 1;
+
+        if let Some(target) = k.reference {
+            self.check(target, value, path, depth);
+        }
+        if let Some(types) = &k.types {
+            if !types.iter().any(|t| has_type(value, t)) {
+                self.fail(node, "type", path, format!("expected {}, got {}", types.join(" or "), json_type(value)));
+            }
+        }
+        if let Some(values) = &k.enum_values {
+            if !values.iter().any(|v| json_equal(v, value)) {
+                let allowed: Vec<String> = values.iter().map(Value::to_string).collect();
+                self.fail(node, "enum", path, format!("must be one of {}", allowed.join(", ")));
+            }
+        }
+        if let Some(expected) = &k.const_value {
+            if !json_equal(expected, value) {
+                self.fail(node, "const", path, format!("must be equal to {}", expected));
+            }
+        }
+
+        match value {
+            Value::Number(n) => self.check_number(node, k, n.as_f64().unwrap_or(f64::NAN), path),
+            Value::String(s) => self.check_string(node, k, s, path),
+            Value::Array(items) => self.check_array(node, k, items, path, depth),
+            Value::Object(map) => self.check_object(node, k, map, path, depth),
+            _ => {}
+        }
+
+        for &sub in &k.all_of {
+            self.check(sub, value, path, depth);
+        }
+        if !k.any_of.is_empty() && !k.any_of.iter().any(|&sub| self.passes(sub, value, path, depth)) {
+            self.fail(node, "anyOf", path, "must match at least one schema in anyOf".to_string());
+        }
+        if !k.one_of.is_empty() {
+            let matched = k.one_of.iter().filter(|&&sub| self.passes(sub, value, path, depth)).count();
+            if matched != 1 {
+                self.fail(node, "oneOf", path, format!("must match exactly one schema in oneOf, matched {}", matched));
+            }
+        }
+        if let Some(sub) = k.not {
+            if self.passes(sub, value, path, depth) {
+                self.fail(node, "not", path, "must not match the schema in not".to_string());
+            }
+        }
+        if let Some(condition) = k.if_schema {
+            let branch = if self.passes(condition, value, path, depth) { k.then_schema } else { k.else_schema };
+            if let Some(branch) = branch {
+                self.check(branch, value, path, depth);
+            }
+        }
+    }
+
+    fn check_number(&mut self, node: NodeId, k: &Keywords, n: f64, path: &str) {
+        if let Some(min) = k.minimum.filter(|&min| n < min) {
+            self.fail(node, "minimum", path, format!("must be >= {}", format_number(min)));
+        }
+        if let Some(max) = k.maximum.filter(|&max| n > max) {
+            self.fail(node, "maximum", path, format!("must be <= {}", format_number(max)));
+        }
+        if let Some(min) = k.exclusive_minimum.filter(|&min| n <= min) {
+            self.fail(node, "exclusiveMinimum", path, format!("must be > {}", format_number(min)));
+        }
+        if let Some(max) = k.exclusive_maximum.filter(|&max| n >= max) {
+            self.fail(node, "exclusiveMaximum", path, format!("must be < {}", format_number(max)));
+        }
+        if let Some(factor) = k.multiple_of {
+            let quotient = n / factor;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.fail(node, "multipleOf", path, format!("must be a multiple of {}", format_number(factor)));
+            }
+        }
+    }
+
+    fn check_string(&mut self, node: NodeId, k: &Keywords, s: &str, path: &str) {
+        let length = s.chars().count();
+        if let Some(min) = k.min_length.filter(|&min| length < min) {
+            self.fail(node, "minLength", path, format!("must be at least {} characters long", min));
+        }
+        if let Some(max) = k.max_length.filter(|&max| length > max) {
+            self.fail(node, "maxLength", path, format!("must be at most {} characters long", max));
+        }
+        if let Some(pattern) = k.pattern.as_ref().filter(|p| !p.is_match(s)) {
+            self.fail(node, "pattern", path, format!("must match pattern '{}'", pattern.as_str()));
+        }
+        if let Some(format) = k.format.as_deref().filter(|_| self.compiled.formats) {
+            if valid_format(format, s) == Some(false) {
+                self.fail(node, "format", path, format!("must be a valid {}", format));
+            }
+        }
+    }
+
+    fn check_array(&mut self, node: NodeId, k: &Keywords, items: &[Value], path: &str, depth: usize) {
+        if let Some(min) = k.min_items.filter(|&min| items.len() < min) {
+            self.fail(node, "minItems", path, format!("must have at least {} items", min));
+        }
+        if let Some(max) = k.max_items.filter(|&max| items.len() > max) {
+            self.fail(node, "maxItems", path, format!("must have at most {} items", max));
+        }
+        if k.unique_items {
+            'outer: for i in 0..items.len() {
+                for j in i + 1..items.len() {
+                    if json_equal(&items[i], &items[j]) {
+                        self.fail(node, "uniqueItems", path, format!("must not contain duplicates (items {} and {} are equal)", i, j));
+                        break 'outer;
+                    }
+                }
+            }
+        }
+        for (i, item) in items.iter().enumerate() {
+            let schema = k.prefix_items.get(i).copied().or(match i >= k.prefix_items.len() { true => k.items, false => None });
+            if let Some(schema) = schema {
+                self.check(schema, item, &format!("{}/{}", path, i), depth);
+            }
+        }
+        if let Some(contains) = k.contains {
+            let matches = items.iter().enumerate()
+                .filter(|(i, item)| self.passes(contains, item, &format!("{}/{}", path, i), depth))
+                .count();
+            let min = k.min_contains.unwrap_or(1);
+            if matches < min {
+                self.fail(node, "contains", path, format!("must contain at least {} matching item{}", min, if min == 1 { "" } else { "s" }));
+            }
+            if let Some(max) = k.max_contains.filter(|&max| matches > max) {
+                self.fail(node, "maxContains", path, format!("must contain at most {} matching item{}", max, if max == 1 { "" } else { "s" }));
+            }
+        }
+    }
+
+    fn check_object(&mut self, node: NodeId, k: &Keywords, map: &serde_json::Map<String, Value>, path: &str, depth: usize) {
+        for name in &k.required {
+            if !map.contains_key(name) {
+                self.fail(node, "required", path, format!("missing required property '{}'", name));
+            }
+        }
+        if let Some(min) = k.min_properties.filter(|&min| map.len() < min) {
+            self.fail(node, "minProperties", path, format!("must have at least {} properties", min));
+        }
+        if let Some(max) = k.max_properties.filter(|&max| map.len() > max) {
+            self.fail(node, "maxProperties", path, format!("must have at most {} properties", max));
+        }
+        for (name, required) in &k.dependent_required {
+            if map.contains_key(name) {
+                for dependency in required.iter().filter(|d| !map.contains_key(d.as_str())) {
+                    self.fail(node, "dependentRequired", path, format!("property '{}' is required when '{}' is present", dependency, name));
+                }
+            }
+        }
+        for (name, schema) in &k.dependent_schemas {
+            if map.contains_key(name) {
+                self.check(*schema, &Value::Object(map.clone()), path, depth);
+            }
+        }
+
+        for (key, value) in map {
+            let child_path = format!("{}/{}", path, pointer_segment(key));
+            if let Some(names) = k.property_names {
+                self.check(names, &Value::String(key.clone()), &child_path, depth);
+            }
+            let mut matched = false;
+            if let Some((_, schema)) = k.properties.iter().find(|(name, _)| name == key) {
+                matched = true;
+                self.check(*schema, value, &child_path, depth);
+            }
+            for (pattern, schema) in &k.pattern_properties {
+                if pattern.is_match(key) {
+                    matched = true;
+                    self.check(*schema, value, &child_path, depth);
+                }
+            }
+            if let (false, Some(additional)) = (matched, k.additional_properties) {
+                if let Node::Bool(false) = self.compiled.nodes[additional] {
+                    self.fail(node, "additionalProperties", path, format!("additional property '{}' is not allowed", key));
+                } else {
+                    self.check(additional, value, &child_path, depth);
+                }
+            }
+        }
+    }
+}
+
+fn errors_array(failures: Vec<Failure>) -> QValue {
+    let str_value = |s: String| QValue::Str(QString::new(s));
+    let errors = failures.into_iter().map(|failure| {
+        let mut map = IndexMap::new();
+        map.insert("path".to_string(), str_value(failure.path));
+        map.insert("message".to_string(), str_value(failure.message));
+        map.insert("keyword".to_string(), str_value(failure.keyword.to_string()));
+        map.insert("schema_path".to_string(), str_value(failure.schema_path));
+        QValue::Dict(Box::new(QDict::new(map)))
+    }).collect();
+    QValue::Array(QArray::new(errors))
+}
+
+// ============================================================================
+// JsonSchema object
+// ============================================================================
+
+/// A compiled JSON Schema, created by `jsonschema.compile(schema)`
+#[derive(Clone)]
+pub struct QJsonSchema {
+    compiled: Rc<Compiled>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QJsonSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QJsonSchema").field("id", &self.id).finish()
+    }
+}
+
+impl QJsonSchema {
+    fn compile(schema: &QValue, formats: bool) -> Result<Self, String> {
+        let root = match schema {
+            QValue::Dict(_) | QValue::Bool(_) => qvalue_to_json(schema)
+                .map_err(|e| format!("TypeErr: Schema must be JSON-compatible: {}", e))?,
+            other => return type_err!("compile expects a Dict or Bool schema, got {}", other.as_obj().cls()),
+        };
+        let mut compiler = Compiler { root: &root, nodes: Vec::new(), locations: Vec::new(), by_location: HashMap::new() };
+        compiler.compile(&root, String::new())?;
+        let (nodes, locations) = (compiler.nodes, compiler.locations);
+        Ok(QJsonSchema { compiled: Rc::new(Compiled { nodes, locations, root, formats }), id: next_object_id() })
+    }
+
+    fn errors(&self, func: &str, value: &QValue) -> Result<Vec<Failure>, String> {
+        let instance = qvalue_to_json(value)
+            .map_err(|e| format!("TypeErr: {} expects JSON-compatible data: {}", func, e))?;
+        let mut validator = Validator { compiled: &self.compiled, failures: Vec::new() };
+        validator.check(0, &instance, "", 0);
+        Ok(validator.failures)
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "validate" | "is_valid" | "assert_valid" => {
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument (value), got {}", method_name, args.len());
+                }
+                let failures = self.errors(method_name, &args[0])?;
+                match method_name {
+                    "validate" => Ok(errors_array(failures)),
+                    "is_valid" => Ok(QValue::Bool(QBool::new(failures.is_empty()))),
+                    _ if failures.is_empty() => Ok(args[0].clone()),
+                    _ => {
+                        let shown: Vec<String> = failures.iter().take(5).map(|f| {
+                            let path = if f.path.is_empty() { "root" } else { f.path.as_str() };
+                            format!("{}: {}", path, f.message)
+                        }).collect();
+                        let more = match failures.len() {
+                            n if n > 5 => format!(" (and {} more)", n - 5),
+                            _ => String::new(),
+                        };
+                        value_err!("Validation failed: {}{}", shown.join("; "), more)
+                    }
+                }
+            }
+            "schema" => {
+                if !args.is_empty() {
+                    return arg_err!("schema expects 0 arguments, got {}", args.len());
+                }
+                json_to_qvalue(self.compiled.root.clone())
+            }
+            _ => attr_err!("Unknown method '{}' on JsonSchema", method_name),
+        }
+    }
+}
+
+impl QObj for QJsonSchema {
+    fn cls(&self) -> String {
+        "JsonSchema".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "JsonSchema"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "JsonSchema"
+    }
+
+    fn str(&self) -> String {
+        "<JsonSchema>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Compiled JSON Schema".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
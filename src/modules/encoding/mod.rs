@@ -5,10 +5,20 @@ pub mod r#struct;
 pub mod hex;
 pub mod url;
 pub mod csv;
+pub mod yaml;
+pub mod xml;
+pub mod proto;
+pub mod jsonschema;
+pub mod qr;
 
 pub use b64::{create_b64_module, call_b64_function};
 pub use json::{create_json_module, call_json_function};
 pub use r#struct::{create_struct_module, call_struct_function};
 pub use hex::{create_hex_module, call_hex_function};
 pub use url::{create_url_module, call_url_function};
-pub use csv::{create_csv_module, call_csv_function};
\ No newline at end of file
+pub use csv::{create_csv_module, call_csv_function};
+pub use yaml::{create_yaml_module, call_yaml_function};
+pub use xml::{create_xml_module, call_xml_function};
+pub use proto::{create_proto_module, call_proto_function};
+pub use jsonschema::{create_jsonschema_module, call_jsonschema_function};
+pub use qr::{create_qr_module, call_qr_function};
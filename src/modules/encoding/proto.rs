@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::path::Path;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use prost_reflect::prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, ReflectMessage, Value};
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+
+pub fn create_proto_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("load".to_string(), create_fn("proto", "load"));
+    members.insert("from_descriptor_set".to_string(), create_fn("proto", "from_descriptor_set"));
+
+    QValue::Module(Box::new(QModule::new("proto".to_string(), members)))
+}
+
+/// Handle proto.* function calls
+pub fn call_proto_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "proto.load" => {
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("load expects 1-2 arguments (path, [options]), got {}", args.len());
+            }
+            let paths = match &args[0] {
+                QValue::Array(arr) => arr.elements.borrow().iter().map(|v| v.as_str()).collect(),
+                other => vec![other.as_str()],
+            };
+            if paths.is_empty() {
+                return value_err!("load expects at least one path");
+            }
+            let includes = match args.get(1) {
+                Some(QValue::Dict(options)) => match options.get("include") {
+                    Some(QValue::Array(arr)) => arr.elements.borrow().iter().map(|v| v.as_str()).collect(),
+                    Some(value) => vec![value.as_str()],
+                    None => Vec::new(),
+                },
+                Some(other) => return type_err!("load options must be Dict, got {}", other.as_obj().cls()),
+                None => Vec::new(),
+            };
+
+            let pool = if paths.iter().all(|p| p.ends_with(".proto")) {
+                compile_protos(&paths, includes)?
+            } else if paths.len() == 1 {
+                let data = std::fs::read(&paths[0])
+                    .map_err(|e| format!("IOErr: Failed to read '{}': {}", paths[0], e))?;
+                decode_descriptor_set(&data)?
+            } else {
+                return value_err!("load accepts several paths only for .proto files");
+            };
+            Ok(QValue::ProtoSchema(QProtoSchema::new(pool)))
+        }
+
+        "proto.from_descriptor_set" => {
+            if args.len() != 1 {
+                return arg_err!("from_descriptor_set expects 1 argument (bytes), got {}", args.len());
+            }
+            match &args[0] {
+                QValue::Bytes(b) => Ok(QValue::ProtoSchema(QProtoSchema::new(decode_descriptor_set(&b.data)?))),
+                other => type_err!("from_descriptor_set expects Bytes, got {}", other.as_obj().cls()),
+            }
+        }
+
+        _ => attr_err!("Unknown proto function: {}", func_name)
+    }
+}
+
+/// Compile .proto sources at runtime. Each file's directory is an implicit include path.
+fn compile_protos(paths: &[String], mut includes: Vec<String>) -> Result<DescriptorPool, EvalError> {
+    for path in paths {
+        if !Path::new(path).exists() {
+            return io_err!("Proto file not found: {}", path);
+        }
+        let dir = Path::new(path).parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+        if !includes.contains(&dir) {
+            includes.push(dir);
+        }
+    }
+
+    let mut compiler = protox::Compiler::new(&includes)
+        .map_err(|e| format!("IOErr: Invalid include path: {}", e))?;
+    compiler.include_imports(true);
+    compiler.open_files(paths)
+        .map_err(|e| format!("ValueErr: Failed to compile proto: {}", e))?;
+    Ok(compiler.descriptor_pool())
+}
+
+fn decode_descriptor_set(data: &[u8]) -> Result<DescriptorPool, EvalError> {
+    DescriptorPool::decode(data)
+        .map_err(|e| format!("ValueErr: Invalid descriptor set: {}", e).into())
+}
+
+#[derive(Debug, Clone)]
+pub struct QProtoSchema {
+    pool: DescriptorPool,
+    pub id: u64,
+}
+
+impl QProtoSchema {
+    fn new(pool: DescriptorPool) -> Self {
+        QProtoSchema { pool, id: next_object_id() }
+    }
+
+    fn message(&self, name: &str) -> Result<MessageDescriptor, EvalError> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        match self.pool.get_message_by_name(name) {
+            Some(desc) => Ok(desc),
+            None => value_err!("Unknown message type '{}'", name),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "encode" => {
+                if args.len() != 2 {
+                    return arg_err!("encode expects 2 arguments (type_name, dict), got {}", args.len());
+                }
+                let desc = self.message(&args[0].as_str())?;
+                let message = dict_to_message(desc, &args[1])?;
+                Ok(QValue::Bytes(QBytes::new(message.encode_to_vec())))
+            }
+            "decode" => {
+                if args.len() != 2 {
+                    return arg_err!("decode expects 2 arguments (type_name, bytes), got {}", args.len());
+                }
+                let desc = self.message(&args[0].as_str())?;
+                let data = match &args[1] {
+                    QValue::Bytes(b) => &b.data,
+                    other => return type_err!("decode expects Bytes, got {}", other.as_obj().cls()),
+                };
+                let message = DynamicMessage::decode(desc.clone(), data.as_slice())
+                    .map_err(|e| format!("ValueErr: Failed to decode {}: {}", desc.full_name(), e))?;
+                Ok(message_to_dict(&message))
+            }
+            "messages" => {
+                let mut names: Vec<String> = self.pool.all_messages()
+                    .filter(|m| !m.is_map_entry())
+                    .map(|m| m.full_name().to_string())
+                    .collect();
+                names.sort();
+                Ok(QValue::Array(QArray::new(names.into_iter().map(|n| QValue::Str(QString::new(n))).collect())))
+            }
+            "has" => {
+                if args.len() != 1 {
+                    return arg_err!("has expects 1 argument (type_name), got {}", args.len());
+                }
+                Ok(QValue::Bool(QBool::new(self.message(&args[0].as_str()).is_ok())))
+            }
+            "fields" => {
+                if args.len() != 1 {
+                    return arg_err!("fields expects 1 argument (type_name), got {}", args.len());
+                }
+                let fields = self.message(&args[0].as_str())?.fields().map(|f| {
+                    let mut map = IndexMap::new();
+                    map.insert("name".to_string(), QValue::Str(QString::new(f.name().to_string())));
+                    map.insert("number".to_string(), QValue::Int(QInt::new(f.number() as i64)));
+                    map.insert("type".to_string(), QValue::Str(QString::new(kind_name(&f.kind()))));
+                    map.insert("repeated".to_string(), QValue::Bool(QBool::new(f.is_list())));
+                    map.insert("map".to_string(), QValue::Bool(QBool::new(f.is_map())));
+                    QValue::Dict(Box::new(QDict::new(map)))
+                }).collect();
+                Ok(QValue::Array(QArray::new(fields)))
+            }
+            "descriptor_set" => Ok(QValue::Bytes(QBytes::new(self.pool.encode_to_vec()))),
+            _ => attr_err!("Unknown method '{}' on ProtoSchema", method_name),
+        }
+    }
+}
+
+impl QObj for QProtoSchema {
+    fn cls(&self) -> String {
+        "ProtoSchema".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "ProtoSchema"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "ProtoSchema"
+    }
+
+    fn str(&self) -> String {
+        format!("<ProtoSchema {} messages>", self.pool.all_messages().len())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Protocol Buffers schema for encoding and decoding messages".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Message(m) => m.full_name().to_string(),
+        Kind::Enum(e) => e.full_name().to_string(),
+        other => format!("{:?}", other).to_lowercase(),
+    }
+}
+
+// ============================================================================
+// Dict -> message
+// ============================================================================
+
+fn dict_to_message(desc: MessageDescriptor, value: &QValue) -> Result<DynamicMessage, EvalError> {
+    let dict = match value {
+        QValue::Dict(dict) => dict,
+        other => return type_err!("{} must be encoded from a Dict, got {}", desc.full_name(), other.as_obj().cls()),
+    };
+
+    let mut message = DynamicMessage::new(desc.clone());
+    for (key, value) in dict.map.borrow().iter() {
+        if matches!(value, QValue::Nil(_)) {
+            continue;
+        }
+        let field = match desc.get_field_by_name(key).or_else(|| desc.get_field_by_json_name(key)) {
+            Some(field) => field,
+            None => return value_err!("Unknown field '{}' for message {}", key, desc.full_name()),
+        };
+        let value = field_value(&field, value)?;
+        message.try_set_field(&field, value)
+            .map_err(|e| format!("ValueErr: Invalid value for {}: {}", field.full_name(), e))?;
+    }
+    Ok(message)
+}
+
+fn field_value(field: &FieldDescriptor, value: &QValue) -> Result<Value, EvalError> {
+    if field.is_map() {
+        let entry = match field.kind() {
+            Kind::Message(entry) => entry,
+            _ => unreachable!("map fields are message-typed"),
+        };
+        let (key_field, value_field) = (entry.map_entry_key_field(), entry.map_entry_value_field());
+        let dict = match value {
+            QValue::Dict(dict) => dict,
+            other => return type_err!("Field {} is a map and expects Dict, got {}", field.name(), other.as_obj().cls()),
+        };
+        let mut map = HashMap::new();
+        for (key, item) in dict.map.borrow().iter() {
+            map.insert(map_key(&key_field.kind(), key, field)?, scalar_value(&value_field.kind(), item, field)?);
+        }
+        Ok(Value::Map(map))
+    } else if field.is_list() {
+        let items = match value {
+            QValue::Array(arr) => arr.elements.borrow().clone(),
+            other => return type_err!("Field {} is repeated and expects Array, got {}", field.name(), other.as_obj().cls()),
+        };
+        let values = items.iter()
+            .map(|item| scalar_value(&field.kind(), item, field))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::List(values))
+    } else {
+        scalar_value(&field.kind(), value, field)
+    }
+}
+
+/// Integer value of an Int or BigInt, range-checked into the target type
+fn integer<T: TryFrom<i128>>(value: &QValue, field: &FieldDescriptor) -> Result<T, EvalError> {
+    let wide: i128 = match value {
+        QValue::Int(i) => i.value as i128,
+        QValue::BigInt(b) => match b.value.to_i128() {
+            Some(v) => v,
+            None => return value_err!("Value for field {} is out of range", field.name()),
+        },
+        other => return type_err!("Field {} expects Int, got {}", field.name(), other.as_obj().cls()),
+    };
+    T::try_from(wide).map_err(|_| format!("ValueErr: Value {} is out of range for field {}", wide, field.name()).into())
+}
+
+fn scalar_value(kind: &Kind, value: &QValue, field: &FieldDescriptor) -> Result<Value, EvalError> {
+    Ok(match kind {
+        Kind::Double => Value::F64(value.as_num()?),
+        Kind::Float => Value::F32(value.as_num()? as f32),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(integer(value, field)?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(integer(value, field)?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(integer(value, field)?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(integer(value, field)?),
+        Kind::Bool => match value {
+            QValue::Bool(b) => Value::Bool(b.value),
+            other => return type_err!("Field {} expects Bool, got {}", field.name(), other.as_obj().cls()),
+        },
+        Kind::String => match value {
+            QValue::Str(s) => Value::String(s.value.to_string()),
+            other => return type_err!("Field {} expects Str, got {}", field.name(), other.as_obj().cls()),
+        },
+        Kind::Bytes => match value {
+            QValue::Bytes(b) => Value::Bytes(b.data.clone().into()),
+            QValue::Str(s) => Value::Bytes(s.value.as_bytes().to_vec().into()),
+            other => return type_err!("Field {} expects Bytes, got {}", field.name(), other.as_obj().cls()),
+        },
+        Kind::Enum(desc) => match value {
+            QValue::Str(s) => match desc.get_value_by_name(&s.value) {
+                Some(v) => Value::EnumNumber(v.number()),
+                None => return value_err!("Unknown value '{}' for enum {}", s.value, desc.full_name()),
+            },
+            QValue::Int(_) => Value::EnumNumber(integer(value, field)?),
+            other => return type_err!("Field {} expects enum name or Int, got {}", field.name(), other.as_obj().cls()),
+        },
+        Kind::Message(desc) => Value::Message(dict_to_message(desc.clone(), value)?),
+    })
+}
+
+fn map_key(kind: &Kind, key: &str, field: &FieldDescriptor) -> Result<MapKey, EvalError> {
+    let bad_key = || -> EvalError { format!("ValueErr: Invalid key '{}' for map field {}", key, field.name()).into() };
+    Ok(match kind {
+        Kind::String => MapKey::String(key.to_string()),
+        Kind::Bool => MapKey::Bool(key.parse().map_err(|_| bad_key())?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => MapKey::I32(key.parse().map_err(|_| bad_key())?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => MapKey::I64(key.parse().map_err(|_| bad_key())?),
+        Kind::Uint32 | Kind::Fixed32 => MapKey::U32(key.parse().map_err(|_| bad_key())?),
+        Kind::Uint64 | Kind::Fixed64 => MapKey::U64(key.parse().map_err(|_| bad_key())?),
+        _ => return Err(bad_key()),
+    })
+}
+
+// ============================================================================
+// Message -> Dict
+// ============================================================================
+
+/// Convert a message to a Dict. Every field is present with its proto3 default,
+/// except fields with explicit presence (messages, `optional`, oneof members)
+/// which are omitted when unset.
+fn message_to_dict(message: &DynamicMessage) -> QValue {
+    let mut map = IndexMap::new();
+    for field in message.descriptor().fields() {
+        if field.supports_presence() && !message.has_field(&field) {
+            continue;
+        }
+        let value = message.get_field(&field);
+        map.insert(field.name().to_string(), proto_to_qvalue(&value, &field.kind()));
+    }
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+fn proto_to_qvalue(value: &Value, kind: &Kind) -> QValue {
+    match value {
+        Value::Bool(b) => QValue::Bool(QBool::new(*b)),
+        Value::I32(i) => QValue::Int(QInt::new(*i as i64)),
+        Value::I64(i) => QValue::Int(QInt::new(*i)),
+        Value::U32(u) => QValue::Int(QInt::new(*u as i64)),
+        Value::U64(u) => match i64::try_from(*u) {
+            Ok(i) => QValue::Int(QInt::new(i)),
+            Err(_) => QValue::BigInt(QBigInt::new(BigInt::from(*u))),
+        },
+        Value::F32(f) => QValue::Float(QFloat::new(*f as f64)),
+        Value::F64(f) => QValue::Float(QFloat::new(*f)),
+        Value::String(s) => QValue::Str(QString::new(s.clone())),
+        Value::Bytes(b) => QValue::Bytes(QBytes::new(b.to_vec())),
+        Value::EnumNumber(n) => match kind {
+            Kind::Enum(desc) => match desc.get_value(*n) {
+                Some(v) => QValue::Str(QString::new(v.name().to_string())),
+                None => QValue::Int(QInt::new(*n as i64)),
+            },
+            _ => QValue::Int(QInt::new(*n as i64)),
+        },
+        Value::Message(m) => message_to_dict(m),
+        Value::List(items) => {
+            QValue::Array(QArray::new(items.iter().map(|v| proto_to_qvalue(v, kind)).collect()))
+        }
+        Value::Map(entries) => {
+            let value_kind = match kind {
+                Kind::Message(entry) => entry.map_entry_value_field().kind(),
+                other => other.clone(),
+            };
+            let map = entries.iter().map(|(key, v)| {
+                let key = match key {
+                    MapKey::Bool(b) => b.to_string(),
+                    MapKey::I32(i) => i.to_string(),
+                    MapKey::I64(i) => i.to_string(),
+                    MapKey::U32(u) => u.to_string(),
+                    MapKey::U64(u) => u.to_string(),
+                    MapKey::String(s) => s.clone(),
+                };
+                (key, proto_to_qvalue(v, &value_kind))
+            }).collect();
+            QValue::Dict(Box::new(QDict::new(map)))
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// std/encoding/qr - QR code generation
+//
+// The `qrcode` crate picks the smallest version and the densest mode
+// (numeric, alphanumeric or byte) for the data. Rendering is done here: SVG
+// as one path, PNG and Image through std/image. Terminal block art is
+// built in lib/std/encoding/qr.q from matrix().
+
+use std::collections::HashMap;
+
+use image::{Rgba, RgbaImage};
+use qrcode::{Color, EcLevel, QrCode, QrResult};
+
+use crate::control_flow::EvalError;
+use crate::modules::image::{encode, parse_color, QImage};
+use crate::modules::args::{option, take_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+/// Quiet zone the QR spec requires around the code, in modules
+const DEFAULT_BORDER: usize = 4;
+const DEFAULT_SCALE: usize = 8;
+/// Largest module size in pixels, so a typo can't allocate gigabytes
+const MAX_SCALE: usize = 100;
+
+pub fn create_qr_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("matrix".to_string(), create_fn("qr", "matrix"));
+    members.insert("svg".to_string(), create_fn("qr", "svg"));
+    members.insert("png".to_string(), create_fn("qr", "png"));
+    members.insert("image".to_string(), create_fn("qr", "image"));
+
+    QValue::Module(Box::new(QModule::new("qr".to_string(), members)))
+}
+
+/// The modules of a code, row by row; true is dark
+struct Modules {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl Modules {
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+}
+
+fn ecc_arg(func_name: &str, options: &Option<Box<QDict>>) -> Result<EcLevel, String> {
+    let Some(value) = option(options, "ecc") else {
+        return Ok(EcLevel::M);
+    };
+    match value.as_str().to_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        _ => value_err!("{} ecc must be \"L\", \"M\", \"Q\" or \"H\", got {}", func_name, value.as_str()),
+    }
+}
+
+fn size_arg(func_name: &str, options: &Option<Box<QDict>>, name: &str, default: usize, min: i64) -> Result<usize, String> {
+    match option(options, name) {
+        None => Ok(default),
+        Some(QValue::Int(n)) if n.value >= min && n.value <= MAX_SCALE as i64 => Ok(n.value as usize),
+        Some(other) => value_err!("{} {} must be an Int from {} to {}, got {}", func_name, name, min, MAX_SCALE, other.as_str()),
+    }
+}
+
+fn color_option(options: &Option<Box<QDict>>, name: &str, default: &str) -> Result<Rgba<u8>, String> {
+    match option(options, name) {
+        Some(value) => parse_color(&value),
+        None => parse_color(&QValue::Str(QString::new(default.to_string()))),
+    }
+}
+
+fn build(func_name: &str, args: &[QValue], options: &Option<Box<QDict>>) -> Result<Modules, String> {
+    if args.len() != 1 {
+        return arg_err!("{} expects 1 argument (data), got {}", func_name, args.len());
+    }
+    let data: Vec<u8> = match &args[0] {
+        QValue::Str(s) => s.value.as_bytes().to_vec(),
+        QValue::Bytes(b) => b.data.clone(),
+        other => return type_err!("{} expects Str or Bytes, got {}", func_name, other.as_obj().cls()),
+    };
+    let ecc = ecc_arg(func_name, options)?;
+    let code: QrResult<QrCode> = QrCode::with_error_correction_level(&data, ecc);
+    match code {
+        Ok(code) => Ok(Modules {
+            width: code.width(),
+            dark: code.to_colors().into_iter().map(|c| c == Color::Dark).collect(),
+        }),
+        Err(e) => value_err!("{}: cannot encode {} bytes: {}", func_name, data.len(), e),
+    }
+}
+
+/// Hex color for SVG attributes, plus an opacity attribute when translucent
+fn svg_paint(attribute: &str, color: Rgba<u8>) -> String {
+    let mut paint = format!("{}=\"#{:02x}{:02x}{:02x}\"", attribute, color[0], color[1], color[2]);
+    if color[3] < 255 {
+        paint.push_str(&format!(" {}-opacity=\"{:.3}\"", attribute, color[3] as f64 / 255.0));
+    }
+    paint
+}
+
+/// One path for the dark modules, a horizontal run per subpath. The viewBox
+/// is in modules and scaled to `scale` pixels each.
+fn render_svg(modules: &Modules, scale: usize, border: usize, color: Rgba<u8>, background: Rgba<u8>) -> String {
+    let size = modules.width + 2 * border;
+    let mut path = String::new();
+    for y in 0..modules.width {
+        let mut x = 0;
+        while x < modules.width {
+            if !modules.is_dark(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < modules.width && modules.is_dark(x, y) {
+                x += 1;
+            }
+            path.push_str(&format!("M{} {}h{}v1h-{}z", start + border, y + border, x - start, x - start));
+        }
+    }
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {1} {1}\" shape-rendering=\"crispEdges\">\n",
+        size * scale, size
+    );
+    if background[3] > 0 {
+        svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" {}/>\n", size, size, svg_paint("fill", background)));
+    }
+    svg.push_str(&format!("<path d=\"{}\" {}/>\n</svg>\n", path, svg_paint("fill", color)));
+    svg
+}
+
+fn render_image(modules: &Modules, scale: usize, border: usize, color: Rgba<u8>, background: Rgba<u8>) -> RgbaImage {
+    let size = ((modules.width + 2 * border) * scale) as u32;
+    RgbaImage::from_fn(size, size, |px, py| {
+        let x = (px as usize / scale).wrapping_sub(border);
+        let y = (py as usize / scale).wrapping_sub(border);
+        if x < modules.width && y < modules.width && modules.is_dark(x, y) {
+            color
+        } else {
+            background
+        }
+    })
+}
+
+pub fn call_qr_function(func_name: &str, mut args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let options = take_options(&mut args);
+    match func_name {
+        "qr.matrix" => {
+            // matrix(data, ecc: "M") -> rows of Bools, true for dark, without a quiet zone
+            let modules = build(func_name, &args, &options)?;
+            let rows = (0..modules.width)
+                .map(|y| {
+                    let row = (0..modules.width).map(|x| QValue::Bool(QBool::new(modules.is_dark(x, y)))).collect();
+                    QValue::Array(QArray::new(row))
+                })
+                .collect();
+            Ok(QValue::Array(QArray::new(rows)))
+        }
+        "qr.svg" | "qr.png" | "qr.image" => {
+            // svg/png/image(data, ecc: "M", scale: 8, border: 4, color: "black", background: "white")
+            let modules = build(func_name, &args, &options)?;
+            let scale = size_arg(func_name, &options, "scale", DEFAULT_SCALE, 1)?;
+            let border = size_arg(func_name, &options, "border", DEFAULT_BORDER, 0)?;
+            let color = color_option(&options, "color", "black")?;
+            let background = color_option(&options, "background", "white")?;
+            match func_name {
+                "qr.svg" => Ok(QValue::Str(QString::new(render_svg(&modules, scale, border, color, background)))),
+                "qr.png" => Ok(QValue::Bytes(QBytes::new(encode(&render_image(&modules, scale, border, color, background), "png", 0)?))),
+                _ => Ok(QValue::Image(QImage::new(render_image(&modules, scale, border, color, background), None))),
+            }
+        }
+        _ => attr_err!("Unknown qr function: {}", func_name),
+    }
+}
@@ -12,6 +12,12 @@ pub fn create_struct_module() -> QValue {
     members.insert("calcsize".to_string(), create_fn("struct", "calcsize"));
     members.insert("pack_into".to_string(), create_fn("struct", "pack_into"));
 
+    // BytesBuffer constructor - create nested type object
+    let mut buffer_members = HashMap::new();
+    buffer_members.insert("new".to_string(), create_fn("struct.BytesBuffer", "new"));
+    members.insert("BytesBuffer".to_string(),
+        QValue::Module(Box::new(QModule::new("BytesBuffer".to_string(), buffer_members))));
+
     QValue::Module(Box::new(QModule::new("struct".to_string(), members)))
 }
 
@@ -119,6 +125,7 @@ pub fn call_struct_function(func_name: &str, args: Vec<QValue>, _scope: &mut cra
         "struct.unpack_from" => struct_unpack_from(args),
         "struct.calcsize" => struct_calcsize(args),
         "struct.pack_into" => struct_pack_into(args),
+        "struct.BytesBuffer.new" => bytes_buffer_new(args),
         _ => attr_err!("Unknown struct function: {}", func_name)
     }
 }
@@ -553,9 +560,41 @@ fn struct_pack_into(args: Vec<QValue>) -> Result<QValue, EvalError> {
         return arg_err!("pack_into expects at least 3 arguments, got {}", args.len());
     }
 
-    let _format_str = args[0].as_str();
-    match &args[1] {
-        QValue::Bytes(_) => return Err("pack_into buffer modification not yet implemented (bytes are immutable)".into()),
-        _ => return Err("pack_into expects Bytes as second argument".into()),
+    // pack_into(format, buffer, offset, values...) overwrites a BytesBuffer in place
+    let buffer = match &args[1] {
+        QValue::BytesBuffer(b) => b,
+        QValue::Bytes(_) => return type_err!("pack_into can't modify Bytes (they are immutable); use a BytesBuffer"),
+        other => return type_err!("pack_into expects a BytesBuffer as second argument, got {}", other.as_obj().cls()),
+    };
+    let offset = match &args[2] {
+        QValue::Int(i) if i.value >= 0 => i.value as usize,
+        QValue::Int(i) => return value_err!("pack_into offset must not be negative, got {}", i.value),
+        other => return type_err!("pack_into offset must be Int, got {}", other.as_obj().cls()),
+    };
+    let mut pack_args = vec![args[0].clone()];
+    pack_args.extend_from_slice(&args[3..]);
+    let packed = match struct_pack(pack_args)? {
+        QValue::Bytes(b) => b.data.clone(),
+        _ => unreachable!("struct_pack returns Bytes"),
+    };
+    buffer.write_at(offset, &packed)?;
+    Ok(QValue::Nil(QNil))
+}
+
+/// struct.BytesBuffer.new(data = nil) - data is Bytes, a Str (UTF-8) or an Array of byte values
+fn bytes_buffer_new(args: Vec<QValue>) -> Result<QValue, EvalError> {
+    if args.len() > 1 {
+        return arg_err!("BytesBuffer.new expects 0 or 1 arguments (data), got {}", args.len());
+    }
+    let buffer = QBytesBuffer::new(Vec::new());
+    match args.into_iter().next() {
+        None | Some(QValue::Nil(_)) => {}
+        Some(QValue::Str(s)) => {
+            buffer.call_method("write_str", vec![QValue::Str(s)])?;
+        }
+        Some(data) => {
+            buffer.call_method("write_bytes", vec![data])?;
+        }
     }
+    Ok(QValue::BytesBuffer(buffer))
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::types::*;
 use crate::{arg_err, attr_err, type_err};
@@ -184,7 +185,7 @@ fn url_parse_query(args: Vec<QValue>) -> Result<QValue, EvalError> {
     // Strip leading ? if present
     let query = query.strip_prefix('?').unwrap_or(&query);
 
-    let mut params = HashMap::new();
+    let mut params = IndexMap::new();
 
     // Split by &
     for pair in query.split('&') {
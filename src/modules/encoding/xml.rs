@@ -0,0 +1,828 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::io::{BufRead, BufReader, Cursor};
+use std::rc::Rc;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::escape::{escape, partial_escape};
+use quick_xml::Reader;
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+pub fn create_xml_module() -> QValue {
+    let mut members = HashMap::new();
+
+    // Tree API
+    members.insert("parse".to_string(), create_fn("xml", "parse"));
+    members.insert("stringify".to_string(), create_fn("xml", "stringify"));
+    members.insert("element".to_string(), create_fn("xml", "element"));
+
+    // Pull parser
+    members.insert("reader".to_string(), create_fn("xml", "reader"));
+    members.insert("open".to_string(), create_fn("xml", "open"));
+
+    QValue::Module(Box::new(QModule::new("xml".to_string(), members)))
+}
+
+/// Handle xml.* function calls
+pub fn call_xml_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "xml.parse" => {
+            if args.len() != 1 {
+                return arg_err!("parse expects 1 argument, got {}", args.len());
+            }
+            let mut pull = XmlPull::new(Box::new(Cursor::new(args[0].as_str().into_bytes())));
+            Ok(QValue::XmlElement(QXmlElement::from_node(pull.read_document()?)))
+        }
+
+        "xml.stringify" => {
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("stringify expects 1-2 arguments (element, [options]), got {}", args.len());
+            }
+            let element = match &args[0] {
+                QValue::XmlElement(el) => el,
+                other => return type_err!("stringify expects XmlElement, got {}", other.as_obj().cls()),
+            };
+
+            let (pretty, indent, declaration) = match args.get(1) {
+                Some(QValue::Dict(options)) => (
+                    options.get("pretty").map(|v| v.as_bool()).unwrap_or(false),
+                    match options.get("indent") {
+                        Some(v) => v.as_num()? as usize,
+                        None => 2,
+                    },
+                    options.get("declaration").map(|v| v.as_bool()).unwrap_or(false),
+                ),
+                Some(other) => return type_err!("stringify options must be Dict, got {}", other.as_obj().cls()),
+                None => (false, 2, false),
+            };
+
+            let mut out = String::new();
+            if declaration {
+                out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+                if pretty {
+                    out.push('\n');
+                }
+            }
+            write_node(&element.node.borrow(), &mut out, if pretty { Some(indent) } else { None }, 0);
+            if pretty {
+                out.push('\n');
+            }
+            Ok(QValue::Str(QString::new(out)))
+        }
+
+        "xml.element" => {
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("element expects 1-2 arguments (tag, [options]), got {}", args.len());
+            }
+            let tag = args[0].as_str();
+            if tag.is_empty() {
+                return value_err!("Element tag cannot be empty");
+            }
+
+            let element = QXmlElement::from_node(XmlNode { tag, ..Default::default() });
+            match args.get(1) {
+                Some(QValue::Dict(options)) => {
+                    for key in options.keys() {
+                        let value = options.get(&key).unwrap();
+                        match key.as_str() {
+                            "attrs" => match value {
+                                QValue::Dict(attrs) => {
+                                    for (name, value) in attrs.map.borrow().iter() {
+                                        element.set_attr(name.clone(), value.as_str());
+                                    }
+                                }
+                                other => return type_err!("attrs must be Dict, got {}", other.as_obj().cls()),
+                            },
+                            "text" => element.set_text(value.as_str()),
+                            "children" => match value {
+                                QValue::Array(children) => {
+                                    for child in children.elements.borrow().iter() {
+                                        element.append(child)?;
+                                    }
+                                }
+                                other => return type_err!("children must be Array, got {}", other.as_obj().cls()),
+                            },
+                            _ => return arg_err!("element got unexpected option '{}' (expected attrs, text, children)", key),
+                        }
+                    }
+                }
+                Some(other) => return type_err!("element options must be Dict, got {}", other.as_obj().cls()),
+                None => {}
+            }
+            Ok(QValue::XmlElement(element))
+        }
+
+        "xml.reader" => {
+            if args.len() != 1 {
+                return arg_err!("reader expects 1 argument (text), got {}", args.len());
+            }
+            let source: Box<dyn BufRead> = Box::new(Cursor::new(args[0].as_str().into_bytes()));
+            Ok(QValue::XmlReader(QXmlReader::new(source)))
+        }
+
+        "xml.open" => {
+            if args.len() != 1 {
+                return arg_err!("open expects 1 argument (path), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let file = std::fs::File::open(&path)
+                .map_err(|e| format!("IOErr: Failed to open '{}': {}", path, e))?;
+            Ok(QValue::XmlReader(QXmlReader::new(Box::new(BufReader::new(file)))))
+        }
+
+        _ => attr_err!("Unknown xml function: {}", func_name)
+    }
+}
+
+// ============================================================================
+// Element tree
+// ============================================================================
+
+#[derive(Debug, Default)]
+pub struct XmlNode {
+    tag: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<XmlChild>,
+}
+
+#[derive(Debug, Clone)]
+enum XmlChild {
+    Element(Rc<RefCell<XmlNode>>),
+    Text(String),
+}
+
+impl XmlNode {
+    fn child_elements(&self) -> Vec<Rc<RefCell<XmlNode>>> {
+        self.children.iter().filter_map(|child| match child {
+            XmlChild::Element(node) => Some(node.clone()),
+            XmlChild::Text(_) => None,
+        }).collect()
+    }
+
+    fn text(&self) -> String {
+        self.children.iter().filter_map(|child| match child {
+            XmlChild::Text(text) => Some(text.as_str()),
+            XmlChild::Element(_) => None,
+        }).collect()
+    }
+
+    fn all_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                XmlChild::Text(text) => out.push_str(text),
+                XmlChild::Element(node) => node.borrow().all_text(out),
+            }
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Serialize a node. `pretty` is the indent width; mixed content is kept inline.
+fn write_node(node: &XmlNode, out: &mut String, pretty: Option<usize>, depth: usize) {
+    out.push('<');
+    out.push_str(&node.tag);
+    for (name, value) in &node.attrs {
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        out.push_str(&escape(value.as_str()));
+        out.push('"');
+    }
+    if node.children.is_empty() {
+        out.push_str("/>");
+        return;
+    }
+    out.push('>');
+
+    let has_text = node.children.iter().any(|c| matches!(c, XmlChild::Text(_)));
+    let indent = pretty.filter(|_| !has_text);
+    for child in &node.children {
+        if let Some(width) = indent {
+            out.push('\n');
+            out.push_str(&" ".repeat(width * (depth + 1)));
+        }
+        match child {
+            XmlChild::Text(text) => out.push_str(&partial_escape(text.as_str())),
+            XmlChild::Element(child) => write_node(&child.borrow(), out, pretty, depth + 1),
+        }
+    }
+    if let Some(width) = indent {
+        out.push('\n');
+        out.push_str(&" ".repeat(width * depth));
+    }
+    out.push_str("</");
+    out.push_str(&node.tag);
+    out.push('>');
+}
+
+#[derive(Debug, Clone)]
+pub struct QXmlElement {
+    node: Rc<RefCell<XmlNode>>,
+    pub id: u64,
+}
+
+impl QXmlElement {
+    fn from_node(node: XmlNode) -> Self {
+        Self::wrap(Rc::new(RefCell::new(node)))
+    }
+
+    fn wrap(node: Rc<RefCell<XmlNode>>) -> Self {
+        QXmlElement { node, id: next_object_id() }
+    }
+
+    fn set_attr(&self, name: String, value: String) {
+        let mut node = self.node.borrow_mut();
+        match node.attrs.iter_mut().find(|(n, _)| *n == name) {
+            Some(attr) => attr.1 = value,
+            None => node.attrs.push((name, value)),
+        }
+    }
+
+    /// Replace all direct text with a single leading text node
+    fn set_text(&self, text: String) {
+        let mut node = self.node.borrow_mut();
+        node.children.retain(|c| matches!(c, XmlChild::Element(_)));
+        if !text.is_empty() {
+            node.children.insert(0, XmlChild::Text(text));
+        }
+    }
+
+    fn append(&self, child: &QValue) -> Result<(), EvalError> {
+        let child = match child {
+            QValue::XmlElement(el) => {
+                if contains_node(&el.node, &self.node) {
+                    return value_err!("Cannot append an element to itself or its descendant");
+                }
+                XmlChild::Element(el.node.clone())
+            }
+            QValue::Str(s) => XmlChild::Text(s.value.to_string()),
+            other => return type_err!("append expects XmlElement or Str, got {}", other.as_obj().cls()),
+        };
+        self.node.borrow_mut().children.push(child);
+        Ok(())
+    }
+
+    fn elements_value(nodes: Vec<Rc<RefCell<XmlNode>>>) -> QValue {
+        let elements = nodes.into_iter().map(|n| QValue::XmlElement(QXmlElement::wrap(n))).collect();
+        QValue::Array(QArray::new(elements))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "tag" => Ok(QValue::Str(QString::new(self.node.borrow().tag.clone()))),
+            "attr" => {
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("attr expects 1-2 arguments (name, [default]), got {}", args.len());
+                }
+                match self.node.borrow().attr(&args[0].as_str()) {
+                    Some(value) => Ok(QValue::Str(QString::new(value.to_string()))),
+                    None => Ok(args.get(1).cloned().unwrap_or(QValue::Nil(QNil))),
+                }
+            }
+            "attrs" => {
+                let map = self.node.borrow().attrs.iter()
+                    .map(|(n, v)| (n.clone(), QValue::Str(QString::new(v.clone()))))
+                    .collect();
+                Ok(QValue::Dict(Box::new(QDict::new(map))))
+            }
+            "has_attr" => {
+                if args.len() != 1 {
+                    return arg_err!("has_attr expects 1 argument, got {}", args.len());
+                }
+                Ok(QValue::Bool(QBool::new(self.node.borrow().attr(&args[0].as_str()).is_some())))
+            }
+            "set_attr" => {
+                if args.len() != 2 {
+                    return arg_err!("set_attr expects 2 arguments (name, value), got {}", args.len());
+                }
+                self.set_attr(args[0].as_str(), args[1].as_str());
+                Ok(QValue::XmlElement(self.clone()))
+            }
+            "remove_attr" => {
+                if args.len() != 1 {
+                    return arg_err!("remove_attr expects 1 argument, got {}", args.len());
+                }
+                let name = args[0].as_str();
+                self.node.borrow_mut().attrs.retain(|(n, _)| *n != name);
+                Ok(QValue::XmlElement(self.clone()))
+            }
+            "text" => Ok(QValue::Str(QString::new(self.node.borrow().text()))),
+            "all_text" => {
+                let mut out = String::new();
+                self.node.borrow().all_text(&mut out);
+                Ok(QValue::Str(QString::new(out)))
+            }
+            "set_text" => {
+                if args.len() != 1 {
+                    return arg_err!("set_text expects 1 argument, got {}", args.len());
+                }
+                self.set_text(args[0].as_str());
+                Ok(QValue::XmlElement(self.clone()))
+            }
+            "children" => Ok(Self::elements_value(self.node.borrow().child_elements())),
+            "len" => Ok(QValue::Int(QInt::new(self.node.borrow().child_elements().len() as i64))),
+            "append" => {
+                if args.len() != 1 {
+                    return arg_err!("append expects 1 argument (element or text), got {}", args.len());
+                }
+                self.append(&args[0])?;
+                Ok(QValue::XmlElement(self.clone()))
+            }
+            "find" => {
+                if args.len() != 1 {
+                    return arg_err!("find expects 1 argument (path), got {}", args.len());
+                }
+                let matches = select(&self.node, &args[0].as_str())?;
+                Ok(matches.into_iter().next()
+                    .map(|n| QValue::XmlElement(QXmlElement::wrap(n)))
+                    .unwrap_or(QValue::Nil(QNil)))
+            }
+            "find_all" => {
+                if args.len() != 1 {
+                    return arg_err!("find_all expects 1 argument (path), got {}", args.len());
+                }
+                Ok(Self::elements_value(select(&self.node, &args[0].as_str())?))
+            }
+            "find_text" => {
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("find_text expects 1-2 arguments (path, [default]), got {}", args.len());
+                }
+                match select(&self.node, &args[0].as_str())?.into_iter().next() {
+                    Some(node) => Ok(QValue::Str(QString::new(node.borrow().text()))),
+                    None => Ok(args.get(1).cloned().unwrap_or(QValue::Nil(QNil))),
+                }
+            }
+            _ => attr_err!("Unknown method '{}' on XmlElement", method_name),
+        }
+    }
+}
+
+fn contains_node(root: &Rc<RefCell<XmlNode>>, target: &Rc<RefCell<XmlNode>>) -> bool {
+    Rc::ptr_eq(root, target)
+        || root.borrow().child_elements().iter().any(|child| contains_node(child, target))
+}
+
+impl QObj for QXmlElement {
+    fn cls(&self) -> String {
+        "XmlElement".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "XmlElement"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "XmlElement"
+    }
+
+    fn str(&self) -> String {
+        let mut out = String::new();
+        write_node(&self.node.borrow(), &mut out, None, 0);
+        out
+    }
+
+    fn _rep(&self) -> String {
+        format!("<XmlElement {}>", self.node.borrow().tag)
+    }
+
+    fn _doc(&self) -> String {
+        "XML element with attributes, text, and child elements".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+// ============================================================================
+// Path queries
+// ============================================================================
+//
+// A small XPath subset, relative to the element `find` is called on:
+//   tag, *, .           child step / any child / the element itself
+//   a/b, a//b, //b      child and descendant axes
+//   [@attr], [@attr='v'], [tag], [tag='text'], [n], [last()]
+
+#[derive(Debug)]
+enum Predicate {
+    HasAttr(String),
+    AttrEquals(String, String),
+    HasChild(String),
+    ChildText(String, String),
+    Position(usize),
+    Last,
+}
+
+#[derive(Debug)]
+struct Step {
+    descendant: bool,
+    name: String,
+    predicates: Vec<Predicate>,
+}
+
+fn parse_path(path: &str) -> Result<Vec<Step>, EvalError> {
+    let path = path.trim();
+    if path.is_empty() {
+        return value_err!("XML path cannot be empty");
+    }
+    if path.starts_with('/') && !path.starts_with("//") {
+        return value_err!("Absolute XML path '{}' is not supported, use a relative path or '//'", path);
+    }
+
+    // Split on '/' outside of predicates, tracking '//' as the descendant axis
+    let mut steps = Vec::new();
+    let mut current = String::new();
+    let mut descendant = false;
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' if depth > 0 => {
+                quote = match quote {
+                    Some(q) if q == c => None,
+                    None => Some(c),
+                    other => other,
+                };
+                current.push(c);
+            }
+            '[' if quote.is_none() => { depth += 1; current.push(c); }
+            ']' if quote.is_none() => { depth -= 1; current.push(c); }
+            '/' if depth == 0 => {
+                if !current.is_empty() {
+                    steps.push(parse_step(&current, descendant, path)?);
+                    current.clear();
+                }
+                descendant = chars.peek() == Some(&'/');
+                if descendant {
+                    chars.next();
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if current.is_empty() {
+        return value_err!("XML path '{}' ends with '/'", path);
+    }
+    steps.push(parse_step(&current, descendant, path)?);
+    Ok(steps)
+}
+
+fn parse_step(step: &str, descendant: bool, path: &str) -> Result<Step, EvalError> {
+    let (name, mut rest) = match step.find('[') {
+        Some(i) => (&step[..i], &step[i..]),
+        None => (step, ""),
+    };
+    if name.is_empty() {
+        return value_err!("Invalid XML path '{}': missing tag name", path);
+    }
+
+    let mut predicates = Vec::new();
+    while !rest.is_empty() {
+        let end = match rest.find(']') {
+            Some(end) if rest.starts_with('[') => end,
+            _ => return value_err!("Invalid XML path '{}': malformed predicate", path),
+        };
+        predicates.push(parse_predicate(rest[1..end].trim(), path)?);
+        rest = &rest[end + 1..];
+    }
+    Ok(Step { descendant, name: name.to_string(), predicates })
+}
+
+fn parse_predicate(pred: &str, path: &str) -> Result<Predicate, EvalError> {
+    if pred == "last()" {
+        return Ok(Predicate::Last);
+    }
+    if let Ok(n) = pred.parse::<usize>() {
+        if n == 0 {
+            return value_err!("Invalid XML path '{}': positions start at 1", path);
+        }
+        return Ok(Predicate::Position(n));
+    }
+
+    let (lhs, value) = match pred.split_once('=') {
+        Some((lhs, rhs)) => {
+            let rhs = rhs.trim();
+            let unquoted = rhs.strip_prefix('\'').and_then(|r| r.strip_suffix('\''))
+                .or_else(|| rhs.strip_prefix('"').and_then(|r| r.strip_suffix('"')));
+            match unquoted {
+                Some(v) => (lhs.trim(), Some(v.to_string())),
+                None => return value_err!("Invalid XML path '{}': predicate values must be quoted", path),
+            }
+        }
+        None => (pred, None),
+    };
+    if lhs.is_empty() {
+        return value_err!("Invalid XML path '{}': empty predicate", path);
+    }
+
+    Ok(match (lhs.strip_prefix('@'), value) {
+        (Some(attr), None) => Predicate::HasAttr(attr.to_string()),
+        (Some(attr), Some(v)) => Predicate::AttrEquals(attr.to_string(), v),
+        (None, None) => Predicate::HasChild(lhs.to_string()),
+        (None, Some(v)) => Predicate::ChildText(lhs.to_string(), v),
+    })
+}
+
+fn select(root: &Rc<RefCell<XmlNode>>, path: &str) -> Result<Vec<Rc<RefCell<XmlNode>>>, EvalError> {
+    let mut context = vec![root.clone()];
+    for step in parse_path(path)? {
+        let mut next: Vec<Rc<RefCell<XmlNode>>> = Vec::new();
+        for node in &context {
+            let mut candidates = Vec::new();
+            if step.name == "." {
+                candidates.push(node.clone());
+            } else if step.descendant {
+                collect_descendants(node, &mut candidates);
+            } else {
+                candidates = node.borrow().child_elements();
+            }
+            candidates.retain(|c| step.name == "*" || step.name == "." || c.borrow().tag == step.name);
+
+            for pred in &step.predicates {
+                candidates = apply_predicate(candidates, pred);
+            }
+            for candidate in candidates {
+                if !next.iter().any(|n| Rc::ptr_eq(n, &candidate)) {
+                    next.push(candidate);
+                }
+            }
+        }
+        context = next;
+    }
+    Ok(context)
+}
+
+fn collect_descendants(node: &Rc<RefCell<XmlNode>>, out: &mut Vec<Rc<RefCell<XmlNode>>>) {
+    for child in node.borrow().child_elements() {
+        out.push(child.clone());
+        collect_descendants(&child, out);
+    }
+}
+
+fn apply_predicate(candidates: Vec<Rc<RefCell<XmlNode>>>, pred: &Predicate) -> Vec<Rc<RefCell<XmlNode>>> {
+    match pred {
+        Predicate::Position(n) => candidates.into_iter().nth(n - 1).into_iter().collect(),
+        Predicate::Last => candidates.into_iter().last().into_iter().collect(),
+        _ => candidates.into_iter().filter(|c| {
+            let node = c.borrow();
+            match pred {
+                Predicate::HasAttr(name) => node.attr(name).is_some(),
+                Predicate::AttrEquals(name, value) => node.attr(name) == Some(value.as_str()),
+                Predicate::HasChild(tag) => node.child_elements().iter().any(|e| e.borrow().tag == *tag),
+                Predicate::ChildText(tag, text) => node.child_elements().iter()
+                    .any(|e| e.borrow().tag == *tag && e.borrow().text() == *text),
+                Predicate::Position(_) | Predicate::Last => unreachable!(),
+            }
+        }).collect(),
+    }
+}
+
+// ============================================================================
+// Pull parser
+// ============================================================================
+
+enum PullEvent {
+    Start(String, Vec<(String, String)>),
+    End(String),
+    Text(String),
+    Comment(String),
+}
+
+struct XmlPull {
+    reader: Reader<Box<dyn BufRead>>,
+    buf: Vec<u8>,
+    /// End event owed for a self-closing tag (`<a/>` is reported as start + end)
+    pending_end: Option<String>,
+    depth: usize,
+}
+
+impl XmlPull {
+    fn new(source: Box<dyn BufRead>) -> Self {
+        XmlPull { reader: Reader::from_reader(source), buf: Vec::new(), pending_end: None, depth: 0 }
+    }
+
+    fn parse_error(&self, e: impl std::fmt::Display) -> EvalError {
+        format!("ValueErr: XML parse error at position {}: {}", self.reader.error_position(), e).into()
+    }
+
+    /// Next event, or None at end of input. Whitespace-only text is skipped.
+    fn next_event(&mut self) -> Result<Option<PullEvent>, EvalError> {
+        if let Some(name) = self.pending_end.take() {
+            self.depth -= 1;
+            return Ok(Some(PullEvent::End(name)));
+        }
+
+        loop {
+            self.buf.clear();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event.into_owned(),
+                Err(e) => return Err(self.parse_error(e)),
+            };
+            return Ok(Some(match event {
+                Event::Start(start) => {
+                    self.depth += 1;
+                    let (name, attrs) = self.start_tag(&start)?;
+                    PullEvent::Start(name, attrs)
+                }
+                Event::Empty(start) => {
+                    self.depth += 1;
+                    let (name, attrs) = self.start_tag(&start)?;
+                    self.pending_end = Some(name.clone());
+                    PullEvent::Start(name, attrs)
+                }
+                Event::End(end) => {
+                    self.depth = self.depth.saturating_sub(1);
+                    PullEvent::End(String::from_utf8_lossy(end.name().as_ref()).to_string())
+                }
+                Event::Text(text) => {
+                    let text = text.unescape().map_err(|e| self.parse_error(e))?.to_string();
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    PullEvent::Text(text)
+                }
+                Event::CData(data) => {
+                    PullEvent::Text(data.decode().map_err(|e| self.parse_error(e))?.to_string())
+                }
+                Event::Comment(text) => {
+                    PullEvent::Comment(String::from_utf8_lossy(&text.into_inner()).to_string())
+                }
+                Event::Decl(_) | Event::PI(_) | Event::DocType(_) => continue,
+                Event::Eof => return Ok(None),
+            }));
+        }
+    }
+
+    fn start_tag(&self, start: &BytesStart) -> Result<(String, Vec<(String, String)>), EvalError> {
+        let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+        let mut attrs = Vec::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(|e| self.parse_error(e))?;
+            let value = attr.unescape_value().map_err(|e| self.parse_error(e))?.to_string();
+            attrs.push((String::from_utf8_lossy(attr.key.as_ref()).to_string(), value));
+        }
+        Ok((name, attrs))
+    }
+
+    /// Build the element whose start tag was just read, consuming through its end tag
+    fn read_subtree(&mut self, tag: String, attrs: Vec<(String, String)>) -> Result<XmlNode, EvalError> {
+        let mut node = XmlNode { tag, attrs, children: Vec::new() };
+        loop {
+            match self.next_event()? {
+                Some(PullEvent::Start(name, attrs)) => {
+                    let child = self.read_subtree(name, attrs)?;
+                    node.children.push(XmlChild::Element(Rc::new(RefCell::new(child))));
+                }
+                Some(PullEvent::End(_)) => return Ok(node),
+                Some(PullEvent::Text(text)) => match node.children.last_mut() {
+                    Some(XmlChild::Text(prev)) => prev.push_str(&text),
+                    _ => node.children.push(XmlChild::Text(text)),
+                },
+                Some(PullEvent::Comment(_)) => {}
+                None => return value_err!("XML parse error: unexpected end of document, <{}> is not closed", node.tag),
+            }
+        }
+    }
+
+    /// Parse a complete document with exactly one root element
+    fn read_document(&mut self) -> Result<XmlNode, EvalError> {
+        let mut root = None;
+        while let Some(event) = self.next_event()? {
+            match event {
+                PullEvent::Start(name, attrs) => {
+                    if root.is_some() {
+                        return value_err!("XML parse error: document has more than one root element");
+                    }
+                    root = Some(self.read_subtree(name, attrs)?);
+                }
+                PullEvent::Text(_) => return value_err!("XML parse error: text outside of the root element"),
+                PullEvent::End(name) => return value_err!("XML parse error: unexpected </{}>", name),
+                PullEvent::Comment(_) => {}
+            }
+        }
+        match root {
+            Some(root) => Ok(root),
+            None => value_err!("XML parse error: document has no root element"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct QXmlReader {
+    state: Rc<RefCell<XmlPull>>,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QXmlReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QXmlReader").field("id", &self.id).finish()
+    }
+}
+
+impl QXmlReader {
+    fn new(source: Box<dyn BufRead>) -> Self {
+        QXmlReader { state: Rc::new(RefCell::new(XmlPull::new(source))), id: next_object_id() }
+    }
+
+    fn event_dict(event: PullEvent) -> QValue {
+        let mut map = IndexMap::new();
+        let str_value = |s: String| QValue::Str(QString::new(s));
+        let kind = match event {
+            PullEvent::Start(name, attrs) => {
+                let attrs = attrs.into_iter().map(|(n, v)| (n, str_value(v))).collect();
+                map.insert("name".to_string(), str_value(name));
+                map.insert("attrs".to_string(), QValue::Dict(Box::new(QDict::new(attrs))));
+                "start"
+            }
+            PullEvent::End(name) => {
+                map.insert("name".to_string(), str_value(name));
+                "end"
+            }
+            PullEvent::Text(text) => {
+                map.insert("text".to_string(), str_value(text));
+                "text"
+            }
+            PullEvent::Comment(text) => {
+                map.insert("text".to_string(), str_value(text));
+                "comment"
+            }
+        };
+        map.insert("type".to_string(), str_value(kind.to_string()));
+        QValue::Dict(Box::new(QDict::new(map)))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "next" => {
+                if !args.is_empty() {
+                    return arg_err!("next expects 0 arguments, got {}", args.len());
+                }
+                Ok(self.state.borrow_mut().next_event()?
+                    .map(Self::event_dict)
+                    .unwrap_or(QValue::Nil(QNil)))
+            }
+            "next_element" => {
+                if args.len() > 1 {
+                    return arg_err!("next_element expects 0-1 arguments ([tag]), got {}", args.len());
+                }
+                let wanted = args.first().map(|v| v.as_str());
+                let mut pull = self.state.borrow_mut();
+                while let Some(event) = pull.next_event()? {
+                    if let PullEvent::Start(name, attrs) = event {
+                        if wanted.as_ref().is_none_or(|w| *w == name) {
+                            let node = pull.read_subtree(name, attrs)?;
+                            return Ok(QValue::XmlElement(QXmlElement::from_node(node)));
+                        }
+                    }
+                }
+                Ok(QValue::Nil(QNil))
+            }
+            "depth" => Ok(QValue::Int(QInt::new(self.state.borrow().depth as i64))),
+            _ => attr_err!("Unknown method '{}' on XmlReader", method_name),
+        }
+    }
+}
+
+impl QObj for QXmlReader {
+    fn cls(&self) -> String {
+        "XmlReader".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "XmlReader"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "XmlReader"
+    }
+
+    fn str(&self) -> String {
+        "<XmlReader>".to_string()
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Streaming XML pull parser".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::encoding::json_utils::qvalue_to_json;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+pub fn create_yaml_module() -> QValue {
+    let mut members = HashMap::new();
+
+    // Parsing functions
+    members.insert("parse".to_string(), create_fn("yaml", "parse"));
+    members.insert("parse_all".to_string(), create_fn("yaml", "parse_all"));
+    members.insert("try_parse".to_string(), create_fn("yaml", "try_parse"));
+    members.insert("is_valid".to_string(), create_fn("yaml", "is_valid"));
+
+    // Serialization functions
+    members.insert("stringify".to_string(), create_fn("yaml", "stringify"));
+    members.insert("stringify_all".to_string(), create_fn("yaml", "stringify_all"));
+
+    QValue::Module(Box::new(QModule::new("yaml".to_string(), members)))
+}
+
+/// Handle yaml.* function calls
+pub fn call_yaml_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "yaml.parse" => {
+            let (text, allow_tags) = parse_args("parse", &args)?;
+            let mut docs = parse_documents(&text, allow_tags)?;
+            match docs.len() {
+                0 => Ok(QValue::Nil(QNil)),
+                1 => Ok(docs.remove(0)),
+                n => value_err!("YAML input contains {} documents, use yaml.parse_all()", n),
+            }
+        }
+
+        "yaml.parse_all" => {
+            let (text, allow_tags) = parse_args("parse_all", &args)?;
+            Ok(QValue::Array(QArray::new(parse_documents(&text, allow_tags)?)))
+        }
+
+        "yaml.try_parse" => {
+            let (text, allow_tags) = parse_args("try_parse", &args)?;
+            match parse_documents(&text, allow_tags) {
+                Ok(mut docs) if docs.len() == 1 => Ok(docs.remove(0)),
+                _ => Ok(QValue::Nil(QNil)),
+            }
+        }
+
+        "yaml.is_valid" => {
+            let (text, allow_tags) = parse_args("is_valid", &args)?;
+            Ok(QValue::Bool(QBool::new(parse_documents(&text, allow_tags).is_ok())))
+        }
+
+        "yaml.stringify" => {
+            if args.len() != 1 {
+                return arg_err!("stringify expects 1 argument, got {}", args.len());
+            }
+            Ok(QValue::Str(QString::new(stringify_document(&args[0])?)))
+        }
+
+        "yaml.stringify_all" => {
+            if args.len() != 1 {
+                return arg_err!("stringify_all expects 1 argument (array of documents), got {}", args.len());
+            }
+            let docs = match &args[0] {
+                QValue::Array(arr) => arr.elements.borrow().clone(),
+                other => return type_err!("stringify_all expects Array, got {}", other.as_obj().cls()),
+            };
+            let mut out = String::new();
+            for doc in &docs {
+                out.push_str("---\n");
+                out.push_str(&stringify_document(doc)?);
+            }
+            Ok(QValue::Str(QString::new(out)))
+        }
+
+        _ => attr_err!("Unknown yaml function: {}", func_name)
+    }
+}
+
+/// Parse `(text, options?)` where options may contain `allow_tags`
+fn parse_args(name: &str, args: &[QValue]) -> Result<(String, bool), EvalError> {
+    if args.is_empty() || args.len() > 2 {
+        return arg_err!("{} expects 1-2 arguments (text, [options]), got {}", name, args.len());
+    }
+
+    let allow_tags = match args.get(1) {
+        Some(QValue::Dict(options)) => options.get("allow_tags").map(|v| v.as_bool()).unwrap_or(false),
+        Some(other) => return type_err!("{} options must be Dict, got {}", name, other.as_obj().cls()),
+        None => false,
+    };
+    Ok((args[0].as_str(), allow_tags))
+}
+
+/// Parse every document in a YAML stream. Empty documents become nil.
+pub(crate) fn parse_documents(text: &str, allow_tags: bool) -> Result<Vec<QValue>, EvalError> {
+    let mut docs = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let value = serde_yaml::Value::deserialize(document)
+            .map_err(|e| format!("ValueErr: YAML parse error: {}", e))?;
+        docs.push(yaml_to_qvalue(value, allow_tags)?);
+    }
+
+    // A stream with no content at all still yields one (empty) document
+    if docs.len() == 1 && text.trim().is_empty() {
+        docs.clear();
+    }
+    Ok(docs)
+}
+
+/// Convert a YAML value to a Quest QValue.
+/// Custom tags (`!foo`) are rejected unless `allow_tags` is set, in which case the
+/// tag is dropped and the tagged value is kept.
+fn yaml_to_qvalue(value: serde_yaml::Value, allow_tags: bool) -> Result<QValue, EvalError> {
+    match value {
+        serde_yaml::Value::Null => Ok(QValue::Nil(QNil)),
+        serde_yaml::Value::Bool(b) => Ok(QValue::Bool(QBool::new(b))),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(QValue::Int(QInt::new(i)))
+            } else if let Some(f) = n.as_f64() {
+                Ok(QValue::Float(QFloat::new(f)))
+            } else {
+                value_err!("Invalid YAML number: {}", n)
+            }
+        }
+        serde_yaml::Value::String(s) => Ok(QValue::Str(QString::new(s))),
+        serde_yaml::Value::Sequence(seq) => {
+            let mut elements = Vec::with_capacity(seq.len());
+            for item in seq {
+                elements.push(yaml_to_qvalue(item, allow_tags)?);
+            }
+            Ok(QValue::Array(QArray::new(elements)))
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map = IndexMap::new();
+            for (key, val) in mapping {
+                map.insert(yaml_key(key)?, yaml_to_qvalue(val, allow_tags)?);
+            }
+            Ok(QValue::Dict(Box::new(QDict::new(map))))
+        }
+        serde_yaml::Value::Tagged(tagged) => {
+            if !allow_tags {
+                return value_err!("YAML tag {} is not allowed (pass allow_tags: true to ignore tags)", tagged.tag);
+            }
+            yaml_to_qvalue(tagged.value, allow_tags)
+        }
+    }
+}
+
+/// Dict keys are strings; scalar keys are converted, complex keys are rejected
+fn yaml_key(key: serde_yaml::Value) -> Result<String, EvalError> {
+    match key {
+        serde_yaml::Value::String(s) => Ok(s),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        serde_yaml::Value::Null => Ok("null".to_string()),
+        _ => type_err!("YAML mapping keys must be scalars"),
+    }
+}
+
+fn stringify_document(value: &QValue) -> Result<String, EvalError> {
+    let json_value = qvalue_to_json(value)?;
+    Ok(serde_yaml::to_string(&json_value)
+        .map_err(|e| format!("YAML stringify error: {}", e))?)
+}
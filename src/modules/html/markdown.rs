@@ -1,7 +1,11 @@
-use crate::types::{QValue, QModule, QFun, QString, next_object_id};
+use crate::types::*;
 use crate::control_flow::EvalError;
+use crate::function_call::{call_user_function, CallArguments};
+use crate::modules::html::templates::escape_html;
 use crate::scope::Scope;
-use pulldown_cmark::{Parser, Options, html, Event, Tag, TagEnd, HeadingLevel};
+use crate::{arg_err, type_err, value_err};
+use indexmap::IndexMap;
+use pulldown_cmark::{Parser, Options, html, Event, Tag, TagEnd, HeadingLevel, CodeBlockKind};
 use pulldown_cmark::CowStr;
 use std::collections::HashMap;
 
@@ -15,18 +19,175 @@ pub fn create_markdown_module() -> QValue {
         parent_type: "markdown".to_string(),
         id: next_object_id(),
     }));
+    members.insert("front_matter".to_string(), QValue::Fun(QFun {
+        name: "front_matter".to_string(),
+        parent_type: "markdown".to_string(),
+        id: next_object_id(),
+    }));
+    members.insert("render_document".to_string(), QValue::Fun(QFun {
+        name: "render_document".to_string(),
+        parent_type: "markdown".to_string(),
+        id: next_object_id(),
+    }));
 
     QValue::Module(Box::new(QModule::new("markdown".to_string(), members)))
 }
 
 /// Call markdown module functions
-pub fn call_markdown_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
+pub fn call_markdown_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
-        "markdown.to_html" => markdown_to_html(args),
+        "markdown.to_html" => {
+            let (text, options) = text_and_options("to_html", &args)?;
+            Ok(QValue::Str(QString::new(markdown_to_html(text, &options, scope)?)))
+        }
+        "markdown.front_matter" => {
+            // front_matter(text) -> [metadata, body]
+            if args.len() != 1 {
+                return arg_err!("front_matter expects 1 argument (text), got {}", args.len());
+            }
+            let text = text_arg("front_matter", &args[0])?;
+            let (metadata, body) = split_front_matter(text)?;
+            Ok(QValue::Array(QArray::new(vec![metadata, QValue::Str(QString::new(body.to_string()))])))
+        }
+        "markdown.render_document" => {
+            // render_document(text, options) -> [metadata, html]
+            let (text, options) = text_and_options("render_document", &args)?;
+            let (metadata, body) = split_front_matter(text)?;
+            let html = markdown_to_html(body, &options, scope)?;
+            Ok(QValue::Array(QArray::new(vec![metadata, QValue::Str(QString::new(html))])))
+        }
         _ => Err(format!("Unknown markdown function: {}", func_name).into()),
     }
 }
 
+/// Rendering options (keyword arguments or a trailing Dict)
+struct RenderOptions {
+    parser: Options,
+    /// Add `#` links to H1-H4
+    heading_anchors: bool,
+    /// Treat input as untrusted: escape raw HTML, drop unsafe link URLs
+    sanitize: bool,
+    /// fun (code, lang) returning the HTML for a code block, or nil for the default
+    highlight: Option<QUserFun>,
+}
+
+fn text_arg<'a>(function: &str, value: &'a QValue) -> Result<&'a str, String> {
+    match value {
+        QValue::Str(s) => Ok(s.value.as_str()),
+        other => type_err!("{} expects Str text, got {}", function, other.as_obj().cls()),
+    }
+}
+
+fn text_and_options<'a>(function: &str, args: &'a [QValue]) -> Result<(&'a str, RenderOptions), String> {
+    if args.is_empty() || args.len() > 2 {
+        return arg_err!("{} expects 1 or 2 arguments (text, options), got {}", function, args.len());
+    }
+    let text = text_arg(function, &args[0])?;
+    let options = match args.get(1) {
+        None => IndexMap::new(),
+        Some(QValue::Dict(options)) => options.map.borrow().clone(),
+        Some(other) => return type_err!("{} options must be a Dict, got {}", function, other.as_obj().cls()),
+    };
+    Ok((text, parse_options(&options)?))
+}
+
+fn parse_options(options: &IndexMap<String, QValue>) -> Result<RenderOptions, String> {
+    // Extensions that are on unless turned off
+    let mut parser = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_TASKLISTS | Options::ENABLE_HEADING_ATTRIBUTES;
+    let mut rendered = RenderOptions { parser, heading_anchors: true, sanitize: false, highlight: None };
+    for (name, value) in options {
+        if name == "highlight" {
+            rendered.highlight = match value {
+                QValue::UserFun(f) => Some(f.as_ref().clone()),
+                QValue::Nil(_) => None,
+                other => return type_err!("markdown highlight must be a function, got {}", other.as_obj().cls()),
+            };
+            continue;
+        }
+        let QValue::Bool(enabled) = value else {
+            return type_err!("markdown option '{}' must be Bool, got {}", name, value.as_obj().cls());
+        };
+        let flag = match name.as_str() {
+            "tables" => Options::ENABLE_TABLES,
+            "footnotes" => Options::ENABLE_FOOTNOTES,
+            "tasklists" => Options::ENABLE_TASKLISTS,
+            "strikethrough" => Options::ENABLE_STRIKETHROUGH,
+            "smart_punctuation" => Options::ENABLE_SMART_PUNCTUATION,
+            "heading_attributes" => Options::ENABLE_HEADING_ATTRIBUTES,
+            "heading_anchors" => {
+                rendered.heading_anchors = enabled.value;
+                continue;
+            }
+            "sanitize" => {
+                rendered.sanitize = enabled.value;
+                continue;
+            }
+            _ => return value_err!(
+                "Unknown markdown option '{}' (expected tables, footnotes, tasklists, strikethrough, smart_punctuation, heading_attributes, heading_anchors, sanitize or highlight)",
+                name
+            ),
+        };
+        parser.set(flag, enabled.value);
+    }
+    rendered.parser = parser;
+    Ok(rendered)
+}
+
+/// Split `---` YAML or `+++` TOML front matter off the top of a document.
+/// Returns the metadata (an empty Dict when there is none) and the rest.
+fn split_front_matter(text: &str) -> Result<(QValue, &str), String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let empty = || QValue::Dict(Box::new(QDict::new(IndexMap::new())));
+    let Some(fence) = ["---", "+++"].into_iter().find(|fence| {
+        text.lines().next().map(str::trim_end) == Some(*fence)
+    }) else {
+        return Ok((empty(), text));
+    };
+
+    // Find the closing fence ("..." also ends YAML)
+    let mut offset = text.find('\n').map(|i| i + 1).unwrap_or(text.len());
+    let start = offset;
+    loop {
+        if offset >= text.len() {
+            return value_err!("Front matter starting with '{}' is not closed", fence);
+        }
+        let line_end = text[offset..].find('\n').map(|i| offset + i + 1).unwrap_or(text.len());
+        let line = text[offset..line_end].trim_end();
+        if line == fence || (fence == "---" && line == "...") {
+            let source = &text[start..offset];
+            let body = &text[line_end..];
+            let metadata = if fence == "---" {
+                let mut docs = crate::modules::encoding::yaml::parse_documents(source, false)?;
+                match docs.pop() {
+                    None | Some(QValue::Nil(_)) => empty(),
+                    Some(dict @ QValue::Dict(_)) if docs.is_empty() => dict,
+                    Some(other) => return type_err!("Front matter must be a mapping, got {}", other.as_obj().cls()),
+                }
+            } else {
+                let value: toml::Value = toml::from_str(source)
+                    .map_err(|e| format!("ValueErr: TOML front matter parse error: {}", e))?;
+                crate::modules::toml::toml_to_qvalue(&value)
+            };
+            return Ok((metadata, body));
+        }
+        offset = line_end;
+    }
+}
+
+/// Whether a link or image URL is safe in sanitized output: relative, or
+/// http(s), mailto or tel
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim();
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url.as_bytes()[i] == b':' => {
+            let scheme = url[..i].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto" | "tel")
+        }
+        _ => true,
+    }
+}
+
 /// Convert heading text to kebab-case anchor ID
 fn to_kebab_case(text: &str) -> String {
     text.to_lowercase()
@@ -50,35 +211,54 @@ fn to_kebab_case(text: &str) -> String {
 }
 
 /// Convert markdown to HTML using pulldown-cmark with Prism-compatible code blocks and heading anchors
-fn markdown_to_html(args: Vec<QValue>) -> Result<QValue, EvalError> {
-    if args.is_empty() {
-        return Err("to_html() requires 1 argument: markdown text".into());
-    }
-
-    let markdown_text = match &args[0] {
-        QValue::Str(s) => s.value.as_str(),
-        _ => return Err("to_html() requires a string argument".into()),
-    };
-
-    // Configure parser options (enable strikethrough, tables, footnotes, etc.)
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_FOOTNOTES);
-    options.insert(Options::ENABLE_TASKLISTS);
-    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
-
+fn markdown_to_html(markdown_text: &str, options: &RenderOptions, scope: &mut Scope) -> Result<String, String> {
     // Parse markdown and collect events
-    let parser = Parser::new_ext(&markdown_text, options);
+    let parser = Parser::new_ext(markdown_text, options.parser);
 
     // Transform events to add heading anchors
     let mut events = Vec::new();
     let mut heading_text = String::new();
     let mut in_heading = false;
+    // Code block being collected for the highlight callback: (lang, code)
+    let mut code_block: Option<(Option<String>, String)> = None;
 
     for event in parser {
+        // Untrusted input: raw HTML becomes text, unsafe URLs are dropped
+        let event = match event {
+            Event::Html(raw) | Event::InlineHtml(raw) if options.sanitize => Event::Text(raw),
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) if options.sanitize && !is_safe_url(&dest_url) => {
+                Event::Start(Tag::Link { link_type, dest_url: CowStr::from(""), title, id })
+            }
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) if options.sanitize && !is_safe_url(&dest_url) => {
+                Event::Start(Tag::Image { link_type, dest_url: CowStr::from(""), title, id })
+            }
+            event => event,
+        };
+
+        if let Some((_, code)) = code_block.as_mut() {
+            match event {
+                Event::Text(text) => {
+                    code.push_str(&text);
+                    continue;
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    let (lang, code) = code_block.take().unwrap_or_default();
+                    events.extend(highlight_code_block(options, lang, code, scope)?);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
         match event {
-            Event::Start(Tag::Heading { level, .. }) => {
+            Event::Start(Tag::CodeBlock(ref kind)) if options.highlight.is_some() => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                code_block = Some((lang, String::new()));
+            }
+            Event::Start(Tag::Heading { level, .. }) if options.heading_anchors => {
                 in_heading = true;
                 heading_text.clear();
                 events.push(Event::Start(Tag::Heading {
@@ -92,7 +272,7 @@ fn markdown_to_html(args: Vec<QValue>) -> Result<QValue, EvalError> {
                 heading_text.push_str(text);
                 events.push(event.clone());
             }
-            Event::End(TagEnd::Heading(level)) => {
+            Event::End(TagEnd::Heading(level)) if options.heading_anchors => {
                 in_heading = false;
                 // Only add anchors for H1-H4
                 if matches!(level, HeadingLevel::H1 | HeadingLevel::H2 | HeadingLevel::H3 | HeadingLevel::H4) {
@@ -100,7 +280,7 @@ fn markdown_to_html(args: Vec<QValue>) -> Result<QValue, EvalError> {
                     // Insert anchor link before closing the heading
                     events.push(Event::Html(CowStr::from(format!(
                         " <a href=\"#{}\" class=\"heading-anchor\" aria-label=\"Link to section: {}\">#</a>",
-                        anchor_id, heading_text
+                        anchor_id, escape_html(&heading_text)
                     ))));
                 }
                 events.push(Event::End(TagEnd::Heading(level)));
@@ -113,5 +293,30 @@ fn markdown_to_html(args: Vec<QValue>) -> Result<QValue, EvalError> {
     let mut html_output = String::new();
     html::push_html(&mut html_output, events.into_iter());
 
-    Ok(QValue::Str(QString::new(html_output)))
+    Ok(html_output)
+}
+
+/// Events for a code block: the highlight callback's HTML, or the default
+/// `<pre><code class="language-x">` rendering when it returns nil
+fn highlight_code_block(options: &RenderOptions, lang: Option<String>, code: String, scope: &mut Scope) -> Result<Vec<Event<'static>>, String> {
+    if let Some(highlight) = &options.highlight {
+        let lang_arg = match &lang {
+            Some(lang) => QValue::Str(QString::new(lang.clone())),
+            None => QValue::Nil(QNil),
+        };
+        let args = vec![QValue::Str(QString::new(code.clone())), lang_arg];
+        match call_user_function(highlight, CallArguments::positional_only(args), scope, None)? {
+            QValue::Nil(_) => {}
+            html => return Ok(vec![Event::Html(CowStr::from(html.as_str()))]),
+        }
+    }
+    let kind = match lang {
+        Some(lang) => CodeBlockKind::Fenced(CowStr::from(lang)),
+        None => CodeBlockKind::Indented,
+    };
+    Ok(vec![
+        Event::Start(Tag::CodeBlock(kind)),
+        Event::Text(CowStr::from(code)),
+        Event::End(TagEnd::CodeBlock),
+    ])
 }
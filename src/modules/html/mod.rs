@@ -1,5 +1,5 @@
 pub mod templates;
 pub mod markdown;
 
-pub use templates::{QHtmlTemplate, create_templates_module, call_templates_function};
+pub use templates::{QHtmlTemplate, QSafeString, create_templates_module, call_templates_function};
 pub use markdown::{create_markdown_module, call_markdown_function};
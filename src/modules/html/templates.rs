@@ -1,16 +1,503 @@
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use crate::control_flow::EvalError;
-use crate::{arg_err, attr_err};
+use crate::function_call::{call_user_function, CallArguments};
+use crate::{arg_err, attr_err, type_err};
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use tera::{Tera, Context};
+use tera::{Tera, Context, Value};
+use tera::ast::{Expr, ExprVal, FunctionCall, Node};
 use crate::types::*;
 use crate::scope::Scope;
 use crate::modules::encoding::json_utils;
 
+// Quest functions as template filters, tests and global functions.
+//
+// Tera requires helpers to be Send + Sync, which Quest functions are not, so
+// the closures registered with Tera only carry an id into CALLBACKS. Helpers
+// run on the rendering thread, inside render()/render_str(), which make the
+// caller's scope available through RENDER_SCOPE for as long as they run.
+thread_local! {
+    static CALLBACKS: RefCell<HashMap<u64, QUserFun>> = RefCell::new(HashMap::new());
+    /// Scope of the render in progress; null outside render()
+    static RENDER_SCOPE: Cell<*mut Scope> = const { Cell::new(std::ptr::null_mut()) };
+    /// First error raised by a helper during the current render, re-raised as is
+    static HELPER_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// Helpers registered with templates.register_* apply to every engine
+    static MODULE_HELPERS: RefCell<Helpers> = RefCell::new(Helpers::default());
+    /// SafeStrings in the current render's context and helper results
+    static SAFE_STRINGS: RefCell<SafeStrings> = RefCell::new(SafeStrings::new());
+}
+
+// Auto-escaping
+//
+// Engines escape every `{{ expression }}` that produces a string, Array or
+// Dict, in all templates (not just *.html), unless the expression ends in
+// `| safe` or the text came from a SafeString (templates.safe).
+//
+// Tera only hands the escape function the text, so SafeStrings are tracked by
+// identity: each one is wrapped in private-use markers holding a random
+// per-render nonce and its index in SAFE_STRINGS. The escape function keeps a
+// wrapped SafeString whose text is unchanged and escapes everything else, and
+// the markers are removed once the render is done. A SafeString changed by a
+// filter no longer matches its entry and is escaped.
+//
+// Tera never escapes Arrays and Dicts, so mark_outputs() ends every `{{ }}`
+// expression Tera would escape with RENDER_FILTER, which renders containers
+// to text first.
+
+/// Escape function installed on every engine
+fn escape(text: &str) -> String {
+    SAFE_STRINGS.with(|safe| safe.borrow().escape(text))
+}
+
+/// Escape text for HTML element content and quoted attribute values. Unlike
+/// Tera's default this leaves `/` alone, so URLs stay readable.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The SafeStrings of one render. In template values SafeString `i` is
+/// `{nonce}#{i}\u{E001}{text}{nonce}\u{E002}`.
+struct SafeStrings {
+    /// Starts every marker; random, so untrusted text can't forge one
+    nonce: String,
+    texts: Vec<String>,
+}
+
+impl SafeStrings {
+    fn new() -> Self {
+        SafeStrings { nonce: format!("\u{E000}{:020}", rand::random::<u64>()), texts: Vec::new() }
+    }
+
+    /// Wrap `text` in markers as a new SafeString
+    fn wrap(&mut self, text: &str) -> String {
+        let wrapped = format!("{nonce}#{}\u{E001}{text}{nonce}\u{E002}", self.texts.len(), nonce = self.nonce);
+        self.texts.push(text.to_string());
+        wrapped
+    }
+
+    /// Convert `value` to a template value, wrapping the SafeStrings in it
+    fn wrap_value(&mut self, value: &QValue) -> Result<Value, String> {
+        match value {
+            QValue::SafeString(s) => Ok(Value::String(self.wrap(&s.value))),
+            QValue::Array(a) => a.elements.borrow().iter()
+                .map(|item| self.wrap_value(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            QValue::Dict(d) => {
+                let mut object = serde_json::Map::new();
+                for (key, item) in d.map.borrow().iter() {
+                    object.insert(key.clone(), self.wrap_value(item)?);
+                }
+                Ok(Value::Object(object))
+            }
+            _ => json_utils::qvalue_to_json(value),
+        }
+    }
+
+    /// Convert a template value back to a Quest value: unchanged SafeStrings
+    /// become SafeStrings again, other markers are dropped
+    fn unwrap_value(&self, value: Value) -> QValue {
+        match value {
+            Value::String(text) => match self.next_safe_string(&text) {
+                Some(("", index, inner, "")) if self.is_unchanged(index, inner) => {
+                    QValue::SafeString(QSafeString::new(inner.to_string()))
+                }
+                _ => QValue::Str(QString::new(self.strip(&text))),
+            },
+            Value::Array(items) => {
+                QValue::Array(QArray::new(items.into_iter().map(|item| self.unwrap_value(item)).collect()))
+            }
+            Value::Object(object) => QValue::Dict(Box::new(QDict::new(object.into_iter()
+                .map(|(key, item)| (key, self.unwrap_value(item)))
+                .collect()))),
+            other => QValue::from_serde(other),
+        }
+    }
+
+    fn is_unchanged(&self, index: usize, text: &str) -> bool {
+        self.texts.get(index).is_some_and(|safe| safe == text)
+    }
+
+    /// Find the first wrapped SafeString in `text`:
+    /// (text before it, index, wrapped text, text after it)
+    fn next_safe_string<'a>(&self, text: &'a str) -> Option<(&'a str, usize, &'a str, &'a str)> {
+        let close = format!("{}\u{E002}", self.nonce);
+        let mut from = 0;
+        loop {
+            let start = from + text[from..].find(&self.nonce)?;
+            let open = &text[start + self.nonce.len()..];
+            if let Some((index, body)) = open.strip_prefix('#').and_then(|tail| tail.split_once('\u{E001}')) {
+                if let (Ok(index), Some((inner, after))) = (index.parse(), body.split_once(&close)) {
+                    return Some((&text[..start], index, inner, after));
+                }
+            }
+            from = start + self.nonce.len();
+        }
+    }
+
+    /// Escape `text`, keeping unchanged SafeStrings (still wrapped)
+    fn escape(&self, text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some((before, index, inner, after)) = self.next_safe_string(rest) {
+            escaped.push_str(&escape_html(&self.strip(before)));
+            if self.is_unchanged(index, inner) {
+                escaped.push_str(&rest[before.len()..rest.len() - after.len()]);
+            } else {
+                escaped.push_str(&escape_html(&self.strip(inner)));
+            }
+            rest = after;
+        }
+        escaped.push_str(&escape_html(&self.strip(rest)));
+        escaped
+    }
+
+    /// Remove the markers from `text`
+    fn strip(&self, text: &str) -> String {
+        let mut stripped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(&self.nonce) {
+            stripped.push_str(&rest[..start]);
+            rest = &rest[start + self.nonce.len()..];
+            rest = match rest.strip_prefix('#') {
+                Some(tail) => {
+                    let tail = tail.trim_start_matches(|c: char| c.is_ascii_digit());
+                    tail.strip_prefix('\u{E001}').unwrap_or(tail)
+                }
+                None => rest.strip_prefix('\u{E002}').unwrap_or(rest),
+            };
+        }
+        stripped.push_str(rest);
+        stripped
+    }
+}
+
+/// Internal filter ending `{{ }}` expressions; see mark_outputs
+const RENDER_FILTER: &str = "__quest_render";
+
+/// Name render_str gives its template, as Tera's own render_str does
+const ONE_OFF_TEMPLATE: &str = "__tera_one_off";
+
+/// Render Arrays and Dicts to text the way Tera outputs them, so the result
+/// is escaped like any other string
+fn render_filter(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    match value {
+        Value::Array(_) | Value::Object(_) => {
+            let mut text = String::new();
+            render_value(value, &mut text);
+            Ok(Value::String(text))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+fn render_value(value: &Value, text: &mut String) {
+    match value {
+        Value::String(s) => text.push_str(s),
+        Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => text.push_str(&i.to_string()),
+            (_, Some(u), _) => text.push_str(&u.to_string()),
+            (_, _, Some(f)) => text.push_str(&f.to_string()),
+            _ => {}
+        },
+        Value::Bool(b) => text.push_str(&b.to_string()),
+        Value::Null => {}
+        Value::Array(items) => {
+            text.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    text.push_str(", ");
+                }
+                render_value(item, text);
+            }
+            text.push(']');
+        }
+        Value::Object(_) => text.push_str("[object]"),
+    }
+}
+
+/// Append RENDER_FILTER to every `{{ }}` expression in `tera`'s templates
+/// whose result Tera would escape. Call after adding templates.
+fn mark_outputs(tera: &mut Tera) {
+    for template in tera.templates.values_mut() {
+        mark_nodes(&mut template.ast);
+        for definition in template.macros.values_mut() {
+            mark_nodes(&mut definition.body);
+        }
+        for block in template.blocks.values_mut() {
+            mark_nodes(&mut block.body);
+        }
+        for (_, block) in template.blocks_definitions.values_mut().flatten() {
+            mark_nodes(&mut block.body);
+        }
+    }
+}
+
+fn mark_nodes(nodes: &mut [Node]) {
+    for node in nodes {
+        match node {
+            Node::VariableBlock(_, expr) => mark_output(expr),
+            Node::MacroDefinition(_, definition, _) => mark_nodes(&mut definition.body),
+            Node::FilterSection(_, section, _) => mark_nodes(&mut section.body),
+            Node::Block(_, block, _) => mark_nodes(&mut block.body),
+            Node::Forloop(_, forloop, _) => {
+                mark_nodes(&mut forloop.body);
+                if let Some(body) = &mut forloop.empty_body {
+                    mark_nodes(body);
+                }
+            }
+            Node::If(branches, _) => {
+                for (_, _, body) in &mut branches.conditions {
+                    mark_nodes(body);
+                }
+                if let Some((_, body)) = &mut branches.otherwise {
+                    mark_nodes(body);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn mark_output(expr: &mut Expr) {
+    // Tera doesn't escape macro output or the context dump
+    let escaped = match &expr.val {
+        ExprVal::MacroCall(_) => !expr.filters.is_empty(),
+        ExprVal::Ident(name) => name != "__tera_context",
+        _ => true,
+    };
+    let marked = expr.filters.last().is_some_and(|filter| filter.name == RENDER_FILTER);
+    if escaped && !marked && !expr.negated && !expr.is_marked_safe() {
+        expr.filters.push(FunctionCall { name: RENDER_FILTER.to_string(), args: HashMap::new() });
+    }
+}
+
+/// Tera's render_str, marking the template's outputs first
+fn render_one_off(tera: &mut Tera, input: &str, context: &Context) -> tera::Result<String> {
+    tera.add_raw_template(ONE_OFF_TEMPLATE, input)?;
+    mark_outputs(tera);
+    let result = tera.render(ONE_OFF_TEMPLATE, context);
+    tera.templates.remove(ONE_OFF_TEMPLATE);
+    result
+}
+
+/// Text that templates output without escaping (`templates.safe(html)`).
+/// Str operations on it (`..`, slice, ...) return plain, unsafe Strs.
+#[derive(Debug, Clone)]
+pub struct QSafeString {
+    pub value: String,
+    pub id: u64,
+}
+
+impl QSafeString {
+    pub fn new(value: String) -> Self {
+        QSafeString { value, id: next_object_id() }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+        // Everything else works on the text as a plain Str
+        QString::new(self.value.clone()).call_method(method_name, args)
+    }
+}
+
+impl QObj for QSafeString {
+    fn cls(&self) -> String {
+        "SafeString".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "SafeString"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "SafeString" || type_name == "obj"
+    }
+
+    fn str(&self) -> String {
+        self.value.clone()
+    }
+
+    fn _rep(&self) -> String {
+        format!("safe({:?})", self.value)
+    }
+
+    fn _doc(&self) -> String {
+        "Text that templates output without HTML escaping".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Filters, tests and globals registered from Quest code
+#[derive(Debug, Default, Clone)]
+struct Helpers {
+    /// Name -> callback id
+    filters: HashMap<String, u64>,
+    tests: HashMap<String, u64>,
+    functions: HashMap<String, u64>,
+    /// Global values, available in every render's context
+    values: HashMap<String, QValue>,
+}
+
+impl Helpers {
+    /// Register `value` under `name`; `kind` is "filter", "test" or "global"
+    fn register(&mut self, kind: &str, name: &str, value: &QValue) -> Result<(), String> {
+        let callback = match value {
+            QValue::UserFun(f) => {
+                let id = next_object_id();
+                CALLBACKS.with(|callbacks| callbacks.borrow_mut().insert(id, f.as_ref().clone()));
+                Some(id)
+            }
+            _ => None,
+        };
+        match (kind, callback) {
+            ("filter", Some(id)) => { self.filters.insert(name.to_string(), id); }
+            ("test", Some(id)) => { self.tests.insert(name.to_string(), id); }
+            ("global", Some(id)) => {
+                self.values.remove(name);
+                self.functions.insert(name.to_string(), id);
+            }
+            ("global", None) => {
+                self.functions.remove(name);
+                // Fail now rather than on every render
+                json_utils::qvalue_to_json(value)?;
+                self.values.insert(name.to_string(), value.clone());
+            }
+            _ => return type_err!("register_{} expects a function, got {}", kind, value.as_obj().cls()),
+        }
+        Ok(())
+    }
+
+    /// Register the filters, tests and global functions with `tera`
+    fn install(&self, tera: &mut Tera) {
+        for (name, &id) in &self.filters {
+            tera.register_filter(name, move |value: &Value, args: &HashMap<String, Value>| {
+                call_helper(id, vec![value.clone()], args)
+            });
+        }
+        for (name, &id) in &self.tests {
+            tera.register_tester(name, move |value: Option<&Value>, args: &[Value]| {
+                let mut positional = vec![value.cloned().unwrap_or(Value::Null)];
+                positional.extend_from_slice(args);
+                call_helper(id, positional, &HashMap::new()).map(|result| is_truthy(&result))
+            });
+        }
+        for (name, &id) in &self.functions {
+            tera.register_function(name, move |args: &HashMap<String, Value>| call_helper(id, Vec::new(), args));
+        }
+    }
+}
+
+/// Quest truthiness of a helper result converted to JSON
+fn is_truthy(value: &Value) -> bool {
+    match json_utils::json_to_qvalue(value.clone()) {
+        Ok(value) => value.as_bool(),
+        Err(_) => false,
+    }
+}
+
+/// Call the Quest function `id` with template values; Tera keyword arguments
+/// become Quest keyword arguments
+fn call_helper(id: u64, positional: Vec<Value>, keyword: &HashMap<String, Value>) -> tera::Result<Value> {
+    let result = (|| -> Result<Value, String> {
+        let f = CALLBACKS.with(|callbacks| callbacks.borrow().get(&id).cloned())
+            .ok_or_else(|| "RuntimeErr: template helper is not registered".to_string())?;
+        let scope = RENDER_SCOPE.with(|scope| scope.get());
+        if scope.is_null() {
+            return Err("RuntimeErr: template helpers can only run during render".to_string());
+        }
+        let (positional, keyword_args) = SAFE_STRINGS.with(|safe| {
+            let safe = safe.borrow();
+            let positional: Vec<QValue> = positional.into_iter()
+                .map(|value| safe.unwrap_value(value))
+                .collect();
+            let mut names: Vec<&String> = keyword.keys().collect();
+            names.sort();
+            let mut keyword_args = indexmap::IndexMap::new();
+            for name in names {
+                keyword_args.insert(name.clone(), safe.unwrap_value(keyword[name].clone()));
+            }
+            (positional, keyword_args)
+        });
+        // SAFETY: RENDER_SCOPE points at the &mut Scope passed to the render
+        // call on this thread, which is still running (helpers only run inside
+        // Tera's render) and doesn't touch the scope itself while Tera runs.
+        let scope = unsafe { &mut *scope };
+        let result = call_user_function(&f, CallArguments::new(positional, keyword_args), scope, None)?;
+        SAFE_STRINGS.with(|safe| safe.borrow_mut().wrap_value(&result))
+    })();
+    result.map_err(|e| {
+        HELPER_ERROR.with(|error| {
+            error.borrow_mut().get_or_insert_with(|| e.clone());
+        });
+        tera::Error::msg(e)
+    })
+}
+
+/// Makes `scope` and the context's SafeStrings available to helpers and the
+/// escape function until dropped, restoring the previous ones so nested
+/// renders work
+struct RenderScope {
+    previous: *mut Scope,
+    previous_safe: Option<SafeStrings>,
+}
+
+impl RenderScope {
+    fn enter(scope: &mut Scope, safe: SafeStrings) -> Self {
+        let previous = RENDER_SCOPE.with(|current| current.replace(scope as *mut Scope));
+        let previous_safe = SAFE_STRINGS.with(|current| current.replace(safe));
+        HELPER_ERROR.with(|error| error.borrow_mut().take());
+        RenderScope { previous, previous_safe: Some(previous_safe) }
+    }
+
+    /// Rendered output without the SafeString markers
+    fn finish(&self, rendered: String) -> QValue {
+        let text = SAFE_STRINGS.with(|safe| safe.borrow().strip(&rendered));
+        QValue::Str(QString::new(text))
+    }
+}
+
+impl Drop for RenderScope {
+    fn drop(&mut self) {
+        RENDER_SCOPE.with(|current| current.set(self.previous));
+        if let Some(previous_safe) = self.previous_safe.take() {
+            SAFE_STRINGS.with(|current| current.replace(previous_safe));
+        }
+    }
+}
+
+/// A Tera error, or the error a helper raised during the render
+fn render_error(e: tera::Error) -> String {
+    match HELPER_ERROR.with(|error| error.borrow_mut().take()) {
+        Some(helper_error) => helper_error,
+        None => format!("Template error: {}", e),
+    }
+}
+
 /// Wrapper for Tera template engine
 #[derive(Clone)]
 pub struct QHtmlTemplate {
     tera: Arc<Mutex<Tera>>,
+    /// Helpers registered on this engine; they take precedence over the module's
+    helpers: Rc<RefCell<Helpers>>,
     id: u64,
 }
 
@@ -23,14 +510,37 @@ impl std::fmt::Debug for QHtmlTemplate {
 }
 
 impl QHtmlTemplate {
-    pub fn new(tera: Tera) -> Self {
+    pub fn new(mut tera: Tera, autoescape: bool) -> Self {
+        tera.set_escape_fn(escape);
+        tera.register_filter(RENDER_FILTER, render_filter);
+        mark_outputs(&mut tera);
+        set_autoescape(&mut tera, autoescape);
         QHtmlTemplate {
             tera: Arc::new(Mutex::new(tera)),
+            helpers: Rc::new(RefCell::new(Helpers::default())),
             id: next_object_id(),
         }
     }
 
-    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+    /// Install the module's and this engine's helpers, and build the render
+    /// context (global values first, then the caller's Dict) and the
+    /// SafeStrings in it
+    fn prepare(&self, tera: &mut Tera, context_dict: &QDict) -> Result<(Context, SafeStrings), String> {
+        let module_helpers = MODULE_HELPERS.with(|helpers| helpers.borrow().clone());
+        let helpers = self.helpers.borrow();
+        module_helpers.install(tera);
+        helpers.install(tera);
+
+        let mut context = Context::new();
+        let mut safe = SafeStrings::new();
+        for (name, value) in module_helpers.values.iter().chain(helpers.values.iter()) {
+            context.insert(name, &safe.wrap_value(value)?);
+        }
+        context.extend(dict_to_tera_context(context_dict, &mut safe)?);
+        Ok((context, safe))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
         match method_name {
             "render" => {
                 if args.len() != 2 {
@@ -42,14 +552,13 @@ impl QHtmlTemplate {
                     _ => return Err("render expects second argument to be a Dict".into()),
                 };
 
-                // Convert Quest Dict to Tera Context via serde_json
-                let context = dict_to_tera_context(context_dict)?;
-
-                let tera = self.tera.lock().unwrap();
+                let mut tera = self.tera.lock().unwrap();
+                let (context, safe) = self.prepare(&mut tera, context_dict)?;
+                let render_scope = RenderScope::enter(scope, safe);
                 let rendered = tera.render(&template_name, &context)
-                    .map_err(|e| format!("Template error: {}", e))?;
+                    .map_err(render_error)?;
 
-                Ok(QValue::Str(QString::new(rendered)))
+                Ok(render_scope.finish(rendered))
             }
 
             "render_str" => {
@@ -62,14 +571,13 @@ impl QHtmlTemplate {
                     _ => return Err("render_str expects second argument to be a Dict".into()),
                 };
 
-                // Convert Quest Dict to Tera Context via serde_json
-                let context = dict_to_tera_context(context_dict)?;
-
                 let mut tera = self.tera.lock().unwrap();
-                let rendered = tera.render_str(&template_str, &context)
-                    .map_err(|e| format!("Template error: {}", e))?;
+                let (context, safe) = self.prepare(&mut tera, context_dict)?;
+                let render_scope = RenderScope::enter(scope, safe);
+                let rendered = render_one_off(&mut tera, &template_str, &context)
+                    .map_err(render_error)?;
 
-                Ok(QValue::Str(QString::new(rendered)))
+                Ok(render_scope.finish(rendered))
             }
 
             "add_template" => {
@@ -82,6 +590,7 @@ impl QHtmlTemplate {
                 let mut tera = self.tera.lock().unwrap();
                 tera.add_raw_template(&name, &content)
                     .map_err(|e| format!("Template error: {}", e))?;
+                mark_outputs(&mut tera);
 
                 Ok(QValue::Nil(QNil))
             }
@@ -100,10 +609,33 @@ impl QHtmlTemplate {
                 let mut tera = self.tera.lock().unwrap();
                 tera.add_raw_template(&name, &content)
                     .map_err(|e| format!("Template error: {}", e))?;
+                mark_outputs(&mut tera);
 
                 Ok(QValue::Nil(QNil))
             }
 
+            "register_filter" | "register_test" | "register_global" => {
+                // Helpers for this engine only; see templates.register_filter
+                if args.len() != 2 {
+                    return arg_err!("{} expects 2 arguments (name, {}), got {}", method_name,
+                        if method_name == "register_global" { "value" } else { "function" }, args.len());
+                }
+                let kind = &method_name["register_".len()..];
+                self.helpers.borrow_mut().register(kind, &args[0].as_str(), &args[1])?;
+                Ok(QValue::Nil(QNil))
+            }
+
+            "set_autoescape" => {
+                if args.len() != 1 {
+                    return arg_err!("set_autoescape expects 1 argument (enabled), got {}", args.len());
+                }
+                let QValue::Bool(enabled) = &args[0] else {
+                    return type_err!("set_autoescape expects Bool, got {}", args[0].as_obj().cls());
+                };
+                set_autoescape(&mut self.tera.lock().unwrap(), enabled.value);
+                Ok(QValue::Nil(QNil))
+            }
+
             "get_template_names" => {
                 if !args.is_empty() {
                     return arg_err!("get_template_names expects 0 arguments, got {}", args.len());
@@ -157,10 +689,29 @@ impl QObj for QHtmlTemplate {
     }
 }
 
-/// Convert Quest Dict to Tera Context via serde_json
-fn dict_to_tera_context(dict: &QDict) -> Result<Context, String> {
+/// Escape output in every template, or in none
+fn set_autoescape(tera: &mut Tera, enabled: bool) {
+    // Every name ends with ""
+    tera.autoescape_on(if enabled { vec![""] } else { Vec::new() });
+}
+
+/// The `autoescape` keyword argument of create/from_dir (default true)
+fn autoescape_option(func_name: &str, options: Option<&QValue>) -> Result<bool, String> {
+    match options {
+        None => Ok(true),
+        Some(QValue::Dict(options)) => match options.get("autoescape") {
+            None => Ok(true),
+            Some(QValue::Bool(b)) => Ok(b.value),
+            Some(other) => type_err!("{} autoescape must be Bool, got {}", func_name, other.as_obj().cls()),
+        },
+        Some(other) => type_err!("{} expects keyword arguments (autoescape), got {}", func_name, other.as_obj().cls()),
+    }
+}
+
+/// Convert Quest Dict to Tera Context via serde_json, wrapping its SafeStrings
+fn dict_to_tera_context(dict: &QDict, safe: &mut SafeStrings) -> Result<Context, String> {
     // Convert QDict to serde_json::Value
-    let json_value = json_utils::qvalue_to_json(&QValue::Dict(Box::new(dict.clone())))?;
+    let json_value = safe.wrap_value(&QValue::Dict(Box::new(dict.clone())))?;
 
     // Create Tera context from JSON value
     let context = Context::from_serialize(&json_value)
@@ -186,6 +737,15 @@ pub fn create_templates_module() -> QValue {
         id: next_object_id(),
     }));
 
+    // Auto-escaping
+    members.insert("safe".to_string(), create_fn("templates", "safe"));
+    members.insert("escape".to_string(), create_fn("templates", "escape"));
+
+    // Helpers for every template engine
+    members.insert("register_filter".to_string(), create_fn("templates", "register_filter"));
+    members.insert("register_test".to_string(), create_fn("templates", "register_test"));
+    members.insert("register_global".to_string(), create_fn("templates", "register_global"));
+
     QValue::Module(Box::new(QModule::new("templates".to_string(), members)))
 }
 
@@ -193,27 +753,59 @@ pub fn create_templates_module() -> QValue {
 pub fn call_templates_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
         "templates.create" => {
-            if !args.is_empty() {
+            // create(autoescape: true)
+            if args.len() > 1 {
                 return arg_err!("templates.create expects 0 arguments, got {}", args.len());
             }
+            let autoescape = autoescape_option(func_name, args.first())?;
 
             // Create empty Tera instance
             let tera = Tera::default();
-            Ok(QValue::HtmlTemplate(QHtmlTemplate::new(tera)))
+            Ok(QValue::HtmlTemplate(QHtmlTemplate::new(tera, autoescape)))
         }
 
         "templates.from_dir" => {
-            if args.len() != 1 {
+            // from_dir(pattern, autoescape: true)
+            if args.is_empty() || args.len() > 2 {
                 return arg_err!("templates.from_dir expects 1 argument (pattern), got {}", args.len());
             }
             let pattern = args[0].as_str();
+            let autoescape = autoescape_option(func_name, args.get(1))?;
 
             // Use pattern as-is - relative paths are resolved relative to CWD
             // This matches standard file I/O behavior in most languages
             let tera = Tera::new(&pattern)
                 .map_err(|e| format!("Failed to create Tera from pattern '{}': {}", pattern, e))?;
 
-            Ok(QValue::HtmlTemplate(QHtmlTemplate::new(tera)))
+            Ok(QValue::HtmlTemplate(QHtmlTemplate::new(tera, autoescape)))
+        }
+
+        "templates.safe" | "templates.escape" => {
+            // safe(text): mark text as safe HTML; escape(text): escape it, leaving SafeStrings alone.
+            // Both return a SafeString.
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument (text), got {}", func_name, args.len());
+            }
+            if let QValue::SafeString(s) = &args[0] {
+                return Ok(QValue::SafeString(s.clone()));
+            }
+            let text = args[0].as_str();
+            let value = if func_name == "templates.safe" { text } else { escape_html(&text) };
+            Ok(QValue::SafeString(QSafeString::new(value)))
+        }
+
+        "templates.register_filter" | "templates.register_test" | "templates.register_global" => {
+            // register_filter(name, fn): {{ value | name(key=arg) }} calls fn(value, key: arg)
+            // register_test(name, fn): {% if value is name(arg) %} calls fn(value, arg)
+            // register_global(name, value): a value in every context, or a function
+            // called as {{ name(key=arg) }} with keyword arguments
+            let kind = &func_name["templates.register_".len()..];
+            if args.len() != 2 {
+                return arg_err!("{} expects 2 arguments (name, {}), got {}", func_name,
+                    if kind == "global" { "value" } else { "function" }, args.len());
+            }
+            MODULE_HELPERS.with(|helpers| helpers.borrow_mut().register(kind, &args[0].as_str(), &args[1]))?;
+            Ok(QValue::Nil(QNil))
         }
 
         _ => attr_err!("Unknown function: {}", func_name)
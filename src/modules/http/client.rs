@@ -1,12 +1,451 @@
 use std::sync::{Arc, Mutex};
 use crate::control_flow::EvalError;
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use reqwest;
+use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
 use bytes::Bytes;
 use crate::types::*;
 use crate::scope::Scope;
 use super::runtime::RUNTIME;
-use crate::{attr_err, value_err};
+use crate::{arg_err, attr_err, type_err, value_err};
+
+// ============================================================================
+// Client configuration
+// ============================================================================
+
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Redirect handling for a client or a single request
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RedirectPolicy {
+    follow: bool,
+    max: usize,
+}
+
+impl RedirectPolicy {
+    fn to_reqwest(self) -> reqwest::redirect::Policy {
+        if self.follow {
+            reqwest::redirect::Policy::limited(self.max)
+        } else {
+            reqwest::redirect::Policy::none()
+        }
+    }
+
+    /// Apply `follow_redirects` / `max_redirects` overrides from an options Dict
+    fn with_overrides(self, options: Option<&QValue>) -> Result<Self, EvalError> {
+        let mut policy = self;
+        if let Some(QValue::Dict(dict)) = options {
+            if let Some(follow) = dict.get("follow_redirects") {
+                policy.follow = follow.as_bool();
+            }
+            if let Some(max) = dict.get("max_redirects") {
+                let max = max.as_num()?;
+                if max < 0.0 {
+                    return value_err!("max_redirects must be >= 0, got {}", max);
+                }
+                policy.max = max as usize;
+            }
+        }
+        Ok(policy)
+    }
+}
+
+/// Delay growth between retry attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backoff {
+    Constant,
+    Linear,
+    Exponential,
+}
+
+impl Backoff {
+    fn parse(name: &str) -> Result<Self, EvalError> {
+        match name {
+            "constant" | "none" => Ok(Backoff::Constant),
+            "linear" => Ok(Backoff::Linear),
+            "exponential" => Ok(Backoff::Exponential),
+            _ => value_err!("Unknown backoff '{}' (expected constant, linear, or exponential)", name),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Backoff::Constant => "constant",
+            Backoff::Linear => "linear",
+            Backoff::Exponential => "exponential",
+        }
+    }
+}
+
+/// Retry behaviour for a client or a single request
+#[derive(Debug, Clone, PartialEq)]
+struct RetryPolicy {
+    retries: u32,
+    backoff: Backoff,
+    base_delay: f64,  // seconds
+    max_delay: f64,   // seconds
+    jitter: bool,
+    retry_on: Vec<u16>,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 0,
+            backoff: Backoff::Exponential,
+            base_delay: 0.5,
+            max_delay: 30.0,
+            jitter: true,
+            retry_on: vec![429, 502, 503, 504],
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Apply `retries` / `backoff` / `backoff_base` / `backoff_max` / `jitter` /
+    /// `retry_on` / `retry_non_idempotent` overrides from an options Dict
+    fn with_overrides(&self, options: Option<&QValue>) -> Result<Self, EvalError> {
+        let mut policy = self.clone();
+        let dict = match options {
+            Some(QValue::Dict(dict)) => dict,
+            _ => return Ok(policy),
+        };
+
+        if let Some(retries) = dict.get("retries") {
+            let retries = retries.as_num()?;
+            if retries < 0.0 {
+                return value_err!("retries must be >= 0, got {}", retries);
+            }
+            policy.retries = retries as u32;
+        }
+        if let Some(backoff) = dict.get("backoff") {
+            policy.backoff = Backoff::parse(&backoff.as_str())?;
+        }
+        if let Some(base) = dict.get("backoff_base") {
+            policy.base_delay = non_negative_secs("backoff_base", &base)?;
+        }
+        if let Some(max) = dict.get("backoff_max") {
+            policy.max_delay = non_negative_secs("backoff_max", &max)?;
+        }
+        if let Some(jitter) = dict.get("jitter") {
+            policy.jitter = jitter.as_bool();
+        }
+        if let Some(retry_on) = dict.get("retry_on") {
+            let QValue::Array(arr) = retry_on else {
+                return type_err!("retry_on must be Array of status codes, got {}", retry_on.as_obj().cls());
+            };
+            policy.retry_on.clear();
+            for value in arr.elements.borrow().iter() {
+                let code = value.as_num()?;
+                if !(100.0..=599.0).contains(&code) || code.fract() != 0.0 {
+                    return value_err!("retry_on status codes must be integers from 100 to 599, got {}", code);
+                }
+                policy.retry_on.push(code as u16);
+            }
+        }
+        if let Some(retry_non_idempotent) = dict.get("retry_non_idempotent") {
+            policy.retry_non_idempotent = retry_non_idempotent.as_bool();
+        }
+        Ok(policy)
+    }
+
+    /// Delay before retry number `attempt` (0-based), honoring a server `Retry-After`
+    fn delay(&self, attempt: u32, retry_after: Option<f64>) -> std::time::Duration {
+        let mut secs = match self.backoff {
+            Backoff::Constant => self.base_delay,
+            Backoff::Linear => self.base_delay * (attempt + 1) as f64,
+            Backoff::Exponential => self.base_delay * 2f64.powi(attempt.min(30) as i32),
+        };
+        if self.jitter {
+            // Equal jitter: keep half the delay, randomize the other half
+            secs = secs / 2.0 + rand::random::<f64>() * secs / 2.0;
+        }
+        if let Some(retry_after) = retry_after {
+            secs = secs.max(retry_after);
+        }
+        std::time::Duration::from_secs_f64(secs.min(self.max_delay))
+    }
+
+    fn to_dict(&self) -> QValue {
+        let mut dict = IndexMap::new();
+        dict.insert("retries".to_string(), QValue::Int(QInt::new(self.retries as i64)));
+        dict.insert("backoff".to_string(), QValue::Str(QString::new(self.backoff.name().to_string())));
+        dict.insert("backoff_base".to_string(), QValue::Float(QFloat::new(self.base_delay)));
+        dict.insert("backoff_max".to_string(), QValue::Float(QFloat::new(self.max_delay)));
+        dict.insert("jitter".to_string(), QValue::Bool(QBool::new(self.jitter)));
+        let retry_on = self.retry_on.iter().map(|code| QValue::Int(QInt::new(*code as i64))).collect();
+        dict.insert("retry_on".to_string(), QValue::Array(QArray::new(retry_on)));
+        dict.insert("retry_non_idempotent".to_string(), QValue::Bool(QBool::new(self.retry_non_idempotent)));
+        QValue::Dict(Box::new(QDict::new(dict)))
+    }
+}
+
+fn non_negative_secs(name: &str, value: &QValue) -> Result<f64, EvalError> {
+    let secs = value.as_num()?;
+    if secs < 0.0 || !secs.is_finite() {
+        return value_err!("{} must be a non-negative number of seconds, got {}", name, secs);
+    }
+    Ok(secs)
+}
+
+/// Format a reqwest error with its cause chain (e.g. "...: operation timed out")
+fn request_error(e: reqwest::Error, attempts: u32) -> EvalError {
+    let mut message = if e.is_timeout() {
+        format!("HTTP request timed out: {}", e)
+    } else {
+        format!("HTTP request failed: {}", e)
+    };
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    if attempts > 1 {
+        message.push_str(&format!(" (after {} attempts)", attempts));
+    }
+    message.into()
+}
+
+/// Send a request, retrying connection failures, timeouts, and `retry_on`
+/// statuses according to `policy`. Returns the response and attempt count.
+///
+/// A timeout or broken connection may come after the server acted on the
+/// request, so those are only retried for idempotent methods unless the
+/// policy sets `retry_non_idempotent`. Failing to connect is always retried.
+async fn send_with_retry(
+    builder: reqwest::RequestBuilder,
+    method: &reqwest::Method,
+    policy: &RetryPolicy,
+) -> Result<(reqwest::Response, u32), EvalError> {
+    let resend_safe = method.is_idempotent() || policy.retry_non_idempotent;
+    let mut attempt = 0;
+    loop {
+        // Streaming bodies can't be cloned; those requests are sent once
+        let request = match builder.try_clone() {
+            Some(request) if attempt < policy.retries => request,
+            _ => {
+                let response = builder.send().await
+                    .map_err(|e| request_error(e, attempt + 1))?;
+                return Ok((response, attempt + 1));
+            }
+        };
+
+        let retry_after = match request.send().await {
+            Ok(response) if policy.retry_on.contains(&response.status().as_u16()) => {
+                response.headers().get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+            }
+            Ok(response) => return Ok((response, attempt + 1)),
+            Err(e) if e.is_connect() => None,
+            Err(e) if resend_safe && (e.is_timeout() || e.is_request()) => None,
+            Err(e) => return Err(request_error(e, attempt + 1)),
+        };
+
+        tokio::time::sleep(policy.delay(attempt, retry_after)).await;
+        attempt += 1;
+    }
+}
+
+/// Proxy routing. Without an explicit proxy, reqwest honors the
+/// HTTP_PROXY / HTTPS_PROXY / ALL_PROXY / NO_PROXY environment variables.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ProxyConfig {
+    all: Option<String>,
+    http: Option<String>,
+    https: Option<String>,
+    no_proxy: Option<String>,
+    from_env: bool,
+}
+
+impl ProxyConfig {
+    fn from_options(dict: &QDict) -> Result<Self, EvalError> {
+        let mut config = ProxyConfig { from_env: true, ..Default::default() };
+
+        match dict.get("proxy") {
+            None | Some(QValue::Nil(_)) => {}
+            Some(QValue::Str(url)) => config.all = Some(url.value.to_string()),
+            Some(QValue::Dict(schemes)) => {
+                for (scheme, url) in schemes.map.borrow().iter() {
+                    let url = Some(url.as_str());
+                    match scheme.as_str() {
+                        "all" => config.all = url,
+                        "http" => config.http = url,
+                        "https" => config.https = url,
+                        _ => return value_err!("Unknown proxy scheme '{}' (expected all, http, or https)", scheme),
+                    }
+                }
+            }
+            Some(other) => return type_err!("proxy must be Str or Dict, got {}", other.as_obj().cls()),
+        }
+        if let Some(no_proxy) = dict.get("no_proxy") {
+            config.no_proxy = Some(match no_proxy {
+                QValue::Array(arr) => arr.elements.borrow().iter().map(|v| v.as_str()).collect::<Vec<_>>().join(","),
+                other => other.as_str(),
+            });
+        }
+        if let Some(from_env) = dict.get("proxy_from_env") {
+            config.from_env = from_env.as_bool();
+        }
+        Ok(config)
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, EvalError> {
+        let no_proxy = self.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string);
+        let proxies = [("all", &self.all), ("http", &self.http), ("https", &self.https)];
+        for (scheme, url) in proxies {
+            if let Some(url) = url {
+                let proxy = match scheme {
+                    "http" => reqwest::Proxy::http(url),
+                    "https" => reqwest::Proxy::https(url),
+                    _ => reqwest::Proxy::all(url),
+                }.map_err(|e| format!("ValueErr: Invalid proxy URL '{}': {}", url, e))?;
+                builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+            }
+        }
+        if !self.from_env && !self.is_explicit() {
+            builder = builder.no_proxy();
+        }
+        Ok(builder)
+    }
+
+    fn is_explicit(&self) -> bool {
+        self.all.is_some() || self.http.is_some() || self.https.is_some()
+    }
+
+    fn to_value(&self) -> QValue {
+        if !self.is_explicit() {
+            return QValue::Nil(QNil);
+        }
+        let mut dict = IndexMap::new();
+        for (scheme, url) in [("all", &self.all), ("http", &self.http), ("https", &self.https)] {
+            if let Some(url) = url {
+                dict.insert(scheme.to_string(), QValue::Str(QString::new(url.clone())));
+            }
+        }
+        QValue::Dict(Box::new(QDict::new(dict)))
+    }
+}
+
+/// TLS trust settings
+#[derive(Debug, Clone, PartialEq)]
+struct TlsConfig {
+    ca_certs: Vec<String>,  // PEM bundle paths
+    verify: bool,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig { ca_certs: Vec::new(), verify: true }
+    }
+}
+
+impl TlsConfig {
+    fn from_options(dict: &QDict) -> Result<Self, EvalError> {
+        let mut config = TlsConfig::default();
+
+        match dict.get("ca_cert") {
+            None | Some(QValue::Nil(_)) => {}
+            Some(QValue::Array(paths)) => {
+                config.ca_certs = paths.elements.borrow().iter().map(|p| p.as_str()).collect();
+            }
+            Some(path) => config.ca_certs = vec![path.as_str()],
+        }
+        if let Some(verify) = dict.get("verify") {
+            config.verify = verify.as_bool();
+        }
+        Ok(config)
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, EvalError> {
+        for path in &self.ca_certs {
+            let pem = read_pem(path)?;
+            let certs = reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("ValueErr: Invalid CA certificate '{}': {}", path, e))?;
+            if certs.is_empty() {
+                return value_err!("No certificates found in '{}'", path);
+            }
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        Ok(builder.danger_accept_invalid_certs(!self.verify))
+    }
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, EvalError> {
+    std::fs::read(path).map_err(|e| format!("IOErr: Failed to read '{}': {}", path, e).into())
+}
+
+/// Settings used to build the underlying reqwest client
+#[derive(Debug, Clone)]
+struct ClientConfig {
+    cookies: bool,
+    redirect: RedirectPolicy,
+    retry: RetryPolicy,
+    connect_timeout: Option<f64>,  // seconds
+    proxy: ProxyConfig,
+    tls: TlsConfig,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            cookies: true,
+            redirect: RedirectPolicy { follow: true, max: DEFAULT_MAX_REDIRECTS },
+            retry: RetryPolicy::default(),
+            connect_timeout: None,
+            proxy: ProxyConfig { from_env: true, ..Default::default() },
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+fn build_reqwest_client(
+    config: &ClientConfig,
+    redirect: RedirectPolicy,
+    cookie_store: &Arc<CookieStoreMutex>,
+) -> Result<reqwest::Client, EvalError> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .redirect(redirect.to_reqwest())
+        .gzip(true);  // Enable gzip compression
+
+    if config.cookies {
+        builder = builder.cookie_provider(Arc::clone(cookie_store));
+    }
+    if let Some(secs) = config.connect_timeout {
+        builder = builder.connect_timeout(std::time::Duration::from_secs_f64(secs));
+    }
+    builder = config.proxy.apply(builder)?;
+    builder = config.tls.apply(builder)?;
+
+    builder.build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e).into())
+}
+
+fn parse_cookie_url(url: &str) -> Result<reqwest::Url, EvalError> {
+    reqwest::Url::parse(url)
+        .map_err(|e| format!("ValueErr: Invalid URL '{}': {}", url, e).into())
+}
+
+fn cookie_to_dict(cookie: &cookie_store::Cookie<'static>) -> QValue {
+    let mut dict = IndexMap::new();
+    dict.insert("name".to_string(), QValue::Str(QString::new(cookie.name().to_string())));
+    dict.insert("value".to_string(), QValue::Str(QString::new(cookie.value().to_string())));
+    dict.insert("domain".to_string(), match cookie.domain.as_cow() {
+        Some(domain) => QValue::Str(QString::new(domain.into_owned())),
+        None => QValue::Nil(QNil),
+    });
+    dict.insert("path".to_string(), QValue::Str(QString::new(String::from(&cookie.path))));
+    dict.insert("secure".to_string(), QValue::Bool(QBool::new(cookie.secure().unwrap_or(false))));
+    dict.insert("http_only".to_string(), QValue::Bool(QBool::new(cookie.http_only().unwrap_or(false))));
+    dict.insert("persistent".to_string(), QValue::Bool(QBool::new(cookie.is_persistent())));
+    QValue::Dict(Box::new(QDict::new(dict)))
+}
 
 // ============================================================================
 // HttpClient - Reusable client with connection pooling
@@ -15,6 +454,8 @@ use crate::{attr_err, value_err};
 #[derive(Debug, Clone)]
 pub struct QHttpClient {
     client: Arc<reqwest::Client>,
+    config: ClientConfig,
+    cookie_store: Arc<CookieStoreMutex>,
     default_headers: Arc<Mutex<HashMap<String, String>>>,
     timeout: Arc<Mutex<Option<u64>>>,  // seconds
     id: u64,
@@ -22,18 +463,66 @@ pub struct QHttpClient {
 
 impl QHttpClient {
     pub fn new() -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .cookie_store(true)  // Enable cookie handling
-            .gzip(true)          // Enable gzip compression
-            .build()
-            .unwrap();
-
-        QHttpClient {
+        Self::with_config(ClientConfig::default())
+            .expect("default HTTP client configuration is valid")
+    }
+
+    /// Create a client from `http.client(...)` named arguments
+    pub fn from_options(options: Option<&QValue>) -> Result<Self, EvalError> {
+        let mut config = ClientConfig::default();
+        let mut timeout = None;
+        let mut headers = None;
+
+        if let Some(options) = options {
+            let dict = match options {
+                QValue::Dict(dict) => dict,
+                other => return type_err!("http.client options must be Dict, got {}", other.as_obj().cls()),
+            };
+            if let Some(cookies) = dict.get("cookies") {
+                config.cookies = cookies.as_bool();
+            }
+            config.redirect = config.redirect.with_overrides(Some(options))?;
+            config.retry = config.retry.with_overrides(Some(options))?;
+            if let Some(secs) = dict.get("connect_timeout") {
+                config.connect_timeout = Some(non_negative_secs("connect_timeout", &secs)?);
+            }
+            config.proxy = ProxyConfig::from_options(dict)?;
+            config.tls = TlsConfig::from_options(dict)?;
+            timeout = dict.get("timeout");
+            headers = dict.get("headers");
+        }
+
+        let client = Self::with_config(config)?;
+        if let Some(timeout) = timeout {
+            client.set_timeout(vec![timeout])?;
+        }
+        if let Some(headers) = headers {
+            client.set_headers(vec![headers])?;
+        }
+        Ok(client)
+    }
+
+    fn with_config(config: ClientConfig) -> Result<Self, EvalError> {
+        let cookie_store = Arc::new(CookieStoreMutex::new(CookieStore::default()));
+        let client = build_reqwest_client(&config, config.redirect, &cookie_store)?;
+
+        Ok(QHttpClient {
             client: Arc::new(client),
+            config,
+            cookie_store,
             default_headers: Arc::new(Mutex::new(HashMap::new())),
             timeout: Arc::new(Mutex::new(Some(30))),
             id: next_object_id(),
+        })
+    }
+
+    /// Client to use for a request, honoring per-request redirect overrides.
+    /// Overridden clients share this client's cookie jar.
+    fn client_for(&self, redirect: RedirectPolicy) -> Result<Arc<reqwest::Client>, EvalError> {
+        if redirect == self.config.redirect {
+            Ok(Arc::clone(&self.client))
+        } else {
+            Ok(Arc::new(build_reqwest_client(&self.config, redirect, &self.cookie_store)?))
         }
     }
 
@@ -55,6 +544,19 @@ impl QHttpClient {
                 Ok(QValue::Int(QInt::new(timeout.unwrap_or(30) as i64)))
             }
             "headers" => self.get_headers(),
+            "cookies" => self.get_cookies(args),
+            "cookie" => self.get_cookie(args),
+            "set_cookie" => self.set_cookie(args),
+            "clear_cookies" => self.clear_cookies(args),
+            "max_redirects" => Ok(QValue::Int(QInt::new(self.config.redirect.max as i64))),
+            "follow_redirects" => Ok(QValue::Bool(QBool::new(self.config.redirect.follow))),
+            "retry_policy" => Ok(self.config.retry.to_dict()),
+            "proxy" => Ok(self.config.proxy.to_value()),
+            "verify" => Ok(QValue::Bool(QBool::new(self.config.tls.verify))),
+            "connect_timeout" => Ok(match self.config.connect_timeout {
+                Some(secs) => QValue::Float(QFloat::new(secs)),
+                None => QValue::Nil(QNil),
+            }),
             "cls" => Ok(QValue::Str(QString::new(self.cls()))),
             "_id" => Ok(QValue::Int(QInt::new(self.id as i64))),
             "str" => Ok(QValue::Str(QString::new(format!("<HttpClient {}>", self.id)))),
@@ -69,13 +571,7 @@ impl QHttpClient {
         }
 
         let url = args[0].as_str();
-
-        // Parse optional named arguments
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
-
-        self.execute_request("GET", &url, None, headers, query, timeout)
+        self.execute_request("GET", &url, None, self.options_arg(&args))
     }
 
     fn http_post(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -85,11 +581,8 @@ impl QHttpClient {
 
         let url = args[0].as_str();
         let body = self.extract_named_arg(&args, "body")?;
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
 
-        self.execute_request("POST", &url, body, headers, query, timeout)
+        self.execute_request("POST", &url, body, self.options_arg(&args))
     }
 
     fn http_put(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -99,11 +592,8 @@ impl QHttpClient {
 
         let url = args[0].as_str();
         let body = self.extract_named_arg(&args, "body")?;
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
 
-        self.execute_request("PUT", &url, body, headers, query, timeout)
+        self.execute_request("PUT", &url, body, self.options_arg(&args))
     }
 
     fn http_delete(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -112,11 +602,7 @@ impl QHttpClient {
         }
 
         let url = args[0].as_str();
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
-
-        self.execute_request("DELETE", &url, None, headers, query, timeout)
+        self.execute_request("DELETE", &url, None, self.options_arg(&args))
     }
 
     fn http_patch(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -126,11 +612,8 @@ impl QHttpClient {
 
         let url = args[0].as_str();
         let body = self.extract_named_arg(&args, "body")?;
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
 
-        self.execute_request("PATCH", &url, body, headers, query, timeout)
+        self.execute_request("PATCH", &url, body, self.options_arg(&args))
     }
 
     fn http_head(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -139,11 +622,7 @@ impl QHttpClient {
         }
 
         let url = args[0].as_str();
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
-
-        self.execute_request("HEAD", &url, None, headers, query, timeout)
+        self.execute_request("HEAD", &url, None, self.options_arg(&args))
     }
 
     fn http_options(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -152,11 +631,7 @@ impl QHttpClient {
         }
 
         let url = args[0].as_str();
-        let headers = self.extract_named_arg(&args, "headers")?;
-        let query = self.extract_named_arg(&args, "query")?;
-        let timeout = self.extract_named_arg(&args, "timeout")?;
-
-        self.execute_request("OPTIONS", &url, None, headers, query, timeout)
+        self.execute_request("OPTIONS", &url, None, self.options_arg(&args))
     }
 
     fn create_request(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -167,7 +642,7 @@ impl QHttpClient {
         let method = args[0].as_str();
         let url = args[1].as_str();
 
-        let request = QHttpRequest::new(self.client.clone(), method, url);
+        let request = QHttpRequest::new(self.clone(), method, url);
         Ok(QValue::HttpRequest(request))
     }
 
@@ -211,18 +686,84 @@ impl QHttpClient {
 
     fn get_headers(&self) -> Result<QValue, EvalError> {
         let headers = self.default_headers.lock().unwrap();
-        let mut dict = HashMap::new();
+        let mut dict = IndexMap::new();
         for (key, value) in headers.iter() {
             dict.insert(key.clone(), QValue::Str(QString::new(value.clone())));
         }
         Ok(QValue::Dict(Box::new(QDict::new(dict))))
     }
 
-    fn extract_named_arg(&self, _args: &[QValue], _name: &str) -> Result<Option<QValue>, String> {
-        // Named arguments would be passed as part of the args
-        // This is a placeholder - actual implementation depends on how Quest handles named args
-        // For now, return None (no named arg found)
-        Ok(None)
+    fn get_cookies(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if args.len() > 1 {
+            return arg_err!("cookies expects 0 or 1 arguments (url), got {}", args.len());
+        }
+
+        let store = self.cookie_store.lock().unwrap();
+        let cookies: Vec<QValue> = match args.first() {
+            Some(url) => {
+                let url = parse_cookie_url(&url.as_str())?;
+                store.matches(&url).into_iter().map(cookie_to_dict).collect()
+            }
+            None => store.iter_unexpired().map(cookie_to_dict).collect(),
+        };
+        Ok(QValue::Array(QArray::new(cookies)))
+    }
+
+    fn get_cookie(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if args.is_empty() || args.len() > 2 {
+            return arg_err!("cookie expects 1 or 2 arguments (name, [url]), got {}", args.len());
+        }
+
+        let name = args[0].as_str();
+        let store = self.cookie_store.lock().unwrap();
+        let found = match args.get(1) {
+            Some(url) => {
+                let url = parse_cookie_url(&url.as_str())?;
+                store.matches(&url).into_iter().find(|c| c.name() == name).map(|c| c.value().to_string())
+            }
+            None => store.iter_unexpired().find(|c| c.name() == name).map(|c| c.value().to_string()),
+        };
+
+        Ok(match found {
+            Some(value) => QValue::Str(QString::new(value)),
+            None => QValue::Nil(QNil),
+        })
+    }
+
+    fn set_cookie(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if args.len() != 2 {
+            return arg_err!("set_cookie expects 2 arguments (url, cookie), got {}", args.len());
+        }
+
+        let url = parse_cookie_url(&args[0].as_str())?;
+        let cookie = args[1].as_str();
+        self.cookie_store.lock().unwrap().parse(&cookie, &url)
+            .map_err(|e| format!("ValueErr: Invalid cookie '{}': {}", cookie, e))?;
+        Ok(QValue::Nil(QNil))
+    }
+
+    fn clear_cookies(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if !args.is_empty() {
+            return arg_err!("clear_cookies expects 0 arguments, got {}", args.len());
+        }
+
+        self.cookie_store.lock().unwrap().clear();
+        Ok(QValue::Nil(QNil))
+    }
+
+    /// Trailing options Dict holding named arguments (e.g. `get(url, timeout: 5)`)
+    fn options_arg<'a>(&self, args: &'a [QValue]) -> Option<&'a QValue> {
+        match args.last() {
+            Some(last @ QValue::Dict(_)) if args.len() > 1 => Some(last),
+            _ => None,
+        }
+    }
+
+    fn extract_named_arg(&self, args: &[QValue], name: &str) -> Result<Option<QValue>, String> {
+        match self.options_arg(args) {
+            Some(QValue::Dict(dict)) => Ok(dict.get(name)),
+            _ => Ok(None),
+        }
     }
 
     fn execute_request(
@@ -230,11 +771,16 @@ impl QHttpClient {
         method: &str,
         url: &str,
         body: Option<QValue>,
-        headers: Option<QValue>,
-        query: Option<QValue>,
-        timeout: Option<QValue>,
+        options: Option<&QValue>,
     ) -> Result<QValue, EvalError> {
-        let client = self.client.clone();
+        // Optional named arguments
+        let (headers, query, timeout) = match options {
+            Some(QValue::Dict(dict)) => (dict.get("headers"), dict.get("query"), dict.get("timeout")),
+            _ => (None, None, None),
+        };
+        let redirect = self.config.redirect.with_overrides(options)?;
+        let retry = self.config.retry.with_overrides(options)?;
+        let client = self.client_for(redirect)?;
         let url = url.to_string();
         let method_str = method.to_string();
 
@@ -243,23 +789,23 @@ impl QHttpClient {
 
         // Get timeout (use provided or default)
         let timeout_secs = match timeout {
-            Some(QValue::Int(n)) => n.value as u64,
-            Some(QValue::Float(n)) => n.value as u64,
-            _ => self.timeout.lock().unwrap().unwrap_or(30)
+            Some(value) => non_negative_secs("timeout", &value)?,
+            None => self.timeout.lock().unwrap().unwrap_or(30) as f64,
         };
 
         RUNTIME.block_on(async move {
             // Build request
-            let mut req_builder = match method_str.as_str() {
-                "GET" => client.get(&url),
-                "POST" => client.post(&url),
-                "PUT" => client.put(&url),
-                "DELETE" => client.delete(&url),
-                "PATCH" => client.patch(&url),
-                "HEAD" => client.head(&url),
-                "OPTIONS" => client.request(reqwest::Method::OPTIONS, &url),
+            let method = match method_str.as_str() {
+                "GET" => reqwest::Method::GET,
+                "POST" => reqwest::Method::POST,
+                "PUT" => reqwest::Method::PUT,
+                "DELETE" => reqwest::Method::DELETE,
+                "PATCH" => reqwest::Method::PATCH,
+                "HEAD" => reqwest::Method::HEAD,
+                "OPTIONS" => reqwest::Method::OPTIONS,
                 _ => return value_err!("Unsupported HTTP method: {}", method_str),
             };
+            let mut req_builder = client.request(method.clone(), &url);
 
             // Add default headers
             for (key, value) in default_headers {
@@ -298,14 +844,13 @@ impl QHttpClient {
             }
 
             // Set timeout
-            req_builder = req_builder.timeout(std::time::Duration::from_secs(timeout_secs));
+            req_builder = req_builder.timeout(std::time::Duration::from_secs_f64(timeout_secs));
 
-            // Execute request
-            let response = req_builder.send().await
-                .map_err(|e| format!("HTTP request failed: {}", e))?;
+            // Execute request (with retries)
+            let (response, attempts) = send_with_retry(req_builder, &method, &retry).await?;
 
             // Convert to QHttpResponse
-            QHttpResponse::from_reqwest_response(response).await
+            QHttpResponse::from_reqwest_response(response, attempts).await
         })
     }
 }
@@ -346,13 +891,15 @@ impl QObj for QHttpClient {
 
 #[derive(Debug, Clone)]
 pub struct QHttpRequest {
-    client: Arc<reqwest::Client>,
+    client: QHttpClient,
     method: String,
     url: String,
     headers: Arc<Mutex<HashMap<String, String>>>,
     query_params: Arc<Mutex<HashMap<String, String>>>,
     body: Arc<Mutex<Option<RequestBody>>>,
     timeout: Arc<Mutex<Option<u64>>>,
+    redirect: Arc<Mutex<RedirectPolicy>>,
+    retry: Arc<Mutex<RetryPolicy>>,
     id: u64,
 }
 
@@ -361,11 +908,141 @@ enum RequestBody {
     Text(String),
     Json(serde_json::Value),
     Bytes(Bytes),
-    Form(HashMap<String, String>),
+    Form(Vec<(String, String)>),
+    Multipart(MultipartBody),
+}
+
+/// Parts of a multipart/form-data body. Files are read when the body is set,
+/// so missing files are reported at `multipart()` rather than at `send()`.
+#[derive(Debug, Clone, Default)]
+struct MultipartBody {
+    fields: Vec<(String, String)>,
+    files: Vec<FilePart>,
+}
+
+#[derive(Debug, Clone)]
+struct FilePart {
+    field: String,
+    filename: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+impl MultipartBody {
+    /// Build from `multipart(files: {...}, fields: {...})` named arguments
+    fn from_options(options: &QDict) -> Result<Self, EvalError> {
+        let mut body = MultipartBody::default();
+
+        for key in options.keys() {
+            if key != "files" && key != "fields" {
+                return arg_err!("multipart got unexpected argument '{}' (expected files, fields)", key);
+            }
+        }
+        if let Some(fields) = options.get("fields") {
+            body.fields = form_pairs(&fields, "fields")?;
+        }
+        if let Some(files) = options.get("files") {
+            let files = match files {
+                QValue::Dict(dict) => dict,
+                other => return type_err!("files must be Dict, got {}", other.as_obj().cls()),
+            };
+            for field in files.keys() {
+                match files.get(&field).unwrap() {
+                    QValue::Array(arr) => {
+                        for spec in arr.elements.borrow().iter() {
+                            body.files.push(FilePart::from_spec(&field, spec)?);
+                        }
+                    }
+                    spec => body.files.push(FilePart::from_spec(&field, &spec)?),
+                }
+            }
+        }
+        Ok(body)
+    }
+
+    fn to_form(&self) -> Result<reqwest::multipart::Form, EvalError> {
+        let mut form = reqwest::multipart::Form::new();
+        for (name, value) in &self.fields {
+            form = form.text(name.clone(), value.clone());
+        }
+        for file in &self.files {
+            let part = reqwest::multipart::Part::bytes(file.data.clone())
+                .file_name(file.filename.clone())
+                .mime_str(&file.content_type)
+                .map_err(|e| format!("ValueErr: Invalid content type '{}': {}", file.content_type, e))?;
+            form = form.part(file.field.clone(), part);
+        }
+        Ok(form)
+    }
+}
+
+impl FilePart {
+    /// A file is a path (Str), raw content (Bytes), or a Dict with `path` or
+    /// `content` plus optional `filename` and `content_type`
+    fn from_spec(field: &str, spec: &QValue) -> Result<Self, EvalError> {
+        let (path, content, filename, content_type) = match spec {
+            QValue::Str(path) => (Some(path.value.to_string()), None, None, None),
+            QValue::Bytes(b) => (None, Some(b.data.clone()), None, None),
+            QValue::Dict(dict) => (
+                dict.get("path").map(|v| v.as_str()),
+                match dict.get("content") {
+                    Some(QValue::Bytes(b)) => Some(b.data.clone()),
+                    Some(other) => Some(other.as_str().into_bytes()),
+                    None => None,
+                },
+                dict.get("filename").map(|v| v.as_str()),
+                dict.get("content_type").map(|v| v.as_str()),
+            ),
+            other => return type_err!("File '{}' must be a path, Bytes, or Dict, got {}", field, other.as_obj().cls()),
+        };
+
+        let (data, default_name) = match (path, content) {
+            (Some(path), None) => {
+                let data = std::fs::read(&path)
+                    .map_err(|e| format!("IOErr: Failed to read '{}': {}", path, e))?;
+                let name = std::path::Path::new(&path).file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| field.to_string());
+                (data, name)
+            }
+            (None, Some(data)) => (data, field.to_string()),
+            _ => return arg_err!("File '{}' needs exactly one of path or content", field),
+        };
+
+        let filename = filename.unwrap_or(default_name);
+        let content_type = content_type.unwrap_or_else(|| {
+            mime_guess::from_path(&filename).first_or_octet_stream().to_string()
+        });
+        Ok(FilePart { field: field.to_string(), filename, content_type, data })
+    }
+}
+
+/// Flatten a Dict into form pairs in insertion order. Array values repeat the key; nil values are skipped.
+fn form_pairs(value: &QValue, what: &str) -> Result<Vec<(String, String)>, EvalError> {
+    let dict = match value {
+        QValue::Dict(dict) => dict,
+        other => return type_err!("{} must be Dict, got {}", what, other.as_obj().cls()),
+    };
+
+    let mut pairs = Vec::new();
+    for key in dict.keys() {
+        match dict.get(&key).unwrap() {
+            QValue::Nil(_) => {}
+            QValue::Array(arr) => {
+                for item in arr.elements.borrow().iter() {
+                    pairs.push((key.clone(), item.as_str()));
+                }
+            }
+            value => pairs.push((key, value.as_str())),
+        }
+    }
+    Ok(pairs)
 }
 
 impl QHttpRequest {
-    pub fn new(client: Arc<reqwest::Client>, method: String, url: String) -> Self {
+    pub fn new(client: QHttpClient, method: String, url: String) -> Self {
+        let redirect = client.config.redirect;
+        let retry = client.config.retry.clone();
         QHttpRequest {
             client,
             method,
@@ -374,6 +1051,8 @@ impl QHttpRequest {
             query_params: Arc::new(Mutex::new(HashMap::new())),
             body: Arc::new(Mutex::new(None)),
             timeout: Arc::new(Mutex::new(None)),
+            redirect: Arc::new(Mutex::new(redirect)),
+            retry: Arc::new(Mutex::new(retry)),
             id: next_object_id(),
         }
     }
@@ -387,9 +1066,13 @@ impl QHttpRequest {
             "body" => self.set_body(args),
             "json" => self.set_json(args),
             "form" => self.set_form(args),
+            "multipart" => self.set_multipart(args),
             "text" => self.set_text(args),
             "bytes" => self.set_bytes(args),
             "timeout" => self.set_timeout(args),
+            "follow_redirects" => self.set_follow_redirects(args),
+            "max_redirects" => self.set_max_redirects(args),
+            "retry" => self.set_retry(args),
             "send" => self.send(),
             "url" => Ok(QValue::Str(QString::new(self.url.clone()))),
             "method" => Ok(QValue::Str(QString::new(self.method.clone()))),
@@ -501,17 +1184,19 @@ impl QHttpRequest {
             return Err("form expects 1 argument (dict)".into());
         }
 
-        match &args[0] {
-            QValue::Dict(dict) => {
-                let mut form_data = HashMap::new();
-                for (key, value) in dict.map.borrow().iter() {
-                    form_data.insert(key.clone(), value.as_str());
-                }
-                *self.body.lock().unwrap() = Some(RequestBody::Form(form_data));
-                Ok(QValue::HttpRequest(self.clone()))
-            }
-            _ => Err("form expects a Dict argument".into())
-        }
+        let form_data = form_pairs(&args[0], "form")?;
+        *self.body.lock().unwrap() = Some(RequestBody::Form(form_data));
+        Ok(QValue::HttpRequest(self.clone()))
+    }
+
+    fn set_multipart(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        let body = match args.as_slice() {
+            [QValue::Dict(options)] => MultipartBody::from_options(options)?,
+            _ => return Err("multipart expects named arguments (files: Dict, fields: Dict)".into()),
+        };
+
+        *self.body.lock().unwrap() = Some(RequestBody::Multipart(body));
+        Ok(QValue::HttpRequest(self.clone()))
     }
 
     fn set_text(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
@@ -548,6 +1233,53 @@ impl QHttpRequest {
         Ok(QValue::HttpRequest(self.clone()))
     }
 
+    fn set_follow_redirects(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if args.len() != 1 {
+            return Err("follow_redirects expects 1 argument (bool)".into());
+        }
+
+        self.redirect.lock().unwrap().follow = args[0].as_bool();
+        Ok(QValue::HttpRequest(self.clone()))
+    }
+
+    fn set_max_redirects(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if args.len() != 1 {
+            return Err("max_redirects expects 1 argument (count)".into());
+        }
+
+        let max = args[0].as_num()?;
+        if max < 0.0 {
+            return value_err!("max_redirects must be >= 0, got {}", max);
+        }
+        let mut redirect = self.redirect.lock().unwrap();
+        redirect.follow = true;
+        redirect.max = max as usize;
+        Ok(QValue::HttpRequest(self.clone()))
+    }
+
+    fn set_retry(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        // retry(3) or retry(retries: 3, backoff: "linear", ...)
+        let options = match args.as_slice() {
+            [QValue::Dict(_)] => args[0].clone(),
+            [count] => {
+                let mut dict = IndexMap::new();
+                dict.insert("retries".to_string(), count.clone());
+                QValue::Dict(Box::new(QDict::new(dict)))
+            }
+            [count, QValue::Dict(options)] => {
+                let mut options = options.map.borrow().clone();
+                options.insert("retries".to_string(), count.clone());
+                QValue::Dict(Box::new(QDict::new(options)))
+            }
+            _ => return Err("retry expects a count and/or named retry options".into()),
+        };
+
+        let mut retry = self.retry.lock().unwrap();
+        *retry = retry.with_overrides(Some(&options))?;
+        drop(retry);
+        Ok(QValue::HttpRequest(self.clone()))
+    }
+
     fn get_header(&self, args: Vec<QValue>) -> Result<QValue, EvalError> {
         if args.len() != 1 {
             return Err("get_header expects 1 argument (name)".into());
@@ -564,7 +1296,7 @@ impl QHttpRequest {
 
     fn get_headers(&self) -> Result<QValue, EvalError> {
         let headers = self.headers.lock().unwrap();
-        let mut dict = HashMap::new();
+        let mut dict = IndexMap::new();
         for (key, value) in headers.iter() {
             dict.insert(key.clone(), QValue::Str(QString::new(value.clone())));
         }
@@ -587,7 +1319,7 @@ impl QHttpRequest {
 
     fn get_queries(&self) -> Result<QValue, EvalError> {
         let params = self.query_params.lock().unwrap();
-        let mut dict = HashMap::new();
+        let mut dict = IndexMap::new();
         for (key, value) in params.iter() {
             dict.insert(key.clone(), QValue::Str(QString::new(value.clone())));
         }
@@ -595,20 +1327,19 @@ impl QHttpRequest {
     }
 
     fn send(&self) -> Result<QValue, EvalError> {
-        let client = self.client.clone();
+        let client = self.client.client_for(*self.redirect.lock().unwrap())?;
         let method = self.method.clone();
         let url = self.url.clone();
         let headers = self.headers.lock().unwrap().clone();
         let query_params = self.query_params.lock().unwrap().clone();
         let body = self.body.lock().unwrap().clone();
-        let timeout = *self.timeout.lock().unwrap();
+        let timeout = self.timeout.lock().unwrap().or(*self.client.timeout.lock().unwrap());
+        let retry = self.retry.lock().unwrap().clone();
 
         RUNTIME.block_on(async move {
             // Build request
-            let mut req_builder = client.request(
-                method.parse().map_err(|e| format!("Invalid HTTP method: {}", e))?,
-                &url
-            );
+            let method: reqwest::Method = method.parse().map_err(|e| format!("Invalid HTTP method: {}", e))?;
+            let mut req_builder = client.request(method.clone(), &url);
 
             // Add headers
             for (key, value) in headers {
@@ -628,6 +1359,7 @@ impl QHttpRequest {
                     RequestBody::Bytes(bytes) => req_builder.body(bytes),
                     RequestBody::Json(json) => req_builder.json(&json),
                     RequestBody::Form(form) => req_builder.form(&form),
+                    RequestBody::Multipart(multipart) => req_builder.multipart(multipart.to_form()?),
                 };
             }
 
@@ -636,12 +1368,11 @@ impl QHttpRequest {
                 req_builder = req_builder.timeout(std::time::Duration::from_secs(secs));
             }
 
-            // Execute request
-            let response = req_builder.send().await
-                .map_err(|e| format!("HTTP request failed: {}", e))?;
+            // Execute request (with retries)
+            let (response, attempts) = send_with_retry(req_builder, &method, &retry).await?;
 
             // Convert to QHttpResponse
-            QHttpResponse::from_reqwest_response(response).await
+            QHttpResponse::from_reqwest_response(response, attempts).await
         })
     }
 }
@@ -689,11 +1420,12 @@ pub struct QHttpResponse {
     body_text: Arc<Mutex<Option<String>>>,  // Cached text
     url: String,
     content_length: Option<u64>,
+    attempts: u32,
     id: u64,
 }
 
 impl QHttpResponse {
-    pub async fn from_reqwest_response(resp: reqwest::Response) -> Result<QValue, EvalError> {
+    pub async fn from_reqwest_response(resp: reqwest::Response, attempts: u32) -> Result<QValue, EvalError> {
         let status = resp.status().as_u16();
         let url = resp.url().to_string();
 
@@ -706,8 +1438,10 @@ impl QHttpResponse {
             );
         }
 
-        // Extract cookies (placeholder - reqwest cookie store is complex)
-        let cookies = HashMap::new();
+        // Cookies set by this response (the client's jar keeps them across requests)
+        let cookies = resp.cookies()
+            .map(|c| (c.name().to_string(), c.value().to_string()))
+            .collect();
 
         // Get content length
         let content_length = resp.content_length();
@@ -724,6 +1458,7 @@ impl QHttpResponse {
             body_text: Arc::new(Mutex::new(None)),
             url,
             content_length,
+            attempts,
             id: next_object_id(),
         };
 
@@ -754,6 +1489,7 @@ impl QHttpResponse {
             "bytes" => self.body_bytes(),
             "body" => self.body_bytes(),
             "content_length" => self.get_content_length(),
+            "attempts" => Ok(QValue::Int(QInt::new(self.attempts as i64))),
             "_id" => Ok(QValue::Int(QInt::new(self.id as i64))),
             "str" => Ok(QValue::Str(QString::new(format!("<HttpResponse {}>", self.status)))),
             "cls" => Ok(QValue::Str(QString::new(self.cls()))),
@@ -775,7 +1511,7 @@ impl QHttpResponse {
     }
 
     fn get_headers(&self) -> Result<QValue, EvalError> {
-        let mut dict = HashMap::new();
+        let mut dict = IndexMap::new();
         for (key, value) in &self.headers {
             dict.insert(key.clone(), QValue::Str(QString::new(value.clone())));
         }
@@ -804,7 +1540,7 @@ impl QHttpResponse {
     }
 
     fn get_cookies(&self) -> Result<QValue, EvalError> {
-        let mut dict = HashMap::new();
+        let mut dict = IndexMap::new();
         for (key, value) in &self.cookies {
             dict.insert(key.clone(), QValue::Str(QString::new(value.clone())));
         }
@@ -945,8 +1681,10 @@ pub fn create_http_client_module() -> QValue {
 pub fn call_http_client_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
         "http.client" => {
-            // TODO: Parse optional named args: timeout, headers
-            Ok(QValue::HttpClient(QHttpClient::new()))
+            if args.len() > 1 {
+                return arg_err!("client expects named arguments only, got {} positional", args.len());
+            }
+            Ok(QValue::HttpClient(QHttpClient::from_options(args.first())?))
         }
         "http.get" => {
             let client = QHttpClient::new();
@@ -979,3 +1717,23 @@ pub fn call_http_client_function(func_name: &str, args: Vec<QValue>, _scope: &mu
         _ => attr_err!("Unknown function: {}", func_name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form_pairs_keep_insertion_order() {
+        let mut map = IndexMap::new();
+        map.insert("zeta".to_string(), QValue::Str(QString::new("1".to_string())));
+        map.insert("alpha".to_string(), QValue::Array(QArray::new(vec![
+            QValue::Str(QString::new("a".to_string())),
+            QValue::Str(QString::new("b".to_string())),
+        ])));
+        map.insert("skipped".to_string(), QValue::Nil(QNil));
+        map.insert("mid".to_string(), QValue::Int(QInt::new(2)));
+        let pairs = form_pairs(&QValue::Dict(Box::new(QDict::new(map))), "form").unwrap();
+        let pairs: Vec<(&str, &str)> = pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(pairs, vec![("zeta", "1"), ("alpha", "a"), ("alpha", "b"), ("mid", "2")]);
+    }
+}
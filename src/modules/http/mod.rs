@@ -1,6 +1,7 @@
 pub mod runtime;
 pub mod client;
 pub mod urlparse;
+pub mod url;
 
 pub use client::{
     QHttpClient,
@@ -0,0 +1,386 @@
+// Url objects for std/http/urlparse
+//
+// A Url wraps a parsed WHATWG URL (the `url` crate, the same parser reqwest
+// uses). Getters return components as they appear in the URL (still
+// percent-encoded), except segments() and the query parameter methods, which
+// decode. Setters take raw text and percent-encode it with the rules for that
+// component, so a space becomes %20 in a path but + in a query parameter. Setters mutate the Url and return
+// it for chaining, like Array.push(). copy() gives an independent Url.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+/// Characters percent-encoded in each URL component (WHATWG URL standard)
+const FRAGMENT: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>').add(b'`');
+const QUERY: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>').add(b'\'');
+const PATH: &AsciiSet = &QUERY.add(b'?').add(b'`').add(b'{').add(b'}');
+const SEGMENT: &AsciiSet = &PATH.add(b'/').add(b'%');
+const USERINFO: &AsciiSet = &PATH.add(b'/').add(b':').add(b';').add(b'=').add(b'@')
+    .add(b'[').add(b'\\').add(b']').add(b'^').add(b'|');
+
+/// Percent-encode `text` for one URL component
+pub fn quote_component(text: &str, component: &str) -> Result<String, String> {
+    let set = match component {
+        "path" => PATH,
+        "segment" => SEGMENT,
+        "query" => QUERY,
+        "fragment" => FRAGMENT,
+        "userinfo" => USERINFO,
+        // application/x-www-form-urlencoded, used for query parameter keys and values
+        "form" => return Ok(url::form_urlencoded::byte_serialize(text.as_bytes()).collect()),
+        _ => return value_err!("Unknown URL component '{}' (expected path, segment, query, fragment, userinfo or form)", component),
+    };
+    Ok(utf8_percent_encode(text, set).to_string())
+}
+
+/// Parse `text`, resolving it against `base` when given
+pub fn parse_url(text: &str, base: Option<&str>) -> Result<Url, String> {
+    let parsed = match base {
+        Some(base) => Url::parse(base)
+            .map_err(|e| format!("ValueErr: Invalid base URL '{}': {}", base, e))?
+            .join(text),
+        None => Url::parse(text),
+    };
+    parsed.map_err(|e| format!("ValueErr: Invalid URL '{}': {}", text, e))
+}
+
+#[derive(Debug, Clone)]
+pub struct QUrl {
+    url: Rc<RefCell<Url>>,
+    pub id: u64,
+}
+
+fn str_value(s: &str) -> QValue {
+    QValue::Str(QString::new(s.to_string()))
+}
+
+fn optional_str(s: Option<&str>) -> QValue {
+    match s {
+        Some(s) if !s.is_empty() => str_value(s),
+        _ => QValue::Nil(QNil),
+    }
+}
+
+/// The Str form of a query parameter value; Arrays give one value per element
+fn param_values(value: &QValue) -> Result<Vec<String>, String> {
+    match value {
+        QValue::Nil(_) => Ok(Vec::new()),
+        QValue::Str(s) => Ok(vec![s.value.as_ref().clone()]),
+        QValue::Int(_) | QValue::Float(_) | QValue::Bool(_) | QValue::Decimal(_) | QValue::BigInt(_) | QValue::Uuid(_) => Ok(vec![value.as_str()]),
+        QValue::Array(a) => a.elements.borrow().iter()
+            .map(|v| match v {
+                QValue::Array(_) | QValue::Dict(_) | QValue::Nil(_) => type_err!("Query parameter Array elements must be scalars, got {}", v.as_obj().cls()),
+                _ => Ok(param_values(v)?.remove(0)),
+            })
+            .collect(),
+        _ => type_err!("Query parameter values must be Str, Num, Bool, Array or nil, got {}", value.as_obj().cls()),
+    }
+}
+
+fn optional_text(name: &str, value: &QValue) -> Result<Option<String>, String> {
+    match value {
+        QValue::Nil(_) => Ok(None),
+        QValue::Str(s) => Ok(Some(s.value.as_ref().clone())),
+        _ => type_err!("{} must be Str or nil, got {}", name, value.as_obj().cls()),
+    }
+}
+
+fn check_count(method: &str, args: &[QValue], expected: usize, usage: &str) -> Result<(), String> {
+    if args.len() != expected {
+        if expected == 0 {
+            return arg_err!("{} expects 0 arguments, got {}", method, args.len());
+        }
+        return arg_err!("{} expects {} argument ({}), got {}", method, expected, usage, args.len());
+    }
+    Ok(())
+}
+
+impl QUrl {
+    pub fn new(url: Url) -> Self {
+        QUrl {
+            url: Rc::new(RefCell::new(url)),
+            id: next_object_id(),
+        }
+    }
+
+    fn pairs(&self) -> Vec<(String, String)> {
+        self.url.borrow().query_pairs().into_owned().collect()
+    }
+
+    fn write_pairs(&self, pairs: Vec<(String, String)>) {
+        let mut url = self.url.borrow_mut();
+        if pairs.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+
+    /// Replace every value of `key`, keeping its position; nil removes it
+    fn set_param(&self, key: &str, value: &QValue) -> Result<(), String> {
+        let mut values = param_values(value)?.into_iter();
+        let mut pairs = Vec::new();
+        let mut placed = false;
+        for (k, v) in self.pairs() {
+            if k != key {
+                pairs.push((k, v));
+            } else if !placed {
+                pairs.extend(values.by_ref().map(|v| (key.to_string(), v)));
+                placed = true;
+            }
+        }
+        pairs.extend(values.map(|v| (key.to_string(), v)));
+        self.write_pairs(pairs);
+        Ok(())
+    }
+
+    fn this(&self) -> QValue {
+        QValue::Url(self.clone())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        // Try QObj trait methods first
+        use crate::types::try_call_qobj_method;
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            // Getters
+            "scheme" | "host" | "port" | "port_or_default" | "path" | "query" | "fragment"
+            | "username" | "password" | "origin" | "segments" | "params" | "query_pairs" => {
+                check_count(method_name, &args, 0, "")?;
+                let url = self.url.borrow();
+                Ok(match method_name {
+                    "scheme" => str_value(url.scheme()),
+                    "host" => optional_str(url.host_str()),
+                    "port" | "port_or_default" => {
+                        let port = if method_name == "port" { url.port() } else { url.port_or_known_default() };
+                        match port {
+                            Some(port) => QValue::Int(QInt::new(port as i64)),
+                            None => QValue::Nil(QNil),
+                        }
+                    }
+                    "path" => str_value(url.path()),
+                    "query" => optional_str(url.query()),
+                    "fragment" => optional_str(url.fragment()),
+                    "username" => optional_str(Some(url.username())),
+                    "password" => optional_str(url.password()),
+                    "origin" => str_value(&url.origin().ascii_serialization()),
+                    "segments" => {
+                        // Decoded path segments, without the empty one before the leading /
+                        let segments = url.path_segments().map(|s| s.collect::<Vec<_>>()).unwrap_or_default();
+                        let decoded = segments.into_iter()
+                            .filter(|s| !s.is_empty())
+                            .map(|s| str_value(&percent_encoding::percent_decode_str(s).decode_utf8_lossy()))
+                            .collect();
+                        QValue::Array(QArray::new(decoded))
+                    }
+                    "params" => {
+                        // Decoded query parameters; the last value wins for repeated keys
+                        let map: IndexMap<String, QValue> = url.query_pairs().into_owned()
+                            .map(|(k, v)| (k, QValue::Str(QString::new(v))))
+                            .collect();
+                        QValue::Dict(Box::new(QDict::new(map)))
+                    }
+                    _ => {
+                        let pairs = url.query_pairs().into_owned()
+                            .map(|(k, v)| QValue::Array(QArray::new(vec![QValue::Str(QString::new(k)), QValue::Str(QString::new(v))])))
+                            .collect();
+                        QValue::Array(QArray::new(pairs))
+                    }
+                })
+            }
+            "param" => {
+                // param(key, default = nil) - last value of a query parameter
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("param expects 1 or 2 arguments (key, default), got {}", args.len());
+                }
+                let key = args[0].as_str();
+                let value = self.pairs().into_iter().rev().find(|(k, _)| *k == key).map(|(_, v)| v);
+                Ok(match value {
+                    Some(v) => QValue::Str(QString::new(v)),
+                    None => args.get(1).cloned().unwrap_or(QValue::Nil(QNil)),
+                })
+            }
+            "param_list" => {
+                // All values of a repeated query parameter, in order
+                check_count(method_name, &args, 1, "key")?;
+                let key = args[0].as_str();
+                let values = self.pairs().into_iter()
+                    .filter(|(k, _)| *k == key)
+                    .map(|(_, v)| QValue::Str(QString::new(v)))
+                    .collect();
+                Ok(QValue::Array(QArray::new(values)))
+            }
+            "has_param" => {
+                check_count(method_name, &args, 1, "key")?;
+                let key = args[0].as_str();
+                Ok(QValue::Bool(QBool::new(self.pairs().iter().any(|(k, _)| *k == key))))
+            }
+
+            // Setters (mutate and return self)
+            "set_scheme" => {
+                check_count(method_name, &args, 1, "scheme")?;
+                let scheme = args[0].as_str();
+                if self.url.borrow_mut().set_scheme(&scheme).is_err() {
+                    return value_err!("Cannot change the scheme of '{}' to '{}'", self.url.borrow(), scheme);
+                }
+                Ok(self.this())
+            }
+            "set_host" => {
+                check_count(method_name, &args, 1, "host")?;
+                let host = optional_text("host", &args[0])?;
+                let result = self.url.borrow_mut().set_host(host.as_deref());
+                if let Err(e) = result {
+                    return value_err!("Cannot set host of '{}' to {:?}: {}", self.url.borrow(), host.unwrap_or_default(), e);
+                }
+                Ok(self.this())
+            }
+            "set_port" => {
+                check_count(method_name, &args, 1, "port")?;
+                let port = match &args[0] {
+                    QValue::Nil(_) => None,
+                    QValue::Int(i) if (0..=65535).contains(&i.value) => Some(i.value as u16),
+                    QValue::Int(i) => return value_err!("Port must be between 0 and 65535, got {}", i.value),
+                    other => return type_err!("Port must be Int or nil, got {}", other.as_obj().cls()),
+                };
+                if self.url.borrow_mut().set_port(port).is_err() {
+                    return value_err!("'{}' cannot have a port", self.url.borrow());
+                }
+                Ok(self.this())
+            }
+            "set_path" => {
+                check_count(method_name, &args, 1, "path")?;
+                self.url.borrow_mut().set_path(&args[0].as_str());
+                Ok(self.this())
+            }
+            "set_query" => {
+                check_count(method_name, &args, 1, "query")?;
+                let query = optional_text("query", &args[0])?;
+                self.url.borrow_mut().set_query(query.as_deref());
+                Ok(self.this())
+            }
+            "set_fragment" => {
+                check_count(method_name, &args, 1, "fragment")?;
+                let fragment = optional_text("fragment", &args[0])?;
+                self.url.borrow_mut().set_fragment(fragment.as_deref());
+                Ok(self.this())
+            }
+            "set_username" => {
+                check_count(method_name, &args, 1, "username")?;
+                let username = optional_text("username", &args[0])?.unwrap_or_default();
+                if self.url.borrow_mut().set_username(&username).is_err() {
+                    return value_err!("'{}' cannot have a username", self.url.borrow());
+                }
+                Ok(self.this())
+            }
+            "set_password" => {
+                check_count(method_name, &args, 1, "password")?;
+                let password = optional_text("password", &args[0])?;
+                if self.url.borrow_mut().set_password(password.as_deref()).is_err() {
+                    return value_err!("'{}' cannot have a password", self.url.borrow());
+                }
+                Ok(self.this())
+            }
+            "set_param" => {
+                // set_param(key, value) - replace all values; an Array sets several, nil removes
+                check_count(method_name, &args, 2, "key, value")?;
+                self.set_param(&args[0].as_str(), &args[1])?;
+                Ok(self.this())
+            }
+            "merge_params" => {
+                // merge_params(dict) - set_param() for each entry, keeping other parameters
+                check_count(method_name, &args, 1, "params")?;
+                let QValue::Dict(params) = &args[0] else {
+                    return type_err!("merge_params expects a Dict, got {}", args[0].as_obj().cls());
+                };
+                for (key, value) in params.map.borrow().iter() {
+                    self.set_param(key, value)?;
+                }
+                Ok(self.this())
+            }
+            "remove_param" => {
+                check_count(method_name, &args, 1, "key")?;
+                self.set_param(&args[0].as_str(), &QValue::Nil(QNil))?;
+                Ok(self.this())
+            }
+            "append_path" => {
+                // append_path(*segments) - each segment is encoded, so "/" becomes %2F
+                if self.url.borrow().cannot_be_a_base() {
+                    return value_err!("Cannot append a path to '{}'", self.url.borrow());
+                }
+                let mut url = self.url.borrow_mut();
+                if let Ok(mut path) = url.path_segments_mut() {
+                    path.pop_if_empty();
+                    for segment in &args {
+                        path.push(&segment.as_str());
+                    }
+                }
+                drop(url);
+                Ok(self.this())
+            }
+
+            // New Urls
+            "join" => {
+                // join(reference) - resolve a relative reference like a browser does
+                check_count(method_name, &args, 1, "reference")?;
+                let reference = args[0].as_str();
+                let joined = self.url.borrow().join(&reference)
+                    .map_err(|e| format!("ValueErr: Cannot join '{}' to '{}': {}", reference, self.url.borrow(), e))?;
+                Ok(QValue::Url(QUrl::new(joined)))
+            }
+            "copy" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(QValue::Url(QUrl::new(self.url.borrow().clone())))
+            }
+            "eq" | "neq" => {
+                check_count(method_name, &args, 1, "other")?;
+                let same = match &args[0] {
+                    QValue::Url(other) => *self.url.borrow() == *other.url.borrow(),
+                    _ => false,
+                };
+                Ok(QValue::Bool(QBool::new(same == (method_name == "eq"))))
+            }
+            _ => attr_err!("Unknown method '{}' on Url", method_name),
+        }
+    }
+}
+
+impl QObj for QUrl {
+    fn cls(&self) -> String {
+        "Url".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Url"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Url"
+    }
+
+    fn str(&self) -> String {
+        self.url.borrow().to_string()
+    }
+
+    fn _rep(&self) -> String {
+        format!("Url({})", self.url.borrow())
+    }
+
+    fn _doc(&self) -> String {
+        "A parsed URL with component getters and setters".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
@@ -6,13 +6,16 @@ use crate::types::*;
 use crate::{arg_err, attr_err};
 use crate::Scope;
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use urlparse::{urlparse as parse_url, quote, unquote, Url};
+use super::url::{parse_url as parse_absolute, quote_component, QUrl};
 
 /// Create the urlparse module
 pub fn create_urlparse_module() -> QValue {
     let mut members = HashMap::new();
 
     // URL parsing
+    members.insert("url".to_string(), create_fn("urlparse", "url"));
     members.insert("urlparse".to_string(), create_fn("urlparse", "urlparse"));
     members.insert("urljoin".to_string(), create_fn("urlparse", "urljoin"));
 
@@ -26,6 +29,7 @@ pub fn create_urlparse_module() -> QValue {
     members.insert("quote_plus".to_string(), create_fn("urlparse", "quote_plus"));
     members.insert("unquote".to_string(), create_fn("urlparse", "unquote"));
     members.insert("unquote_plus".to_string(), create_fn("urlparse", "unquote_plus"));
+    members.insert("quote_component".to_string(), create_fn("urlparse", "quote_component"));
 
     QValue::Module(Box::new(QModule::new("urlparse".to_string(), members)))
 }
@@ -33,6 +37,19 @@ pub fn create_urlparse_module() -> QValue {
 /// Handle urlparse.* function calls
 pub fn call_urlparse_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
     match func_name {
+        "urlparse.url" => {
+            // Parse into a Url object, resolving relative references against base
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("url expects 1-2 arguments (url, [base]), got {}", args.len());
+            }
+            let base = match args.get(1) {
+                None | Some(QValue::Nil(_)) => None,
+                Some(base) => Some(base.as_str()),
+            };
+            let parsed = parse_absolute(&args[0].as_str(), base.as_deref())?;
+            Ok(QValue::Url(QUrl::new(parsed)))
+        }
+
         "urlparse.urlparse" => {
             // Parse URL into components (scheme, netloc, path, params, query, fragment)
             if args.is_empty() || args.len() > 2 {
@@ -49,7 +66,7 @@ pub fn call_urlparse_function(func_name: &str, args: Vec<QValue>, _scope: &mut S
             // Use the urlparse crate
             let parsed = parse_url(&url_str);
 
-            let mut result = HashMap::new();
+            let mut result = IndexMap::new();
             result.insert("scheme".to_string(), QValue::Str(QString::new(parsed.scheme)));
             result.insert("netloc".to_string(), QValue::Str(QString::new(parsed.netloc)));
             result.insert("path".to_string(), QValue::Str(QString::new(parsed.path)));
@@ -94,6 +111,12 @@ pub fn call_urlparse_function(func_name: &str, args: Vec<QValue>, _scope: &mut S
             let base = args[0].as_str();
             let relative = args[1].as_str();
 
+            // Resolve per RFC 3986 (handles ../, ?query and //host references)
+            // when the base is an absolute URL
+            if let Ok(joined) = parse_absolute(&relative, Some(&base)) {
+                return Ok(QValue::Str(QString::new(joined.to_string())));
+            }
+
             // Parse base URL
             let base_parsed = parse_url(&base);
             let base_url = Url {
@@ -161,7 +184,7 @@ pub fn call_urlparse_function(func_name: &str, args: Vec<QValue>, _scope: &mut S
             }
 
             // Convert to Quest dict of arrays
-            let quest_dict: HashMap<String, QValue> = result.into_iter()
+            let quest_dict: IndexMap<String, QValue> = result.into_iter()
                 .map(|(k, v)| (k, QValue::Array(QArray::new(v))))
                 .collect();
 
@@ -285,6 +308,15 @@ pub fn call_urlparse_function(func_name: &str, args: Vec<QValue>, _scope: &mut S
             Ok(QValue::Str(QString::new(decoded)))
         }
 
+        "urlparse.quote_component" => {
+            // Percent-encode for one URL component (path, segment, query, fragment, userinfo, form)
+            if args.len() != 2 {
+                return arg_err!("quote_component expects 2 arguments (string, component), got {}", args.len());
+            }
+            let encoded = quote_component(&args[0].as_str(), &args[1].as_str())?;
+            Ok(QValue::Str(QString::new(encoded)))
+        }
+
         _ => attr_err!("Unknown urlparse function: {}", func_name)
     }
 }
@@ -0,0 +1,991 @@
+// std/image - raster images
+//
+// An Image is always 8-bit RGBA, held behind Rc<RefCell> so drawing methods
+// can paint in place and return self for chaining. Transforms (resize, crop,
+// rotate, flips, grayscale) return new images. Only PNG and JPEG are read and
+// written. Shapes are drawn without anti-aliasing and alpha-blended over the
+// existing pixels; text uses a built-in 8x8 bitmap font unless a TrueType or
+// OpenType font is given.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use font8x8::{UnicodeFonts, BASIC_FONTS, LATIN_FONTS};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageFormat, ImageReader, Rgba, RgbaImage};
+
+use crate::control_flow::EvalError;
+use crate::modules::args::{option, take_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, index_err, io_err, type_err, value_err};
+
+/// new(), from_pixels() and resize() refuse to allocate more pixels than this (1 GiB of RGBA)
+const MAX_PIXELS: u64 = 1 << 28;
+
+const DEFAULT_QUALITY: u8 = 85;
+const DEFAULT_TEXT_SIZE: f64 = 16.0;
+
+#[derive(Debug)]
+pub struct ImageData {
+    pub pixels: RgbaImage,
+    /// "png" or "jpeg" for decoded images, nil for ones built in code
+    pub format: Option<&'static str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QImage {
+    pub data: Rc<RefCell<ImageData>>,
+    pub id: u64,
+}
+
+fn image_value(pixels: RgbaImage, format: Option<&'static str>) -> QValue {
+    QValue::Image(QImage::new(pixels, format))
+}
+
+// ---------------------------------------------------------------------------
+// Arguments
+
+/// Positional argument `index`, else keyword `name`; nil counts as missing
+fn arg(args: &[QValue], options: &Option<Box<QDict>>, index: usize, name: &str) -> Option<QValue> {
+    match args.get(index) {
+        Some(QValue::Nil(_)) => None,
+        Some(value) => Some(value.clone()),
+        None => option(options, name),
+    }
+}
+
+fn check_count(method: &str, args: &[QValue], min: usize, max: usize, usage: &str) -> Result<(), String> {
+    if args.len() < min || args.len() > max {
+        let expected = if min == max { min.to_string() } else { format!("{} to {}", min, max) };
+        return arg_err!("{} expects {} arguments ({}), got {}", method, expected, usage, args.len());
+    }
+    Ok(())
+}
+
+fn num_arg(method: &str, name: &str, value: &QValue) -> Result<f64, String> {
+    match value {
+        QValue::Int(i) => Ok(i.value as f64),
+        QValue::Float(f) if f.value.is_finite() => Ok(f.value),
+        QValue::Float(f) => value_err!("{} {} must be finite, got {}", method, name, f.value),
+        other => type_err!("{} {} must be a number, got {}", method, name, other.as_obj().cls()),
+    }
+}
+
+/// A pixel coordinate; Floats are rounded
+fn coord_arg(method: &str, name: &str, value: &QValue) -> Result<i64, String> {
+    Ok(num_arg(method, name, value)?.round() as i64)
+}
+
+/// A width or height between 1 and u32::MAX
+fn dimension_arg(method: &str, name: &str, value: &QValue) -> Result<u32, String> {
+    let n = coord_arg(method, name, value)?;
+    if n < 1 || n > u32::MAX as i64 {
+        return value_err!("{} {} must be positive, got {}", method, name, n);
+    }
+    Ok(n as u32)
+}
+
+fn check_pixels(method: &str, width: u32, height: u32) -> Result<(), String> {
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return value_err!("{} size {}x{} is too large (at most {} pixels)", method, width, height, MAX_PIXELS);
+    }
+    Ok(())
+}
+
+/// An Array of at least 2 [x, y] points
+fn points_arg(method: &str, value: &QValue) -> Result<Vec<(f64, f64)>, String> {
+    let QValue::Array(items) = value else {
+        return type_err!("{} expects an Array of [x, y] points, got {}", method, value.as_obj().cls());
+    };
+    let mut points = Vec::new();
+    for item in items.elements.borrow().iter() {
+        let QValue::Array(pair) = item else {
+            return type_err!("{} points must be [x, y] Arrays, got {}", method, item.as_obj().cls());
+        };
+        let pair = pair.elements.borrow();
+        if pair.len() != 2 {
+            return value_err!("{} points must be [x, y] Arrays, got {} items", method, pair.len());
+        }
+        points.push((num_arg(method, "x", &pair[0])?, num_arg(method, "y", &pair[1])?));
+    }
+    if points.len() < 2 {
+        return value_err!("{} needs at least 2 points, got {}", method, points.len());
+    }
+    Ok(points)
+}
+
+fn stroke_arg(method: &str, options: &Option<Box<QDict>>) -> Result<f64, String> {
+    match options.as_ref().and_then(|o| o.get("stroke")) {
+        None | Some(QValue::Nil(_)) => Ok(1.0),
+        Some(value) => {
+            let stroke = num_arg(method, "stroke", &value)?;
+            if stroke <= 0.0 {
+                return value_err!("{} stroke must be positive, got {}", method, stroke);
+            }
+            Ok(stroke)
+        }
+    }
+}
+
+fn fill_arg(options: &Option<Box<QDict>>) -> bool {
+    options.as_ref().and_then(|o| o.get("fill")).is_some_and(|v| v.as_bool())
+}
+
+fn quality_arg(method: &str, options: &Option<Box<QDict>>) -> Result<u8, String> {
+    match options.as_ref().and_then(|o| o.get("quality")) {
+        None | Some(QValue::Nil(_)) => Ok(DEFAULT_QUALITY),
+        Some(QValue::Int(q)) if (1..=100).contains(&q.value) => Ok(q.value as u8),
+        Some(other) => value_err!("{} quality must be an Int from 1 to 100, got {}", method, other.as_str()),
+    }
+}
+
+/// "png" or "jpeg" from a format name or file extension
+fn format_name(method: &str, name: &str) -> Result<&'static str, String> {
+    match name.to_lowercase().as_str() {
+        "png" => Ok("png"),
+        "jpg" | "jpeg" => Ok("jpeg"),
+        other => value_err!("{} supports png and jpeg, got '{}'", method, other),
+    }
+}
+
+fn filter_arg(method: &str, value: Option<QValue>) -> Result<FilterType, String> {
+    let Some(value) = value else {
+        return Ok(FilterType::Lanczos3);
+    };
+    match value.as_str().as_str() {
+        "nearest" => Ok(FilterType::Nearest),
+        "linear" | "bilinear" => Ok(FilterType::Triangle),
+        "cubic" | "bicubic" => Ok(FilterType::CatmullRom),
+        "gaussian" => Ok(FilterType::Gaussian),
+        "lanczos" => Ok(FilterType::Lanczos3),
+        other => value_err!("{} filter must be nearest, linear, cubic, gaussian or lanczos, got '{}'", method, other),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Colors
+
+const NAMED_COLORS: &[(&str, [u8; 4])] = &[
+    ("transparent", [0, 0, 0, 0]),
+    ("black", [0, 0, 0, 255]),
+    ("white", [255, 255, 255, 255]),
+    ("gray", [128, 128, 128, 255]),
+    ("grey", [128, 128, 128, 255]),
+    ("silver", [192, 192, 192, 255]),
+    ("red", [255, 0, 0, 255]),
+    ("green", [0, 128, 0, 255]),
+    ("lime", [0, 255, 0, 255]),
+    ("blue", [0, 0, 255, 255]),
+    ("navy", [0, 0, 128, 255]),
+    ("yellow", [255, 255, 0, 255]),
+    ("orange", [255, 165, 0, 255]),
+    ("purple", [128, 0, 128, 255]),
+    ("magenta", [255, 0, 255, 255]),
+    ("cyan", [0, 255, 255, 255]),
+    ("teal", [0, 128, 128, 255]),
+    ("brown", [165, 42, 42, 255]),
+    ("pink", [255, 192, 203, 255]),
+];
+
+/// "#rgb", "#rgba", "#rrggbb", "#rrggbbaa", a color name, or [r, g, b] / [r, g, b, a]
+pub(crate) fn parse_color(value: &QValue) -> Result<Rgba<u8>, String> {
+    match value {
+        QValue::Str(s) => {
+            let text = s.value.trim().to_lowercase();
+            if let Some(hex) = text.strip_prefix('#') {
+                let digits: Vec<u8> = match hex.len() {
+                    3 | 4 => hex.chars().map(|c| c.to_digit(16).map(|d| d as u8 * 17)).collect::<Option<_>>(),
+                    6 | 8 => (0..hex.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                        .collect::<Option<_>>(),
+                    _ => None,
+                }
+                .ok_or_else(|| format!("ValueErr: Invalid color '{}'", s.value))?;
+                let alpha = digits.get(3).copied().unwrap_or(255);
+                return Ok(Rgba([digits[0], digits[1], digits[2], alpha]));
+            }
+            match NAMED_COLORS.iter().find(|(name, _)| *name == text) {
+                Some((_, rgba)) => Ok(Rgba(*rgba)),
+                None => value_err!("Unknown color '{}'", s.value),
+            }
+        }
+        QValue::Array(arr) => {
+            let items = arr.elements.borrow();
+            if items.len() != 3 && items.len() != 4 {
+                return value_err!("Color arrays must be [r, g, b] or [r, g, b, a], got {} items", items.len());
+            }
+            let mut rgba = [0, 0, 0, 255];
+            for (i, item) in items.iter().enumerate() {
+                match item {
+                    QValue::Int(c) if (0..=255).contains(&c.value) => rgba[i] = c.value as u8,
+                    other => return value_err!("Color components must be Ints from 0 to 255, got {}", other.as_str()),
+                }
+            }
+            Ok(Rgba(rgba))
+        }
+        other => type_err!("Color must be a Str or Array, got {}", other.as_obj().cls()),
+    }
+}
+
+fn color_arg(value: Option<QValue>, default: &str) -> Result<Rgba<u8>, String> {
+    match value {
+        Some(value) => parse_color(&value),
+        None => parse_color(&QValue::Str(QString::new(default.to_string()))),
+    }
+}
+
+fn color_value(pixel: &Rgba<u8>) -> QValue {
+    QValue::Array(QArray::new(pixel.0.iter().map(|c| QValue::Int(QInt::new(*c as i64))).collect()))
+}
+
+// ---------------------------------------------------------------------------
+// Decoding and encoding
+
+fn decode(reader: ImageReader<impl std::io::BufRead + std::io::Seek>, source: &str) -> Result<QValue, String> {
+    let reader = reader.with_guessed_format().map_err(|e| format!("IOErr: Cannot read {}: {}", source, e))?;
+    let format = match reader.format() {
+        Some(ImageFormat::Png) => "png",
+        Some(ImageFormat::Jpeg) => "jpeg",
+        Some(other) => return value_err!("{} is {:?}; only PNG and JPEG are supported", source, other),
+        None => return value_err!("{} is not a PNG or JPEG image", source),
+    };
+    let mut decoder = reader.into_decoder().map_err(|e| format!("ValueErr: Cannot decode {}: {}", source, e))?;
+    // Phone cameras store rotation as EXIF metadata; bake it into the pixels
+    let orientation = decoder.orientation().map_err(|e| format!("ValueErr: Cannot decode {}: {}", source, e))?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| format!("ValueErr: Cannot decode {}: {}", source, e))?;
+    image.apply_orientation(orientation);
+    Ok(image_value(image.to_rgba8(), Some(format)))
+}
+
+pub(crate) fn encode(pixels: &RgbaImage, format: &str, quality: u8) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let (width, height) = pixels.dimensions();
+    let result = if format == "jpeg" {
+        // JPEG has no alpha channel
+        let rgb = DynamicImage::ImageRgba8(pixels.clone()).to_rgb8();
+        JpegEncoder::new_with_quality(&mut out, quality).write_image(rgb.as_raw(), width, height, image::ExtendedColorType::Rgb8)
+    } else {
+        PngEncoder::new(&mut out).write_image(pixels.as_raw(), width, height, image::ExtendedColorType::Rgba8)
+    };
+    result.map_err(|e| format!("ValueErr: Cannot encode {}: {}", format, e))?;
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// Drawing
+//
+// Coordinates are pixel corners: a rectangle at (0, 0) of size 10x10 covers
+// pixels 0..9, and a polygon pixel is painted when its center is inside.
+
+/// Paint `color` over the pixel at (x, y), scaled by `coverage` (0.0 to 1.0)
+fn blend(img: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= img.width() as i64 || y >= img.height() as i64 {
+        return;
+    }
+    let src_a = color[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    if src_a <= 0.0 {
+        return;
+    }
+    let dst = img.get_pixel_mut(x as u32, y as u32);
+    if src_a >= 1.0 {
+        *dst = color;
+        return;
+    }
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    for c in 0..3 {
+        let value = (color[c] as f32 * src_a + dst[c] as f32 * dst_a * (1.0 - src_a)) / out_a;
+        dst[c] = value.round() as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+/// Pixels x0..=x1 on row y
+fn fill_span(img: &mut RgbaImage, y: i64, x0: i64, x1: i64, color: Rgba<u8>) {
+    if y < 0 || y >= img.height() as i64 {
+        return;
+    }
+    for x in x0.max(0)..=x1.min(img.width() as i64 - 1) {
+        blend(img, x, y, color, 1.0);
+    }
+}
+
+fn fill_rect(img: &mut RgbaImage, x: i64, y: i64, width: i64, height: i64, color: Rgba<u8>) {
+    for row in y.max(0)..(y + height).min(img.height() as i64) {
+        fill_span(img, row, x, x + width - 1, color);
+    }
+}
+
+/// Even-odd scanline fill
+fn fill_polygon(img: &mut RgbaImage, points: &[(f64, f64)], color: Rgba<u8>) {
+    if points.len() < 3 {
+        return;
+    }
+    let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).floor().max(0.0) as i64;
+    let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil().min(img.height() as f64) as i64;
+    let mut crossings = Vec::new();
+    for y in min_y..max_y {
+        let yc = y as f64 + 0.5;
+        crossings.clear();
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            if (y1 <= yc && yc < y2) || (y2 <= yc && yc < y1) {
+                crossings.push(x1 + (yc - y1) / (y2 - y1) * (x2 - x1));
+            }
+        }
+        crossings.sort_by(|a, b| a.total_cmp(b));
+        for pair in crossings.chunks_exact(2) {
+            let start = (pair[0] - 0.5).ceil() as i64;
+            let end = (pair[1] - 0.5).ceil() as i64 - 1;
+            fill_span(img, y, start, end, color);
+        }
+    }
+}
+
+/// A one pixel wide line between pixel centers (Bresenham)
+fn thin_line(img: &mut RgbaImage, from: (i64, i64), to: (i64, i64), color: Rgba<u8>) {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let sx = if x < to.0 { 1 } else { -1 };
+    let sy = if y < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        blend(img, x, y, color, 1.0);
+        if (x, y) == to {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+fn draw_line(img: &mut RgbaImage, from: (i64, i64), to: (i64, i64), stroke: f64, color: Rgba<u8>) {
+    if stroke <= 1.0 || from == to {
+        thin_line(img, from, to, color);
+        return;
+    }
+    // A quad around the segment through the pixel centers
+    let (x1, y1) = (from.0 as f64 + 0.5, from.1 as f64 + 0.5);
+    let (x2, y2) = (to.0 as f64 + 0.5, to.1 as f64 + 0.5);
+    let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+    let (nx, ny) = (-(y2 - y1) / length * stroke / 2.0, (x2 - x1) / length * stroke / 2.0);
+    fill_polygon(img, &[(x1 + nx, y1 + ny), (x2 + nx, y2 + ny), (x2 - nx, y2 - ny), (x1 - nx, y1 - ny)], color);
+}
+
+/// Pixels whose offset from the center pixel is inside the ellipse; with a
+/// stroke, only those outside the ellipse shrunk by the stroke
+fn draw_ellipse(img: &mut RgbaImage, cx: i64, cy: i64, rx: f64, ry: f64, stroke: Option<f64>, color: Rgba<u8>) {
+    let half_span = |dy: f64, rx: f64, ry: f64| {
+        if ry <= 0.0 {
+            if dy == 0.0 { Some(rx) } else { None }
+        } else if dy.abs() <= ry {
+            Some(rx * (1.0 - (dy / ry).powi(2)).max(0.0).sqrt())
+        } else {
+            None
+        }
+    };
+    let inner = stroke.map(|s| (rx - s, ry - s)).filter(|(irx, iry)| *irx >= 0.0 && *iry >= 0.0);
+    for dy in -(ry.floor() as i64)..=ry.floor() as i64 {
+        let Some(outer) = half_span(dy as f64, rx, ry) else { continue };
+        let (left, right) = (cx - outer.floor() as i64, cx + outer.floor() as i64);
+        match inner.and_then(|(irx, iry)| half_span(dy as f64, irx, iry)) {
+            // Pixels inside the inner ellipse are skipped
+            Some(h) => {
+                let gap = h.floor() as i64;
+                fill_span(img, cy + dy, left, cx - gap - 1, color);
+                fill_span(img, cy + dy, cx + gap + 1, right, color);
+            }
+            None => fill_span(img, cy + dy, left, right, color),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Text
+
+const BITMAP_GLYPH: i64 = 8;
+
+enum TextFont {
+    /// font8x8 scaled by an integer factor
+    Bitmap(i64),
+    Outline(Box<FontVec>, f32),
+}
+
+fn font_arg(method: &str, options: &Option<Box<QDict>>) -> Result<TextFont, String> {
+    let size = match options.as_ref().and_then(|o| o.get("size")) {
+        None | Some(QValue::Nil(_)) => DEFAULT_TEXT_SIZE,
+        Some(value) => num_arg(method, "size", &value)?,
+    };
+    if size <= 0.0 {
+        return value_err!("{} size must be positive, got {}", method, size);
+    }
+    let data = match options.as_ref().and_then(|o| o.get("font")) {
+        None | Some(QValue::Nil(_)) => return Ok(TextFont::Bitmap(((size / BITMAP_GLYPH as f64).round() as i64).max(1))),
+        Some(QValue::Bytes(b)) => b.data.clone(),
+        Some(QValue::Str(path)) => std::fs::read(path.value.as_str()).map_err(|e| format!("IOErr: Cannot read font {}: {}", path.value, e))?,
+        Some(other) => return type_err!("{} font must be a file path or Bytes, got {}", method, other.as_obj().cls()),
+    };
+    let font = FontVec::try_from_vec(data).map_err(|_| format!("ValueErr: {} font is not a TrueType or OpenType font", method))?;
+    Ok(TextFont::Outline(Box::new(font), size as f32))
+}
+
+fn bitmap_glyph(c: char) -> [u8; 8] {
+    BASIC_FONTS.get(c).or_else(|| LATIN_FONTS.get(c)).or_else(|| BASIC_FONTS.get('?')).unwrap_or([0; 8])
+}
+
+/// Width and height of the text's bounding box
+fn measure_text(text: &str, font: &TextFont) -> (i64, i64) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    match font {
+        TextFont::Bitmap(scale) => {
+            let widest = lines.iter().map(|line| line.chars().count() as i64).max().unwrap_or(0);
+            let height = lines.len() as i64 * BITMAP_GLYPH * scale + (lines.len() as i64 - 1) * 2 * scale;
+            (widest * BITMAP_GLYPH * scale, height)
+        }
+        TextFont::Outline(font, size) => {
+            let scaled = font.as_scaled(PxScale::from(*size));
+            let widest = lines.iter().map(|line| line_width(&scaled, line)).fold(0.0, f32::max);
+            let line_height = scaled.ascent() - scaled.descent();
+            let height = lines.len() as f32 * line_height + (lines.len() as f32 - 1.0) * scaled.line_gap();
+            (widest.ceil() as i64, height.ceil() as i64)
+        }
+    }
+}
+
+fn line_width<F: Font>(scaled: &ab_glyph::PxScaleFont<F>, line: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in line.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            width += scaled.kern(prev, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Draw text with its top-left corner at (x, y)
+fn draw_text(img: &mut RgbaImage, x: i64, y: i64, text: &str, font: &TextFont, color: Rgba<u8>) {
+    match font {
+        TextFont::Bitmap(scale) => {
+            let line_height = (BITMAP_GLYPH + 2) * scale;
+            for (row, line) in text.split('\n').enumerate() {
+                let top = y + row as i64 * line_height;
+                for (col, c) in line.chars().enumerate() {
+                    let left = x + col as i64 * BITMAP_GLYPH * scale;
+                    // One byte per glyph row, bit 0 is the leftmost pixel
+                    for (gy, bits) in bitmap_glyph(c).iter().enumerate() {
+                        for gx in 0..BITMAP_GLYPH {
+                            if bits & (1 << gx) != 0 {
+                                fill_rect(img, left + gx * scale, top + gy as i64 * scale, *scale, *scale, color);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        TextFont::Outline(font, size) => {
+            let scaled = font.as_scaled(PxScale::from(*size));
+            let line_height = scaled.ascent() - scaled.descent() + scaled.line_gap();
+            for (row, line) in text.split('\n').enumerate() {
+                let baseline = y as f32 + scaled.ascent() + row as f32 * line_height;
+                let mut caret = x as f32;
+                let mut previous = None;
+                for c in line.chars() {
+                    let id = scaled.glyph_id(c);
+                    if let Some(prev) = previous {
+                        caret += scaled.kern(prev, id);
+                    }
+                    let glyph = id.with_scale_and_position(*size, point(caret, baseline));
+                    caret += scaled.h_advance(id);
+                    previous = Some(id);
+                    if let Some(outlined) = font.outline_glyph(glyph) {
+                        let bounds = outlined.px_bounds();
+                        outlined.draw(|gx, gy, coverage| {
+                            blend(img, bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64, color, coverage);
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Transforms
+
+fn resized_dimensions(method: &str, src: (u32, u32), width: Option<QValue>, height: Option<QValue>) -> Result<(u32, u32), String> {
+    let (w, h) = (src.0 as f64, src.1 as f64);
+    let size = match (width, height) {
+        (Some(width), Some(height)) => (dimension_arg(method, "width", &width)?, dimension_arg(method, "height", &height)?),
+        (Some(width), None) => {
+            let width = dimension_arg(method, "width", &width)?;
+            (width, ((width as f64 * h / w).round() as u32).max(1))
+        }
+        (None, Some(height)) => {
+            let height = dimension_arg(method, "height", &height)?;
+            (((height as f64 * w / h).round() as u32).max(1), height)
+        }
+        (None, None) => return arg_err!("{} requires a width or a height", method),
+    };
+    check_pixels(method, size.0, size.1)?;
+    Ok(size)
+}
+
+/// Bilinear sample at a pixel-center position, in premultiplied alpha so
+/// transparent neighbors do not darken the edges
+fn sample(img: &RgbaImage, x: f64, y: f64, background: Rgba<u8>) -> Rgba<u8> {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let mut sum = [0.0f64; 4];
+    for (dx, dy, weight) in [(0, 0, (1.0 - fx) * (1.0 - fy)), (1, 0, fx * (1.0 - fy)), (0, 1, (1.0 - fx) * fy), (1, 1, fx * fy)] {
+        let (px, py) = (x0 as i64 + dx, y0 as i64 + dy);
+        let pixel = if px >= 0 && py >= 0 && px < img.width() as i64 && py < img.height() as i64 {
+            *img.get_pixel(px as u32, py as u32)
+        } else {
+            background
+        };
+        let alpha = pixel[3] as f64 / 255.0;
+        for c in 0..3 {
+            sum[c] += pixel[c] as f64 * alpha * weight;
+        }
+        sum[3] += alpha * weight;
+    }
+    if sum[3] <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    Rgba([
+        (sum[0] / sum[3]).round().clamp(0.0, 255.0) as u8,
+        (sum[1] / sum[3]).round().clamp(0.0, 255.0) as u8,
+        (sum[2] / sum[3]).round().clamp(0.0, 255.0) as u8,
+        (sum[3] * 255.0).round().clamp(0.0, 255.0) as u8,
+    ])
+}
+
+/// Rotate clockwise; the canvas grows to fit unless `expand` is false
+fn rotate(img: &RgbaImage, degrees: f64, expand: bool, background: Rgba<u8>) -> RgbaImage {
+    let turn = degrees.rem_euclid(360.0);
+    let quarters = (turn / 90.0).round();
+    // Quarter turns are exact, unless a non-square image must keep its shape
+    if (turn / 90.0 - quarters).abs() < 1e-9 && (expand || quarters as i64 % 2 == 0 || img.width() == img.height()) {
+        return match quarters as i64 % 4 {
+            1 => imageops::rotate90(img),
+            2 => imageops::rotate180(img),
+            3 => imageops::rotate270(img),
+            _ => img.clone(),
+        };
+    }
+    let (sin, cos) = turn.to_radians().sin_cos();
+    let (w, h) = (img.width() as f64, img.height() as f64);
+    let (out_w, out_h) = if expand {
+        (((w * cos.abs() + h * sin.abs()) - 1e-6).ceil().max(1.0) as u32, ((w * sin.abs() + h * cos.abs()) - 1e-6).ceil().max(1.0) as u32)
+    } else {
+        img.dimensions()
+    };
+    RgbaImage::from_fn(out_w, out_h, |ox, oy| {
+        // Map each output pixel center back into the source
+        let dx = ox as f64 + 0.5 - out_w as f64 / 2.0;
+        let dy = oy as f64 + 0.5 - out_h as f64 / 2.0;
+        let sx = dx * cos + dy * sin + w / 2.0;
+        let sy = -dx * sin + dy * cos + h / 2.0;
+        sample(img, sx - 0.5, sy - 0.5, background)
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Image
+
+impl QImage {
+    pub fn new(pixels: RgbaImage, format: Option<&'static str>) -> Self {
+        QImage {
+            data: Rc::new(RefCell::new(ImageData { pixels, format })),
+            id: next_object_id(),
+        }
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.data.borrow().pixels.dimensions()
+    }
+
+    fn new_image(&self, pixels: RgbaImage) -> QValue {
+        image_value(pixels, self.data.borrow().format)
+    }
+
+    fn point_arg(&self, method: &str, x: &QValue, y: &QValue) -> Result<(u32, u32), String> {
+        let (x, y) = (coord_arg(method, "x", x)?, coord_arg(method, "y", y)?);
+        let (width, height) = self.dimensions();
+        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            return index_err!("{} ({}, {}) is outside the {}x{} image", method, x, y, width, height);
+        }
+        Ok((x as u32, y as u32))
+    }
+
+    pub fn call_method(&self, method_name: &str, mut args: Vec<QValue>) -> Result<QValue, EvalError> {
+        // Try QObj trait methods first
+        use crate::types::try_call_qobj_method;
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        let options = take_options(&mut args);
+        let args = args.as_slice();
+        match method_name {
+            "width" | "height" => {
+                check_count(method_name, args, 0, 0, "")?;
+                let (width, height) = self.dimensions();
+                Ok(QValue::Int(QInt::new(if method_name == "width" { width } else { height } as i64)))
+            }
+            "size" => {
+                check_count(method_name, args, 0, 0, "")?;
+                let (width, height) = self.dimensions();
+                Ok(QValue::Array(QArray::new(vec![QValue::Int(QInt::new(width as i64)), QValue::Int(QInt::new(height as i64))])))
+            }
+            "format" => {
+                check_count(method_name, args, 0, 0, "")?;
+                Ok(match self.data.borrow().format {
+                    Some(format) => QValue::Str(QString::new(format.to_string())),
+                    None => QValue::Nil(QNil),
+                })
+            }
+            "save" => {
+                // save(path, format: nil, quality: 85) - format defaults to the extension
+                check_count(method_name, args, 1, 1, "path")?;
+                let path = args[0].as_str();
+                let format = match options.as_ref().and_then(|o| o.get("format")) {
+                    Some(QValue::Str(f)) => format_name("save", &f.value)?,
+                    _ => match std::path::Path::new(&path).extension() {
+                        Some(ext) => format_name("save", &ext.to_string_lossy())?,
+                        None => return value_err!("save cannot tell the format of '{}'; use a .png or .jpg extension or format:", path),
+                    },
+                };
+                let bytes = encode(&self.data.borrow().pixels, format, quality_arg("save", &options)?)?;
+                std::fs::write(&path, bytes).map_err(|e| format!("IOErr: Cannot write {}: {}", path, e))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "encode" => {
+                // encode(format = "png", quality: 85) -> Bytes
+                check_count(method_name, args, 0, 1, "format")?;
+                let format = match arg(args, &options, 0, "format") {
+                    Some(f) => format_name("encode", &f.as_str())?,
+                    None => "png",
+                };
+                let bytes = encode(&self.data.borrow().pixels, format, quality_arg("encode", &options)?)?;
+                Ok(QValue::Bytes(QBytes::new(bytes)))
+            }
+            "pixels" => {
+                // Raw RGBA bytes, row by row
+                check_count(method_name, args, 0, 0, "")?;
+                Ok(QValue::Bytes(QBytes::new(self.data.borrow().pixels.as_raw().clone())))
+            }
+            "copy" => {
+                check_count(method_name, args, 0, 0, "")?;
+                Ok(self.new_image(self.data.borrow().pixels.clone()))
+            }
+            "resize" => {
+                // resize(width, height = nil, filter: "lanczos") - a missing side keeps the aspect ratio
+                check_count(method_name, args, 0, 3, "width, height, filter")?;
+                let size = resized_dimensions("resize", self.dimensions(), arg(args, &options, 0, "width"), arg(args, &options, 1, "height"))?;
+                let filter = filter_arg("resize", arg(args, &options, 2, "filter"))?;
+                let resized = imageops::resize(&self.data.borrow().pixels, size.0, size.1, filter);
+                Ok(self.new_image(resized))
+            }
+            "thumbnail" => {
+                // thumbnail(max_width, max_height = max_width) - fit inside the box, never enlarge
+                check_count(method_name, args, 1, 2, "max_width, max_height")?;
+                let max_width = dimension_arg("thumbnail", "max_width", &args[0])?;
+                let max_height = match arg(args, &options, 1, "max_height") {
+                    Some(h) => dimension_arg("thumbnail", "max_height", &h)?,
+                    None => max_width,
+                };
+                let (width, height) = self.dimensions();
+                let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64).min(1.0);
+                let size = (((width as f64 * scale).round() as u32).max(1), ((height as f64 * scale).round() as u32).max(1));
+                let data = self.data.borrow();
+                if size == (width, height) {
+                    return Ok(self.new_image(data.pixels.clone()));
+                }
+                Ok(self.new_image(imageops::thumbnail(&data.pixels, size.0, size.1)))
+            }
+            "cover" => {
+                // cover(width, height) - scale to fill exactly, cropping the overflow around the center
+                check_count(method_name, args, 2, 2, "width, height")?;
+                let target = (dimension_arg("cover", "width", &args[0])?, dimension_arg("cover", "height", &args[1])?);
+                check_pixels("cover", target.0, target.1)?;
+                let (width, height) = self.dimensions();
+                let scale = (target.0 as f64 / width as f64).max(target.1 as f64 / height as f64);
+                let scaled = (((width as f64 * scale).round() as u32).max(target.0), ((height as f64 * scale).round() as u32).max(target.1));
+                let resized = imageops::resize(&self.data.borrow().pixels, scaled.0, scaled.1, FilterType::Lanczos3);
+                let (x, y) = ((scaled.0 - target.0) / 2, (scaled.1 - target.1) / 2);
+                Ok(self.new_image(imageops::crop_imm(&resized, x, y, target.0, target.1).to_image()))
+            }
+            "crop" => {
+                // crop(x, y, width, height) - clipped to the image
+                check_count(method_name, args, 4, 4, "x, y, width, height")?;
+                let x = coord_arg("crop", "x", &args[0])?;
+                let y = coord_arg("crop", "y", &args[1])?;
+                let right = x + dimension_arg("crop", "width", &args[2])? as i64;
+                let bottom = y + dimension_arg("crop", "height", &args[3])? as i64;
+                let (width, height) = self.dimensions();
+                let (left, top) = (x.max(0), y.max(0));
+                let (right, bottom) = (right.min(width as i64), bottom.min(height as i64));
+                if left >= right || top >= bottom {
+                    return value_err!("crop region is outside the {}x{} image", width, height);
+                }
+                let cropped = imageops::crop_imm(&self.data.borrow().pixels, left as u32, top as u32, (right - left) as u32, (bottom - top) as u32).to_image();
+                Ok(self.new_image(cropped))
+            }
+            "rotate" => {
+                // rotate(degrees, expand: true, background: "transparent") - clockwise
+                check_count(method_name, args, 1, 1, "degrees")?;
+                let degrees = num_arg("rotate", "degrees", &args[0])?;
+                let expand = options.as_ref().and_then(|o| o.get("expand")).is_none_or(|v| v.as_bool());
+                let background = color_arg(options.as_ref().and_then(|o| o.get("background")), "transparent")?;
+                Ok(self.new_image(rotate(&self.data.borrow().pixels, degrees, expand, background)))
+            }
+            "flip_horizontal" | "flip_vertical" => {
+                check_count(method_name, args, 0, 0, "")?;
+                let data = self.data.borrow();
+                let flipped = if method_name == "flip_horizontal" {
+                    imageops::flip_horizontal(&data.pixels)
+                } else {
+                    imageops::flip_vertical(&data.pixels)
+                };
+                Ok(self.new_image(flipped))
+            }
+            "grayscale" => {
+                check_count(method_name, args, 0, 0, "")?;
+                let gray = DynamicImage::ImageRgba8(self.data.borrow().pixels.clone()).grayscale().to_rgba8();
+                Ok(self.new_image(gray))
+            }
+            "get_pixel" => {
+                check_count(method_name, args, 2, 2, "x, y")?;
+                let (x, y) = self.point_arg("get_pixel", &args[0], &args[1])?;
+                Ok(color_value(self.data.borrow().pixels.get_pixel(x, y)))
+            }
+            "set_pixel" => {
+                // Replaces the pixel, alpha included
+                check_count(method_name, args, 3, 3, "x, y, color")?;
+                let (x, y) = self.point_arg("set_pixel", &args[0], &args[1])?;
+                let color = parse_color(&args[2])?;
+                self.data.borrow_mut().pixels.put_pixel(x, y, color);
+                Ok(QValue::Image(self.clone()))
+            }
+            "fill" => {
+                // Replaces every pixel, so fill("transparent") clears the image
+                check_count(method_name, args, 1, 1, "color")?;
+                let color = parse_color(&args[0])?;
+                self.data.borrow_mut().pixels.pixels_mut().for_each(|p| *p = color);
+                Ok(QValue::Image(self.clone()))
+            }
+            "paste" => {
+                // paste(other, x, y) - alpha-blended; other may hang off the edges
+                check_count(method_name, args, 3, 3, "image, x, y")?;
+                let QValue::Image(other) = &args[0] else {
+                    return type_err!("paste expects an Image, got {}", args[0].as_obj().cls());
+                };
+                let top = other.data.borrow().pixels.clone();
+                let (x, y) = (coord_arg("paste", "x", &args[1])?, coord_arg("paste", "y", &args[2])?);
+                imageops::overlay(&mut self.data.borrow_mut().pixels, &top, x, y);
+                Ok(QValue::Image(self.clone()))
+            }
+            "draw_line" => {
+                // draw_line(x1, y1, x2, y2, color = "black", stroke: 1)
+                check_count(method_name, args, 4, 5, "x1, y1, x2, y2, color")?;
+                let from = (coord_arg(method_name, "x1", &args[0])?, coord_arg(method_name, "y1", &args[1])?);
+                let to = (coord_arg(method_name, "x2", &args[2])?, coord_arg(method_name, "y2", &args[3])?);
+                let color = color_arg(arg(args, &options, 4, "color"), "black")?;
+                let stroke = stroke_arg(method_name, &options)?;
+                draw_line(&mut self.data.borrow_mut().pixels, from, to, stroke, color);
+                Ok(QValue::Image(self.clone()))
+            }
+            "draw_rect" => {
+                // draw_rect(x, y, width, height, color = "black", fill: false, stroke: 1) - strokes run inside the box
+                check_count(method_name, args, 4, 5, "x, y, width, height, color")?;
+                let x = coord_arg(method_name, "x", &args[0])?;
+                let y = coord_arg(method_name, "y", &args[1])?;
+                let width = dimension_arg(method_name, "width", &args[2])? as i64;
+                let height = dimension_arg(method_name, "height", &args[3])? as i64;
+                let color = color_arg(arg(args, &options, 4, "color"), "black")?;
+                let stroke = stroke_arg(method_name, &options)?.round().max(1.0) as i64;
+                let img = &mut self.data.borrow_mut().pixels;
+                if fill_arg(&options) || stroke * 2 >= width.min(height) {
+                    fill_rect(img, x, y, width, height, color);
+                } else {
+                    fill_rect(img, x, y, width, stroke, color);
+                    fill_rect(img, x, y + height - stroke, width, stroke, color);
+                    fill_rect(img, x, y + stroke, stroke, height - 2 * stroke, color);
+                    fill_rect(img, x + width - stroke, y + stroke, stroke, height - 2 * stroke, color);
+                }
+                Ok(QValue::Image(self.clone()))
+            }
+            "draw_circle" | "draw_ellipse" => {
+                // draw_circle(cx, cy, radius, color = "black", fill: false, stroke: 1)
+                // draw_ellipse(cx, cy, rx, ry, color = "black", fill: false, stroke: 1)
+                let radii = if method_name == "draw_circle" { 1 } else { 2 };
+                let usage = if radii == 1 { "cx, cy, radius, color" } else { "cx, cy, rx, ry, color" };
+                check_count(method_name, args, 2 + radii, 3 + radii, usage)?;
+                let cx = coord_arg(method_name, "cx", &args[0])?;
+                let cy = coord_arg(method_name, "cy", &args[1])?;
+                let rx = num_arg(method_name, "radius", &args[2])?;
+                let ry = num_arg(method_name, "radius", &args[1 + radii])?;
+                if rx < 0.0 || ry < 0.0 {
+                    return value_err!("{} radius cannot be negative", method_name);
+                }
+                let color = color_arg(arg(args, &options, 2 + radii, "color"), "black")?;
+                let stroke = if fill_arg(&options) { None } else { Some(stroke_arg(method_name, &options)?) };
+                draw_ellipse(&mut self.data.borrow_mut().pixels, cx, cy, rx, ry, stroke, color);
+                Ok(QValue::Image(self.clone()))
+            }
+            "draw_polygon" | "draw_polyline" => {
+                // draw_polygon(points, color = "black", fill: false, stroke: 1) - points is [[x, y], ...]
+                // draw_polyline(points, color = "black", stroke: 1) - the same, left open
+                check_count(method_name, args, 1, 2, "points, color")?;
+                let points = points_arg(method_name, &args[0])?;
+                let color = color_arg(arg(args, &options, 1, "color"), "black")?;
+                let img = &mut self.data.borrow_mut().pixels;
+                let closed = method_name == "draw_polygon";
+                if closed && fill_arg(&options) {
+                    fill_polygon(img, &points, color);
+                    return Ok(QValue::Image(self.clone()));
+                }
+                let stroke = stroke_arg(method_name, &options)?;
+                let corners: Vec<(i64, i64)> = points.iter().map(|(x, y)| (x.round() as i64, y.round() as i64)).collect();
+                let segments = if closed { corners.len() } else { corners.len() - 1 };
+                for i in 0..segments {
+                    draw_line(img, corners[i], corners[(i + 1) % corners.len()], stroke, color);
+                }
+                if !closed && stroke > 2.0 {
+                    // Round joins, so thick bends have no notches
+                    for &(x, y) in &corners[1..corners.len() - 1] {
+                        draw_ellipse(img, x, y, stroke / 2.0, stroke / 2.0, None, color);
+                    }
+                }
+                Ok(QValue::Image(self.clone()))
+            }
+            "draw_text" => {
+                // draw_text(x, y, text, color = "black", size: 16, font: nil) - (x, y) is the top-left corner
+                check_count(method_name, args, 3, 4, "x, y, text, color")?;
+                let x = coord_arg(method_name, "x", &args[0])?;
+                let y = coord_arg(method_name, "y", &args[1])?;
+                let text = args[2].as_str();
+                let color = color_arg(arg(args, &options, 3, "color"), "black")?;
+                let font = font_arg(method_name, &options)?;
+                draw_text(&mut self.data.borrow_mut().pixels, x, y, &text, &font, color);
+                Ok(QValue::Image(self.clone()))
+            }
+            _ => attr_err!("Unknown method '{}' on Image", method_name),
+        }
+    }
+}
+
+impl QObj for QImage {
+    fn cls(&self) -> String {
+        "Image".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Image"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Image"
+    }
+
+    fn str(&self) -> String {
+        let (width, height) = self.dimensions();
+        format!("Image({}x{})", width, height)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "An RGBA raster image".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_image_module() -> QValue {
+    let mut members = HashMap::new();
+    members.insert("open".to_string(), create_fn("image", "open"));
+    members.insert("decode".to_string(), create_fn("image", "decode"));
+    members.insert("new".to_string(), create_fn("image", "new"));
+    members.insert("from_pixels".to_string(), create_fn("image", "from_pixels"));
+    members.insert("text_size".to_string(), create_fn("image", "text_size"));
+    QValue::Module(Box::new(QModule::new("image".to_string(), members)))
+}
+
+/// Handle image.* function calls
+pub fn call_image_function(func_name: &str, mut args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let options = take_options(&mut args);
+    let args = args.as_slice();
+    match func_name {
+        "image.open" => {
+            check_count(func_name, args, 1, 1, "path")?;
+            let path = args[0].as_str();
+            let reader = match ImageReader::open(&path) {
+                Ok(reader) => reader,
+                Err(e) => return io_err!("Cannot open {}: {}", path, e),
+            };
+            Ok(decode(reader, &path)?)
+        }
+        "image.decode" => {
+            check_count(func_name, args, 1, 1, "data")?;
+            let QValue::Bytes(bytes) = &args[0] else {
+                return type_err!("image.decode expects Bytes, got {}", args[0].as_obj().cls());
+            };
+            Ok(decode(ImageReader::new(Cursor::new(bytes.data.as_slice())), "data")?)
+        }
+        "image.new" => {
+            // new(width, height, color = "transparent")
+            check_count(func_name, args, 2, 3, "width, height, color")?;
+            let width = dimension_arg(func_name, "width", &args[0])?;
+            let height = dimension_arg(func_name, "height", &args[1])?;
+            check_pixels(func_name, width, height)?;
+            let color = color_arg(arg(args, &options, 2, "color"), "transparent")?;
+            Ok(image_value(RgbaImage::from_pixel(width, height, color), None))
+        }
+        "image.from_pixels" => {
+            // from_pixels(width, height, data) - the inverse of Image.pixels()
+            check_count(func_name, args, 3, 3, "width, height, data")?;
+            let width = dimension_arg(func_name, "width", &args[0])?;
+            let height = dimension_arg(func_name, "height", &args[1])?;
+            check_pixels(func_name, width, height)?;
+            let QValue::Bytes(bytes) = &args[2] else {
+                return type_err!("image.from_pixels expects Bytes, got {}", args[2].as_obj().cls());
+            };
+            match RgbaImage::from_raw(width, height, bytes.data.clone()) {
+                Some(pixels) if pixels.as_raw().len() == bytes.data.len() => Ok(image_value(pixels, None)),
+                _ => value_err!("image.from_pixels expects {} bytes of RGBA data for {}x{}, got {}", width as u64 * height as u64 * 4, width, height, bytes.data.len()),
+            }
+        }
+        "image.text_size" => {
+            // text_size(text, size: 16, font: nil) -> [width, height] as draw_text would paint it
+            check_count(func_name, args, 1, 1, "text")?;
+            let font = font_arg(func_name, &options)?;
+            let (width, height) = measure_text(&args[0].as_str(), &font);
+            Ok(QValue::Array(QArray::new(vec![QValue::Int(QInt::new(width)), QValue::Int(QInt::new(height))])))
+        }
+        _ => attr_err!("Unknown image function: {}", func_name),
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, name_err, type_err};
+use crate::types::*;
+use crate::Scope;
+
+pub fn create_inspect_module() -> QValue {
+    let mut members = HashMap::new();
+    members.insert("methods".to_string(), create_fn("inspect", "methods"));
+    members.insert("class_methods".to_string(), create_fn("inspect", "class_methods"));
+    members.insert("fields".to_string(), create_fn("inspect", "fields"));
+    members.insert("signature".to_string(), create_fn("inspect", "signature"));
+    members.insert("doc".to_string(), create_fn("inspect", "doc"));
+    members.insert("source".to_string(), create_fn("inspect", "source"));
+    QValue::Module(Box::new(QModule::new("inspect".to_string(), members)))
+}
+
+/// Handle inspect.* function calls
+pub fn call_inspect_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
+    let name = &func_name["inspect.".len()..];
+    match func_name {
+        "inspect.methods" | "inspect.class_methods" | "inspect.fields" => {
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument, got {}", name, args.len());
+            }
+            match name {
+                "methods" => methods(&args[0], scope),
+                "class_methods" => class_methods(&args[0]),
+                _ => fields(&args[0], scope),
+            }
+        }
+        "inspect.signature" | "inspect.doc" | "inspect.source" => {
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("{} expects 1 or 2 arguments (value, member = nil), got {}", name, args.len());
+            }
+            // With a member name, look at that method or module member instead
+            let target = match args.get(1) {
+                Some(QValue::Nil(_)) | None => args[0].clone(),
+                Some(QValue::Str(member)) => {
+                    if let (QValue::Trait(t), "signature") = (&args[0], name) {
+                        return trait_method_signature(t, &member.value);
+                    }
+                    member_function(&args[0], &member.value, scope)?
+                }
+                Some(other) => return type_err!("{} member name must be Str, got {}", name, other.q_type()),
+            };
+            match name {
+                "signature" => signature(&target),
+                "doc" => Ok(doc(&target, scope)),
+                _ => source(&target),
+            }
+        }
+        _ => name_err!("Unknown inspect function: {}", func_name)
+    }
+}
+
+fn str_value(s: &str) -> QValue {
+    QValue::Str(QString::new(s.to_string()))
+}
+
+fn opt_str(s: &Option<String>) -> QValue {
+    match s {
+        Some(s) => str_value(s),
+        None => QValue::Nil(QNil),
+    }
+}
+
+fn names_array(mut names: Vec<String>) -> QValue {
+    names.sort();
+    names.dedup();
+    QValue::Array(QArray::new(names.into_iter().map(|n| QValue::Str(QString::new(n))).collect()))
+}
+
+fn dict(map: IndexMap<String, QValue>) -> QValue {
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+/// The type an instance was created from
+fn struct_type(instance: &QStruct, scope: &Scope) -> Result<QType, EvalError> {
+    match crate::find_type_definition(&instance.type_name, scope) {
+        Some(qtype) => Ok(qtype),
+        None => name_err!("Type {} is not defined", instance.type_name),
+    }
+}
+
+/// Methods callable on `value`: instance methods of a type (or of an
+/// instance's type), a trait's required methods, or a module's functions
+fn methods(value: &QValue, scope: &Scope) -> Result<QValue, EvalError> {
+    let instance_methods = |qtype: &QType| {
+        qtype.methods.keys().filter(|n| !n.starts_with("__class__:")).cloned().collect()
+    };
+    match value {
+        QValue::Type(qtype) => Ok(names_array(instance_methods(qtype))),
+        QValue::Struct(s) => Ok(names_array(instance_methods(&struct_type(&s.borrow(), scope)?))),
+        QValue::Trait(t) => Ok(names_array(t.required_methods.iter().map(|m| m.name.clone()).collect())),
+        QValue::Module(m) => {
+            let members = m.get_members_ref();
+            let members = members.borrow();
+            let names = m.public_member_names().into_iter()
+                .filter(|n| matches!(members.get(n), Some(QValue::Fun(_) | QValue::UserFun(_))))
+                .collect();
+            Ok(names_array(names))
+        }
+        other => type_err!("inspect.methods expects a type, instance, trait or module, got {}", other.q_type()),
+    }
+}
+
+/// Class methods (`fun self.name()`) of a type
+fn class_methods(value: &QValue) -> Result<QValue, EvalError> {
+    match value {
+        QValue::Type(qtype) => Ok(names_array(
+            qtype.methods.keys().filter_map(|n| n.strip_prefix("__class__:")).map(String::from).collect()
+        )),
+        other => type_err!("inspect.class_methods expects a type, got {}", other.q_type()),
+    }
+}
+
+/// Field declarations of a type, plus current values for an instance
+fn fields(value: &QValue, scope: &Scope) -> Result<QValue, EvalError> {
+    let (qtype, instance) = match value {
+        QValue::Type(qtype) => ((**qtype).clone(), None),
+        QValue::Struct(s) => (struct_type(&s.borrow(), scope)?, Some(s.borrow())),
+        other => return type_err!("inspect.fields expects a type or instance, got {}", other.q_type()),
+    };
+    let mut result = Vec::new();
+    for field in &qtype.fields {
+        let mut map = IndexMap::new();
+        map.insert("name".to_string(), str_value(&field.name));
+        map.insert("type".to_string(), opt_str(&field.type_annotation));
+        map.insert("optional".to_string(), QValue::Bool(QBool::new(field.optional)));
+        map.insert("default".to_string(), field.default_value.clone().unwrap_or(QValue::Nil(QNil)));
+        map.insert("public".to_string(), QValue::Bool(QBool::new(field.is_public)));
+        if let Some(instance) = &instance {
+            map.insert("value".to_string(), instance.fields.get(&field.name).cloned().unwrap_or(QValue::Nil(QNil)));
+        }
+        result.push(dict(map));
+    }
+    Ok(QValue::Array(QArray::new(result)))
+}
+
+/// The method or module member `name` of `target`
+fn member_function(target: &QValue, name: &str, scope: &Scope) -> Result<QValue, EvalError> {
+    let from_type = |qtype: &QType| {
+        qtype.methods.get(name)
+            .or_else(|| qtype.methods.get(&format!("__class__:{}", name)))
+            .map(|f| QValue::UserFun(Box::new(f.clone())))
+    };
+    let found = match target {
+        QValue::Type(qtype) => from_type(qtype),
+        QValue::Struct(s) => from_type(&struct_type(&s.borrow(), scope)?),
+        QValue::Module(m) if m.is_public(name) => m.get_member(name),
+        QValue::Module(_) => None,
+        other => return type_err!("Can't look up member '{}' of {}", name, other.q_type()),
+    };
+    match found {
+        Some(f) => Ok(f),
+        None => attr_err!("{} has no member '{}'", target.as_obj().str(), name),
+    }
+}
+
+fn param_dict(name: &str, kind: &str, type_annotation: &Option<String>, default: &Option<String>) -> QValue {
+    let mut map = IndexMap::new();
+    map.insert("name".to_string(), str_value(name));
+    map.insert("kind".to_string(), str_value(kind));
+    map.insert("type".to_string(), opt_str(type_annotation));
+    map.insert("default".to_string(), opt_str(default));
+    map.insert("required".to_string(), QValue::Bool(QBool::new(kind == "positional" && default.is_none())));
+    dict(map)
+}
+
+fn signature_dict(name: QValue, params: Option<Vec<QValue>>, return_type: &Option<String>, builtin: bool) -> QValue {
+    let mut map = IndexMap::new();
+    map.insert("name".to_string(), name);
+    map.insert("params".to_string(), match params {
+        Some(params) => QValue::Array(QArray::new(params)),
+        None => QValue::Nil(QNil),
+    });
+    map.insert("return_type".to_string(), opt_str(return_type));
+    map.insert("builtin".to_string(), QValue::Bool(QBool::new(builtin)));
+    dict(map)
+}
+
+/// Parameters of a function: names, kinds, type annotations and default
+/// expressions (as source text)
+fn signature(value: &QValue) -> Result<QValue, EvalError> {
+    match value {
+        QValue::UserFun(f) => {
+            let mut params = Vec::new();
+            for (i, name) in f.params.iter().enumerate() {
+                let type_annotation = f.param_types.get(i).cloned().flatten();
+                let default = f.param_defaults.get(i).cloned().flatten();
+                params.push(param_dict(name, "positional", &type_annotation, &default));
+            }
+            if let Some(name) = &f.varargs {
+                params.push(param_dict(name, "varargs", &f.varargs_type, &None));
+            }
+            if let Some(name) = &f.kwargs {
+                params.push(param_dict(name, "kwargs", &f.kwargs_type, &None));
+            }
+            Ok(signature_dict(opt_str(&f.name), Some(params), &f.return_type, false))
+        }
+        // Built-in functions check their arguments themselves
+        QValue::Fun(f) => Ok(signature_dict(str_value(&f.name), None, &None, true)),
+        other => type_err!("inspect.signature expects a function, got {}", other.q_type()),
+    }
+}
+
+fn trait_method_signature(t: &QTrait, name: &str) -> Result<QValue, EvalError> {
+    let Some(method) = t.required_methods.iter().find(|m| m.name == name) else {
+        return attr_err!("Trait {} has no method '{}'", t.name, name);
+    };
+    let params = method.parameters.iter()
+        .map(|p| param_dict(p, "positional", &None, &None))
+        .collect();
+    Ok(signature_dict(str_value(&method.name), Some(params), &method.return_type, false))
+}
+
+/// Docstring of a function, type, trait, instance or module; nil if it has none
+fn doc(value: &QValue, scope: &Scope) -> QValue {
+    let doc = match value {
+        QValue::UserFun(f) => f.doc.clone(),
+        QValue::Type(t) => t.doc.clone(),
+        QValue::Trait(t) => t.doc.clone(),
+        QValue::Module(m) => m.doc.clone(),
+        QValue::Struct(s) => struct_type(&s.borrow(), scope).ok().and_then(|t| t.doc),
+        QValue::Fun(f) => Some(crate::doc::load_doc_from_overlay(&f.parent_type, &f.name)),
+        _ => None,
+    };
+    match doc {
+        Some(doc) if !doc.trim().is_empty() => QValue::Str(QString::new(doc)),
+        _ => QValue::Nil(QNil),
+    }
+}
+
+/// Definition text of a Quest function, dedented to column 0
+fn source(value: &QValue) -> Result<QValue, EvalError> {
+    let f = match value {
+        QValue::UserFun(f) => f,
+        QValue::Fun(f) => return type_err!("{} is built in and has no Quest source", f.str()),
+        other => return type_err!("inspect.source expects a function, got {}", other.q_type()),
+    };
+    let Some(text) = &f.source else {
+        return type_err!("No source recorded for {}", f.str());
+    };
+    // The first line starts at `fun`; later lines keep the file's indentation,
+    // which the closing `end` shows
+    let lines: Vec<&str> = text.lines().collect();
+    let indent = lines.last()
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .unwrap_or("");
+    let dedented: Vec<&str> = lines.iter().enumerate()
+        .map(|(i, l)| if i == 0 { l } else { l.strip_prefix(indent).unwrap_or(l) })
+        .collect();
+    Ok(QValue::Str(QString::new(dedented.join("\n"))))
+}
@@ -0,0 +1,700 @@
+// std/jobs - background jobs for `quest serve`
+//
+// A job is the name of a top-level function plus its arguments encoded as
+// JSON. Quest values are Rc-based and can't move between threads, so a
+// worker looks the function up by name in its own scope: under `web.run()`
+// that is the per-thread copy of the script that request handlers already
+// use (src/server.rs starts the job threads).
+//
+// Jobs enqueued while a request is being handled are held until the handler
+// returns, so they never delay the response and are dropped if the request
+// fails. Outside the server, jobs.work() runs due jobs on the calling thread.
+// The queue lives in memory unless configure(store:) points it at a SQLite
+// file, which survives restarts and is shared by every server process.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use ordered_float::OrderedFloat;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+
+use crate::control_flow::EvalError;
+use crate::function_call::{call_value, CallArguments};
+use crate::modules::encoding::json_utils::{json_to_qvalue, qvalue_to_json};
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+const DEFAULT_WORKERS: usize = 2;
+const DEFAULT_BACKOFF: f64 = 1.0;
+/// Finished in-memory jobs kept for status()
+const FINISHED_LIMIT: usize = 1000;
+/// A SQLite job still marked running this long after it was claimed is
+/// assumed lost with its process and runs again
+const LEASE_SECS: f64 = 600.0;
+/// How often idle workers look for jobs added to a SQLite store by other processes
+const POLL_SECS: f64 = 1.0;
+/// Longest an idle worker sleeps on an in-memory queue between checks
+const IDLE_SECS: f64 = 60.0;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS quest_jobs (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        args TEXT NOT NULL,
+        kwargs TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        retries INTEGER NOT NULL,
+        backoff REAL NOT NULL,
+        run_at REAL NOT NULL,
+        locked_until REAL,
+        error TEXT,
+        created_at REAL NOT NULL,
+        finished_at REAL
+    );
+    CREATE INDEX IF NOT EXISTS quest_jobs_due ON quest_jobs (status, run_at);
+";
+
+const JOB_COLUMNS: &str = "id, name, args, kwargs, attempts, retries, backoff, run_at";
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    id: String,
+    name: String,
+    /// JSON array of positional arguments
+    args: String,
+    /// JSON object of keyword arguments
+    kwargs: String,
+    attempts: i64,
+    retries: i64,
+    backoff: f64,
+    run_at: f64,
+}
+
+impl Job {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Job {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            args: row.get(2)?,
+            kwargs: row.get(3)?,
+            attempts: row.get(4)?,
+            retries: row.get(5)?,
+            backoff: row.get(6)?,
+            run_at: row.get(7)?,
+        })
+    }
+}
+
+/// What happened to a claimed job
+enum Outcome {
+    Done,
+    Retry(f64, String),
+    Failed(String),
+}
+
+struct Record {
+    job: Job,
+    status: &'static str,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    records: HashMap<String, Record>,
+    /// Queued job ids by (run_at, insertion order)
+    queued: BTreeMap<(OrderedFloat<f64>, u64), String>,
+    seq: u64,
+    /// Finished job ids, oldest first
+    finished: VecDeque<String>,
+}
+
+impl MemoryStore {
+    fn queue(&mut self, job: &Job) {
+        self.seq += 1;
+        self.queued.insert((OrderedFloat(job.run_at), self.seq), job.id.clone());
+    }
+
+    fn retire(&mut self, id: &str) {
+        self.finished.push_back(id.to_string());
+        while self.finished.len() > FINISHED_LIMIT {
+            if let Some(old) = self.finished.pop_front() {
+                self.records.remove(&old);
+            }
+        }
+    }
+}
+
+enum Store {
+    Memory(MemoryStore),
+    Sqlite { path: String, conn: Connection },
+}
+
+fn sql_err(e: rusqlite::Error) -> String {
+    format!("IOErr: jobs store: {}", e)
+}
+
+impl Store {
+    fn open(path: &str) -> Result<Store, String> {
+        let conn = Connection::open(path).map_err(sql_err)?;
+        conn.busy_timeout(Duration::from_secs(5)).map_err(sql_err)?;
+        conn.execute_batch(SCHEMA).map_err(sql_err)?;
+        Ok(Store::Sqlite { path: path.to_string(), conn })
+    }
+
+    fn push(&mut self, job: Job) -> Result<(), String> {
+        match self {
+            Store::Memory(m) => {
+                m.queue(&job);
+                m.records.insert(job.id.clone(), Record { job, status: "queued", error: None });
+            }
+            Store::Sqlite { conn, .. } => {
+                conn.execute(
+                    "INSERT INTO quest_jobs (id, name, args, kwargs, status, retries, backoff, run_at, created_at)
+                     VALUES (?1, ?2, ?3, ?4, 'queued', ?5, ?6, ?7, ?8)",
+                    params![job.id, job.name, job.args, job.kwargs, job.retries, job.backoff, job.run_at, now()],
+                ).map_err(sql_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the earliest due job, marking it running
+    fn claim(&mut self, now: f64) -> Result<Option<Job>, String> {
+        match self {
+            Store::Memory(m) => {
+                let due = matches!(m.queued.first_key_value(), Some(((run_at, _), _)) if run_at.0 <= now);
+                let Some((_, id)) = due.then(|| m.queued.pop_first()).flatten() else {
+                    return Ok(None);
+                };
+                let Some(record) = m.records.get_mut(&id) else {
+                    return Ok(None);
+                };
+                record.status = "running";
+                record.job.attempts += 1;
+                Ok(Some(record.job.clone()))
+            }
+            Store::Sqlite { conn, .. } => {
+                let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_err)?;
+                let id: Option<String> = tx.query_row(
+                    "SELECT id FROM quest_jobs
+                     WHERE (status = 'queued' AND run_at <= ?1) OR (status = 'running' AND locked_until <= ?1)
+                     ORDER BY run_at LIMIT 1",
+                    params![now],
+                    |row| row.get(0),
+                ).optional().map_err(sql_err)?;
+                let Some(id) = id else {
+                    return Ok(None);
+                };
+                tx.execute(
+                    "UPDATE quest_jobs SET status = 'running', attempts = attempts + 1, locked_until = ?2 WHERE id = ?1",
+                    params![id, now + LEASE_SECS],
+                ).map_err(sql_err)?;
+                let job = tx.query_row(
+                    &format!("SELECT {} FROM quest_jobs WHERE id = ?1", JOB_COLUMNS),
+                    params![id],
+                    Job::from_row,
+                ).map_err(sql_err)?;
+                tx.commit().map_err(sql_err)?;
+                Ok(Some(job))
+            }
+        }
+    }
+
+    fn finish(&mut self, job: &Job, outcome: Outcome) -> Result<(), String> {
+        match self {
+            Store::Memory(m) => {
+                let Some(record) = m.records.get_mut(&job.id) else {
+                    return Ok(());
+                };
+                match outcome {
+                    Outcome::Done => {
+                        record.status = "done";
+                        record.error = None;
+                        m.retire(&job.id);
+                    }
+                    Outcome::Retry(run_at, error) => {
+                        record.status = "queued";
+                        record.error = Some(error);
+                        record.job.run_at = run_at;
+                        let job = record.job.clone();
+                        m.queue(&job);
+                    }
+                    Outcome::Failed(error) => {
+                        record.status = "failed";
+                        record.error = Some(error);
+                        m.retire(&job.id);
+                    }
+                }
+            }
+            Store::Sqlite { conn, .. } => {
+                let (status, run_at, error, finished_at) = match outcome {
+                    Outcome::Done => ("done", job.run_at, None, Some(now())),
+                    Outcome::Retry(run_at, error) => ("queued", run_at, Some(error), None),
+                    Outcome::Failed(error) => ("failed", job.run_at, Some(error), Some(now())),
+                };
+                conn.execute(
+                    "UPDATE quest_jobs SET status = ?2, run_at = ?3, error = ?4, finished_at = ?5, locked_until = NULL WHERE id = ?1",
+                    params![job.id, status, run_at, error, finished_at],
+                ).map_err(sql_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancel a job that hasn't started yet
+    fn cancel(&mut self, id: &str) -> Result<bool, String> {
+        match self {
+            Store::Memory(m) => {
+                let Some(key) = m.queued.iter().find(|(_, v)| v.as_str() == id).map(|(k, _)| *k) else {
+                    return Ok(false);
+                };
+                m.queued.remove(&key);
+                if let Some(record) = m.records.get_mut(id) {
+                    record.status = "cancelled";
+                }
+                m.retire(id);
+                Ok(true)
+            }
+            Store::Sqlite { conn, .. } => {
+                let changed = conn.execute(
+                    "UPDATE quest_jobs SET status = 'cancelled', finished_at = ?2 WHERE id = ?1 AND status = 'queued'",
+                    params![id, now()],
+                ).map_err(sql_err)?;
+                Ok(changed > 0)
+            }
+        }
+    }
+
+    fn status(&self, id: &str) -> Result<Option<(Job, String, Option<String>)>, String> {
+        match self {
+            Store::Memory(m) => Ok(m.records.get(id).map(|r| (r.job.clone(), r.status.to_string(), r.error.clone()))),
+            Store::Sqlite { conn, .. } => conn.query_row(
+                &format!("SELECT {}, status, error FROM quest_jobs WHERE id = ?1", JOB_COLUMNS),
+                params![id],
+                |row| Ok((Job::from_row(row)?, row.get(8)?, row.get(9)?)),
+            ).optional().map_err(sql_err),
+        }
+    }
+
+    /// Job counts by status
+    fn counts(&self) -> Result<IndexMap<String, i64>, String> {
+        let mut counts: IndexMap<String, i64> = ["queued", "running", "done", "failed", "cancelled"]
+            .iter().map(|s| (s.to_string(), 0)).collect();
+        match self {
+            Store::Memory(m) => {
+                for record in m.records.values() {
+                    *counts.entry(record.status.to_string()).or_insert(0) += 1;
+                }
+            }
+            Store::Sqlite { conn, .. } => {
+                let mut stmt = conn.prepare("SELECT status, COUNT(*) FROM quest_jobs GROUP BY status").map_err(sql_err)?;
+                let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))).map_err(sql_err)?;
+                for row in rows {
+                    let (status, n) = row.map_err(sql_err)?;
+                    counts.insert(status, n);
+                }
+            }
+        }
+        Ok(counts)
+    }
+
+    /// When the next queued job is due
+    fn next_run_at(&self) -> Result<Option<f64>, String> {
+        match self {
+            Store::Memory(m) => Ok(m.queued.first_key_value().map(|((run_at, _), _)| run_at.0)),
+            Store::Sqlite { conn, .. } => conn.query_row(
+                "SELECT MIN(run_at) FROM quest_jobs WHERE status = 'queued'", [], |row| row.get(0),
+            ).map_err(sql_err),
+        }
+    }
+
+    /// Forget finished jobs, returning how many were removed
+    fn purge(&mut self) -> Result<i64, String> {
+        match self {
+            Store::Memory(m) => {
+                let removed = m.finished.len() as i64;
+                for id in m.finished.drain(..) {
+                    m.records.remove(&id);
+                }
+                Ok(removed)
+            }
+            Store::Sqlite { conn, .. } => {
+                let removed = conn.execute("DELETE FROM quest_jobs WHERE status IN ('done', 'failed', 'cancelled')", [])
+                    .map_err(sql_err)?;
+                Ok(removed as i64)
+            }
+        }
+    }
+}
+
+struct QueueState {
+    workers: usize,
+    retries: i64,
+    backoff: f64,
+    store: Store,
+}
+
+struct JobQueue {
+    state: Mutex<QueueState>,
+    /// Signalled when jobs are added or finish
+    changed: Condvar,
+}
+
+lazy_static! {
+    static ref QUEUE: JobQueue = JobQueue {
+        state: Mutex::new(QueueState {
+            workers: DEFAULT_WORKERS,
+            retries: 0,
+            backoff: DEFAULT_BACKOFF,
+            store: Store::Memory(MemoryStore::default()),
+        }),
+        changed: Condvar::new(),
+    };
+}
+
+/// Set once the script imports std/jobs; the server only starts job threads then
+static IN_USE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Jobs enqueued during the current request, released when it ends
+    static HELD: RefCell<Option<Vec<Job>>> = const { RefCell::new(None) };
+}
+
+fn lock() -> MutexGuard<'static, QueueState> {
+    QUEUE.state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn now() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0)
+}
+
+/// Whether the running script uses std/jobs
+pub(crate) fn in_use() -> bool {
+    IN_USE.load(Ordering::Relaxed)
+}
+
+/// Job threads each server process should start
+pub(crate) fn worker_count() -> usize {
+    lock().workers
+}
+
+/// Hold jobs enqueued on this thread until end_request()
+pub(crate) fn begin_request() {
+    HELD.with(|held| *held.borrow_mut() = Some(Vec::new()));
+}
+
+/// Queue the jobs held since begin_request(), or drop them
+pub(crate) fn end_request(release: bool) {
+    let Some(jobs) = HELD.with(|held| held.borrow_mut().take()) else {
+        return;
+    };
+    if !release || jobs.is_empty() {
+        return;
+    }
+    let mut state = lock();
+    for job in jobs {
+        if let Err(e) = state.store.push(job) {
+            eprintln!("Failed to queue job: {}", e);
+        }
+    }
+    QUEUE.changed.notify_all();
+}
+
+fn push(job: Job) -> Result<(), String> {
+    let job = match HELD.with(|held| held.borrow_mut().as_mut().map(|jobs| jobs.push(job.clone()))) {
+        Some(()) => return Ok(()),
+        None => job,
+    };
+    lock().store.push(job)?;
+    QUEUE.changed.notify_all();
+    Ok(())
+}
+
+/// Wait for the next due job (server job threads)
+pub(crate) fn next_job() -> Job {
+    let mut state = lock();
+    loop {
+        let wait = match state.store.claim(now()) {
+            Ok(Some(job)) => return job,
+            Ok(None) => {
+                let idle = if matches!(state.store, Store::Sqlite { .. }) { POLL_SECS } else { IDLE_SECS };
+                match state.store.next_run_at() {
+                    Ok(Some(run_at)) => (run_at - now()).clamp(0.01, idle),
+                    _ => idle,
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to read jobs: {}", e);
+                POLL_SECS
+            }
+        };
+        state = QUEUE.changed.wait_timeout(state, Duration::from_secs_f64(wait))
+            .unwrap_or_else(|e| e.into_inner()).0;
+    }
+}
+
+fn claim_due() -> Result<Option<Job>, String> {
+    lock().store.claim(now())
+}
+
+fn run(job: &Job, scope: &mut crate::Scope) -> Result<(), String> {
+    let func = scope.get(&job.name)
+        .ok_or_else(|| format!("NameErr: Job function '{}' is not defined", job.name))?;
+    let args = match serde_json::from_str(&job.args) {
+        Ok(serde_json::Value::Array(values)) => values.into_iter()
+            .map(|v| json_to_qvalue(v).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return value_err!("Job {} has malformed arguments", job.id),
+    };
+    let kwargs = match serde_json::from_str(&job.kwargs) {
+        Ok(serde_json::Value::Object(map)) => map.into_iter()
+            .map(|(k, v)| Ok((k, json_to_qvalue(v).map_err(|e| e.to_string())?)))
+            .collect::<Result<IndexMap<_, _>, String>>()?,
+        _ => return value_err!("Job {} has malformed keyword arguments", job.id),
+    };
+    call_value(&func, CallArguments::new(args, kwargs), scope)?;
+    Ok(())
+}
+
+/// Run a claimed job and record the outcome, scheduling a retry on failure
+pub(crate) fn execute(job: Job, scope: &mut crate::Scope) {
+    let outcome = match run(&job, scope) {
+        Ok(()) => Outcome::Done,
+        Err(e) if job.attempts <= job.retries => {
+            // Exponential backoff: backoff, 2 * backoff, 4 * backoff, ...
+            let wait = job.backoff * 2f64.powi(job.attempts as i32 - 1);
+            eprintln!("Job {} ({}) failed, retrying in {}s: {}", job.name, job.id, wait, e);
+            Outcome::Retry(now() + wait, e)
+        }
+        Err(e) => {
+            eprintln!("Job {} ({}) failed after {} attempt(s): {}", job.name, job.id, job.attempts, e);
+            Outcome::Failed(e)
+        }
+    };
+    if let Err(e) = lock().store.finish(&job, outcome) {
+        eprintln!("Failed to record job {}: {}", job.id, e);
+    }
+    QUEUE.changed.notify_all();
+}
+
+pub fn create_jobs_module() -> QValue {
+    IN_USE.store(true, Ordering::Relaxed);
+    let mut members = HashMap::new();
+    members.insert("configure".to_string(), create_fn("jobs", "configure"));
+    members.insert("submit".to_string(), create_fn("jobs", "submit"));
+    members.insert("status".to_string(), create_fn("jobs", "status"));
+    members.insert("cancel".to_string(), create_fn("jobs", "cancel"));
+    members.insert("stats".to_string(), create_fn("jobs", "stats"));
+    members.insert("work".to_string(), create_fn("jobs", "work"));
+    members.insert("purge".to_string(), create_fn("jobs", "purge"));
+    QValue::Module(Box::new(QModule::new("jobs".to_string(), members)))
+}
+
+fn count_value(name: &str, value: &QValue) -> Result<i64, String> {
+    match value {
+        QValue::Int(i) if i.value >= 0 => Ok(i.value),
+        QValue::Int(i) => value_err!("{} cannot be negative, got {}", name, i.value),
+        _ => type_err!("{} must be Int, got {}", name, value.as_obj().cls()),
+    }
+}
+
+fn seconds_option(name: &str, value: &QValue) -> Result<f64, String> {
+    let secs = crate::modules::time::seconds_value(name, value)?;
+    if !secs.is_finite() || secs < 0.0 {
+        return value_err!("{} cannot be negative, got {}", name, secs);
+    }
+    Ok(secs)
+}
+
+/// The name a job function is looked up by in the worker's scope. The worker
+/// runs whatever that name refers to at the top level of the script.
+fn job_name(func: &QValue) -> Result<String, String> {
+    let name = match func {
+        QValue::UserFun(f) => f.name.clone().filter(|n| n != "<anonymous>"),
+        QValue::Str(s) => Some(s.value.as_ref().clone()),
+        _ => return type_err!("Job must be a function, got {}", func.as_obj().cls()),
+    };
+    match name {
+        Some(name) => Ok(name),
+        None => value_err!("Jobs must be named functions (fun send_email(...) ... end), not anonymous functions"),
+    }
+}
+
+fn to_json(value: &QValue) -> Result<String, String> {
+    let json = qvalue_to_json(value)
+        .map_err(|e| format!("TypeErr: Job arguments must be JSON-serializable: {}", e))?;
+    Ok(json.to_string())
+}
+
+fn status_dict(job: Job, status: String, error: Option<String>) -> QValue {
+    let mut map = IndexMap::new();
+    map.insert("id".to_string(), QValue::Str(QString::new(job.id)));
+    map.insert("name".to_string(), QValue::Str(QString::new(job.name)));
+    map.insert("status".to_string(), QValue::Str(QString::new(status)));
+    map.insert("attempts".to_string(), QValue::Int(QInt::new(job.attempts)));
+    map.insert("error".to_string(), match error {
+        Some(e) => QValue::Str(QString::new(e)),
+        None => QValue::Nil(QNil),
+    });
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+/// Handle jobs.* function calls
+pub fn call_jobs_function(func_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "jobs.configure" => {
+            // configure(workers: 2, retries: 0, backoff: 1.0, store: nil)
+            let options = match args.as_slice() {
+                [] => None,
+                [QValue::Dict(options)] => Some(options),
+                _ => return arg_err!("jobs.configure expects keyword arguments (workers, retries, backoff, store)"),
+            };
+            let mut state = lock();
+            let Some(options) = options else {
+                return Ok(QValue::Nil(QNil));
+            };
+            for (key, value) in options.map.borrow().iter() {
+                match key.as_str() {
+                    "workers" => state.workers = count_value("workers", value)? as usize,
+                    "retries" => state.retries = count_value("retries", value)?,
+                    "backoff" => state.backoff = seconds_option("backoff", value)?,
+                    "store" => {
+                        let path = match value {
+                            QValue::Nil(_) => None,
+                            QValue::Str(s) => Some(s.value.as_ref().clone()),
+                            _ => return type_err!("store must be a file path (Str) or nil, got {}", value.as_obj().cls()),
+                        };
+                        // Every server thread re-runs configure(); keep an open store
+                        match (&state.store, path) {
+                            (Store::Sqlite { path: open, .. }, Some(path)) if *open == path => {}
+                            (Store::Memory(_), None) => {}
+                            (_, Some(path)) => state.store = Store::open(&path)?,
+                            (_, None) => state.store = Store::Memory(MemoryStore::default()),
+                        }
+                    }
+                    _ => return arg_err!("jobs.configure got an unknown option '{}'", key),
+                }
+            }
+            Ok(QValue::Nil(QNil))
+        }
+        "jobs.submit" => {
+            // submit(fn, args, kwargs, delay, retries, backoff) - used by enqueue() in lib/std/jobs.q
+            if args.is_empty() || args.len() > 6 {
+                return arg_err!("jobs.submit expects 1 to 6 arguments (fn, args, kwargs, delay, retries, backoff), got {}", args.len());
+            }
+            let arg = |i: usize| args.get(i).filter(|v| !matches!(v, QValue::Nil(_)));
+            let name = job_name(&args[0])?;
+            let positional = match arg(1) {
+                None => "[]".to_string(),
+                Some(v @ QValue::Array(_)) => to_json(v)?,
+                Some(v) => return type_err!("jobs.submit args must be Array, got {}", v.as_obj().cls()),
+            };
+            let keyword = match arg(2) {
+                None => "{}".to_string(),
+                Some(v @ QValue::Dict(_)) => to_json(v)?,
+                Some(v) => return type_err!("jobs.submit kwargs must be Dict, got {}", v.as_obj().cls()),
+            };
+            let delay = arg(3).map(|v| seconds_option("delay", v)).transpose()?.unwrap_or(0.0);
+            let (default_retries, default_backoff) = {
+                let state = lock();
+                (state.retries, state.backoff)
+            };
+            let job = Job {
+                id: uuid::Uuid::new_v4().to_string(),
+                name,
+                args: positional,
+                kwargs: keyword,
+                attempts: 0,
+                retries: arg(4).map(|v| count_value("retries", v)).transpose()?.unwrap_or(default_retries),
+                backoff: arg(5).map(|v| seconds_option("backoff", v)).transpose()?.unwrap_or(default_backoff),
+                run_at: now() + delay,
+            };
+            let id = job.id.clone();
+            push(job)?;
+            Ok(QValue::Str(QString::new(id)))
+        }
+        "jobs.status" => {
+            if args.len() != 1 {
+                return arg_err!("jobs.status expects 1 argument (id), got {}", args.len());
+            }
+            let id = args[0].as_str();
+            // A job held by the current request hasn't reached the queue yet
+            let held = HELD.with(|held| held.borrow().as_ref()
+                .and_then(|jobs| jobs.iter().find(|j| j.id == id).cloned()));
+            if let Some(job) = held {
+                return Ok(status_dict(job, "held".to_string(), None));
+            }
+            match lock().store.status(&id)? {
+                Some((job, status, error)) => Ok(status_dict(job, status, error)),
+                None => Ok(QValue::Nil(QNil)),
+            }
+        }
+        "jobs.cancel" => {
+            if args.len() != 1 {
+                return arg_err!("jobs.cancel expects 1 argument (id), got {}", args.len());
+            }
+            let id = args[0].as_str();
+            let removed = HELD.with(|held| match held.borrow_mut().as_mut() {
+                Some(jobs) => {
+                    let before = jobs.len();
+                    jobs.retain(|j| j.id != id);
+                    jobs.len() < before
+                }
+                None => false,
+            });
+            Ok(QValue::Bool(QBool::new(removed || lock().store.cancel(&id)?)))
+        }
+        "jobs.stats" => {
+            if !args.is_empty() {
+                return arg_err!("jobs.stats expects 0 arguments, got {}", args.len());
+            }
+            let state = lock();
+            let mut stats: IndexMap<String, QValue> = state.store.counts()?.into_iter()
+                .map(|(k, v)| (k, QValue::Int(QInt::new(v))))
+                .collect();
+            stats.insert("workers".to_string(), QValue::Int(QInt::new(state.workers as i64)));
+            stats.insert("store".to_string(), match &state.store {
+                Store::Memory(_) => QValue::Nil(QNil),
+                Store::Sqlite { path, .. } => QValue::Str(QString::new(path.clone())),
+            });
+            Ok(QValue::Dict(Box::new(QDict::new(stats))))
+        }
+        "jobs.work" => {
+            // work(block = false) - run due jobs here; with block, wait for delayed
+            // jobs and retries until the queue is empty
+            let block = match args.first() {
+                None => false,
+                Some(QValue::Dict(options)) if args.len() == 1 => options.get("block").map(|v| v.as_bool()).unwrap_or(false),
+                Some(value) if args.len() == 1 => value.as_bool(),
+                _ => return arg_err!("jobs.work expects 0 or 1 arguments (block), got {}", args.len()),
+            };
+            let mut ran = 0;
+            loop {
+                if let Some(job) = claim_due()? {
+                    execute(job, scope);
+                    ran += 1;
+                    continue;
+                }
+                let next = lock().store.next_run_at()?;
+                match next {
+                    Some(run_at) if block => std::thread::sleep(Duration::from_secs_f64((run_at - now()).clamp(0.001, POLL_SECS))),
+                    _ => break,
+                }
+            }
+            Ok(QValue::Int(QInt::new(ran)))
+        }
+        "jobs.purge" => {
+            if !args.is_empty() {
+                return arg_err!("jobs.purge expects 0 arguments, got {}", args.len());
+            }
+            Ok(QValue::Int(QInt::new(lock().store.purge()?)))
+        }
+        _ => attr_err!("Unknown jobs function: {}", func_name),
+    }
+}
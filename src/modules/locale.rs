@@ -0,0 +1,697 @@
+// std/locale - locale-aware number, currency and date formatting
+//
+// Reports for international users need "1.234.567,89" in Germany and
+// "12,34,567.89" in India, the currency symbol on the right side of the
+// amount, and month names in the reader's language. The data for each
+// supported locale is a small table taken from CLDR.
+
+use std::collections::HashMap;
+use jiff::civil::Date as JiffDate;
+use rust_decimal::prelude::*;
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::modules::args::{option, take_options};
+use crate::{arg_err, name_err, type_err, value_err};
+
+/// Formatting data for one locale (CLDR)
+pub(crate) struct Locale {
+    pub tag: &'static str,
+    decimal: &'static str,
+    group: &'static str,
+    minus: &'static str,
+    /// Digits needed in the leftmost group before grouping starts (2 means
+    /// "1234" stays ungrouped)
+    min_grouping: usize,
+    /// Lakh/crore grouping: 12,34,567
+    indian: bool,
+    /// `#` is the number
+    percent: &'static str,
+    /// `¤` is the currency symbol and `#` the amount
+    currency: &'static str,
+    currency_code: &'static str,
+    months: [&'static str; 12],
+    /// Month names as used next to a day number, where they differ
+    /// (Polish and Russian genitive)
+    months_format: Option<[&'static str; 12]>,
+    months_abbr: [&'static str; 12],
+    /// Monday first, matching day_of_week()
+    days: [&'static str; 7],
+    days_abbr: [&'static str; 7],
+    am_pm: [&'static str; 2],
+    /// short, medium, long, full (strftime)
+    date_styles: [&'static str; 4],
+    /// short, medium (strftime)
+    time_styles: [&'static str; 2],
+    datetime_sep: &'static str,
+}
+
+const EN_MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+const EN_DAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const EN_DAYS_ABBR: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const DE_MONTHS: [&str; 12] = ["Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November", "Dezember"];
+const DE_MONTHS_ABBR: [&str; 12] = ["Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.", "Nov.", "Dez."];
+const DE_DAYS: [&str; 7] = ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"];
+const DE_DAYS_ABBR: [&str; 7] = ["Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa.", "So."];
+const DE_DATES: [&str; 4] = ["%d.%m.%y", "%d.%m.%Y", "%-d. %B %Y", "%A, %-d. %B %Y"];
+const H24: [&str; 2] = ["%H:%M", "%H:%M:%S"];
+const CJK_MONTHS: [&str; 12] = ["1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月"];
+
+/// Supported locales. The first entry for a language is used for a bare
+/// language tag ("de") or an unsupported region ("de-AT").
+static LOCALES: &[Locale] = &[
+    Locale {
+        tag: "en-US", decimal: ".", group: ",", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤#", currency_code: "USD",
+        months: EN_MONTHS, months_format: None,
+        months_abbr: ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"],
+        days: EN_DAYS, days_abbr: EN_DAYS_ABBR, am_pm: ["AM", "PM"],
+        date_styles: ["%-m/%-d/%y", "%b %-d, %Y", "%B %-d, %Y", "%A, %B %-d, %Y"],
+        time_styles: ["%-I:%M %p", "%-I:%M:%S %p"], datetime_sep: ", ",
+    },
+    Locale {
+        tag: "en-GB", decimal: ".", group: ",", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤#", currency_code: "GBP",
+        months: EN_MONTHS, months_format: None,
+        months_abbr: ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sept", "Oct", "Nov", "Dec"],
+        days: EN_DAYS, days_abbr: EN_DAYS_ABBR, am_pm: ["am", "pm"],
+        date_styles: ["%d/%m/%Y", "%-d %b %Y", "%-d %B %Y", "%A %-d %B %Y"],
+        time_styles: H24, datetime_sep: ", ",
+    },
+    Locale {
+        tag: "de-DE", decimal: ",", group: ".", minus: "-", min_grouping: 1, indian: false,
+        percent: "#\u{a0}%", currency: "#\u{a0}¤", currency_code: "EUR",
+        months: DE_MONTHS, months_format: None, months_abbr: DE_MONTHS_ABBR,
+        days: DE_DAYS, days_abbr: DE_DAYS_ABBR, am_pm: ["AM", "PM"],
+        date_styles: DE_DATES, time_styles: H24, datetime_sep: ", ",
+    },
+    Locale {
+        tag: "de-CH", decimal: ".", group: "’", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤\u{a0}#", currency_code: "CHF",
+        months: DE_MONTHS, months_format: None, months_abbr: DE_MONTHS_ABBR,
+        days: DE_DAYS, days_abbr: DE_DAYS_ABBR, am_pm: ["AM", "PM"],
+        date_styles: DE_DATES, time_styles: H24, datetime_sep: ", ",
+    },
+    Locale {
+        tag: "fr-FR", decimal: ",", group: "\u{202f}", minus: "-", min_grouping: 1, indian: false,
+        percent: "#\u{202f}%", currency: "#\u{a0}¤", currency_code: "EUR",
+        months: ["janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre", "décembre"],
+        months_format: None,
+        months_abbr: ["janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.", "déc."],
+        days: ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"],
+        days_abbr: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."], am_pm: ["AM", "PM"],
+        date_styles: ["%d/%m/%Y", "%-d %b %Y", "%-d %B %Y", "%A %-d %B %Y"],
+        time_styles: H24, datetime_sep: " ",
+    },
+    Locale {
+        tag: "es-ES", decimal: ",", group: ".", minus: "-", min_grouping: 2, indian: false,
+        percent: "#\u{a0}%", currency: "#\u{a0}¤", currency_code: "EUR",
+        months: ["enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre", "noviembre", "diciembre"],
+        months_format: None,
+        months_abbr: ["ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic"],
+        days: ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"],
+        days_abbr: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"], am_pm: ["a.\u{a0}m.", "p.\u{a0}m."],
+        date_styles: ["%-d/%-m/%y", "%-d %b %Y", "%-d de %B de %Y", "%A, %-d de %B de %Y"],
+        time_styles: ["%-H:%M", "%-H:%M:%S"], datetime_sep: ", ",
+    },
+    Locale {
+        tag: "it-IT", decimal: ",", group: ".", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "#\u{a0}¤", currency_code: "EUR",
+        months: ["gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto", "settembre", "ottobre", "novembre", "dicembre"],
+        months_format: None,
+        months_abbr: ["gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic"],
+        days: ["lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato", "domenica"],
+        days_abbr: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"], am_pm: ["AM", "PM"],
+        date_styles: ["%d/%m/%y", "%-d %b %Y", "%-d %B %Y", "%A %-d %B %Y"],
+        time_styles: H24, datetime_sep: ", ",
+    },
+    Locale {
+        tag: "pt-BR", decimal: ",", group: ".", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤\u{a0}#", currency_code: "BRL",
+        months: ["janeiro", "fevereiro", "março", "abril", "maio", "junho", "julho", "agosto", "setembro", "outubro", "novembro", "dezembro"],
+        months_format: None,
+        months_abbr: ["jan.", "fev.", "mar.", "abr.", "mai.", "jun.", "jul.", "ago.", "set.", "out.", "nov.", "dez."],
+        days: ["segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado", "domingo"],
+        days_abbr: ["seg.", "ter.", "qua.", "qui.", "sex.", "sáb.", "dom."], am_pm: ["AM", "PM"],
+        date_styles: ["%d/%m/%Y", "%-d de %b de %Y", "%-d de %B de %Y", "%A, %-d de %B de %Y"],
+        time_styles: H24, datetime_sep: " ",
+    },
+    Locale {
+        tag: "nl-NL", decimal: ",", group: ".", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤\u{a0}#", currency_code: "EUR",
+        months: ["januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september", "oktober", "november", "december"],
+        months_format: None,
+        months_abbr: ["jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+        days: ["maandag", "dinsdag", "woensdag", "donderdag", "vrijdag", "zaterdag", "zondag"],
+        days_abbr: ["ma", "di", "wo", "do", "vr", "za", "zo"], am_pm: ["a.m.", "p.m."],
+        date_styles: ["%d-%m-%Y", "%-d %b %Y", "%-d %B %Y", "%A %-d %B %Y"],
+        time_styles: H24, datetime_sep: " ",
+    },
+    Locale {
+        tag: "sv-SE", decimal: ",", group: "\u{a0}", minus: "\u{2212}", min_grouping: 1, indian: false,
+        percent: "#\u{a0}%", currency: "#\u{a0}¤", currency_code: "SEK",
+        months: ["januari", "februari", "mars", "april", "maj", "juni", "juli", "augusti", "september", "oktober", "november", "december"],
+        months_format: None,
+        months_abbr: ["jan.", "feb.", "mars", "apr.", "maj", "juni", "juli", "aug.", "sep.", "okt.", "nov.", "dec."],
+        days: ["måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag", "söndag"],
+        days_abbr: ["mån", "tis", "ons", "tors", "fre", "lör", "sön"], am_pm: ["fm", "em"],
+        date_styles: ["%Y-%m-%d", "%-d %b %Y", "%-d %B %Y", "%A %-d %B %Y"],
+        time_styles: H24, datetime_sep: " ",
+    },
+    Locale {
+        tag: "pl-PL", decimal: ",", group: "\u{a0}", minus: "-", min_grouping: 2, indian: false,
+        percent: "#%", currency: "#\u{a0}¤", currency_code: "PLN",
+        months: ["styczeń", "luty", "marzec", "kwiecień", "maj", "czerwiec", "lipiec", "sierpień", "wrzesień", "październik", "listopad", "grudzień"],
+        months_format: Some(["stycznia", "lutego", "marca", "kwietnia", "maja", "czerwca", "lipca", "sierpnia", "września", "października", "listopada", "grudnia"]),
+        months_abbr: ["sty", "lut", "mar", "kwi", "maj", "cze", "lip", "sie", "wrz", "paź", "lis", "gru"],
+        days: ["poniedziałek", "wtorek", "środa", "czwartek", "piątek", "sobota", "niedziela"],
+        days_abbr: ["pon.", "wt.", "śr.", "czw.", "pt.", "sob.", "niedz."], am_pm: ["AM", "PM"],
+        date_styles: ["%d.%m.%Y", "%-d %b %Y", "%-d %B %Y", "%A, %-d %B %Y"],
+        time_styles: H24, datetime_sep: ", ",
+    },
+    Locale {
+        tag: "ru-RU", decimal: ",", group: "\u{a0}", minus: "-", min_grouping: 1, indian: false,
+        percent: "#\u{a0}%", currency: "#\u{a0}¤", currency_code: "RUB",
+        months: ["январь", "февраль", "март", "апрель", "май", "июнь", "июль", "август", "сентябрь", "октябрь", "ноябрь", "декабрь"],
+        months_format: Some(["января", "февраля", "марта", "апреля", "мая", "июня", "июля", "августа", "сентября", "октября", "ноября", "декабря"]),
+        months_abbr: ["янв.", "февр.", "март", "апр.", "май", "июнь", "июль", "авг.", "сент.", "окт.", "нояб.", "дек."],
+        days: ["понедельник", "вторник", "среда", "четверг", "пятница", "суббота", "воскресенье"],
+        days_abbr: ["пн", "вт", "ср", "чт", "пт", "сб", "вс"], am_pm: ["AM", "PM"],
+        date_styles: ["%d.%m.%Y", "%-d %b %Y г.", "%-d %B %Y г.", "%A, %-d %B %Y г."],
+        time_styles: H24, datetime_sep: ", ",
+    },
+    Locale {
+        tag: "ja-JP", decimal: ".", group: ",", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤#", currency_code: "JPY",
+        months: CJK_MONTHS, months_format: None, months_abbr: CJK_MONTHS,
+        days: ["月曜日", "火曜日", "水曜日", "木曜日", "金曜日", "土曜日", "日曜日"],
+        days_abbr: ["月", "火", "水", "木", "金", "土", "日"], am_pm: ["午前", "午後"],
+        date_styles: ["%Y/%m/%d", "%Y/%m/%d", "%Y年%-m月%-d日", "%Y年%-m月%-d日%A"],
+        time_styles: H24, datetime_sep: " ",
+    },
+    Locale {
+        tag: "zh-CN", decimal: ".", group: ",", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤#", currency_code: "CNY",
+        months: ["一月", "二月", "三月", "四月", "五月", "六月", "七月", "八月", "九月", "十月", "十一月", "十二月"],
+        months_format: None, months_abbr: CJK_MONTHS,
+        days: ["星期一", "星期二", "星期三", "星期四", "星期五", "星期六", "星期日"],
+        days_abbr: ["周一", "周二", "周三", "周四", "周五", "周六", "周日"], am_pm: ["上午", "下午"],
+        date_styles: ["%Y/%-m/%-d", "%Y年%-m月%-d日", "%Y年%-m月%-d日", "%Y年%-m月%-d日%A"],
+        time_styles: H24, datetime_sep: " ",
+    },
+    Locale {
+        tag: "ko-KR", decimal: ".", group: ",", minus: "-", min_grouping: 1, indian: false,
+        percent: "#%", currency: "¤#", currency_code: "KRW",
+        months: ["1월", "2월", "3월", "4월", "5월", "6월", "7월", "8월", "9월", "10월", "11월", "12월"],
+        months_format: None,
+        months_abbr: ["1월", "2월", "3월", "4월", "5월", "6월", "7월", "8월", "9월", "10월", "11월", "12월"],
+        days: ["월요일", "화요일", "수요일", "목요일", "금요일", "토요일", "일요일"],
+        days_abbr: ["월", "화", "수", "목", "금", "토", "일"], am_pm: ["오전", "오후"],
+        date_styles: ["%y. %-m. %-d.", "%Y. %-m. %-d.", "%Y년 %-m월 %-d일", "%Y년 %-m월 %-d일 %A"],
+        time_styles: ["%p %-I:%M", "%p %-I:%M:%S"], datetime_sep: " ",
+    },
+    Locale {
+        tag: "hi-IN", decimal: ".", group: ",", minus: "-", min_grouping: 1, indian: true,
+        percent: "#%", currency: "¤#", currency_code: "INR",
+        months: ["जनवरी", "फ़रवरी", "मार्च", "अप्रैल", "मई", "जून", "जुलाई", "अगस्त", "सितंबर", "अक्तूबर", "नवंबर", "दिसंबर"],
+        months_format: None,
+        months_abbr: ["जन॰", "फ़र॰", "मार्च", "अप्रैल", "मई", "जून", "जुल॰", "अग॰", "सित॰", "अक्तू॰", "नव॰", "दिस॰"],
+        days: ["सोमवार", "मंगलवार", "बुधवार", "गुरुवार", "शुक्रवार", "शनिवार", "रविवार"],
+        days_abbr: ["सोम", "मंगल", "बुध", "गुरु", "शुक्र", "शनि", "रवि"], am_pm: ["am", "pm"],
+        date_styles: ["%-d/%-m/%y", "%-d %b %Y", "%-d %B %Y", "%A, %-d %B %Y"],
+        time_styles: ["%-I:%M %p", "%-I:%M:%S %p"], datetime_sep: ", ",
+    },
+];
+
+/// ISO 4217 code, symbol, symbol in the currency's home locale, minor digits
+static CURRENCIES: &[(&str, &str, &str, u32)] = &[
+    ("USD", "$", "$", 2),
+    ("EUR", "€", "€", 2),
+    ("GBP", "£", "£", 2),
+    ("JPY", "¥", "￥", 0),
+    ("CNY", "CN¥", "¥", 2),
+    ("KRW", "₩", "₩", 0),
+    ("INR", "₹", "₹", 2),
+    ("CHF", "CHF", "CHF", 2),
+    ("SEK", "SEK", "kr", 2),
+    ("PLN", "PLN", "zł", 2),
+    ("RUB", "RUB", "₽", 2),
+    ("BRL", "R$", "R$", 2),
+    ("CAD", "CA$", "$", 2),
+    ("AUD", "A$", "$", 2),
+    ("NZD", "NZ$", "$", 2),
+    ("MXN", "MX$", "$", 2),
+    ("HKD", "HK$", "$", 2),
+    ("NOK", "NOK", "kr", 2),
+    ("DKK", "DKK", "kr.", 2),
+    ("CZK", "CZK", "Kč", 2),
+];
+
+pub fn create_locale_module() -> QValue {
+    let mut members = HashMap::new();
+
+    // Numbers
+    members.insert("format_number".to_string(), create_fn("locale", "format_number"));
+    members.insert("format_currency".to_string(), create_fn("locale", "format_currency"));
+    members.insert("format_percent".to_string(), create_fn("locale", "format_percent"));
+    members.insert("parse_number".to_string(), create_fn("locale", "parse_number"));
+
+    // Dates
+    members.insert("format_date".to_string(), create_fn("locale", "format_date"));
+    members.insert("format_time".to_string(), create_fn("locale", "format_time"));
+    members.insert("format_datetime".to_string(), create_fn("locale", "format_datetime"));
+    members.insert("month_names".to_string(), create_fn("locale", "month_names"));
+    members.insert("day_names".to_string(), create_fn("locale", "day_names"));
+
+    // Locales
+    members.insert("available".to_string(), create_fn("locale", "available"));
+    members.insert("current".to_string(), create_fn("locale", "current"));
+    members.insert("info".to_string(), create_fn("locale", "info"));
+
+    QValue::Module(Box::new(QModule::new("locale".to_string(), members)))
+}
+
+/// Handle locale.* function calls
+pub fn call_locale_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let name = &func_name["locale.".len()..];
+    let mut args = args;
+    let options = take_options(&mut args);
+    let option = |index: usize, key: &str| args.get(index).cloned().or_else(|| option(&options, key));
+    let check_args = |min: usize, max: usize, usage: &str| -> Result<(), String> {
+        if args.len() < min || args.len() > max {
+            return arg_err!("{} expects {} ({}), got {} positional arguments", name,
+                if min == max { min.to_string() } else { format!("{} to {}", min, max) }, usage, args.len());
+        }
+        Ok(())
+    };
+
+    match func_name {
+        "locale.format_number" | "locale.format_percent" => {
+            // format_number(value, locale = current, decimals: nil, grouping: true)
+            check_args(1, 2, "value, locale")?;
+            let loc = locale_option(name, option(1, "locale"))?;
+            let decimals = decimals_option(name, option(2, "decimals"))?;
+            let grouping = option(3, "grouping").map(|v| v.as_bool()).unwrap_or(true);
+            let mut number = Number::from_value(name, &args[0])?;
+            if name == "format_percent" {
+                number = number.scaled(100);
+                let text = number.format(loc, Some(decimals.unwrap_or(0)), grouping);
+                return Ok(str_value(apply_sign(loc.percent, &text, &number, loc)));
+            }
+            let text = number.format(loc, decimals, grouping);
+            Ok(str_value(apply_sign("#", &text, &number, loc)))
+        }
+        "locale.format_currency" => {
+            // format_currency(amount, currency, locale = current, decimals: nil, display: "symbol")
+            check_args(2, 3, "amount, currency, locale")?;
+            let code = match &args[1] {
+                QValue::Str(s) if s.value.len() == 3 && s.value.chars().all(|c| c.is_ascii_alphabetic()) => s.value.to_ascii_uppercase(),
+                QValue::Str(s) => return value_err!("format_currency currency must be an ISO 4217 code like \"EUR\", got '{}'", s.value),
+                other => return type_err!("format_currency currency must be Str, got {}", other.q_type()),
+            };
+            let loc = locale_option(name, option(2, "locale"))?;
+            let decimals = decimals_option(name, option(3, "decimals"))?;
+            let display = match option(4, "display") {
+                None | Some(QValue::Nil(_)) => "symbol".to_string(),
+                Some(QValue::Str(s)) if s.value.as_str() == "symbol" || s.value.as_str() == "code" => s.value.as_ref().clone(),
+                Some(QValue::Str(s)) => return value_err!("format_currency display must be \"symbol\" or \"code\", got '{}'", s.value),
+                Some(other) => return type_err!("format_currency display must be Str, got {}", other.q_type()),
+            };
+            let known = CURRENCIES.iter().find(|c| c.0 == code);
+            let symbol = match known {
+                _ if display == "code" => code.as_str(),
+                Some((_, _, local, _)) if code == loc.currency_code => local,
+                Some((_, symbol, _, _)) => symbol,
+                None => code.as_str(),
+            };
+            let minor = known.map(|c| c.3).unwrap_or(2);
+            let number = Number::from_value(name, &args[0])?;
+            let text = number.format(loc, Some(decimals.unwrap_or(minor)), true);
+            Ok(str_value(apply_sign(&currency_pattern(loc.currency, symbol), &text, &number, loc)))
+        }
+        "locale.parse_number" => {
+            // parse_number(text, locale = current)
+            check_args(1, 2, "text, locale")?;
+            let text = match &args[0] {
+                QValue::Str(s) => s.value.as_ref().clone(),
+                other => return type_err!("parse_number expects Str, got {}", other.q_type()),
+            };
+            let loc = locale_option(name, option(1, "locale"))?;
+            Ok(parse_number(&text, loc)?)
+        }
+        "locale.format_date" | "locale.format_time" | "locale.format_datetime" => {
+            // format_date(value, style = "medium", locale = current)
+            check_args(1, 3, "value, style, locale")?;
+            let default_style = if name == "format_time" { "short" } else { "medium" };
+            let style = match option(1, "style") {
+                None | Some(QValue::Nil(_)) => default_style.to_string(),
+                Some(QValue::Str(s)) => s.value.as_ref().clone(),
+                Some(other) => return type_err!("{} style must be Str, got {}", name, other.q_type()),
+            };
+            let loc = locale_option(name, option(2, "locale"))?;
+            let date_pattern = || style_index(&style).map(|i| loc.date_styles[i]);
+            let time_pattern = || style_index(&style).map(|i| loc.time_styles[i.min(1)]);
+            let pattern = match name {
+                "format_date" => date_pattern().map(str::to_string),
+                "format_time" => time_pattern().map(str::to_string),
+                _ => date_pattern().zip(time_pattern()).map(|(d, t)| format!("{}{}{}", d, loc.datetime_sep, t)),
+            }.unwrap_or(style);
+            Ok(str_value(crate::modules::time::format_temporal(name, &args[0], &pattern, Some(loc))?))
+        }
+        "locale.month_names" | "locale.day_names" => {
+            // month_names(locale = current, abbr: false, context: "standalone")
+            check_args(0, 1, "locale")?;
+            let loc = locale_option(name, option(0, "locale"))?;
+            let abbr = option(1, "abbr").map(|v| v.as_bool()).unwrap_or(false);
+            let format_context = match option(2, "context") {
+                None | Some(QValue::Nil(_)) => false,
+                Some(QValue::Str(s)) if s.value.as_str() == "standalone" => false,
+                Some(QValue::Str(s)) if s.value.as_str() == "format" => true,
+                Some(QValue::Str(s)) => return value_err!("{} context must be \"standalone\" or \"format\", got '{}'", name, s.value),
+                Some(other) => return type_err!("{} context must be Str, got {}", name, other.q_type()),
+            };
+            let names: &[&str] = match (name, abbr) {
+                ("month_names", true) => &loc.months_abbr,
+                ("month_names", false) if format_context => loc.months_format.as_ref().unwrap_or(&loc.months),
+                ("month_names", false) => &loc.months,
+                (_, true) => &loc.days_abbr,
+                (_, false) => &loc.days,
+            };
+            Ok(QValue::Array(QArray::new(names.iter().map(|n| str_value(n.to_string())).collect())))
+        }
+        "locale.available" => {
+            check_args(0, 0, "no arguments")?;
+            Ok(QValue::Array(QArray::new(LOCALES.iter().map(|l| str_value(l.tag.to_string())).collect())))
+        }
+        "locale.current" => {
+            check_args(0, 0, "no arguments")?;
+            Ok(str_value(current_locale().tag.to_string()))
+        }
+        "locale.info" => {
+            check_args(0, 1, "locale")?;
+            let loc = locale_option(name, option(0, "locale"))?;
+            let mut info = indexmap::IndexMap::new();
+            info.insert("tag".to_string(), str_value(loc.tag.to_string()));
+            info.insert("decimal".to_string(), str_value(loc.decimal.to_string()));
+            info.insert("group".to_string(), str_value(loc.group.to_string()));
+            info.insert("currency".to_string(), str_value(loc.currency_code.to_string()));
+            Ok(QValue::Dict(Box::new(QDict::new(info))))
+        }
+        _ => name_err!("Unknown locale function: {}", func_name)
+    }
+}
+
+fn str_value(s: String) -> QValue {
+    QValue::Str(QString::new(s))
+}
+
+/// Resolve a locale tag ("de-DE", "de_DE.UTF-8", "de"), falling back from an
+/// unsupported region to the first locale for the language
+pub(crate) fn lookup_locale(tag: &str) -> Result<&'static Locale, String> {
+    let cleaned = tag.split(['.', '@']).next().unwrap_or("").replace('_', "-");
+    let mut parts = cleaned.split('-');
+    let language = parts.next().unwrap_or("").to_ascii_lowercase();
+    let region = parts.next().map(|r| r.to_ascii_uppercase());
+    let exact = region.map(|r| format!("{}-{}", language, r));
+    LOCALES.iter()
+        .find(|l| Some(l.tag) == exact.as_deref())
+        .or_else(|| LOCALES.iter().find(|l| l.tag.split('-').next() == Some(language.as_str())))
+        .ok_or_else(|| format!("ValueErr: Unsupported locale '{}' (see locale.available())", tag))
+}
+
+/// The locale from LC_ALL, LC_NUMERIC or LANG; en-US when unset, "C" or unsupported
+fn current_locale() -> &'static Locale {
+    ["LC_ALL", "LC_NUMERIC", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| lookup_locale(&value).ok())
+        .unwrap_or(&LOCALES[0])
+}
+
+fn locale_option(func: &str, value: Option<QValue>) -> Result<&'static Locale, String> {
+    match value {
+        None | Some(QValue::Nil(_)) => Ok(current_locale()),
+        Some(QValue::Str(s)) => lookup_locale(&s.value),
+        Some(other) => type_err!("{} locale must be Str, got {}", func, other.q_type()),
+    }
+}
+
+fn decimals_option(func: &str, value: Option<QValue>) -> Result<Option<u32>, String> {
+    match value {
+        None | Some(QValue::Nil(_)) => Ok(None),
+        Some(QValue::Int(n)) if (0..=20).contains(&n.value) => Ok(Some(n.value as u32)),
+        Some(QValue::Int(n)) => value_err!("{} decimals must be between 0 and 20, got {}", func, n.value),
+        Some(other) => type_err!("{} decimals must be Int, got {}", func, other.q_type()),
+    }
+}
+
+fn style_index(style: &str) -> Option<usize> {
+    ["short", "medium", "long", "full"].iter().position(|s| *s == style)
+}
+
+/// A number split into sign and decimal digits, before localization
+struct Number {
+    negative: bool,
+    /// Plain decimal digits with an optional '.', e.g. "1234.5"; "NaN" or "inf"
+    digits: String,
+    /// Whether `digits` is exact (Int, BigInt, Decimal) rather than a Float
+    exact: bool,
+}
+
+impl Number {
+    fn from_value(func: &str, value: &QValue) -> Result<Number, String> {
+        let (negative, digits, exact) = match value {
+            QValue::Int(n) => (n.value < 0, n.value.unsigned_abs().to_string(), true),
+            QValue::BigInt(n) => (n.value.sign() == num_bigint::Sign::Minus, n.value.magnitude().to_string(), true),
+            QValue::Decimal(d) => (d.value.is_sign_negative(), d.value.abs().to_string(), true),
+            QValue::Float(f) if f.value.is_nan() => (false, "NaN".to_string(), false),
+            QValue::Float(f) if f.value.is_infinite() => (f.value < 0.0, "inf".to_string(), false),
+            QValue::Float(f) => (f.value.is_sign_negative(), f.value.abs().to_string(), false),
+            other => return type_err!("{} expects a number, got {}", func, other.q_type()),
+        };
+        Ok(Number { negative, digits, exact })
+    }
+
+    /// Multiply by a power of ten (percentages)
+    fn scaled(self, factor: u32) -> Number {
+        if self.digits == "NaN" || self.digits == "inf" {
+            return self;
+        }
+        let value = Decimal::from_str(&self.digits)
+            .ok()
+            .and_then(|d| d.checked_mul(Decimal::from(factor)));
+        match value {
+            Some(d) => Number { digits: d.normalize().to_string(), ..self },
+            None => {
+                let f: f64 = self.digits.parse().unwrap_or(0.0) * factor as f64;
+                Number { digits: f.to_string(), ..self }
+            }
+        }
+    }
+
+    /// Localized digits without the sign. With `decimals` the fraction is
+    /// rounded or padded to exactly that many digits; otherwise exact values
+    /// keep their digits and floats show at most three
+    fn format(&self, loc: &Locale, decimals: Option<u32>, grouping: bool) -> String {
+        match self.digits.as_str() {
+            "NaN" => return "NaN".to_string(),
+            "inf" => return "∞".to_string(),
+            _ => {}
+        }
+        let digits = match (decimals, self.exact) {
+            (Some(n), _) => round_digits(&self.digits, n),
+            (None, true) => self.digits.clone(),
+            (None, false) => {
+                let rounded = round_digits(&self.digits, 3);
+                match rounded.split_once('.') {
+                    Some((int, frac)) if frac.trim_end_matches('0').is_empty() => int.to_string(),
+                    Some(_) => rounded.trim_end_matches('0').to_string(),
+                    None => rounded,
+                }
+            }
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut out = if grouping { group_digits(int, loc) } else { int.to_string() };
+        if !frac.is_empty() {
+            out.push_str(loc.decimal);
+            out.push_str(frac);
+        }
+        out
+    }
+}
+
+/// Round plain decimal digits to `places` fraction digits (half away from zero)
+fn round_digits(digits: &str, places: u32) -> String {
+    match Decimal::from_str(digits).or_else(|_| Decimal::from_scientific(digits)) {
+        Ok(d) => {
+            let rounded = d.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
+            format!("{:.*}", places as usize, rounded)
+        }
+        // Too large for Decimal: these are floats beyond 7.9e28
+        Err(_) => format!("{:.*}", places as usize, digits.parse::<f64>().unwrap_or(0.0)),
+    }
+}
+
+/// Insert group separators into integer digits
+fn group_digits(int: &str, loc: &Locale) -> String {
+    if int.len() < 3 + loc.min_grouping {
+        return int.to_string();
+    }
+    let (head, tail) = int.split_at(int.len() - 3);
+    let size = if loc.indian { 2 } else { 3 };
+    let mut groups: Vec<&str> = Vec::new();
+    let mut end = head.len();
+    while end > size {
+        groups.push(&head[end - size..end]);
+        end -= size;
+    }
+    groups.push(&head[..end]);
+    groups.reverse();
+    groups.push(tail);
+    groups.join(loc.group)
+}
+
+/// Put localized digits into `pattern` (`#` is the number), with the minus
+/// sign in front of everything for negative values that don't round to zero
+fn apply_sign(pattern: &str, text: &str, number: &Number, loc: &Locale) -> String {
+    let formatted = pattern.replace('#', text);
+    let rounds_to_zero = text.chars().all(|c| !c.is_ascii_digit() || c == '0') && text != "∞";
+    if number.negative && !rounds_to_zero {
+        format!("{}{}", loc.minus, formatted)
+    } else {
+        formatted
+    }
+}
+
+/// Substitute the currency symbol into the locale's currency pattern,
+/// separating alphabetic symbols ("CHF", "kr") from adjacent digits
+fn currency_pattern(pattern: &str, symbol: &str) -> String {
+    let alphabetic_end = symbol.chars().last().is_some_and(|c| c.is_alphabetic());
+    let alphabetic_start = symbol.chars().next().is_some_and(|c| c.is_alphabetic());
+    if pattern.starts_with("¤#") && alphabetic_end {
+        pattern.replacen('¤', &format!("{}\u{a0}", symbol), 1)
+    } else if pattern.ends_with("#¤") && alphabetic_start {
+        pattern.replacen('¤', &format!("\u{a0}{}", symbol), 1)
+    } else {
+        pattern.replacen('¤', symbol, 1)
+    }
+}
+
+/// Parse a number written for `loc`: "1.234,5" in de-DE is 1234.5
+fn parse_number(text: &str, loc: &Locale) -> Result<QValue, String> {
+    let spaces = ['\u{a0}', '\u{202f}', ' '];
+    let mut plain = String::new();
+    let trimmed = text.trim();
+    let mut rest = trimmed;
+    if let Some(stripped) = rest.strip_prefix(['-', '\u{2212}']) {
+        plain.push('-');
+        rest = stripped;
+    } else if let Some(stripped) = rest.strip_prefix('+') {
+        rest = stripped;
+    }
+    let group_is_space = loc.group.chars().all(|c| spaces.contains(&c));
+    let group_is_quote = loc.group == "’";
+    let mut seen_decimal = false;
+    let mut seen_digit = false;
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if rest[i..].starts_with(loc.decimal) && !seen_decimal {
+            seen_decimal = true;
+            plain.push('.');
+            for _ in 1..loc.decimal.chars().count() {
+                chars.next();
+            }
+        } else if c.is_ascii_digit() {
+            seen_digit = true;
+            plain.push(c);
+        } else if !seen_decimal && seen_digit && (rest[i..].starts_with(loc.group)
+            || (group_is_space && spaces.contains(&c))
+            || (group_is_quote && c == '\'')) {
+            continue;
+        } else {
+            return value_err!("parse_number: '{}' is not a number in {}", text, loc.tag);
+        }
+    }
+    if !seen_digit {
+        return value_err!("parse_number: '{}' is not a number in {}", text, loc.tag);
+    }
+    if !seen_decimal {
+        if let Ok(n) = plain.parse::<i64>() {
+            return Ok(QValue::Int(QInt::new(n)));
+        }
+    }
+    plain.parse::<f64>()
+        .map(|f| QValue::Float(QFloat::new(f)))
+        .map_err(|_| format!("ValueErr: parse_number: '{}' is not a number in {}", text, loc.tag))
+}
+
+/// Replace the name directives of a strftime pattern (%A %a %B %b %h %p %P)
+/// with `loc`'s names and expand %c, %x and %X to its date and time styles.
+/// Months next to a day number use the format-context (genitive) forms.
+pub(crate) fn localize_strftime(pattern: &str, loc: &Locale, date: Option<JiffDate>, hour: Option<i8>) -> Result<String, String> {
+    let with_day = directives(pattern).any(|(_, d)| d == 'd' || d == 'e');
+    let mut out = String::with_capacity(pattern.len());
+    let mut last = 0;
+    for (range, directive) in directives(pattern) {
+        out.push_str(&pattern[last..range.start]);
+        last = range.end;
+        let spec = &pattern[range.start..range.end];
+        let upper = spec.contains('^');
+        let name = match directive {
+            'A' | 'a' | 'B' | 'b' | 'h' => {
+                let Some(date) = date else {
+                    return value_err!("format: %{} needs a date", directive);
+                };
+                let month = date.month() as usize - 1;
+                let day = date.weekday().to_monday_zero_offset() as usize;
+                match directive {
+                    'A' => loc.days[day],
+                    'a' => loc.days_abbr[day],
+                    'B' if with_day => loc.months_format.as_ref().unwrap_or(&loc.months)[month],
+                    'B' => loc.months[month],
+                    _ => loc.months_abbr[month],
+                }.to_string()
+            }
+            'p' | 'P' => {
+                let Some(hour) = hour else {
+                    return value_err!("format: %{} needs a time", directive);
+                };
+                let marker = loc.am_pm[(hour >= 12) as usize];
+                if directive == 'P' { marker.to_lowercase() } else { marker.to_string() }
+            }
+            'c' => localize_strftime(&format!("{}{}{}", loc.date_styles[1], loc.datetime_sep, loc.time_styles[1]), loc, date, hour)?,
+            'x' => localize_strftime(loc.date_styles[0], loc, date, hour)?,
+            'X' => localize_strftime(loc.time_styles[1], loc, date, hour)?,
+            _ => {
+                out.push_str(spec);
+                continue;
+            }
+        };
+        out.push_str(&if upper { name.to_uppercase() } else { name });
+    }
+    out.push_str(&pattern[last..]);
+    Ok(out)
+}
+
+/// The directives of a strftime pattern: byte range (including flags) and
+/// conversion character
+fn directives(pattern: &str) -> impl Iterator<Item = (std::ops::Range<usize>, char)> + '_ {
+    let mut chars = pattern.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while let Some((start, c)) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+            while let Some((_, flag)) = chars.peek() {
+                if "-_0^#:.".contains(*flag) || flag.is_ascii_digit() {
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if let Some((i, directive)) = chars.next() {
+                return Some((start..i + directive.len_utf8(), directive));
+            }
+        }
+        None
+    })
+}
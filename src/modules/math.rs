@@ -1,7 +1,8 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::types::*;
-use crate::{arg_err, name_err};
+use crate::{arg_err, name_err, type_err, value_err};
 
 pub fn create_math_module() -> QValue {
     let mut members = HashMap::new();
@@ -28,6 +29,12 @@ pub fn create_math_module() -> QValue {
     members.insert("ceil".to_string(), create_fn("math", "ceil"));
     members.insert("round".to_string(), create_fn("math", "round"));
 
+    // Statistics (Arrays of numbers or NDArrays)
+    for name in ["mean", "median", "mode", "variance", "pvariance", "stddev", "pstddev",
+                 "percentile", "quantiles", "correlation", "histogram"] {
+        members.insert(name.to_string(), create_fn("math", name));
+    }
+
     QValue::Module(Box::new(QModule::new("math".to_string(), members)))
 }
 
@@ -81,6 +88,222 @@ pub fn call_math_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
                 Ok(QValue::Float(QFloat::new(result)))
             }
         }
+        "math.mean" | "math.median" | "math.variance" | "math.pvariance" |
+        "math.stddev" | "math.pstddev" => {
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument, got {}", func_name, args.len());
+            }
+            let values = numeric_values(&args[0], func_name)?;
+            let result = match func_name.trim_start_matches("math.") {
+                "mean" => mean(&values),
+                "median" => percentile(&values, 50.0),
+                "variance" => sample_variance(&values, func_name)?,
+                "pvariance" => population_variance(&values),
+                "stddev" => sample_variance(&values, func_name)?.sqrt(),
+                "pstddev" => population_variance(&values).sqrt(),
+                _ => unreachable!(),
+            };
+            Ok(QValue::Float(QFloat::new(result)))
+        }
+        "math.mode" => {
+            // Most common value; the first one seen wins a tie
+            if args.len() != 1 {
+                return arg_err!("math.mode expects 1 argument, got {}", args.len());
+            }
+            let values = numeric_values(&args[0], func_name)?;
+            let index = mode_index(&values);
+            match &args[0] {
+                // Return the element itself so Ints stay Ints
+                QValue::Array(arr) => Ok(arr.elements.borrow()[index].clone()),
+                _ => Ok(QValue::Float(QFloat::new(values[index]))),
+            }
+        }
+        "math.percentile" => {
+            // percentile(data, p) - p from 0 to 100, interpolating between values
+            if args.len() != 2 {
+                return arg_err!("math.percentile expects 2 arguments (data, p), got {}", args.len());
+            }
+            let values = numeric_values(&args[0], func_name)?;
+            let p = args[1].as_num()?;
+            if !(0.0..=100.0).contains(&p) {
+                return value_err!("math.percentile p must be between 0 and 100, got {}", p);
+            }
+            Ok(QValue::Float(QFloat::new(percentile(&values, p))))
+        }
+        "math.quantiles" => {
+            // quantiles(data, n = 4) - the n - 1 cut points dividing data into n groups
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("math.quantiles expects 1 or 2 arguments (data, n?), got {}", args.len());
+            }
+            let values = numeric_values(&args[0], func_name)?;
+            let n = if args.len() == 2 { args[1].as_num()? as i64 } else { 4 };
+            if n < 2 {
+                return value_err!("math.quantiles n must be at least 2, got {}", n);
+            }
+            let cuts = (1..n)
+                .map(|i| QValue::Float(QFloat::new(percentile(&values, 100.0 * i as f64 / n as f64))))
+                .collect();
+            Ok(QValue::Array(QArray::new(cuts)))
+        }
+        "math.correlation" => {
+            // Pearson correlation coefficient of two equally long data sets
+            if args.len() != 2 {
+                return arg_err!("math.correlation expects 2 arguments (x, y), got {}", args.len());
+            }
+            let xs = numeric_values(&args[0], func_name)?;
+            let ys = numeric_values(&args[1], func_name)?;
+            if xs.len() != ys.len() {
+                return value_err!("math.correlation expects data of the same length, got {} and {}", xs.len(), ys.len());
+            }
+            if xs.len() < 2 {
+                return value_err!("math.correlation needs at least 2 values");
+            }
+            let (mean_x, mean_y) = (mean(&xs), mean(&ys));
+            let mut covariance = 0.0;
+            let mut spread_x = 0.0;
+            let mut spread_y = 0.0;
+            for (x, y) in xs.iter().zip(&ys) {
+                covariance += (x - mean_x) * (y - mean_y);
+                spread_x += (x - mean_x) * (x - mean_x);
+                spread_y += (y - mean_y) * (y - mean_y);
+            }
+            if spread_x == 0.0 || spread_y == 0.0 {
+                return value_err!("math.correlation is undefined when a data set is constant");
+            }
+            Ok(QValue::Float(QFloat::new(covariance / (spread_x * spread_y).sqrt())))
+        }
+        "math.histogram" => {
+            // histogram(data, bins = 10) - bins is a count of equal-width bins
+            // or an Array of bin edges. Returns {"counts": [...], "edges": [...]}.
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("math.histogram expects 1 or 2 arguments (data, bins?), got {}", args.len());
+            }
+            let values = numeric_values(&args[0], func_name)?;
+            let edges = match args.get(1) {
+                Some(QValue::Array(_)) => {
+                    let edges = numeric_values(&args[1], func_name)?;
+                    if edges.len() < 2 || edges.windows(2).any(|w| w[0] >= w[1]) {
+                        return value_err!("math.histogram edges must be at least 2 increasing values");
+                    }
+                    edges
+                }
+                Some(bins) => equal_width_edges(&values, bins.as_num()? as i64)?,
+                None => equal_width_edges(&values, 10)?,
+            };
+
+            let mut counts = vec![0i64; edges.len() - 1];
+            let last = edges.len() - 1;
+            for value in &values {
+                // Bins include their left edge; the last bin also includes its right edge
+                if *value < edges[0] || *value > edges[last] {
+                    continue;
+                }
+                let bin = edges.partition_point(|edge| edge <= value).saturating_sub(1).min(last - 1);
+                counts[bin] += 1;
+            }
+
+            let mut result = IndexMap::new();
+            result.insert("counts".to_string(), QValue::Array(QArray::new(
+                counts.into_iter().map(|c| QValue::Int(QInt::new(c))).collect(),
+            )));
+            result.insert("edges".to_string(), QValue::Array(QArray::new(
+                edges.into_iter().map(|e| QValue::Float(QFloat::new(e))).collect(),
+            )));
+            Ok(QValue::Dict(Box::new(QDict::new(result))))
+        }
         _ => name_err!("Unknown math function: {}", func_name)
     }
 }
+
+// =============================================================================
+// Statistics helpers
+// =============================================================================
+
+/// Numbers from an Array or NDArray (flattened). Empty data is a ValueErr.
+fn numeric_values(value: &QValue, func_name: &str) -> Result<Vec<f64>, EvalError> {
+    let values: Vec<f64> = match value {
+        QValue::Array(arr) => {
+            let elements = arr.elements.borrow();
+            let mut values = Vec::with_capacity(elements.len());
+            for element in elements.iter() {
+                match element {
+                    QValue::Int(_) | QValue::Float(_) | QValue::Decimal(_) | QValue::BigInt(_) => {
+                        values.push(element.as_num()?)
+                    }
+                    other => return type_err!("{} expects numbers, got {}", func_name, other.as_obj().cls()),
+                }
+            }
+            values
+        }
+        QValue::NDArray(nd) => nd.data.iter().copied().collect(),
+        other => return type_err!("{} expects an Array or NDArray, got {}", func_name, other.as_obj().cls()),
+    };
+    if values.is_empty() {
+        return value_err!("{} requires at least one value", func_name);
+    }
+    Ok(values)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn squared_deviations(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    values.iter().map(|v| (v - mean) * (v - mean)).sum()
+}
+
+/// Variance of a sample (divides by n - 1)
+fn sample_variance(values: &[f64], func_name: &str) -> Result<f64, EvalError> {
+    if values.len() < 2 {
+        return value_err!("{} requires at least two values", func_name);
+    }
+    Ok(squared_deviations(values) / (values.len() - 1) as f64)
+}
+
+/// Variance of a whole population (divides by n)
+fn population_variance(values: &[f64]) -> f64 {
+    squared_deviations(values) / values.len() as f64
+}
+
+/// p-th percentile (0-100), interpolating linearly between the closest ranks
+fn percentile(values: &[f64], p: f64) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Index of the first occurrence of the most common value
+fn mode_index(values: &[f64]) -> usize {
+    let mut counts: HashMap<u64, (usize, usize)> = HashMap::new();
+    for (i, value) in values.iter().enumerate() {
+        // Normalise -0.0 so it counts as 0
+        let key = if *value == 0.0 { 0.0f64.to_bits() } else { value.to_bits() };
+        counts.entry(key).or_insert((0, i)).0 += 1;
+    }
+    counts.values()
+        .max_by(|(count_a, first_a), (count_b, first_b)| count_a.cmp(count_b).then(first_b.cmp(first_a)))
+        .map(|(_, first)| *first)
+        .unwrap_or(0)
+}
+
+/// Edges of `bins` equal-width bins spanning the data
+fn equal_width_edges(values: &[f64], bins: i64) -> Result<Vec<f64>, EvalError> {
+    if bins < 1 {
+        return value_err!("math.histogram bins must be at least 1, got {}", bins);
+    }
+    let mut low = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let mut high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if low == high {
+        // All values equal: center a unit-wide range on them
+        low -= 0.5;
+        high += 0.5;
+    }
+    let width = (high - low) / bins as f64;
+    let mut edges: Vec<f64> = (0..bins).map(|i| low + width * i as f64).collect();
+    edges.push(high);
+    Ok(edges)
+}
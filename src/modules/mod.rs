@@ -1,3 +1,4 @@
+pub mod args;
 pub mod math;
 pub mod os;
 pub mod term;
@@ -10,6 +11,10 @@ pub mod time;
 pub mod serial;
 pub mod regex;
 pub mod db;
+pub mod email;
+pub mod net;
+pub mod text;
+pub mod msg;
 pub mod uuid;
 pub mod html;
 pub mod http;
@@ -20,6 +25,14 @@ pub mod compress;
 pub mod process;
 pub mod toml;
 pub mod web;
+pub mod collections;
+pub mod inspect;
+pub mod unicode;
+pub mod locale;
+pub mod collate;
+pub mod cache;
+pub mod jobs;
+pub mod image;
 
 pub use math::{create_math_module, call_math_function};
 pub use os::{create_os_module, call_os_function};
@@ -28,21 +41,38 @@ pub use hash::{create_hash_module, call_hash_function};
 pub use io::{create_io_module, call_io_function};
 pub use sys::{create_sys_module, call_sys_function};
 pub use crypto::{create_crypto_module, call_crypto_function};
-pub use encoding::{create_b64_module, create_json_module as create_encoding_json_module, call_json_function, call_b64_function, create_struct_module, call_struct_function, create_hex_module, call_hex_function, create_url_module, call_url_function, create_csv_module, call_csv_function};
+pub use crypto::jwt::{create_jwt_module, call_jwt_function};
+pub use encoding::{create_b64_module, create_json_module as create_encoding_json_module, call_json_function, call_b64_function, create_struct_module, call_struct_function, create_hex_module, call_hex_function, create_url_module, call_url_function, create_csv_module, call_csv_function, create_yaml_module, call_yaml_function, create_xml_module, call_xml_function, create_proto_module, call_proto_function, create_jsonschema_module, call_jsonschema_function, create_qr_module, call_qr_function};
 pub use time::{create_time_module, call_time_function};
 pub use serial::{create_serial_module, call_serial_function};
+pub use serial::modbus::{create_modbus_module, call_modbus_function};
+pub use serial::can::{create_can_module, call_can_function};
 pub use regex::{create_regex_module, call_regex_function};
 pub use db::{create_sqlite_module, call_sqlite_function, create_postgres_module, call_postgres_function, create_mysql_module, call_mysql_function};
+pub use email::{create_imap_module, call_imap_function, create_pop3_module, call_pop3_function};
+pub use net::{create_ftp_module, call_ftp_function, create_sftp_module, call_sftp_function, create_ip_module, call_ip_function};
+pub use text::{create_diff_module, call_diff_function, create_fuzzy_module, call_fuzzy_function, create_shlex_module, call_shlex_function};
+pub use msg::{create_nats_module, call_nats_function};
 pub use uuid::{create_uuid_module, call_uuid_function};
 pub use html::{create_templates_module, call_templates_function, create_markdown_module, call_markdown_function};
 pub use http::{create_http_client_module, call_http_client_function, create_urlparse_module, call_urlparse_function};
 pub use ndarray::{create_ndarray_module, call_ndarray_function};
 pub use settings::{create_settings_module, call_settings_function, init_settings};
+pub use settings::config::{create_config_module, call_config_function};
 pub use rand::{create_rand_module, call_rand_function, call_rng_method};
 pub use compress::gzip::{create_gzip_module, call_gzip_function};
 pub use compress::bzip2::{create_bzip2_module, call_bzip2_function};
 pub use compress::deflate::{create_deflate_module, call_deflate_function};
 pub use compress::zlib::{create_zlib_module, call_zlib_function};
+pub use compress::tar::{create_tar_module, call_tar_function};
 pub use process::{create_process_module, call_process_function};
 pub use toml::{create_toml_module, call_toml_function};
 pub use web::{create_web_module, call_web_function};
+pub use collections::{create_collections_module, call_collections_function};
+pub use inspect::{create_inspect_module, call_inspect_function};
+pub use unicode::{create_unicode_module, call_unicode_function};
+pub use locale::{create_locale_module, call_locale_function};
+pub use collate::{create_collate_module, call_collate_function};
+pub use cache::{create_cache_module, call_cache_function};
+pub use jobs::{create_jobs_module, call_jobs_function};
+pub use image::{create_image_module, call_image_function};
@@ -0,0 +1,8 @@
+// Messaging clients: std/msg/nats
+//
+// NATS speaks its text protocol over net/stream.rs. Kafka (std/msg/kafka) is
+// written in Quest in lib/std/msg/kafka.q and talks to a Kafka REST Proxy.
+
+pub mod nats;
+
+pub use nats::{create_nats_module, call_nats_function};
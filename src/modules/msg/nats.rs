@@ -0,0 +1,623 @@
+// NATS client (std/msg/nats)
+//
+//     use "std/msg/nats"
+//     let nc = nats.connect("nats://localhost:4222")
+//     let sub = nc.subscribe("orders.*", queue: "billing")
+//     nc.publish("orders.created", json.stringify(order))
+//     let msg = sub.next(timeout: 1)
+//     let reply = nc.request("pricing.quote", "sku-42", timeout: 2)
+//
+// Messages are read only when the script asks for one (sub.next, request,
+// flush), so there are no callbacks or background threads: everything that
+// arrives in the meantime is queued on its subscription. PINGs from the
+// server are answered whenever the connection is read.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use indexmap::IndexMap;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+use crate::types::*;
+use crate::scope::Scope;
+use crate::modules::encoding::json_utils::qvalue_to_json;
+use crate::modules::net::stream::{ConnectOptions, LineStream};
+
+const DEFAULT_PORT: u16 = 4222;
+const DEFAULT_TIMEOUT_SECS: f64 = 5.0;
+
+struct Session {
+    stream: LineStream,
+    next_sid: u64,
+    /// Messages received but not yet taken, by subscription id
+    queues: HashMap<u64, VecDeque<QValue>>,
+    pongs_pending: usize,
+    max_payload: usize,
+    info: serde_json::Value,
+    timeout: Duration,
+}
+
+impl Session {
+    fn handle_line(&mut self, line: &[u8]) -> Result<(), String> {
+        let text = String::from_utf8_lossy(line);
+        let (op, rest) = text.split_once(' ').unwrap_or((&text, ""));
+        match op.to_ascii_uppercase().as_str() {
+            "MSG" => {
+                // MSG <subject> <sid> [reply-to] <#bytes>
+                let parts: Vec<&str> = rest.split_whitespace().collect();
+                let (subject, sid, reply, len) = match parts.as_slice() {
+                    [subject, sid, len] => (*subject, *sid, None, *len),
+                    [subject, sid, reply, len] => (*subject, *sid, Some(*reply), *len),
+                    _ => return io_err!("Malformed NATS message header: {}", text),
+                };
+                let sid: u64 = sid.parse().map_err(|_| format!("IOErr: Malformed NATS message header: {}", text))?;
+                let len: usize = len.parse().map_err(|_| format!("IOErr: Malformed NATS message header: {}", text))?;
+                let mut payload = self.stream.read_exact(len + 2)?;
+                payload.truncate(len);
+                let message = message(subject, sid, reply, payload);
+                // Messages for subscriptions we've dropped are discarded
+                if let Some(queue) = self.queues.get_mut(&sid) {
+                    queue.push_back(message);
+                }
+            }
+            "PING" => self.stream.send_line("PONG")?,
+            "PONG" => self.pongs_pending = self.pongs_pending.saturating_sub(1),
+            "+OK" => {}
+            "-ERR" => return io_err!("NATS error: {}", rest.trim().trim_matches('\'')),
+            "INFO" => {
+                if let Ok(info) = serde_json::from_str(rest) {
+                    self.info = info;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Read and handle one line, waiting at most until `deadline` (None: no
+    /// limit). Returns false on timeout.
+    fn pump(&mut self, deadline: Option<Instant>) -> Result<bool, String> {
+        let wait = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(false);
+                }
+                Some(deadline - now)
+            }
+            None => None,
+        };
+        match self.stream.poll_line(wait)? {
+            Some(line) => {
+                self.handle_line(&line)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Next queued message for `sid`, reading until one arrives or the deadline passes
+    fn next_message(&mut self, sid: u64, deadline: Option<Instant>) -> Result<Option<QValue>, String> {
+        loop {
+            if let Some(message) = self.queues.get_mut(&sid).and_then(|q| q.pop_front()) {
+                return Ok(Some(message));
+            }
+            if !self.queues.contains_key(&sid) {
+                return Ok(None);
+            }
+            if !self.pump(deadline)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn publish(&mut self, subject: &str, reply: Option<&str>, payload: &[u8]) -> Result<(), String> {
+        if payload.len() > self.max_payload {
+            return value_err!("NATS message of {} bytes exceeds the server's limit of {} bytes", payload.len(), self.max_payload);
+        }
+        let mut frame = match reply {
+            Some(reply) => format!("PUB {} {} {}\r\n", subject, reply, payload.len()),
+            None => format!("PUB {} {}\r\n", subject, payload.len()),
+        }.into_bytes();
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(b"\r\n");
+        self.stream.write_all(&frame)
+    }
+
+    fn subscribe(&mut self, subject: &str, queue: Option<&str>) -> Result<u64, String> {
+        let sid = self.next_sid;
+        self.next_sid += 1;
+        match queue {
+            Some(queue) => self.stream.send_line(&format!("SUB {} {} {}", subject, queue, sid))?,
+            None => self.stream.send_line(&format!("SUB {} {}", subject, sid))?,
+        }
+        self.queues.insert(sid, VecDeque::new());
+        Ok(sid)
+    }
+
+    fn unsubscribe(&mut self, sid: u64) -> Result<(), String> {
+        if self.queues.remove(&sid).is_some() {
+            self.stream.send_line(&format!("UNSUB {}", sid))?;
+        }
+        Ok(())
+    }
+
+    /// Round trip a PING so everything sent before has reached the server
+    fn flush(&mut self, timeout: Duration) -> Result<(), String> {
+        self.stream.send_line("PING")?;
+        self.pongs_pending += 1;
+        let target = self.pongs_pending - 1;
+        let deadline = Instant::now() + timeout;
+        while self.pongs_pending > target {
+            if !self.pump(Some(deadline))? {
+                return io_err!("NATS server did not answer PING within {:?}", timeout);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn message(subject: &str, sid: u64, reply: Option<&str>, payload: Vec<u8>) -> QValue {
+    let mut map = IndexMap::new();
+    map.insert("subject".to_string(), QValue::Str(QString::new(subject.to_string())));
+    map.insert("data".to_string(), QValue::Str(QString::new(String::from_utf8_lossy(&payload).into_owned())));
+    map.insert("bytes".to_string(), QValue::Bytes(QBytes::new(payload)));
+    map.insert("reply".to_string(), match reply {
+        Some(reply) => QValue::Str(QString::new(reply.to_string())),
+        None => QValue::Nil(QNil),
+    });
+    map.insert("sid".to_string(), QValue::Int(QInt::new(sid as i64)));
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+fn new_inbox() -> String {
+    let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(22).map(char::from).collect();
+    format!("_INBOX.{}", token)
+}
+
+/// Subjects and queue names are tokens without whitespace
+fn check_subject(what: &str, subject: &str) -> Result<(), String> {
+    if subject.is_empty() || subject.chars().any(char::is_whitespace) {
+        return value_err!("Invalid NATS {} '{}'", what, subject);
+    }
+    Ok(())
+}
+
+/// Message payload: Str and Bytes as they are, anything else as JSON
+fn payload(value: &QValue) -> Result<Vec<u8>, String> {
+    match value {
+        QValue::Str(s) => Ok(s.value.as_bytes().to_vec()),
+        QValue::Bytes(b) => Ok(b.data.clone()),
+        QValue::Nil(_) => Ok(Vec::new()),
+        other => Ok(qvalue_to_json(other)?.to_string().into_bytes()),
+    }
+}
+
+fn secs_option(method: &str, value: Option<QValue>) -> Result<Option<Duration>, String> {
+    match value {
+        None | Some(QValue::Nil(_)) => Ok(None),
+        Some(QValue::Int(n)) if n.value >= 0 => Ok(Some(Duration::from_secs(n.value as u64))),
+        Some(QValue::Float(f)) if f.value >= 0.0 => Ok(Some(Duration::from_secs_f64(f.value))),
+        Some(other) => value_err!("{} timeout must be a non-negative number of seconds, got {}", method, other.as_str()),
+    }
+}
+
+/// Split positional arguments from a trailing kwargs Dict. A Dict counts as
+/// kwargs only if all its keys are options the method takes, so a Dict passed
+/// as message data stays positional.
+fn split_kwargs<'a>(args: &'a [QValue], options: &[&str]) -> (&'a [QValue], Option<&'a QDict>) {
+    match args {
+        [rest @ .., QValue::Dict(d)] if !d.map.borrow().is_empty() && d.map.borrow().keys().all(|k| options.contains(&k.as_str())) => (rest, Some(d.as_ref())),
+        _ => (args, None),
+    }
+}
+
+fn str_arg(method: &str, what: &str, value: &QValue) -> Result<String, String> {
+    match value {
+        QValue::Str(s) => Ok(s.value.to_string()),
+        other => type_err!("{} expects {} to be Str, got {}", method, what, other.as_obj().cls()),
+    }
+}
+
+#[derive(Clone)]
+pub struct QNatsClient {
+    session: Rc<RefCell<Option<Session>>>,
+    url: String,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QNatsClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QNatsClient").field("url", &self.url).field("id", &self.id).finish()
+    }
+}
+
+impl QNatsClient {
+    fn connect(args: Vec<QValue>) -> Result<Self, String> {
+        // nats.connect(url = "nats://localhost:4222", name:, user:, password:, token:, timeout: 5, verify: true)
+        let (positional, kwargs) = split_kwargs(&args, &["name", "user", "password", "token", "timeout", "verify"]);
+        let url = match positional {
+            [] => "nats://localhost:4222".to_string(),
+            [url] => str_arg("nats.connect", "url", url)?,
+            _ => return arg_err!("nats.connect expects 0 or 1 arguments (url), got {}", positional.len()),
+        };
+        let get = |key: &str| kwargs.and_then(|d| d.get(key)).filter(|v| !matches!(v, QValue::Nil(_)));
+        let get_str = |key: &str| -> Result<Option<String>, String> {
+            get(key).map(|v| str_arg("nats.connect", key, &v)).transpose()
+        };
+
+        let (want_tls, rest) = match url.split_once("://") {
+            Some(("nats", rest)) => (false, rest),
+            Some(("tls", rest)) => (true, rest),
+            Some((scheme, _)) => return value_err!("nats.connect expects a nats:// or tls:// URL, got {}://", scheme),
+            None => (false, url.as_str()),
+        };
+        let rest = rest.trim_end_matches('/');
+        let (userinfo, hostport) = match rest.rsplit_once('@') {
+            Some((userinfo, hostport)) => (Some(userinfo), hostport),
+            None => (None, rest),
+        };
+        let (host, port) = split_host_port(hostport)?;
+
+        let decode = |s: &str| urlencoding::decode(s).map(|s| s.into_owned()).unwrap_or_else(|_| s.to_string());
+        let (mut user, mut password, mut token) = (None, None, None);
+        match userinfo.map(|u| u.split_once(':')) {
+            Some(Some((u, p))) => {
+                user = Some(decode(u));
+                password = Some(decode(p));
+            }
+            Some(None) => token = userinfo.map(decode),
+            None => {}
+        }
+        user = get_str("user")?.or(user);
+        password = get_str("password")?.or(password);
+        token = get_str("token")?.or(token);
+
+        let timeout = secs_option("nats.connect", get("timeout"))?
+            .unwrap_or(Duration::from_secs_f64(DEFAULT_TIMEOUT_SECS));
+        let opts = ConnectOptions {
+            host,
+            port,
+            // NATS servers greet in plain text and upgrade after INFO
+            tls: false,
+            starttls: want_tls,
+            verify: get("verify").is_none_or(|v| v.as_bool()),
+            timeout: Some(timeout),
+            user: user.clone(),
+            password: password.clone(),
+        };
+
+        let mut stream = LineStream::connect(&opts)?;
+        let greeting = String::from_utf8_lossy(&stream.read_line()?).into_owned();
+        let info: serde_json::Value = match greeting.split_once(' ') {
+            Some((op, json)) if op.eq_ignore_ascii_case("INFO") => serde_json::from_str(json)
+                .map_err(|e| format!("IOErr: Malformed NATS INFO: {}", e))?,
+            _ => return io_err!("{}:{} is not a NATS server: {}", opts.host, opts.port, greeting),
+        };
+        let tls_required = info["tls_required"].as_bool().unwrap_or(false);
+        if want_tls || tls_required {
+            stream = stream.start_tls(&opts)?;
+        }
+
+        let mut connect = serde_json::json!({
+            "verbose": false,
+            "pedantic": false,
+            "tls_required": want_tls || tls_required,
+            "lang": "quest",
+            "version": env!("CARGO_PKG_VERSION"),
+            "protocol": 1,
+        });
+        if let Some(name) = get_str("name")? {
+            connect["name"] = name.into();
+        }
+        if let Some(user) = user {
+            connect["user"] = user.into();
+        }
+        if let Some(password) = password {
+            connect["pass"] = password.into();
+        }
+        if let Some(token) = token {
+            connect["auth_token"] = token.into();
+        }
+        stream.send_line(&format!("CONNECT {}", connect))?;
+
+        let max_payload = info["max_payload"].as_u64().unwrap_or(1024 * 1024) as usize;
+        let mut session = Session {
+            stream,
+            next_sid: 1,
+            queues: HashMap::new(),
+            pongs_pending: 0,
+            max_payload,
+            info,
+            timeout,
+        };
+        // Authentication errors come back as -ERR before the PONG
+        session.flush(timeout)?;
+
+        Ok(QNatsClient {
+            session: Rc::new(RefCell::new(Some(session))),
+            // Shown by str(), so leave out any credentials
+            url: format!("{}://{}", if want_tls { "tls" } else { "nats" }, hostport),
+            id: next_object_id(),
+        })
+    }
+
+    fn with_session<T>(&self, f: impl FnOnce(&mut Session) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.session.borrow_mut();
+        match guard.as_mut() {
+            Some(session) => f(session),
+            None => io_err!("NATS connection is closed"),
+        }
+    }
+
+    fn close(&self) {
+        if let Some(mut session) = self.session.borrow_mut().take() {
+            // Make sure published messages are out before hanging up
+            let timeout = session.timeout;
+            let _ = session.flush(timeout);
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        let (positional, kwargs) = split_kwargs(&args, &["reply", "queue", "timeout"]);
+        let kwarg = |key: &str| kwargs.and_then(|d| d.get(key)).filter(|v| !matches!(v, QValue::Nil(_)));
+
+        match method_name {
+            "publish" => {
+                // publish(subject, data = nil, reply: nil)
+                let (subject, data) = match positional {
+                    [subject] => (subject, None),
+                    [subject, data] => (subject, Some(data)),
+                    _ => return arg_err!("publish expects 1 or 2 arguments (subject, data), got {}", positional.len()),
+                };
+                let subject = str_arg("publish", "subject", subject)?;
+                check_subject("subject", &subject)?;
+                let reply = kwarg("reply").map(|r| str_arg("publish", "reply", &r)).transpose()?;
+                if let Some(reply) = &reply {
+                    check_subject("reply subject", reply)?;
+                }
+                let data = match data {
+                    Some(data) => payload(data)?,
+                    None => Vec::new(),
+                };
+                self.with_session(|s| s.publish(&subject, reply.as_deref(), &data))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "subscribe" => {
+                // subscribe(subject, queue: nil)
+                let [subject] = positional else {
+                    return arg_err!("subscribe expects 1 argument (subject), got {}", positional.len());
+                };
+                let subject = str_arg("subscribe", "subject", subject)?;
+                check_subject("subject", &subject)?;
+                let queue = kwarg("queue").map(|q| str_arg("subscribe", "queue", &q)).transpose()?;
+                if let Some(queue) = &queue {
+                    check_subject("queue group", queue)?;
+                }
+                let sid = self.with_session(|s| s.subscribe(&subject, queue.as_deref()))?;
+                Ok(QValue::NatsSubscription(QNatsSubscription {
+                    client: self.clone(),
+                    sid,
+                    subject,
+                    queue,
+                    id: next_object_id(),
+                }))
+            }
+            "request" => {
+                // request(subject, data = nil, timeout: 5)
+                let (subject, data) = match positional {
+                    [subject] => (subject, None),
+                    [subject, data] => (subject, Some(data)),
+                    _ => return arg_err!("request expects 1 or 2 arguments (subject, data), got {}", positional.len()),
+                };
+                let subject = str_arg("request", "subject", subject)?;
+                check_subject("subject", &subject)?;
+                let data = match data {
+                    Some(data) => payload(data)?,
+                    None => Vec::new(),
+                };
+                let timeout = secs_option("request", kwarg("timeout"))?
+                    .unwrap_or(Duration::from_secs_f64(DEFAULT_TIMEOUT_SECS));
+
+                let reply = self.with_session(|s| {
+                    let inbox = new_inbox();
+                    let sid = s.subscribe(&inbox, None)?;
+                    s.stream.send_line(&format!("UNSUB {} 1", sid))?;
+                    s.publish(&subject, Some(&inbox), &data)?;
+                    let reply = s.next_message(sid, Some(Instant::now() + timeout));
+                    s.queues.remove(&sid);
+                    reply
+                })?;
+                match reply {
+                    Some(reply) => Ok(reply),
+                    None => io_err!("NATS request to '{}' timed out after {}s", subject, timeout.as_secs_f64()),
+                }
+            }
+            "flush" => {
+                let timeout = match positional {
+                    [] => secs_option("flush", kwarg("timeout"))?,
+                    [timeout] => secs_option("flush", Some(timeout.clone()))?,
+                    _ => return arg_err!("flush expects 0 or 1 arguments (timeout), got {}", positional.len()),
+                };
+                self.with_session(|s| {
+                    let timeout = timeout.unwrap_or(s.timeout);
+                    s.flush(timeout)
+                })?;
+                Ok(QValue::Nil(QNil))
+            }
+            "new_inbox" => Ok(QValue::Str(QString::new(new_inbox()))),
+            "server_info" => {
+                let info = self.with_session(|s| Ok(s.info.clone()))?;
+                Ok(crate::modules::encoding::json_utils::json_to_qvalue(info)?)
+            }
+            "close" => {
+                self.close();
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.session.borrow().is_none()))),
+            "_enter" => Ok(QValue::NatsClient(self.clone())),
+            "_exit" => {
+                self.close();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on NatsClient", method_name),
+        }
+    }
+}
+
+impl QObj for QNatsClient {
+    fn cls(&self) -> String {
+        "NatsClient".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "NatsClient"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "NatsClient"
+    }
+
+    fn str(&self) -> String {
+        format!("<NatsClient {}>", self.url)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "NATS connection".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QNatsSubscription {
+    client: QNatsClient,
+    sid: u64,
+    subject: String,
+    queue: Option<String>,
+    pub id: u64,
+}
+
+impl QNatsSubscription {
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "next" => {
+                // next(timeout: nil) waits until a message arrives, or up to timeout seconds
+                let (positional, kwargs) = split_kwargs(&args, &["timeout"]);
+                let timeout = match positional {
+                    [] => secs_option("next", kwargs.and_then(|d| d.get("timeout")))?,
+                    [timeout] => secs_option("next", Some(timeout.clone()))?,
+                    _ => return arg_err!("next expects 0 or 1 arguments (timeout), got {}", positional.len()),
+                };
+                let deadline = timeout.map(|t| Instant::now() + t);
+                let message = self.client.with_session(|s| s.next_message(self.sid, deadline))?;
+                Ok(message.unwrap_or(QValue::Nil(QNil)))
+            }
+            "pending" => {
+                let count = self.client.with_session(|s| Ok(s.queues.get(&self.sid).map_or(0, |q| q.len())))?;
+                Ok(QValue::Int(QInt::new(count as i64)))
+            }
+            "unsubscribe" => {
+                // Unsubscribing a closed connection has nothing left to do
+                if self.client.session.borrow().is_some() {
+                    self.client.with_session(|s| s.unsubscribe(self.sid))?;
+                }
+                Ok(QValue::Nil(QNil))
+            }
+            "active" => {
+                let active = self.client.session.borrow().as_ref().is_some_and(|s| s.queues.contains_key(&self.sid));
+                Ok(QValue::Bool(QBool::new(active)))
+            }
+            "subject" => Ok(QValue::Str(QString::new(self.subject.clone()))),
+            "queue" => Ok(match &self.queue {
+                Some(queue) => QValue::Str(QString::new(queue.clone())),
+                None => QValue::Nil(QNil),
+            }),
+            _ => attr_err!("Unknown method '{}' on NatsSubscription", method_name),
+        }
+    }
+}
+
+impl QObj for QNatsSubscription {
+    fn cls(&self) -> String {
+        "NatsSubscription".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "NatsSubscription"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "NatsSubscription"
+    }
+
+    fn str(&self) -> String {
+        format!("<NatsSubscription {}>", self.subject)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "NATS subscription".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+fn split_host_port(hostport: &str) -> Result<(String, u16), String> {
+    let parse_port = |p: &str| p.parse::<u16>().map_err(|_| format!("ValueErr: Invalid NATS port '{}'", p));
+    // [::1]:4222
+    if let Some(rest) = hostport.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').ok_or_else(|| format!("ValueErr: Invalid NATS address '{}'", hostport))?;
+        let port = match after.strip_prefix(':') {
+            Some(p) => parse_port(p)?,
+            None => DEFAULT_PORT,
+        };
+        return Ok((host.to_string(), port));
+    }
+    match hostport.rsplit_once(':') {
+        Some((host, port)) => Ok((host.to_string(), parse_port(port)?)),
+        None if hostport.is_empty() => Ok(("localhost".to_string(), DEFAULT_PORT)),
+        None => Ok((hostport.to_string(), DEFAULT_PORT)),
+    }
+}
+
+pub fn create_nats_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("connect".to_string(), QValue::Fun(QFun {
+        name: "connect".to_string(),
+        parent_type: "nats".to_string(),
+        id: next_object_id(),
+    }));
+
+    QValue::Module(Box::new(QModule::new("nats".to_string(), members)))
+}
+
+pub fn call_nats_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "nats.connect" => Ok(QValue::NatsClient(QNatsClient::connect(args)?)),
+        _ => attr_err!("Unknown function: {}", func_name),
+    }
+}
@@ -4,6 +4,8 @@ use crate::{arg_err, attr_err, value_err};
 use ndarray::{ArrayD, IxDyn};
 use std::collections::HashMap;
 
+mod io;
+
 pub fn create_ndarray_module() -> QValue {
     let mut members = HashMap::new();
 
@@ -16,6 +18,13 @@ pub fn create_ndarray_module() -> QValue {
     members.insert("arange".to_string(), create_fn("ndarray", "arange"));
     members.insert("linspace".to_string(), create_fn("ndarray", "linspace"));
 
+    // File I/O
+    members.insert("save".to_string(), create_fn("ndarray", "save"));
+    members.insert("savez".to_string(), create_fn("ndarray", "savez"));
+    members.insert("load".to_string(), create_fn("ndarray", "load"));
+    members.insert("save_csv".to_string(), create_fn("ndarray", "save_csv"));
+    members.insert("load_csv".to_string(), create_fn("ndarray", "load_csv"));
+
     QValue::Module(Box::new(QModule::new("ndarray".to_string(), members)))
 }
 
@@ -150,6 +159,10 @@ pub fn call_ndarray_function(name: &str, args: Vec<QValue>) -> Result<QValue, Ev
             Ok(QValue::NDArray(QNDArray::new(data)))
         }
 
+        "ndarray.save" | "ndarray.savez" | "ndarray.load" | "ndarray.save_csv" | "ndarray.load_csv" => {
+            io::call_io_function(name, args)
+        }
+
         _ => attr_err!("Unknown ndarray function: {}", name),
     }
 }
@@ -0,0 +1,473 @@
+// File I/O for NDArrays: NumPy's .npy and .npz formats plus CSV text.
+//
+// Only the parts of the formats needed to exchange numeric arrays with NumPy
+// are implemented. Arrays are always written as little-endian f64 ('<f8'),
+// while reading accepts any integer, float or bool dtype and converts to f64.
+
+use crate::types::*;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, type_err, value_err};
+use csv::{ReaderBuilder, WriterBuilder};
+use ndarray::{ArrayD, IxDyn};
+use std::io::Read;
+use indexmap::IndexMap;
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+
+pub fn call_io_function(name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+    match name {
+        "ndarray.save" => {
+            // save("data.npy", arr) - write a single array in NPY format
+            if args.len() != 2 {
+                return arg_err!("save expects 2 arguments (path, array), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let arr = expect_ndarray(&args[1], "save")?;
+            write_file(&path, &encode_npy(arr))?;
+            Ok(QValue::Nil(QNil))
+        }
+
+        "ndarray.savez" => {
+            // savez("data.npz", {weights: w, bias: b}) - write several named arrays
+            if args.len() != 2 {
+                return arg_err!("savez expects 2 arguments (path, arrays), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let arrays = match &args[1] {
+                QValue::Dict(d) => d,
+                other => return type_err!("savez expects a Dict of arrays, got {}", other.as_obj().cls()),
+            };
+
+            let mut names: Vec<String> = arrays.map.borrow().keys().cloned().collect();
+            names.sort();
+            let mut entries = Vec::with_capacity(names.len());
+            for name in names {
+                let value = arrays.get(&name).unwrap();
+                let arr = match &value {
+                    QValue::NDArray(arr) => arr,
+                    other => return type_err!("savez value '{}' must be an NDArray, got {}", name, other.as_obj().cls()),
+                };
+                entries.push((format!("{}.npy", name), encode_npy(arr)));
+            }
+            write_file(&path, &encode_zip(&entries))?;
+            Ok(QValue::Nil(QNil))
+        }
+
+        "ndarray.load" => {
+            // load("data.npy") -> NDArray, load("data.npz") -> Dict of NDArrays
+            if args.len() != 1 {
+                return arg_err!("load expects 1 argument (path), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let bytes = read_file(&path)?;
+
+            if bytes.starts_with(NPY_MAGIC) {
+                return Ok(QValue::NDArray(QNDArray::new(decode_npy(&bytes)?)));
+            }
+            if bytes.len() >= 4 && read_u32(&bytes, 0) == ZIP_LOCAL_HEADER {
+                let mut arrays = IndexMap::new();
+                for (name, data) in decode_zip(&bytes)? {
+                    let key = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+                    arrays.insert(key, QValue::NDArray(QNDArray::new(decode_npy(&data)?)));
+                }
+                return Ok(QValue::Dict(Box::new(QDict::new(arrays))));
+            }
+            value_err!("'{}' is not an NPY or NPZ file", path)
+        }
+
+        "ndarray.save_csv" => {
+            // save_csv("data.csv", arr, delimiter: ",", header: ["a", "b"])
+            if args.len() < 2 || args.len() > 3 {
+                return arg_err!("save_csv expects 2-3 arguments (path, array, [options]), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let arr = expect_ndarray(&args[1], "save_csv")?;
+            let options = csv_options(args.get(2), "save_csv")?;
+            let delimiter = csv_delimiter(options)?;
+
+            let (rows, cols) = match arr.data.shape() {
+                [n] => (*n, 1),
+                [r, c] => (*r, *c),
+                shape => return value_err!("save_csv expects a 1D or 2D array, got shape {:?}", shape),
+            };
+
+            let mut writer = WriterBuilder::new().delimiter(delimiter).from_writer(Vec::new());
+            if let Some(header) = options.and_then(|o| o.get("header")) {
+                let names: Vec<String> = match &header {
+                    QValue::Array(a) => a.elements.borrow().iter().map(|v| v.as_str()).collect(),
+                    other => return type_err!("save_csv header must be an Array, got {}", other.as_obj().cls()),
+                };
+                if names.len() != cols {
+                    return value_err!("save_csv header has {} names but the array has {} columns", names.len(), cols);
+                }
+                writer.write_record(&names).map_err(|e| format!("IOErr: Failed to write CSV: {}", e))?;
+            }
+
+            let values: Vec<f64> = arr.data.iter().copied().collect();
+            for row in 0..rows {
+                let record = values[row * cols..(row + 1) * cols].iter().map(|v| v.to_string());
+                writer.write_record(record).map_err(|e| format!("IOErr: Failed to write CSV: {}", e))?;
+            }
+            let bytes = writer.into_inner().map_err(|e| format!("IOErr: Failed to write CSV: {}", e))?;
+            write_file(&path, &bytes)?;
+            Ok(QValue::Nil(QNil))
+        }
+
+        "ndarray.load_csv" => {
+            // load_csv("data.csv", delimiter: ",", header: true)
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("load_csv expects 1-2 arguments (path, [options]), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let options = csv_options(args.get(1), "load_csv")?;
+            let delimiter = csv_delimiter(options)?;
+            let has_header = options
+                .and_then(|o| o.get("header"))
+                .map(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let bytes = read_file(&path)?;
+            let mut reader = ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(has_header)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(bytes.as_slice());
+
+            let mut values = Vec::new();
+            let mut rows = 0;
+            let mut cols = 0;
+            for (i, record) in reader.records().enumerate() {
+                let record = record.map_err(|e| format!("IOErr: Failed to read CSV: {}", e))?;
+                if i == 0 {
+                    cols = record.len();
+                } else if record.len() != cols {
+                    return value_err!("load_csv row {} has {} columns, expected {}", i + 1, record.len(), cols);
+                }
+                for (j, field) in record.iter().enumerate() {
+                    let value = field.parse::<f64>().map_err(|_| {
+                        format!("ValueErr: load_csv could not parse '{}' at row {}, column {}", field, i + 1, j + 1)
+                    })?;
+                    values.push(value);
+                }
+                rows += 1;
+            }
+
+            // A single column loads as a 1D array, matching numpy.loadtxt
+            let shape = if cols == 1 { vec![rows] } else { vec![rows, cols] };
+            let data = ArrayD::from_shape_vec(IxDyn(&shape), values)
+                .map_err(|e| format!("Failed to create array: {}", e))?;
+            Ok(QValue::NDArray(QNDArray::new(data)))
+        }
+
+        _ => attr_err!("Unknown ndarray function: {}", name),
+    }
+}
+
+fn expect_ndarray<'a>(value: &'a QValue, func: &str) -> Result<&'a QNDArray, String> {
+    match value {
+        QValue::NDArray(arr) => Ok(arr),
+        other => type_err!("{} expects an NDArray, got {}", func, other.as_obj().cls()),
+    }
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("IOErr: Failed to read file '{}': {}", path, e))
+}
+
+fn write_file(path: &str, bytes: &[u8]) -> Result<(), String> {
+    std::fs::write(path, bytes).map_err(|e| format!("IOErr: Failed to write file '{}': {}", path, e))
+}
+
+fn csv_options<'a>(value: Option<&'a QValue>, func: &str) -> Result<Option<&'a QDict>, String> {
+    match value {
+        None => Ok(None),
+        Some(QValue::Dict(d)) => Ok(Some(d)),
+        Some(other) => type_err!("{} options must be Dict, got {}", func, other.as_obj().cls()),
+    }
+}
+
+fn csv_delimiter(options: Option<&QDict>) -> Result<u8, String> {
+    let delimiter = options
+        .and_then(|o| o.get("delimiter"))
+        .map(|v| v.as_str())
+        .unwrap_or_else(|| ",".to_string());
+    if delimiter.len() != 1 {
+        return value_err!("Delimiter must be a single character");
+    }
+    Ok(delimiter.as_bytes()[0])
+}
+
+// ---------------------------------------------------------------------------
+// NPY
+// ---------------------------------------------------------------------------
+
+/// Encode an array as NPY version 1.0, little-endian f64 in C order
+fn encode_npy(arr: &QNDArray) -> Vec<u8> {
+    let shape = match arr.data.shape() {
+        [n] => format!("({},)", n),
+        dims => format!("({})", dims.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape);
+
+    // Pad with spaces so the data starts on a 64-byte boundary, ending in a newline
+    let preamble = NPY_MAGIC.len() + 4;
+    let padding = 64 - (preamble + header.len() + 1) % 64;
+    header.push_str(&" ".repeat(padding % 64));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(preamble + header.len() + arr.data.len() * 8);
+    out.extend_from_slice(NPY_MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for value in arr.data.iter() {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out
+}
+
+fn decode_npy(bytes: &[u8]) -> Result<ArrayD<f64>, String> {
+    if !bytes.starts_with(NPY_MAGIC) || bytes.len() < 10 {
+        return value_err!("Not an NPY file");
+    }
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        2 | 3 if bytes.len() >= 12 => (12, read_u32(bytes, 8) as usize),
+        version => return value_err!("Unsupported NPY version {}", version),
+    };
+    let data_start = header_start + header_len;
+    if bytes.len() < data_start {
+        return value_err!("Truncated NPY header");
+    }
+    let header = String::from_utf8_lossy(&bytes[header_start..data_start]);
+
+    let descr = header_field(&header, "descr")
+        .and_then(|v| v.strip_prefix('\''))
+        .and_then(|v| v.split('\'').next())
+        .ok_or("ValueErr: NPY header is missing 'descr'")?;
+    let fortran_order = header_field(&header, "fortran_order")
+        .map(|v| v.starts_with("True"))
+        .unwrap_or(false);
+    let shape: Vec<usize> = header_field(&header, "shape")
+        .and_then(|v| v.strip_prefix('('))
+        .and_then(|v| v.split(')').next())
+        .ok_or("ValueErr: NPY header is missing 'shape'")?
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>().map_err(|_| format!("ValueErr: Invalid NPY shape entry '{}'", s)))
+        .collect::<Result<_, _>>()?;
+
+    let dtype = Dtype::parse(descr)?;
+    let count: usize = shape.iter().product();
+    let data = &bytes[data_start..];
+    if data.len() < count * dtype.size {
+        return value_err!("NPY data is truncated: expected {} bytes, found {}", count * dtype.size, data.len());
+    }
+    let values: Vec<f64> = data[..count * dtype.size]
+        .chunks_exact(dtype.size)
+        .map(|chunk| dtype.decode(chunk))
+        .collect();
+
+    if fortran_order {
+        // Column-major data is the C-order layout of the reversed shape, transposed
+        let reversed: Vec<usize> = shape.iter().rev().copied().collect();
+        let arr = ArrayD::from_shape_vec(IxDyn(&reversed), values)
+            .map_err(|e| format!("Failed to create array: {}", e))?;
+        Ok(arr.reversed_axes().as_standard_layout().into_owned())
+    } else {
+        ArrayD::from_shape_vec(IxDyn(&shape), values).map_err(|e| format!("Failed to create array: {}", e))
+    }
+}
+
+/// Find the value following `'key':` in an NPY header dict literal
+fn header_field<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let marker = format!("'{}':", key);
+    let start = header.find(&marker)? + marker.len();
+    Some(header[start..].trim_start())
+}
+
+struct Dtype {
+    kind: char,
+    size: usize,
+    big_endian: bool,
+}
+
+impl Dtype {
+    fn parse(descr: &str) -> Result<Self, String> {
+        let mut chars = descr.chars();
+        let (big_endian, kind) = match (chars.next(), chars.next()) {
+            (Some('>'), Some(kind)) => (true, kind),
+            (Some('<' | '|' | '='), Some(kind)) => (false, kind),
+            _ => return value_err!("Unsupported NPY dtype '{}'", descr),
+        };
+        let size: usize = chars.as_str().parse().map_err(|_| format!("ValueErr: Unsupported NPY dtype '{}'", descr))?;
+        match (kind, size) {
+            ('f', 4 | 8) | ('i' | 'u', 1 | 2 | 4 | 8) | ('b', 1) => Ok(Dtype { kind, size, big_endian }),
+            _ => value_err!("Unsupported NPY dtype '{}'", descr),
+        }
+    }
+
+    fn decode(&self, chunk: &[u8]) -> f64 {
+        let mut buf = [0u8; 8];
+        if self.big_endian {
+            for (i, b) in chunk.iter().rev().enumerate() {
+                buf[i] = *b;
+            }
+        } else {
+            buf[..self.size].copy_from_slice(chunk);
+        }
+        match (self.kind, self.size) {
+            ('f', 4) => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ('f', _) => f64::from_le_bytes(buf),
+            ('i', 1) => buf[0] as i8 as f64,
+            ('i', 2) => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ('i', 4) => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ('i', _) => i64::from_le_bytes(buf) as f64,
+            _ => u64::from_le_bytes(buf) as f64,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// NPZ (a zip archive of .npy entries)
+// ---------------------------------------------------------------------------
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// Write an uncompressed zip archive, as numpy.savez does
+fn encode_zip(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in entries {
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+        let offset = out.len() as u32;
+
+        // Fields shared by the local and central headers, from "version needed" on
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        common.extend_from_slice(&0u16.to_le_bytes()); // flags
+        common.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+        common.extend_from_slice(&0u16.to_le_bytes()); // mod time
+        common.extend_from_slice(&0x21u16.to_le_bytes()); // mod date: 1980-01-01
+        common.extend_from_slice(&crc.sum().to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes()); // compressed size
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes()); // uncompressed size
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+
+        out.extend_from_slice(&ZIP_LOCAL_HEADER.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&ZIP_CENTRAL_HEADER.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&common);
+        central.extend_from_slice(&[0; 6]); // comment length, disk, internal attrs
+        central.extend_from_slice(&0u32.to_le_bytes()); // external attrs
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&ZIP_END_OF_CENTRAL_DIR.to_le_bytes());
+    out.extend_from_slice(&[0; 4]); // disk numbers
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    out
+}
+
+/// Read every entry of a zip archive. Handles stored and deflated entries,
+/// including the zip64 size fields numpy.savez writes.
+fn decode_zip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let truncated = || "ValueErr: NPZ archive is truncated or corrupt".to_string();
+
+    // The end of central directory record sits within the last 64KB (comment limit)
+    let search_from = bytes.len().saturating_sub(22 + 0xFFFF);
+    let eocd = (search_from..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(bytes, i) == ZIP_END_OF_CENTRAL_DIR)
+        .ok_or_else(truncated)?;
+    let count = read_u16(bytes, eocd + 10) as usize;
+    let mut pos = read_u32(bytes, eocd + 16) as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if pos + 46 > bytes.len() || read_u32(bytes, pos) != ZIP_CENTRAL_HEADER {
+            return Err(truncated());
+        }
+        let method = read_u16(bytes, pos + 10);
+        let mut compressed = read_u32(bytes, pos + 20) as u64;
+        let mut uncompressed = read_u32(bytes, pos + 24) as u64;
+        let name_len = read_u16(bytes, pos + 28) as usize;
+        let extra_len = read_u16(bytes, pos + 30) as usize;
+        let comment_len = read_u16(bytes, pos + 32) as usize;
+        let mut offset = read_u32(bytes, pos + 42) as u64;
+        let name_end = pos + 46 + name_len;
+        let extra_end = name_end + extra_len;
+        if extra_end > bytes.len() {
+            return Err(truncated());
+        }
+        let name = String::from_utf8_lossy(&bytes[pos + 46..name_end]).to_string();
+
+        // Zip64 extra field: 64-bit values replace any field stored as 0xFFFFFFFF
+        let mut extra = name_end;
+        while extra + 4 <= extra_end {
+            let id = read_u16(bytes, extra);
+            let size = read_u16(bytes, extra + 2) as usize;
+            if id == 0x0001 {
+                let mut field = extra + 4;
+                for value in [&mut uncompressed, &mut compressed, &mut offset] {
+                    if *value == 0xFFFF_FFFF && field + 8 <= extra_end {
+                        *value = u64::from_le_bytes(bytes[field..field + 8].try_into().unwrap());
+                        field += 8;
+                    }
+                }
+            }
+            extra += 4 + size;
+        }
+        pos = extra_end + comment_len;
+
+        let local = offset as usize;
+        if local + 30 > bytes.len() || read_u32(bytes, local) != ZIP_LOCAL_HEADER {
+            return Err(truncated());
+        }
+        let data_start = local + 30 + read_u16(bytes, local + 26) as usize + read_u16(bytes, local + 28) as usize;
+        let data_end = data_start + compressed as usize;
+        if data_end > bytes.len() {
+            return Err(truncated());
+        }
+        let raw = &bytes[data_start..data_end];
+
+        let data = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut out = Vec::with_capacity(uncompressed as usize);
+                flate2::read::DeflateDecoder::new(raw)
+                    .read_to_end(&mut out)
+                    .map_err(|e| format!("ValueErr: Failed to inflate '{}': {}", name, e))?;
+                out
+            }
+            other => return value_err!("Unsupported NPZ compression method {} for '{}'", other, name),
+        };
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
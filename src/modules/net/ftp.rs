@@ -0,0 +1,496 @@
+// FTP client (std/net/ftp)
+//
+//     use "std/net/ftp"
+//     with ftp.connect("ftp.example.com", user: "deploy", password: pw) as conn
+//         conn.upload("dist/site.tar.gz", "incoming/site.tar.gz")
+//         for entry in conn.list("incoming")
+//             puts(entry["name"], " ", entry["size"])
+//         end
+//     end
+//
+// Plain FTP in binary mode with passive data connections (EPSV, falling back
+// to PASV). FTP sends passwords unencrypted; use std/net/sftp where that
+// matters.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::rc::Rc;
+use std::time::Duration;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+use crate::types::*;
+use crate::scope::Scope;
+use super::{list_entry, parse_list_line};
+
+const DEFAULT_TIMEOUT_SECS: f64 = 30.0;
+
+struct Reply {
+    code: u16,
+    text: String,
+}
+
+impl Reply {
+    fn is_ok(&self) -> bool {
+        (200..400).contains(&self.code)
+    }
+}
+
+struct Session {
+    control: BufReader<TcpStream>,
+    peer: IpAddr,
+    timeout: Option<Duration>,
+}
+
+impl Session {
+    fn read_reply(&mut self) -> Result<Reply, String> {
+        let first = self.read_line()?;
+        let code: u16 = first.get(..3).and_then(|c| c.parse().ok())
+            .ok_or_else(|| format!("IOErr: Malformed FTP reply: {}", first))?;
+        let mut text = first[3..].trim_start_matches(['-', ' ']).to_string();
+        // Multi-line replies start with "123-" and end with "123 "
+        if first.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{} ", code);
+            loop {
+                let line = self.read_line()?;
+                let last = line.starts_with(&end) || line == end.trim_end();
+                text.push('\n');
+                text.push_str(line.strip_prefix(&end).unwrap_or(&line));
+                if last {
+                    break;
+                }
+            }
+        }
+        Ok(Reply { code, text })
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        let n = self.control.read_line(&mut line).map_err(io_error)?;
+        if n == 0 {
+            return io_err!("Connection closed by the FTP server");
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    fn send(&mut self, command: &str) -> Result<Reply, String> {
+        if command.contains(['\r', '\n']) {
+            return value_err!("FTP commands cannot contain line breaks");
+        }
+        let stream = self.control.get_mut();
+        stream.write_all(format!("{}\r\n", command).as_bytes()).map_err(io_error)?;
+        stream.flush().map_err(io_error)?;
+        self.read_reply()
+    }
+
+    /// Send a command and raise IOErr unless the reply is 2xx or 3xx
+    fn command(&mut self, command: &str) -> Result<Reply, String> {
+        let reply = self.send(command)?;
+        if !reply.is_ok() {
+            return failed(command, &reply);
+        }
+        Ok(reply)
+    }
+
+    fn login(&mut self, user: &str, password: &str) -> Result<(), String> {
+        let reply = self.send(&format!("USER {}", user))?;
+        let reply = match reply.code {
+            230 => reply,
+            331 | 332 => self.send(&format!("PASS {}", password))?,
+            _ => return failed("USER", &reply),
+        };
+        if reply.code != 230 && reply.code != 202 {
+            return failed("PASS", &reply);
+        }
+        self.command("TYPE I")?;
+        Ok(())
+    }
+
+    /// Open a passive data connection
+    fn data_connection(&mut self) -> Result<TcpStream, String> {
+        let reply = self.send("EPSV")?;
+        let addr = if reply.code == 229 {
+            // 229 Entering Extended Passive Mode (|||6446|)
+            let port = reply.text.split('|').nth(3).and_then(|p| p.parse::<u16>().ok())
+                .ok_or_else(|| format!("IOErr: Malformed EPSV reply: {}", reply.text))?;
+            SocketAddr::new(self.peer, port)
+        } else {
+            let reply = self.command("PASV")?;
+            // 227 Entering Passive Mode (h1,h2,h3,h4,p1,p2). The address is
+            // ignored in favour of the control connection's, as NATs rewrite it.
+            let numbers: Vec<u16> = reply.text
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|s| !s.is_empty())
+                .filter_map(|s| s.parse().ok())
+                .collect();
+            let [.., p1, p2] = numbers.as_slice() else {
+                return io_err!("Malformed PASV reply: {}", reply.text);
+            };
+            SocketAddr::new(self.peer, p1 * 256 + p2)
+        };
+        let stream = match self.timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        }.map_err(|e| format!("IOErr: Cannot open FTP data connection: {}", e))?;
+        stream.set_read_timeout(self.timeout).map_err(io_error)?;
+        stream.set_write_timeout(self.timeout).map_err(io_error)?;
+        Ok(stream)
+    }
+
+    /// Run a transfer command; `transfer` reads or writes the data connection
+    fn transfer<T>(&mut self, command: &str, transfer: impl FnOnce(&mut TcpStream) -> Result<T, String>) -> Result<T, String> {
+        let mut data = self.data_connection()?;
+        let reply = self.send(command)?;
+        if reply.code >= 300 {
+            return failed(command, &reply);
+        }
+        let result = transfer(&mut data);
+        drop(data);
+        // A 2xx straight away means the server finished before we read
+        if reply.code < 200 {
+            let done = self.read_reply()?;
+            if !done.is_ok() {
+                return failed(command, &done);
+            }
+        }
+        result
+    }
+
+    fn retrieve(&mut self, command: &str) -> Result<Vec<u8>, String> {
+        self.transfer(command, |data| {
+            let mut buf = Vec::new();
+            data.read_to_end(&mut buf).map_err(io_error)?;
+            Ok(buf)
+        })
+    }
+
+    fn store(&mut self, remote: &str, mut source: impl Read) -> Result<u64, String> {
+        self.transfer(&format!("STOR {}", remote), |data| {
+            let sent = std::io::copy(&mut source, data).map_err(io_error)?;
+            data.flush().map_err(io_error)?;
+            Ok(sent)
+        })
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<QValue>, String> {
+        let command = if path.is_empty() { "MLSD".to_string() } else { format!("MLSD {}", path) };
+        match self.retrieve(&command) {
+            Ok(listing) => Ok(String::from_utf8_lossy(&listing).lines().filter_map(parse_mlsd_line).collect()),
+            // 500 / 502: the server doesn't know MLSD; fall back to ls-style LIST
+            Err(e) if e.contains("FTP MLSD failed: 50") => {
+                let command = if path.is_empty() { "LIST".to_string() } else { format!("LIST {}", path) };
+                let listing = self.retrieve(&command)?;
+                Ok(String::from_utf8_lossy(&listing).lines().filter_map(parse_list_line).collect())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn failed<T>(command: &str, reply: &Reply) -> Result<T, String> {
+    let verb = command.split(' ').next().unwrap_or(command);
+    io_err!("FTP {} failed: {} {}", verb, reply.code, reply.text)
+}
+
+fn io_error(e: std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => "IOErr: FTP server timed out".to_string(),
+        _ => format!("IOErr: {}", e),
+    }
+}
+
+/// `type=file;size=1234;modify=20250101120000; name` (RFC 3659)
+fn parse_mlsd_line(line: &str) -> Option<QValue> {
+    let (facts, name) = line.split_once(' ')?;
+    let mut kind = "";
+    let mut size = 0;
+    let mut modified = QValue::Nil(QNil);
+    let mut permissions = QValue::Nil(QNil);
+    for fact in facts.split(';').filter(|f| !f.is_empty()) {
+        let (key, value) = fact.split_once('=')?;
+        match key.to_ascii_lowercase().as_str() {
+            "type" => kind = value,
+            "size" => size = value.parse().unwrap_or(0),
+            "modify" if value.len() >= 14 => {
+                // 20250101120000[.sss] -> 2025-01-01T12:00:00Z
+                let v = value;
+                let iso = format!("{}-{}-{}T{}:{}:{}Z", &v[0..4], &v[4..6], &v[6..8], &v[8..10], &v[10..12], &v[12..14]);
+                modified = QValue::Str(QString::new(iso));
+            }
+            "unix.mode" => permissions = QValue::Str(QString::new(value.to_string())),
+            _ => {}
+        }
+    }
+    let kind = kind.to_ascii_lowercase();
+    if kind == "cdir" || kind == "pdir" {
+        return None;
+    }
+    Some(list_entry(name, size, kind == "dir", modified, permissions))
+}
+
+#[derive(Clone)]
+pub struct QFtpClient {
+    session: Rc<RefCell<Option<Session>>>,
+    host: String,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QFtpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QFtpClient").field("host", &self.host).field("id", &self.id).finish()
+    }
+}
+
+impl QFtpClient {
+    fn connect(args: Vec<QValue>) -> Result<Self, String> {
+        // ftp.connect(host, port: 21, user: "anonymous", password: "", timeout: 30)
+        let (positional, options) = match args.as_slice() {
+            [rest @ .., QValue::Dict(d)] if !rest.is_empty() => (rest, Some(d.as_ref().clone())),
+            _ => (args.as_slice(), None),
+        };
+        let [host] = positional else {
+            return arg_err!("ftp.connect expects 1 argument (host), got {}", positional.len());
+        };
+        let host = host.as_str();
+        let get = |key: &str| options.as_ref().and_then(|d| d.get(key)).filter(|v| !matches!(v, QValue::Nil(_)));
+        let get_str = |key: &str, default: &str| -> Result<String, String> {
+            match get(key) {
+                None => Ok(default.to_string()),
+                Some(QValue::Str(s)) => Ok(s.value.to_string()),
+                Some(other) => type_err!("ftp.connect {} must be Str, got {}", key, other.as_obj().cls()),
+            }
+        };
+
+        let port = match get("port") {
+            None => 21,
+            Some(QValue::Int(n)) if (1..=65535).contains(&n.value) => n.value as u16,
+            Some(other) => return value_err!("ftp.connect port must be an Int between 1 and 65535, got {}", other.as_str()),
+        };
+        let secs = match get("timeout") {
+            None => DEFAULT_TIMEOUT_SECS,
+            Some(QValue::Int(n)) => n.value as f64,
+            Some(QValue::Float(f)) => f.value,
+            Some(other) => return type_err!("ftp.connect timeout must be a number of seconds, got {}", other.as_obj().cls()),
+        };
+        if secs < 0.0 {
+            return value_err!("ftp.connect timeout must not be negative, got {}", secs);
+        }
+        // 0 waits forever
+        let timeout = (secs > 0.0).then(|| Duration::from_secs_f64(secs));
+        let user = get_str("user", "anonymous")?;
+        let password = get_str("password", "")?;
+
+        let addr = (host.as_str(), port).to_socket_addrs()
+            .map_err(|e| format!("IOErr: Cannot resolve {}: {}", host, e))?
+            .next()
+            .ok_or_else(|| format!("IOErr: Cannot resolve {}", host))?;
+        let stream = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        }.map_err(|e| format!("IOErr: Cannot connect to {}:{}: {}", host, port, e))?;
+        stream.set_read_timeout(timeout).map_err(io_error)?;
+        stream.set_write_timeout(timeout).map_err(io_error)?;
+
+        let mut session = Session { control: BufReader::new(stream), peer: addr.ip(), timeout };
+        let greeting = session.read_reply()?;
+        if greeting.code != 220 {
+            return failed("connect", &greeting);
+        }
+        session.login(&user, &password)?;
+
+        Ok(QFtpClient {
+            session: Rc::new(RefCell::new(Some(session))),
+            host,
+            id: next_object_id(),
+        })
+    }
+
+    fn with_session<T>(&self, f: impl FnOnce(&mut Session) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.session.borrow_mut();
+        match guard.as_mut() {
+            Some(session) => f(session),
+            None => io_err!("FTP connection is closed"),
+        }
+    }
+
+    fn quit(&self) -> Result<(), String> {
+        let Some(mut session) = self.session.borrow_mut().take() else {
+            return Ok(());
+        };
+        session.command("QUIT").map(|_| ())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        let path_arg = |index: usize| -> Result<String, String> {
+            match args.get(index) {
+                Some(QValue::Str(s)) => Ok(s.value.to_string()),
+                Some(other) => type_err!("{} expects Str paths, got {}", method_name, other.as_obj().cls()),
+                None => arg_err!("{} expects {} arguments, got {}", method_name, index + 1, args.len()),
+            }
+        };
+        let expect_args = |count: usize| -> Result<(), String> {
+            if args.len() != count {
+                return arg_err!("{} expects {} arguments, got {}", method_name, count, args.len());
+            }
+            Ok(())
+        };
+
+        match method_name {
+            "list" | "names" => {
+                let path = match args.len() {
+                    0 => String::new(),
+                    1 => path_arg(0)?,
+                    n => return arg_err!("{} expects 0 or 1 arguments, got {}", method_name, n),
+                };
+                if method_name == "list" {
+                    return Ok(QValue::Array(QArray::new(self.with_session(|s| s.list(&path))?)));
+                }
+                let command = if path.is_empty() { "NLST".to_string() } else { format!("NLST {}", path) };
+                let listing = self.with_session(|s| s.retrieve(&command))?;
+                let names = String::from_utf8_lossy(&listing).lines()
+                    .filter(|l| !l.is_empty())
+                    .map(|l| QValue::Str(QString::new(l.to_string())))
+                    .collect();
+                Ok(QValue::Array(QArray::new(names)))
+            }
+            "upload" => {
+                expect_args(2)?;
+                let (local, remote) = (path_arg(0)?, path_arg(1)?);
+                let file = std::fs::File::open(&local)
+                    .map_err(|e| format!("IOErr: Cannot read '{}': {}", local, e))?;
+                let sent = self.with_session(|s| s.store(&remote, file))?;
+                Ok(QValue::Int(QInt::new(sent as i64)))
+            }
+            "download" => {
+                expect_args(2)?;
+                let (remote, local) = (path_arg(0)?, path_arg(1)?);
+                let data = self.with_session(|s| s.retrieve(&format!("RETR {}", remote)))?;
+                std::fs::write(&local, &data)
+                    .map_err(|e| format!("IOErr: Cannot write '{}': {}", local, e))?;
+                Ok(QValue::Int(QInt::new(data.len() as i64)))
+            }
+            "read" => {
+                expect_args(1)?;
+                let remote = path_arg(0)?;
+                let data = self.with_session(|s| s.retrieve(&format!("RETR {}", remote)))?;
+                Ok(QValue::Bytes(QBytes::new(data)))
+            }
+            "write" => {
+                expect_args(2)?;
+                let remote = path_arg(0)?;
+                let data = match &args[1] {
+                    QValue::Bytes(b) => b.data.clone(),
+                    QValue::Str(s) => s.value.as_bytes().to_vec(),
+                    other => return type_err!("write expects Str or Bytes content, got {}", other.as_obj().cls()),
+                };
+                let sent = self.with_session(|s| s.store(&remote, data.as_slice()))?;
+                Ok(QValue::Int(QInt::new(sent as i64)))
+            }
+            "rename" => {
+                expect_args(2)?;
+                let (from, target) = (path_arg(0)?, path_arg(1)?);
+                self.with_session(|s| {
+                    s.command(&format!("RNFR {}", from))?;
+                    s.command(&format!("RNTO {}", target))
+                })?;
+                Ok(QValue::Nil(QNil))
+            }
+            "delete" | "mkdir" | "rmdir" | "cd" => {
+                expect_args(1)?;
+                let path = path_arg(0)?;
+                let verb = match method_name {
+                    "delete" => "DELE",
+                    "mkdir" => "MKD",
+                    "rmdir" => "RMD",
+                    _ => "CWD",
+                };
+                self.with_session(|s| s.command(&format!("{} {}", verb, path)))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "pwd" => {
+                expect_args(0)?;
+                // 257 "/home/user" is the current directory
+                let reply = self.with_session(|s| s.command("PWD"))?;
+                let dir = match (reply.text.find('"'), reply.text.rfind('"')) {
+                    (Some(start), Some(end)) if end > start => reply.text[start + 1..end].replace("\"\"", "\""),
+                    _ => reply.text,
+                };
+                Ok(QValue::Str(QString::new(dir)))
+            }
+            "size" => {
+                expect_args(1)?;
+                let path = path_arg(0)?;
+                let reply = self.with_session(|s| s.command(&format!("SIZE {}", path)))?;
+                match reply.text.trim().parse::<i64>() {
+                    Ok(size) => Ok(QValue::Int(QInt::new(size))),
+                    Err(_) => io_err!("Malformed SIZE reply: {}", reply.text),
+                }
+            }
+            "quit" | "close" => {
+                self.quit()?;
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.session.borrow().is_none()))),
+            "_enter" => Ok(QValue::FtpClient(self.clone())),
+            "_exit" => {
+                let _ = self.quit();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on FtpClient", method_name),
+        }
+    }
+}
+
+impl QObj for QFtpClient {
+    fn cls(&self) -> String {
+        "FtpClient".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "FtpClient"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "FtpClient"
+    }
+
+    fn str(&self) -> String {
+        format!("<FtpClient {}>", self.host)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "FTP connection".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_ftp_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("connect".to_string(), QValue::Fun(QFun {
+        name: "connect".to_string(),
+        parent_type: "ftp".to_string(),
+        id: next_object_id(),
+    }));
+
+    QValue::Module(Box::new(QModule::new("ftp".to_string(), members)))
+}
+
+pub fn call_ftp_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "ftp.connect" => Ok(QValue::FtpClient(QFtpClient::connect(args)?)),
+        _ => attr_err!("Unknown function: {}", func_name),
+    }
+}
@@ -0,0 +1,611 @@
+// std/net/ip - IP addresses and CIDR networks
+//
+// IpAddr wraps std::net::IpAddr and Cidr wraps ipnet::IpNet (normalized to
+// its network address). Both are immutable. Address classification uses the
+// IANA special-purpose registries; an IPv4-mapped IPv6 address
+// (::ffff:10.0.0.1) is classified as the IPv4 address it carries.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+use num_bigint::BigInt;
+
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, attr_err, index_err, type_err, value_err};
+
+/// hosts() and subnets() refuse to build Arrays longer than this without a limit
+const MAX_ENUMERATE: u128 = 65536;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Range {
+    Private,
+    Loopback,
+    LinkLocal,
+    Multicast,
+    Unspecified,
+    Documentation,
+    Shared,
+    Reserved,
+    /// Special-purpose but none of the above (benchmarking, IETF protocol, discard, ...)
+    Other,
+}
+
+lazy_static! {
+    static ref SPECIAL: Vec<(IpNet, Range)> = [
+        ("10.0.0.0/8", Range::Private),
+        ("172.16.0.0/12", Range::Private),
+        ("192.168.0.0/16", Range::Private),
+        ("fc00::/7", Range::Private),
+        ("127.0.0.0/8", Range::Loopback),
+        ("::1/128", Range::Loopback),
+        ("169.254.0.0/16", Range::LinkLocal),
+        ("fe80::/10", Range::LinkLocal),
+        ("224.0.0.0/4", Range::Multicast),
+        ("ff00::/8", Range::Multicast),
+        ("0.0.0.0/32", Range::Unspecified),
+        ("::/128", Range::Unspecified),
+        ("192.0.2.0/24", Range::Documentation),
+        ("198.51.100.0/24", Range::Documentation),
+        ("203.0.113.0/24", Range::Documentation),
+        ("2001:db8::/32", Range::Documentation),
+        ("100.64.0.0/10", Range::Shared),
+        ("240.0.0.0/4", Range::Reserved),
+        ("0.0.0.0/8", Range::Other),
+        ("192.0.0.0/24", Range::Other),
+        ("198.18.0.0/15", Range::Other),
+        ("100::/64", Range::Other),
+        ("2001::/23", Range::Other),
+    ].iter().map(|(net, range)| (net.parse().expect("valid special range"), *range)).collect();
+}
+
+/// Classify through IPv4-mapped IPv6 addresses
+fn unmapped(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => addr,
+        },
+        v4 => v4,
+    }
+}
+
+fn unmapped_net(net: IpNet) -> IpNet {
+    match (net, unmapped(net.network())) {
+        (IpNet::V6(v6), IpAddr::V4(v4)) if v6.prefix_len() >= 96 => {
+            IpNet::new(IpAddr::V4(v4), v6.prefix_len() - 96).unwrap_or(net)
+        }
+        _ => net,
+    }
+}
+
+/// Whether the whole network lies in a range of this kind
+fn in_range(net: IpNet, range: Range) -> bool {
+    let net = unmapped_net(net);
+    SPECIAL.iter().any(|(special, kind)| *kind == range && special.contains(&net))
+}
+
+fn is_global(net: IpNet) -> bool {
+    let net = unmapped_net(net);
+    !SPECIAL.iter().any(|(special, _)| special.contains(&net) || net.contains(special))
+        && net != "255.255.255.255/32".parse::<IpNet>().expect("valid broadcast")
+}
+
+fn host_net(addr: IpAddr) -> IpNet {
+    IpNet::from(addr)
+}
+
+fn to_u128(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(v4) => u32::from(v4) as u128,
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+fn from_u128(value: u128, v6: bool) -> Option<IpAddr> {
+    if v6 {
+        Some(IpAddr::V6(Ipv6Addr::from(value)))
+    } else {
+        u32::try_from(value).ok().map(|v| IpAddr::V4(Ipv4Addr::from(v)))
+    }
+}
+
+fn int_value(n: u128) -> QValue {
+    match i64::try_from(n) {
+        Ok(n) => QValue::Int(QInt::new(n)),
+        Err(_) => QValue::BigInt(QBigInt::new(BigInt::from(n))),
+    }
+}
+
+fn ip_value(addr: IpAddr) -> QValue {
+    QValue::IpAddr(QIpAddr::new(addr))
+}
+
+fn cidr_value(net: IpNet) -> QValue {
+    QValue::Cidr(QCidr::new(net))
+}
+
+fn parse_addr(text: &str) -> Result<IpAddr, String> {
+    text.trim().parse::<IpAddr>()
+        .map_err(|_| format!("ValueErr: Invalid IP address: '{}'", text))
+}
+
+/// Parse "10.0.0.0/8", or a bare address as a single-address network.
+/// With strict, host bits after the prefix must be zero.
+fn parse_net(text: &str, strict: bool) -> Result<IpNet, String> {
+    let text = text.trim();
+    let net = if text.contains('/') {
+        text.parse::<IpNet>().map_err(|_| format!("ValueErr: Invalid CIDR network: '{}'", text))?
+    } else {
+        host_net(parse_addr(text)?)
+    };
+    if strict && net.addr() != net.network() {
+        return value_err!("'{}' has host bits set (network is {})", text, net.trunc());
+    }
+    Ok(net.trunc())
+}
+
+/// An address from an IpAddr or Str argument
+fn addr_arg(value: &QValue) -> Result<IpAddr, String> {
+    match value {
+        QValue::IpAddr(a) => Ok(a.addr),
+        QValue::Str(s) => parse_addr(&s.value),
+        _ => type_err!("Expected an IP address (IpAddr or Str), got {}", value.as_obj().cls()),
+    }
+}
+
+/// A network from a Cidr, IpAddr (single address) or Str argument
+fn net_arg(value: &QValue) -> Result<IpNet, String> {
+    match value {
+        QValue::Cidr(n) => Ok(n.net),
+        QValue::IpAddr(a) => Ok(host_net(a.addr)),
+        QValue::Str(s) => parse_net(&s.value, false),
+        _ => type_err!("Expected a network (Cidr, IpAddr or Str), got {}", value.as_obj().cls()),
+    }
+}
+
+fn check_count(method: &str, args: &[QValue], expected: usize, usage: &str) -> Result<(), String> {
+    if args.len() != expected {
+        if expected == 0 {
+            return arg_err!("{} expects 0 arguments, got {}", method, args.len());
+        }
+        return arg_err!("{} expects {} argument ({}), got {}", method, expected, usage, args.len());
+    }
+    Ok(())
+}
+
+/// Address classification methods shared by IpAddr and Cidr
+fn classify(method: &str, net: IpNet) -> Option<bool> {
+    Some(match method {
+        "is_private" => in_range(net, Range::Private),
+        "is_loopback" => in_range(net, Range::Loopback),
+        "is_link_local" => in_range(net, Range::LinkLocal),
+        "is_multicast" => in_range(net, Range::Multicast),
+        "is_unspecified" => in_range(net, Range::Unspecified),
+        "is_documentation" => in_range(net, Range::Documentation),
+        "is_shared" => in_range(net, Range::Shared),
+        "is_reserved" => in_range(net, Range::Reserved),
+        "is_global" => is_global(net),
+        _ => return None,
+    })
+}
+
+/// An optional Int limit for hosts()/subnets(), positional or `limit:`
+fn limit_arg(method: &str, args: &[QValue], index: usize) -> Result<Option<u128>, String> {
+    match args.get(index) {
+        None | Some(QValue::Nil(_)) => Ok(None),
+        Some(QValue::Dict(options)) => match options.get("limit") {
+            Some(limit) => limit_arg(method, &[limit], 0),
+            None => Ok(None),
+        },
+        Some(QValue::Int(i)) if i.value >= 0 => Ok(Some(i.value as u128)),
+        Some(QValue::Int(i)) => value_err!("{} limit cannot be negative, got {}", method, i.value),
+        Some(other) => type_err!("{} limit must be Int, got {}", method, other.as_obj().cls()),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QIpAddr {
+    pub addr: IpAddr,
+    pub id: u64,
+}
+
+impl QIpAddr {
+    pub fn new(addr: IpAddr) -> Self {
+        QIpAddr { addr, id: next_object_id() }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        // Try QObj trait methods first
+        use crate::types::try_call_qobj_method;
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        if let Some(result) = classify(method_name, host_net(self.addr)) {
+            check_count(method_name, &args, 0, "")?;
+            return Ok(QValue::Bool(QBool::new(result)));
+        }
+
+        match method_name {
+            "version" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(QValue::Int(QInt::new(if self.addr.is_ipv4() { 4 } else { 6 })))
+            }
+            "to_int" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(int_value(to_u128(self.addr)))
+            }
+            "to_bytes" => {
+                check_count(method_name, &args, 0, "")?;
+                let bytes = match self.addr {
+                    IpAddr::V4(v4) => v4.octets().to_vec(),
+                    IpAddr::V6(v6) => v6.octets().to_vec(),
+                };
+                Ok(QValue::Bytes(QBytes::new(bytes)))
+            }
+            "reverse_pointer" => {
+                // PTR record name: 1.0.168.192.in-addr.arpa
+                check_count(method_name, &args, 0, "")?;
+                let name = match self.addr {
+                    IpAddr::V4(v4) => {
+                        let octets: Vec<String> = v4.octets().iter().rev().map(|o| o.to_string()).collect();
+                        format!("{}.in-addr.arpa", octets.join("."))
+                    }
+                    IpAddr::V6(v6) => {
+                        let nibbles: Vec<String> = format!("{:032x}", u128::from(v6)).chars().rev().map(|c| c.to_string()).collect();
+                        format!("{}.ip6.arpa", nibbles.join("."))
+                    }
+                };
+                Ok(QValue::Str(QString::new(name)))
+            }
+            "add" => {
+                // add(n) - the address n places later (or earlier, for negative n)
+                check_count(method_name, &args, 1, "n")?;
+                let QValue::Int(n) = &args[0] else {
+                    return type_err!("add expects an Int, got {}", args[0].as_obj().cls());
+                };
+                let base = to_u128(self.addr);
+                let next = if n.value >= 0 {
+                    base.checked_add(n.value as u128)
+                } else {
+                    base.checked_sub(n.value.unsigned_abs() as u128)
+                };
+                match next.and_then(|v| from_u128(v, self.addr.is_ipv6())) {
+                    Some(addr) => Ok(ip_value(addr)),
+                    None => value_err!("{} + {} is outside the IPv{} address space", self.addr, n.value, if self.addr.is_ipv4() { 4 } else { 6 }),
+                }
+            }
+            "ipv4_mapped" => {
+                // The IPv4 address in ::ffff:a.b.c.d, or nil
+                check_count(method_name, &args, 0, "")?;
+                Ok(match self.addr {
+                    IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                        Some(v4) => ip_value(IpAddr::V4(v4)),
+                        None => QValue::Nil(QNil),
+                    },
+                    IpAddr::V4(_) => QValue::Nil(QNil),
+                })
+            }
+            "to_ipv6" => {
+                // IPv4 addresses become ::ffff:a.b.c.d
+                check_count(method_name, &args, 0, "")?;
+                Ok(match self.addr {
+                    IpAddr::V4(v4) => ip_value(IpAddr::V6(v4.to_ipv6_mapped())),
+                    IpAddr::V6(_) => ip_value(self.addr),
+                })
+            }
+            "in" => {
+                // in(network) - same as network.contains(self)
+                check_count(method_name, &args, 1, "network")?;
+                Ok(QValue::Bool(QBool::new(net_arg(&args[0])?.contains(&self.addr))))
+            }
+            "eq" | "neq" => {
+                check_count(method_name, &args, 1, "other")?;
+                let same = matches!(&args[0], QValue::IpAddr(other) if other.addr == self.addr);
+                Ok(QValue::Bool(QBool::new(same == (method_name == "eq"))))
+            }
+            _ => attr_err!("Unknown method '{}' on IpAddr", method_name),
+        }
+    }
+}
+
+impl QObj for QIpAddr {
+    fn cls(&self) -> String {
+        "IpAddr".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "IpAddr"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "IpAddr"
+    }
+
+    fn str(&self) -> String {
+        self.addr.to_string()
+    }
+
+    fn _rep(&self) -> String {
+        format!("IpAddr({})", self.addr)
+    }
+
+    fn _doc(&self) -> String {
+        "An IPv4 or IPv6 address".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QCidr {
+    pub net: IpNet,
+    pub id: u64,
+}
+
+impl QCidr {
+    pub fn new(net: IpNet) -> Self {
+        QCidr { net, id: next_object_id() }
+    }
+
+    fn size(&self) -> Option<u128> {
+        let bits = self.net.max_prefix_len() - self.net.prefix_len();
+        1u128.checked_shl(bits as u32)
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        // Try QObj trait methods first
+        use crate::types::try_call_qobj_method;
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        if let Some(result) = classify(method_name, self.net) {
+            check_count(method_name, &args, 0, "")?;
+            return Ok(QValue::Bool(QBool::new(result)));
+        }
+
+        match method_name {
+            "network" | "first" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(ip_value(self.net.network()))
+            }
+            "broadcast" | "last" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(ip_value(self.net.broadcast()))
+            }
+            "netmask" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(ip_value(self.net.netmask()))
+            }
+            "hostmask" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(ip_value(self.net.hostmask()))
+            }
+            "prefix" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(QValue::Int(QInt::new(self.net.prefix_len() as i64)))
+            }
+            "version" => {
+                check_count(method_name, &args, 0, "")?;
+                Ok(QValue::Int(QInt::new(if self.net.network().is_ipv4() { 4 } else { 6 })))
+            }
+            "size" => {
+                // Number of addresses, including network and broadcast
+                check_count(method_name, &args, 0, "")?;
+                Ok(match self.size() {
+                    Some(n) => int_value(n),
+                    None => QValue::BigInt(QBigInt::new(BigInt::from(1) << 128)),
+                })
+            }
+            "contains" => {
+                // contains(ip_or_network)
+                check_count(method_name, &args, 1, "ip")?;
+                Ok(QValue::Bool(QBool::new(self.net.contains(&net_arg(&args[0])?))))
+            }
+            "overlaps" => {
+                check_count(method_name, &args, 1, "network")?;
+                let other = net_arg(&args[0])?;
+                Ok(QValue::Bool(QBool::new(self.net.contains(&other) || other.contains(&self.net))))
+            }
+            "address" => {
+                // address(i) - the i-th address; negative counts from the end
+                check_count(method_name, &args, 1, "index")?;
+                let QValue::Int(i) = &args[0] else {
+                    return type_err!("address expects an Int, got {}", args[0].as_obj().cls());
+                };
+                let size = self.size().unwrap_or(u128::MAX);
+                let offset = if i.value >= 0 {
+                    Some(i.value as u128).filter(|&o| o < size)
+                } else {
+                    size.checked_sub(i.value.unsigned_abs() as u128)
+                };
+                let Some(offset) = offset else {
+                    return index_err!("Address index {} out of range for {}", i.value, self.net);
+                };
+                let addr = from_u128(to_u128(self.net.network()) + offset, self.net.network().is_ipv6());
+                Ok(ip_value(addr.expect("offset within network")))
+            }
+            "hosts" => {
+                // hosts(limit = nil) - usable addresses; IPv4 excludes network and broadcast below /31
+                if args.len() > 1 {
+                    return arg_err!("hosts expects 0 or 1 arguments (limit), got {}", args.len());
+                }
+                let limit = limit_arg(method_name, &args, 0)?;
+                if limit.is_none() && self.size().is_none_or(|n| n > MAX_ENUMERATE) {
+                    return value_err!("{} has too many hosts to list; pass a limit or split it with subnets()", self.net);
+                }
+                let take = limit.unwrap_or(MAX_ENUMERATE).min(usize::MAX as u128) as usize;
+                Ok(QValue::Array(QArray::new(self.net.hosts().take(take).map(ip_value).collect())))
+            }
+            "subnets" => {
+                // subnets(prefix, limit = nil) - split into networks with a longer prefix
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("subnets expects 1 or 2 arguments (prefix, limit), got {}", args.len());
+                }
+                let QValue::Int(prefix) = &args[0] else {
+                    return type_err!("subnets prefix must be Int, got {}", args[0].as_obj().cls());
+                };
+                let prefix = prefix.value;
+                if prefix < self.net.prefix_len() as i64 || prefix > self.net.max_prefix_len() as i64 {
+                    return value_err!("subnets prefix must be between {} and {}, got {}", self.net.prefix_len(), self.net.max_prefix_len(), prefix);
+                }
+                let count = 1u128.checked_shl((prefix as u32) - self.net.prefix_len() as u32);
+                let limit = limit_arg(method_name, &args, 1)?;
+                if limit.is_none() && count.is_none_or(|n| n > MAX_ENUMERATE) {
+                    return value_err!("{} splits into too many /{} subnets to list; pass a limit", self.net, prefix);
+                }
+                let take = limit.unwrap_or(MAX_ENUMERATE).min(usize::MAX as u128) as usize;
+                let subnets = self.net.subnets(prefix as u8)
+                    .map_err(|_| format!("ValueErr: Cannot split {} into /{} subnets", self.net, prefix))?;
+                Ok(QValue::Array(QArray::new(subnets.take(take).map(cidr_value).collect())))
+            }
+            "supernet" => {
+                // supernet(prefix = prefix - 1) - the enclosing network
+                if args.len() > 1 {
+                    return arg_err!("supernet expects 0 or 1 arguments (prefix), got {}", args.len());
+                }
+                let prefix = match args.first() {
+                    None => self.net.prefix_len() as i64 - 1,
+                    Some(QValue::Int(p)) => p.value,
+                    Some(other) => return type_err!("supernet prefix must be Int, got {}", other.as_obj().cls()),
+                };
+                if prefix < 0 || prefix > self.net.prefix_len() as i64 {
+                    return value_err!("supernet prefix must be between 0 and {}, got {}", self.net.prefix_len(), prefix);
+                }
+                let net = IpNet::new(self.net.network(), prefix as u8).expect("valid prefix").trunc();
+                Ok(cidr_value(net))
+            }
+            "eq" | "neq" => {
+                check_count(method_name, &args, 1, "other")?;
+                let same = matches!(&args[0], QValue::Cidr(other) if other.net == self.net);
+                Ok(QValue::Bool(QBool::new(same == (method_name == "eq"))))
+            }
+            _ => attr_err!("Unknown method '{}' on Cidr", method_name),
+        }
+    }
+}
+
+impl QObj for QCidr {
+    fn cls(&self) -> String {
+        "Cidr".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Cidr"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Cidr"
+    }
+
+    fn str(&self) -> String {
+        self.net.to_string()
+    }
+
+    fn _rep(&self) -> String {
+        format!("Cidr({})", self.net)
+    }
+
+    fn _doc(&self) -> String {
+        "An IPv4 or IPv6 network in CIDR notation".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_ip_module() -> QValue {
+    let mut members = HashMap::new();
+    members.insert("parse".to_string(), create_fn("ip", "parse"));
+    members.insert("cidr".to_string(), create_fn("ip", "cidr"));
+    members.insert("is_valid".to_string(), create_fn("ip", "is_valid"));
+    members.insert("is_valid_cidr".to_string(), create_fn("ip", "is_valid_cidr"));
+    members.insert("from_int".to_string(), create_fn("ip", "from_int"));
+    members.insert("collapse".to_string(), create_fn("ip", "collapse"));
+    QValue::Module(Box::new(QModule::new("ip".to_string(), members)))
+}
+
+/// Handle ip.* function calls
+pub fn call_ip_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "ip.parse" => {
+            check_count("ip.parse", &args, 1, "address")?;
+            Ok(ip_value(addr_arg(&args[0])?))
+        }
+        "ip.cidr" => {
+            // cidr(text, strict = false) - host bits are cleared unless strict
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("ip.cidr expects 1 or 2 arguments (network, strict), got {}", args.len());
+            }
+            let strict = match args.get(1) {
+                Some(QValue::Dict(options)) => options.get("strict").is_some_and(|v| v.as_bool()),
+                Some(value) => value.as_bool(),
+                None => false,
+            };
+            let net = match &args[0] {
+                QValue::Str(s) => parse_net(&s.value, strict)?,
+                other => net_arg(other)?,
+            };
+            Ok(cidr_value(net))
+        }
+        "ip.is_valid" => {
+            check_count("ip.is_valid", &args, 1, "address")?;
+            Ok(QValue::Bool(QBool::new(args[0].as_str().trim().parse::<IpAddr>().is_ok())))
+        }
+        "ip.is_valid_cidr" => {
+            check_count("ip.is_valid_cidr", &args, 1, "network")?;
+            Ok(QValue::Bool(QBool::new(parse_net(&args[0].as_str(), false).is_ok())))
+        }
+        "ip.from_int" => {
+            // from_int(n, version = 4)
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("ip.from_int expects 1 or 2 arguments (n, version), got {}", args.len());
+            }
+            let version = match args.get(1) {
+                None => 4,
+                Some(QValue::Int(v)) if v.value == 4 || v.value == 6 => v.value,
+                Some(QValue::Dict(options)) => match options.get("version") {
+                    Some(QValue::Int(v)) if v.value == 4 || v.value == 6 => v.value,
+                    None => 4,
+                    Some(other) => return value_err!("version must be 4 or 6, got {}", other.as_str()),
+                },
+                Some(other) => return value_err!("version must be 4 or 6, got {}", other.as_str()),
+            };
+            let n: Option<u128> = match &args[0] {
+                QValue::Int(i) => u128::try_from(i.value).ok(),
+                QValue::BigInt(b) => u128::try_from(&b.value).ok(),
+                other => return type_err!("ip.from_int expects an Int, got {}", other.as_obj().cls()),
+            };
+            match n.and_then(|n| from_u128(n, version == 6)) {
+                Some(addr) => Ok(ip_value(addr)),
+                None => value_err!("{} is not a valid IPv{} address number", args[0].as_str(), version),
+            }
+        }
+        "ip.collapse" => {
+            // collapse(networks) - merge overlapping and adjacent networks
+            check_count("ip.collapse", &args, 1, "networks")?;
+            let QValue::Array(items) = &args[0] else {
+                return type_err!("ip.collapse expects an Array of networks, got {}", args[0].as_obj().cls());
+            };
+            let nets = items.elements.borrow().iter().map(net_arg).collect::<Result<Vec<_>, _>>()?;
+            Ok(QValue::Array(QArray::new(IpNet::aggregate(&nets).into_iter().map(cidr_value).collect())))
+        }
+        _ => attr_err!("Unknown ip function: {}", func_name),
+    }
+}
+
+/// Order addresses and networks numerically, IPv4 before IPv6
+pub fn compare_ip(a: &QValue, b: &QValue) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (QValue::IpAddr(a), QValue::IpAddr(b)) => Some(a.addr.cmp(&b.addr)),
+        (QValue::Cidr(a), QValue::Cidr(b)) => Some(a.net.cmp(&b.net)),
+        _ => None,
+    }
+}
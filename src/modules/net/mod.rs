@@ -0,0 +1,58 @@
+// Networking modules: std/net/ftp, std/net/sftp and std/net/ip
+//
+// FTP is spoken natively. SFTP runs over SSH, so it drives the OpenSSH `sftp`
+// program instead, which brings key files, ssh-agent and ~/.ssh/config along.
+// Both return directory listings in the same shape.
+
+pub mod ftp;
+pub mod ip;
+pub mod sftp;
+pub mod stream;
+
+pub use ftp::{create_ftp_module, call_ftp_function};
+pub use ip::{create_ip_module, call_ip_function};
+pub use sftp::{create_sftp_module, call_sftp_function};
+
+use indexmap::IndexMap;
+use crate::types::*;
+
+/// One directory entry as returned by `list()`
+pub fn list_entry(name: &str, size: i64, is_dir: bool, modified: QValue, permissions: QValue) -> QValue {
+    let mut map = IndexMap::new();
+    map.insert("name".to_string(), QValue::Str(QString::new(name.to_string())));
+    map.insert("size".to_string(), QValue::Int(QInt::new(size)));
+    map.insert("is_dir".to_string(), QValue::Bool(QBool::new(is_dir)));
+    map.insert("modified".to_string(), modified);
+    map.insert("permissions".to_string(), permissions);
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+/// `drwxr-xr-x   2 user group   4096 Jan  1 12:00 name` (Unix ls format)
+pub fn parse_list_line(line: &str) -> Option<QValue> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 9 || !"-dl".contains(fields[0].chars().next()?) {
+        return None;
+    }
+    // The name is everything after the 8th field, spaces included
+    let mut rest = line;
+    for field in &fields[..8] {
+        rest = rest.trim_start().strip_prefix(field)?;
+    }
+    let name = rest.trim_start();
+    let name = match fields[0].starts_with('l') {
+        true => name.split(" -> ").next().unwrap_or(name),
+        false => name,
+    };
+    if name == "." || name == ".." {
+        return None;
+    }
+    let modified = QValue::Str(QString::new(fields[5..8].join(" ")));
+    Some(list_entry(
+        name,
+        fields[4].parse().unwrap_or(0),
+        fields[0].starts_with('d'),
+        modified,
+        QValue::Str(QString::new(fields[0].to_string())),
+    ))
+}
+
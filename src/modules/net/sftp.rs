@@ -0,0 +1,296 @@
+// SFTP client (std/net/sftp)
+//
+//     use "std/net/sftp"
+//     let server = sftp.connect("deploy.example.com", user: "deploy", key: "~/.ssh/deploy_ed25519")
+//     server.upload("dist/app.tar.gz", "/srv/releases/app.tar.gz")
+//     server.rename("/srv/releases/app.tar.gz", "/srv/releases/current.tar.gz")
+//
+// Each operation runs `sftp -b -` with the commands on stdin. Batch mode never
+// prompts, so authentication is by key file or ssh-agent, and a failing
+// command makes sftp exit non-zero, which is raised as IOErr.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use crate::control_flow::EvalError;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+use crate::types::*;
+use crate::scope::Scope;
+use super::parse_list_line;
+
+const DEFAULT_TIMEOUT_SECS: i64 = 30;
+
+#[derive(Debug, Clone)]
+pub struct QSftpClient {
+    /// Options and destination passed to every sftp run
+    args: Vec<String>,
+    host: String,
+    closed: Rc<Cell<bool>>,
+    pub id: u64,
+}
+
+impl QSftpClient {
+    fn connect(args: Vec<QValue>) -> Result<Self, String> {
+        // sftp.connect(host, user:, port: 22, key:, known_hosts:, verify_host: true, timeout: 30)
+        let (positional, options) = match args.as_slice() {
+            [rest @ .., QValue::Dict(d)] if !rest.is_empty() => (rest, Some(d.as_ref().clone())),
+            _ => (args.as_slice(), None),
+        };
+        let [host] = positional else {
+            return arg_err!("sftp.connect expects 1 argument (host), got {}", positional.len());
+        };
+        let host = host.as_str();
+        let get = |key: &str| options.as_ref().and_then(|d| d.get(key)).filter(|v| !matches!(v, QValue::Nil(_)));
+        let get_str = |key: &str| -> Result<Option<String>, String> {
+            match get(key) {
+                None => Ok(None),
+                Some(QValue::Str(s)) => Ok(Some(s.value.to_string())),
+                Some(other) => type_err!("sftp.connect {} must be Str, got {}", key, other.as_obj().cls()),
+            }
+        };
+
+        if get("password").is_some() {
+            return value_err!("sftp.connect doesn't support passwords; use key: or an ssh-agent");
+        }
+        if host.is_empty() || host.starts_with('-') {
+            return value_err!("sftp.connect got an invalid host '{}'", host);
+        }
+
+        let mut args = vec!["-b".to_string(), "-".to_string()];
+        match get("port") {
+            None => {}
+            Some(QValue::Int(n)) if (1..=65535).contains(&n.value) => {
+                args.extend(["-P".to_string(), n.value.to_string()]);
+            }
+            Some(other) => return value_err!("sftp.connect port must be an Int between 1 and 65535, got {}", other.as_str()),
+        }
+        if let Some(key) = get_str("key")? {
+            args.extend(["-i".to_string(), expand_home(&key), "-o".to_string(), "IdentitiesOnly=yes".to_string()]);
+        }
+        let timeout = match get("timeout") {
+            None => DEFAULT_TIMEOUT_SECS,
+            Some(QValue::Int(n)) if n.value > 0 => n.value,
+            Some(other) => return value_err!("sftp.connect timeout must be a positive Int, got {}", other.as_str()),
+        };
+        args.extend(["-o".to_string(), format!("ConnectTimeout={}", timeout)]);
+        if let Some(known_hosts) = get_str("known_hosts")? {
+            args.extend(["-o".to_string(), format!("UserKnownHostsFile={}", expand_home(&known_hosts))]);
+        }
+        if get("verify_host").is_some_and(|v| !v.as_bool()) {
+            args.extend([
+                "-o".to_string(), "StrictHostKeyChecking=no".to_string(),
+                "-o".to_string(), "UserKnownHostsFile=/dev/null".to_string(),
+            ]);
+        }
+        args.push(match get_str("user")? {
+            Some(user) => format!("{}@{}", user, host),
+            None => host.clone(),
+        });
+
+        let client = QSftpClient {
+            args,
+            host,
+            closed: Rc::new(Cell::new(false)),
+            id: next_object_id(),
+        };
+        // Fail here rather than on first use if the host or key is wrong
+        client.run(&["pwd".to_string()])?;
+        Ok(client)
+    }
+
+    /// Run batch commands in one sftp session and return their output
+    fn run(&self, commands: &[String]) -> Result<String, String> {
+        if self.closed.get() {
+            return io_err!("SFTP connection is closed");
+        }
+        let mut child = Command::new("sftp")
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => "IOErr: The sftp program was not found; install the OpenSSH client".to_string(),
+                _ => format!("IOErr: Cannot start sftp: {}", e),
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let script = commands.join("\n") + "\n";
+            stdin.write_all(script.as_bytes())
+                .map_err(|e| format!("IOErr: Cannot send commands to sftp: {}", e))?;
+        }
+        let output = child.wait_with_output()
+            .map_err(|e| format!("IOErr: sftp failed: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason: Vec<&str> = stderr.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+            if reason.is_empty() {
+                return io_err!("SFTP {} failed with {}", self.host, output.status);
+            }
+            return io_err!("SFTP {} failed: {}", self.host, reason.join("; "));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        let path_arg = |index: usize| -> Result<String, String> {
+            match args.get(index) {
+                Some(QValue::Str(s)) => quote(&s.value),
+                Some(other) => type_err!("{} expects Str paths, got {}", method_name, other.as_obj().cls()),
+                None => arg_err!("{} expects {} arguments, got {}", method_name, index + 1, args.len()),
+            }
+        };
+        let expect_args = |count: usize| -> Result<(), String> {
+            if args.len() != count {
+                return arg_err!("{} expects {} arguments, got {}", method_name, count, args.len());
+            }
+            Ok(())
+        };
+
+        match method_name {
+            "list" => {
+                let path = match args.len() {
+                    0 => quote(".")?,
+                    1 => path_arg(0)?,
+                    n => return arg_err!("list expects 0 or 1 arguments, got {}", n),
+                };
+                let output = self.run(&[format!("ls -la {}", path)])?;
+                let entries = output.lines()
+                    // Batch mode echoes each command as "sftp> ..."
+                    .filter(|line| !line.starts_with("sftp>"))
+                    .filter_map(parse_list_line)
+                    .map(strip_dir_prefix)
+                    .collect();
+                Ok(QValue::Array(QArray::new(entries)))
+            }
+            "upload" => {
+                expect_args(2)?;
+                if let QValue::Str(local) = &args[0] {
+                    if !std::path::Path::new(local.value.as_str()).is_file() {
+                        return io_err!("Cannot upload '{}': no such file", local.value);
+                    }
+                }
+                self.run(&[format!("put {} {}", path_arg(0)?, path_arg(1)?)])?;
+                Ok(QValue::Nil(QNil))
+            }
+            "download" => {
+                expect_args(2)?;
+                self.run(&[format!("get {} {}", path_arg(0)?, path_arg(1)?)])?;
+                Ok(QValue::Nil(QNil))
+            }
+            "rename" => {
+                expect_args(2)?;
+                self.run(&[format!("rename {} {}", path_arg(0)?, path_arg(1)?)])?;
+                Ok(QValue::Nil(QNil))
+            }
+            "delete" | "mkdir" | "rmdir" => {
+                expect_args(1)?;
+                let command = if method_name == "delete" { "rm" } else { method_name };
+                self.run(&[format!("{} {}", command, path_arg(0)?)])?;
+                Ok(QValue::Nil(QNil))
+            }
+            "pwd" => {
+                expect_args(0)?;
+                // "Remote working directory: /home/deploy"
+                let output = self.run(&["pwd".to_string()])?;
+                let dir = output.lines()
+                    .find_map(|line| line.split_once("working directory: ").map(|(_, dir)| dir.trim().to_string()))
+                    .unwrap_or_default();
+                Ok(QValue::Str(QString::new(dir)))
+            }
+            "close" => {
+                self.closed.set(true);
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.closed.get()))),
+            "_enter" => Ok(QValue::SftpClient(self.clone())),
+            "_exit" => {
+                self.closed.set(true);
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on SftpClient", method_name),
+        }
+    }
+}
+
+/// Double-quote a path for an sftp batch line
+fn quote(path: &str) -> Result<String, String> {
+    if path.contains(['\r', '\n']) {
+        return value_err!("SFTP paths cannot contain line breaks");
+    }
+    Ok(format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\"")))
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+/// `ls` on a directory may print names with the directory in front
+fn strip_dir_prefix(entry: QValue) -> QValue {
+    if let QValue::Dict(dict) = &entry {
+        if let Some(QValue::Str(name)) = dict.get("name") {
+            if let Some((_, base)) = name.value.trim_end_matches('/').rsplit_once('/') {
+                dict.map.borrow_mut().insert("name".to_string(), QValue::Str(QString::new(base.to_string())));
+            }
+        }
+    }
+    entry
+}
+
+impl QObj for QSftpClient {
+    fn cls(&self) -> String {
+        "SftpClient".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "SftpClient"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "SftpClient"
+    }
+
+    fn str(&self) -> String {
+        format!("<SftpClient {}>", self.host)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "SFTP connection (runs the OpenSSH sftp program)".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_sftp_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("connect".to_string(), QValue::Fun(QFun {
+        name: "connect".to_string(),
+        parent_type: "sftp".to_string(),
+        id: next_object_id(),
+    }));
+
+    QValue::Module(Box::new(QModule::new("sftp".to_string(), members)))
+}
+
+pub fn call_sftp_function(func_name: &str, args: Vec<QValue>, _scope: &mut Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "sftp.connect" => Ok(QValue::SftpClient(QSftpClient::connect(args)?)),
+        _ => attr_err!("Unknown function: {}", func_name),
+    }
+}
@@ -0,0 +1,232 @@
+// Buffered TCP connections for the line-based protocol clients (IMAP, POP3,
+// NATS). A connection is either TLS from the start, plain, or upgraded to TLS
+// after the server agrees (STARTTLS).
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use crate::{arg_err, type_err, value_err};
+use crate::types::*;
+
+const DEFAULT_TIMEOUT_SECS: f64 = 30.0;
+const TIMED_OUT: &str = "IOErr: Server timed out";
+
+/// Options shared by `imap.connect`, `pop3.connect` and `nats.connect`
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    pub host: String,
+    pub port: u16,
+    /// TLS from the first byte
+    pub tls: bool,
+    /// Upgrade a plain connection with STARTTLS (IMAP) or STLS (POP3)
+    pub starttls: bool,
+    /// Check the server certificate and host name
+    pub verify: bool,
+    pub timeout: Option<Duration>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+impl ConnectOptions {
+    /// Parse `connect(host, port: n, tls: true, starttls: false, verify: true,
+    /// timeout: 30, user: "...", password: "...")`
+    pub fn parse(func: &str, mut args: Vec<QValue>, tls_port: u16, plain_port: u16) -> Result<Self, String> {
+        let options = match args.last() {
+            Some(QValue::Dict(_)) if args.len() > 1 => args.pop(),
+            _ => None,
+        };
+        if args.len() != 1 {
+            return arg_err!("{} expects 1 argument (host), got {}", func, args.len());
+        }
+        let get = |key: &str| match &options {
+            Some(QValue::Dict(d)) => d.get(key).filter(|v| !matches!(v, QValue::Nil(_))),
+            _ => None,
+        };
+        let get_str = |key: &str| -> Result<Option<String>, String> {
+            match get(key) {
+                None => Ok(None),
+                Some(QValue::Str(s)) => Ok(Some(s.value.to_string())),
+                Some(other) => type_err!("{} {} must be Str, got {}", func, key, other.as_obj().cls()),
+            }
+        };
+
+        let starttls = get("starttls").is_some_and(|v| v.as_bool());
+        let tls = !starttls && get("tls").is_none_or(|v| v.as_bool());
+        let port = match get("port") {
+            None => if tls { tls_port } else { plain_port },
+            Some(QValue::Int(n)) if (1..=65535).contains(&n.value) => n.value as u16,
+            Some(other) => return value_err!("{} port must be an Int between 1 and 65535, got {}", func, other.as_str()),
+        };
+        let secs = match get("timeout") {
+            None => DEFAULT_TIMEOUT_SECS,
+            Some(QValue::Int(n)) => n.value as f64,
+            Some(QValue::Float(f)) => f.value,
+            Some(other) => return type_err!("{} timeout must be a number of seconds, got {}", func, other.as_obj().cls()),
+        };
+        if secs < 0.0 {
+            return value_err!("{} timeout must not be negative, got {}", func, secs);
+        }
+        // 0 waits forever
+        let timeout = (secs > 0.0).then(|| Duration::from_secs_f64(secs));
+
+        Ok(ConnectOptions {
+            host: args[0].as_str(),
+            port,
+            tls,
+            starttls,
+            verify: get("verify").is_none_or(|v| v.as_bool()),
+            timeout,
+            user: get_str("user")?,
+            password: get_str("password")?,
+        })
+    }
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<native_tls::TlsStream<TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.read(buf),
+            Stream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.write(buf),
+            Stream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(s) => s.flush(),
+            Stream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+impl Stream {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Stream::Plain(s) => s,
+            Stream::Tls(s) => s.get_ref(),
+        }
+    }
+}
+
+/// A buffered connection to a line-based server
+pub struct LineStream {
+    reader: BufReader<Stream>,
+    /// Start of a line that a timeout interrupted
+    partial: Vec<u8>,
+    timeout: Option<Duration>,
+}
+
+impl std::fmt::Debug for LineStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tls = matches!(self.reader.get_ref(), Stream::Tls(_));
+        f.debug_struct("LineStream").field("tls", &tls).finish()
+    }
+}
+
+impl LineStream {
+    pub fn connect(opts: &ConnectOptions) -> Result<Self, String> {
+        let tcp = TcpStream::connect((opts.host.as_str(), opts.port))
+            .map_err(|e| format!("IOErr: Cannot connect to {}:{}: {}", opts.host, opts.port, e))?;
+        tcp.set_read_timeout(opts.timeout).map_err(io_error)?;
+        tcp.set_write_timeout(opts.timeout).map_err(io_error)?;
+
+        let stream = if opts.tls {
+            Stream::Tls(Box::new(tls_handshake(tcp, opts)?))
+        } else {
+            Stream::Plain(tcp)
+        };
+        Ok(LineStream { reader: BufReader::new(stream), partial: Vec::new(), timeout: opts.timeout })
+    }
+
+    /// Switch a plain connection to TLS once the server agreed to STARTTLS
+    pub fn start_tls(self, opts: &ConnectOptions) -> Result<Self, String> {
+        let stream = match self.reader.into_inner() {
+            Stream::Plain(tcp) => Stream::Tls(Box::new(tls_handshake(tcp, opts)?)),
+            tls => tls,
+        };
+        Ok(LineStream { reader: BufReader::new(stream), partial: Vec::new(), timeout: self.timeout })
+    }
+
+    /// Next line without its line ending
+    pub fn read_line(&mut self) -> Result<Vec<u8>, String> {
+        let n = self.reader.read_until(b'\n', &mut self.partial).map_err(io_error)?;
+        if n == 0 {
+            return Err("IOErr: Connection closed by the server".to_string());
+        }
+        let mut line = std::mem::take(&mut self.partial);
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+        Ok(line)
+    }
+
+    /// Next line, or None if nothing complete arrives within `wait`
+    /// (None waits indefinitely)
+    pub fn poll_line(&mut self, wait: Option<Duration>) -> Result<Option<Vec<u8>>, String> {
+        // A zero timeout would mean "block forever" to the socket
+        let wait = wait.map(|w| w.max(Duration::from_millis(1)));
+        self.reader.get_ref().tcp().set_read_timeout(wait).map_err(io_error)?;
+        let result = self.read_line();
+        self.reader.get_ref().tcp().set_read_timeout(self.timeout).map_err(io_error)?;
+        match result {
+            Ok(line) => Ok(Some(line)),
+            Err(e) if e == TIMED_OUT => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn read_exact(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        let mut data = vec![0; len];
+        self.reader.read_exact(&mut data).map_err(io_error)?;
+        Ok(data)
+    }
+
+    /// Send raw bytes as they are
+    pub fn write_all(&mut self, data: &[u8]) -> Result<(), String> {
+        let stream = self.reader.get_mut();
+        stream.write_all(data).map_err(io_error)?;
+        stream.flush().map_err(io_error)
+    }
+
+    /// Send one command line, adding CRLF
+    pub fn send_line(&mut self, line: &str) -> Result<(), String> {
+        if line.contains(['\r', '\n']) {
+            return value_err!("Protocol commands cannot contain line breaks");
+        }
+        self.write_all(format!("{}\r\n", line).as_bytes())
+    }
+}
+
+fn tls_handshake(tcp: TcpStream, opts: &ConnectOptions) -> Result<native_tls::TlsStream<TcpStream>, String> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(!opts.verify)
+        .danger_accept_invalid_hostnames(!opts.verify)
+        .build()
+        .map_err(|e| format!("IOErr: TLS setup failed: {}", e))?;
+    connector.connect(&opts.host, tcp)
+        .map_err(|e| format!("IOErr: TLS handshake with {} failed: {}", opts.host, e))
+}
+
+fn io_error(e: std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => TIMED_OUT.to_string(),
+        _ => format!("IOErr: {}", e),
+    }
+}
+
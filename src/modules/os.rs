@@ -1,6 +1,7 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
-use crate::{arg_err, name_err};
+use crate::{arg_err, io_err, name_err, type_err, value_err};
 use std::env;
 use crate::types::*;
 
@@ -16,6 +17,14 @@ pub fn create_os_module() -> QValue {
     members.insert("remove".to_string(), create_fn("os", "remove"));
     members.insert("rename".to_string(), create_fn("os", "rename"));
 
+    // Permissions, ownership and links
+    members.insert("stat".to_string(), create_fn("os", "stat"));
+    members.insert("lstat".to_string(), create_fn("os", "lstat"));
+    members.insert("chmod".to_string(), create_fn("os", "chmod"));
+    members.insert("chown".to_string(), create_fn("os", "chown"));
+    members.insert("symlink".to_string(), create_fn("os", "symlink"));
+    members.insert("readlink".to_string(), create_fn("os", "readlink"));
+
     // Environment and working directory
     members.insert("getenv".to_string(), create_fn("os", "getenv"));
     members.insert("setenv".to_string(), create_fn("os", "setenv"));
@@ -24,32 +33,10 @@ pub fn create_os_module() -> QValue {
     members.insert("getcwd".to_string(), create_fn("os", "getcwd"));
     members.insert("chdir".to_string(), create_fn("os", "chdir"));
 
-    // Module search path - matches the actual paths Quest uses for module resolution
-    let mut search_paths = Vec::new();
-
-    // 1. Development lib/ directory (if exists)
-    if std::path::Path::new("lib/").exists() {
-        search_paths.push(QValue::Str(QString::new("lib/".to_string())));
-    }
-
-    // 2. QUEST_INCLUDE environment variable
-    let quest_include = env::var("QUEST_INCLUDE").unwrap_or_else(|_| String::new());
-    if !quest_include.is_empty() {
-        let separator = if cfg!(windows) { ';' } else { ':' };
-        for path in quest_include.split(separator) {
-            if !path.is_empty() {
-                search_paths.push(QValue::Str(QString::new(path.to_string())));
-            }
-        }
-    }
-
-    // 3. Installed stdlib in ~/.quest/lib (if exists)
-    let stdlib_dir = crate::embedded_lib::get_stdlib_dir();
-    if stdlib_dir.exists() {
-        if let Some(stdlib_str) = stdlib_dir.to_str() {
-            search_paths.push(QValue::Str(QString::new(stdlib_str.to_string())));
-        }
-    }
+    // Module search path - starts as the directories Quest searches from the environment
+    let search_paths = crate::module_loader::default_search_dirs().into_iter()
+        .map(|(dir, _)| QValue::Str(QString::new(dir)))
+        .collect();
 
     members.insert("search_path".to_string(), QValue::Array(QArray::new(search_paths)));
 
@@ -120,6 +107,66 @@ pub fn call_os_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::
                 .map_err(|e| format!("Failed to rename '{}' to '{}': {}", src, dst, e))?;
             Ok(QValue::Nil(QNil))
         }
+        "os.stat" | "os.lstat" => {
+            let name = &func_name[3..];
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument, got {}", name, args.len());
+            }
+            let path = args[0].as_str();
+            let metadata = if name == "lstat" {
+                std::fs::symlink_metadata(&path)
+            } else {
+                std::fs::metadata(&path)
+            };
+            match metadata {
+                Ok(metadata) => Ok(stat_dict(&path, &metadata)),
+                Err(e) => io_err!("Failed to stat '{}': {}", path, e),
+            }
+        }
+        "os.chmod" => {
+            if args.len() != 2 {
+                return arg_err!("chmod expects 2 arguments (path, mode), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            let mode = match &args[1] {
+                QValue::Int(i) if (0..=0o7777).contains(&i.value) => i.value as u32,
+                QValue::Int(i) => return value_err!("Invalid file mode: {:o}", i.value),
+                other => return type_err!("chmod mode must be Int (e.g. 0o755), got {}", other.q_type()),
+            };
+            if let Err(e) = set_mode(&path, mode) {
+                return io_err!("Failed to chmod '{}': {}", path, e);
+            }
+            Ok(QValue::Nil(QNil))
+        }
+        "os.chown" => {
+            if args.len() < 2 || args.len() > 3 {
+                return arg_err!("chown expects 2 or 3 arguments (path, user, group), got {}", args.len());
+            }
+            let path = args[0].as_str();
+            chown(&path, &args[1], args.get(2).unwrap_or(&QValue::Nil(QNil)))?;
+            Ok(QValue::Nil(QNil))
+        }
+        "os.symlink" => {
+            if args.len() != 2 {
+                return arg_err!("symlink expects 2 arguments (target, link), got {}", args.len());
+            }
+            let target = args[0].as_str();
+            let link = args[1].as_str();
+            if let Err(e) = symlink(&target, &link) {
+                return io_err!("Failed to create symlink '{}' -> '{}': {}", link, target, e);
+            }
+            Ok(QValue::Nil(QNil))
+        }
+        "os.readlink" => {
+            if args.len() != 1 {
+                return arg_err!("readlink expects 1 argument, got {}", args.len());
+            }
+            let path = args[0].as_str();
+            match std::fs::read_link(&path) {
+                Ok(target) => Ok(QValue::Str(QString::new(target.to_string_lossy().to_string()))),
+                Err(e) => io_err!("Failed to read link '{}': {}", path, e),
+            }
+        }
         "os.getenv" => {
             if args.len() != 1 {
                 return arg_err!("getenv expects 1 argument, got {}", args.len());
@@ -160,7 +207,7 @@ pub fn call_os_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::
             if !args.is_empty() {
                 return arg_err!("environ expects 0 arguments, got {}", args.len());
             }
-            let mut env_dict = HashMap::new();
+            let mut env_dict = IndexMap::new();
             for (key, value) in env::vars() {
                 env_dict.insert(key, QValue::Str(QString::new(value)));
             }
@@ -169,3 +216,162 @@ pub fn call_os_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::
         _ => name_err!("Unknown os function: {}", func_name)
     }
 }
+
+fn str_value(s: impl Into<String>) -> QValue {
+    QValue::Str(QString::new(s.into()))
+}
+
+fn int_value(n: i64) -> QValue {
+    QValue::Int(QInt::new(n))
+}
+
+fn epoch_secs(time: std::io::Result<std::time::SystemTime>) -> QValue {
+    match time.ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok()) {
+        Some(d) => int_value(d.as_secs() as i64),
+        None => QValue::Nil(QNil),
+    }
+}
+
+/// Permission bits in `ls -l` form, e.g. "rwxr-xr-x"
+fn permission_string(mode: u32) -> String {
+    let mut s = String::with_capacity(9);
+    for (shift, special, set_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 2 != 0 { 'w' } else { '-' });
+        s.push(match (bits & 1 != 0, mode & special != 0) {
+            (true, true) => set_char,
+            (false, true) => set_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+fn stat_dict(path: &str, metadata: &std::fs::Metadata) -> QValue {
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_dir() {
+        "dir"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "other"
+    };
+
+    let mut map = IndexMap::new();
+    map.insert("path".to_string(), str_value(path));
+    map.insert("type".to_string(), str_value(kind));
+    map.insert("size".to_string(), int_value(metadata.len() as i64));
+    map.insert("is_file".to_string(), QValue::Bool(QBool::new(file_type.is_file())));
+    map.insert("is_dir".to_string(), QValue::Bool(QBool::new(file_type.is_dir())));
+    map.insert("is_symlink".to_string(), QValue::Bool(QBool::new(file_type.is_symlink())));
+    map.insert("mtime".to_string(), epoch_secs(metadata.modified()));
+    map.insert("atime".to_string(), epoch_secs(metadata.accessed()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        use nix::unistd::{Gid, Group, Uid, User};
+
+        let mode = metadata.mode() & 0o7777;
+        let owner = User::from_uid(Uid::from_raw(metadata.uid())).ok().flatten()
+            .map(|u| str_value(u.name))
+            .unwrap_or(QValue::Nil(QNil));
+        let group = Group::from_gid(Gid::from_raw(metadata.gid())).ok().flatten()
+            .map(|g| str_value(g.name))
+            .unwrap_or(QValue::Nil(QNil));
+
+        map.insert("mode".to_string(), int_value(mode as i64));
+        map.insert("permissions".to_string(), str_value(permission_string(mode)));
+        map.insert("ctime".to_string(), int_value(metadata.ctime()));
+        map.insert("uid".to_string(), int_value(metadata.uid() as i64));
+        map.insert("gid".to_string(), int_value(metadata.gid() as i64));
+        map.insert("owner".to_string(), owner);
+        map.insert("group".to_string(), group);
+        map.insert("inode".to_string(), int_value(metadata.ino() as i64));
+        map.insert("nlink".to_string(), int_value(metadata.nlink() as i64));
+    }
+    #[cfg(not(unix))]
+    {
+        // Only the read-only flag maps onto Unix permission bits
+        let mode = if metadata.permissions().readonly() { 0o444 } else { 0o666 }
+            | if file_type.is_dir() { 0o111 } else { 0 };
+        map.insert("mode".to_string(), int_value(mode as i64));
+        map.insert("permissions".to_string(), str_value(permission_string(mode)));
+        map.insert("ctime".to_string(), epoch_secs(metadata.created()));
+        for key in ["uid", "gid", "owner", "group", "inode", "nlink"] {
+            map.insert(key.to_string(), QValue::Nil(QNil));
+        }
+    }
+
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+#[cfg(unix)]
+fn set_mode(path: &str, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(path: &str, mode: u32) -> std::io::Result<()> {
+    // Without Unix permissions a file is read-only when no one may write it
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_readonly(mode & 0o222 == 0);
+    std::fs::set_permissions(path, permissions)
+}
+
+#[cfg(unix)]
+fn chown(path: &str, user: &QValue, group: &QValue) -> Result<(), EvalError> {
+    use nix::unistd::{Group, User};
+
+    let uid = match user {
+        QValue::Nil(_) => None,
+        QValue::Int(i) => Some(i.value as u32),
+        QValue::Str(s) => match User::from_name(&s.value) {
+            Ok(Some(u)) => Some(u.uid.as_raw()),
+            _ => return value_err!("Unknown user '{}'", s.value),
+        },
+        other => return type_err!("chown user must be Int, Str or nil, got {}", other.q_type()),
+    };
+    let gid = match group {
+        QValue::Nil(_) => None,
+        QValue::Int(i) => Some(i.value as u32),
+        QValue::Str(s) => match Group::from_name(&s.value) {
+            Ok(Some(g)) => Some(g.gid.as_raw()),
+            _ => return value_err!("Unknown group '{}'", s.value),
+        },
+        other => return type_err!("chown group must be Int, Str or nil, got {}", other.q_type()),
+    };
+    match std::os::unix::fs::chown(path, uid, gid) {
+        Ok(()) => Ok(()),
+        Err(e) => io_err!("Failed to chown '{}': {}", path, e),
+    }
+}
+
+#[cfg(not(unix))]
+fn chown(_path: &str, _user: &QValue, _group: &QValue) -> Result<(), EvalError> {
+    crate::runtime_err!("os.chown is not supported on this platform")
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, link: &str) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &str, link: &str) -> std::io::Result<()> {
+    // Windows distinguishes links to directories; a relative target is
+    // resolved from the link's directory
+    let resolved = std::path::Path::new(link).parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join(target);
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
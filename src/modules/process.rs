@@ -1,15 +1,18 @@
 // std/process - External Process Execution (QEP-012)
 use crate::control_flow::EvalError;
 use std::collections::HashMap;
-use crate::{arg_err, attr_err, io_err, runtime_err, value_err};
+use indexmap::IndexMap;
+use crate::{arg_err, attr_err, io_err, runtime_err, type_err, value_err};
 use std::process::{Command, Stdio, Child, ChildStdin, ChildStdout, ChildStderr};
 use std::io::{Write, Read, BufRead, BufReader};
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::thread;
 use std::sync::mpsc;
 use crate::types::*;
 use crate::Scope;
+use crate::function_call::{call_user_function, CallArguments};
 
 // ============================================================================
 // ProcessResult Type
@@ -23,6 +26,7 @@ pub struct QProcessResult {
     pub stdout_bytes: Vec<u8>,
     pub stderr_bytes: Vec<u8>,
     pub code: i64,
+    pub timed_out: bool,
     pub id: u64,
 }
 
@@ -34,6 +38,7 @@ impl QProcessResult {
             stdout_bytes,
             stderr_bytes,
             code,
+            timed_out: false,
             id: next_object_id(),
         }
     }
@@ -76,6 +81,12 @@ impl QProcessResult {
                 }
                 Ok(QValue::Int(QInt::new(self.code)))
             }
+            "timed_out" => {
+                if !args.is_empty() {
+                    return arg_err!("timed_out expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Bool(QBool::new(self.timed_out)))
+            }
             "_id" => Ok(QValue::Int(QInt::new(self.id as i64))),
             "str" => Ok(QValue::Str(QString::new(format!("<ProcessResult code={}>", self.code)))),
             "cls" => Ok(QValue::Str(QString::new(self.cls()))),
@@ -115,6 +126,113 @@ impl QObj for QProcessResult {
     }
 }
 
+// ============================================================================
+// PipelineResult Type
+// ============================================================================
+
+/// Result from process.pipeline(): the output of the last command plus a
+/// ProcessResult for every stage
+#[derive(Debug, Clone)]
+pub struct QPipelineResult {
+    pub stages: Vec<QProcessResult>,
+    /// Exit code of the last stage, or with pipefail the last non-zero one
+    pub code: i64,
+    pub id: u64,
+}
+
+impl QPipelineResult {
+    pub fn new(stages: Vec<QProcessResult>, pipefail: bool) -> Self {
+        let last = stages.last().map_or(0, |s| s.code);
+        let code = if pipefail {
+            stages.iter().rev().map(|s| s.code).find(|&c| c != 0).unwrap_or(0)
+        } else {
+            last
+        };
+        QPipelineResult {
+            stages,
+            code,
+            id: next_object_id(),
+        }
+    }
+
+    fn last(&self) -> &QProcessResult {
+        self.stages.last().expect("pipeline has at least one stage")
+    }
+
+    /// Output of the last stage
+    pub fn stdout(&self) -> &str {
+        &self.last().stdout
+    }
+
+    /// stderr of every stage, in pipeline order
+    pub fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.stderr_bytes()).to_string()
+    }
+
+    fn stderr_bytes(&self) -> Vec<u8> {
+        self.stages.iter().flat_map(|s| s.stderr_bytes.iter().copied()).collect()
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.stages.iter().any(|s| s.timed_out)
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if !args.is_empty() && !matches!(method_name, "_id" | "str" | "cls" | "_rep") {
+            return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+        }
+        match method_name {
+            "success" => Ok(QValue::Bool(QBool::new(self.code == 0))),
+            "code" => Ok(QValue::Int(QInt::new(self.code))),
+            "codes" => Ok(QValue::Array(QArray::new(
+                self.stages.iter().map(|s| QValue::Int(QInt::new(s.code))).collect()
+            ))),
+            "stdout" => Ok(QValue::Str(QString::new(self.stdout().to_string()))),
+            "stdout_bytes" => Ok(QValue::Bytes(QBytes::new(self.last().stdout_bytes.clone()))),
+            "stderr" => Ok(QValue::Str(QString::new(self.stderr()))),
+            "stderr_bytes" => Ok(QValue::Bytes(QBytes::new(self.stderr_bytes()))),
+            "stages" => Ok(QValue::Array(QArray::new(
+                self.stages.iter().map(|s| QValue::ProcessResult(s.clone())).collect()
+            ))),
+            "timed_out" => Ok(QValue::Bool(QBool::new(self.timed_out()))),
+            "_id" => Ok(QValue::Int(QInt::new(self.id as i64))),
+            "str" | "_rep" => Ok(QValue::Str(QString::new(self.str()))),
+            "cls" => Ok(QValue::Str(QString::new(self.cls()))),
+            _ => attr_err!("Unknown method '{}' on PipelineResult", method_name)
+        }
+    }
+}
+
+impl QObj for QPipelineResult {
+    fn cls(&self) -> String {
+        "PipelineResult".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "PipelineResult"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "PipelineResult"
+    }
+
+    fn str(&self) -> String {
+        format!("<PipelineResult code={} stages={}>", self.code, self.stages.len())
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Result from process.pipeline()".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
 // ============================================================================
 // WritableStream Type (stdin)
 // ============================================================================
@@ -221,6 +339,23 @@ impl Clone for QWritableStream {
     }
 }
 
+/// Lets Rust-side adapters (e.g. compress writers) stream into a child's stdin
+impl Write for QWritableStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.stdin.lock().unwrap().as_mut() {
+            Some(stdin) => stdin.write(buf),
+            None => Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "stdin is closed")),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.stdin.lock().unwrap().as_mut() {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
 impl QObj for QWritableStream {
     fn cls(&self) -> String {
         "WritableStream".to_string()
@@ -440,6 +575,13 @@ impl Clone for QReadableStream {
     }
 }
 
+/// Lets Rust-side adapters (e.g. compress readers) consume a child's output
+impl Read for QReadableStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.lock().unwrap().read(buf)
+    }
+}
+
 impl QObj for QReadableStream {
     fn cls(&self) -> String {
         "ReadableStream".to_string()
@@ -480,6 +622,8 @@ pub struct QProcess {
     pub stdin: QWritableStream,
     pub stdout: QReadableStream,
     pub stderr: QReadableStream,
+    /// Set when spawned with on_stdout/on_stderr
+    output: Option<Rc<OutputCallbacks>>,
     pid: u32,
     pub id: u64,
 }
@@ -509,72 +653,115 @@ impl QProcess {
             stdin: QWritableStream::new(stdin),
             stdout: QReadableStream::from_stdout(stdout),
             stderr: QReadableStream::from_stderr(stderr),
+            output: None,
             pid,
             id: next_object_id(),
         })
     }
 
-    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+    /// Pass each queued output line to its callback. With `wait`, block up to
+    /// that long for the first line; with `until_closed`, keep going until
+    /// both streams have ended.
+    fn run_callbacks(&self, scope: &mut Scope, wait: Option<Duration>, until_closed: bool) -> Result<(), EvalError> {
+        let Some(output) = &self.output else {
+            if let Some(wait) = wait {
+                thread::sleep(wait);
+            }
+            return Ok(());
+        };
+        let mut wait = wait;
+        loop {
+            let line = if until_closed {
+                output.lines.recv().ok()
+            } else if let Some(timeout) = wait.take() {
+                output.lines.recv_timeout(timeout).ok()
+            } else {
+                output.lines.try_recv().ok()
+            };
+            let Some(line) = line else {
+                return Ok(());
+            };
+            let (callback, text) = match line {
+                OutputLine::Stdout(text) => (&output.on_stdout, text),
+                OutputLine::Stderr(text) => (&output.on_stderr, text),
+            };
+            if let Some(QValue::UserFun(callback)) = callback {
+                let call_args = CallArguments::positional_only(vec![QValue::Str(QString::new(text))]);
+                call_user_function(callback, call_args, scope, None)?;
+            }
+        }
+    }
+
+    /// Wait for the process to exit, running output callbacks meanwhile.
+    /// Returns None if `timeout` passes first, leaving the process running.
+    fn wait_for(&self, timeout: Option<Duration>, scope: &mut Scope) -> Result<Option<i64>, EvalError> {
+        if self.output.is_none() && timeout.is_none() {
+            let mut child = self.child.lock().unwrap().take()
+                .ok_or("Process already waited on")?;
+            let status = child.wait()
+                .map_err(|e| format!("Failed to wait for process: {}", e))?;
+            return Ok(Some(status.code().unwrap_or(-1) as i64));
+        }
+
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            // The lock is released before callbacks run, so they can use the process
+            let status = {
+                let mut child_lock = self.child.lock().unwrap();
+                let child = child_lock.as_mut().ok_or("Process already waited on")?;
+                let status = child.try_wait()
+                    .map_err(|e| format!("Failed to wait for process: {}", e))?;
+                if status.is_some() {
+                    child_lock.take();
+                }
+                status
+            };
+            if let Some(status) = status {
+                // Output written just before exiting may still be queued
+                self.run_callbacks(scope, None, true)?;
+                return Ok(Some(status.code().unwrap_or(-1) as i64));
+            }
+
+            let mut pause = Duration::from_millis(10);
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    self.run_callbacks(scope, None, false)?;
+                    return Ok(None);
+                }
+                pause = pause.min(deadline - now);
+            }
+            self.run_callbacks(scope, Some(pause), false)?;
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
         match method_name {
             "wait" => {
-                if !args.is_empty() {
-                    return arg_err!("wait expects 0 arguments, got {}", args.len());
-                }
-                let mut child_lock = self.child.lock().unwrap();
-                if let Some(mut child) = child_lock.take() {
-                    let status = child.wait()
-                        .map_err(|e| format!("Failed to wait for process: {}", e))?;
-                    let code = status.code().unwrap_or(-1);
-                    Ok(QValue::Int(QInt::new(code as i64)))
-                } else {
-                    Err("Process already waited on".into())
+                // wait(timeout?) - exit code, or nil if the timeout passes first
+                let timeout = match args.as_slice() {
+                    [] => None,
+                    [QValue::Dict(opts)] => match opts.get("timeout") {
+                        Some(value) => timeout_arg(&value, "wait")?,
+                        None => None,
+                    },
+                    [value] => timeout_arg(value, "wait")?,
+                    _ => return arg_err!("wait expects 0 or 1 arguments (timeout?), got {}", args.len()),
+                };
+                match self.wait_for(timeout, scope)? {
+                    Some(code) => Ok(QValue::Int(QInt::new(code))),
+                    None => Ok(QValue::Nil(QNil)),
                 }
             }
             "wait_with_timeout" => {
                 if args.len() != 1 {
                     return arg_err!("wait_with_timeout expects 1 argument (seconds), got {}", args.len());
                 }
-
-                let timeout_secs = match &args[0] {
-                    QValue::Int(i) => {
-                        if i.value <= 0 {
-                            return Err("timeout must be positive".into());
-                        }
-                        Duration::from_secs(i.value as u64)
-                    }
-                    QValue::Float(f) => {
-                        if f.value <= 0.0 {
-                            return Err("timeout must be positive".into());
-                        }
-                        Duration::from_secs_f64(f.value)
-                    }
-                    _ => return Err("wait_with_timeout expects int or float (seconds)".into()),
-                };
-
-                let mut child_lock = self.child.lock().unwrap();
-                if let Some(mut child) = child_lock.take() {
-                    // Use channel for timeout
-                    let (tx, rx) = mpsc::channel();
-
-                    thread::spawn(move || {
-                        let status = child.wait();
-                        let _ = tx.send(status);
-                    });
-
-                    match rx.recv_timeout(timeout_secs) {
-                        Ok(Ok(status)) => {
-                            let code = status.code().unwrap_or(-1);
-                            Ok(QValue::Int(QInt::new(code as i64)))
-                        }
-                        Ok(Err(e)) => runtime_err!("Failed to wait for process: {}", e),
-                        Err(_) => {
-                            // Timeout - return nil
-                            // Note: Process is still running, child handle moved to thread
-                            Ok(QValue::Nil(QNil))
-                        }
-                    }
-                } else {
-                    Err("Process already waited on".into())
+                let timeout = timeout_arg(&args[0], "wait_with_timeout")?
+                    .ok_or("wait_with_timeout expects int or float (seconds)")?;
+                match self.wait_for(Some(timeout), scope)? {
+                    Some(code) => Ok(QValue::Int(QInt::new(code))),
+                    None => Ok(QValue::Nil(QNil)),
                 }
             }
             "pid" => {
@@ -614,13 +801,13 @@ impl QProcess {
                 };
 
                 // Wait for process
-                let exit_code = match self.call_method("wait", vec![])? {
+                let exit_code = match self.call_method("wait", vec![], scope)? {
                     QValue::Int(i) => i.value,
                     _ => -1,
                 };
 
                 // Return dict with stdout, stderr, code
-                let mut result_map = HashMap::new();
+                let mut result_map = IndexMap::new();
                 result_map.insert("stdout".to_string(), QValue::Str(QString::new(stdout_str)));
                 result_map.insert("stderr".to_string(), QValue::Str(QString::new(stderr_str)));
                 result_map.insert("code".to_string(), QValue::Int(QInt::new(exit_code)));
@@ -633,26 +820,38 @@ impl QProcess {
                 if !args.is_empty() {
                     return arg_err!("poll expects 0 arguments, got {}", args.len());
                 }
-                let mut child_lock = self.child.lock().unwrap();
-                if let Some(ref mut child) = *child_lock {
-                    match child.try_wait() {
-                        Ok(Some(status)) => {
-                            let code = status.code().unwrap_or(-1);
-                            Ok(QValue::Int(QInt::new(code as i64)))
-                        }
-                        Ok(None) => {
-                            // Still running
-                            Ok(QValue::Nil(QNil))
+                self.run_callbacks(scope, None, false)?;
+                let status = {
+                    let mut child_lock = self.child.lock().unwrap();
+                    if let Some(ref mut child) = *child_lock {
+                        match child.try_wait() {
+                            Ok(status) => status,
+                            Err(e) => return runtime_err!("Failed to poll process: {}", e),
                         }
-                        Err(e) => runtime_err!("Failed to poll process: {}", e)
+                    } else {
+                        return Err("Process already waited on".into());
                     }
-                } else {
-                    Err("Process already waited on".into())
+                };
+                match status {
+                    Some(status) => {
+                        self.run_callbacks(scope, None, true)?;
+                        let code = status.code().unwrap_or(-1);
+                        Ok(QValue::Int(QInt::new(code as i64)))
+                    }
+                    // Still running
+                    None => Ok(QValue::Nil(QNil)),
                 }
             }
             "kill" => {
-                if !args.is_empty() {
-                    return arg_err!("kill expects 0 arguments, got {}", args.len());
+                // kill(signal?) - SIGKILL unless another signal is given
+                let signal = match args.as_slice() {
+                    [] => None,
+                    [QValue::Dict(opts)] => opts.get("signal"),
+                    [value] => Some(value.clone()),
+                    _ => return arg_err!("kill expects 0 or 1 arguments (signal?), got {}", args.len()),
+                };
+                if let Some(signal) = signal {
+                    return self.call_method("send_signal", vec![signal], scope);
                 }
                 let mut child_lock = self.child.lock().unwrap();
                 if let Some(ref mut child) = *child_lock {
@@ -773,6 +972,45 @@ impl QProcess {
                     Err("terminate not supported on this platform".into())
                 }
             }
+            "pipe" => {
+                // pipe(next: Process | Array[Str]) -> Process
+                // Streams this process's stdout into the next one's stdin on a
+                // background thread and returns the next process, so calls chain.
+                if args.len() != 1 {
+                    return arg_err!("pipe expects 1 argument (process or command), got {}", args.len());
+                }
+                let next = match &args[0] {
+                    QValue::Process(p) => p.clone(),
+                    QValue::Array(_) => {
+                        let command = parse_command(&args[0], "pipe")?;
+                        let child = Command::new(&command[0])
+                            .args(&command[1..])
+                            .stdin(Stdio::piped())
+                            .stdout(Stdio::piped())
+                            .stderr(Stdio::piped())
+                            .spawn()
+                            .map_err(|e| format!("IOErr: Failed to spawn process '{}': {}", command[0], e))?;
+                        QProcess::new(child)?
+                    }
+                    other => return type_err!("pipe expects a Process or command array, got {}", other.as_obj().cls()),
+                };
+                if next.id == self.id {
+                    return value_err!("Cannot pipe a process into itself");
+                }
+
+                let Some(mut stdin) = next.stdin.stdin.lock().unwrap().take() else {
+                    return io_err!("Cannot pipe into process {}: its stdin is closed", next.pid);
+                };
+                let reader = Arc::clone(&self.stdout.reader);
+                thread::spawn(move || {
+                    if let Ok(mut reader) = reader.lock() {
+                        // The next process may exit early (e.g. head); that's not an error
+                        let _ = std::io::copy(&mut *reader, &mut stdin);
+                    }
+                    // Dropping stdin here gives the next process end of input
+                });
+                Ok(QValue::Process(next))
+            }
             "_enter" => {
                 // Context manager entry - return self
                 if !args.is_empty() {
@@ -785,9 +1023,9 @@ impl QProcess {
                 if !args.is_empty() {
                     return arg_err!("_exit expects 0 arguments, got {}", args.len());
                 }
-                let mut child_lock = self.child.lock().unwrap();
-                if let Some(mut child) = child_lock.take() {
-                    let _ = child.wait(); // Ignore errors on exit
+                let running = self.child.lock().unwrap().is_some();
+                if running {
+                    self.wait_for(None, scope)?;
                 }
                 Ok(QValue::Nil(QNil))
             }
@@ -807,6 +1045,7 @@ impl Clone for QProcess {
             stdin: self.stdin.clone(),
             stdout: self.stdout.clone(),
             stderr: self.stderr.clone(),
+            output: self.output.clone(),
             pid: self.pid,
             id: self.id,
         }
@@ -861,6 +1100,15 @@ pub fn create_process_result_type() -> QType {
     )
 }
 
+/// Create PipelineResult type definition for type annotations
+pub fn create_pipeline_result_type() -> QType {
+    QType::with_doc(
+        "PipelineResult".to_string(),
+        Vec::new(),
+        Some("Result from process.pipeline()".to_string())
+    )
+}
+
 /// Create the process module
 pub fn create_process_module() -> QValue {
     let mut members = HashMap::new();
@@ -871,10 +1119,12 @@ pub fn create_process_module() -> QValue {
     members.insert("check_run".to_string(), create_fn("process", "check_run"));
     members.insert("shell".to_string(), create_fn("process", "shell"));
     members.insert("pipeline".to_string(), create_fn("process", "pipeline"));
+    members.insert("run_all".to_string(), create_fn("process", "run_all"));
 
     // Export types (for type annotations in user code)
     members.insert("Process".to_string(), QValue::Type(Box::new(create_process_type())));
     members.insert("ProcessResult".to_string(), QValue::Type(Box::new(create_process_result_type())));
+    members.insert("PipelineResult".to_string(), QValue::Type(Box::new(create_pipeline_result_type())));
 
     QValue::Module(Box::new(QModule::new("process".to_string(), members)))
 }
@@ -1086,6 +1336,8 @@ pub fn call_process_function(func_name: &str, args: Vec<QValue>, _scope: &mut Sc
             // Parse options dict
             let mut cwd: Option<String> = None;
             let mut env: Option<HashMap<String, String>> = None;
+            let mut on_stdout: Option<QValue> = None;
+            let mut on_stderr: Option<QValue> = None;
 
             if args.len() == 2 {
                 match &args[1] {
@@ -1098,6 +1350,15 @@ pub fn call_process_function(func_name: &str, args: Vec<QValue>, _scope: &mut Sc
                             }
                         }
 
+                        // on_stdout / on_stderr line callbacks
+                        for (key, slot) in [("on_stdout", &mut on_stdout), ("on_stderr", &mut on_stderr)] {
+                            match dict.map.borrow().get(key) {
+                                Some(callback @ QValue::UserFun(_)) => *slot = Some(callback.clone()),
+                                Some(QValue::Nil(_)) | None => {}
+                                Some(_) => return type_err!("process.spawn {} option must be a function", key),
+                            }
+                        }
+
                         // env option
                         if let Some(env_val) = dict.map.borrow().get("env") {
                             match env_val {
@@ -1151,7 +1412,17 @@ pub fn call_process_function(func_name: &str, args: Vec<QValue>, _scope: &mut Sc
                 .map_err(|e| format!("Failed to spawn process '{}': {}", command[0], e))?;
 
             // Create Process object
-            let process = QProcess::new(child)?;
+            let mut process = QProcess::new(child)?;
+            if on_stdout.is_some() || on_stderr.is_some() {
+                let (tx, rx) = mpsc::channel();
+                if on_stdout.is_some() {
+                    forward_lines(Arc::clone(&process.stdout.reader), tx.clone(), OutputLine::Stdout);
+                }
+                if on_stderr.is_some() {
+                    forward_lines(Arc::clone(&process.stderr.reader), tx, OutputLine::Stderr);
+                }
+                process.output = Some(Rc::new(OutputCallbacks { on_stdout, on_stderr, lines: rx }));
+            }
             Ok(QValue::Process(process))
         }
 
@@ -1230,109 +1501,472 @@ pub fn call_process_function(func_name: &str, args: Vec<QValue>, _scope: &mut Sc
         }
 
         "process.pipeline" => {
-            // process.pipeline(commands) - Chain multiple commands with pipes
-            if args.len() != 1 {
-                return arg_err!("process.pipeline expects 1 argument (commands array), got {}", args.len());
+            // process.pipeline(commands: Array[Array[Str]], options?: Dict) -> PipelineResult
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("process.pipeline expects 1 or 2 arguments (commands, options?), got {}", args.len());
             }
 
             let commands = match &args[0] {
                 QValue::Array(arr) => {
-                    let elements = arr.elements.borrow();
-                    let mut cmd_list = Vec::new();
+                    let mut commands = Vec::new();
+                    for cmd in arr.elements.borrow().iter() {
+                        commands.push(parse_command(cmd, "process.pipeline")?);
+                    }
+                    commands
+                }
+                _ => return Err("process.pipeline expects array of commands".into()),
+            };
 
-                    for elem in elements.iter() {
-                        match elem {
-                            QValue::Array(cmd_arr) => {
-                                let cmd_elements = cmd_arr.elements.borrow();
-                                let mut cmd_parts = Vec::new();
-                                for cmd_elem in cmd_elements.iter() {
-                                    match cmd_elem {
-                                        QValue::Str(s) => cmd_parts.push((*s.value).clone()),
-                                        _ => return Err("pipeline commands must be arrays of strings".into()),
+            if commands.is_empty() {
+                return Err("pipeline must have at least one command".into());
+            }
+
+            // Parse options (stdin, cwd, env, timeout, pipefail)
+            let mut stdin_data: Option<Vec<u8>> = None;
+            let mut cwd: Option<String> = None;
+            let mut env: Option<HashMap<String, String>> = None;
+            let mut timeout: Option<Duration> = None;
+            let mut pipefail = false;
+
+            if args.len() == 2 {
+                match &args[1] {
+                    QValue::Dict(dict) => {
+                        let map = dict.map.borrow();
+                        if let Some(stdin_val) = map.get("stdin") {
+                            match stdin_val {
+                                QValue::Str(s) => stdin_data = Some(s.value.as_bytes().to_vec()),
+                                QValue::Bytes(b) => stdin_data = Some(b.data.clone()),
+                                QValue::Nil(_) => {}
+                                _ => return Err("process.pipeline stdin option must be string or bytes".into()),
+                            }
+                        }
+                        if let Some(cwd_val) = map.get("cwd") {
+                            match cwd_val {
+                                QValue::Str(s) => cwd = Some(s.value.as_ref().clone()),
+                                _ => return Err("process.pipeline cwd option must be string".into()),
+                            }
+                        }
+                        if let Some(env_val) = map.get("env") {
+                            match env_val {
+                                QValue::Dict(env_dict) => {
+                                    let mut env_map = HashMap::new();
+                                    for (k, v) in env_dict.map.borrow().iter() {
+                                        match v {
+                                            QValue::Str(s) => { env_map.insert(k.clone(), s.value.as_ref().clone()); }
+                                            _ => return Err("process.pipeline env values must be strings".into()),
+                                        }
                                     }
+                                    env = Some(env_map);
                                 }
-                                if cmd_parts.is_empty() {
-                                    return Err("pipeline command cannot be empty".into());
-                                }
-                                cmd_list.push(cmd_parts);
+                                _ => return Err("process.pipeline env option must be dict".into()),
+                            }
+                        }
+                        if let Some(timeout_val) = map.get("timeout") {
+                            match timeout_val {
+                                QValue::Int(secs) if secs.value > 0 => timeout = Some(Duration::from_secs(secs.value as u64)),
+                                QValue::Float(secs) if secs.value > 0.0 => timeout = Some(Duration::from_secs_f64(secs.value)),
+                                QValue::Nil(_) => {}
+                                _ => return Err("timeout must be positive number of seconds".into()),
                             }
-                            _ => return Err("pipeline expects array of command arrays".into()),
+                        }
+                        if let Some(pipefail_val) = map.get("pipefail") {
+                            pipefail = pipefail_val.as_bool();
                         }
                     }
-                    cmd_list
+                    _ => return Err("process.pipeline options must be dict".into()),
                 }
-                _ => return Err("process.pipeline expects array of commands".into()),
-            };
+            }
 
-            if commands.is_empty() {
-                return Err("pipeline must have at least one command".into());
+            // Spawn every stage with the previous stage's stdout as its stdin,
+            // so data flows between them through OS pipes
+            let mut children: Vec<Child> = Vec::new();
+            let mut previous: Option<ChildStdout> = None;
+            for (i, command) in commands.iter().enumerate() {
+                let mut cmd = Command::new(&command[0]);
+                cmd.args(&command[1..]);
+                if let Some(dir) = &cwd {
+                    cmd.current_dir(dir);
+                }
+                if let Some(env_vars) = &env {
+                    cmd.env_clear();
+                    cmd.envs(env_vars);
+                }
+                match previous.take() {
+                    Some(stdout) => cmd.stdin(Stdio::from(stdout)),
+                    None if stdin_data.is_some() => cmd.stdin(Stdio::piped()),
+                    None => cmd.stdin(Stdio::null()),
+                };
+                cmd.stdout(Stdio::piped());
+                cmd.stderr(Stdio::piped());
+
+                let mut child = match cmd.spawn() {
+                    Ok(child) => child,
+                    Err(e) => {
+                        for mut child in children {
+                            let _ = child.kill();
+                            let _ = child.wait();
+                        }
+                        return io_err!("Failed to spawn process '{}': {}", command[0], e);
+                    }
+                };
+                if i + 1 < commands.len() {
+                    previous = child.stdout.take();
+                }
+                children.push(child);
             }
 
-            // Spawn all processes with piped I/O
-            let mut children = Vec::new();
-            for (i, cmd) in commands.iter().enumerate() {
-                let mut command = Command::new(&cmd[0]);
-                if cmd.len() > 1 {
-                    for arg in &cmd[1..] {
-                        command.arg(arg);
+            // Feed stdin and drain every output on background threads so no
+            // stage blocks on a full pipe
+            if let (Some(data), Some(mut stdin)) = (stdin_data, children[0].stdin.take()) {
+                thread::spawn(move || {
+                    let _ = stdin.write_all(&data);
+                });
+            }
+            let mut outputs = Vec::new();
+            for child in children.iter_mut() {
+                let stdout = child.stdout.take().map(|s| Box::new(s) as Box<dyn Read + Send>);
+                let stderr = child.stderr.take().map(|s| Box::new(s) as Box<dyn Read + Send>);
+                outputs.push((drain(stdout), drain(stderr)));
+            }
+
+            // Wait for every stage; on timeout kill whatever is still running
+            let started = Instant::now();
+            let mut statuses: Vec<Option<(i64, bool)>> = vec![None; children.len()];
+            while statuses.iter().any(Option::is_none) {
+                let expired = timeout.is_some_and(|t| started.elapsed() >= t);
+                for (i, child) in children.iter_mut().enumerate() {
+                    if statuses[i].is_some() {
+                        continue;
                     }
+                    statuses[i] = match child.try_wait() {
+                        Ok(Some(status)) => Some((status.code().unwrap_or(-1) as i64, false)),
+                        Ok(None) if expired => {
+                            let _ = child.kill();
+                            let _ = child.wait();
+                            Some((-1, true))
+                        }
+                        Ok(None) => None,
+                        Err(e) => {
+                            for child in children.iter_mut() {
+                                let _ = child.kill();
+                                let _ = child.wait();
+                            }
+                            return runtime_err!("Failed to poll process: {}", e);
+                        }
+                    };
                 }
+                if statuses.iter().any(Option::is_none) {
+                    thread::sleep(Duration::from_millis(5));
+                }
+            }
 
-                // All processes need stdin and stdout piped
-                command.stdin(Stdio::piped());
-                command.stdout(Stdio::piped());
-                command.stderr(Stdio::piped());
+            let stages = outputs.into_iter().zip(statuses).map(|((stdout, stderr), status)| {
+                let (code, timed_out) = status.unwrap_or((-1, false));
+                // Only the last stage's stdout is captured; the others fed the next stage
+                let stdout = stdout.join().unwrap_or_default();
+                let stderr = stderr.join().unwrap_or_default();
+                let mut result = QProcessResult::new(
+                    String::from_utf8_lossy(&stdout).to_string(),
+                    String::from_utf8_lossy(&stderr).to_string(),
+                    stdout,
+                    stderr,
+                    code,
+                );
+                result.timed_out = timed_out;
+                result
+            }).collect();
+
+            Ok(QValue::PipelineResult(QPipelineResult::new(stages, pipefail)))
+        }
 
-                let child = command.spawn()
-                    .map_err(|e| format!("Failed to spawn command {}: {}", i, e))?;
-                children.push(child);
+        "process.run_all" => {
+            // process.run_all(commands: Array[Array[Str]], options?: Dict) -> Array[ProcessResult]
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("process.run_all expects 1 or 2 arguments (commands, options?), got {}", args.len());
+            }
+
+            let commands = match &args[0] {
+                QValue::Array(arr) => {
+                    let mut commands = Vec::new();
+                    for cmd in arr.elements.borrow().iter() {
+                        commands.push(parse_command(cmd, "process.run_all")?);
+                    }
+                    commands
+                }
+                _ => return Err("process.run_all expects array of commands".into()),
+            };
+
+            // Parse options (jobs, timeout, cwd, env, on_complete)
+            let mut jobs = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            let mut timeout: Option<Duration> = None;
+            let mut cwd: Option<String> = None;
+            let mut env: Option<HashMap<String, String>> = None;
+            let mut on_complete: Option<QValue> = None;
+
+            if args.len() == 2 {
+                match &args[1] {
+                    QValue::Dict(dict) => {
+                        let map = dict.map.borrow();
+                        if let Some(jobs_val) = map.get("jobs") {
+                            match jobs_val {
+                                QValue::Int(n) if n.value > 0 => jobs = n.value as usize,
+                                _ => return value_err!("process.run_all jobs option must be a positive int"),
+                            }
+                        }
+                        if let Some(timeout_val) = map.get("timeout") {
+                            match timeout_val {
+                                QValue::Int(secs) if secs.value > 0 => timeout = Some(Duration::from_secs(secs.value as u64)),
+                                QValue::Float(secs) if secs.value > 0.0 => timeout = Some(Duration::from_secs_f64(secs.value)),
+                                QValue::Nil(_) => {}
+                                _ => return Err("timeout must be positive number of seconds".into()),
+                            }
+                        }
+                        if let Some(cwd_val) = map.get("cwd") {
+                            match cwd_val {
+                                QValue::Str(s) => cwd = Some(s.value.as_ref().clone()),
+                                _ => return Err("process.run_all cwd option must be string".into()),
+                            }
+                        }
+                        if let Some(env_val) = map.get("env") {
+                            match env_val {
+                                QValue::Dict(env_dict) => {
+                                    let mut env_map = HashMap::new();
+                                    for (k, v) in env_dict.map.borrow().iter() {
+                                        match v {
+                                            QValue::Str(s) => { env_map.insert(k.clone(), s.value.as_ref().clone()); }
+                                            _ => return Err("process.run_all env values must be strings".into()),
+                                        }
+                                    }
+                                    env = Some(env_map);
+                                }
+                                _ => return Err("process.run_all env option must be dict".into()),
+                            }
+                        }
+                        if let Some(callback) = map.get("on_complete") {
+                            match callback {
+                                QValue::UserFun(_) => on_complete = Some(callback.clone()),
+                                QValue::Nil(_) => {}
+                                _ => return Err("process.run_all on_complete option must be a function".into()),
+                            }
+                        }
+                    }
+                    _ => return Err("process.run_all options must be dict".into()),
+                }
             }
 
-            // Connect pipes: stdout of i -> stdin of i+1
-            for i in 0..children.len() {
-                if i > 0 {
-                    // Get stdout from previous process
-                    if let Some(mut stdout) = children[i - 1].stdout.take() {
-                        let mut buffer = Vec::new();
-                        stdout.read_to_end(&mut buffer)
-                            .map_err(|e| format!("Failed to read from process {}: {}", i - 1, e))?;
-
-                        // Write to current process stdin
-                        if let Some(mut stdin) = children[i].stdin.take() {
-                            stdin.write_all(&buffer)
-                                .map_err(|e| format!("Failed to write to process {}: {}", i, e))?;
+            let mut results: Vec<Option<QValue>> = vec![None; commands.len()];
+            let mut running: Vec<RunningCommand> = Vec::new();
+            let mut next = 0;
+
+            while next < commands.len() || !running.is_empty() {
+                // Keep up to `jobs` commands running
+                while running.len() < jobs && next < commands.len() {
+                    let command = &commands[next];
+                    let mut cmd = Command::new(&command[0]);
+                    cmd.args(&command[1..]);
+                    if let Some(dir) = &cwd {
+                        cmd.current_dir(dir);
+                    }
+                    if let Some(env_vars) = &env {
+                        cmd.env_clear();
+                        cmd.envs(env_vars);
+                    }
+                    cmd.stdin(Stdio::null());
+                    cmd.stdout(Stdio::piped());
+                    cmd.stderr(Stdio::piped());
+
+                    match cmd.spawn() {
+                        Ok(child) => running.push(RunningCommand::start(next, child)),
+                        Err(e) => {
+                            kill_all(running);
+                            return Err(format!("Failed to spawn process '{}': {}", command[0], e).into());
                         }
                     }
+                    next += 1;
+                }
+
+                // Collect finished (or timed out) commands
+                let mut finished_any = false;
+                let mut i = 0;
+                while i < running.len() {
+                    let status = match running[i].child.try_wait() {
+                        Ok(Some(status)) => Some((status.code().unwrap_or(-1) as i64, false)),
+                        Ok(None) if timeout.is_some_and(|t| running[i].started.elapsed() >= t) => {
+                            let _ = running[i].child.kill();
+                            let _ = running[i].child.wait();
+                            Some((-1, true))
+                        }
+                        Ok(None) => None,
+                        Err(e) => {
+                            kill_all(running);
+                            return runtime_err!("Failed to poll process: {}", e);
+                        }
+                    };
+
+                    let Some((code, timed_out)) = status else {
+                        i += 1;
+                        continue;
+                    };
+
+                    finished_any = true;
+                    let (index, result) = running.remove(i).finish(code, timed_out);
+                    let value = QValue::ProcessResult(result);
+
+                    if let Some(QValue::UserFun(callback)) = &on_complete {
+                        let call_args = crate::function_call::CallArguments::positional_only(vec![
+                            QValue::Int(QInt::new(index as i64)),
+                            value.clone(),
+                        ]);
+                        if let Err(e) = crate::function_call::call_user_function(callback, call_args, _scope, None) {
+                            kill_all(running);
+                            return Err(e);
+                        }
+                    }
+                    results[index] = Some(value);
+                }
+
+                if !finished_any {
+                    thread::sleep(Duration::from_millis(10));
                 }
             }
 
-            // Get output from last process
-            let last_child = children.pop().unwrap();
+            let results = results.into_iter().map(|r| r.unwrap_or(QValue::Nil(QNil))).collect();
+            Ok(QValue::Array(QArray::new(results)))
+        }
 
-            let output = last_child.wait_with_output()
-                .map_err(|e| format!("Failed to wait for last process: {}", e))?;
+        _ => attr_err!("Unknown process function: {}", func_name)
+    }
+}
 
-            // Wait for all other processes
-            for mut child in children {
-                let _ = child.wait();
+/// Parse a command array (Array[Str]) into program and arguments
+fn parse_command(value: &QValue, func_name: &str) -> Result<Vec<String>, EvalError> {
+    let parts = match value {
+        QValue::Array(arr) => {
+            let mut parts = Vec::new();
+            for elem in arr.elements.borrow().iter() {
+                match elem {
+                    QValue::Str(s) => parts.push(s.value.as_ref().clone()),
+                    _ => return Err(format!("{} command must be array of strings", func_name).into()),
+                }
             }
+            parts
+        }
+        _ => return Err(format!("{} expects each command to be an array", func_name).into()),
+    };
+    if parts.is_empty() {
+        return Err(format!("{} command array cannot be empty", func_name).into());
+    }
+    Ok(parts)
+}
 
-            // Return ProcessResult from last command
-            let stdout_str = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr_str = String::from_utf8_lossy(&output.stderr).to_string();
-            let exit_code = output.status.code().unwrap_or(-1);
+/// A child started by process.run_all(), with its output drained on background
+/// threads so a full pipe never blocks it
+struct RunningCommand {
+    index: usize,
+    child: Child,
+    started: Instant,
+    stdout: thread::JoinHandle<Vec<u8>>,
+    stderr: thread::JoinHandle<Vec<u8>>,
+}
 
-            let result = QProcessResult::new(
-                stdout_str,
-                stderr_str,
-                output.stdout.clone(),
-                output.stderr,
-                exit_code as i64
-            );
+impl RunningCommand {
+    fn start(index: usize, mut child: Child) -> Self {
+        let stdout = child.stdout.take().map(|s| Box::new(s) as Box<dyn Read + Send>);
+        let stderr = child.stderr.take().map(|s| Box::new(s) as Box<dyn Read + Send>);
+        RunningCommand {
+            index,
+            child,
+            started: Instant::now(),
+            stdout: drain(stdout),
+            stderr: drain(stderr),
+        }
+    }
 
-            Ok(QValue::ProcessResult(result))
+    fn finish(self, code: i64, timed_out: bool) -> (usize, QProcessResult) {
+        let stdout = self.stdout.join().unwrap_or_default();
+        let stderr = self.stderr.join().unwrap_or_default();
+        let mut result = QProcessResult::new(
+            String::from_utf8_lossy(&stdout).to_string(),
+            String::from_utf8_lossy(&stderr).to_string(),
+            stdout,
+            stderr,
+            code,
+        );
+        result.timed_out = timed_out;
+        (self.index, result)
+    }
+}
+
+fn drain(stream: Option<Box<dyn Read + Send>>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut stream) = stream {
+            let _ = stream.read_to_end(&mut buffer);
         }
+        buffer
+    })
+}
 
-        _ => attr_err!("Unknown process function: {}", func_name)
+fn kill_all(running: Vec<RunningCommand>) {
+    for mut job in running {
+        let _ = job.child.kill();
+        let _ = job.child.wait();
+    }
+}
+
+/// A line of output from a process spawned with callbacks
+enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Callbacks for a process spawned with on_stdout/on_stderr. Reader threads
+/// queue lines; wait() and poll() pass them to the callbacks on the
+/// interpreter thread.
+struct OutputCallbacks {
+    on_stdout: Option<QValue>,
+    on_stderr: Option<QValue>,
+    lines: mpsc::Receiver<OutputLine>,
+}
+
+/// Read lines from a process stream on a background thread until it closes
+fn forward_lines(
+    reader: Arc<Mutex<BufReader<Box<dyn Read + Send>>>>,
+    tx: mpsc::Sender<OutputLine>,
+    wrap: fn(String) -> OutputLine,
+) {
+    thread::spawn(move || {
+        let Ok(mut reader) = reader.lock() else {
+            return;
+        };
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if line.ends_with(b"\n") {
+                        line.pop();
+                        if line.ends_with(b"\r") {
+                            line.pop();
+                        }
+                    }
+                    if tx.send(wrap(String::from_utf8_lossy(&line).into_owned())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Parse a timeout in seconds; nil means no timeout
+fn timeout_arg(value: &QValue, method: &str) -> Result<Option<Duration>, EvalError> {
+    match value {
+        QValue::Nil(_) => Ok(None),
+        QValue::Int(i) if i.value > 0 => Ok(Some(Duration::from_secs(i.value as u64))),
+        QValue::Float(f) if f.value > 0.0 => Ok(Some(Duration::from_secs_f64(f.value))),
+        QValue::Int(_) | QValue::Float(_) => Err("timeout must be positive".into()),
+        _ => Err(format!("{} expects int or float (seconds)", method).into()),
     }
 }
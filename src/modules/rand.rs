@@ -12,6 +12,12 @@ use rand::seq::{SliceRandom, IteratorRandom};
 use rand_pcg::Pcg64;
 
 use crate::types::*;
+use crate::modules::args::{option, take_options};
+
+thread_local! {
+    /// Generator behind the module-level functions (rand.int, rand.shuffle, ...)
+    static DEFAULT_RNG: QRng = QRng::Fast(Rc::new(RefCell::new(Pcg64::from_entropy())));
+}
 
 /// QRng represents a random number generator object in Quest
 #[derive(Debug, Clone)]
@@ -125,6 +131,119 @@ impl QRng {
         bytes
     }
 
+    /// Uniform float in [0.0, 1.0)
+    fn unit(&self) -> f64 {
+        self.float(None, None).unwrap_or(0.0)
+    }
+
+    /// Sample from a normal (Gaussian) distribution (Box-Muller)
+    pub fn normal(&self, mean: f64, std_dev: f64) -> Result<f64, String> {
+        if std_dev.is_nan() || std_dev < 0.0 {
+            return value_err!("normal() std must be non-negative, got {}", std_dev);
+        }
+        // 1 - unit() is in (0, 1], so the logarithm is finite
+        let u1 = 1.0 - self.unit();
+        let u2 = self.unit();
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        Ok(mean + std_dev * z)
+    }
+
+    /// Sample from an exponential distribution with the given rate (1 / mean)
+    pub fn exponential(&self, rate: f64) -> Result<f64, String> {
+        if rate.is_nan() || rate <= 0.0 {
+            return value_err!("exponential() rate must be positive, got {}", rate);
+        }
+        Ok(-(1.0 - self.unit()).ln() / rate)
+    }
+
+    /// Sample from a Poisson distribution with mean `lambda`
+    pub fn poisson(&self, lambda: f64) -> Result<i64, String> {
+        if !lambda.is_finite() || lambda < 0.0 {
+            return value_err!("poisson() lambda must be a non-negative number, got {}", lambda);
+        }
+        if lambda < 30.0 {
+            // Knuth: count uniform draws until their product drops below e^-lambda
+            let limit = (-lambda).exp();
+            let mut k = 0;
+            let mut product = self.unit();
+            while product > limit {
+                k += 1;
+                product *= self.unit();
+            }
+            return Ok(k);
+        }
+        // Transformed rejection with squeeze (Hörmann's PTRS) for large means
+        let slam = lambda.sqrt();
+        let loglam = lambda.ln();
+        let b = 0.931 + 2.53 * slam;
+        let a = -0.059 + 0.02483 * b;
+        let inv_alpha = 1.1239 + 1.1328 / (b - 3.4);
+        let vr = 0.9277 - 3.6224 / (b - 2.0);
+        loop {
+            let u = self.unit() - 0.5;
+            let v = self.unit();
+            let us = 0.5 - u.abs();
+            let k = ((2.0 * a / us + b) * u + lambda + 0.43).floor();
+            if us >= 0.07 && v <= vr {
+                return Ok(k as i64);
+            }
+            if k < 0.0 || (us < 0.013 && v > us) {
+                continue;
+            }
+            if v.ln() + inv_alpha.ln() - (a / (us * us) + b).ln() <= -lambda + k * loglam - ln_factorial(k) {
+                return Ok(k as i64);
+            }
+        }
+    }
+
+    /// Pick an element with probability proportional to its weight
+    pub fn weighted_choice(&self, array: &QArray, weights: &[f64]) -> Result<QValue, String> {
+        let elements = array.elements.borrow();
+        if elements.len() != weights.len() {
+            return value_err!("choice() got {} weights for {} elements", weights.len(), elements.len());
+        }
+        if let Some(w) = weights.iter().find(|w| !w.is_finite() || **w < 0.0) {
+            return value_err!("choice() weights must be non-negative numbers, got {}", w);
+        }
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return value_err!("choice() weights must not all be zero");
+        }
+        let mut target = self.unit() * total;
+        for (element, weight) in elements.iter().zip(weights) {
+            if target < *weight {
+                return Ok(element.clone());
+            }
+            target -= weight;
+        }
+        // Rounding left `target` just past the end: take the last weighted element
+        let last = weights.iter().rposition(|w| *w > 0.0).unwrap_or(0);
+        Ok(elements[last].clone())
+    }
+
+    /// A new generator of the same kind. Without a key the child is seeded
+    /// from this generator's output (advancing it); with a key it is derived
+    /// from the current state and the key without advancing, so the same key
+    /// gives the same stream.
+    pub fn fork(&self, key: Option<u64>) -> QRng {
+        fn seed<R: RngCore + Clone>(rng: &RefCell<R>, key: Option<u64>) -> [u8; 32] {
+            match key {
+                None => rng.borrow_mut().gen(),
+                Some(key) => {
+                    let mut seed: [u8; 32] = rng.borrow().clone().gen();
+                    let mask: [u8; 32] = StdRng::seed_from_u64(key).gen();
+                    seed.iter_mut().zip(mask).for_each(|(byte, m)| *byte ^= m);
+                    seed
+                }
+            }
+        }
+        match self {
+            QRng::Secure(rng) => QRng::Secure(Rc::new(RefCell::new(StdRng::from_seed(seed(rng, key))))),
+            QRng::Fast(rng) => QRng::Fast(Rc::new(RefCell::new(Pcg64::from_seed(seed(rng, key))))),
+            QRng::Seeded(rng) => QRng::Seeded(Rc::new(RefCell::new(StdRng::from_seed(seed(rng, key))))),
+        }
+    }
+
     /// Pick random element from array
     pub fn choice(&self, array: &QArray) -> Result<QValue, EvalError> {
         let elements = array.elements.borrow();
@@ -184,6 +303,27 @@ impl QRng {
     }
 }
 
+/// ln(k!) for a whole number k: exact sum for small k, Stirling series above
+fn ln_factorial(k: f64) -> f64 {
+    if k < 10.0 {
+        return (2..=k as u32).map(|i| (i as f64).ln()).sum();
+    }
+    k * k.ln() - k + 0.5 * (2.0 * std::f64::consts::PI * k).ln() + 1.0 / (12.0 * k) - 1.0 / (360.0 * k * k * k)
+}
+
+/// Seed value from an Int or Str (strings are hashed)
+fn seed_value(func: &str, value: &QValue) -> Result<u64, String> {
+    match value {
+        QValue::Int(i) => Ok(i.value as u64),
+        QValue::Str(s) => {
+            let mut hasher = DefaultHasher::new();
+            s.value.hash(&mut hasher);
+            Ok(hasher.finish())
+        }
+        _ => type_err!("{}() expects Int or Str, got {}", func, value.as_obj().cls()),
+    }
+}
+
 /// Create the rand module
 pub fn create_rand_module() -> QValue {
     let mut members = HashMap::new();
@@ -192,6 +332,11 @@ pub fn create_rand_module() -> QValue {
     members.insert("fast".to_string(), create_fn("rand", "fast"));
     members.insert("seed".to_string(), create_fn("rand", "seed"));
 
+    // Shortcuts on a per-thread fast generator
+    for name in ["int", "float", "bool", "choice", "shuffle", "sample", "normal", "exponential", "poisson"] {
+        members.insert(name.to_string(), create_fn("rand", name));
+    }
+
     QValue::Module(Box::new(QModule::new("rand".to_string(), members)))
 }
 
@@ -201,6 +346,12 @@ pub fn call_rand_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
         "rand.secure" => rand_secure(args),
         "rand.fast" => rand_fast(args),
         "rand.seed" => rand_seed(args),
+        "rand.int" | "rand.float" | "rand.bool" | "rand.choice" | "rand.shuffle" | "rand.sample"
+        | "rand.normal" | "rand.exponential" | "rand.poisson" => {
+            let method = &func_name["rand.".len()..];
+            let rng = DEFAULT_RNG.with(QRng::clone);
+            call_rng_method(&rng, method, args)
+        }
         _ => attr_err!("Unknown rand function: {}", func_name)
     }
 }
@@ -235,16 +386,7 @@ fn rand_seed(args: Vec<QValue>) -> Result<QValue, EvalError> {
         return arg_err!("seed() expects 1 argument, got {}", args.len());
     }
 
-    let seed = match &args[0] {
-        QValue::Int(i) => i.value as u64,
-        QValue::Str(s) => {
-            // Hash the string to get a seed
-            let mut hasher = DefaultHasher::new();
-            s.value.hash(&mut hasher);
-            hasher.finish()
-        }
-        _ => return type_err!("seed() expects Int or Str, got {}", args[0].as_obj().cls()),
-    };
+    let seed = seed_value("seed", &args[0])?;
 
     let rng = StdRng::seed_from_u64(seed);
     Ok(QValue::Rng(Box::new(QRng::Seeded(Rc::new(RefCell::new(rng))))))
@@ -261,6 +403,10 @@ pub fn call_rng_method(rng: &QRng, method_name: &str, args: Vec<QValue>) -> Resu
         "choice" => rng_choice(rng, args),
         "shuffle" => rng_shuffle(rng, args),
         "sample" => rng_sample(rng, args),
+        "normal" => Ok(rng_normal(rng, args)?),
+        "exponential" => Ok(rng_exponential(rng, args)?),
+        "poisson" => Ok(rng_poisson(rng, args)?),
+        "fork" => Ok(rng_fork(rng, args)?),
         // Object introspection methods
         "cls" | "_type" => {
             if !args.is_empty() {
@@ -372,10 +518,11 @@ fn rng_bytes(rng: &QRng, args: Vec<QValue>) -> Result<QValue, EvalError> {
     Ok(QValue::Bytes(QBytes::new(bytes)))
 }
 
-/// rng.choice(array) - Pick random element from array
-fn rng_choice(rng: &QRng, args: Vec<QValue>) -> Result<QValue, EvalError> {
-    if args.len() != 1 {
-        return arg_err!("choice() expects 1 argument, got {}", args.len());
+/// rng.choice(array, weights: nil) - Pick random element from array
+fn rng_choice(rng: &QRng, mut args: Vec<QValue>) -> Result<QValue, EvalError> {
+    let options = take_options(&mut args);
+    if args.is_empty() || args.len() > 2 {
+        return arg_err!("choice() expects 1 or 2 arguments (array, weights), got {}", args.len());
     }
 
     let array = match &args[0] {
@@ -383,7 +530,16 @@ fn rng_choice(rng: &QRng, args: Vec<QValue>) -> Result<QValue, EvalError> {
         _ => return type_err!("choice() expects Array, got {}", args[0].as_obj().cls()),
     };
 
-    rng.choice(array)
+    match args.get(1).cloned().or_else(|| option(&options, "weights")) {
+        None | Some(QValue::Nil(_)) => rng.choice(array),
+        Some(QValue::Array(weights)) => {
+            let weights = weights.elements.borrow().iter()
+                .map(|w| number_arg("choice", "weights", w))
+                .collect::<Result<Vec<f64>, String>>()?;
+            Ok(rng.weighted_choice(array, &weights)?)
+        }
+        Some(other) => type_err!("choice() weights must be Array, got {}", other.as_obj().cls()),
+    }
 }
 
 /// rng.shuffle(array) - Shuffle array in place
@@ -425,3 +581,70 @@ fn rng_sample(rng: &QRng, args: Vec<QValue>) -> Result<QValue, EvalError> {
     let sampled = rng.sample(array, k)?;
     Ok(QValue::Array(QArray::new(sampled)))
 }
+
+/// rng.normal(mean = 0, std = 1) - Sample from a normal distribution
+fn rng_normal(rng: &QRng, mut args: Vec<QValue>) -> Result<QValue, String> {
+    let options = take_options(&mut args);
+    if args.len() > 2 {
+        return arg_err!("normal() expects 0 to 2 arguments (mean, std), got {}", args.len());
+    }
+
+    let mean = match args.first().cloned().or_else(|| option(&options, "mean")) {
+        Some(value) => number_arg("normal", "mean", &value)?,
+        None => 0.0,
+    };
+    let std_dev = match args.get(1).cloned().or_else(|| option(&options, "std")) {
+        Some(value) => number_arg("normal", "std", &value)?,
+        None => 1.0,
+    };
+
+    Ok(QValue::Float(QFloat::new(rng.normal(mean, std_dev)?)))
+}
+
+/// rng.exponential(rate = 1) - Sample from an exponential distribution
+fn rng_exponential(rng: &QRng, mut args: Vec<QValue>) -> Result<QValue, String> {
+    let options = take_options(&mut args);
+    if args.len() > 1 {
+        return arg_err!("exponential() expects 0 or 1 arguments (rate), got {}", args.len());
+    }
+
+    let rate = match args.first().cloned().or_else(|| option(&options, "rate")) {
+        Some(value) => number_arg("exponential", "rate", &value)?,
+        None => 1.0,
+    };
+
+    Ok(QValue::Float(QFloat::new(rng.exponential(rate)?)))
+}
+
+/// rng.poisson(lambda) - Sample from a Poisson distribution
+fn rng_poisson(rng: &QRng, mut args: Vec<QValue>) -> Result<QValue, String> {
+    let options = take_options(&mut args);
+    let lambda = match (args.len(), args.first().cloned().or_else(|| option(&options, "lambda"))) {
+        (0 | 1, Some(value)) => number_arg("poisson", "lambda", &value)?,
+        (n, _) => return arg_err!("poisson() expects 1 argument (lambda), got {}", n),
+    };
+
+    Ok(QValue::Int(QInt::new(rng.poisson(lambda)?)))
+}
+
+/// rng.fork(key = nil) - Independent child generator of the same kind
+fn rng_fork(rng: &QRng, args: Vec<QValue>) -> Result<QValue, String> {
+    if args.len() > 1 {
+        return arg_err!("fork() expects 0 or 1 arguments (key), got {}", args.len());
+    }
+
+    let key = match args.first() {
+        None | Some(QValue::Nil(_)) => None,
+        Some(value) => Some(seed_value("fork", value)?),
+    };
+
+    Ok(QValue::Rng(Box::new(rng.fork(key))))
+}
+
+fn number_arg(func: &str, name: &str, value: &QValue) -> Result<f64, String> {
+    match value {
+        QValue::Int(i) => Ok(i.value as f64),
+        QValue::Float(f) => Ok(f.value),
+        _ => type_err!("{}() {} must be Int or Float, got {}", func, name, value.as_obj().cls()),
+    }
+}
@@ -1,18 +1,36 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
-use crate::{arg_err, name_err};
+use crate::function_call::{call_user_function, CallArguments};
+use crate::{arg_err, attr_err, index_err, key_err, name_err, type_err, value_err};
 use crate::types::*;
-use regex::Regex;
+use regex::{Captures, Regex, RegexBuilder};
 
+/// Patterns passed as strings are compiled once and reused from here, so
+/// `regex.match(pat, line)` in a loop doesn't recompile `pat` every time
+const CACHE_SIZE: usize = 256;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
 
 pub fn create_regex_module() -> QValue {
     let mut members = HashMap::new();
 
+    // Compiled patterns
+    members.insert("compile".to_string(), create_fn("regex", "compile"));
+
     // Pattern matching and testing
     members.insert("match".to_string(), create_fn("regex", "match"));
     members.insert("find".to_string(), create_fn("regex", "find"));
     members.insert("find_all".to_string(), create_fn("regex", "find_all"));
 
+    // Match objects (spans and named groups)
+    members.insert("search".to_string(), create_fn("regex", "search"));
+    members.insert("find_iter".to_string(), create_fn("regex", "find_iter"));
+
     // Capture groups
     members.insert("captures".to_string(), create_fn("regex", "captures"));
     members.insert("captures_all".to_string(), create_fn("regex", "captures_all"));
@@ -20,7 +38,9 @@ pub fn create_regex_module() -> QValue {
     // String manipulation
     members.insert("replace".to_string(), create_fn("regex", "replace"));
     members.insert("replace_all".to_string(), create_fn("regex", "replace_all"));
+    members.insert("replace_with".to_string(), create_fn("regex", "replace_with"));
     members.insert("split".to_string(), create_fn("regex", "split"));
+    members.insert("escape".to_string(), create_fn("regex", "escape"));
 
     // Pattern validation
     members.insert("is_valid".to_string(), create_fn("regex", "is_valid"));
@@ -28,152 +48,437 @@ pub fn create_regex_module() -> QValue {
     QValue::Module(Box::new(QModule::new("regex".to_string(), members)))
 }
 
-pub fn call_regex_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+pub fn call_regex_function(func_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
     match func_name {
-        "regex.match" => {
-            if args.len() != 2 {
-                return arg_err!("regex.match expects 2 arguments (pattern, text), got {}", args.len());
+        "regex.compile" => {
+            // compile(pattern, flags = "") - flags: i (ignore case), m (multi-line), s (dot matches newline), x (verbose)
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("regex.compile expects 1 or 2 arguments (pattern, flags), got {}", args.len());
             }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
-
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
-
-            let is_match = re.is_match(&text);
-            Ok(QValue::Bool(QBool::new(is_match)))
+            let pattern = match &args[0] {
+                QValue::Str(s) => s.value.as_ref().clone(),
+                QValue::RegexPattern(p) => return Ok(QValue::RegexPattern(p.clone())),
+                other => return type_err!("regex.compile expects Str pattern, got {}", other.as_obj().cls()),
+            };
+            let flags = match args.get(1) {
+                None | Some(QValue::Nil(_)) => String::new(),
+                Some(QValue::Str(s)) => s.value.as_ref().clone(),
+                Some(QValue::Dict(options)) => options.get("flags").map(|f| f.as_str()).unwrap_or_default(),
+                Some(other) => return type_err!("regex.compile flags must be Str, got {}", other.as_obj().cls()),
+            };
+            Ok(QValue::RegexPattern(Box::new(QRegexPattern::compile(&pattern, &flags)?)))
         }
-        "regex.find" => {
-            if args.len() != 2 {
-                return arg_err!("regex.find expects 2 arguments (pattern, text), got {}", args.len());
+        "regex.is_valid" => {
+            if args.len() != 1 {
+                return arg_err!("regex.is_valid expects 1 argument, got {}", args.len());
             }
             let pattern = args[0].as_str();
-            let text = args[1].as_str();
-
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
 
-            match re.find(&text) {
-                Some(m) => Ok(QValue::Str(QString::new(m.as_str().to_string()))),
-                None => Ok(QValue::Nil(QNil)),
+            match Regex::new(&pattern) {
+                Ok(_) => Ok(QValue::Bool(QBool::new(true))),
+                Err(_) => Ok(QValue::Bool(QBool::new(false))),
             }
         }
-        "regex.find_all" => {
-            if args.len() != 2 {
-                return arg_err!("regex.find_all expects 2 arguments (pattern, text), got {}", args.len());
+        "regex.escape" => {
+            // Quote every metacharacter so the text matches literally
+            if args.len() != 1 {
+                return arg_err!("regex.escape expects 1 argument, got {}", args.len());
             }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
+            Ok(QValue::Str(QString::new(regex::escape(&args[0].as_str()))))
+        }
+        _ => {
+            // Everything else takes (pattern, text, ...) where pattern is a Str or a compiled Pattern
+            let name = &func_name["regex.".len()..];
+            let Some((min, max, params)) = signature(name) else {
+                return name_err!("Unknown regex function: {}", func_name);
+            };
+            if args.len() < min + 1 || args.len() > max + 1 {
+                return arg_err!("{} expects {} (pattern, {}), got {}", func_name, count(min + 1, max + 1), params, args.len());
+            }
+            let re = match &args[0] {
+                QValue::RegexPattern(p) => p.regex.clone(),
+                other => compile_cached(&other.as_str())?,
+            };
+            Ok(apply(&re, name, &args[1..], scope)?)
+        }
+    }
+}
 
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+/// (min args, max args, parameter names) of each operation, not counting the pattern
+fn signature(name: &str) -> Option<(usize, usize, &'static str)> {
+    match name {
+        "match" | "is_match" | "find" | "find_all" | "captures" | "captures_all" | "search" | "find_iter" => Some((1, 1, "text")),
+        "replace" | "replace_all" => Some((2, 2, "text, replacement")),
+        "replace_with" => Some((2, 2, "text, function")),
+        "split" => Some((1, 2, "text, maxsplit")),
+        _ => None,
+    }
+}
 
+fn count(min: usize, max: usize) -> String {
+    match (min, max) {
+        (1, 1) => "1 argument".to_string(),
+        (min, max) if min == max => format!("{} arguments", min),
+        (min, max) => format!("{} or {} arguments", min, max),
+    }
+}
+
+fn compile_cached(pattern: &str) -> Result<Regex, String> {
+    if let Some(re) = CACHE.with(|cache| cache.borrow().get(pattern).cloned()) {
+        return Ok(re);
+    }
+    let re = Regex::new(pattern)
+        .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), re.clone());
+    });
+    Ok(re)
+}
+
+/// Text of each capture group, nil for groups that didn't participate
+fn capture_values(caps: &Captures) -> QValue {
+    let captured: Vec<QValue> = caps.iter()
+        .map(|c| match c {
+            Some(m) => QValue::Str(QString::new(m.as_str().to_string())),
+            None => QValue::Nil(QNil),
+        })
+        .collect();
+    QValue::Array(QArray::new(captured))
+}
+
+/// Run a regex operation; `args` has already been checked against `signature`
+fn apply(re: &Regex, name: &str, args: &[QValue], scope: &mut crate::Scope) -> Result<QValue, String> {
+    let text = args[0].as_str();
+    match name {
+        "match" | "is_match" => Ok(QValue::Bool(QBool::new(re.is_match(&text)))),
+        "find" => match re.find(&text) {
+            Some(m) => Ok(QValue::Str(QString::new(m.as_str().to_string()))),
+            None => Ok(QValue::Nil(QNil)),
+        },
+        "find_all" => {
             let matches: Vec<QValue> = re.find_iter(&text)
                 .map(|m| QValue::Str(QString::new(m.as_str().to_string())))
                 .collect();
             Ok(QValue::Array(QArray::new(matches)))
         }
-        "regex.captures" => {
-            if args.len() != 2 {
-                return arg_err!("regex.captures expects 2 arguments (pattern, text), got {}", args.len());
-            }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
-
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
-
+        "captures" => match re.captures(&text) {
+            Some(caps) => Ok(capture_values(&caps)),
+            None => Ok(QValue::Nil(QNil)),
+        },
+        "captures_all" => {
+            let all_captures: Vec<QValue> = re.captures_iter(&text)
+                .map(|caps| capture_values(&caps))
+                .collect();
+            Ok(QValue::Array(QArray::new(all_captures)))
+        }
+        "search" => {
+            // First match as a Match object
+            let text: Rc<str> = Rc::from(text.as_str());
             match re.captures(&text) {
-                Some(caps) => {
-                    let captured: Vec<QValue> = caps.iter()
-                        .map(|c| match c {
-                            Some(m) => QValue::Str(QString::new(m.as_str().to_string())),
-                            None => QValue::Nil(QNil),
-                        })
-                        .collect();
-                    Ok(QValue::Array(QArray::new(captured)))
-                }
+                Some(caps) => Ok(QValue::RegexMatch(Box::new(QRegexMatch::new(re, &text, &caps)))),
                 None => Ok(QValue::Nil(QNil)),
             }
         }
-        "regex.captures_all" => {
-            if args.len() != 2 {
-                return arg_err!("regex.captures_all expects 2 arguments (pattern, text), got {}", args.len());
-            }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
-
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
-
-            let all_captures: Vec<QValue> = re.captures_iter(&text)
-                .map(|caps| {
-                    let captured: Vec<QValue> = caps.iter()
-                        .map(|c| match c {
-                            Some(m) => QValue::Str(QString::new(m.as_str().to_string())),
-                            None => QValue::Nil(QNil),
-                        })
-                        .collect();
-                    QValue::Array(QArray::new(captured))
-                })
+        "find_iter" => {
+            // Every match as a Match object
+            let text: Rc<str> = Rc::from(text.as_str());
+            let matches: Vec<QValue> = re.captures_iter(&text)
+                .map(|caps| QValue::RegexMatch(Box::new(QRegexMatch::new(re, &text, &caps))))
                 .collect();
-            Ok(QValue::Array(QArray::new(all_captures)))
+            Ok(QValue::Array(QArray::new(matches)))
+        }
+        "replace" => {
+            let replacement = args[1].as_str();
+            Ok(QValue::Str(QString::new(re.replace(&text, replacement.as_str()).to_string())))
         }
-        "regex.replace" => {
-            if args.len() != 3 {
-                return arg_err!("regex.replace expects 3 arguments (pattern, text, replacement), got {}", args.len());
+        "replace_all" => {
+            let replacement = args[1].as_str();
+            Ok(QValue::Str(QString::new(re.replace_all(&text, replacement.as_str()).to_string())))
+        }
+        "replace_with" => {
+            // Replace every match with what the function returns for its Match object
+            let QValue::UserFun(f) = &args[1] else {
+                return type_err!("replace_with expects a function, got {}", args[1].as_obj().cls());
+            };
+            let text: Rc<str> = Rc::from(text.as_str());
+            let mut result = String::with_capacity(text.len());
+            let mut last = 0;
+            for caps in re.captures_iter(&text) {
+                let whole = caps.get(0).expect("group 0 always matches");
+                result.push_str(&text[last..whole.start()]);
+                let m = QValue::RegexMatch(Box::new(QRegexMatch::new(re, &text, &caps)));
+                let replacement = call_user_function(f, CallArguments::positional_only(vec![m]), scope, None)?;
+                result.push_str(&replacement.as_str());
+                last = whole.end();
             }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
-            let replacement = args[2].as_str();
+            result.push_str(&text[last..]);
+            Ok(QValue::Str(QString::new(result)))
+        }
+        "split" => {
+            // Split at most `maxsplit` times (all when omitted or negative), like Str.split.
+            // maxsplit: given by name arrives as a trailing options Dict
+            let maxsplit = match args.get(1) {
+                Some(QValue::Dict(options)) => {
+                    if let Some(key) = options.keys().into_iter().find(|key| key.as_str() != "maxsplit") {
+                        return arg_err!("Unknown split option '{}' (expected maxsplit)", key);
+                    }
+                    options.get("maxsplit")
+                }
+                other => other.cloned(),
+            };
+            let maxsplit = match maxsplit {
+                None | Some(QValue::Nil(_)) => -1,
+                Some(QValue::Int(n)) => n.value,
+                Some(other) => return type_err!("split maxsplit must be Int, got {}", other.as_obj().cls()),
+            };
+            let parts: Vec<QValue> = if maxsplit < 0 {
+                re.split(&text)
+                    .map(|s| QValue::Str(QString::new(s.to_string())))
+                    .collect()
+            } else {
+                re.splitn(&text, maxsplit as usize + 1)
+                    .map(|s| QValue::Str(QString::new(s.to_string())))
+                    .collect()
+            };
+            Ok(QValue::Array(QArray::new(parts)))
+        }
+        _ => name_err!("Unknown regex function: regex.{}", name),
+    }
+}
 
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+/// A compiled regular expression (`regex.compile`). Has the same operations
+/// as the module functions, without the pattern argument.
+#[derive(Debug, Clone)]
+pub struct QRegexPattern {
+    regex: Regex,
+    flags: String,
+    id: u64,
+}
 
-            let result = re.replace(&text, replacement.as_str()).to_string();
-            Ok(QValue::Str(QString::new(result)))
+impl QRegexPattern {
+    pub fn compile(pattern: &str, flags: &str) -> Result<Self, String> {
+        let mut builder = RegexBuilder::new(pattern);
+        for flag in flags.chars() {
+            match flag {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                _ => return value_err!("Unknown regex flag '{}' (expected i, m, s or x)", flag),
+            };
+        }
+        let regex = builder.build()
+            .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+        Ok(QRegexPattern { regex, flags: flags.to_string(), id: next_object_id() })
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
         }
-        "regex.replace_all" => {
-            if args.len() != 3 {
-                return arg_err!("regex.replace_all expects 3 arguments (pattern, text, replacement), got {}", args.len());
+
+        match method_name {
+            "pattern" | "flags" | "groups" | "group_names" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                Ok(match method_name {
+                    "pattern" => QValue::Str(QString::new(self.regex.as_str().to_string())),
+                    "flags" => QValue::Str(QString::new(self.flags.clone())),
+                    // Number of capture groups, not counting the whole match
+                    "groups" => QValue::Int(QInt::new(self.regex.captures_len() as i64 - 1)),
+                    _ => {
+                        let names: Vec<QValue> = self.regex.capture_names()
+                            .flatten()
+                            .map(|name| QValue::Str(QString::new(name.to_string())))
+                            .collect();
+                        QValue::Array(QArray::new(names))
+                    }
+                })
             }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
-            let replacement = args[2].as_str();
+            _ => {
+                let Some((min, max, params)) = signature(method_name) else {
+                    return attr_err!("Pattern has no method '{}'", method_name);
+                };
+                if args.len() < min || args.len() > max {
+                    return arg_err!("{} expects {} ({}), got {}", method_name, count(min, max), params, args.len());
+                }
+                Ok(apply(&self.regex, method_name, &args, scope)?)
+            }
+        }
+    }
+}
 
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+impl QObj for QRegexPattern {
+    fn cls(&self) -> String {
+        "Pattern".to_string()
+    }
 
-            let result = re.replace_all(&text, replacement.as_str()).to_string();
-            Ok(QValue::Str(QString::new(result)))
+    fn q_type(&self) -> &'static str {
+        "pattern"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "pattern" || type_name == "obj"
+    }
+
+    fn str(&self) -> String {
+        format!("<Pattern /{}/{}>", self.regex.as_str(), self.flags)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Compiled regular expression (regex.compile)".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// One match of a pattern (`regex.search`, `regex.find_iter`, `replace_with`
+/// callbacks) with its capture groups. Positions are character offsets, so
+/// they work with Str.slice.
+#[derive(Debug, Clone)]
+pub struct QRegexMatch {
+    regex: Regex,
+    text: Rc<str>,
+    /// Byte span of each group; None for groups that didn't participate
+    spans: Vec<Option<(usize, usize)>>,
+    id: u64,
+}
+
+impl QRegexMatch {
+    fn new(regex: &Regex, text: &Rc<str>, caps: &Captures) -> Self {
+        let spans = caps.iter().map(|m| m.map(|m| (m.start(), m.end()))).collect();
+        QRegexMatch { regex: regex.clone(), text: Rc::clone(text), spans, id: next_object_id() }
+    }
+
+    fn char_offset(&self, byte: usize) -> usize {
+        if self.text.is_ascii() {
+            byte
+        } else {
+            self.text[..byte].chars().count()
         }
-        "regex.split" => {
-            if args.len() != 2 {
-                return arg_err!("regex.split expects 2 arguments (pattern, text), got {}", args.len());
-            }
-            let pattern = args[0].as_str();
-            let text = args[1].as_str();
+    }
 
-            let re = Regex::new(&pattern)
-                .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+    fn group_text(&self, index: usize) -> QValue {
+        match self.spans[index] {
+            Some((start, end)) => QValue::Str(QString::new(self.text[start..end].to_string())),
+            None => QValue::Nil(QNil),
+        }
+    }
 
-            let parts: Vec<QValue> = re.split(&text)
-                .map(|s| QValue::Str(QString::new(s.to_string())))
-                .collect();
-            Ok(QValue::Array(QArray::new(parts)))
+    /// Group number for an Int index or a group name (whole match when omitted)
+    fn group_index(&self, method_name: &str, args: &[QValue]) -> Result<usize, String> {
+        if args.len() > 1 {
+            return arg_err!("{} expects 0 or 1 arguments (group), got {}", method_name, args.len());
         }
-        "regex.is_valid" => {
-            if args.len() != 1 {
-                return arg_err!("regex.is_valid expects 1 argument, got {}", args.len());
+        match args.first() {
+            None => Ok(0),
+            Some(QValue::Int(i)) => {
+                if i.value < 0 || i.value as usize >= self.spans.len() {
+                    return index_err!("No group {} (pattern has {} groups)", i.value, self.spans.len() - 1);
+                }
+                Ok(i.value as usize)
             }
-            let pattern = args[0].as_str();
+            Some(QValue::Str(name)) => {
+                match self.regex.capture_names().position(|n| n == Some(name.value.as_str())) {
+                    Some(index) => Ok(index),
+                    None => key_err!("No group named '{}'", name.value),
+                }
+            }
+            Some(other) => type_err!("{} group must be Int or Str, got {}", method_name, other.as_obj().cls()),
+        }
+    }
 
-            match Regex::new(&pattern) {
-                Ok(_) => Ok(QValue::Bool(QBool::new(true))),
-                Err(_) => Ok(QValue::Bool(QBool::new(false))),
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            "text" => {
+                if !args.is_empty() {
+                    return arg_err!("text expects 0 arguments, got {}", args.len());
+                }
+                Ok(self.group_text(0))
+            }
+            "group" => Ok(self.group_text(self.group_index(method_name, &args)?)),
+            "start" | "end" | "span" => {
+                // nil when the group didn't participate in the match
+                let index = self.group_index(method_name, &args)?;
+                let Some((start, end)) = self.spans[index] else {
+                    return Ok(QValue::Nil(QNil));
+                };
+                let start = QValue::Int(QInt::new(self.char_offset(start) as i64));
+                let end = QValue::Int(QInt::new(self.char_offset(end) as i64));
+                Ok(match method_name {
+                    "start" => start,
+                    "end" => end,
+                    _ => QValue::Array(QArray::new(vec![start, end])),
+                })
+            }
+            "groups" => {
+                // Groups 1.. (not the whole match), like captures()[1..]
+                if !args.is_empty() {
+                    return arg_err!("groups expects 0 arguments, got {}", args.len());
+                }
+                let groups: Vec<QValue> = (1..self.spans.len()).map(|i| self.group_text(i)).collect();
+                Ok(QValue::Array(QArray::new(groups)))
             }
+            "named" => {
+                // Named groups only, in pattern order
+                if !args.is_empty() {
+                    return arg_err!("named expects 0 arguments, got {}", args.len());
+                }
+                let mut named = IndexMap::new();
+                for (index, name) in self.regex.capture_names().enumerate() {
+                    if let Some(name) = name {
+                        named.insert(name.to_string(), self.group_text(index));
+                    }
+                }
+                Ok(QValue::Dict(Box::new(QDict::new(named))))
+            }
+            _ => attr_err!("Match has no method '{}'", method_name),
         }
+    }
+}
+
+impl QObj for QRegexMatch {
+    fn cls(&self) -> String {
+        "Match".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "match"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "match" || type_name == "obj"
+    }
+
+    fn str(&self) -> String {
+        let (start, end) = self.spans[0].unwrap_or((0, 0));
+        format!("<Match {:?} at {}..{}>", &self.text[start..end], self.char_offset(start), self.char_offset(end))
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "A regex match with its capture groups and positions".to_string()
+    }
 
-        _ => name_err!("Unknown term function: {}", func_name)
+    fn _id(&self) -> u64 {
+        self.id
     }
 }
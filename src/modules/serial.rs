@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use crate::{arg_err, io_err, attr_err, value_err};
 use std::sync::{Arc, Mutex};
@@ -7,10 +8,16 @@ use std::time::Duration;
 use serialport::{SerialPort, DataBits, Parity, StopBits, FlowControl};
 use crate::types::*;
 
+pub mod can;
+pub mod modbus;
+
+/// A serial port shared between its SerialPort value and protocol clients built on it
+pub(crate) type SharedPort = Arc<Mutex<Box<dyn SerialPort>>>;
+
 // Wrapper for SerialPort that implements QObj
 #[derive(Debug, Clone)]
 pub struct QSerialPort {
-    port: Arc<Mutex<Box<dyn SerialPort>>>,
+    port: SharedPort,
     name: String,
     id: u64,
 }
@@ -24,6 +31,15 @@ impl QSerialPort {
         }
     }
 
+    /// The open port, shared with Modbus and CAN clients layered on top of it
+    pub(crate) fn shared_port(&self) -> SharedPort {
+        Arc::clone(&self.port)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         match method_name {
             "name" => Ok(QValue::Str(QString::new(self.name.clone()))),
@@ -300,7 +316,7 @@ pub fn call_serial_function(func_name: &str, args: Vec<QValue>, _scope: &mut cra
             match serialport::available_ports() {
                 Ok(ports) => {
                     let port_list: Vec<QValue> = ports.iter().map(|p| {
-                        let mut info = std::collections::HashMap::new();
+                        let mut info = IndexMap::new();
                         info.insert("port_name".to_string(), QValue::Str(QString::new(p.port_name.clone())));
 
                         match &p.port_type {
@@ -363,3 +379,55 @@ pub fn call_serial_function(func_name: &str, args: Vec<QValue>, _scope: &mut cra
         _ => attr_err!("Undefined function: {}", func_name),
     }
 }
+
+/// The port under a protocol client (Modbus RTU, SLCAN): either an open
+/// SerialPort, used as configured, or a device name opened with `baud:`,
+/// `parity:`, `data_bits:` and `stop_bits:` from the options.
+/// Returns the shared port and its name.
+pub(crate) fn open_line(
+    func: &str,
+    port: &QValue,
+    options: Option<&QDict>,
+    default_baud: u32,
+    timeout: Duration,
+) -> Result<(SharedPort, String), String> {
+    let name = match port {
+        QValue::SerialPort(p) => return Ok((p.shared_port(), p.name().to_string())),
+        QValue::Str(s) => s.value.to_string(),
+        other => return crate::type_err!("{} port must be a Str or SerialPort, got {}", func, other.as_obj().cls()),
+    };
+    let get = |key: &str| options.and_then(|o| o.get(key)).filter(|v| !matches!(v, QValue::Nil(_)));
+    let baud = match get("baud") {
+        None => default_baud,
+        Some(QValue::Int(n)) if n.value > 0 && n.value <= u32::MAX as i64 => n.value as u32,
+        Some(other) => return value_err!("{} baud must be a positive Int, got {}", func, other.as_str()),
+    };
+    let parity = match get("parity").map(|v| v.as_str()).as_deref() {
+        None | Some("none") => Parity::None,
+        Some("even") => Parity::Even,
+        Some("odd") => Parity::Odd,
+        Some(other) => return value_err!("{} parity must be 'none', 'even' or 'odd', got {}", func, other),
+    };
+    let data_bits = match get("data_bits") {
+        None => DataBits::Eight,
+        Some(QValue::Int(n)) if n.value == 7 => DataBits::Seven,
+        Some(QValue::Int(n)) if n.value == 8 => DataBits::Eight,
+        Some(other) => return value_err!("{} data_bits must be 7 or 8, got {}", func, other.as_str()),
+    };
+    let stop_bits = match get("stop_bits") {
+        None => StopBits::One,
+        Some(QValue::Int(n)) if n.value == 1 => StopBits::One,
+        Some(QValue::Int(n)) if n.value == 2 => StopBits::Two,
+        Some(other) => return value_err!("{} stop_bits must be 1 or 2, got {}", func, other.as_str()),
+    };
+    match serialport::new(&name, baud)
+        .parity(parity)
+        .data_bits(data_bits)
+        .stop_bits(stop_bits)
+        .timeout(timeout)
+        .open()
+    {
+        Ok(port) => Ok((Arc::new(Mutex::new(port)), name)),
+        Err(e) => io_err!("Failed to open serial port '{}': {}", name, e),
+    }
+}
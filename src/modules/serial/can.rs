@@ -0,0 +1,540 @@
+// std/serial/can - CAN bus frames over SocketCAN or SLCAN adapters
+//
+// On Linux, CAN interfaces (can0, vcan0) are network devices read and written
+// through raw CAN sockets. Many USB and serial adapters instead speak SLCAN
+// (the Lawicel ASCII protocol) over a serial port: "t1232ABCD\r" is standard
+// frame 0x123 with the two bytes AB CD. Both give the same CanBus methods and
+// the same frame Dicts. Only classic CAN frames (up to 8 bytes) are supported.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::rc::Rc;
+use super::SharedPort;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use indexmap::IndexMap;
+use serialport::{ClearBuffer, SerialPort};
+
+use crate::control_flow::EvalError;
+use crate::modules::args::{get_option, split_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+
+const MAX_STANDARD_ID: u32 = 0x7FF;
+const MAX_EXTENDED_ID: u32 = 0x1FFF_FFFF;
+const DEFAULT_SLCAN_BITRATE: u32 = 500_000;
+const DEFAULT_SLCAN_BAUD: u32 = 115_200;
+/// How long an SLCAN adapter gets to acknowledge a command
+const SLCAN_ACK_TIMEOUT: Duration = Duration::from_millis(500);
+/// SLCAN bitrate commands S0 to S8
+const SLCAN_BITRATES: [u32; 9] = [10_000, 20_000, 50_000, 100_000, 125_000, 250_000, 500_000, 800_000, 1_000_000];
+
+/// One classic CAN frame
+struct Frame {
+    id: u32,
+    extended: bool,
+    rtr: bool,
+    /// Data length code; for remote frames, the length requested
+    dlc: u8,
+    data: Vec<u8>,
+}
+
+impl Frame {
+    fn to_value(&self) -> QValue {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0);
+        let mut map = IndexMap::new();
+        map.insert("id".to_string(), QValue::Int(QInt::new(self.id as i64)));
+        map.insert("data".to_string(), QValue::Bytes(QBytes::new(self.data.clone())));
+        map.insert("extended".to_string(), QValue::Bool(QBool::new(self.extended)));
+        map.insert("rtr".to_string(), QValue::Bool(QBool::new(self.rtr)));
+        map.insert("dlc".to_string(), QValue::Int(QInt::new(self.dlc as i64)));
+        map.insert("timestamp".to_string(), QValue::Float(QFloat::new(timestamp)));
+        QValue::Dict(Box::new(QDict::new(map)))
+    }
+
+    /// SLCAN line without the trailing "\r": t/T/r/R, id, dlc, data
+    fn to_slcan(&self) -> String {
+        let kind = match (self.extended, self.rtr) {
+            (false, false) => 't',
+            (true, false) => 'T',
+            (false, true) => 'r',
+            (true, true) => 'R',
+        };
+        let id = if self.extended { format!("{:08X}", self.id) } else { format!("{:03X}", self.id) };
+        let data: String = self.data.iter().map(|b| format!("{:02X}", b)).collect();
+        format!("{}{}{}{}", kind, id, self.dlc, data)
+    }
+
+    /// Parse a received SLCAN frame line; other lines (acks, status) give None.
+    /// A 4-digit timestamp after the data, if the adapter adds one, is ignored.
+    fn from_slcan(line: &[u8]) -> Option<Frame> {
+        let text = std::str::from_utf8(line).ok()?;
+        let (extended, rtr) = match text.chars().next()? {
+            't' => (false, false),
+            'T' => (true, false),
+            'r' => (false, true),
+            'R' => (true, true),
+            _ => return None,
+        };
+        let id_len = if extended { 8 } else { 3 };
+        let id = u32::from_str_radix(text.get(1..1 + id_len)?, 16).ok()?;
+        let dlc = text.get(1 + id_len..2 + id_len)?.parse::<u8>().ok().filter(|&n| n <= 8)?;
+        let mut data = Vec::new();
+        if !rtr {
+            let hex = text.get(2 + id_len..2 + id_len + 2 * dlc as usize)?;
+            for i in 0..dlc as usize {
+                data.push(u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?);
+            }
+        }
+        Some(Frame { id, extended, rtr, dlc, data })
+    }
+}
+
+enum Channel {
+    #[cfg(target_os = "linux")]
+    SocketCan(std::os::fd::OwnedFd),
+    Slcan {
+        port: SharedPort,
+        /// Received bytes not yet split into lines
+        buffer: Vec<u8>,
+    },
+}
+
+#[cfg(target_os = "linux")]
+mod socketcan {
+    use std::mem::{size_of, zeroed};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::time::Duration;
+
+    use super::Frame;
+
+    fn last_error() -> String {
+        format!("IOErr: {}", std::io::Error::last_os_error())
+    }
+
+    /// A raw CAN socket bound to `interface`
+    pub fn open(interface: &str) -> Result<OwnedFd, String> {
+        let name = std::ffi::CString::new(interface).map_err(|_| format!("ValueErr: Invalid CAN interface name '{}'", interface))?;
+        // SAFETY: plain libc calls; the descriptor is owned as soon as it is valid
+        unsafe {
+            let index = libc::if_nametoindex(name.as_ptr());
+            if index == 0 {
+                return Err(format!("IOErr: No CAN interface '{}'", interface));
+            }
+            let fd = libc::socket(libc::PF_CAN, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::CAN_RAW);
+            if fd < 0 {
+                return Err(format!("IOErr: Cannot open a CAN socket: {}", std::io::Error::last_os_error()));
+            }
+            let fd = OwnedFd::from_raw_fd(fd);
+            let mut addr: libc::sockaddr_can = zeroed();
+            addr.can_family = libc::AF_CAN as libc::sa_family_t;
+            addr.can_ifindex = index as libc::c_int;
+            let result = libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_can as *const libc::sockaddr,
+                size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            );
+            if result < 0 {
+                return Err(format!("IOErr: Cannot bind to CAN interface '{}': {}", interface, std::io::Error::last_os_error()));
+            }
+            Ok(fd)
+        }
+    }
+
+    pub fn send(fd: &OwnedFd, frame: &Frame) -> Result<(), String> {
+        // SAFETY: can_frame is plain data, and write() reads exactly its size
+        unsafe {
+            let mut raw: libc::can_frame = zeroed();
+            raw.can_id = frame.id
+                | if frame.extended { libc::CAN_EFF_FLAG } else { 0 }
+                | if frame.rtr { libc::CAN_RTR_FLAG } else { 0 };
+            raw.can_dlc = frame.dlc;
+            raw.data[..frame.data.len()].copy_from_slice(&frame.data);
+            let written = libc::write(fd.as_raw_fd(), &raw as *const libc::can_frame as *const libc::c_void, size_of::<libc::can_frame>());
+            if written != size_of::<libc::can_frame>() as isize {
+                return Err(last_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// The next frame, or None when `timeout` passes first (None waits forever)
+    pub fn recv(fd: &OwnedFd, timeout: Option<Duration>) -> Result<Option<Frame>, String> {
+        let wait_ms = timeout.map(|t| t.as_millis().min(i32::MAX as u128) as libc::c_int).unwrap_or(-1);
+        // SAFETY: poll() and read() get valid pointers sized for one descriptor and one frame
+        unsafe {
+            let mut poll_fd = libc::pollfd { fd: fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            match libc::poll(&mut poll_fd, 1, wait_ms) {
+                0 => return Ok(None),
+                n if n < 0 => return Err(last_error()),
+                _ => {}
+            }
+            let mut raw: libc::can_frame = zeroed();
+            let read = libc::read(fd.as_raw_fd(), &mut raw as *mut libc::can_frame as *mut libc::c_void, size_of::<libc::can_frame>());
+            if read != size_of::<libc::can_frame>() as isize {
+                return Err(last_error());
+            }
+            let extended = raw.can_id & libc::CAN_EFF_FLAG != 0;
+            let rtr = raw.can_id & libc::CAN_RTR_FLAG != 0;
+            let dlc = raw.can_dlc.min(8);
+            Ok(Some(Frame {
+                id: raw.can_id & if extended { libc::CAN_EFF_MASK } else { libc::CAN_SFF_MASK },
+                extended,
+                rtr,
+                dlc,
+                data: if rtr { Vec::new() } else { raw.data[..dlc as usize].to_vec() },
+            }))
+        }
+    }
+}
+
+fn serial_error(e: std::io::Error) -> String {
+    format!("IOErr: {}", e)
+}
+
+/// Send an SLCAN command and wait for its acknowledgement: "\r" for success,
+/// BEL for failure
+fn slcan_command(port: &mut Box<dyn SerialPort>, command: &str) -> Result<bool, String> {
+    port.write_all(format!("{}\r", command).as_bytes()).map_err(serial_error)?;
+    port.flush().map_err(serial_error)?;
+    let mut reply = [0u8; 1];
+    match port.read_exact(&mut reply) {
+        Ok(()) => Ok(reply[0] == b'\r'),
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => io_err!("SLCAN adapter did not answer '{}'", command),
+        Err(e) => Err(serial_error(e)),
+    }
+}
+
+impl Channel {
+    fn send(&mut self, frame: &Frame) -> Result<(), String> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Channel::SocketCan(fd) => socketcan::send(fd, frame),
+            // The adapter's acknowledgement arrives with received frames and is skipped there
+            Channel::Slcan { port, .. } => {
+                let mut port = port.lock().unwrap();
+                port.write_all(format!("{}\r", frame.to_slcan()).as_bytes()).map_err(serial_error)?;
+                port.flush().map_err(serial_error)
+            }
+        }
+    }
+
+    fn recv(&mut self, timeout: Option<Duration>) -> Result<Option<Frame>, String> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Channel::SocketCan(fd) => socketcan::recv(fd, timeout),
+            Channel::Slcan { port, buffer } => {
+                let deadline = timeout.map(|t| Instant::now() + t);
+                let mut port = port.lock().unwrap();
+                loop {
+                    while let Some(end) = buffer.iter().position(|&b| b == b'\r' || b == 0x07) {
+                        let line: Vec<u8> = buffer.drain(..=end).collect();
+                        if let Some(frame) = Frame::from_slcan(&line[..end]) {
+                            return Ok(Some(frame));
+                        }
+                    }
+                    let wait = match deadline {
+                        Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                            Some(left) if !left.is_zero() => left,
+                            _ => return Ok(None),
+                        },
+                        None => Duration::from_secs(1),
+                    };
+                    port.set_timeout(wait).map_err(|e| format!("IOErr: {}", e))?;
+                    let mut chunk = [0u8; 256];
+                    match port.read(&mut chunk) {
+                        Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                        Err(e) => return Err(serial_error(e)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn close(self) {
+        if let Channel::Slcan { port, .. } = self {
+            let mut port = port.lock().unwrap();
+            let _ = port.write_all(b"C\r");
+            let _ = port.flush();
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct QCanBus {
+    channel: Rc<RefCell<Option<Channel>>>,
+    /// (id, mask) pairs; a frame passes if any matches, or if there are none
+    filters: Rc<RefCell<Vec<(u32, u32)>>>,
+    name: String,
+    interface: &'static str,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QCanBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QCanBus").field("name", &self.name).field("interface", &self.interface).field("id", &self.id).finish()
+    }
+}
+
+/// `[{id:, mask:}]` as (id, mask) pairs; the mask defaults to every id bit
+fn filters_arg(func: &str, value: Option<QValue>) -> Result<Vec<(u32, u32)>, String> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+    let QValue::Array(filters) = value else {
+        return type_err!("{} filters must be an Array of Dicts with id and mask, got {}", func, value.as_obj().cls());
+    };
+    let mut pairs = Vec::new();
+    for filter in filters.elements.borrow().iter() {
+        let QValue::Dict(filter) = filter else {
+            return type_err!("{} filters must be Dicts with id and mask, got {}", func, filter.as_obj().cls());
+        };
+        let field = |key: &str, default: Option<u32>| -> Result<u32, String> {
+            match (filter.get(key), default) {
+                (Some(QValue::Int(n)), _) if (0..=MAX_EXTENDED_ID as i64).contains(&n.value) => Ok(n.value as u32),
+                (None, Some(default)) => Ok(default),
+                (other, _) => value_err!("{} filter {} must be an Int from 0 to 0x1FFFFFFF, got {}", func, key,
+                    other.map(|v| v.as_str()).unwrap_or_else(|| "nothing".to_string())),
+            }
+        };
+        pairs.push((field("id", None)?, field("mask", Some(MAX_EXTENDED_ID))?));
+    }
+    Ok(pairs)
+}
+
+fn timeout_arg(func: &str, value: Option<QValue>) -> Result<Option<Duration>, String> {
+    let secs = match value {
+        None => return Ok(None),
+        Some(QValue::Int(n)) => n.value as f64,
+        Some(QValue::Float(f)) => f.value,
+        Some(other) => return type_err!("{} timeout must be a number of seconds, got {}", func, other.as_obj().cls()),
+    };
+    if secs < 0.0 {
+        return value_err!("{} timeout must not be negative, got {}", func, secs);
+    }
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
+impl QCanBus {
+    fn open(args: Vec<QValue>) -> Result<Self, String> {
+        // can.open(channel, interface: "socketcan", bitrate:, listen_only: false, baud: 115200, filters: [])
+        let (positional, options) = split_options(&args);
+        let [channel] = positional else {
+            return arg_err!("can.open expects 1 argument (channel), got {}", positional.len());
+        };
+        let default_interface = if matches!(channel, QValue::SerialPort(_)) { "slcan" } else { "socketcan" };
+        let interface = match get_option(options, "interface").map(|v| v.as_str()).as_deref().unwrap_or(default_interface) {
+            "socketcan" => "socketcan",
+            "slcan" => "slcan",
+            other => return value_err!("can.open interface must be 'socketcan' or 'slcan', got {}", other),
+        };
+        let bitrate = match get_option(options, "bitrate") {
+            None => None,
+            Some(QValue::Int(n)) if SLCAN_BITRATES.contains(&(n.value as u32)) && n.value > 0 => Some(n.value as u32),
+            Some(other) => return value_err!("can.open bitrate must be one of 10000, 20000, 50000, 100000, 125000, 250000, 500000, 800000 or 1000000, got {}", other.as_str()),
+        };
+        let listen_only = get_option(options, "listen_only").is_some_and(|v| v.as_bool());
+        let filters = filters_arg("can.open", get_option(options, "filters"))?;
+
+        let (channel, name) = if interface == "socketcan" {
+            if bitrate.is_some() || listen_only {
+                return value_err!("can.open: set the bitrate and listen-only mode of a SocketCAN interface with `ip link`, not here");
+            }
+            let QValue::Str(name) = channel else {
+                return type_err!("can.open socketcan channel must be an interface name Str, got {}", channel.as_obj().cls());
+            };
+            (Self::open_socketcan(&name.value)?, name.value.to_string())
+        } else {
+            let (port, name) = super::open_line("can.open", channel, options, DEFAULT_SLCAN_BAUD, SLCAN_ACK_TIMEOUT)?;
+            {
+                let mut port = port.lock().unwrap();
+                port.set_timeout(SLCAN_ACK_TIMEOUT).map_err(|e| format!("IOErr: {}", e))?;
+                let _ = port.clear(ClearBuffer::Input);
+                // Close first, in case an earlier program left the channel open; a closed
+                // channel answers with BEL, which is fine
+                slcan_command(&mut port, "C")?;
+                let index = SLCAN_BITRATES.iter().position(|&b| b == bitrate.unwrap_or(DEFAULT_SLCAN_BITRATE)).unwrap_or(6);
+                if !slcan_command(&mut port, &format!("S{}", index))? {
+                    return io_err!("SLCAN adapter on {} rejected the bitrate", name);
+                }
+                if !slcan_command(&mut port, if listen_only { "L" } else { "O" })? {
+                    return io_err!("SLCAN adapter on {} could not open the CAN channel", name);
+                }
+            }
+            (Channel::Slcan { port, buffer: Vec::new() }, name)
+        };
+
+        Ok(QCanBus {
+            channel: Rc::new(RefCell::new(Some(channel))),
+            filters: Rc::new(RefCell::new(filters)),
+            name,
+            interface,
+            id: next_object_id(),
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open_socketcan(name: &str) -> Result<Channel, String> {
+        Ok(Channel::SocketCan(socketcan::open(name)?))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open_socketcan(_name: &str) -> Result<Channel, String> {
+        value_err!("can.open: SocketCAN is only available on Linux; use interface: \"slcan\" with a serial adapter")
+    }
+
+    fn with_channel<T>(&self, f: impl FnOnce(&mut Channel) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.channel.borrow_mut();
+        match guard.as_mut() {
+            Some(channel) => f(channel),
+            None => io_err!("CAN bus is closed"),
+        }
+    }
+
+    fn close(&self) {
+        if let Some(channel) = self.channel.borrow_mut().take() {
+            channel.close();
+        }
+    }
+
+    fn passes(&self, frame: &Frame) -> bool {
+        let filters = self.filters.borrow();
+        filters.is_empty() || filters.iter().any(|&(id, mask)| frame.id & mask == id & mask)
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+        let (positional, options) = split_options(&args);
+
+        match method_name {
+            "send" => {
+                // send(id, data = b"", extended: id > 0x7FF, rtr: false, dlc:)
+                if positional.is_empty() || positional.len() > 2 {
+                    return arg_err!("send expects 1 or 2 arguments (id, data), got {}", positional.len());
+                }
+                let id = match &positional[0] {
+                    QValue::Int(n) if (0..=MAX_EXTENDED_ID as i64).contains(&n.value) => n.value as u32,
+                    other => return value_err!("send id must be an Int from 0 to 0x1FFFFFFF, got {}", other.as_str()),
+                };
+                let data = match positional.get(1) {
+                    None => Vec::new(),
+                    Some(QValue::Bytes(b)) => b.data.clone(),
+                    Some(QValue::Array(a)) => a.elements.borrow().iter().map(|v| match v {
+                        QValue::Int(n) if (0..=255).contains(&n.value) => Ok(n.value as u8),
+                        other => value_err!("send data bytes must be Ints from 0 to 255, got {}", other.as_str()),
+                    }).collect::<Result<Vec<u8>, String>>()?,
+                    Some(other) => return type_err!("send data must be Bytes or an Array of Ints, got {}", other.as_obj().cls()),
+                };
+                if data.len() > 8 {
+                    return value_err!("send data is {} bytes; classic CAN frames hold at most 8", data.len());
+                }
+                let extended = get_option(options, "extended").map(|v| v.as_bool()).unwrap_or(id > MAX_STANDARD_ID);
+                if !extended && id > MAX_STANDARD_ID {
+                    return value_err!("send id 0x{:X} needs an extended (29-bit) frame", id);
+                }
+                let rtr = get_option(options, "rtr").is_some_and(|v| v.as_bool());
+                let dlc = match get_option(options, "dlc") {
+                    None => data.len() as u8,
+                    Some(QValue::Int(n)) if rtr && (0..=8).contains(&n.value) => n.value as u8,
+                    Some(other) => return value_err!("send dlc must be an Int from 0 to 8 and is only for remote (rtr) frames, got {}", other.as_str()),
+                };
+                if rtr && !data.is_empty() {
+                    return value_err!("send: remote (rtr) frames carry no data; pass dlc: instead");
+                }
+                let frame = Frame { id, extended, rtr, dlc, data };
+                self.with_channel(|c| c.send(&frame))?;
+                Ok(QValue::Nil(QNil))
+            }
+            "recv" => {
+                // recv(timeout: nil) -> frame Dict, or nil if the timeout passes first
+                if !positional.is_empty() {
+                    return arg_err!("recv expects 0 positional arguments, got {}", positional.len());
+                }
+                let timeout = timeout_arg("recv", get_option(options, "timeout"))?;
+                let deadline = timeout.map(|t| Instant::now() + t);
+                loop {
+                    let left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+                    match self.with_channel(|c| c.recv(left))? {
+                        Some(frame) if self.passes(&frame) => return Ok(frame.to_value()),
+                        Some(_) => {
+                            if left.is_some_and(|l| l.is_zero()) {
+                                return Ok(QValue::Nil(QNil));
+                            }
+                        }
+                        None => return Ok(QValue::Nil(QNil)),
+                    }
+                }
+            }
+            "set_filters" => {
+                if positional.len() != 1 {
+                    return arg_err!("set_filters expects 1 argument (filters), got {}", positional.len());
+                }
+                let filters = filters_arg("set_filters", Some(positional[0].clone()).filter(|v| !matches!(v, QValue::Nil(_))))?;
+                *self.filters.borrow_mut() = filters;
+                Ok(QValue::Nil(QNil))
+            }
+            "channel" => Ok(QValue::Str(QString::new(self.name.clone()))),
+            "interface" => Ok(QValue::Str(QString::new(self.interface.to_string()))),
+            "close" => {
+                self.close();
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.channel.borrow().is_none()))),
+            "_enter" => Ok(QValue::CanBus(self.clone())),
+            "_exit" => {
+                self.close();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on CanBus", method_name),
+        }
+    }
+}
+
+impl QObj for QCanBus {
+    fn cls(&self) -> String {
+        "CanBus".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "CanBus"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "CanBus"
+    }
+
+    fn str(&self) -> String {
+        format!("<CanBus {} {}>", self.interface, self.name)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "CAN bus connection".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub fn create_can_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("open".to_string(), create_fn("can", "open"));
+
+    QValue::Module(Box::new(QModule::new("can".to_string(), members)))
+}
+
+pub fn call_can_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "can.open" => Ok(QValue::CanBus(QCanBus::open(args)?)),
+        _ => attr_err!("Unknown can function: {}", func_name),
+    }
+}
@@ -0,0 +1,574 @@
+// std/serial/modbus - Modbus RTU and TCP master
+//
+// A ModbusClient polls devices ("units") for coils, discrete inputs, holding
+// registers and input registers. RTU frames travel over a serial port and end
+// in a CRC-16; TCP frames start with an MBAP header instead. Both carry the
+// same PDU: a function code and its data, big-endian. A device that rejects
+// a request answers with an exception code, raised here as IOErr.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::rc::Rc;
+use super::SharedPort;
+use std::time::Duration;
+
+use serialport::ClearBuffer;
+
+use crate::control_flow::EvalError;
+use crate::modules::args::{get_option, split_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, io_err, type_err, value_err};
+
+const DEFAULT_TCP_PORT: u16 = 502;
+const DEFAULT_TCP_TIMEOUT_SECS: f64 = 5.0;
+const DEFAULT_RTU_TIMEOUT_SECS: f64 = 1.0;
+const DEFAULT_BAUD: u32 = 9600;
+
+// Quantity limits per request from the Modbus application protocol spec
+const MAX_READ_BITS: usize = 2000;
+const MAX_READ_REGISTERS: usize = 125;
+const MAX_WRITE_BITS: usize = 1968;
+const MAX_WRITE_REGISTERS: usize = 123;
+
+/// CRC-16/MODBUS (polynomial 0xA001 reflected, initial 0xFFFF)
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn exception_name(code: u8) -> &'static str {
+    match code {
+        1 => "illegal function",
+        2 => "illegal data address",
+        3 => "illegal data value",
+        4 => "server device failure",
+        5 => "acknowledge",
+        6 => "server device busy",
+        8 => "memory parity error",
+        10 => "gateway path unavailable",
+        11 => "gateway target device failed to respond",
+        _ => "unknown exception",
+    }
+}
+
+fn io_error(e: std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => "IOErr: Modbus device timed out".to_string(),
+        _ => format!("IOErr: {}", e),
+    }
+}
+
+enum Transport {
+    Tcp { stream: TcpStream, transaction: u16 },
+    Rtu(SharedPort),
+}
+
+impl Transport {
+    /// Send a request PDU to `unit` and return the response PDU. RTU
+    /// broadcasts (unit 0) get no response, so they return an empty PDU.
+    fn request(&mut self, unit: u8, pdu: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Transport::Tcp { stream, transaction } => {
+                *transaction = transaction.wrapping_add(1);
+                let mut frame = Vec::with_capacity(7 + pdu.len());
+                frame.extend_from_slice(&transaction.to_be_bytes());
+                frame.extend_from_slice(&[0, 0]);
+                frame.extend_from_slice(&((pdu.len() + 1) as u16).to_be_bytes());
+                frame.push(unit);
+                frame.extend_from_slice(pdu);
+                stream.write_all(&frame).map_err(io_error)?;
+
+                // Skip late responses to earlier requests that timed out
+                loop {
+                    let mut header = [0u8; 7];
+                    stream.read_exact(&mut header).map_err(io_error)?;
+                    let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+                    if length < 2 {
+                        return io_err!("Malformed Modbus TCP response: length {}", length);
+                    }
+                    let mut body = vec![0u8; length - 1];
+                    stream.read_exact(&mut body).map_err(io_error)?;
+                    if header[0..2] == transaction.to_be_bytes() {
+                        return Ok(body);
+                    }
+                }
+            }
+            Transport::Rtu(port) => {
+                let mut frame = Vec::with_capacity(pdu.len() + 3);
+                frame.push(unit);
+                frame.extend_from_slice(pdu);
+                frame.extend_from_slice(&crc16(&frame).to_le_bytes());
+
+                let mut port = port.lock().unwrap();
+                // Drop noise and stale replies so they aren't read as this response
+                let _ = port.clear(ClearBuffer::Input);
+                port.write_all(&frame).map_err(io_error)?;
+                port.flush().map_err(io_error)?;
+                if unit == 0 {
+                    return Ok(Vec::new());
+                }
+
+                let mut response = vec![0u8; 2];
+                port.read_exact(&mut response).map_err(io_error)?;
+                let function = response[1];
+                let remaining = if function & 0x80 != 0 {
+                    1
+                } else {
+                    match function {
+                        1..=4 => {
+                            let mut count = [0u8; 1];
+                            port.read_exact(&mut count).map_err(io_error)?;
+                            response.push(count[0]);
+                            count[0] as usize
+                        }
+                        5 | 6 | 15 | 16 => 4,
+                        other => return io_err!("Malformed Modbus RTU response: function code {}", other),
+                    }
+                };
+                let start = response.len();
+                response.resize(start + remaining + 2, 0);
+                port.read_exact(&mut response[start..]).map_err(io_error)?;
+
+                let (body, checksum) = response.split_at(response.len() - 2);
+                if crc16(body).to_le_bytes() != checksum {
+                    return io_err!("Modbus RTU response failed its CRC check");
+                }
+                if body[0] != unit {
+                    return io_err!("Modbus RTU response came from unit {}, expected {}", body[0], unit);
+                }
+                Ok(body[1..].to_vec())
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct QModbusClient {
+    transport: Rc<RefCell<Option<Transport>>>,
+    unit: Rc<Cell<u8>>,
+    /// host:port or the serial device
+    target: String,
+    kind: &'static str,
+    pub id: u64,
+}
+
+impl std::fmt::Debug for QModbusClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QModbusClient").field("target", &self.target).field("id", &self.id).finish()
+    }
+}
+
+fn unit_option(func: &str, options: Option<&QDict>) -> Result<Option<u8>, String> {
+    match get_option(options, "unit") {
+        None => Ok(None),
+        Some(QValue::Int(n)) if (0..=247).contains(&n.value) => Ok(Some(n.value as u8)),
+        Some(other) => value_err!("{} unit must be an Int from 0 to 247, got {}", func, other.as_str()),
+    }
+}
+
+fn timeout_option(func: &str, options: Option<&QDict>, default: f64) -> Result<Duration, String> {
+    let secs = match get_option(options, "timeout") {
+        None => default,
+        Some(QValue::Int(n)) => n.value as f64,
+        Some(QValue::Float(f)) => f.value,
+        Some(other) => return type_err!("{} timeout must be a number of seconds, got {}", func, other.as_obj().cls()),
+    };
+    if secs <= 0.0 {
+        return value_err!("{} timeout must be positive, got {}", func, secs);
+    }
+    Ok(Duration::from_secs_f64(secs))
+}
+
+impl QModbusClient {
+    fn new(transport: Transport, unit: u8, target: String, kind: &'static str) -> Self {
+        QModbusClient {
+            transport: Rc::new(RefCell::new(Some(transport))),
+            unit: Rc::new(Cell::new(unit)),
+            target,
+            kind,
+            id: next_object_id(),
+        }
+    }
+
+    fn connect_tcp(args: Vec<QValue>) -> Result<Self, String> {
+        // modbus.connect_tcp(host, port: 502, unit: 1, timeout: 5)
+        let (positional, options) = split_options(&args);
+        let [host] = positional else {
+            return arg_err!("modbus.connect_tcp expects 1 argument (host), got {}", positional.len());
+        };
+        let host = host.as_str();
+        let port = match get_option(options, "port") {
+            None => DEFAULT_TCP_PORT,
+            Some(QValue::Int(n)) if (1..=65535).contains(&n.value) => n.value as u16,
+            Some(other) => return value_err!("modbus.connect_tcp port must be an Int between 1 and 65535, got {}", other.as_str()),
+        };
+        let unit = unit_option("modbus.connect_tcp", options)?.unwrap_or(1);
+        let timeout = timeout_option("modbus.connect_tcp", options, DEFAULT_TCP_TIMEOUT_SECS)?;
+
+        let addr = (host.as_str(), port).to_socket_addrs()
+            .map_err(|e| format!("IOErr: Cannot resolve {}: {}", host, e))?
+            .next()
+            .ok_or_else(|| format!("IOErr: Cannot resolve {}", host))?;
+        let stream = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| format!("IOErr: Cannot connect to {}:{}: {}", host, port, e))?;
+        stream.set_read_timeout(Some(timeout)).map_err(io_error)?;
+        stream.set_write_timeout(Some(timeout)).map_err(io_error)?;
+        stream.set_nodelay(true).map_err(io_error)?;
+
+        Ok(Self::new(Transport::Tcp { stream, transaction: 0 }, unit, format!("{}:{}", host, port), "tcp"))
+    }
+
+    fn connect_rtu(args: Vec<QValue>) -> Result<Self, String> {
+        // modbus.connect_rtu(port, baud: 9600, parity: "none", data_bits: 8, stop_bits: 1, unit: 1, timeout: 1)
+        let (positional, options) = split_options(&args);
+        let [port] = positional else {
+            return arg_err!("modbus.connect_rtu expects 1 argument (port), got {}", positional.len());
+        };
+        let unit = unit_option("modbus.connect_rtu", options)?.unwrap_or(1);
+        let timeout = timeout_option("modbus.connect_rtu", options, DEFAULT_RTU_TIMEOUT_SECS)?;
+        let (port, name) = super::open_line("modbus.connect_rtu", port, options, DEFAULT_BAUD, timeout)?;
+        // An already open SerialPort keeps its settings, except for an explicit timeout
+        if get_option(options, "timeout").is_some() {
+            port.lock().unwrap().set_timeout(timeout).map_err(|e| format!("IOErr: {}", e))?;
+        }
+        Ok(Self::new(Transport::Rtu(port), unit, name, "rtu"))
+    }
+
+    /// Send a request and check the response's function code, returning the
+    /// response data after the function code
+    fn call(&self, unit: u8, pdu: &[u8]) -> Result<Vec<u8>, String> {
+        let mut guard = self.transport.borrow_mut();
+        let Some(transport) = guard.as_mut() else {
+            return io_err!("Modbus connection is closed");
+        };
+        let response = transport.request(unit, pdu)?;
+        if unit == 0 && response.is_empty() {
+            return Ok(Vec::new());
+        }
+        let function = pdu[0];
+        match response.first() {
+            Some(&code) if code == function => Ok(response[1..].to_vec()),
+            Some(&code) if code == function | 0x80 => {
+                let exception = response.get(1).copied().unwrap_or(0);
+                io_err!("Modbus exception {} ({}) from unit {}", exception, exception_name(exception), unit)
+            }
+            _ => io_err!("Malformed Modbus response to function {}", function),
+        }
+    }
+
+    fn close(&self) {
+        self.transport.borrow_mut().take();
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+        let (positional, options) = split_options(&args);
+        let expect_args = |count: usize, usage: &str| -> Result<(), String> {
+            if positional.len() != count {
+                return arg_err!("{} expects {} arguments ({}), got {}", method_name, count, usage, positional.len());
+            }
+            Ok(())
+        };
+        let unit = || -> Result<u8, String> {
+            Ok(unit_option(method_name, options)?.unwrap_or(self.unit.get()))
+        };
+
+        match method_name {
+            "read_coils" | "read_discrete_inputs" | "read_holding_registers" | "read_input_registers" => {
+                // read_*(address, count, unit:)
+                expect_args(2, "address, count")?;
+                let (function, max) = match method_name {
+                    "read_coils" => (1u8, MAX_READ_BITS),
+                    "read_discrete_inputs" => (2, MAX_READ_BITS),
+                    "read_holding_registers" => (3, MAX_READ_REGISTERS),
+                    _ => (4, MAX_READ_REGISTERS),
+                };
+                let address = address_arg(method_name, &positional[0])?;
+                let count = count_arg(method_name, &positional[1], address, max)?;
+                let unit = unit()?;
+                if unit == 0 {
+                    return value_err!("{} can't broadcast to unit 0", method_name);
+                }
+                let mut pdu = vec![function];
+                pdu.extend_from_slice(&address.to_be_bytes());
+                pdu.extend_from_slice(&(count as u16).to_be_bytes());
+                let data = self.call(unit, &pdu)?;
+                let bytes = match data.split_first() {
+                    Some((&len, rest)) if rest.len() == len as usize => rest,
+                    _ => return io_err!("Malformed Modbus response: byte count doesn't match"),
+                };
+                let values: Vec<QValue> = if function <= 2 {
+                    if bytes.len() * 8 < count {
+                        return io_err!("Malformed Modbus response: expected {} bits, got {} bytes", count, bytes.len());
+                    }
+                    (0..count).map(|i| QValue::Bool(QBool::new(bytes[i / 8] >> (i % 8) & 1 == 1))).collect()
+                } else {
+                    if bytes.len() != count * 2 {
+                        return io_err!("Malformed Modbus response: expected {} registers, got {} bytes", count, bytes.len());
+                    }
+                    bytes.chunks(2).map(|r| QValue::Int(QInt::new(u16::from_be_bytes([r[0], r[1]]) as i64))).collect()
+                };
+                Ok(QValue::Array(QArray::new(values)))
+            }
+            "write_coil" | "write_register" => {
+                // write_coil(address, value, unit:) / write_register(address, value, unit:)
+                expect_args(2, "address, value")?;
+                let address = address_arg(method_name, &positional[0])?;
+                let (function, value) = if method_name == "write_coil" {
+                    (5u8, if positional[1].as_bool() { 0xFF00 } else { 0x0000 })
+                } else {
+                    (6, register_arg(method_name, &positional[1])?)
+                };
+                let mut pdu = vec![function];
+                pdu.extend_from_slice(&address.to_be_bytes());
+                pdu.extend_from_slice(&value.to_be_bytes());
+                self.call(unit()?, &pdu)?;
+                Ok(QValue::Nil(QNil))
+            }
+            "write_coils" | "write_registers" => {
+                // write_coils(address, [Bool], unit:) / write_registers(address, [Int], unit:)
+                expect_args(2, "address, values")?;
+                let address = address_arg(method_name, &positional[0])?;
+                let QValue::Array(values) = &positional[1] else {
+                    return type_err!("{} values must be an Array, got {}", method_name, positional[1].as_obj().cls());
+                };
+                let values = values.elements.borrow().clone();
+                let max = if method_name == "write_coils" { MAX_WRITE_BITS } else { MAX_WRITE_REGISTERS };
+                if values.is_empty() || values.len() > max || address as usize + values.len() > 65536 {
+                    return value_err!("{} takes 1 to {} values that fit below address 65536, got {}", method_name, max, values.len());
+                }
+                let (function, data) = if method_name == "write_coils" {
+                    let mut packed = vec![0u8; values.len().div_ceil(8)];
+                    for (i, value) in values.iter().enumerate() {
+                        if value.as_bool() {
+                            packed[i / 8] |= 1 << (i % 8);
+                        }
+                    }
+                    (15u8, packed)
+                } else {
+                    let mut data = Vec::with_capacity(values.len() * 2);
+                    for value in &values {
+                        data.extend_from_slice(&register_arg(method_name, value)?.to_be_bytes());
+                    }
+                    (16, data)
+                };
+                let mut pdu = vec![function];
+                pdu.extend_from_slice(&address.to_be_bytes());
+                pdu.extend_from_slice(&(values.len() as u16).to_be_bytes());
+                pdu.push(data.len() as u8);
+                pdu.extend_from_slice(&data);
+                self.call(unit()?, &pdu)?;
+                Ok(QValue::Nil(QNil))
+            }
+            "unit" => {
+                expect_args(0, "no arguments")?;
+                Ok(QValue::Int(QInt::new(self.unit.get() as i64)))
+            }
+            "set_unit" => {
+                expect_args(1, "unit")?;
+                match &positional[0] {
+                    QValue::Int(n) if (0..=247).contains(&n.value) => self.unit.set(n.value as u8),
+                    other => return value_err!("set_unit expects an Int from 0 to 247, got {}", other.as_str()),
+                }
+                Ok(QValue::Nil(QNil))
+            }
+            "close" => {
+                self.close();
+                Ok(QValue::Nil(QNil))
+            }
+            "closed" => Ok(QValue::Bool(QBool::new(self.transport.borrow().is_none()))),
+            "_enter" => Ok(QValue::ModbusClient(self.clone())),
+            "_exit" => {
+                self.close();
+                Ok(QValue::Nil(QNil))
+            }
+            _ => attr_err!("Unknown method '{}' on ModbusClient", method_name),
+        }
+    }
+}
+
+fn address_arg(func: &str, value: &QValue) -> Result<u16, String> {
+    match value {
+        QValue::Int(n) if (0..=65535).contains(&n.value) => Ok(n.value as u16),
+        other => value_err!("{} address must be an Int from 0 to 65535, got {}", func, other.as_str()),
+    }
+}
+
+fn count_arg(func: &str, value: &QValue, address: u16, max: usize) -> Result<usize, String> {
+    match value {
+        QValue::Int(n) if n.value >= 1 && n.value as usize <= max && address as usize + n.value as usize <= 65536 => Ok(n.value as usize),
+        other => value_err!("{} count must be from 1 to {} and fit below address 65536, got {}", func, max, other.as_str()),
+    }
+}
+
+/// A register value: 0 to 65535, or -32768 to -1 written as two's complement
+fn register_arg(func: &str, value: &QValue) -> Result<u16, String> {
+    match value {
+        QValue::Int(n) if (-32768..=65535).contains(&n.value) => Ok(n.value as i32 as u16),
+        other => value_err!("{} register values must be Ints from -32768 to 65535, got {}", func, other.as_str()),
+    }
+}
+
+impl QObj for QModbusClient {
+    fn cls(&self) -> String {
+        "ModbusClient".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "ModbusClient"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "ModbusClient"
+    }
+
+    fn str(&self) -> String {
+        format!("<ModbusClient {} {}>", self.kind, self.target)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Modbus RTU or TCP master connection".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Register layout of decode()/encode() types: (registers, kind)
+fn value_type(func: &str, name: &str) -> Result<(usize, &'static str), String> {
+    match name {
+        "int16" => Ok((1, "int")),
+        "uint16" => Ok((1, "uint")),
+        "int32" => Ok((2, "int")),
+        "uint32" => Ok((2, "uint")),
+        "int64" => Ok((4, "int")),
+        "float32" => Ok((2, "float")),
+        "float64" => Ok((4, "float")),
+        other => value_err!("{} kind must be int16, uint16, int32, uint32, int64, float32 or float64, got {}", func, other),
+    }
+}
+
+/// Whether the first register holds the low word ("little") instead of the
+/// high word ("big", the Modbus convention)
+fn little_word_order(func: &str, options: Option<&QDict>) -> Result<bool, String> {
+    match get_option(options, "word_order").map(|v| v.as_str()).as_deref() {
+        None | Some("big") => Ok(false),
+        Some("little") => Ok(true),
+        Some(other) => value_err!("{} word_order must be 'big' or 'little', got {}", func, other),
+    }
+}
+
+pub fn create_modbus_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("connect_tcp".to_string(), create_fn("modbus", "connect_tcp"));
+    members.insert("connect_rtu".to_string(), create_fn("modbus", "connect_rtu"));
+    members.insert("decode".to_string(), create_fn("modbus", "decode"));
+    members.insert("encode".to_string(), create_fn("modbus", "encode"));
+    members.insert("crc16".to_string(), create_fn("modbus", "crc16"));
+
+    QValue::Module(Box::new(QModule::new("modbus".to_string(), members)))
+}
+
+pub fn call_modbus_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "modbus.connect_tcp" => Ok(QValue::ModbusClient(QModbusClient::connect_tcp(args)?)),
+        "modbus.connect_rtu" => Ok(QValue::ModbusClient(QModbusClient::connect_rtu(args)?)),
+        "modbus.decode" => {
+            // decode(registers, kind, word_order: "big") -> Int or Float
+            let (positional, options) = split_options(&args);
+            let [QValue::Array(registers), QValue::Str(kind)] = positional else {
+                return arg_err!("modbus.decode expects 2 arguments (registers Array, kind Str), got {}", positional.len());
+            };
+            let type_name = kind.value.to_string();
+            let (count, kind) = value_type(func_name, &type_name)?;
+            let registers = registers.elements.borrow();
+            if registers.len() != count {
+                return value_err!("modbus.decode {} needs {} registers, got {}", type_name, count, registers.len());
+            }
+            let mut words = Vec::with_capacity(count);
+            for register in registers.iter() {
+                match register {
+                    QValue::Int(n) if (0..=65535).contains(&n.value) => words.push(n.value as u16),
+                    other => return value_err!("modbus.decode registers must be Ints from 0 to 65535, got {}", other.as_str()),
+                }
+            }
+            if little_word_order(func_name, options)? {
+                words.reverse();
+            }
+            let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+            Ok(match (count, kind) {
+                (1, "int") => QValue::Int(QInt::new(i16::from_be_bytes([bytes[0], bytes[1]]) as i64)),
+                (1, _) => QValue::Int(QInt::new(u16::from_be_bytes([bytes[0], bytes[1]]) as i64)),
+                (2, "int") => QValue::Int(QInt::new(i32::from_be_bytes(bytes[..4].try_into().unwrap()) as i64)),
+                (2, "uint") => QValue::Int(QInt::new(u32::from_be_bytes(bytes[..4].try_into().unwrap()) as i64)),
+                (2, _) => QValue::Float(QFloat::new(f32::from_be_bytes(bytes[..4].try_into().unwrap()) as f64)),
+                (_, "int") => QValue::Int(QInt::new(i64::from_be_bytes(bytes[..8].try_into().unwrap()))),
+                _ => QValue::Float(QFloat::new(f64::from_be_bytes(bytes[..8].try_into().unwrap()))),
+            })
+        }
+        "modbus.encode" => {
+            // encode(value, kind, word_order: "big") -> [Int] registers
+            let (positional, options) = split_options(&args);
+            let [value, QValue::Str(kind)] = positional else {
+                return arg_err!("modbus.encode expects 2 arguments (value, kind), got {}", positional.len());
+            };
+            let type_name = kind.value.to_string();
+            let (count, kind) = value_type(func_name, &type_name)?;
+            let int_value = || -> Result<i64, String> {
+                match value {
+                    QValue::Int(n) => Ok(n.value),
+                    other => type_err!("modbus.encode {} value must be an Int, got {}", type_name, other.as_obj().cls()),
+                }
+            };
+            let in_range = |n: i64, lo: i64, hi: i64| -> Result<i64, String> {
+                if n < lo || n > hi {
+                    return value_err!("modbus.encode value {} is out of range for {}", n, type_name);
+                }
+                Ok(n)
+            };
+            let bytes: Vec<u8> = match (count, kind) {
+                (1, "int") => (in_range(int_value()?, i16::MIN as i64, i16::MAX as i64)? as i16).to_be_bytes().to_vec(),
+                (1, _) => (in_range(int_value()?, 0, u16::MAX as i64)? as u16).to_be_bytes().to_vec(),
+                (2, "int") => (in_range(int_value()?, i32::MIN as i64, i32::MAX as i64)? as i32).to_be_bytes().to_vec(),
+                (2, "uint") => (in_range(int_value()?, 0, u32::MAX as i64)? as u32).to_be_bytes().to_vec(),
+                (_, "int") => int_value()?.to_be_bytes().to_vec(),
+                (_, _) => {
+                    let float = value.as_num().map_err(|_| format!("TypeErr: modbus.encode {} value must be a number, got {}", type_name, value.as_obj().cls()))?;
+                    if count == 2 { (float as f32).to_be_bytes().to_vec() } else { float.to_be_bytes().to_vec() }
+                }
+            };
+            let mut words: Vec<u16> = bytes.chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]])).collect();
+            if little_word_order(func_name, options)? {
+                words.reverse();
+            }
+            Ok(QValue::Array(QArray::new(words.into_iter().map(|w| QValue::Int(QInt::new(w as i64))).collect())))
+        }
+        "modbus.crc16" => {
+            // crc16(data) - CRC-16/MODBUS as an Int; RTU frames end with it low byte first
+            let data = match args.as_slice() {
+                [QValue::Bytes(b)] => b.data.clone(),
+                [QValue::Str(s)] => s.value.as_bytes().to_vec(),
+                [other] => return type_err!("modbus.crc16 expects Bytes or Str, got {}", other.as_obj().cls()),
+                _ => return arg_err!("modbus.crc16 expects 1 argument (data), got {}", args.len()),
+            };
+            Ok(QValue::Int(QInt::new(crc16(&data) as i64)))
+        }
+        _ => attr_err!("Unknown modbus function: {}", func_name),
+    }
+}
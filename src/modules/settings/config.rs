@@ -0,0 +1,535 @@
+// Layered configuration (std/config)
+//
+// Values are looked up through five layers, highest priority first:
+//   set() overrides > CLI arguments > environment variables > settings file > defaults
+// Each layer is stored as plain TOML values so the state can live in a global RwLock.
+use super::{loaded_settings, navigate_path, read_settings_file, replace_settings, toml_to_qvalue, SETTINGS_FILE};
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::{arg_err, attr_err, key_err, type_err, value_err};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+type Layer = HashMap<String, toml::Value>;
+
+/// Layer names, lowest priority first (as reported by config.source())
+const LAYER_NAMES: [&str; 5] = ["default", "file", "env", "cli", "override"];
+
+struct ConfigOptions {
+    file: Option<String>,
+    required: bool,
+    env_prefix: Option<String>,
+    env_separator: String,
+    args: Vec<String>,
+}
+
+impl Default for ConfigOptions {
+    fn default() -> Self {
+        Self {
+            file: Some(SETTINGS_FILE.to_string()),
+            required: false,
+            env_prefix: None,
+            env_separator: "__".to_string(),
+            args: Vec::new(),
+        }
+    }
+}
+
+struct ConfigState {
+    options: ConfigOptions,
+    /// Indexed like LAYER_NAMES
+    layers: [Layer; 5],
+    merged: Layer,
+}
+
+const DEFAULTS: usize = 0;
+const FILE: usize = 1;
+const ENV: usize = 2;
+const CLI: usize = 3;
+const OVERRIDES: usize = 4;
+
+impl ConfigState {
+    /// Initial state before config.load(): just the settings file loaded at startup
+    fn from_startup_settings() -> Self {
+        let mut state = ConfigState {
+            options: ConfigOptions::default(),
+            layers: Default::default(),
+            merged: Layer::new(),
+        };
+        state.layers[FILE] = loaded_settings();
+        state.merge();
+        state
+    }
+
+    fn merge(&mut self) {
+        let mut merged = Layer::new();
+        for layer in &self.layers {
+            for (key, value) in layer {
+                match merged.get_mut(key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        self.merged = merged;
+    }
+
+    /// Re-read the file and environment layers from the current options
+    fn read_sources(&mut self) -> Result<(), EvalError> {
+        self.layers[FILE] = match &self.options.file {
+            Some(path) => match read_settings_file(path)? {
+                Some(data) => {
+                    if path == SETTINGS_FILE {
+                        // Keep std/settings in sync with what config sees
+                        replace_settings(data.clone());
+                    }
+                    data
+                }
+                None if self.options.required => {
+                    return Err(format!("IOErr: Config file '{}' not found", path).into());
+                }
+                None => Layer::new(),
+            },
+            None => Layer::new(),
+        };
+        self.layers[ENV] = match &self.options.env_prefix {
+            Some(prefix) => env_layer(prefix, &self.options.env_separator),
+            None => Layer::new(),
+        };
+        self.layers[CLI] = cli_layer(&self.options.args);
+        Ok(())
+    }
+}
+
+lazy_static! {
+    /// Global config state - created from .settings.toml on first use, replaced by config.load()
+    static ref CONFIG: RwLock<Option<ConfigState>> = RwLock::new(None);
+}
+
+/// Run `f` against the config state, initializing it from the startup settings if needed
+fn with_state<T>(f: impl FnOnce(&mut ConfigState) -> Result<T, EvalError>) -> Result<T, EvalError> {
+    let mut guard = CONFIG.write().unwrap();
+    let state = guard.get_or_insert_with(ConfigState::from_startup_settings);
+    f(state)
+}
+
+/// Merge `overlay` into `base`: tables merge key by key, anything else replaces
+fn deep_merge(base: &mut toml::Value, overlay: &toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Insert `value` at a dot-separated path, creating intermediate tables
+fn insert_path(layer: &mut Layer, path: &str, value: toml::Value) {
+    let mut parts = path.split('.');
+    let first = parts.next().unwrap_or_default().to_string();
+    let rest: Vec<&str> = parts.collect();
+    if rest.is_empty() {
+        layer.insert(first, value);
+        return;
+    }
+    let mut current = layer.entry(first).or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+    for part in rest {
+        if !current.is_table() {
+            *current = toml::Value::Table(toml::map::Map::new());
+        }
+        current = match current {
+            toml::Value::Table(table) => table.entry(part.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::map::Map::new())),
+            _ => unreachable!(),
+        };
+    }
+    *current = value;
+}
+
+/// Remove the value at a dot-separated path (no-op if absent)
+fn remove_path(layer: &mut Layer, path: &str) {
+    match path.rsplit_once('.') {
+        None => {
+            layer.remove(path);
+        }
+        Some((parent, key)) => {
+            let mut parts = parent.split('.');
+            let mut current = match parts.next().and_then(|p| layer.get_mut(p)) {
+                Some(value) => value,
+                None => return,
+            };
+            for part in parts {
+                current = match current {
+                    toml::Value::Table(table) => match table.get_mut(part) {
+                        Some(value) => value,
+                        None => return,
+                    },
+                    _ => return,
+                };
+            }
+            if let toml::Value::Table(table) = current {
+                table.remove(key);
+            }
+        }
+    }
+}
+
+/// Parse a raw environment/CLI string as a TOML literal (8080, true, 1.5, [1, 2]),
+/// falling back to the plain string
+fn parse_raw_value(raw: &str) -> toml::Value {
+    match toml::from_str::<toml::Table>(&format!("v = {}", raw)) {
+        Ok(mut table) => table.remove("v").unwrap_or_else(|| toml::Value::String(raw.to_string())),
+        Err(_) => toml::Value::String(raw.to_string()),
+    }
+}
+
+/// APP_SERVER__PORT=9000 with prefix "APP_" and separator "__" -> server.port = 9000
+fn env_layer(prefix: &str, separator: &str) -> Layer {
+    let mut layer = Layer::new();
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| name.starts_with(prefix) && name.len() > prefix.len())
+        .collect();
+    // Sort so that a parent key (APP_DB) is applied before its children (APP_DB__HOST)
+    vars.sort();
+    for (name, raw) in vars {
+        let path = name[prefix.len()..]
+            .trim_start_matches('_')
+            .split(separator)
+            .map(|part| part.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(".");
+        if !path.is_empty() {
+            insert_path(&mut layer, &path, parse_raw_value(&raw));
+        }
+    }
+    layer
+}
+
+/// `--server.port=9000` style arguments; everything else is left for the program.
+/// Parsing stops at a bare `--`.
+fn cli_layer(args: &[String]) -> Layer {
+    let mut layer = Layer::new();
+    for arg in args {
+        if arg == "--" {
+            break;
+        }
+        if let Some((path, raw)) = arg.strip_prefix("--").and_then(|a| a.split_once('=')) {
+            if !path.is_empty() {
+                insert_path(&mut layer, path, parse_raw_value(raw));
+            }
+        }
+    }
+    layer
+}
+
+/// Convert a Quest value to TOML for storage in a layer
+fn qvalue_to_toml(value: &QValue) -> Result<toml::Value, EvalError> {
+    Ok(match value {
+        QValue::Int(i) => toml::Value::Integer(i.value),
+        QValue::Float(f) => toml::Value::Float(f.value),
+        QValue::Bool(b) => toml::Value::Boolean(b.value),
+        QValue::Str(s) => toml::Value::String(s.value.to_string()),
+        QValue::Array(arr) => toml::Value::Array(
+            arr.elements.borrow().iter().map(qvalue_to_toml).collect::<Result<_, _>>()?,
+        ),
+        QValue::Dict(dict) => {
+            let mut table = toml::map::Map::new();
+            for (key, value) in dict.map.borrow().iter() {
+                if !matches!(value, QValue::Nil(_)) {
+                    table.insert(key.clone(), qvalue_to_toml(value)?);
+                }
+            }
+            toml::Value::Table(table)
+        }
+        other => return type_err!("Config values must be Int, Float, Bool, Str, Array or Dict, got {}", other.as_obj().cls()),
+    })
+}
+
+fn dict_to_layer(dict: &QDict) -> Result<Layer, EvalError> {
+    match qvalue_to_toml(&QValue::Dict(Box::new(dict.clone())))? {
+        toml::Value::Table(table) => Ok(table.into_iter().collect()),
+        _ => unreachable!(),
+    }
+}
+
+fn path_arg(func: &str, args: &[QValue]) -> Result<String, EvalError> {
+    match args.first() {
+        Some(QValue::Str(s)) => Ok(s.value.to_string()),
+        Some(other) => type_err!("config.{} expects a Str path, got {}", func, other.as_obj().cls()),
+        None => arg_err!("config.{} expects a path argument", func),
+    }
+}
+
+/// Look up a path in the merged configuration
+fn lookup(path: &str) -> Result<Option<toml::Value>, EvalError> {
+    with_state(|state| Ok(navigate_path(&state.merged, path).cloned()))
+}
+
+/// Shared body of the typed getters: missing keys return the default, or raise KeyErr without one
+fn typed_get(
+    func: &str,
+    args: &[QValue],
+    convert: impl FnOnce(&toml::Value) -> Option<QValue>,
+) -> Result<QValue, EvalError> {
+    if args.is_empty() || args.len() > 2 {
+        return arg_err!("config.{} expects 1 or 2 arguments (path, default?), got {}", func, args.len());
+    }
+    let path = path_arg(func, args)?;
+    match lookup(&path)? {
+        Some(value) => match convert(&value) {
+            Some(converted) => Ok(converted),
+            None => type_err!("config.{}: '{}' is {} ({}), which can't be converted",
+                func, path, value.type_str(), value),
+        },
+        None => match args.get(1) {
+            Some(default) => Ok(default.clone()),
+            None => key_err!("Missing config key '{}'", path),
+        },
+    }
+}
+
+fn to_int(value: &toml::Value) -> Option<QValue> {
+    let n = match value {
+        toml::Value::Integer(i) => *i,
+        toml::Value::Float(f) if f.fract() == 0.0 && f.is_finite() => *f as i64,
+        toml::Value::String(s) => s.trim().parse().ok()?,
+        _ => return None,
+    };
+    Some(QValue::Int(QInt::new(n)))
+}
+
+fn to_float(value: &toml::Value) -> Option<QValue> {
+    let n = match value {
+        toml::Value::Integer(i) => *i as f64,
+        toml::Value::Float(f) => *f,
+        toml::Value::String(s) => s.trim().parse().ok()?,
+        _ => return None,
+    };
+    Some(QValue::Float(QFloat::new(n)))
+}
+
+fn to_bool(value: &toml::Value) -> Option<QValue> {
+    let b = match value {
+        toml::Value::Boolean(b) => *b,
+        toml::Value::Integer(0) => false,
+        toml::Value::Integer(1) => true,
+        toml::Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => true,
+            "false" | "no" | "off" | "0" => false,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(QValue::Bool(QBool::new(b)))
+}
+
+fn to_str(value: &toml::Value) -> Option<QValue> {
+    let s = match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) | toml::Value::Datetime(_) => value.to_string(),
+        _ => return None,
+    };
+    Some(QValue::Str(QString::new(s)))
+}
+
+/// Arrays as-is; strings split on commas ("a, b,c" -> ["a", "b", "c"]) for env-friendly lists
+fn to_array(value: &toml::Value) -> Option<QValue> {
+    match value {
+        toml::Value::Array(_) => Some(toml_to_qvalue(value)),
+        toml::Value::String(s) => {
+            let items = s.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| QValue::Str(QString::new(item.to_string())))
+                .collect();
+            Some(QValue::Array(QArray::new(items)))
+        }
+        _ => None,
+    }
+}
+
+fn to_dict(value: &toml::Value) -> Option<QValue> {
+    value.is_table().then(|| toml_to_qvalue(value))
+}
+
+/// config.load(defaults?, file?, required?, env_prefix?, env_separator?, args?)
+fn load(args: &[QValue]) -> Result<QValue, EvalError> {
+    let options = match args {
+        [] | [QValue::Nil(_)] => None,
+        [QValue::Dict(options)] => Some(options),
+        [other] => return type_err!("config.load expects an options Dict, got {}", other.as_obj().cls()),
+        _ => return arg_err!("config.load expects 0 or 1 arguments (options), got {}", args.len()),
+    };
+
+    let mut config = ConfigOptions::default();
+    let mut defaults = Layer::new();
+    if let Some(options) = options {
+        for (key, value) in options.map.borrow().iter() {
+            match (key.as_str(), value) {
+                ("defaults", QValue::Dict(d)) => defaults = dict_to_layer(d)?,
+                ("file", QValue::Str(s)) => config.file = Some(s.value.to_string()),
+                ("file", QValue::Nil(_)) => config.file = None,
+                ("required", value) => config.required = value.as_bool(),
+                ("env_prefix", QValue::Str(s)) => config.env_prefix = Some(s.value.to_string()),
+                ("env_prefix", QValue::Nil(_)) => config.env_prefix = None,
+                ("env_separator", QValue::Str(s)) if !s.value.is_empty() => config.env_separator = s.value.to_string(),
+                ("args", QValue::Array(arr)) => {
+                    config.args = arr.elements.borrow().iter().map(|a| a.as_str()).collect();
+                }
+                ("defaults" | "file" | "env_prefix" | "env_separator" | "args", other) => {
+                    return type_err!("config.load option '{}' has invalid value of type {}", key, other.as_obj().cls());
+                }
+                _ => return arg_err!("config.load got unknown option '{}' (expected defaults, file, required, env_prefix, env_separator or args)", key),
+            }
+        }
+    }
+
+    let mut state = ConfigState { options: config, layers: Default::default(), merged: Layer::new() };
+    state.layers[DEFAULTS] = defaults;
+    state.read_sources()?;
+    state.merge();
+    *CONFIG.write().unwrap() = Some(state);
+    Ok(QValue::Nil(QNil))
+}
+
+/// Create the config module with all functions
+pub fn create_config_module() -> QValue {
+    let mut module_map = HashMap::new();
+
+    for name in [
+        "load", "reload", "get", "get_int", "get_float", "get_bool", "get_str", "get_array", "get_dict",
+        "contains", "section", "all", "set", "unset", "source",
+    ] {
+        module_map.insert(
+            name.to_string(),
+            QValue::Fun(QFun::new(name.to_string(), "config".to_string())),
+        );
+    }
+
+    QValue::Module(Box::new(QModule::new("std/config".to_string(), module_map)))
+}
+
+/// Call a config function
+pub fn call_config_function(func_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+    match func_name {
+        "config.load" => load(&args),
+
+        "config.reload" => {
+            if !args.is_empty() {
+                return arg_err!("config.reload expects no arguments, got {}", args.len());
+            }
+            // Returns true if any value changed
+            with_state(|state| {
+                let before = state.merged.clone();
+                state.read_sources()?;
+                state.merge();
+                Ok(QValue::Bool(QBool::new(before != state.merged)))
+            })
+        }
+
+        "config.get" => {
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("config.get expects 1 or 2 arguments (path, default?), got {}", args.len());
+            }
+            let path = path_arg("get", &args)?;
+            Ok(match lookup(&path)? {
+                Some(value) => toml_to_qvalue(&value),
+                None => args.get(1).cloned().unwrap_or(QValue::Nil(QNil)),
+            })
+        }
+
+        "config.get_int" => typed_get("get_int", &args, to_int),
+        "config.get_float" => typed_get("get_float", &args, to_float),
+        "config.get_bool" => typed_get("get_bool", &args, to_bool),
+        "config.get_str" => typed_get("get_str", &args, to_str),
+        "config.get_array" => typed_get("get_array", &args, to_array),
+        "config.get_dict" => typed_get("get_dict", &args, to_dict),
+
+        "config.contains" => {
+            if args.len() != 1 {
+                return arg_err!("config.contains expects 1 argument, got {}", args.len());
+            }
+            let path = path_arg("contains", &args)?;
+            Ok(QValue::Bool(QBool::new(lookup(&path)?.is_some())))
+        }
+
+        "config.section" => {
+            if args.len() != 1 {
+                return arg_err!("config.section expects 1 argument, got {}", args.len());
+            }
+            let path = path_arg("section", &args)?;
+            Ok(match lookup(&path)? {
+                Some(value) if value.is_table() => toml_to_qvalue(&value),
+                Some(value) => return type_err!("config.section: '{}' is {}, not a table", path, value.type_str()),
+                None => QValue::Nil(QNil),
+            })
+        }
+
+        "config.all" => {
+            if !args.is_empty() {
+                return arg_err!("config.all expects no arguments, got {}", args.len());
+            }
+            with_state(|state| {
+                let map = state.merged.iter()
+                    .map(|(key, value)| (key.clone(), toml_to_qvalue(value)))
+                    .collect();
+                Ok(QValue::Dict(Box::new(QDict::new(map))))
+            })
+        }
+
+        "config.set" => {
+            if args.len() != 2 {
+                return arg_err!("config.set expects 2 arguments (path, value), got {}", args.len());
+            }
+            let path = path_arg("set", &args)?;
+            if path.is_empty() || path.split('.').any(str::is_empty) {
+                return value_err!("config.set: invalid path '{}'", path);
+            }
+            let value = qvalue_to_toml(&args[1])?;
+            with_state(|state| {
+                insert_path(&mut state.layers[OVERRIDES], &path, value);
+                state.merge();
+                Ok(QValue::Nil(QNil))
+            })
+        }
+
+        "config.unset" => {
+            if args.len() != 1 {
+                return arg_err!("config.unset expects 1 argument, got {}", args.len());
+            }
+            // Only removes a set() override; lower layers show through again
+            let path = path_arg("unset", &args)?;
+            with_state(|state| {
+                remove_path(&mut state.layers[OVERRIDES], &path);
+                state.merge();
+                Ok(QValue::Nil(QNil))
+            })
+        }
+
+        "config.source" => {
+            if args.len() != 1 {
+                return arg_err!("config.source expects 1 argument, got {}", args.len());
+            }
+            // Name of the highest-priority layer that defines the path, or nil
+            let path = path_arg("source", &args)?;
+            with_state(|state| {
+                Ok(state.layers.iter().zip(LAYER_NAMES).rev()
+                    .find(|(layer, _)| navigate_path(layer, &path).is_some())
+                    .map(|(_, name)| QValue::Str(QString::new(name.to_string())))
+                    .unwrap_or(QValue::Nil(QNil)))
+            })
+        }
+
+        _ => attr_err!("Unknown config function: {}", func_name),
+    }
+}
@@ -1,32 +1,54 @@
 use crate::types::*;
 use crate::control_flow::EvalError;
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::{arg_err, attr_err};
 use std::sync::RwLock;
 use lazy_static::lazy_static;
 
+pub mod config;
+
 lazy_static! {
     /// Global settings storage - loaded once at interpreter startup
     static ref SETTINGS_DATA: RwLock<Option<HashMap<String, toml::Value>>> = RwLock::new(None);
 }
 
+pub(crate) const SETTINGS_FILE: &str = ".settings.toml";
+
 /// Initialize settings from .settings.toml file in current directory
 /// Called once at interpreter startup
 pub fn init_settings() -> Result<(), String> {
-    let settings_path = ".settings.toml";
+    // File doesn't exist - that's okay, settings will just return nil
+    if let Some(data) = read_settings_file(SETTINGS_FILE)? {
+        replace_settings(data);
+    }
+    Ok(())
+}
 
-    // Check if file exists
+/// Replace the data seen by std/settings (used by config.reload())
+pub(crate) fn replace_settings(data: HashMap<String, toml::Value>) {
+    let mut settings = SETTINGS_DATA.write().unwrap();
+    *settings = Some(data);
+}
+
+/// Snapshot of the settings loaded at startup (empty if there was no file)
+pub(crate) fn loaded_settings() -> HashMap<String, toml::Value> {
+    SETTINGS_DATA.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Read and parse a settings file, applying and removing its [os.environ] section.
+/// Returns None if the file doesn't exist.
+pub(crate) fn read_settings_file(settings_path: &str) -> Result<Option<HashMap<String, toml::Value>>, String> {
     if !std::path::Path::new(settings_path).exists() {
-        // File doesn't exist - that's okay, settings will just return nil
-        return Ok(());
+        return Ok(None);
     }
 
     // Read and parse the file
     let contents = std::fs::read_to_string(settings_path)
-        .map_err(|e| format!("Failed to read .settings.toml: {}", e))?;
+        .map_err(|e| format!("Failed to read {}: {}", settings_path, e))?;
 
     let mut data: HashMap<String, toml::Value> = toml::from_str(&contents)
-        .map_err(|e| format!("Failed to parse .settings.toml: {}", e))?;
+        .map_err(|e| format!("Failed to parse {}: {}", settings_path, e))?;
 
     // Process [os.environ] section
     if let Some(toml::Value::Table(environ_table)) = data.remove("os") {
@@ -39,15 +61,11 @@ pub fn init_settings() -> Result<(), String> {
         }
     }
 
-    // Store the remaining settings
-    let mut settings = SETTINGS_DATA.write().unwrap();
-    *settings = Some(data);
-
-    Ok(())
+    Ok(Some(data))
 }
 
 /// Convert a TOML value to a Quest QValue
-fn toml_to_qvalue(value: &toml::Value) -> QValue {
+pub(crate) fn toml_to_qvalue(value: &toml::Value) -> QValue {
     match value {
         toml::Value::String(s) => QValue::Str(QString::new(s.clone())),
         toml::Value::Integer(i) => QValue::Int(QInt::new(*i)),
@@ -58,7 +76,7 @@ fn toml_to_qvalue(value: &toml::Value) -> QValue {
             QValue::Array(QArray::new(elements))
         }
         toml::Value::Table(table) => {
-            let mut map = HashMap::new();
+            let mut map = IndexMap::new();
             for (key, val) in table {
                 map.insert(key.clone(), toml_to_qvalue(val));
             }
@@ -72,7 +90,7 @@ fn toml_to_qvalue(value: &toml::Value) -> QValue {
 }
 
 /// Navigate a dot-separated path through TOML structure
-fn navigate_path<'a>(data: &'a HashMap<String, toml::Value>, path: &str) -> Option<&'a toml::Value> {
+pub(crate) fn navigate_path<'a>(data: &'a HashMap<String, toml::Value>, path: &str) -> Option<&'a toml::Value> {
     let parts: Vec<&str> = path.split('.').collect();
 
     if parts.is_empty() {
@@ -210,11 +228,11 @@ pub fn call_settings_function(func_name: &str, args: Vec<QValue>) -> Result<QVal
             // If no settings loaded, return empty dict
             let data = match settings.as_ref() {
                 Some(d) => d,
-                None => return Ok(QValue::Dict(Box::new(QDict::new(HashMap::new()))))
+                None => return Ok(QValue::Dict(Box::new(QDict::new(IndexMap::new()))))
             };
 
             // Convert entire settings to Dict
-            let mut map = HashMap::new();
+            let mut map = IndexMap::new();
             for (key, value) in data {
                 map.insert(key.clone(), toml_to_qvalue(value));
             }
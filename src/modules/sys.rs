@@ -1,15 +1,28 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::control_flow::EvalError;
 use std::env;
 use std::path::Path;
-use crate::{arg_err, name_err};
+use crate::{arg_err, attr_err, name_err, runtime_err, type_err, value_err};
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use crate::types::*;
+use crate::modules::args::{option, take_options};
 use crate::Scope;
 use crate::{QuestParser, Rule, eval_pair, extract_docstring};
 use pest::Parser;
 
+thread_local! {
+    /// Whether sys.eval and sys.load_module_from_string may run; off until
+    /// the program calls sys.allow_dynamic_code()
+    static DYNAMIC_CODE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the program has opted in to running code held in strings
+fn dynamic_code_allowed() -> bool {
+    DYNAMIC_CODE.with(|enabled| enabled.get())
+}
+
 pub fn create_sys_module(args: &[String], script_path: Option<&str>) -> QValue {
     let mut members = HashMap::new();
 
@@ -75,9 +88,20 @@ pub fn create_sys_module(args: &[String], script_path: Option<&str>) -> QValue {
     members.insert("load_module".to_string(), create_fn("sys", "load_module"));
     members.insert("exit".to_string(), create_fn("sys", "exit"));
     members.insert("fail".to_string(), create_fn("sys", "fail"));
+    members.insert("warn".to_string(), create_fn("sys", "warn"));
+    members.insert("strict".to_string(), create_fn("sys", "strict"));
+    members.insert("set_float_format".to_string(), create_fn("sys", "set_float_format"));
+    members.insert("float_format".to_string(), create_fn("sys", "float_format"));
     members.insert("eval".to_string(), create_fn("sys", "eval"));
+    members.insert("load_module_from_string".to_string(), create_fn("sys", "load_module_from_string"));
+    members.insert("allow_dynamic_code".to_string(), create_fn("sys", "allow_dynamic_code"));
+    members.insert("dynamic_code_allowed".to_string(), create_fn("sys", "dynamic_code_allowed"));
     members.insert("pid".to_string(), create_fn("sys", "pid"));
 
+    // Code coverage (used by quest test --coverage)
+    members.insert("coverage_start".to_string(), create_fn("sys", "coverage_start"));
+    members.insert("coverage_report".to_string(), create_fn("sys", "coverage_report"));
+
     // System stream singletons (QEP-010)
     members.insert("stdout".to_string(), QValue::SystemStream(QSystemStream::stdout()));
     members.insert("stderr".to_string(), QValue::SystemStream(QSystemStream::stderr()));
@@ -97,6 +121,15 @@ pub fn create_sys_module(args: &[String], script_path: Option<&str>) -> QValue {
     // QEP-059: Scope depth introspection (RAII scope management)
     members.insert("get_scope_depth".to_string(), create_fn("sys", "get_scope_depth"));
 
+    // Heap usage and per-type object counts
+    members.insert("memory_stats".to_string(), create_fn("sys", "memory_stats"));
+    members.insert("object_counts".to_string(), create_fn("sys", "object_counts"));
+
+    // Module member reflection and test patching (used by test.mock())
+    members.insert("get_member".to_string(), create_fn("sys", "get_member"));
+    members.insert("patch_member".to_string(), create_fn("sys", "patch_member"));
+    members.insert("unpatch_member".to_string(), create_fn("sys", "unpatch_member"));
+
     QValue::Module(Box::new(QModule::new("sys".to_string(), members)))
 }
 
@@ -140,27 +173,8 @@ pub fn call_sys_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
                 // Extract module docstring
                 let module_docstring = extract_docstring(&file_content);
 
-                // Create a fresh scope for the module
-                let mut module_scope = Scope::new();
-                module_scope.module_cache = Rc::clone(&scope.module_cache);
-                module_scope.current_script_path = Rc::new(RefCell::new(Some(canonical_path.clone())));
-
-                // Parse and evaluate the module file
-                let pairs = QuestParser::parse(Rule::program, &file_content)
-                    .map_err(|e| format!("Parse error in module '{}': {}", path, e))?;
-
-                // Execute all statements in the module
-                for pair in pairs {
-                    if matches!(pair.as_rule(), Rule::EOI) {
-                        continue;
-                    }
-                    for statement in pair.into_inner() {
-                        if matches!(statement.as_rule(), Rule::EOI) {
-                            continue;
-                        }
-                        eval_pair(statement, &mut module_scope)?;
-                    }
-                }
+                let mut module_scope = new_module_scope(scope, Some(canonical_path.clone()));
+                run_module_source(&file_content, &path, &mut module_scope)?;
 
                 // Create a module object
                 let members = module_scope.to_flat_map();
@@ -208,10 +222,143 @@ pub fn call_sys_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
             }
         }
 
+        "sys.warn" => {
+            // sys.warn(message, category = "UserWarning", stacklevel: 1)
+            let mut args = args;
+            let options = match args.last() {
+                Some(QValue::Dict(_)) if args.len() > 1 => match args.pop() {
+                    Some(QValue::Dict(options)) => Some(options),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("sys.warn expects 1 or 2 arguments, got {}", args.len());
+            }
+            let message = match &args[0] {
+                QValue::Str(s) => s.value.to_string(),
+                other => return type_err!("sys.warn message must be Str, got {}", other.q_type()),
+            };
+            let category = match args.get(1).cloned().or_else(|| options.as_ref().and_then(|o| o.get("category"))) {
+                None | Some(QValue::Nil(_)) => crate::warnings::USER_WARNING.to_string(),
+                Some(QValue::Str(s)) => s.value.to_string(),
+                Some(other) => return type_err!("sys.warn category must be Str, got {}", other.q_type()),
+            };
+            let stacklevel = match options.as_ref().and_then(|o| o.get("stacklevel")) {
+                None => 1,
+                Some(QValue::Int(n)) if n.value >= 1 => n.value as usize,
+                Some(_) => return arg_err!("sys.warn stacklevel must be an Int of at least 1"),
+            };
+            crate::warnings::warn(scope, &message, &category, stacklevel)?;
+            Ok(QValue::Nil(QNil))
+        }
+
+        "sys.strict" => {
+            // sys.strict(enabled) - strict mode for the calling file; sys.strict() reports it
+            match args.as_slice() {
+                [] => Ok(QValue::Bool(QBool::new(crate::strict::is_strict(scope)))),
+                [QValue::Bool(enabled)] => {
+                    crate::strict::set(scope, enabled.value);
+                    Ok(QValue::Nil(QNil))
+                }
+                [other] => type_err!("sys.strict expects a Bool, got {}", other.q_type()),
+                _ => arg_err!("sys.strict expects 0 or 1 arguments, got {}", args.len()),
+            }
+        }
+
+        "sys.set_float_format" => {
+            // sys.set_float_format(precision = nil, mode = "shortest"); no arguments restores the default
+            use crate::types::float::{set_float_format, FloatMode};
+            let mut args = args;
+            let options = take_options(&mut args);
+            if args.len() > 2 {
+                return arg_err!("sys.set_float_format expects 0 to 2 arguments, got {}", args.len());
+            }
+            let argument = |index: usize, name: &str| args.get(index).cloned().or_else(|| option(&options, name));
+            let precision = match argument(0, "precision") {
+                None | Some(QValue::Nil(_)) => None,
+                Some(QValue::Int(n)) if (0..=28).contains(&n.value) => Some(n.value as usize),
+                Some(QValue::Int(n)) => return value_err!("sys.set_float_format precision must be between 0 and 28, got {}", n.value),
+                Some(other) => return type_err!("sys.set_float_format precision must be Int, got {}", other.q_type()),
+            };
+            let mode = match argument(1, "mode") {
+                None | Some(QValue::Nil(_)) => FloatMode::Shortest,
+                Some(QValue::Str(s)) => match s.value.as_str() {
+                    "shortest" => FloatMode::Shortest,
+                    "fixed" => FloatMode::Fixed,
+                    other => return value_err!("sys.set_float_format mode must be \"shortest\" or \"fixed\", got \"{}\"", other),
+                },
+                Some(other) => return type_err!("sys.set_float_format mode must be Str, got {}", other.q_type()),
+            };
+            if mode == FloatMode::Fixed && precision.is_none() {
+                return value_err!("sys.set_float_format mode \"fixed\" needs a precision");
+            }
+            set_float_format(precision, mode);
+            Ok(QValue::Nil(QNil))
+        }
+
+        "sys.float_format" => {
+            // sys.float_format() - the current setting as {"precision": Int or nil, "mode": Str}
+            if !args.is_empty() {
+                return arg_err!("sys.float_format expects 0 arguments, got {}", args.len());
+            }
+            let (precision, mode) = crate::types::float::float_format();
+            let mut map = IndexMap::new();
+            map.insert("precision".to_string(), match precision {
+                Some(p) => QValue::Int(QInt::new(p as i64)),
+                None => QValue::Nil(QNil),
+            });
+            map.insert("mode".to_string(), QValue::Str(QString::new(match mode {
+                crate::types::float::FloatMode::Shortest => "shortest",
+                crate::types::float::FloatMode::Fixed => "fixed",
+            }.to_string())));
+            Ok(QValue::Dict(Box::new(QDict::new(map))))
+        }
+
+        "sys.allow_dynamic_code" => {
+            // sys.allow_dynamic_code(enabled = true) - returns the previous setting
+            let enabled = match args.as_slice() {
+                [] => true,
+                [QValue::Bool(enabled)] => enabled.value,
+                [other] => return type_err!("sys.allow_dynamic_code expects a Bool, got {}", other.q_type()),
+                _ => return arg_err!("sys.allow_dynamic_code expects 0 or 1 arguments, got {}", args.len()),
+            };
+            let previous = DYNAMIC_CODE.with(|cell| cell.replace(enabled));
+            Ok(QValue::Bool(QBool::new(previous)))
+        }
+
+        "sys.dynamic_code_allowed" => {
+            if !args.is_empty() {
+                return arg_err!("sys.dynamic_code_allowed expects 0 arguments, got {}", args.len());
+            }
+            Ok(QValue::Bool(QBool::new(dynamic_code_allowed())))
+        }
+
         "sys.eval" => {
             // QEP-018: Dynamic code execution
+            if !dynamic_code_allowed() {
+                return runtime_err!("{} is disabled; call sys.allow_dynamic_code() to run code from strings", func_name);
+            }
+            let mut args = args;
+            let variables = match args.get(1) {
+                Some(QValue::Dict(options)) if args.len() == 2 => {
+                    let options = options.map.borrow().clone();
+                    args.pop();
+                    let mut variables = None;
+                    for (key, value) in options.iter() {
+                        match (key.as_str(), value) {
+                            ("scope", QValue::Nil(_)) => {}
+                            ("scope", QValue::Dict(d)) => variables = Some(d.map.borrow().clone()),
+                            ("scope", other) => return type_err!("sys.eval scope must be a Dict, got {}", other.q_type()),
+                            (other, _) => return arg_err!("Unknown sys.eval option '{}' (expected scope)", other),
+                        }
+                    }
+                    variables
+                }
+                _ => None,
+            };
             if args.len() != 1 {
-                return arg_err!("sys.eval expects 1 argument, got {}", args.len());
+                return arg_err!("sys.eval expects 1 argument (code, scope: nil), got {}", args.len());
             }
 
             let code = match &args[0] {
@@ -219,38 +366,45 @@ pub fn call_sys_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
                 _ => return Err("sys.eval: argument must be String".into()),
             };
 
-            // Handle empty/whitespace-only code
-            if code.trim().is_empty() {
-                return Ok(QValue::Nil(QNil));
-            }
-
-            // Parse the code (QEP-037: use SyntaxErr for parse errors)
-            let pairs = QuestParser::parse(Rule::program, &code)
-                .map_err(|e| format!("SyntaxErr: {}", e))?;
-
-            // Evaluate in current scope
-            let mut result = QValue::Nil(QNil);
-            for pair in pairs {
-                if pair.as_rule() == Rule::program {
-                    for statement in pair.into_inner() {
-                        if !matches!(statement.as_rule(), Rule::EOI) {
-                            match eval_pair(statement, scope) {
-                                Ok(val) => result = val,
-                                Err(crate::control_flow::EvalError::ControlFlow(
-                                    crate::control_flow::ControlFlow::FunctionReturn(_val)
-                                )) => {
-                                    // QEP-056: Top-level return in eval() exits cleanly
-                                    // Return nil (the return value is not propagated from eval)
-                                    return Ok(QValue::Nil(QNil));
-                                }
-                                Err(e) => return Err(e.to_string().into()),
-                            }
-                        }
+            // With scope:, the code sees only the given variables (plus
+            // built-ins) and its own definitions are discarded afterwards
+            match variables {
+                Some(variables) => {
+                    let mut eval_scope = new_module_scope(scope, None);
+                    for (name, value) in variables {
+                        eval_scope.declare(&name, value)?;
                     }
+                    eval_code(&code, &mut eval_scope)
                 }
+                None => eval_code(&code, scope),
+            }
+        }
+
+        "sys.load_module_from_string" => {
+            if !dynamic_code_allowed() {
+                return runtime_err!("{} is disabled; call sys.allow_dynamic_code() to run code from strings", func_name);
             }
+            if args.len() != 2 {
+                return arg_err!("sys.load_module_from_string expects 2 arguments (name, code), got {}", args.len());
+            }
+            let name = match &args[0] {
+                QValue::Str(s) => s.value.to_string(),
+                other => return type_err!("sys.load_module_from_string name must be Str, got {}", other.q_type()),
+            };
+            let code = match &args[1] {
+                QValue::Str(s) => s.value.to_string(),
+                other => return type_err!("sys.load_module_from_string code must be Str, got {}", other.q_type()),
+            };
 
-            Ok(result)
+            // Not cached: each call builds a fresh module
+            let mut module_scope = new_module_scope(scope, None);
+            run_module_source(&code, &name, &mut module_scope)?;
+            Ok(QValue::Module(Box::new(QModule::with_doc(
+                name,
+                module_scope.to_flat_map(),
+                None,
+                extract_docstring(&code)
+            ))))
         }
 
         "sys.redirect_stream" => {
@@ -308,6 +462,86 @@ pub fn call_sys_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
             Ok(QValue::Int(QInt::new(std::process::id() as i64)))
         }
 
+        "sys.coverage_start" => {
+            if !args.is_empty() {
+                return arg_err!("sys.coverage_start expects 0 arguments, got {}", args.len());
+            }
+            crate::coverage::enable();
+            Ok(QValue::Nil(QNil))
+        }
+
+        "sys.coverage_report" => {
+            // Options: lcov (file), html (directory), merge (lcov files), include/exclude (paths)
+            if args.len() > 1 {
+                return arg_err!("sys.coverage_report expects 0 or 1 arguments, got {}", args.len());
+            }
+            let string_list = |value: &QValue, name: &str| -> Result<Vec<String>, EvalError> {
+                match value {
+                    QValue::Array(arr) => arr.elements.borrow().iter().map(|v| match v {
+                        QValue::Str(s) => Ok(s.value.as_ref().clone()),
+                        _ => type_err!("sys.coverage_report {} must be an array of strings", name),
+                    }).collect(),
+                    QValue::Nil(_) => Ok(Vec::new()),
+                    _ => type_err!("sys.coverage_report {} must be an array of strings", name),
+                }
+            };
+            let optional_str = |value: &QValue, name: &str| -> Result<Option<String>, EvalError> {
+                match value {
+                    QValue::Str(s) => Ok(Some(s.value.as_ref().clone())),
+                    QValue::Nil(_) => Ok(None),
+                    _ => type_err!("sys.coverage_report {} must be a string", name),
+                }
+            };
+
+            let (mut lcov_path, mut html_dir) = (None, None);
+            let (mut merge, mut include, mut exclude) = (Vec::new(), Vec::new(), Vec::new());
+            match args.first() {
+                Some(QValue::Dict(dict)) => {
+                    let map = dict.map.borrow();
+                    if let Some(v) = map.get("lcov") { lcov_path = optional_str(v, "lcov")?; }
+                    if let Some(v) = map.get("html") { html_dir = optional_str(v, "html")?; }
+                    if let Some(v) = map.get("merge") { merge = string_list(v, "merge")?; }
+                    if let Some(v) = map.get("include") { include = string_list(v, "include")?; }
+                    if let Some(v) = map.get("exclude") { exclude = string_list(v, "exclude")?; }
+                }
+                Some(QValue::Nil(_)) | None => {}
+                Some(_) => return type_err!("sys.coverage_report options must be a dict"),
+            }
+
+            let mut data = crate::coverage::collect();
+            for path in &merge {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| format!("IOErr: Failed to read coverage file '{}': {}", path, e))?;
+                crate::coverage::merge(&mut data, &crate::coverage::parse_lcov(&text));
+            }
+            crate::coverage::filter(&mut data, &include, &exclude);
+
+            if let Some(path) = &lcov_path {
+                if let Some(parent) = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|e| format!("IOErr: Failed to create '{}': {}", parent.display(), e))?;
+                }
+                std::fs::write(path, crate::coverage::to_lcov(&data))
+                    .map_err(|e| format!("IOErr: Failed to write '{}': {}", path, e))?;
+            }
+            if let Some(dir) = &html_dir {
+                let root = env::current_dir().unwrap_or_default();
+                crate::coverage::write_html(&data, Path::new(dir), &root)
+                    .map_err(|e| format!("IOErr: Failed to write coverage report to '{}': {}", dir, e))?;
+            }
+
+            let count = |f: fn(&crate::coverage::FileCoverage) -> usize| {
+                QValue::Int(QInt::new(data.values().map(f).sum::<usize>() as i64))
+            };
+            let mut summary = IndexMap::new();
+            summary.insert("files".to_string(), QValue::Int(QInt::new(data.len() as i64)));
+            summary.insert("lines_found".to_string(), count(|f| f.lines.len()));
+            summary.insert("lines_hit".to_string(), count(|f| f.lines_hit()));
+            summary.insert("branches_found".to_string(), count(|f| f.branches_found()));
+            summary.insert("branches_hit".to_string(), count(|f| f.branches_hit()));
+            Ok(QValue::Dict(Box::new(QDict::new(summary))))
+        }
+
         "sys.get_call_depth" => {
             // QEP-048: Return current function call depth
             if !args.is_empty() {
@@ -321,7 +555,7 @@ pub fn call_sys_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
             if !args.is_empty() {
                 return arg_err!("sys.get_depth_limits expects 0 arguments, got {}", args.len());
             }
-            let mut map = HashMap::new();
+            let mut map = IndexMap::new();
             // These are the configured limits (hardcoded for now, could be made configurable)
             map.insert("function_calls".to_string(), QValue::Int(QInt::new(1000)));
             map.insert("eval_recursion".to_string(), QValue::Int(QInt::new(2000)));
@@ -337,6 +571,158 @@ pub fn call_sys_function(func_name: &str, args: Vec<QValue>, scope: &mut Scope)
             Ok(QValue::Int(QInt::new(scope.depth() as i64)))
         }
 
+        "sys.memory_stats" => {
+            if !args.is_empty() {
+                return arg_err!("sys.memory_stats expects 0 arguments, got {}", args.len());
+            }
+            crate::alloc_counter::start_tracking();
+            let mut objects = IndexMap::new();
+            for (type_name, counts) in crate::alloc_counter::object_counts() {
+                let mut entry = IndexMap::new();
+                entry.insert("created".to_string(), QValue::Int(QInt::new(counts.created as i64)));
+                entry.insert("cloned".to_string(), QValue::Int(QInt::new(counts.cloned as i64)));
+                entry.insert("dropped".to_string(), QValue::Int(QInt::new(counts.dropped as i64)));
+                entry.insert("live".to_string(), QValue::Int(QInt::new(counts.live())));
+                objects.insert(type_name.to_string(), QValue::Dict(Box::new(QDict::new(entry))));
+            }
+            let mut stats = IndexMap::new();
+            stats.insert("current_bytes".to_string(), QValue::Int(QInt::new(crate::alloc_counter::current_bytes() as i64)));
+            stats.insert("peak_bytes".to_string(), QValue::Int(QInt::new(crate::alloc_counter::peak_bytes() as i64)));
+            stats.insert("allocations".to_string(), QValue::Int(QInt::new(crate::alloc_counter::total_allocations() as i64)));
+            stats.insert("objects".to_string(), QValue::Dict(Box::new(QDict::new(objects))));
+            Ok(QValue::Dict(Box::new(QDict::new(stats))))
+        }
+
+        "sys.object_counts" => {
+            if !args.is_empty() {
+                return arg_err!("sys.object_counts expects 0 arguments, got {}", args.len());
+            }
+            crate::alloc_counter::start_tracking();
+            let counts = crate::alloc_counter::object_counts().into_iter()
+                .map(|(type_name, counts)| (type_name.to_string(), QValue::Int(QInt::new(counts.live()))))
+                .collect();
+            Ok(QValue::Dict(Box::new(QDict::new(counts))))
+        }
+
+        "sys.get_member" => {
+            // sys.get_member(module, name) - module.<name> with a computed name
+            if args.len() != 2 {
+                return arg_err!("sys.get_member expects 2 arguments (module, name), got {}", args.len());
+            }
+            let (module, name) = module_and_name("get_member", &args)?;
+            match module.get_member(&name) {
+                Some(value) => Ok(value),
+                None => attr_err!("Module {} has no member '{}'", module.name, name),
+            }
+        }
+
+        "sys.patch_member" => {
+            // sys.patch_member(module, name, value) - replace a public member for every
+            // importer until sys.unpatch_member(); returns true if it was already patched
+            if args.len() != 3 {
+                return arg_err!("sys.patch_member expects 3 arguments (module, name, value), got {}", args.len());
+            }
+            let (module, name) = module_and_name("patch_member", &args)?;
+            let was_patched = module.patch_member(&name, args[2].clone())?;
+            Ok(QValue::Bool(QBool::new(was_patched)))
+        }
+
+        "sys.unpatch_member" => {
+            // sys.unpatch_member(module, name) - put back the original member
+            if args.len() != 2 {
+                return arg_err!("sys.unpatch_member expects 2 arguments (module, name), got {}", args.len());
+            }
+            let (module, name) = module_and_name("unpatch_member", &args)?;
+            module.unpatch_member(&name);
+            Ok(QValue::Nil(QNil))
+        }
+
         _ => name_err!("Unknown sys function: {}", func_name)
     }
 }
+
+fn module_and_name<'a>(func: &str, args: &'a [QValue]) -> Result<(&'a QModule, String), EvalError> {
+    let module = match &args[0] {
+        QValue::Module(m) => m.as_ref(),
+        other => return type_err!("sys.{} expects a Module, got {}", func, other.as_obj().cls()),
+    };
+    match &args[1] {
+        QValue::Str(s) => Ok((module, s.value.to_string())),
+        other => type_err!("sys.{} member name must be Str, got {}", func, other.as_obj().cls()),
+    }
+}
+
+/// A fresh top-level scope for module code, sharing the caller's module
+/// cache and output targets
+fn new_module_scope(scope: &Scope, path: Option<String>) -> Scope {
+    let mut module_scope = Scope::new();
+    module_scope.module_cache = Rc::clone(&scope.module_cache);
+    module_scope.source_file = path.as_ref().map(|p| Rc::new(p.clone()));
+    module_scope.current_script_path = Rc::new(RefCell::new(path));
+    // Output from the module goes wherever the caller's output currently goes
+    module_scope.stdout_target = scope.stdout_target.clone();
+    module_scope.stderr_target = scope.stderr_target.clone();
+    module_scope
+}
+
+/// Parse and execute every statement of a module's source
+fn run_module_source(code: &str, name: &str, module_scope: &mut Scope) -> Result<(), EvalError> {
+    let pairs = QuestParser::parse(Rule::program, code)
+        .map_err(|e| format!("Parse error in module '{}': {}", name, e))?;
+
+    for pair in pairs {
+        if matches!(pair.as_rule(), Rule::EOI) {
+            continue;
+        }
+        for statement in pair.into_inner() {
+            if matches!(statement.as_rule(), Rule::EOI) {
+                continue;
+            }
+            eval_pair(statement, module_scope)?;
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate code for sys.eval, returning the value of the last statement
+fn eval_code(code: &str, scope: &mut Scope) -> Result<QValue, EvalError> {
+    // Handle empty/whitespace-only code
+    if code.trim().is_empty() {
+        return Ok(QValue::Nil(QNil));
+    }
+
+    // Parse the code (QEP-037: use SyntaxErr for parse errors)
+    let pairs = QuestParser::parse(Rule::program, code)
+        .map_err(|e| format!("SyntaxErr: {}", e))?;
+
+    // The code's lines don't belong to the caller's file, so it is left out
+    // of coverage.
+    let source_file = scope.source_file.take();
+    let mut result = Ok(QValue::Nil(QNil));
+    'program: for pair in pairs {
+        if pair.as_rule() == Rule::program {
+            for statement in pair.into_inner() {
+                if !matches!(statement.as_rule(), Rule::EOI) {
+                    match eval_pair(statement, scope) {
+                        Ok(val) => result = Ok(val),
+                        Err(crate::control_flow::EvalError::ControlFlow(
+                            crate::control_flow::ControlFlow::FunctionReturn(_val)
+                        )) => {
+                            // QEP-056: Top-level return in eval() exits cleanly
+                            // Return nil (the return value is not propagated from eval)
+                            result = Ok(QValue::Nil(QNil));
+                            break 'program;
+                        }
+                        Err(e) => {
+                            result = Err(e.to_string().into());
+                            break 'program;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    scope.source_file = source_file;
+
+    result
+}
@@ -1,7 +1,18 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use crate::control_flow::EvalError;
-use crate::{arg_err, value_err, attr_err};
+use std::io::{BufRead, IsTerminal};
+use unicode_width::UnicodeWidthStr;
+use crate::{arg_err, value_err, attr_err, io_err, type_err};
 use crate::types::*;
+use crate::function_call::{call_user_function, CallArguments};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::history::{DefaultHistory, History};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, Editor, Helper};
 
 pub fn create_term_module() -> QValue {
     let mut members = HashMap::new();
@@ -54,12 +65,19 @@ pub fn create_term_module() -> QValue {
     // ANSI control
     members.insert("reset".to_string(), create_fn("term", "reset"));
     members.insert("strip_colors".to_string(), create_fn("term", "strip_colors"));
+    members.insert("display_width".to_string(), create_fn("term", "display_width"));
+
+    // Input
+    members.insert("is_tty".to_string(), create_fn("term", "is_tty"));
+    members.insert("read_key".to_string(), create_fn("term", "read_key"));
+    members.insert("read_password".to_string(), create_fn("term", "read_password"));
+    members.insert("input".to_string(), create_fn("term", "input"));
 
     QValue::Module(Box::new(QModule::new("term".to_string(), members)))
 }
 
 /// Handle term.* function calls
-pub fn call_term_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+pub fn call_term_function(func_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
     match func_name {
         "term.red" | "term.green" | "term.yellow" |
         "term.blue" | "term.magenta" | "term.cyan" |
@@ -390,30 +408,352 @@ pub fn call_term_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
             if args.len() != 1 {
                 return arg_err!("strip_colors expects 1 argument, got {}", args.len());
             }
-            let text = args[0].as_str();
-            // Simple regex-like replacement to strip ANSI codes
-            let mut result = String::new();
-            let mut chars = text.chars().peekable();
-            while let Some(ch) = chars.next() {
-                if ch == '\x1b' {
-                    // Skip escape sequence
-                    if chars.peek() == Some(&'[') {
-                        chars.next(); // consume '['
-                        // Skip until we find a letter (the command)
-                        while let Some(&c) = chars.peek() {
-                            chars.next();
-                            if c.is_ascii_alphabetic() {
-                                break;
-                            }
-                        }
+            Ok(QValue::Str(QString::new(strip_ansi(&args[0].as_str()))))
+        }
+
+        "term.display_width" => {
+            if args.len() != 1 {
+                return arg_err!("display_width expects 1 argument, got {}", args.len());
+            }
+            let width = UnicodeWidthStr::width(strip_ansi(&args[0].as_str()).as_str());
+            Ok(QValue::Int(QInt::new(width as i64)))
+        }
+
+        "term.is_tty" => {
+            if args.len() > 1 {
+                return arg_err!("is_tty expects 0 or 1 arguments, got {}", args.len());
+            }
+            let stream = args.first().map(|a| a.as_str()).unwrap_or_else(|| "stdout".to_string());
+            let is_tty = match stream.as_str() {
+                "stdout" => std::io::stdout().is_terminal(),
+                "stderr" => std::io::stderr().is_terminal(),
+                "stdin" => std::io::stdin().is_terminal(),
+                _ => return value_err!("Unknown stream '{}' (expected stdout, stderr or stdin)", stream),
+            };
+            Ok(QValue::Bool(QBool::new(is_tty)))
+        }
+
+        "term.read_key" => {
+            if !args.is_empty() {
+                return arg_err!("read_key expects 0 arguments, got {}", args.len());
+            }
+            match read_key() {
+                Ok(Some(key)) => Ok(QValue::Str(QString::new(key))),
+                Ok(None) => Ok(QValue::Nil(QNil)),
+                Err(e) => io_err!("Failed to read key: {}", e),
+            }
+        }
+
+        "term.input" => input(args, scope),
+
+        "term.read_password" => {
+            if !args.is_empty() {
+                return arg_err!("read_password expects 0 arguments, got {}", args.len());
+            }
+            match read_password() {
+                Ok(Some(line)) => Ok(QValue::Str(QString::new(line))),
+                Ok(None) => Ok(QValue::Nil(QNil)),
+                Err(e) => io_err!("Failed to read password: {}", e),
+            }
+        }
+
+        _ => attr_err!("Unknown term function: {}", func_name)
+    }
+}
+
+/// Remove ANSI escape sequences from text
+fn strip_ansi(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            // Skip escape sequence
+            if chars.peek() == Some(&'[') {
+                chars.next(); // consume '['
+                // Skip until we find a letter (the command)
+                while let Some(&c) = chars.peek() {
+                    chars.next();
+                    if c.is_ascii_alphabetic() {
+                        break;
                     }
-                } else {
-                    result.push(ch);
                 }
             }
-            Ok(QValue::Str(QString::new(result)))
+        } else {
+            result.push(ch);
         }
+    }
+    result
+}
 
-        _ => attr_err!("Unknown term function: {}", func_name)
+/// Read a line from stdin without its line ending; None at end of input
+fn read_line() -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let trimmed = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(trimmed);
+    Ok(Some(line))
+}
+
+#[cfg(unix)]
+struct RestoreTermios(nix::sys::termios::Termios);
+
+#[cfg(unix)]
+impl Drop for RestoreTermios {
+    fn drop(&mut self) {
+        let _ = nix::sys::termios::tcsetattr(std::io::stdin(), nix::sys::termios::SetArg::TCSANOW, &self.0);
+    }
+}
+
+/// Switch stdin to the given settings until the returned guard is dropped
+#[cfg(unix)]
+fn set_termios(change: impl FnOnce(&mut nix::sys::termios::Termios)) -> nix::Result<RestoreTermios> {
+    use nix::sys::termios::{tcgetattr, tcsetattr, SetArg};
+    let original = tcgetattr(std::io::stdin())?;
+    let mut settings = original.clone();
+    change(&mut settings);
+    tcsetattr(std::io::stdin(), SetArg::TCSANOW, &settings)?;
+    Ok(RestoreTermios(original))
+}
+
+/// Read one keypress without waiting for Enter. Returns the typed character,
+/// or a name like "up", "enter" or "ctrl-a" for special keys; None at end of
+/// input. When stdin isn't a terminal, reads one character.
+#[cfg(unix)]
+fn read_key() -> Result<Option<String>, String> {
+    use std::os::fd::AsRawFd;
+    use nix::sys::termios::{InputFlags, LocalFlags, SpecialCharacterIndices};
+
+    if !std::io::stdin().is_terminal() {
+        use std::io::Read;
+        let mut stdin = std::io::stdin().lock();
+        let mut buf = [0u8; 4];
+        if stdin.read(&mut buf[..1]).map_err(|e| e.to_string())? == 0 {
+            return Ok(None);
+        }
+        // Read the rest of a multi-byte UTF-8 character
+        let len = match buf[0] {
+            0xf0..=0xff => 4,
+            0xe0..=0xef => 3,
+            0xc0..=0xdf => 2,
+            _ => 1,
+        };
+        let n = 1 + stdin.read(&mut buf[1..len]).map_err(|e| e.to_string())?;
+        return Ok(Some(key_name(&buf[..n])));
+    }
+
+    let guard = set_termios(|t| {
+        t.local_flags.remove(LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::ISIG);
+        t.input_flags.remove(InputFlags::ICRNL | InputFlags::IXON);
+        t.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        t.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+    }).map_err(|e| e.to_string())?;
+
+    // A terminal sends a whole key sequence (e.g. "\x1b[A") in one write
+    let mut buf = [0u8; 16];
+    let n = nix::unistd::read(std::io::stdin().as_raw_fd(), &mut buf).map_err(|e| e.to_string())?;
+    drop(guard);
+    if n == 0 {
+        return Ok(None);
+    }
+    if buf[0] == 0x03 {
+        interrupt();
+    }
+    Ok(Some(key_name(&buf[..n])))
+}
+
+#[cfg(not(unix))]
+fn read_key() -> Result<Option<String>, String> {
+    Err("reading single keys is not supported on this platform".to_string())
+}
+
+fn key_name(bytes: &[u8]) -> String {
+    let name = match bytes {
+        [0x1b, b'[' | b'O', rest @ ..] => match rest {
+            [b'A', ..] => "up",
+            [b'B', ..] => "down",
+            [b'C', ..] => "right",
+            [b'D', ..] => "left",
+            [b'H', ..] | [b'1', b'~', ..] => "home",
+            [b'F', ..] | [b'4', b'~', ..] => "end",
+            [b'3', b'~', ..] => "delete",
+            [b'5', b'~', ..] => "page_up",
+            [b'6', b'~', ..] => "page_down",
+            _ => "escape",
+        },
+        [0x1b, ..] => "escape",
+        [b'\r' | b'\n', ..] => "enter",
+        [b'\t', ..] => "tab",
+        [0x7f | 0x08, ..] => "backspace",
+        [c @ 1..=26, ..] => return format!("ctrl-{}", (b'a' + c - 1) as char),
+        _ => {
+            let text = String::from_utf8_lossy(bytes);
+            return text.chars().next().map(|c| c.to_string()).unwrap_or_default();
+        }
+    };
+    name.to_string()
+}
+
+/// Read a line from stdin without echoing it; None at end of input
+#[cfg(unix)]
+fn read_password() -> Result<Option<String>, String> {
+    use nix::sys::termios::LocalFlags;
+
+    if !std::io::stdin().is_terminal() {
+        return read_line().map_err(|e| e.to_string());
+    }
+    let guard = set_termios(|t| {
+        t.local_flags.remove(LocalFlags::ECHO);
+        t.local_flags.insert(LocalFlags::ECHONL);
+    }).map_err(|e| e.to_string())?;
+    let line = read_line().map_err(|e| e.to_string());
+    drop(guard);
+    line
+}
+
+#[cfg(not(unix))]
+fn read_password() -> Result<Option<String>, String> {
+    read_line().map_err(|e| e.to_string())
+}
+
+/// Ctrl-C while the terminal isn't delivering signals itself: behave as if
+/// it had sent SIGINT
+fn interrupt() {
+    #[cfg(unix)]
+    let _ = nix::sys::signal::raise(nix::sys::signal::Signal::SIGINT);
+}
+
+thread_local! {
+    /// Lines entered at term.input() prompts with `history: true`
+    static INPUT_HISTORY: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Line editor helper that completes words with a Quest function
+struct InputHelper<'a> {
+    completer: Option<QUserFun>,
+    scope: RefCell<&'a mut crate::Scope>,
+    /// Error raised by the completer, re-raised once input ends
+    error: RefCell<Option<String>>,
+}
+
+impl Helper for InputHelper<'_> {}
+impl Highlighter for InputHelper<'_> {}
+impl Validator for InputHelper<'_> {}
+
+impl Hinter for InputHelper<'_> {
+    type Hint = String;
+}
+
+impl Completer for InputHelper<'_> {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let Some(completer) = &self.completer else {
+            return Ok((pos, Vec::new()));
+        };
+        // Complete the word before the cursor
+        let start = line[..pos].char_indices().rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        let call_args = CallArguments::positional_only(vec![QValue::Str(QString::new(line[start..pos].to_string()))]);
+        let result = call_user_function(completer, call_args, &mut self.scope.borrow_mut(), None);
+        let candidates = match result {
+            Ok(QValue::Array(items)) => items.elements.borrow().iter().map(|v| v.as_str()).collect(),
+            Ok(QValue::Nil(_)) => Vec::new(),
+            Ok(other) => {
+                *self.error.borrow_mut() = Some(format!("TypeErr: completer must return an Array, got {}", other.q_type()));
+                Vec::new()
+            }
+            Err(e) => {
+                *self.error.borrow_mut() = Some(e.to_string());
+                Vec::new()
+            }
+        };
+        Ok((start, candidates))
+    }
+}
+
+/// term.input(prompt = "", history: false, completer: nil)
+fn input(args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let mut args = args;
+    let options = match args.last() {
+        Some(QValue::Dict(d)) => {
+            let map = d.map.borrow().clone();
+            args.pop();
+            map
+        }
+        _ => Default::default(),
+    };
+    if args.len() > 1 {
+        return arg_err!("input expects at most 1 positional argument (prompt), got {}", args.len());
+    }
+    let prompt = args.first().map(|p| p.as_str()).unwrap_or_default();
+
+    // history: true keeps lines for this run, a Str names a file to keep them in
+    let mut session_history = false;
+    let mut history_file = None;
+    let mut completer = None;
+    for (key, value) in options.iter() {
+        match (key.as_str(), value) {
+            ("history", QValue::Nil(_)) => {}
+            ("history", QValue::Str(path)) => history_file = Some(path.value.to_string()),
+            ("history", v) => session_history = v.as_bool(),
+            ("completer", QValue::UserFun(f)) => completer = Some((**f).clone()),
+            ("completer", QValue::Nil(_)) => {}
+            ("completer", other) => return type_err!("completer must be a function, got {}", other.q_type()),
+            (other, _) => return arg_err!("Unknown input option '{}' (expected history or completer)", other),
+        }
+    }
+
+    let config = Config::builder()
+        .auto_add_history(false)
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor: Editor<InputHelper, DefaultHistory> = match Editor::with_config(config) {
+        Ok(editor) => editor,
+        Err(e) => return io_err!("Failed to start line editor: {}", e),
+    };
+    if let Some(path) = &history_file {
+        // The file doesn't exist before the first entry is saved
+        let _ = editor.load_history(path);
+    } else if session_history {
+        INPUT_HISTORY.with(|h| {
+            for line in h.borrow().iter() {
+                let _ = editor.history_mut().add(line);
+            }
+        });
+    }
+    editor.set_helper(Some(InputHelper {
+        completer,
+        scope: RefCell::new(scope),
+        error: RefCell::new(None),
+    }));
+
+    let result = editor.readline(&prompt);
+    if let Some(error) = editor.helper().and_then(|h| h.error.borrow_mut().take()) {
+        return Err(error.into());
+    }
+    match result {
+        Ok(line) => {
+            if !line.trim().is_empty() {
+                if let Some(path) = &history_file {
+                    let _ = editor.add_history_entry(line.as_str());
+                    if let Err(e) = editor.append_history(path) {
+                        return io_err!("Failed to save history to '{}': {}", path, e);
+                    }
+                } else if session_history {
+                    INPUT_HISTORY.with(|h| h.borrow_mut().push(line.clone()));
+                }
+            }
+            Ok(QValue::Str(QString::new(line)))
+        }
+        Err(ReadlineError::Eof) => Ok(QValue::Nil(QNil)),
+        Err(ReadlineError::Interrupted) => {
+            interrupt();
+            Ok(QValue::Nil(QNil))
+        }
+        Err(e) => io_err!("Failed to read input: {}", e),
     }
 }
@@ -0,0 +1,189 @@
+// std/text/diff - line, word and character diffs, unified patches and merges
+//
+// Change lists (lines/words/chars) come from `similar` (Myers' algorithm).
+// Unified diffs, patch application and three-way merges use `diffy`, so a
+// patch made by unified() always parses and applies with apply().
+
+use std::collections::HashMap;
+
+use diffy::{ConflictStyle, DiffOptions, MergeOptions, Patch, PatchFormatter};
+use indexmap::IndexMap;
+use similar::{ChangeTag, TextDiff};
+
+use crate::control_flow::EvalError;
+use crate::modules::args::{option, take_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+const DEFAULT_CONTEXT: usize = 3;
+
+pub fn create_diff_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("lines".to_string(), create_fn("diff", "lines"));
+    members.insert("words".to_string(), create_fn("diff", "words"));
+    members.insert("chars".to_string(), create_fn("diff", "chars"));
+    members.insert("unified".to_string(), create_fn("diff", "unified"));
+    members.insert("apply".to_string(), create_fn("diff", "apply"));
+    members.insert("merge".to_string(), create_fn("diff", "merge"));
+
+    QValue::Module(Box::new(QModule::new("diff".to_string(), members)))
+}
+
+fn text_args(func_name: &str, args: &[QValue], names: &[&str]) -> Result<Vec<String>, String> {
+    if args.len() != names.len() {
+        return arg_err!("{} expects {} arguments ({}), got {}", func_name, names.len(), names.join(", "), args.len());
+    }
+    args.iter()
+        .zip(names)
+        .map(|(value, name)| match value {
+            QValue::Str(s) => Ok(s.value.to_string()),
+            other => type_err!("{} {} must be a Str, got {}", func_name, name, other.as_obj().cls()),
+        })
+        .collect()
+}
+
+fn op_name(tag: ChangeTag) -> &'static str {
+    match tag {
+        ChangeTag::Equal => "equal",
+        ChangeTag::Delete => "delete",
+        ChangeTag::Insert => "insert",
+    }
+}
+
+fn str_value(text: &str) -> QValue {
+    QValue::Str(QString::new(text.to_string()))
+}
+
+fn line_number(index: Option<usize>) -> QValue {
+    match index {
+        Some(i) => QValue::Int(QInt::new(i as i64 + 1)),
+        None => QValue::Nil(QNil),
+    }
+}
+
+/// {op, text, old_line, new_line} per line, without line endings
+fn line_changes(old: &str, new: &str) -> QValue {
+    let diff = TextDiff::from_lines(old, new);
+    let changes = diff
+        .iter_all_changes()
+        .map(|change| {
+            let text = change.value();
+            let text = text.strip_suffix('\n').map(|t| t.strip_suffix('\r').unwrap_or(t)).unwrap_or(text);
+            let mut map = IndexMap::new();
+            map.insert("op".to_string(), str_value(op_name(change.tag())));
+            map.insert("text".to_string(), str_value(text));
+            map.insert("old_line".to_string(), line_number(change.old_index()));
+            map.insert("new_line".to_string(), line_number(change.new_index()));
+            QValue::Dict(Box::new(QDict::new(map)))
+        })
+        .collect();
+    QValue::Array(QArray::new(changes))
+}
+
+/// {op, text} per run of words or characters with the same op
+fn inline_changes<'a>(diff: TextDiff<'a, 'a, 'a, str>) -> QValue {
+    let mut runs: Vec<(ChangeTag, String)> = Vec::new();
+    for change in diff.iter_all_changes() {
+        match runs.last_mut() {
+            Some((tag, text)) if *tag == change.tag() => text.push_str(change.value()),
+            _ => runs.push((change.tag(), change.value().to_string())),
+        }
+    }
+    let changes = runs
+        .into_iter()
+        .map(|(tag, text)| {
+            let mut map = IndexMap::new();
+            map.insert("op".to_string(), str_value(op_name(tag)));
+            map.insert("text".to_string(), str_value(&text));
+            QValue::Dict(Box::new(QDict::new(map)))
+        })
+        .collect();
+    QValue::Array(QArray::new(changes))
+}
+
+fn count_conflicts(text: &str) -> i64 {
+    text.lines().filter(|line| line.starts_with("<<<<<<<")).count() as i64
+}
+
+pub fn call_diff_function(func_name: &str, mut args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let options = take_options(&mut args);
+    match func_name {
+        "diff.lines" => {
+            let texts = text_args(func_name, &args, &["old", "new"])?;
+            Ok(line_changes(&texts[0], &texts[1]))
+        }
+        "diff.words" => {
+            let texts = text_args(func_name, &args, &["old", "new"])?;
+            Ok(inline_changes(TextDiff::from_words(texts[0].as_str(), texts[1].as_str())))
+        }
+        "diff.chars" => {
+            let texts = text_args(func_name, &args, &["old", "new"])?;
+            Ok(inline_changes(TextDiff::from_chars(texts[0].as_str(), texts[1].as_str())))
+        }
+        "diff.unified" => {
+            // unified(old, new, context: 3, from_file: "a", to_file: "b", color: false) - "" when equal
+            let texts = text_args(func_name, &args, &["old", "new"])?;
+            if texts[0] == texts[1] {
+                return Ok(str_value(""));
+            }
+            let context = match option(&options, "context") {
+                None => DEFAULT_CONTEXT,
+                Some(QValue::Int(n)) if n.value >= 0 => n.value as usize,
+                Some(other) => return value_err!("diff.unified context must be an Int of at least 0, got {}", other.as_str()),
+            };
+            let from = option(&options, "from_file").map(|v| v.as_str()).unwrap_or_else(|| "a".to_string());
+            let to = option(&options, "to_file").map(|v| v.as_str()).unwrap_or_else(|| "b".to_string());
+            let patch = DiffOptions::new()
+                .set_context_len(context)
+                .set_original_filename(from)
+                .set_modified_filename(to)
+                .create_patch(&texts[0], &texts[1]);
+            let mut formatter = PatchFormatter::new();
+            if option(&options, "color").is_some_and(|v| v.as_bool()) {
+                formatter = formatter.with_color();
+            }
+            let text = formatter.fmt_patch(&patch).to_string();
+            Ok(str_value(&text))
+        }
+        "diff.apply" => {
+            // apply(text, patch, reverse: false)
+            let texts = text_args(func_name, &args, &["text", "patch"])?;
+            // unified() returns "" for equal texts, which applies as a no-op
+            if texts[1].is_empty() {
+                return Ok(str_value(&texts[0]));
+            }
+            let patch = match Patch::from_str(&texts[1]) {
+                Ok(patch) if !patch.hunks().is_empty() => patch,
+                Ok(_) => return value_err!("diff.apply: invalid patch: no hunks found"),
+                Err(e) => return value_err!("diff.apply: invalid patch: {}", e),
+            };
+            let patch = if option(&options, "reverse").is_some_and(|v| v.as_bool()) { patch.reverse() } else { patch };
+            match diffy::apply(&texts[0], &patch) {
+                Ok(result) => Ok(str_value(&result)),
+                Err(e) => value_err!("diff.apply: patch does not apply: {}", e),
+            }
+        }
+        "diff.merge" => {
+            // merge(base, ours, theirs, style: "merge") -> {text, conflicts}
+            let texts = text_args(func_name, &args, &["base", "ours", "theirs"])?;
+            let style = match option(&options, "style").map(|v| v.as_str()).as_deref() {
+                None | Some("merge") => ConflictStyle::Merge,
+                Some("diff3") => ConflictStyle::Diff3,
+                Some(other) => return value_err!("diff.merge style must be \"merge\" or \"diff3\", got {}", other),
+            };
+            let (text, conflicts) = match MergeOptions::new().set_conflict_style(style).merge(&texts[0], &texts[1], &texts[2]) {
+                Ok(text) => (text, 0),
+                Err(text) => {
+                    let conflicts = count_conflicts(&text);
+                    (text, conflicts)
+                }
+            };
+            let mut map = IndexMap::new();
+            map.insert("text".to_string(), str_value(&text));
+            map.insert("conflicts".to_string(), QValue::Int(QInt::new(conflicts)));
+            Ok(QValue::Dict(Box::new(QDict::new(map))))
+        }
+        _ => attr_err!("Unknown diff function: {}", func_name),
+    }
+}
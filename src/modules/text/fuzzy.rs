@@ -0,0 +1,205 @@
+// std/text/fuzzy - string distances and fuzzy matching
+//
+// Edit distances and Jaro-Winkler come from `strsim`, counting Unicode
+// scalar values rather than bytes. Trigram similarity follows PostgreSQL's
+// pg_trgm: lowercase words padded with two spaces in front and one behind,
+// compared as sets. best_match() and matches() rank candidates by one of the
+// similarity scores, all of which run from 0.0 (nothing alike) to 1.0 (equal).
+
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::control_flow::EvalError;
+use crate::modules::args::{option, str_arg, take_options};
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+const DEFAULT_THRESHOLD: f64 = 0.6;
+const DEFAULT_LIMIT: usize = 5;
+
+pub fn create_fuzzy_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("levenshtein".to_string(), create_fn("fuzzy", "levenshtein"));
+    members.insert("similarity".to_string(), create_fn("fuzzy", "similarity"));
+    members.insert("jaro".to_string(), create_fn("fuzzy", "jaro"));
+    members.insert("jaro_winkler".to_string(), create_fn("fuzzy", "jaro_winkler"));
+    members.insert("trigram".to_string(), create_fn("fuzzy", "trigram"));
+    members.insert("trigrams".to_string(), create_fn("fuzzy", "trigrams"));
+    members.insert("best_match".to_string(), create_fn("fuzzy", "best_match"));
+    members.insert("matches".to_string(), create_fn("fuzzy", "matches"));
+
+    QValue::Module(Box::new(QModule::new("fuzzy".to_string(), members)))
+}
+
+#[derive(Clone, Copy)]
+enum Scorer {
+    JaroWinkler,
+    Levenshtein,
+    Trigram,
+}
+
+impl Scorer {
+    fn score(self, a: &str, b: &str) -> f64 {
+        match self {
+            Scorer::JaroWinkler => strsim::jaro_winkler(a, b),
+            Scorer::Levenshtein => strsim::normalized_levenshtein(a, b),
+            Scorer::Trigram => trigram_similarity(a, b),
+        }
+    }
+}
+
+fn pair_args(func_name: &str, args: &[QValue]) -> Result<(String, String), String> {
+    if args.len() != 2 {
+        return arg_err!("{} expects 2 arguments (a, b), got {}", func_name, args.len());
+    }
+    Ok((str_arg(func_name, "a", &args[0])?, str_arg(func_name, "b", &args[1])?))
+}
+
+/// pg_trgm trigrams: each alphanumeric word, lowercased, as "  word "
+fn trigram_set(text: &str) -> HashSet<String> {
+    let mut set = HashSet::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let padded: Vec<char> = format!("  {} ", word.to_lowercase()).chars().collect();
+        for window in padded.windows(3) {
+            set.insert(window.iter().collect());
+        }
+    }
+    set
+}
+
+fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let (a_set, b_set) = (trigram_set(a), trigram_set(b));
+    let union = a_set.union(&b_set).count();
+    if union == 0 {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+    a_set.intersection(&b_set).count() as f64 / union as f64
+}
+
+fn scorer_arg(func_name: &str, options: &Option<Box<QDict>>) -> Result<Scorer, String> {
+    match option(options, "scorer").map(|v| v.as_str()).as_deref() {
+        None | Some("jaro_winkler") => Ok(Scorer::JaroWinkler),
+        Some("levenshtein") => Ok(Scorer::Levenshtein),
+        Some("trigram") => Ok(Scorer::Trigram),
+        Some(other) => value_err!("{} scorer must be \"jaro_winkler\", \"levenshtein\" or \"trigram\", got {}", func_name, other),
+    }
+}
+
+fn threshold_arg(func_name: &str, options: &Option<Box<QDict>>) -> Result<f64, String> {
+    let threshold = match option(options, "threshold") {
+        None => return Ok(DEFAULT_THRESHOLD),
+        Some(QValue::Int(n)) => n.value as f64,
+        Some(QValue::Float(f)) => f.value,
+        Some(other) => return type_err!("{} threshold must be a number, got {}", func_name, other.as_obj().cls()),
+    };
+    if !(0.0..=1.0).contains(&threshold) {
+        return value_err!("{} threshold must be from 0.0 to 1.0, got {}", func_name, threshold);
+    }
+    Ok(threshold)
+}
+
+/// (index, candidate, score) for candidates scoring at least `threshold`,
+/// best first; equal scores keep the candidates' order
+fn ranked(func_name: &str, args: &[QValue], options: &Option<Box<QDict>>) -> Result<Vec<(usize, String, f64)>, String> {
+    if args.len() != 2 {
+        return arg_err!("{} expects 2 arguments (query, candidates), got {}", func_name, args.len());
+    }
+    let query = str_arg(func_name, "query", &args[0])?;
+    let QValue::Array(candidates) = &args[1] else {
+        return type_err!("{} candidates must be an Array of Str, got {}", func_name, args[1].as_obj().cls());
+    };
+    let scorer = scorer_arg(func_name, options)?;
+    let threshold = threshold_arg(func_name, options)?;
+    let ignore_case = option(options, "ignore_case").is_none_or(|v| v.as_bool());
+    let query = if ignore_case { query.to_lowercase() } else { query };
+
+    let mut results = Vec::new();
+    for (index, candidate) in candidates.elements.borrow().iter().enumerate() {
+        let candidate = str_arg(func_name, "candidates", candidate)?;
+        let score = if ignore_case {
+            scorer.score(&query, &candidate.to_lowercase())
+        } else {
+            scorer.score(&query, &candidate)
+        };
+        if score >= threshold {
+            results.push((index, candidate, score));
+        }
+    }
+    results.sort_by(|a, b| b.2.total_cmp(&a.2));
+    Ok(results)
+}
+
+fn float_value(value: f64) -> QValue {
+    QValue::Float(QFloat::new(value))
+}
+
+pub fn call_fuzzy_function(func_name: &str, mut args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let options = take_options(&mut args);
+    match func_name {
+        "fuzzy.levenshtein" => {
+            // levenshtein(a, b, transpositions: false) - with transpositions, a swap of
+            // neighbouring characters is one edit (optimal string alignment)
+            let (a, b) = pair_args(func_name, &args)?;
+            let distance = if option(&options, "transpositions").is_some_and(|v| v.as_bool()) {
+                strsim::osa_distance(&a, &b)
+            } else {
+                strsim::levenshtein(&a, &b)
+            };
+            Ok(QValue::Int(QInt::new(distance as i64)))
+        }
+        "fuzzy.similarity" => {
+            let (a, b) = pair_args(func_name, &args)?;
+            Ok(float_value(strsim::normalized_levenshtein(&a, &b)))
+        }
+        "fuzzy.jaro" => {
+            let (a, b) = pair_args(func_name, &args)?;
+            Ok(float_value(strsim::jaro(&a, &b)))
+        }
+        "fuzzy.jaro_winkler" => {
+            let (a, b) = pair_args(func_name, &args)?;
+            Ok(float_value(strsim::jaro_winkler(&a, &b)))
+        }
+        "fuzzy.trigram" => {
+            let (a, b) = pair_args(func_name, &args)?;
+            Ok(float_value(trigram_similarity(&a, &b)))
+        }
+        "fuzzy.trigrams" => {
+            if args.len() != 1 {
+                return arg_err!("fuzzy.trigrams expects 1 argument (text), got {}", args.len());
+            }
+            let mut trigrams: Vec<String> = trigram_set(&str_arg(func_name, "text", &args[0])?).into_iter().collect();
+            trigrams.sort();
+            Ok(QValue::Array(QArray::new(trigrams.into_iter().map(|t| QValue::Str(QString::new(t))).collect())))
+        }
+        "fuzzy.best_match" => {
+            // best_match(query, candidates, scorer:, threshold: 0.6, ignore_case: true) -> Str or nil
+            match ranked(func_name, &args, &options)?.into_iter().next() {
+                Some((_, candidate, _)) => Ok(QValue::Str(QString::new(candidate))),
+                None => Ok(QValue::Nil(QNil)),
+            }
+        }
+        "fuzzy.matches" => {
+            // matches(query, candidates, limit: 5, ...) -> [{value, score, index}], best first
+            let limit = match option(&options, "limit") {
+                None => DEFAULT_LIMIT,
+                Some(QValue::Int(n)) if n.value >= 0 => n.value as usize,
+                Some(other) => return value_err!("fuzzy.matches limit must be an Int of at least 0, got {}", other.as_str()),
+            };
+            let results = ranked(func_name, &args, &options)?
+                .into_iter()
+                .take(limit)
+                .map(|(index, candidate, score)| {
+                    let mut map = IndexMap::new();
+                    map.insert("value".to_string(), QValue::Str(QString::new(candidate)));
+                    map.insert("score".to_string(), float_value(score));
+                    map.insert("index".to_string(), QValue::Int(QInt::new(index as i64)));
+                    QValue::Dict(Box::new(QDict::new(map)))
+                })
+                .collect();
+            Ok(QValue::Array(QArray::new(results)))
+        }
+        _ => attr_err!("Unknown fuzzy function: {}", func_name),
+    }
+}
@@ -0,0 +1,9 @@
+// Text processing modules: std/text/diff, std/text/fuzzy and std/text/shlex
+
+pub mod diff;
+pub mod fuzzy;
+pub mod shlex;
+
+pub use diff::{create_diff_module, call_diff_function};
+pub use fuzzy::{create_fuzzy_module, call_fuzzy_function};
+pub use shlex::{create_shlex_module, call_shlex_function};
@@ -0,0 +1,78 @@
+// std/text/shlex - POSIX shell word splitting and quoting
+//
+// split() follows sh rules: whitespace separates words, single quotes are
+// literal, double quotes allow \$ \` \" \\ escapes, and a backslash outside
+// quotes escapes the next character. quote() and join() produce text that
+// split() (and sh) reads back as the same words, so a command line built
+// from untrusted input can't grow extra arguments or run other commands.
+
+use std::collections::HashMap;
+
+use crate::control_flow::EvalError;
+use crate::modules::args::str_arg;
+use crate::types::*;
+use crate::{arg_err, attr_err, type_err, value_err};
+
+pub fn create_shlex_module() -> QValue {
+    let mut members = HashMap::new();
+
+    members.insert("split".to_string(), create_fn("shlex", "split"));
+    members.insert("quote".to_string(), create_fn("shlex", "quote"));
+    members.insert("join".to_string(), create_fn("shlex", "join"));
+
+    QValue::Module(Box::new(QModule::new("shlex".to_string(), members)))
+}
+
+fn str_value(text: &str) -> QValue {
+    QValue::Str(QString::new(text.to_string()))
+}
+
+/// Quote one word, raising ValueErr for NUL, which no shell argument can hold
+fn quote_word(func_name: &str, word: &str) -> Result<String, String> {
+    match shlex::try_quote(word) {
+        Ok(quoted) => Ok(quoted.into_owned()),
+        Err(e) => value_err!("{}: {}", func_name, e),
+    }
+}
+
+pub fn call_shlex_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    match func_name {
+        "shlex.split" => {
+            // split(text) -> [Str]; a word starting with # begins a comment to the end of the line
+            if args.len() != 1 {
+                return arg_err!("shlex.split expects 1 argument (text), got {}", args.len());
+            }
+            let text = str_arg(func_name, "text", &args[0])?;
+            let mut lexer = shlex::Shlex::new(&text);
+            let words: Vec<QValue> = lexer.by_ref().map(|word| str_value(&word)).collect();
+            if lexer.had_error {
+                return value_err!("shlex.split: unterminated quote or trailing backslash on line {}", lexer.line_no);
+            }
+            Ok(QValue::Array(QArray::new(words)))
+        }
+        "shlex.quote" => {
+            if args.len() != 1 {
+                return arg_err!("shlex.quote expects 1 argument (word), got {}", args.len());
+            }
+            let word = str_arg(func_name, "word", &args[0])?;
+            Ok(str_value(&quote_word(func_name, &word)?))
+        }
+        "shlex.join" => {
+            // join(words) - quote each word and separate them with spaces
+            if args.len() != 1 {
+                return arg_err!("shlex.join expects 1 argument (words), got {}", args.len());
+            }
+            let QValue::Array(words) = &args[0] else {
+                return type_err!("shlex.join expects an Array of Str, got {}", args[0].as_obj().cls());
+            };
+            let quoted = words
+                .elements
+                .borrow()
+                .iter()
+                .map(|word| quote_word(func_name, &str_arg(func_name, "words", word)?))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(str_value(&quoted.join(" ")))
+        }
+        _ => attr_err!("Unknown shlex function: {}", func_name),
+    }
+}
@@ -3,10 +3,13 @@ use crate::control_flow::EvalError;
 // Provides comprehensive date and time handling using the jiff library
 
 use crate::types::{QObj, QValue, QInt, QFloat, QString, QBool, QNil, next_object_id};
-use crate::{arg_err, attr_err};
+use crate::{arg_err, attr_err, type_err, value_err};
 use jiff::{Timestamp as JiffTimestamp, Zoned as JiffZoned, civil::{Date as JiffDate, Time as JiffTime}, Span as JiffSpan, ToSpan, tz::TimeZone};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use crate::types::*;
+use crate::modules::locale::{Locale, localize_strftime, lookup_locale};
 
 // =============================================================================
 // Type Definitions
@@ -35,18 +38,18 @@ impl QTimestamp {
         }
 
         match method_name {
-            "to_zoned" => {
+            "to_zoned" | "in_tz" => {
                 if args.len() != 1 {
-                    return arg_err!("to_zoned expects 1 argument (timezone), got {}", args.len());
+                    return arg_err!("{} expects 1 argument (timezone), got {}", method_name, args.len());
                 }
                 match &args[0] {
                     QValue::Str(tz) => {
                         let zone = TimeZone::get(&tz.value)
-                            .map_err(|e| format!("Invalid timezone '{}': {}", tz.value, e))?;
+                            .map_err(|e| format!("ValueErr: Invalid timezone '{}': {}", tz.value, e))?;
                         let zoned = self.timestamp.to_zoned(zone);
                         Ok(QValue::Zoned(QZoned::new(zoned)))
                     }
-                    _ => Err("to_zoned expects a string timezone name".into()),
+                    _ => Err(format!("{} expects a string timezone name", method_name).into()),
                 }
             }
             "as_seconds" => {
@@ -86,19 +89,8 @@ impl QTimestamp {
                     _ => Err("since expects a Timestamp object".into()),
                 }
             }
-            "format" => {
-                if args.len() != 1 {
-                    return arg_err!("format expects 1 argument (pattern), got {}", args.len());
-                }
-                match &args[0] {
-                    QValue::Str(pattern) => {
-                        // Convert to UTC for formatting (timestamps are UTC by default)
-                        let zoned = self.timestamp.to_zoned(jiff::tz::TimeZone::UTC);
-                        let result = zoned.strftime(pattern.value.as_ref()).to_string();
-                        Ok(QValue::Str(QString::new(result)))
-                    }
-                    _ => Err("format expects a string pattern".into()),
-                }
+            "format" | "to_iso" | "to_rfc3339" | "to_rfc2822" | "to_http_date" => {
+                Ok(format_method(method_name, &args, Temporal::Timestamp(self.timestamp))?)
             }
             "_id" => {
                 if !args.is_empty() {
@@ -245,40 +237,53 @@ impl QZoned {
                 let quarter = ((self.zoned.month() - 1) / 3) + 1;
                 Ok(QValue::Int(QInt::new(quarter as i64)))
             }
-
-            // Formatting
-            "format" => {
-                if args.len() != 1 {
-                    return arg_err!("format expects 1 argument (pattern), got {}", args.len());
+            "offset" => {
+                if !args.is_empty() {
+                    return arg_err!("offset expects 0 arguments, got {}", args.len());
                 }
-                match &args[0] {
-                    QValue::Str(pattern) => {
-                        let result = self.zoned.strftime(pattern.value.as_ref()).to_string();
-                        Ok(QValue::Str(QString::new(result)))
-                    }
-                    _ => Err("format expects a string pattern".into()),
+                // UTC offset as +HH:MM (or +HH:MM:SS for historical zones)
+                let seconds = self.zoned.offset().seconds();
+                let sign = if seconds < 0 { '-' } else { '+' };
+                let seconds = seconds.unsigned_abs();
+                let mut offset = format!("{}{:02}:{:02}", sign, seconds / 3600, seconds % 3600 / 60);
+                if !seconds.is_multiple_of(60) {
+                    offset.push_str(&format!(":{:02}", seconds % 60));
                 }
+                Ok(QValue::Str(QString::new(offset)))
             }
-            "to_rfc3339" => {
+            "is_dst" => {
                 if !args.is_empty() {
-                    return arg_err!("to_rfc3339 expects 0 arguments, got {}", args.len());
+                    return arg_err!("is_dst expects 0 arguments, got {}", args.len());
                 }
-                Ok(QValue::Str(QString::new(format!("{:?}", self.zoned))))
+                let (_, dst, _) = self.zoned.time_zone().to_offset(self.zoned.timestamp());
+                Ok(QValue::Bool(QBool::new(dst.is_dst())))
+            }
+            "abbreviation" => {
+                if !args.is_empty() {
+                    return arg_err!("abbreviation expects 0 arguments, got {}", args.len());
+                }
+                let (_, _, abbrev) = self.zoned.time_zone().to_offset(self.zoned.timestamp());
+                Ok(QValue::Str(QString::new(abbrev.to_string())))
+            }
+
+            // Formatting
+            "format" | "to_iso" | "to_rfc3339" | "to_rfc2822" | "to_http_date" => {
+                Ok(format_method(method_name, &args, Temporal::Zoned(&self.zoned))?)
             }
 
             // Timezone conversion
-            "to_timezone" => {
+            "to_timezone" | "in_tz" => {
                 if args.len() != 1 {
-                    return arg_err!("to_timezone expects 1 argument (timezone), got {}", args.len());
+                    return arg_err!("{} expects 1 argument (timezone), got {}", method_name, args.len());
                 }
                 match &args[0] {
                     QValue::Str(tz) => {
                         let zone = TimeZone::get(&tz.value)
-                            .map_err(|e| format!("Invalid timezone '{}': {}", tz.value, e))?;
+                            .map_err(|e| format!("ValueErr: Invalid timezone '{}': {}", tz.value, e))?;
                         let new_zoned = self.zoned.with_time_zone(zone);
                         Ok(QValue::Zoned(QZoned::new(new_zoned)))
                     }
-                    _ => Err("to_timezone expects a string timezone name".into()),
+                    _ => Err(format!("{} expects a string timezone name", method_name).into()),
                 }
             }
             "to_utc" => {
@@ -290,201 +295,30 @@ impl QZoned {
                 Ok(QValue::Zoned(QZoned::new(new_zoned)))
             }
 
-            // Arithmetic
-            "add_years" => {
-                if args.len() != 1 {
-                    return arg_err!("add_years expects 1 argument, got {}", args.len());
-                }
-                let years = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_years expects a number".into()),
-                };
-                let span = years.years();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("add_years error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "add_months" => {
-                if args.len() != 1 {
-                    return arg_err!("add_months expects 1 argument, got {}", args.len());
-                }
-                let months = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_months expects a number".into()),
-                };
-                let span = months.months();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("add_months error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "add_days" => {
-                if args.len() != 1 {
-                    return arg_err!("add_days expects 1 argument, got {}", args.len());
-                }
-                let days = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_days expects a number".into()),
-                };
-                let span = days.days();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("add_days error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "add_hours" => {
-                if args.len() != 1 {
-                    return arg_err!("add_hours expects 1 argument, got {}", args.len());
-                }
-                let hours = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_hours expects a number".into()),
-                };
-                let span = hours.hours();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("add_hours error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "add_minutes" => {
+            // Arithmetic. Calendar units (years, months, weeks, days) move the
+            // local date and keep the wall-clock time across DST changes; hours
+            // and smaller add exact elapsed time.
+            "add_years" | "add_months" | "add_weeks" | "add_days" | "add_hours" | "add_minutes" | "add_seconds"
+            | "subtract_years" | "subtract_months" | "subtract_weeks" | "subtract_days"
+            | "subtract_hours" | "subtract_minutes" | "subtract_seconds" => {
                 if args.len() != 1 {
-                    return arg_err!("add_minutes expects 1 argument, got {}", args.len());
+                    return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
                 }
-                let minutes = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_minutes expects a number".into()),
-                };
-                let span = minutes.minutes();
+                let (op, unit) = method_name.split_once('_').unwrap();
+                let span = span_from_units(method_name, &[(unit, &args[0])])?;
+                let span = if op == "subtract" { span.negate() } else { span };
                 let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("add_minutes error: {}", e))?;
+                    .map_err(|e| format!("{} error: {}", method_name, e))?;
                 Ok(QValue::Zoned(QZoned::new(new_zoned)))
             }
-            "add_seconds" => {
-                if args.len() != 1 {
-                    return arg_err!("add_seconds expects 1 argument, got {}", args.len());
-                }
-                let seconds = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_seconds expects a number".into()),
-                };
-                let span = seconds.seconds();
+            "add" | "subtract" => {
+                // add(span) or add(days: 1, hours: 2)
+                let span = span_arg(method_name, &args)?;
+                let span = if method_name == "subtract" { span.negate() } else { span };
                 let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("add_seconds error: {}", e))?;
+                    .map_err(|e| format!("{} error: {}", method_name, e))?;
                 Ok(QValue::Zoned(QZoned::new(new_zoned)))
             }
-            "add" => {
-                if args.len() != 1 {
-                    return arg_err!("add expects 1 argument (span), got {}", args.len());
-                }
-                match &args[0] {
-                    QValue::Span(span) => {
-                        let new_zoned = self.zoned.checked_add(span.span)
-                            .map_err(|e| format!("add error: {}", e))?;
-                        Ok(QValue::Zoned(QZoned::new(new_zoned)))
-                    }
-                    _ => Err("add expects a Span object".into()),
-                }
-            }
-            "subtract_years" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract_years expects 1 argument, got {}", args.len());
-                }
-                let years = match &args[0] {
-                    QValue::Int(n) => -n.value,
-                    QValue::Float(n) => -(n.value as i64),
-                    _ => return Err("subtract_years expects a number".into()),
-                };
-                let span = years.years();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("subtract_years error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "subtract_months" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract_months expects 1 argument, got {}", args.len());
-                }
-                let months = match &args[0] {
-                    QValue::Int(n) => -n.value,
-                    QValue::Float(n) => -(n.value as i64),
-                    _ => return Err("subtract_months expects a number".into()),
-                };
-                let span = months.months();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("subtract_months error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "subtract_days" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract_days expects 1 argument, got {}", args.len());
-                }
-                let days = match &args[0] {
-                    QValue::Int(n) => -n.value,
-                    QValue::Float(n) => -(n.value as i64),
-                    _ => return Err("subtract_days expects a number".into()),
-                };
-                let span = days.days();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("subtract_days error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "subtract_hours" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract_hours expects 1 argument, got {}", args.len());
-                }
-                let hours = match &args[0] {
-                    QValue::Int(n) => -n.value,
-                    QValue::Float(n) => -(n.value as i64),
-                    _ => return Err("subtract_hours expects a number".into()),
-                };
-                let span = hours.hours();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("subtract_hours error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "subtract_minutes" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract_minutes expects 1 argument, got {}", args.len());
-                }
-                let minutes = match &args[0] {
-                    QValue::Int(n) => -n.value,
-                    QValue::Float(n) => -(n.value as i64),
-                    _ => return Err("subtract_minutes expects a number".into()),
-                };
-                let span = minutes.minutes();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("subtract_minutes error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "subtract_seconds" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract_seconds expects 1 argument, got {}", args.len());
-                }
-                let seconds = match &args[0] {
-                    QValue::Int(n) => -n.value,
-                    QValue::Float(n) => -(n.value as i64),
-                    _ => return Err("subtract_seconds expects a number".into()),
-                };
-                let span = seconds.seconds();
-                let new_zoned = self.zoned.checked_add(span)
-                    .map_err(|e| format!("subtract_seconds error: {}", e))?;
-                Ok(QValue::Zoned(QZoned::new(new_zoned)))
-            }
-            "subtract" => {
-                if args.len() != 1 {
-                    return arg_err!("subtract expects 1 argument (span), got {}", args.len());
-                }
-                match &args[0] {
-                    QValue::Span(span) => {
-                        let new_zoned = self.zoned.checked_sub(span.span)
-                            .map_err(|e| format!("subtract error: {}", e))?;
-                        Ok(QValue::Zoned(QZoned::new(new_zoned)))
-                    }
-                    _ => Err("subtract expects a Span object".into()),
-                }
-            }
             "since" => {
                 if args.len() != 1 {
                     return arg_err!("since expects 1 argument (other), got {}", args.len());
@@ -763,48 +597,46 @@ impl QDate {
                 Ok(QValue::Int(QInt::new(quarter as i64)))
             }
 
+            // Formatting
+            "format" | "to_iso" => {
+                Ok(format_method(method_name, &args, Temporal::Date(self.date))?)
+            }
+
             // Arithmetic
-            "add_days" => {
+            "add_years" | "add_months" | "add_weeks" | "add_days"
+            | "subtract_years" | "subtract_months" | "subtract_weeks" | "subtract_days" => {
                 if args.len() != 1 {
-                    return arg_err!("add_days expects 1 argument, got {}", args.len());
+                    return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
                 }
-                let days = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_days expects a number".into()),
-                };
-                let span = days.days();
+                let (op, unit) = method_name.split_once('_').unwrap();
+                let span = span_from_units(method_name, &[(unit, &args[0])])?;
+                let span = if op == "subtract" { span.negate() } else { span };
                 let new_date = self.date.checked_add(span)
-                    .map_err(|e| format!("add_days error: {}", e))?;
+                    .map_err(|e| format!("{} error: {}", method_name, e))?;
                 Ok(QValue::Date(QDate::new(new_date)))
             }
-            "add_months" => {
-                if args.len() != 1 {
-                    return arg_err!("add_months expects 1 argument, got {}", args.len());
-                }
-                let months = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_months expects a number".into()),
-                };
-                let span = months.months();
+            "add" | "subtract" => {
+                let span = span_arg(method_name, &args)?;
+                let span = if method_name == "subtract" { span.negate() } else { span };
                 let new_date = self.date.checked_add(span)
-                    .map_err(|e| format!("add_months error: {}", e))?;
+                    .map_err(|e| format!("{} error: {}", method_name, e))?;
                 Ok(QValue::Date(QDate::new(new_date)))
             }
-            "add_years" => {
-                if args.len() != 1 {
-                    return arg_err!("add_years expects 1 argument, got {}", args.len());
+            "range" => {
+                // range(end, step = 1 day): dates from self through end, like time.range
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("range expects 1 or 2 arguments (end, step?), got {}", args.len());
                 }
-                let years = match &args[0] {
-                    QValue::Int(n) => n.value,
-                    QValue::Float(n) => n.value as i64,
-                    _ => return Err("add_years expects a number".into()),
+                let end = match &args[0] {
+                    QValue::Date(d) => d.date,
+                    other => return type_err!("range expects a Date end, got {}", other.q_type()),
                 };
-                let span = years.years();
-                let new_date = self.date.checked_add(span)
-                    .map_err(|e| format!("add_years error: {}", e))?;
-                Ok(QValue::Date(QDate::new(new_date)))
+                let step = match args.get(1) {
+                    None => 1.days(),
+                    Some(value) => step_span("range", value)?,
+                };
+                let dates = date_steps(self.date, end, step, true)?;
+                Ok(QValue::Array(QArray::new(dates)))
             }
 
             // Duration calculation
@@ -836,7 +668,7 @@ impl QDate {
                         };
 
                         let zone = TimeZone::get(&tz_name)
-                            .map_err(|e| format!("Invalid timezone '{}': {}", tz_name, e))?;
+                            .map_err(|e| format!("ValueErr: Invalid timezone '{}': {}", tz_name, e))?;
 
                         let datetime = self.date.at(
                             time_val.time.hour(),
@@ -979,6 +811,9 @@ impl QTime {
             }
 
             // Duration calculation
+            "format" | "to_iso" => {
+                Ok(format_method(method_name, &args, Temporal::Time(self.time))?)
+            }
             "since" => {
                 if args.len() != 1 {
                     return arg_err!("since expects 1 argument (other time), got {}", args.len());
@@ -1058,6 +893,11 @@ impl QDateRange {
         }
     }
 
+    /// Every date in the range, start and end included (used by `for`)
+    pub fn days(&self) -> Result<Vec<QValue>, String> {
+        date_steps(self.start, self.end, 1.days(), true)
+    }
+
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         // Try QObj trait methods first
         use crate::types::try_call_qobj_method;
@@ -1103,6 +943,26 @@ impl QDateRange {
                     _ => Err("overlaps expects a DateRange object".into()),
                 }
             }
+            "dates" => {
+                // All dates from start through end (inclusive), every `step`
+                if args.len() > 1 {
+                    return arg_err!("dates expects 0 or 1 arguments (step?), got {}", args.len());
+                }
+                let dates = match args.first() {
+                    None => self.days()?,
+                    Some(value) => date_steps(self.start, self.end, step_span("dates", value)?, true)?,
+                };
+                Ok(QValue::Array(QArray::new(dates)))
+            }
+            "len" => {
+                if !args.is_empty() {
+                    return arg_err!("len expects 0 arguments, got {}", args.len());
+                }
+                let days = self.end.since((jiff::Unit::Day, self.start))
+                    .map_err(|e| format!("len error: {}", e))?
+                    .get_days() as i64 + 1;
+                Ok(QValue::Int(QInt::new(days.max(0))))
+            }
             "duration" => {
                 if !args.is_empty() {
                     return arg_err!("duration expects 0 arguments, got {}", args.len());
@@ -1429,6 +1289,519 @@ impl QObj for QSpan {
     }
 }
 
+/// Span of whole seconds plus nanoseconds, for monotonic measurements
+fn span_from_nanos(nanos: i64) -> JiffSpan {
+    JiffSpan::new()
+        .seconds(nanos / 1_000_000_000)
+        .nanoseconds(nanos % 1_000_000_000)
+}
+
+/// Units accepted by time.span and the add/subtract methods, with their length
+/// in nanoseconds (0 for calendar units, whose length depends on the date)
+const SPAN_UNITS: &[(&str, i64)] = &[
+    ("years", 0),
+    ("months", 0),
+    ("weeks", 0),
+    ("days", 0),
+    ("hours", 3_600_000_000_000),
+    ("minutes", 60_000_000_000),
+    ("seconds", 1_000_000_000),
+    ("millis", 1_000_000),
+    ("micros", 1_000),
+    ("nanos", 1),
+];
+
+/// Build a Span from (unit, amount) pairs. Calendar units must be whole;
+/// fractional clock units are carried into nanoseconds, so 1.5 hours is
+/// exactly 90 minutes.
+fn span_from_units(func: &str, units: &[(&str, &QValue)]) -> Result<JiffSpan, String> {
+    let mut span = JiffSpan::new();
+    let mut extra_nanos: i64 = 0;
+    for (unit, value) in units {
+        let Some(&(_, unit_nanos)) = SPAN_UNITS.iter().find(|(name, _)| name == unit) else {
+            let names: Vec<&str> = SPAN_UNITS.iter().map(|(name, _)| *name).collect();
+            return arg_err!("{} got unknown unit '{}' (expected {})", func, unit, names.join(", "));
+        };
+        let whole = match value {
+            QValue::Int(n) => n.value,
+            QValue::Float(f) => {
+                if !f.value.is_finite() {
+                    return value_err!("{} expects a finite number of {}, got {}", func, unit, f.value);
+                }
+                let whole = f.value.trunc();
+                let fraction = f.value - whole;
+                if fraction != 0.0 {
+                    if unit_nanos == 0 {
+                        return value_err!("{} expects a whole number of {}, got {}", func, unit, f.value);
+                    }
+                    extra_nanos += (fraction * unit_nanos as f64).round() as i64;
+                }
+                whole as i64
+            }
+            other => return type_err!("{} expects a number of {}, got {}", func, unit, other.q_type()),
+        };
+        let updated = match *unit {
+            "years" => span.try_years(whole),
+            "months" => span.try_months(whole),
+            "weeks" => span.try_weeks(whole),
+            "days" => span.try_days(whole),
+            "hours" => span.try_hours(whole),
+            "minutes" => span.try_minutes(whole),
+            "seconds" => span.try_seconds(whole),
+            "millis" => span.try_milliseconds(whole),
+            "micros" => span.try_microseconds(whole),
+            _ => span.try_nanoseconds(whole),
+        };
+        span = updated.map_err(|e| format!("ValueErr: {}: {}", func, e))?;
+    }
+    // Spread the carried fractions over the clock units (2.25 hours -> 2h 15m)
+    let carried = [
+        ("minutes", 60_000_000_000),
+        ("seconds", 1_000_000_000),
+        ("millis", 1_000_000),
+        ("micros", 1_000),
+        ("nanos", 1),
+    ];
+    for (unit, unit_nanos) in carried {
+        let amount = extra_nanos / unit_nanos;
+        extra_nanos %= unit_nanos;
+        if amount == 0 {
+            continue;
+        }
+        let updated = match unit {
+            "minutes" => span.try_minutes(span.get_minutes() + amount),
+            "seconds" => span.try_seconds(span.get_seconds() + amount),
+            "millis" => span.try_milliseconds(span.get_milliseconds() + amount),
+            "micros" => span.try_microseconds(span.get_microseconds() + amount),
+            _ => span.try_nanoseconds(span.get_nanoseconds() + amount),
+        };
+        span = updated.map_err(|e| format!("ValueErr: {}: {}", func, e))?;
+    }
+    Ok(span)
+}
+
+/// The argument of add/subtract: a Span, or units as keywords (`days: 1`)
+fn span_arg(func: &str, args: &[QValue]) -> Result<JiffSpan, String> {
+    match args {
+        [QValue::Span(span)] => Ok(span.span),
+        [QValue::Dict(units)] => {
+            let units = units.map.borrow();
+            let pairs: Vec<(&str, &QValue)> = units.iter().map(|(k, v)| (k.as_str(), v)).collect();
+            span_from_units(func, &pairs)
+        }
+        [other] => type_err!("{} expects a Span or unit keywords (e.g. days: 1), got {}", func, other.q_type()),
+        _ => arg_err!("{} expects 1 argument (span), got {}", func, args.len()),
+    }
+}
+
+/// A date range step: an Int number of days or a Span
+fn step_span(func: &str, value: &QValue) -> Result<JiffSpan, String> {
+    let step = match value {
+        QValue::Int(n) => JiffSpan::new().try_days(n.value)
+            .map_err(|e| format!("ValueErr: {}: {}", func, e))?,
+        QValue::Span(span) => span.span,
+        other => return type_err!("{} expects an Int (days) or Span step, got {}", func, other.q_type()),
+    };
+    if step.is_zero() {
+        return value_err!("{} step must not be zero", func);
+    }
+    Ok(step)
+}
+
+/// Dates from `start` towards `end`. Each date is `start + n * step` rather
+/// than the previous date plus `step`, so monthly steps from Jan 31 give
+/// Feb 29, Mar 31, Apr 30 instead of drifting to the 29th.
+fn date_steps(start: JiffDate, end: JiffDate, step: JiffSpan, inclusive: bool) -> Result<Vec<QValue>, String> {
+    let forward = step.signum() > 0;
+    let mut dates = Vec::new();
+    let mut previous = None;
+    for n in 0i64.. {
+        let Ok(date) = step.checked_mul(n).and_then(|offset| start.checked_add(offset)) else {
+            break;
+        };
+        let within = if forward { date < end } else { date > end };
+        if !(within || (inclusive && date == end)) {
+            break;
+        }
+        if previous == Some(date) {
+            return value_err!("date step must be at least one day, got {}", step);
+        }
+        previous = Some(date);
+        dates.push(QValue::Date(QDate::new(date)));
+    }
+    Ok(dates)
+}
+
+/// A date/time value being formatted
+enum Temporal<'a> {
+    Timestamp(JiffTimestamp),
+    Zoned(&'a JiffZoned),
+    Date(JiffDate),
+    Time(JiffTime),
+}
+
+/// format(pattern, locale:) and the to_iso/to_rfc3339/to_rfc2822/to_http_date presets
+fn format_method(method: &str, args: &[QValue], value: Temporal) -> Result<QValue, String> {
+    let (pattern, locale) = match method {
+        "format" => match args {
+            [QValue::Str(pattern)] => (pattern.value.as_ref().clone(), None),
+            [QValue::Str(pattern), QValue::Str(tag)] => (pattern.value.as_ref().clone(), Some(lookup_locale(&tag.value)?)),
+            [QValue::Str(pattern), QValue::Dict(options)] => {
+                let locale = match options.get("locale") {
+                    None | Some(QValue::Nil(_)) => None,
+                    Some(QValue::Str(tag)) => Some(lookup_locale(&tag.value)?),
+                    Some(other) => return type_err!("format locale must be a Str, got {}", other.q_type()),
+                };
+                (pattern.value.as_ref().clone(), locale)
+            }
+            [QValue::Str(_), other] => return type_err!("format locale must be a Str, got {}", other.q_type()),
+            [other, ..] => return type_err!("format expects a Str pattern, got {}", other.q_type()),
+            _ => return arg_err!("format expects 1 or 2 arguments (pattern, locale?), got {}", args.len()),
+        },
+        _ => {
+            if !args.is_empty() {
+                return arg_err!("{} expects 0 arguments, got {}", method, args.len());
+            }
+            (method.trim_start_matches("to_").trim_end_matches("_date").to_string(), None)
+        }
+    };
+    let text = match format_preset(&pattern, &value) {
+        Some(result) => result?,
+        None => format_strftime(&pattern, &value, locale)?,
+    };
+    Ok(QValue::Str(QString::new(text)))
+}
+
+/// Format a Timestamp, Zoned, Date or Time value with a strftime pattern
+/// (used by std/locale)
+pub(crate) fn format_temporal(func: &str, value: &QValue, pattern: &str, locale: Option<&Locale>) -> Result<String, String> {
+    let value = match value {
+        QValue::Timestamp(ts) => Temporal::Timestamp(ts.timestamp),
+        QValue::Zoned(z) => Temporal::Zoned(&z.zoned),
+        QValue::Date(d) => Temporal::Date(d.date),
+        QValue::Time(t) => Temporal::Time(t.time),
+        other => return type_err!("{} expects a Timestamp, Zoned, Date or Time, got {}", func, other.q_type()),
+    };
+    format_strftime(pattern, &value, locale)
+}
+
+/// Render a named format ("iso", "rfc3339", "rfc2822", "http"), or None if
+/// `name` is a strftime pattern
+fn format_preset(name: &str, value: &Temporal) -> Option<Result<String, String>> {
+    use jiff::fmt::rfc2822::DateTimePrinter;
+
+    let timestamp = match value {
+        Temporal::Timestamp(ts) => Some(*ts),
+        Temporal::Zoned(z) => Some(z.timestamp()),
+        Temporal::Date(_) | Temporal::Time(_) => None,
+    };
+    let result = match name {
+        "iso" => Ok(match value {
+            Temporal::Timestamp(ts) => ts.to_string(),
+            Temporal::Zoned(z) => z.to_string(),
+            Temporal::Date(d) => d.to_string(),
+            Temporal::Time(t) => t.to_string(),
+        }),
+        "rfc3339" | "rfc2822" | "http" => {
+            let Some(ts) = timestamp else {
+                return Some(value_err!("'{}' format needs a date and time (Timestamp or Zoned)", name));
+            };
+            let printer = DateTimePrinter::new();
+            let printed = match (name, value) {
+                ("rfc3339", Temporal::Zoned(z)) => Ok(ts.display_with_offset(z.offset()).to_string()),
+                ("rfc3339", _) => Ok(ts.to_string()),
+                ("rfc2822", Temporal::Zoned(z)) => printer.zoned_to_string(z),
+                ("rfc2822", _) => printer.timestamp_to_string(&ts),
+                _ => printer.timestamp_to_rfc9110_string(&ts),
+            };
+            printed.map_err(|e| format!("ValueErr: {} format failed: {}", name, e))
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// strftime formatting. Directives the value can't supply (%H on a Date)
+/// raise ValueErr.
+fn format_strftime(pattern: &str, value: &Temporal, locale: Option<&Locale>) -> Result<String, String> {
+    use jiff::fmt::strtime;
+
+    // Timestamps are formatted in UTC
+    let utc = match value {
+        Temporal::Timestamp(ts) => Some(ts.to_zoned(TimeZone::UTC)),
+        _ => None,
+    };
+    let date = match value {
+        Temporal::Timestamp(_) => utc.as_ref().map(|z| z.date()),
+        Temporal::Zoned(z) => Some(z.date()),
+        Temporal::Date(d) => Some(*d),
+        Temporal::Time(_) => None,
+    };
+    let localized = match locale {
+        Some(locale) => {
+            let hour = match value {
+                Temporal::Timestamp(_) => utc.as_ref().map(|z| z.hour()),
+                Temporal::Zoned(z) => Some(z.hour()),
+                Temporal::Date(_) => None,
+                Temporal::Time(t) => Some(t.hour()),
+            };
+            Some(localize_strftime(pattern, locale, date, hour)?)
+        }
+        None => None,
+    };
+    let pattern = expand_strftime("format", localized.as_deref().unwrap_or(pattern), date)?;
+    let result = match (value, &utc) {
+        (_, Some(z)) => strtime::format(&pattern, z),
+        (Temporal::Zoned(z), _) => strtime::format(&pattern, *z),
+        (Temporal::Date(d), _) => strtime::format(&pattern, *d),
+        (Temporal::Time(t), _) => strtime::format(&pattern, *t),
+        (Temporal::Timestamp(ts), None) => strtime::format(&pattern, *ts),
+    };
+    result.map_err(|e| format!("ValueErr: {}", e))
+}
+
+/// Rewrite strftime directives jiff lacks: %c, %x, %X and %r expand to their
+/// C-locale forms and %V (ISO 8601 week number) is filled in from `date`
+fn expand_strftime(func: &str, pattern: &str, date: Option<JiffDate>) -> Result<String, String> {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('c') => out.push_str("%a %b %e %H:%M:%S %Y"),
+            Some('x') => out.push_str("%m/%d/%y"),
+            Some('X') => out.push_str("%H:%M:%S"),
+            Some('r') => out.push_str("%I:%M:%S %p"),
+            Some('V') => match date {
+                Some(d) => out.push_str(&format!("{:02}", d.iso_week_date().week())),
+                None => return value_err!("{}: %V (ISO week number) needs a date", func),
+            },
+            Some(next) => {
+                out.push('%');
+                out.push(next);
+            }
+            None => out.push('%'),
+        }
+    }
+    Ok(out)
+}
+
+/// time.parse with an explicit format: a preset name or a strptime pattern.
+/// Values with an offset or zone become Zoned, a bare date and time becomes
+/// Zoned in `tz` (UTC by default), and a lone date or time a Date or Time.
+fn parse_with_format(input: &str, format: &str, tz: Option<&TimeZone>) -> Result<QValue, String> {
+    use jiff::fmt::{rfc2822, strtime};
+
+    let failed = |e: &dyn std::fmt::Display| {
+        format!("ValueErr: time.parse: '{}' does not match format '{}': {}", input, format, e)
+    };
+    let zoned_in = |zoned: JiffZoned| match tz {
+        Some(zone) => QValue::Zoned(QZoned::new(zoned.with_time_zone(zone.clone()))),
+        None => QValue::Zoned(QZoned::new(zoned)),
+    };
+    match format {
+        "iso" => return parse_iso(input, tz).ok_or_else(|| failed(&"not an ISO 8601 date/time")),
+        "rfc3339" => {
+            let ts: JiffTimestamp = input.parse().map_err(|e| failed(&e))?;
+            return Ok(match tz {
+                Some(zone) => QValue::Zoned(QZoned::new(ts.to_zoned(zone.clone()))),
+                None => QValue::Timestamp(QTimestamp::new(ts)),
+            });
+        }
+        "rfc2822" | "http" => {
+            let zoned = rfc2822::DateTimeParser::new().parse_zoned(input).map_err(|e| failed(&e))?;
+            if format == "http" && tz.is_none() {
+                return Ok(QValue::Timestamp(QTimestamp::new(zoned.timestamp())));
+            }
+            return Ok(zoned_in(zoned));
+        }
+        _ => {}
+    }
+
+    let pattern = expand_strftime("time.parse", format, None)?;
+    let parsed = strtime::parse(&pattern, input).map_err(|e| failed(&e))?;
+    if parsed.iana_time_zone().is_some() {
+        return Ok(zoned_in(parsed.to_zoned().map_err(|e| failed(&e))?));
+    }
+    if let Ok(ts) = parsed.to_timestamp() {
+        // An offset (%z) or Unix seconds (%s)
+        let zone = parsed.offset().map(TimeZone::fixed).unwrap_or(TimeZone::UTC);
+        return Ok(zoned_in(ts.to_zoned(zone)));
+    }
+    if parsed.hour().is_some() {
+        if let Ok(datetime) = parsed.to_datetime() {
+            let zone = tz.cloned().unwrap_or(TimeZone::UTC);
+            let zoned = datetime.to_zoned(zone).map_err(|e| failed(&e))?;
+            return Ok(QValue::Zoned(QZoned::new(zoned)));
+        }
+        return Ok(QValue::Time(QTime::new(parsed.to_time().map_err(|e| failed(&e))?)));
+    }
+    Ok(QValue::Date(QDate::new(parsed.to_date().map_err(|e| failed(&e))?)))
+}
+
+/// Format-less parsing of ISO 8601 / RFC 3339 / RFC 9557 strings
+fn parse_iso(input: &str, tz: Option<&TimeZone>) -> Option<QValue> {
+    if let Ok(zoned) = input.parse::<JiffZoned>() {
+        return Some(QValue::Zoned(QZoned::new(match tz {
+            Some(zone) => zoned.with_time_zone(zone.clone()),
+            None => zoned,
+        })));
+    }
+    if let Ok(timestamp) = input.parse::<JiffTimestamp>() {
+        return Some(match tz {
+            Some(zone) => QValue::Zoned(QZoned::new(timestamp.to_zoned(zone.clone()))),
+            None => QValue::Timestamp(QTimestamp::new(timestamp)),
+        });
+    }
+    // A date and time of day without an offset is local to `tz` (UTC by default)
+    if input.contains(':') {
+        if let Ok(datetime) = input.parse::<jiff::civil::DateTime>() {
+            let zone = tz.cloned().unwrap_or(TimeZone::UTC);
+            return datetime.to_zoned(zone).ok().map(|z| QValue::Zoned(QZoned::new(z)));
+        }
+    }
+    if let Ok(date) = input.parse::<JiffDate>() {
+        return Some(QValue::Date(QDate::new(date)));
+    }
+    if let Ok(time) = input.parse::<JiffTime>() {
+        return Some(QValue::Time(QTime::new(time)));
+    }
+    None
+}
+
+/// Nanoseconds elapsed since program start (see time.ticks_ns)
+fn ticks_ns() -> i64 {
+    crate::get_start_time().elapsed().as_nanos() as i64
+}
+
+#[derive(Debug)]
+struct StopwatchState {
+    started_at: Option<i64>,  // ticks_ns when last started, None while stopped
+    accumulated: i64,  // Nanoseconds from earlier start/stop periods
+    last_lap: i64,  // Elapsed nanoseconds at the previous lap
+    laps: Vec<i64>,
+}
+
+impl StopwatchState {
+    fn elapsed(&self) -> i64 {
+        match self.started_at {
+            Some(start) => self.accumulated + (ticks_ns() - start),
+            None => self.accumulated,
+        }
+    }
+}
+
+/// QStopwatch - Monotonic timer with laps; copies share the same timer
+#[derive(Debug, Clone)]
+pub struct QStopwatch {
+    state: Rc<RefCell<StopwatchState>>,
+    pub id: u64,
+}
+
+impl QStopwatch {
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(StopwatchState {
+                started_at: Some(ticks_ns()),
+                accumulated: 0,
+                last_lap: 0,
+                laps: Vec::new(),
+            })),
+            id: next_object_id(),
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        // Try QObj trait methods first
+        use crate::types::try_call_qobj_method;
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        if !args.is_empty() {
+            return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+        }
+
+        let mut state = self.state.borrow_mut();
+        match method_name {
+            "elapsed" => Ok(QValue::Span(QSpan::new(span_from_nanos(state.elapsed())))),
+            "elapsed_ns" => Ok(QValue::Int(QInt::new(state.elapsed()))),
+            "lap" => {
+                // Time since the previous lap (or the start), recorded in laps()
+                let elapsed = state.elapsed();
+                let lap = elapsed - state.last_lap;
+                state.last_lap = elapsed;
+                state.laps.push(lap);
+                Ok(QValue::Span(QSpan::new(span_from_nanos(lap))))
+            }
+            "laps" => {
+                let laps = state.laps.iter()
+                    .map(|ns| QValue::Span(QSpan::new(span_from_nanos(*ns))))
+                    .collect();
+                Ok(QValue::Array(QArray::new(laps)))
+            }
+            "stop" => {
+                state.accumulated = state.elapsed();
+                state.started_at = None;
+                Ok(QValue::Span(QSpan::new(span_from_nanos(state.accumulated))))
+            }
+            "start" => {
+                if state.started_at.is_none() {
+                    state.started_at = Some(ticks_ns());
+                }
+                Ok(QValue::Nil(QNil))
+            }
+            "reset" => {
+                // Back to zero, keeping the running/stopped state
+                state.accumulated = 0;
+                state.last_lap = 0;
+                state.laps.clear();
+                if state.started_at.is_some() {
+                    state.started_at = Some(ticks_ns());
+                }
+                Ok(QValue::Nil(QNil))
+            }
+            "is_running" => Ok(QValue::Bool(QBool::new(state.started_at.is_some()))),
+            _ => attr_err!("Unknown method '{}' on Stopwatch", method_name),
+        }
+    }
+}
+
+impl QObj for QStopwatch {
+    fn cls(&self) -> String {
+        "Stopwatch".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Stopwatch"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Stopwatch"
+    }
+
+    fn str(&self) -> String {
+        format!("{}", span_from_nanos(self.state.borrow().elapsed()))
+    }
+
+    fn _rep(&self) -> String {
+        let state = self.state.borrow();
+        let status = if state.started_at.is_some() { "running" } else { "stopped" };
+        format!("Stopwatch({}, {})", span_from_nanos(state.elapsed()), status)
+    }
+
+    fn _doc(&self) -> String {
+        "A monotonic timer with laps".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
 // =============================================================================
 // Module Creation
 // =============================================================================
@@ -1463,10 +1836,17 @@ pub fn create_time_module() -> QValue {
     // Date range functions
     module.insert("range".to_string(), create_fn("time", "range"));
 
+    // Time zone database
+    module.insert("zones".to_string(), create_fn("time", "zones"));
+    module.insert("local_zone".to_string(), create_fn("time", "local_zone"));
+
     // Utility functions
     module.insert("sleep".to_string(), create_fn("time", "sleep"));
     module.insert("is_leap_year".to_string(), create_fn("time", "is_leap_year"));
     module.insert("ticks_ms".to_string(), create_fn("time", "ticks_ms"));
+    module.insert("ticks_ns".to_string(), create_fn("time", "ticks_ns"));
+    module.insert("stopwatch".to_string(), create_fn("time", "stopwatch"));
+    module.insert("measure".to_string(), create_fn("time", "measure"));
 
     QValue::Module(Box::new(QModule::new("time".to_string(), module)))
 }
@@ -1476,7 +1856,66 @@ pub fn create_time_module() -> QValue {
 // =============================================================================
 
 /// Handle time.* function calls
-pub fn call_time_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+/// Parse a duration string like "2h30m", "90s" or "1d12h30m15s" into whole seconds
+pub(crate) fn parse_duration_secs(input: &str) -> Result<i64, String> {
+    let input = input.trim();
+
+    // Parse duration string like "2h30m", "90s", "1d12h30m15s"
+    let mut total_seconds: i64 = 0;
+    let mut current_num = String::new();
+
+    for ch in input.chars() {
+        if ch.is_ascii_digit() {
+            current_num.push(ch);
+        } else if ch.is_alphabetic() {
+            if current_num.is_empty() {
+                return arg_err!("Invalid duration format: '{}' - number expected before unit", input);
+            }
+
+            let num: i64 = current_num.parse()
+                .map_err(|_| format!("Invalid number in duration: '{}'", current_num))?;
+
+            match ch {
+                'd' => total_seconds += num * 86400,      // days
+                'h' => total_seconds += num * 3600,       // hours
+                'm' => total_seconds += num * 60,         // minutes
+                's' => total_seconds += num,              // seconds
+                _ => return arg_err!("Invalid duration unit: '{}'. Supported: d (days), h (hours), m (minutes), s (seconds)", ch),
+            }
+
+            current_num.clear();
+        } else if ch.is_whitespace() {
+            // Allow whitespace between components
+            continue;
+        } else {
+            return arg_err!("Invalid character in duration: '{}'", ch);
+        }
+    }
+
+    if !current_num.is_empty() {
+        return arg_err!("Duration must end with a unit (d, h, m, s): '{}'", input);
+    }
+
+    if total_seconds == 0 {
+        return Err("Duration cannot be zero or empty".into());
+    }
+
+    Ok(total_seconds)
+}
+
+/// Seconds from an Int/Float, a duration string like "5m", or a Span
+pub(crate) fn seconds_value(name: &str, value: &QValue) -> Result<f64, String> {
+    match value {
+        QValue::Int(i) => Ok(i.value as f64),
+        QValue::Float(f) => Ok(f.value),
+        QValue::Str(s) => Ok(parse_duration_secs(&s.value)? as f64),
+        QValue::Span(s) => s.span.total(jiff::Unit::Second)
+            .map_err(|e| format!("ValueErr: {} Span must not use calendar units: {}", name, e)),
+        _ => type_err!("{} must be a number of seconds, a duration string or a Span, got {}", name, value.as_obj().cls()),
+    }
+}
+
+pub fn call_time_function(func_name: &str, args: Vec<QValue>, scope: &mut crate::Scope) -> Result<QValue, EvalError> {
     match func_name {
         "time.now" => {
             if !args.is_empty() {
@@ -1532,7 +1971,7 @@ pub fn call_time_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
             };
 
             let tz = TimeZone::get(&tz_name)
-                .map_err(|e| format!("Invalid timezone '{}': {}", tz_name, e))?;
+                .map_err(|e| format!("ValueErr: Invalid timezone '{}': {}", tz_name, e))?;
 
             let zoned = jiff::civil::date(year, month, day)
                 .at(hour, minute, second, 0)
@@ -1610,33 +2049,50 @@ pub fn call_time_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
         }
 
         "time.parse" => {
-            if args.len() != 1 {
-                return arg_err!("time.parse expects 1 argument (string), got {}", args.len());
+            // time.parse(str, format?, tz: name) - format may also be passed by name
+            let mut args = args;
+            let options = match args.last() {
+                Some(QValue::Dict(_)) if args.len() > 1 => match args.pop() {
+                    Some(QValue::Dict(d)) => d.map.borrow().clone(),
+                    _ => unreachable!(),
+                },
+                _ => Default::default(),
+            };
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("time.parse expects 1 or 2 arguments (string, format?), got {}", args.len());
             }
-
-            let input = args[0].as_str();
-
-            // Try parsing as Zoned (with timezone) first
-            if let Ok(zoned) = input.parse::<JiffZoned>() {
-                return Ok(QValue::Zoned(QZoned::new(zoned)));
+            for key in options.keys() {
+                if key != "format" && key != "tz" {
+                    return arg_err!("time.parse got an unexpected keyword argument '{}'", key);
+                }
             }
+            let input = match &args[0] {
+                QValue::Str(s) => s.value.as_ref().clone(),
+                other => return type_err!("time.parse expects a Str, got {}", other.q_type()),
+            };
+            let format = match args.get(1).or(options.get("format")) {
+                None | Some(QValue::Nil(_)) => None,
+                Some(QValue::Str(f)) => Some(f.value.as_ref().clone()),
+                Some(other) => return type_err!("time.parse format must be a Str, got {}", other.q_type()),
+            };
+            let tz = match options.get("tz") {
+                None | Some(QValue::Nil(_)) => None,
+                Some(QValue::Str(name)) => Some(TimeZone::get(&name.value)
+                    .map_err(|e| format!("ValueErr: Invalid timezone '{}': {}", name.value, e))?),
+                Some(other) => return type_err!("time.parse tz must be a Str, got {}", other.q_type()),
+            };
 
-            // Try parsing as Timestamp (UTC)
-            if let Ok(timestamp) = input.parse::<JiffTimestamp>() {
-                return Ok(QValue::Timestamp(QTimestamp::new(timestamp)));
+            if let Some(format) = format {
+                return Ok(parse_with_format(&input, &format, tz.as_ref())?);
             }
-
-            // Try parsing as Date
-            if let Ok(date) = input.parse::<JiffDate>() {
-                return Ok(QValue::Date(QDate::new(date)));
+            if let Some(value) = parse_iso(&input, tz.as_ref()) {
+                return Ok(value);
             }
-
-            // Try parsing as Time
-            if let Ok(time) = input.parse::<JiffTime>() {
-                return Ok(QValue::Time(QTime::new(time)));
+            if let Ok(value) = parse_with_format(&input, "rfc2822", tz.as_ref()) {
+                return Ok(value);
             }
 
-            arg_err!("Failed to parse '{}' as a date/time value. Supported formats: ISO 8601, RFC 3339, RFC 2822", input)
+            arg_err!("Failed to parse '{}' as a date/time value. Supported formats: ISO 8601, RFC 3339, RFC 2822 (pass format: for others)", input)
         }
 
         "time.parse_duration" => {
@@ -1644,49 +2100,7 @@ pub fn call_time_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
                 return arg_err!("time.parse_duration expects 1 argument (string), got {}", args.len());
             }
 
-            let input_str = args[0].as_str();
-            let input = input_str.trim();
-
-            // Parse duration string like "2h30m", "90s", "1d12h30m15s"
-            let mut total_seconds: i64 = 0;
-            let mut current_num = String::new();
-
-            for ch in input.chars() {
-                if ch.is_ascii_digit() {
-                    current_num.push(ch);
-                } else if ch.is_alphabetic() {
-                    if current_num.is_empty() {
-                        return arg_err!("Invalid duration format: '{}' - number expected before unit", input);
-                    }
-
-                    let num: i64 = current_num.parse()
-                        .map_err(|_| format!("Invalid number in duration: '{}'", current_num))?;
-
-                    match ch {
-                        'd' => total_seconds += num * 86400,      // days
-                        'h' => total_seconds += num * 3600,       // hours
-                        'm' => total_seconds += num * 60,         // minutes
-                        's' => total_seconds += num,              // seconds
-                        _ => return arg_err!("Invalid duration unit: '{}'. Supported: d (days), h (hours), m (minutes), s (seconds)", ch),
-                    }
-
-                    current_num.clear();
-                } else if ch.is_whitespace() {
-                    // Allow whitespace between components
-                    continue;
-                } else {
-                    return arg_err!("Invalid character in duration: '{}'", ch);
-                }
-            }
-
-            if !current_num.is_empty() {
-                return arg_err!("Duration must end with a unit (d, h, m, s): '{}'", input);
-            }
-
-            if total_seconds == 0 {
-                return Err("Duration cannot be zero or empty".into());
-            }
-
+            let total_seconds = parse_duration_secs(&args[0].as_str())?;
             let span = total_seconds.seconds();
             Ok(QValue::Span(QSpan::new(span)))
         }
@@ -1748,6 +2162,41 @@ pub fn call_time_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
             }
         }
 
+        "time.span" => {
+            // time.span(days: 5, hours: 3) - named arguments arrive as a Dict
+            match args.as_slice() {
+                [] => Ok(QValue::Span(QSpan::new(JiffSpan::new()))),
+                [QValue::Dict(_)] => Ok(QValue::Span(QSpan::new(span_arg("time.span", &args)?))),
+                _ => arg_err!("time.span expects named units (e.g. days: 1, hours: 2)"),
+            }
+        }
+
+        "time.zones" => {
+            // Sorted IANA names, optionally only those starting with a prefix
+            if args.len() > 1 {
+                return arg_err!("time.zones expects 0 or 1 arguments (prefix?), got {}", args.len());
+            }
+            let prefix = match args.first() {
+                Some(QValue::Str(s)) => s.value.as_ref().clone(),
+                Some(other) => return type_err!("time.zones expects a Str prefix, got {}", other.q_type()),
+                None => String::new(),
+            };
+            let mut names: Vec<String> = jiff::tz::db().available()
+                .filter(|name| name.starts_with(&prefix))
+                .collect();
+            names.sort();
+            names.dedup();
+            Ok(QValue::Array(QArray::new(names.into_iter().map(|n| QValue::Str(QString::new(n))).collect())))
+        }
+
+        "time.local_zone" => {
+            if !args.is_empty() {
+                return arg_err!("time.local_zone expects 0 arguments, got {}", args.len());
+            }
+            let zone = TimeZone::system();
+            Ok(QValue::Str(QString::new(zone.iana_name().unwrap_or("UTC").to_string())))
+        }
+
         "time.sleep" => {
             if args.len() != 1 {
                 return arg_err!("time.sleep expects 1 argument, got {}", args.len());
@@ -1820,6 +2269,37 @@ pub fn call_time_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate
             Ok(QValue::Int(QInt::new(elapsed)))
         }
 
+        "time.ticks_ns" => {
+            // Return nanoseconds elapsed since program start
+            if !args.is_empty() {
+                return arg_err!("time.ticks_ns() expects 0 arguments, got {}", args.len());
+            }
+            let elapsed = crate::get_start_time().elapsed().as_nanos() as i64;
+            Ok(QValue::Int(QInt::new(elapsed)))
+        }
+
+        "time.stopwatch" => {
+            if !args.is_empty() {
+                return arg_err!("time.stopwatch() expects 0 arguments, got {}", args.len());
+            }
+            Ok(QValue::Stopwatch(QStopwatch::new()))
+        }
+
+        "time.measure" => {
+            // Call fn with no arguments and return [result, elapsed Span]
+            if args.len() != 1 {
+                return arg_err!("time.measure() expects 1 argument (function), got {}", args.len());
+            }
+            let QValue::UserFun(func) = &args[0] else {
+                return type_err!("time.measure() expects a function, got {}", args[0].as_obj().cls());
+            };
+            let start = ticks_ns();
+            let call_args = crate::function_call::CallArguments::positional_only(Vec::new());
+            let result = crate::function_call::call_user_function(func, call_args, scope, None)?;
+            let elapsed = ticks_ns() - start;
+            Ok(QValue::Array(QArray::new(vec![result, QValue::Span(QSpan::new(span_from_nanos(elapsed)))])))
+        }
+
         _ => attr_err!("Unknown time function: {}", func_name)
     }
 }
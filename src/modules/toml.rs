@@ -1,10 +1,11 @@
 use crate::types::*;
 use crate::control_flow::EvalError;
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::arg_err;
 
 /// Convert a TOML value to a Quest QValue
-fn toml_to_qvalue(value: &toml::Value) -> QValue {
+pub(crate) fn toml_to_qvalue(value: &toml::Value) -> QValue {
     match value {
         toml::Value::String(s) => QValue::Str(QString::new(s.clone())),
         toml::Value::Integer(i) => QValue::Int(QInt::new(*i)),
@@ -15,7 +16,7 @@ fn toml_to_qvalue(value: &toml::Value) -> QValue {
             QValue::Array(QArray::new(elements))
         }
         toml::Value::Table(table) => {
-            let mut map = HashMap::new();
+            let mut map = IndexMap::new();
             for (key, val) in table {
                 map.insert(key.clone(), toml_to_qvalue(val));
             }
@@ -60,7 +61,7 @@ pub fn call_toml_function(func_name: &str, args: Vec<QValue>) -> Result<QValue,
                 .map_err(|e| format!("Failed to parse TOML: {}", e))?;
 
             // Convert to QValue dict
-            let mut map = HashMap::new();
+            let mut map = IndexMap::new();
             for (key, value) in data {
                 map.insert(key, toml_to_qvalue(&value));
             }
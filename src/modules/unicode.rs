@@ -0,0 +1,216 @@
+// std/unicode - normalization, case mapping, segmentation and display width
+//
+// Str methods work on code points, which is not enough for real text: "é"
+// can be one code point or two, Turkish has a dotless i, and a CJK character
+// takes two terminal columns. This module covers those cases.
+
+use std::collections::HashMap;
+use unicode_normalization::{is_nfc, is_nfd, is_nfkc, is_nfkd, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use crate::control_flow::EvalError;
+use crate::types::*;
+use crate::types::string::casefold;
+use crate::{arg_err, name_err, type_err, value_err};
+
+pub fn create_unicode_module() -> QValue {
+    let mut members = HashMap::new();
+
+    // Normalization
+    members.insert("normalize".to_string(), create_fn("unicode", "normalize"));
+    members.insert("is_normalized".to_string(), create_fn("unicode", "is_normalized"));
+
+    // Case mapping
+    members.insert("upper".to_string(), create_fn("unicode", "upper"));
+    members.insert("lower".to_string(), create_fn("unicode", "lower"));
+    members.insert("casefold".to_string(), create_fn("unicode", "casefold"));
+    members.insert("caseless_eq".to_string(), create_fn("unicode", "caseless_eq"));
+
+    // Segmentation
+    members.insert("graphemes".to_string(), create_fn("unicode", "graphemes"));
+    members.insert("grapheme_len".to_string(), create_fn("unicode", "grapheme_len"));
+    members.insert("words".to_string(), create_fn("unicode", "words"));
+
+    // Display width
+    members.insert("width".to_string(), create_fn("unicode", "width"));
+    members.insert("truncate".to_string(), create_fn("unicode", "truncate"));
+
+    QValue::Module(Box::new(QModule::new("unicode".to_string(), members)))
+}
+
+/// Handle unicode.* function calls
+pub fn call_unicode_function(func_name: &str, args: Vec<QValue>, _scope: &mut crate::Scope) -> Result<QValue, EvalError> {
+    let name = &func_name["unicode.".len()..];
+    // Keyword arguments arrive as a trailing Dict
+    let mut args = args;
+    let options = match args.last() {
+        Some(QValue::Dict(_)) if name != "caseless_eq" => match args.pop() {
+            Some(QValue::Dict(options)) => Some(options),
+            _ => None,
+        },
+        _ => None,
+    };
+    let option = |index: usize, key: &str| args.get(index).cloned().or_else(|| options.as_ref().and_then(|o| o.get(key)));
+
+    match func_name {
+        "unicode.normalize" | "unicode.is_normalized" => {
+            // normalize(text, form = "NFC")
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("{} expects 1 or 2 arguments (text, form = \"NFC\"), got {}", name, args.len());
+            }
+            let text = text_arg(name, &args[0])?;
+            let form = match option(1, "form") {
+                None => "NFC".to_string(),
+                Some(QValue::Str(s)) => s.value.to_uppercase(),
+                Some(other) => return type_err!("{} form must be Str, got {}", name, other.q_type()),
+            };
+            if name == "is_normalized" {
+                let normalized = match form.as_str() {
+                    "NFC" => is_nfc(text),
+                    "NFD" => is_nfd(text),
+                    "NFKC" => is_nfkc(text),
+                    "NFKD" => is_nfkd(text),
+                    _ => return value_err!("Unknown normalization form '{}' (expected NFC, NFD, NFKC or NFKD)", form),
+                };
+                return Ok(QValue::Bool(QBool::new(normalized)));
+            }
+            let normalized: String = match form.as_str() {
+                "NFC" => text.nfc().collect(),
+                "NFD" => text.nfd().collect(),
+                "NFKC" => text.nfkc().collect(),
+                "NFKD" => text.nfkd().collect(),
+                _ => return value_err!("Unknown normalization form '{}' (expected NFC, NFD, NFKC or NFKD)", form),
+            };
+            Ok(QValue::Str(QString::new(normalized)))
+        }
+        "unicode.upper" | "unicode.lower" => {
+            // upper(text, locale = nil): Turkish and Azerbaijani map i/İ and ı/I
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("{} expects 1 or 2 arguments (text, locale = nil), got {}", name, args.len());
+            }
+            let text = text_arg(name, &args[0])?;
+            let turkic = match option(1, "locale") {
+                None | Some(QValue::Nil(_)) => false,
+                Some(QValue::Str(locale)) => is_turkic(&locale.value),
+                Some(other) => return type_err!("{} locale must be Str, got {}", name, other.q_type()),
+            };
+            let mapped = match (name, turkic) {
+                ("upper", true) => text.replace('i', "İ").to_uppercase(),
+                ("lower", true) => text.replace('I', "ı").replace('İ', "i").to_lowercase(),
+                ("upper", false) => text.to_uppercase(),
+                _ => text.to_lowercase(),
+            };
+            Ok(QValue::Str(QString::new(mapped)))
+        }
+        "unicode.casefold" => {
+            if args.len() != 1 {
+                return arg_err!("casefold expects 1 argument, got {}", args.len());
+            }
+            Ok(QValue::Str(QString::new(casefold(text_arg(name, &args[0])?))))
+        }
+        "unicode.caseless_eq" => {
+            // Equal ignoring case and normalization: "Straße" vs "STRASSE", "é" vs "é"
+            if args.len() != 2 {
+                return arg_err!("caseless_eq expects 2 arguments, got {}", args.len());
+            }
+            let a = caseless_key(text_arg(name, &args[0])?);
+            let b = caseless_key(text_arg(name, &args[1])?);
+            Ok(QValue::Bool(QBool::new(a == b)))
+        }
+        "unicode.graphemes" | "unicode.words" => {
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument, got {}", name, args.len());
+            }
+            let text = text_arg(name, &args[0])?;
+            let parts: Vec<QValue> = if name == "graphemes" {
+                text.graphemes(true).map(|g| QValue::Str(QString::new(g.to_string()))).collect()
+            } else {
+                text.unicode_words().map(|w| QValue::Str(QString::new(w.to_string()))).collect()
+            };
+            Ok(QValue::Array(QArray::new(parts)))
+        }
+        "unicode.grapheme_len" => {
+            if args.len() != 1 {
+                return arg_err!("grapheme_len expects 1 argument, got {}", args.len());
+            }
+            let text = text_arg(name, &args[0])?;
+            Ok(QValue::Int(QInt::new(text.graphemes(true).count() as i64)))
+        }
+        "unicode.width" => {
+            // width(text, cjk = false): terminal columns; cjk counts ambiguous characters as wide
+            if args.is_empty() || args.len() > 2 {
+                return arg_err!("width expects 1 or 2 arguments (text, cjk = false), got {}", args.len());
+            }
+            let text = text_arg(name, &args[0])?;
+            let cjk = option(1, "cjk").map(|v| v.as_bool()).unwrap_or(false);
+            Ok(QValue::Int(QInt::new(display_width(text, cjk) as i64)))
+        }
+        "unicode.truncate" => {
+            // truncate(text, width, ellipsis = "", cjk = false): cut at a grapheme boundary to fit
+            if args.len() < 2 || args.len() > 4 {
+                return arg_err!("truncate expects 2 to 4 arguments (text, width, ellipsis = \"\", cjk = false), got {}", args.len());
+            }
+            let text = text_arg(name, &args[0])?;
+            let width = match &args[1] {
+                QValue::Int(n) if n.value >= 0 => n.value as usize,
+                QValue::Int(n) => return value_err!("truncate width must not be negative, got {}", n.value),
+                other => return type_err!("truncate width must be Int, got {}", other.q_type()),
+            };
+            let ellipsis = match option(2, "ellipsis") {
+                None | Some(QValue::Nil(_)) => String::new(),
+                Some(QValue::Str(s)) => s.value.as_ref().clone(),
+                Some(other) => return type_err!("truncate ellipsis must be Str, got {}", other.q_type()),
+            };
+            let cjk = option(3, "cjk").map(|v| v.as_bool()).unwrap_or(false);
+            Ok(QValue::Str(QString::new(truncate(text, width, &ellipsis, cjk))))
+        }
+        _ => name_err!("Unknown unicode function: {}", func_name)
+    }
+}
+
+/// The text argument of `function`, which must be a Str
+fn text_arg<'a>(function: &str, value: &'a QValue) -> Result<&'a str, String> {
+    match value {
+        QValue::Str(s) => Ok(s.value.as_str()),
+        other => Err(format!("TypeErr: {} expects Str, got {}", function, other.q_type())),
+    }
+}
+
+/// Whether `locale` ("tr", "tr-TR", "az_AZ", ...) uses Turkic dotted/dotless i
+fn is_turkic(locale: &str) -> bool {
+    let language = locale.split(['-', '_']).next().unwrap_or("");
+    language.eq_ignore_ascii_case("tr") || language.eq_ignore_ascii_case("az")
+}
+
+/// Canonical caseless form: decompose, fold case, then recompose compatibly
+fn caseless_key(text: &str) -> String {
+    let folded = casefold(&text.nfd().collect::<String>());
+    folded.nfkc().collect()
+}
+
+fn display_width(text: &str, cjk: bool) -> usize {
+    if cjk { text.width_cjk() } else { text.width() }
+}
+
+/// The longest prefix of `text` (whole graphemes) that fits in `width`
+/// columns together with `ellipsis`; `text` itself if it already fits
+fn truncate(text: &str, width: usize, ellipsis: &str, cjk: bool) -> String {
+    if display_width(text, cjk) <= width {
+        return text.to_string();
+    }
+    let budget = width.saturating_sub(display_width(ellipsis, cjk));
+    let mut result = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let w = display_width(grapheme, cjk);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        result.push_str(grapheme);
+    }
+    if display_width(ellipsis, cjk) <= width {
+        result.push_str(ellipsis);
+    }
+    result
+}
@@ -0,0 +1,513 @@
+// Package management for `quest install`
+//
+// A project lists its dependencies in the `[dependencies]` table of
+// quest.toml. Each one is a local path, a git repository (optionally pinned to
+// a rev, tag or branch), or a version of a package in a registry. A registry is
+// an index directory (local, or a git repository) holding one `<name>.toml`
+// per package with its git URL and a `[versions]` table mapping versions to
+// git refs.
+//
+// `quest install` copies or checks out every dependency, and the dependencies
+// of those, into quest_modules/<name>/ and records exactly what it installed
+// in quest.lock. Later installs reuse the locked commits until the manifest
+// entry changes or `--update` is given, so every checkout of a project gets
+// the same code.
+//
+// At runtime, `use "name"` and `use "name/sub/module"` resolve through the
+// nearest quest.lock (searching up from the script, or the current directory)
+// to the package's entry file or to a file inside the package.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+pub const MANIFEST_FILE: &str = "quest.toml";
+pub const LOCK_FILE: &str = "quest.lock";
+pub const MODULES_DIR: &str = "quest_modules";
+
+/// The parts of quest.toml that matter for packages
+#[derive(Debug, Default, Deserialize)]
+pub struct Manifest {
+    /// Module loaded by `use "<package>"` (default lib.q, then <name>.q, then init.q)
+    pub entry: Option<String>,
+    /// Registry index: a directory or a git URL
+    pub registry: Option<String>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Dependency {
+    /// `name = "1.2"`: a registry version
+    Version(String),
+    Detailed(DependencyDetail),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DependencyDetail {
+    pub path: Option<String>,
+    pub git: Option<String>,
+    pub rev: Option<String>,
+    pub tag: Option<String>,
+    pub branch: Option<String>,
+    pub version: Option<String>,
+}
+
+/// One `<name>.toml` in a registry index
+#[derive(Debug, Deserialize)]
+struct RegistryEntry {
+    git: String,
+    #[serde(default)]
+    versions: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    /// The manifest entry this was resolved from; a different entry means re-resolve
+    pub spec: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Entry module, relative to the package directory (none for a package used only as `name/module`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Lockfile, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let body = toml::to_string(self).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        let content = format!("# Generated by `quest install`. Do not edit by hand.\n\n{}", body);
+        fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+impl Dependency {
+    fn path(&self) -> Option<&str> {
+        match self {
+            Dependency::Detailed(detail) => detail.path.as_deref(),
+            Dependency::Version(_) => None,
+        }
+    }
+
+    /// A canonical description of the manifest entry, stored in the lockfile
+    fn spec(&self) -> Result<String, String> {
+        let detail = match self {
+            Dependency::Version(version) => return Ok(format!("registry {}", version)),
+            Dependency::Detailed(detail) => detail,
+        };
+        match (&detail.path, &detail.git, &detail.version) {
+            (Some(path), None, None) => Ok(format!("path {}", path)),
+            (None, Some(git), None) => {
+                let reference = match (&detail.rev, &detail.tag, &detail.branch) {
+                    (Some(rev), None, None) => format!(" rev {}", rev),
+                    (None, Some(tag), None) => format!(" tag {}", tag),
+                    (None, None, Some(branch)) => format!(" branch {}", branch),
+                    (None, None, None) => String::new(),
+                    _ => return Err("give at most one of rev, tag and branch".to_string()),
+                };
+                Ok(format!("git {}{}", git, reference))
+            }
+            (None, None, Some(version)) => Ok(format!("registry {}", version)),
+            _ => Err("give exactly one of path, git and version".to_string()),
+        }
+    }
+}
+
+/// Install the dependencies of the project in `project_dir` into quest_modules/
+/// and write quest.lock. With `update`, locked commits and versions are ignored.
+pub fn install(project_dir: &Path, update: bool) -> Result<Vec<LockedPackage>, String> {
+    let manifest = Manifest::load(&project_dir.join(MANIFEST_FILE))?;
+    let lock_path = project_dir.join(LOCK_FILE);
+    let previous = if update || !lock_path.exists() {
+        Lockfile::default()
+    } else {
+        Lockfile::load(&lock_path)?
+    };
+    let locked: HashMap<String, LockedPackage> = previous.packages.into_iter().map(|p| (p.name.clone(), p)).collect();
+
+    let modules_dir = project_dir.join(MODULES_DIR);
+    fs::create_dir_all(&modules_dir)
+        .map_err(|e| format!("Failed to create {}: {}", modules_dir.display(), e))?;
+
+    let registry = manifest.registry.clone().or_else(|| std::env::var("QUEST_REGISTRY").ok());
+    let mut installer = Installer {
+        modules_dir: modules_dir.clone(),
+        registry: registry.map(|r| resolve_local(project_dir, &r)),
+        locked,
+        installed: BTreeMap::new(),
+    };
+    // Where each installed package came from, to catch one name with two sources
+    let mut sources: HashMap<String, String> = HashMap::new();
+
+    // Breadth-first over the dependency graph; each package's own manifest adds more
+    let mut queue: Vec<(String, Dependency, PathBuf, String)> = manifest.dependencies.iter()
+        .map(|(name, dep)| (name.clone(), dep.clone(), project_dir.to_path_buf(), "quest.toml".to_string()))
+        .collect();
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for (name, dep, base, required_by) in queue {
+            let spec = dep.spec().map_err(|e| format!("Dependency '{}' in {}: {}", name, required_by, e))?;
+            let source = match dep.path() {
+                Some(path) => {
+                    let dir = base.join(path);
+                    dir.canonicalize().unwrap_or(dir).to_string_lossy().to_string()
+                }
+                None => spec.clone(),
+            };
+            if let Some(existing) = sources.get(&name) {
+                if *existing != source {
+                    return Err(format!(
+                        "Dependency '{}' is required from both '{}' and '{}' (from {})",
+                        name, existing, source, required_by
+                    ));
+                }
+                continue;
+            }
+            sources.insert(name.clone(), source);
+            let package = installer.install_one(&name, &dep, &base, spec)?;
+            let package_dir = modules_dir.join(&name);
+            let package_manifest = package_dir.join(MANIFEST_FILE);
+            if package_manifest.exists() {
+                let nested = Manifest::load(&package_manifest)?;
+                // Path dependencies of a package are relative to where it came from
+                let origin = package.path.as_ref().map(|p| base.join(p)).unwrap_or_else(|| package_dir.clone());
+                for (dep_name, dep) in nested.dependencies {
+                    next.push((dep_name, dep, origin.clone(), name.clone()));
+                }
+            }
+            installer.installed.insert(name, package);
+        }
+        queue = next;
+    }
+
+    // Drop packages that are no longer required
+    if let Ok(entries) = fs::read_dir(&modules_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with('.') && !installer.installed.contains_key(&name) {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+
+    let lockfile = Lockfile { packages: installer.installed.into_values().collect() };
+    lockfile.save(&lock_path)?;
+    LOCKFILES.with(|cache| cache.borrow_mut().clear());
+    Ok(lockfile.packages)
+}
+
+struct Installer {
+    modules_dir: PathBuf,
+    registry: Option<String>,
+    locked: HashMap<String, LockedPackage>,
+    installed: BTreeMap<String, LockedPackage>,
+}
+
+impl Installer {
+    fn install_one(&mut self, name: &str, dep: &Dependency, base: &Path, spec: String) -> Result<LockedPackage, String> {
+        let dest = self.modules_dir.join(name);
+        // Reuse the locked resolution while the manifest entry is unchanged
+        let locked = self.locked.get(name).filter(|p| p.spec == spec).cloned();
+
+        let detail = match dep {
+            Dependency::Version(version) => DependencyDetail { version: Some(version.clone()), ..Default::default() },
+            Dependency::Detailed(detail) => detail.clone(),
+        };
+
+        let mut package = LockedPackage {
+            name: name.to_string(),
+            spec,
+            version: None,
+            path: None,
+            git: None,
+            commit: None,
+            entry: None,
+        };
+
+        if let Some(path) = &detail.path {
+            let source = base.join(path);
+            if !source.is_dir() {
+                return Err(format!("Dependency '{}': '{}' is not a directory", name, source.display()));
+            }
+            let _ = fs::remove_dir_all(&dest);
+            copy_dir(&source, &dest).map_err(|e| format!("Failed to copy '{}': {}", source.display(), e))?;
+            println!("  Installed {} from {}", name, path);
+            package.path = Some(path.clone());
+        } else {
+            let (url, reference, version) = match (&detail.git, &detail.version) {
+                (Some(url), _) => {
+                    let reference = detail.rev.clone().or_else(|| detail.tag.clone())
+                        .or_else(|| detail.branch.as_ref().map(|b| format!("origin/{}", b)));
+                    (url.clone(), reference, None)
+                }
+                (None, Some(requirement)) => {
+                    let (url, version, reference) = match &locked {
+                        Some(p) => (p.git.clone().unwrap_or_default(), p.version.clone().unwrap_or_default(), None),
+                        None => {
+                            let (url, version, reference) = self.lookup(name, requirement)?;
+                            (url, version, Some(reference))
+                        }
+                    };
+                    (url, reference, Some(version))
+                }
+                (None, None) => unreachable!("spec() rejects dependencies without a source"),
+            };
+            // A locked commit wins over the ref it was resolved from
+            let target = locked.as_ref().and_then(|p| p.commit.clone()).or(reference);
+            let commit = checkout(&url, target.as_deref(), &dest)?;
+            println!("  Installed {} {} ({})", name, version.as_deref().unwrap_or(&url), &commit[..commit.len().min(10)]);
+            package.git = Some(url);
+            package.commit = Some(commit);
+            package.version = version;
+        }
+
+        package.entry = entry_module(name, &dest)?;
+        Ok(package)
+    }
+
+    /// Git URL, chosen version and its git ref for a registry package
+    fn lookup(&self, name: &str, requirement: &str) -> Result<(String, String, String), String> {
+        let index = self.registry.as_ref().ok_or_else(|| format!(
+            "Dependency '{}' is a registry version but no registry is configured (set `registry` in quest.toml or QUEST_REGISTRY)",
+            name
+        ))?;
+        let index_dir = if is_remote(index) {
+            let cache = crate::embedded_lib::get_stdlib_dir().with_file_name("registry").join(cache_name(index));
+            checkout(index, Some("origin/HEAD"), &cache)?;
+            cache
+        } else {
+            PathBuf::from(index)
+        };
+        let entry_path = index_dir.join(format!("{}.toml", name));
+        let content = fs::read_to_string(&entry_path)
+            .map_err(|_| format!("Package '{}' not found in registry {}", name, index))?;
+        let entry: RegistryEntry = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", entry_path.display(), e))?;
+
+        let (version, reference) = entry.versions.iter()
+            .filter(|(version, _)| version_matches(version, requirement))
+            .max_by(|a, b| version_key(a.0).cmp(&version_key(b.0)))
+            .ok_or_else(|| format!("No version of '{}' matches '{}'", name, requirement))?;
+        Ok((entry.git, version.clone(), reference.clone()))
+    }
+}
+
+/// Clone `url` into `dest` (or reuse it) and check out `reference`; returns the commit
+fn checkout(url: &str, reference: Option<&str>, dest: &Path) -> Result<String, String> {
+    let fresh = !dest.join(".git").exists();
+    if fresh {
+        let _ = fs::remove_dir_all(dest);
+        git(None, &["clone", "--quiet", url, &dest.to_string_lossy()])?;
+    }
+    if let Some(reference) = reference {
+        let current = git(Some(dest), &["rev-parse", "HEAD"])?;
+        if current != reference {
+            if !fresh {
+                git(Some(dest), &["fetch", "--quiet", "--tags", "origin"])?;
+            }
+            git(Some(dest), &["checkout", "--quiet", "--detach", reference])?;
+        }
+    } else if !fresh {
+        git(Some(dest), &["fetch", "--quiet", "origin"])?;
+        git(Some(dest), &["checkout", "--quiet", "--detach", "origin/HEAD"])?;
+    }
+    git(Some(dest), &["rev-parse", "HEAD"])
+}
+
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<String, String> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+    let output = cmd.args(args).output().map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn is_remote(source: &str) -> bool {
+    source.contains("://") || source.starts_with("git@")
+}
+
+fn resolve_local(base: &Path, source: &str) -> String {
+    if is_remote(source) {
+        source.to_string()
+    } else {
+        base.join(source).to_string_lossy().to_string()
+    }
+}
+
+fn cache_name(url: &str) -> String {
+    url.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// `requirement` is `*`, or a version or version prefix such as `1` or `1.2` (a leading `=` or `^` is ignored)
+fn version_matches(version: &str, requirement: &str) -> bool {
+    let requirement = requirement.trim().trim_start_matches(['=', '^']);
+    if requirement == "*" || requirement.is_empty() {
+        return true;
+    }
+    let wanted = version_key(requirement);
+    let have = version_key(version);
+    have.len() >= wanted.len() && have[..wanted.len()] == wanted[..]
+}
+
+fn version_key(version: &str) -> Vec<u64> {
+    version.trim_start_matches('v').split('.').map(|part| {
+        part.chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse().unwrap_or(0)
+    }).collect()
+}
+
+/// The module `use "<name>"` loads from an installed package, if it has one
+fn entry_module(name: &str, dir: &Path) -> Result<Option<String>, String> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    if manifest_path.exists() {
+        if let Some(entry) = Manifest::load(&manifest_path)?.entry {
+            if !dir.join(&entry).is_file() {
+                return Err(format!("Package '{}' names entry '{}', which does not exist", name, entry));
+            }
+            return Ok(Some(entry));
+        }
+    }
+    Ok(["lib.q".to_string(), format!("{}.q", name), "init.q".to_string()].into_iter()
+        .find(|candidate| dir.join(candidate).is_file()))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" || name == MODULES_DIR {
+            continue;
+        }
+        let target = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+thread_local! {
+    // Parsed quest.lock files by project directory (None when a directory has no lockfile)
+    static LOCKFILES: RefCell<HashMap<PathBuf, Option<Lockfile>>> = RefCell::new(HashMap::new());
+}
+
+/// The nearest directory holding `file`: the script's directory or one of its
+/// parents, or the current directory or one of its parents when there is no script
+fn find_upwards(script_path: Option<&str>, file: &str) -> Option<PathBuf> {
+    let start = match script_path.and_then(|script| Path::new(script).parent()) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::env::current_dir().ok()?,
+    };
+    let start = start.canonicalize().unwrap_or(start);
+    start.ancestors().find(|dir| dir.join(file).is_file()).map(Path::to_path_buf)
+}
+
+/// The module-related settings of a project's quest.toml
+#[derive(Debug, Default, Deserialize)]
+struct ProjectModules {
+    /// Extra module directories, relative to quest.toml
+    #[serde(default)]
+    search_path: Vec<String>,
+    #[serde(default)]
+    dependencies: toml::Table,
+}
+
+thread_local! {
+    // Parsed quest.toml module settings by project directory
+    static PROJECTS: RefCell<HashMap<PathBuf, ProjectModules>> = RefCell::new(HashMap::new());
+}
+
+fn with_project<T>(script_path: Option<&str>, f: impl FnOnce(&Path, &ProjectModules) -> T) -> Option<T> {
+    let root = find_upwards(script_path, MANIFEST_FILE)?;
+    PROJECTS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let project = cache.entry(root.clone()).or_insert_with(|| {
+            fs::read_to_string(root.join(MANIFEST_FILE)).ok()
+                .and_then(|content| toml::from_str(&content).ok())
+                .unwrap_or_default()
+        });
+        Some(f(&root, project))
+    })
+}
+
+/// The `search_path` directories of the nearest quest.toml, made absolute
+pub fn project_search_paths(script_path: Option<&str>) -> Vec<String> {
+    with_project(script_path, |root, project| {
+        project.search_path.iter().map(|dir| root.join(dir).to_string_lossy().to_string()).collect()
+    }).unwrap_or_default()
+}
+
+/// Whether the first segment of `path` is a dependency in the nearest quest.toml
+pub fn is_declared_dependency(path: &str, script_path: Option<&str>) -> bool {
+    let name = path.split('/').next().unwrap_or(path);
+    let name = name.strip_suffix(".q").unwrap_or(name);
+    with_project(script_path, |_, project| project.dependencies.contains_key(name)).unwrap_or(false)
+}
+
+/// Resolve `use "<package>"` or `use "<package>/<path>"` through the nearest quest.lock
+///
+/// The lockfile is looked for in the script's directory and its parents, or in
+/// the current directory and its parents when there is no script.
+pub fn resolve_package_module(path: &str, script_path: Option<&str>) -> Option<String> {
+    let (name, rest) = match path.split_once('/') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path.strip_suffix(".q").unwrap_or(path), None),
+    };
+    if name == "std" || name.is_empty() {
+        return None;
+    }
+
+    let project = find_upwards(script_path, LOCK_FILE)?;
+
+    let entry = LOCKFILES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let lockfile = cache.entry(project.clone()).or_insert_with(|| Lockfile::load(&project.join(LOCK_FILE)).ok());
+        lockfile.as_ref()?.packages.iter().find(|p| p.name == name).map(|p| p.entry.clone())
+    })?;
+
+    let package_dir = project.join(MODULES_DIR).join(name);
+    let file = match rest {
+        Some(rest) if rest.ends_with(".q") => package_dir.join(rest),
+        Some(rest) => package_dir.join(format!("{}.q", rest)),
+        None => package_dir.join(entry?),
+    };
+    file.exists().then(|| file.to_string_lossy().to_string())
+}
@@ -0,0 +1,143 @@
+// Sampling profiler for `quest profile`
+//
+// A timer thread raises a flag every interval; the next statement to start
+// sees it and records the Quest call stack (`Scope::call_stack`) as one
+// sample per interval that elapsed since the previous one. Samples are only
+// taken between statements, so time spent inside a long Rust builtin call is
+// charged to the stack that made the call once it returns. The collected
+// stacks are written as a per-function self/total report, in the collapsed
+// format flamegraph tools read, or as a flamegraph SVG.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::scope::Scope;
+
+/// Set by the timer thread when a sample is due
+static TICK: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static INTERVAL: Cell<Duration> = const { Cell::new(Duration::from_millis(1)) };
+    static LAST_SAMPLE: Cell<Option<Instant>> = const { Cell::new(None) };
+    static ROOT: RefCell<String> = const { RefCell::new(String::new()) };
+    /// Sample counts keyed by stack, outermost frame first
+    static SAMPLES: RefCell<HashMap<Vec<String>, u64>> = RefCell::new(HashMap::new());
+}
+
+/// The timer thread; stops when dropped
+pub struct Sampler {
+    running: Arc<AtomicBool>,
+    timer: Option<thread::JoinHandle<()>>,
+}
+
+/// Start sampling every `interval`, naming the outermost frame `root`
+pub fn start(interval: Duration, root: &str) -> Sampler {
+    INTERVAL.with(|i| i.set(interval));
+    ROOT.with(|r| *r.borrow_mut() = root.to_string());
+    LAST_SAMPLE.with(|l| l.set(Some(Instant::now())));
+
+    let running = Arc::new(AtomicBool::new(true));
+    let flag = Arc::clone(&running);
+    let timer = thread::spawn(move || {
+        while flag.load(Ordering::Relaxed) {
+            thread::sleep(interval);
+            TICK.store(true, Ordering::Relaxed);
+        }
+    });
+    Sampler { running, timer: Some(timer) }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(timer) = self.timer.take() {
+            let _ = timer.join();
+        }
+        TICK.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Take a sample if one is due; called as each statement starts
+pub fn on_statement(scope: &Scope) {
+    if !TICK.load(Ordering::Relaxed) {
+        return;
+    }
+    TICK.store(false, Ordering::Relaxed);
+
+    let now = Instant::now();
+    let interval = INTERVAL.with(|i| i.get());
+    let elapsed = LAST_SAMPLE.with(|l| l.replace(Some(now))).map(|last| now - last).unwrap_or(interval);
+    let count = (elapsed.as_nanos() / interval.as_nanos().max(1)).max(1) as u64;
+
+    let mut stack = vec![ROOT.with(|r| r.borrow().clone())];
+    stack.extend(scope.call_stack.borrow().iter().map(|frame| frame.function_name.clone()));
+    SAMPLES.with(|samples| *samples.borrow_mut().entry(stack).or_insert(0) += count);
+}
+
+/// Every sampled stack with its count, most frequent first
+pub fn samples() -> Vec<(Vec<String>, u64)> {
+    let mut samples: Vec<_> = SAMPLES.with(|s| s.borrow().iter().map(|(k, v)| (k.clone(), *v)).collect());
+    samples.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    samples
+}
+
+/// The samples in collapsed-stack format: `root;outer;inner COUNT` per line
+pub fn collapsed(samples: &[(Vec<String>, u64)]) -> String {
+    let mut lines: Vec<String> = samples.iter()
+        .map(|(stack, count)| format!("{} {}", stack.join(";"), count))
+        .collect();
+    lines.sort();
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Render the samples as a flamegraph SVG
+pub fn flamegraph(samples: &[(Vec<String>, u64)], title: &str) -> Result<Vec<u8>, String> {
+    let collapsed = collapsed(samples);
+    let mut options = inferno::flamegraph::Options::default();
+    options.title = title.to_string();
+    options.count_name = "samples".to_string();
+    let mut svg = Vec::new();
+    inferno::flamegraph::from_lines(&mut options, collapsed.lines(), &mut svg)
+        .map_err(|e| format!("Failed to render flamegraph: {}", e))?;
+    Ok(svg)
+}
+
+/// A table of the `top` functions by self time, with their total time
+pub fn report(samples: &[(Vec<String>, u64)], top: usize) -> String {
+    let interval = INTERVAL.with(|i| i.get());
+    let total: u64 = samples.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        return "No samples collected; the script finished within one sampling interval\n".to_string();
+    }
+
+    // function -> (self, total)
+    let mut functions: HashMap<&str, (u64, u64)> = HashMap::new();
+    for (stack, count) in samples {
+        let mut seen = Vec::new();
+        for name in stack {
+            if !seen.contains(&name) {
+                functions.entry(name.as_str()).or_default().1 += count;
+                seen.push(name);
+            }
+        }
+        if let Some(leaf) = stack.last() {
+            functions.entry(leaf.as_str()).or_default().0 += count;
+        }
+    }
+    let mut rows: Vec<_> = functions.into_iter().collect();
+    rows.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(b.1.1.cmp(&a.1.1)).then(a.0.cmp(b.0)));
+
+    let ms = |count: u64| count as f64 * interval.as_secs_f64() * 1000.0;
+    let pct = |count: u64| count as f64 * 100.0 / total as f64;
+    let mut out = format!("{} samples every {}ms ({:.1}ms)\n\n", total, interval.as_secs_f64() * 1000.0, ms(total));
+    out.push_str(&format!("{:>7} {:>10} {:>7} {:>10}  Function\n", "Self%", "Self", "Total%", "Total"));
+    for (name, (self_count, total_count)) in rows.into_iter().take(top) {
+        out.push_str(&format!("{:>6.1}% {:>8.1}ms {:>6.1}% {:>8.1}ms  {}\n",
+            pct(self_count), ms(self_count), pct(total_count), ms(total_count), name));
+    }
+    out
+}
@@ -1,141 +1,346 @@
+use rustyline::completion::Completer;
 use rustyline::error::ReadlineError;
-use rustyline::DefaultEditor;
-use std::path::PathBuf;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Config, Editor, Helper};
+use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::env;
+use std::fs;
+use std::path::PathBuf;
+use pest::error::InputLocation;
+use pest::Parser;
+use crate::control_flow::EvalError;
+use crate::formatter::{self, Kind};
 use crate::scope::Scope;
-use crate::types::QValue;
-use crate::eval_expression;
+use crate::types::{QNil, QValue};
+use crate::{eval_pair, QuestParser, Rule};
 
-/// Get the path to the history file
-fn get_history_path() -> Option<PathBuf> {
+/// Prompt for the second and later lines of an unfinished statement
+const CONTINUATION_PROMPT: &str = "  ..> ";
+
+// ANSI styles for highlighted input
+const KEYWORD_STYLE: &str = "1;34";
+const CONSTANT_STYLE: &str = "36";
+const STRING_STYLE: &str = "32";
+const COMMENT_STYLE: &str = "2";
+const MATCHED_BRACKET_STYLE: &str = "1;33";
+const UNMATCHED_BRACKET_STYLE: &str = "1;31";
+
+/// Line editor helper for the REPL: syntax highlighting and bracket matching
+struct ReplHelper {
+    color: bool,
+}
+
+impl Helper for ReplHelper {}
+impl Validator for ReplHelper {}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        if self.color {
+            Cow::Owned(highlight(line, pos))
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        // Bracket matching follows the cursor, so redraw on every move
+        self.color
+    }
+}
+
+/// Colorize keywords, literals and comments in the input. The bracket under
+/// (or just before) the cursor is shown with its partner, and closing
+/// brackets without an opener are flagged.
+fn highlight(input: &str, pos: usize) -> String {
+    let tokens: Vec<_> = formatter::lex(input).into_iter().flatten().collect();
+    // Token texts are slices of the input, so their offsets locate them
+    let offset = |text: &str| text.as_ptr() as usize - input.as_ptr() as usize;
+
+    // Pair up brackets by token index
+    let mut partner = vec![None; tokens.len()];
+    let mut unmatched = vec![false; tokens.len()];
+    let mut open = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_open() {
+            open.push(i);
+        } else if token.is_close() {
+            match open.last() {
+                Some(&j) if closes(tokens[j].text, token.text) => {
+                    open.pop();
+                    partner[i] = Some(j);
+                    partner[j] = Some(i);
+                }
+                _ => unmatched[i] = true,
+            }
+        }
+    }
+
+    let at_cursor = |i: usize| {
+        let start = offset(tokens[i].text);
+        start == pos || start + 1 == pos
+    };
+    let cursor_pair = (0..tokens.len())
+        .filter(|&i| partner[i].is_some() && at_cursor(i))
+        .min_by_key(|&i| usize::from(offset(tokens[i].text) != pos))
+        .map(|i| (i, partner[i].unwrap_or(i)));
+
+    let mut out = String::with_capacity(input.len() * 2);
+    let mut copied = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let start = offset(token.text);
+        let style = if unmatched[i] {
+            Some(UNMATCHED_BRACKET_STYLE)
+        } else if cursor_pair.is_some_and(|(a, b)| i == a || i == b) {
+            Some(MATCHED_BRACKET_STYLE)
+        } else {
+            match token.kind {
+                Kind::Str => Some(STRING_STYLE),
+                Kind::Number => Some(CONSTANT_STYLE),
+                Kind::Comment => Some(COMMENT_STYLE),
+                Kind::Word if token.is_keyword() => match token.text {
+                    "true" | "false" | "nil" => Some(CONSTANT_STYLE),
+                    _ => Some(KEYWORD_STYLE),
+                },
+                _ => None,
+            }
+        };
+        let Some(style) = style else {
+            continue;
+        };
+        out.push_str(&input[copied..start]);
+        out.push_str(&format!("\x1b[{}m{}\x1b[0m", style, token.text));
+        copied = start + token.text.len();
+    }
+    out.push_str(&input[copied..]);
+    out
+}
+
+fn closes(opener: &str, closer: &str) -> bool {
+    matches!((opener, closer), ("(", ")") | ("[", "]") | ("{", "}"))
+}
+
+/// Most history entries kept in the history file
+const MAX_HISTORY: usize = 1000;
+
+/// How many recent results are bound as `_1`, `_2`, ...
+const RECENT_RESULTS: usize = 9;
+
+/// Get the user's home directory
+fn get_home_dir() -> Option<PathBuf> {
     // Try HOME on Unix-like systems, USERPROFILE on Windows
     let home = env::var("HOME")
         .or_else(|_| env::var("USERPROFILE"))
         .ok()?;
+    Some(PathBuf::from(home))
+}
+
+/// Get the path to the history file (~/.quest/history)
+fn get_history_path() -> Option<PathBuf> {
+    Some(get_home_dir()?.join(".quest").join("history"))
+}
+
+/// History file used before it moved into ~/.quest, read until the new one exists
+fn get_legacy_history_path() -> Option<PathBuf> {
+    Some(get_home_dir()?.join(".quest_history"))
+}
+
+/// Bind a result to `_` and shift it into `_1`, `_2`, ... (most recent first)
+fn remember_result(result: &QValue, recent: &mut VecDeque<QValue>, scope: &mut Scope) {
+    recent.push_front(result.clone());
+    recent.truncate(RECENT_RESULTS);
+    scope.set("_", result.clone());
+    for (i, value) in recent.iter().enumerate() {
+        scope.set(&format!("_{}", i + 1), value.clone());
+    }
+}
+
+/// Evaluate a complete REPL entry (any number of statements) and return the
+/// value of the last one
+fn eval_input(input: &str, scope: &mut Scope) -> Result<QValue, EvalError> {
+    let pairs = QuestParser::parse(Rule::program, input)
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let mut result = QValue::Nil(QNil);
+    for pair in pairs {
+        for statement in pair.into_inner() {
+            if matches!(statement.as_rule(), Rule::EOI) {
+                continue;
+            }
+            result = eval_pair(statement, scope)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Whether the input stops partway through a statement: inside brackets or a
+/// triple-quoted string, or in a block (`fun`, `if`, ...) that hasn't reached
+/// its `end` yet
+fn is_incomplete(input: &str) -> bool {
+    let input = input.trim_end();
+    let tokens: Vec<_> = formatter::lex(input).into_iter().flatten().collect();
+
+    if let Some(last) = tokens.last() {
+        if last.kind == Kind::Str && is_unterminated(last.text) {
+            // Only triple-quoted strings span lines; a plain string missing its
+            // quote is a syntax error to report straight away
+            return last.text.starts_with("\"\"\"") || last.text.starts_with("'''");
+        }
+    }
+
+    let depth: i32 = tokens.iter()
+        .map(|token| if token.is_open() { 1 } else if token.is_close() { -1 } else { 0 })
+        .sum();
+    if depth > 0 {
+        return true;
+    }
 
-    let mut path = PathBuf::from(home);
-    path.push(".quest_history");
-    Some(path)
+    // An unclosed block fails to parse right at the end of the input, where
+    // the parser was still expecting more statements or `end`
+    match QuestParser::parse(Rule::program, input) {
+        Ok(_) => false,
+        Err(e) => match e.location {
+            InputLocation::Pos(pos) => pos >= input.len(),
+            InputLocation::Span((start, _)) => start >= input.len(),
+        },
+    }
+}
+
+/// Whether a string literal token runs to the end of the input without its
+/// closing quote
+fn is_unterminated(text: &str) -> bool {
+    let body = text.trim_start_matches(['f', 'b', 'B']);
+    for triple in ["\"\"\"", "'''"] {
+        if body.starts_with(triple) {
+            return body.len() < 6 || !body.ends_with(triple);
+        }
+    }
+    let Some(quote) = body.chars().next() else {
+        return false;
+    };
+    if body.len() < 2 || !body.ends_with(quote) {
+        return true;
+    }
+    // A closing quote preceded by an odd number of backslashes is escaped
+    let backslashes = body[..body.len() - 1].chars().rev().take_while(|&c| c == '\\').count();
+    backslashes % 2 == 1
 }
 
-/// Run the Quest REPL (Read-Eval-Print Loop)
-pub fn run_repl() -> rustyline::Result<()> {
+/// Run the Quest REPL (Read-Eval-Print Loop). `color` turns on syntax
+/// highlighting of the input.
+pub fn run_repl(color: bool) -> rustyline::Result<()> {
     println!("Quest REPL v{}", env!("CARGO_PKG_VERSION"));
     println!("(type ':help' for help, ':exit' or ':quit' to exit)");
     println!();
 
-    let mut rl = DefaultEditor::new()?;
+    let config = Config::builder()
+        .max_history_size(MAX_HISTORY)?
+        .history_ignore_dups(true)?
+        .build();
+    let mut rl = Editor::<ReplHelper, DefaultHistory>::with_config(config)?;
+    rl.set_helper(Some(ReplHelper { color }));
 
     // Load history from file
     if let Some(history_path) = get_history_path() {
         // Ignore errors if history file doesn't exist yet
+        let history_path = match get_legacy_history_path() {
+            Some(legacy) if !history_path.exists() => legacy,
+            _ => history_path,
+        };
         let _ = rl.load_history(&history_path);
     }
 
+    // Lines typed so far for a statement that isn't finished yet
     let mut buffer = String::new();
-    let mut nesting_level = 0;
     let mut scope = Scope::new();
+    // Results shown so far, newest first, for `_1`, `_2`, ...
+    let mut recent = VecDeque::new();
 
     loop {
-        let prompt = if nesting_level > 0 {
-            format!("{}> ", ".".repeat(nesting_level))
-        } else {
-            "quest> ".to_string()
-        };
+        let prompt = if buffer.is_empty() { "quest> " } else { CONTINUATION_PROMPT };
 
-        let readline = rl.readline(&prompt);
+        let readline = rl.readline(prompt);
         match readline {
             Ok(line) => {
-                let trimmed = line.trim();
-
-                if trimmed.is_empty() && nesting_level == 0 {
-                    continue;
-                }
+                if buffer.is_empty() {
+                    let trimmed = line.trim();
+                    if trimmed.is_empty() {
+                        continue;
+                    }
 
-                // Handle commands starting with : (only at top level)
-                if trimmed.starts_with(':') && nesting_level == 0 {
-                    match trimmed {
-                        ":exit" | ":quit" => {
-                            println!("Goodbye!");
-                            break;
-                        }
-                        ":help" => {
-                            print_help();
-                            continue;
-                        }
-                        _ => {
-                            eprintln!("Unknown command: {}. Type ':help' for available commands.", trimmed);
-                            continue;
+                    // Handle commands starting with : (only at top level)
+                    if trimmed.starts_with(':') {
+                        match trimmed {
+                            ":exit" | ":quit" => {
+                                println!("Goodbye!");
+                                break;
+                            }
+                            ":help" => {
+                                print_help();
+                                continue;
+                            }
+                            _ => {
+                                eprintln!("Unknown command: {}. Type ':help' for available commands.", trimmed);
+                                continue;
+                            }
                         }
                     }
+                } else {
+                    buffer.push('\n');
                 }
+                buffer.push_str(&line);
 
-                // Track nesting level for multi-line constructs
-                let line_lower = trimmed.to_lowercase();
-
-                // Keywords that start a block and increase nesting
-                if line_lower.starts_with("if ")
-                    || line_lower.starts_with("fun ")
-                    || line_lower.starts_with("type ")
-                    || line_lower.starts_with("trait ")
-                    || line_lower.starts_with("while ")
-                    || line_lower.starts_with("for ")
-                    || line_lower.starts_with("try")
-                    || line_lower.starts_with("pub type ")
-                    || line_lower.starts_with("pub trait ")
-                    || line_lower.starts_with("pub fun ")
-                {
-                    nesting_level += 1;
+                // Keep reading until blocks, brackets and strings are closed
+                if is_incomplete(&buffer) {
+                    continue;
                 }
 
-                // Keywords that don't change nesting but indicate we're in a block
-                if line_lower.starts_with("elif ")
-                    || line_lower.starts_with("else")
-                    || line_lower.starts_with("catch ")
-                    || line_lower.starts_with("ensure")
-                {
-                    // These don't change nesting, but indicate we're still in a block
-                }
+                // The whole statement is one history entry, so up-arrow brings
+                // back a multi-line definition to edit as a unit
+                let input = buffer.trim_end();
+                rl.add_history_entry(input)?;
 
-                // Keywords that end a block and decrease nesting
-                if trimmed == "end" {
-                    nesting_level = nesting_level.saturating_sub(1);
-                }
+                match eval_input(input, &mut scope) {
+                    Ok(result) => {
+                        // Don't print nil results (from statements like puts)
+                        if !matches!(result, QValue::Nil(_)) {
+                            remember_result(&result, &mut recent, &mut scope);
 
-                // Add to buffer
-                if !buffer.is_empty() {
-                    buffer.push('\n');
-                }
-                buffer.push_str(trimmed);
-
-                // If we're at nesting level 0, evaluate the complete statement
-                if nesting_level == 0 && !buffer.is_empty() {
-                    rl.add_history_entry(&buffer)?;
-
-                    match eval_expression(&buffer, &mut scope) {
-                        Ok(result) => {
-                            // Don't print nil results (from statements like puts)
-                            if !matches!(result, QValue::Nil(_)) {
-                                // Special case: if calling ._doc(), print string without quotes
-                                if buffer.trim().ends_with("._doc()") && matches!(result, QValue::Str(_)) {
-                                    if let QValue::Str(s) = result {
-                                        println!("{}", s.value);
-                                    }
-                                } else {
-                                    // Always use the _rep() method for REPL output
-                                    println!("{}", result.as_obj()._rep());
+                            // Special case: if calling ._doc(), print string without quotes
+                            if input.ends_with("._doc()") && matches!(result, QValue::Str(_)) {
+                                if let QValue::Str(s) = result {
+                                    println!("{}", s.value);
                                 }
+                            } else {
+                                // Always use the _rep() method for REPL output
+                                println!("{}", result.as_obj()._rep());
                             }
                         }
-                        Err(e) => eprintln!("Error: {}", e),
                     }
-
-                    // Clear buffer for next statement
-                    buffer.clear();
+                    Err(e) => eprintln!("Error: {}", e),
                 }
+
+                // Clear buffer for next statement
+                buffer.clear();
             }
             Err(ReadlineError::Interrupted) => {
                 println!("^C");
+                // Ctrl-C abandons an unfinished statement; at the prompt it exits
+                if !buffer.is_empty() {
+                    buffer.clear();
+                    continue;
+                }
                 break;
             }
             Err(ReadlineError::Eof) => {
@@ -152,6 +357,9 @@ pub fn run_repl() -> rustyline::Result<()> {
     // Save history to file before exiting
     if let Some(history_path) = get_history_path() {
         // Ignore errors when saving history
+        if let Some(dir) = history_path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
         let _ = rl.save_history(&history_path);
     }
 
@@ -165,6 +373,15 @@ pub fn print_help() {
     println!("  :exit    - Exit the REPL");
     println!("  :quit    - Exit the REPL");
     println!();
+    println!("Editing:");
+    println!("  Up/Down  - Recall earlier entries (saved in ~/.quest/history)");
+    println!("  Ctrl-R   - Search history");
+    println!("  Ctrl-C   - Discard an unfinished entry");
+    println!();
+    println!("Results:");
+    println!("  _        - The last result");
+    println!("  _1 .. _{} - Recent results, most recent first", RECENT_RESULTS);
+    println!();
     println!("Supported operators:");
     println!("  Arithmetic: + - * / %");
     println!("  Comparison: == != < > <= >=");
@@ -219,11 +436,17 @@ pub fn show_help() {
     println!("    quest <file.q>     Execute a Quest script file");
     println!("    quest run <name>   Run a script from quest.toml");
     println!("    cat file.q | quest Read and execute from stdin");
+    println!("    quest -e <code>    Run code from the command line (see 'quest -e --help')");
     println!();
     println!("OPTIONS:");
     println!("    -h, --help         Display this help message");
     println!("    -v, --version      Display version information");
     println!("        --search-path  Display module search paths");
+    println!("    -W <filter>        Warning filter ACTION[:CATEGORY]: default, always, ignore or error");
+    println!("        --no-color     Start the REPL without syntax highlighting");
+    println!("    -e <code>          Run a one-liner; repeat to add more lines");
+    println!("    -p <code>          Run a one-liner and print its last value");
+    println!("    -r <module>        Load a module before the one-liner (bare names are std modules)");
     println!();
     println!("COMMANDS:");
     println!("    run <script_name> [args...]");
@@ -240,6 +463,35 @@ pub fn show_help() {
     println!("            quest run test");
     println!("            quest run install");
     println!();
+    println!("    run --watch <script.q|script_name> [args...]");
+    println!("        Run a Quest script, clear the screen and run it again whenever");
+    println!("        it or any local module it uses changes");
+    println!();
+    println!("    run --trace [--trace-calls] [--trace-module=M] [--trace-output=FILE] <script.q|script_name> [args...]");
+    println!("        Run a Quest script, logging each statement (and with --trace-calls, each");
+    println!("        function call) to stderr or FILE, optionally only for files matching M");
+    println!();
+    println!("    bench [OPTIONS] [PATHS...]");
+    println!("        Run benchmarks in bench_*.q files (see 'quest bench --help')");
+    println!();
+    println!("    fmt [OPTIONS] [PATHS...]");
+    println!("        Format .q files in canonical style (see 'quest fmt --help')");
+    println!();
+    println!("    check [PATHS...]");
+    println!("        Parse and statically check .q files and the modules they use (see 'quest check --help')");
+    println!();
+    println!("    debug <script.q> [args...] | debug --dap [--port PORT]");
+    println!("        Run a script under the debugger, or serve the Debug Adapter Protocol (see 'quest debug --help')");
+    println!();
+    println!("    profile [OPTIONS] <script.q|script_name> [args...]");
+    println!("        Sample a script's call stack and report where time went (see 'quest profile --help')");
+    println!();
+    println!("    install [--update]");
+    println!("        Install quest.toml [dependencies] into quest_modules/ and write quest.lock (see 'quest install --help')");
+    println!();
+    println!("    migrate [COMMAND] [OPTIONS]");
+    println!("        Apply or roll back database migrations (see 'quest migrate --help')");
+    println!();
     println!("ARGUMENTS:");
     println!("    When running a script file, arguments are accessible via:");
     println!("        sys.argv - Array of arguments (including script name)");
@@ -247,10 +499,13 @@ pub fn show_help() {
     println!();
     println!("EXAMPLES:");
     println!("    quest                      # Start REPL");
+    println!("    quest --no-color           # Start REPL without highlighting");
     println!("    quest script.q             # Run script.q");
     println!("    quest script.q arg1 arg2   # Run with arguments");
     println!("    quest run test             # Run 'test' from quest.toml");
     println!("    echo 'puts(\"hi\")' | quest  # Execute from stdin");
+    println!("    quest -p '6 * 7'           # Print 42");
+    println!("    quest -r math -p 'math.sqrt(2)'");
     println!();
     println!("For more information, visit: https://github.com/quest-lang/quest");
 }
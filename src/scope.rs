@@ -88,7 +88,8 @@ impl StackFrame {
 // Output target for I/O redirection (QEP-010)
 #[derive(Debug, Clone)]
 pub enum OutputTarget {
-    Default,  // OS stdout/stderr (print!/eprint!)
+    Default,  // OS stdout (print!)
+    Stderr,  // OS stderr (eprint!)
     File(String),  // File path (appends on each write)
     StringIO(Rc<RefCell<QStringIO>>),  // In-memory buffer
 }
@@ -101,6 +102,11 @@ impl OutputTarget {
                 std::io::stdout().flush().ok();
                 Ok(())
             }
+            OutputTarget::Stderr => {
+                eprint!("{}", data);
+                std::io::stderr().flush().ok();
+                Ok(())
+            }
             OutputTarget::File(path) => {
                 use std::fs::OpenOptions;
                 let mut file = OpenOptions::new()
@@ -140,6 +146,10 @@ pub struct Scope {
     pub current_file: Option<String>,      // Current file being executed
     pub current_line: Option<usize>,       // Current line number being executed
     pub current_function: Option<String>,  // Current function being executed
+    // Coverage: file the evaluated text came from, and how many lines precede it
+    // there (function bodies are re-parsed on their own, so their lines start at 1)
+    pub source_file: Option<Rc<String>>,
+    pub line_offset: usize,
     // QEP-056: return_value removed - values now stored in ControlFlow::FunctionReturn(val)
     // Public items (for module exports) - only items in this set are exported
     // Only applies to the top-level scope of a module
@@ -173,10 +183,12 @@ impl Scope {
             current_file: None,
             current_line: None,
             current_function: None,
+            source_file: None,
+            line_offset: 0,
             // QEP-056: return_value removed
             public_items: HashSet::new(),
             stdout_target: OutputTarget::Default,
-            stderr_target: OutputTarget::Default,
+            stderr_target: OutputTarget::Stderr,
             constants: vec![HashSet::new()],
             variable_types: vec![HashMap::new()],
             eval_depth: 0,
@@ -249,10 +261,12 @@ impl Scope {
             current_file: None,
             current_line: None,
             current_function: None,
+            source_file: None,
+            line_offset: 0,
             // QEP-056: return_value removed
             public_items: HashSet::new(),
             stdout_target: OutputTarget::Default,
-            stderr_target: OutputTarget::Default,
+            stderr_target: OutputTarget::Stderr,
             constants: vec![HashSet::new()],
             variable_types: vec![HashMap::new()],
             eval_depth: 0,
@@ -300,6 +314,16 @@ impl Scope {
         None
     }
 
+    // Names of every variable visible from the innermost scope
+    pub fn variable_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.scopes.iter()
+            .flat_map(|scope| scope.borrow().keys().cloned().collect::<Vec<_>>())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
     // Set variable in the scope where it's defined, or current scope if new
     pub fn set(&mut self, name: &str, value: QValue) {
         // Special handling for 'self' - always set only in current scope
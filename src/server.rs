@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::net::SocketAddr;
 use std::sync::{Arc, RwLock};
 use std::cell::RefCell;
@@ -206,6 +207,26 @@ struct AppState {
     ws_registry: WebSocketRegistry,
 }
 
+/// Replay the script's top-level statements in a worker's scope, skipping
+/// web.run() so the worker doesn't start another server
+fn run_script_statements(pairs: pest::iterators::Pairs<crate::Rule>, scope: &mut Scope) -> Result<(), String> {
+    for pair in pairs {
+        if matches!(pair.as_rule(), crate::Rule::EOI) {
+            continue;
+        }
+        for statement in pair.into_inner() {
+            if matches!(statement.as_rule(), crate::Rule::EOI) {
+                continue;
+            }
+            if statement.as_rule() == crate::Rule::statement && statement.as_str().contains("web.run") {
+                continue;
+            }
+            crate::eval_pair(statement, scope)?;
+        }
+    }
+    Ok(())
+}
+
 /// Initialize thread-local Scope (called once per worker thread)
 fn init_thread_scope(config: &ServerConfig) -> Result<(), String> {
     QUEST_SCOPE.with(|scope_cell| {
@@ -230,33 +251,12 @@ fn init_thread_scope(config: &ServerConfig) -> Result<(), String> {
         let source = config.script_source.trim_end();
         let pairs = crate::QuestParser::parse(crate::Rule::program, source)
             .map_err(|e| format!("Parse error: {}", e))?;
-        
-        for pair in pairs {
-            if matches!(pair.as_rule(), crate::Rule::EOI) {
-                continue;
-            }
-            for statement in pair.into_inner() {
-                if matches!(statement.as_rule(), crate::Rule::EOI) {
-                    continue;
-                }
-
-                let rule = statement.as_rule();
-                let stmt_text = statement.as_str();
-                
-                // Skip web.run() to avoid starting another server
-                let should_skip = if rule == crate::Rule::statement {
-                    stmt_text.contains("web.run")
-                } else {
-                    false
-                };
-
-                if should_skip {
-                    continue;
-                }
 
-                crate::eval_pair(statement, &mut scope)?;
-            }
-        }
+        // The main process already ran any top-level jobs.enqueue(); drop the copies
+        crate::modules::jobs::begin_request();
+        let result = run_script_statements(pairs, &mut scope);
+        crate::modules::jobs::end_request(false);
+        result?;
 
         // Note: handle_request() is optional. If not defined, requests to dynamic routes
         // will return 404. This allows web.run() to work with just static files or middleware.
@@ -334,6 +334,14 @@ pub async fn start_server_with_shutdown(
         ws_registry: WebSocketRegistry::new(),
     };
 
+    // Start background job threads if the script uses std/jobs
+    if crate::modules::jobs::in_use() {
+        for _ in 0..crate::modules::jobs::worker_count() {
+            let config = Arc::clone(&state.config);
+            std::thread::spawn(move || run_job_worker(config));
+        }
+    }
+
     // Build CORS layer if configured
     let cors_layer = if let Some(ref cors_config) = state.config.cors {
         let mut cors = CorsLayer::new();
@@ -452,6 +460,22 @@ async fn handle_http_request(
     }
 }
 
+/// Run queued std/jobs jobs forever in this thread's copy of the script
+fn run_job_worker(config: Arc<ServerConfig>) {
+    if let Err(e) = init_thread_scope(&config) {
+        eprintln!("Job worker failed to start: {}", e);
+        return;
+    }
+    loop {
+        let job = crate::modules::jobs::next_job();
+        QUEST_SCOPE.with(|scope_cell| {
+            if let Some(scope) = scope_cell.borrow_mut().as_mut() {
+                crate::modules::jobs::execute(job, scope);
+            }
+        });
+    }
+}
+
 /// Try to serve a static file from runtime-configured static directories
 fn try_serve_static_file(request_path: &str) -> Option<Response> {
     use std::path::Path;
@@ -582,6 +606,15 @@ fn handle_request_sync(state: AppState, req: Request, client_ip: String) -> Resp
         return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
     }
 
+    // Jobs enqueued by the handler are queued once the response is ready,
+    // and dropped if the request failed
+    crate::modules::jobs::begin_request();
+    let response = handle_request_inner(state, req, client_ip);
+    crate::modules::jobs::end_request(!response.status().is_server_error());
+    response
+}
+
+fn handle_request_inner(state: AppState, req: Request, client_ip: String) -> Response {
     // Convert HTTP request to Quest Dict (synchronous version needed)
     let mut request_dict = match http_request_to_dict_sync(req, client_ip) {
         Ok(dict) => dict,
@@ -682,7 +715,7 @@ fn handle_request_sync(state: AppState, req: Request, client_ip: String) -> Resp
     // Skip Quest handler if static-only mode
     let response_value = if state.config.static_only {
         // Return a simple 404 dict to signal "not found, try static files"
-        let mut map = HashMap::new();
+        let mut map = IndexMap::new();
         map.insert("status".to_string(), QValue::Int(QInt::new(404)));
         map.insert("body".to_string(), QValue::Str(QString::new("Not found".to_string())));
         Ok(QValue::Dict(Box::new(QDict::new(map))))
@@ -728,7 +761,7 @@ fn handle_request_sync(state: AppState, req: Request, client_ip: String) -> Resp
             Some(_) => return Err("handle_request is not a function".to_string()),
             None => {
                 // No handler defined, return 404
-                let mut map = HashMap::new();
+                let mut map = IndexMap::new();
                 map.insert("status".to_string(), QValue::Int(QInt::new(404)));
                 map.insert("body".to_string(), QValue::Str(QString::new("Not found".to_string())));
                 QValue::Dict(Box::new(QDict::new(map)))
@@ -920,13 +953,13 @@ fn get_web_config(scope: &mut Scope) -> Result<QDict, String> {
     let get_config_fn = match &web_value {
         Some(QValue::Module(m)) => match m.get_member("_get_config") {
             Some(QValue::UserFun(f)) => f.clone(),
-            _ => return Ok(QDict::new(HashMap::new())),
+            _ => return Ok(QDict::new(IndexMap::new())),
         },
         Some(QValue::Dict(d)) => match d.get("_get_config") {
             Some(QValue::UserFun(f)) => f.clone(),
-            _ => return Ok(QDict::new(HashMap::new())),
+            _ => return Ok(QDict::new(IndexMap::new())),
         },
-        _ => return Ok(QDict::new(HashMap::new())), // No web module
+        _ => return Ok(QDict::new(IndexMap::new())), // No web module
     };
 
     // Call _get_config()
@@ -935,7 +968,7 @@ fn get_web_config(scope: &mut Scope) -> Result<QDict, String> {
 
     match runtime_config {
         QValue::Dict(d) => Ok(*d),
-        _ => Ok(QDict::new(HashMap::new())),
+        _ => Ok(QDict::new(IndexMap::new())),
     }
 }
 
@@ -1053,7 +1086,7 @@ async fn parse_multipart_body(content_type: &str, body_bytes: Bytes) -> Result<Q
     let mut multipart = Multipart::new(stream, boundary);
 
     // Storage for fields and files
-    let mut fields = HashMap::new();
+    let mut fields = IndexMap::new();
     let mut files = Vec::new();
 
     // Parse all fields
@@ -1075,7 +1108,7 @@ async fn parse_multipart_body(content_type: &str, body_bytes: Bytes) -> Result<Q
                 .map_err(|e| format!("Failed to read file data: {}", e))?;
 
             // Create file metadata dict
-            let mut file_map = HashMap::new();
+            let mut file_map = IndexMap::new();
             file_map.insert("name".to_string(), QValue::Str(QString::new(field_name.clone())));
             file_map.insert("filename".to_string(), QValue::Str(QString::new(filename)));
             file_map.insert("mime_type".to_string(), QValue::Str(QString::new(content_type)));
@@ -1092,7 +1125,7 @@ async fn parse_multipart_body(content_type: &str, body_bytes: Bytes) -> Result<Q
     }
 
     // Build result dict with fields and files
-    let mut result = HashMap::new();
+    let mut result = IndexMap::new();
     result.insert("fields".to_string(), QValue::Dict(Box::new(QDict::new(fields))));
     result.insert("files".to_string(), QValue::Array(QArray::new(files)));
 
@@ -1114,7 +1147,7 @@ fn build_request_dict_from_parts(parts: axum::http::request::Parts, body_bytes:
     let query = if let Some(query_str) = parts.uri.query() {
         parse_query_string(query_str)
     } else {
-        QDict::new(HashMap::new())
+        QDict::new(IndexMap::new())
     };
 
     // Extract headers
@@ -1173,7 +1206,7 @@ fn build_request_dict_from_parts(parts: axum::http::request::Parts, body_bytes:
         .unwrap_or(0);
 
     // Build request dict
-    let mut map = HashMap::new();
+    let mut map = IndexMap::new();
     map.insert("method".to_string(), QValue::Str(method));
     map.insert("path".to_string(), QValue::Str(path));
     map.insert("query_string".to_string(), QValue::Str(query_string));
@@ -1195,7 +1228,7 @@ fn build_request_dict_from_parts(parts: axum::http::request::Parts, body_bytes:
 
 /// Parse query string into Dict
 fn parse_query_string(query: &str) -> QDict {
-    let mut map = HashMap::new();
+    let mut map = IndexMap::new();
 
     for pair in query.split('&') {
         if let Some((key, value)) = pair.split_once('=') {
@@ -1213,7 +1246,7 @@ fn parse_query_string(query: &str) -> QDict {
 
 /// Convert HTTP headers to Quest Dict
 fn headers_to_dict(headers: &HeaderMap) -> QDict {
-    let mut map = HashMap::new();
+    let mut map = IndexMap::new();
 
     for (name, value) in headers.iter() {
         let name_str = name.as_str().to_lowercase();
@@ -1226,7 +1259,7 @@ fn headers_to_dict(headers: &HeaderMap) -> QDict {
 
 /// Parse cookies from headers
 fn parse_cookies(headers: &HeaderMap) -> QDict {
-    let mut map = HashMap::new();
+    let mut map = IndexMap::new();
 
     if let Some(cookie_header) = headers.get(header::COOKIE) {
         if let Ok(cookie_str) = cookie_header.to_str() {
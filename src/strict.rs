@@ -0,0 +1,110 @@
+// Strict mode for implicit conversions (`sys.strict`, `#!strict`)
+//
+// By default Quest is permissive: any value can be an if/while condition,
+// bitwise operators coerce their operands to numbers and truncate them, and
+// comparing a string with a number quietly falls back to type ordering.
+// Strict mode turns each of these into a TypeErr. It is enabled per file,
+// either by a `#!strict` line among the comments at the top of the file or by
+// calling `sys.strict(true)`, and applies to code written in that file
+// (including its functions, wherever they are called from), so a strict
+// script doesn't break the modules it uses. Code with no file (the REPL,
+// `quest -e`) shares one setting.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+
+use crate::scope::Scope;
+use crate::types::QValue;
+
+thread_local! {
+    /// Whether any file is in strict mode, so lax programs skip the lookup
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+    /// Files in strict mode; "" stands for code that has no file
+    static FILES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// The strict-mode key for the code running in `scope`
+fn key(scope: &Scope) -> &str {
+    scope.source_file.as_deref().map(String::as_str).unwrap_or("")
+}
+
+/// Whether the code running in `scope` is in strict mode
+#[inline]
+pub fn is_strict(scope: &Scope) -> bool {
+    ACTIVE.with(|active| active.get()) && FILES.with(|files| files.borrow().contains(key(scope)))
+}
+
+/// Turn strict mode on or off for the code running in `scope`
+pub fn set(scope: &Scope, enabled: bool) {
+    let key = key(scope).to_string();
+    FILES.with(|files| {
+        let mut files = files.borrow_mut();
+        if enabled {
+            files.insert(key);
+        } else {
+            files.remove(&key);
+        }
+        ACTIVE.with(|active| active.set(!files.is_empty()));
+    });
+}
+
+/// Enable strict mode for `path` if its source has a `#!strict` pragma in the
+/// comments before its first line of code
+pub fn check_pragma(path: &str, source: &str) {
+    let has_pragma = source.lines()
+        .map(str::trim)
+        .take_while(|line| line.is_empty() || line.starts_with('#'))
+        .any(|line| line == "#!strict");
+    if has_pragma {
+        FILES.with(|files| files.borrow_mut().insert(path.to_string()));
+        ACTIVE.with(|active| active.set(true));
+    }
+}
+
+/// Truthiness of an if/elif/while condition; strict code requires a Bool
+pub fn condition(value: &QValue, scope: &Scope, statement: &str) -> Result<bool, String> {
+    match value {
+        QValue::Bool(b) => Ok(b.value),
+        _ if is_strict(scope) => Err(format!(
+            "TypeErr: {} condition must be Bool, got {} (strict mode)", statement, value.as_obj().cls()
+        )),
+        _ => Ok(value.as_bool()),
+    }
+}
+
+/// An operand of `|`, `^`, `&`, `<<` or `>>`; strict code requires an Int
+/// rather than truncating Floats and coercing other values
+pub fn bitwise_operand(value: &QValue, scope: &Scope, op: &str) -> Result<i64, String> {
+    match value {
+        QValue::Int(i) => Ok(i.value),
+        _ if is_strict(scope) => Err(format!(
+            "TypeErr: Operand of '{}' must be Int, got {} (strict mode)", op, value.as_obj().cls()
+        )),
+        _ => Ok(value.as_num()? as i64),
+    }
+}
+
+/// Check a comparison in strict code: a string can't be compared with a
+/// number, and values of unrelated types can't be ordered
+pub fn check_comparison(left: &QValue, right: &QValue, op: &str, scope: &Scope) -> Result<(), String> {
+    if !is_strict(scope) {
+        return Ok(());
+    }
+    let numeric = |v: &QValue| matches!(v, QValue::Int(_) | QValue::Float(_) | QValue::Decimal(_) | QValue::BigInt(_));
+    let mismatched = match op {
+        "==" | "!=" => {
+            (matches!(left, QValue::Str(_)) && numeric(right)) || (numeric(left) && matches!(right, QValue::Str(_)))
+        }
+        _ => {
+            let left_cls = left.as_obj().cls();
+            let right_cls = right.as_obj().cls();
+            left_cls != right_cls && !(numeric(left) && numeric(right))
+        }
+    };
+    if mismatched {
+        return Err(format!(
+            "TypeErr: Cannot compare {} and {} with '{}' (strict mode)", left.as_obj().cls(), right.as_obj().cls(), op
+        ));
+    }
+    Ok(())
+}
@@ -18,28 +18,35 @@ pub fn parse_string(s: &str) -> String {
     }
 }
 
-/// Format a value according to a Rust-style format specification
-/// Supports: [fill][align][sign][#][0][width][.precision][type]
+/// Format a value according to a Python-style format specification
+/// Supports: [[fill]align][sign][#][0][width][,|_][.precision][type]
+///
+/// Types: `d` for Int and BigInt; `x`, `X`, `b`, `o` for Int, BigInt (and
+/// truncated Floats); `f`, `F`, `e`, `E`, `g`, `G`, `%` for any number; `s`
+/// for strings. Numbers align right by default and text aligns left; `=` (or a
+/// leading `0` on the width) pads between the sign and the digits.
 pub fn format_value(value: &QValue, spec: &str) -> Result<String, String> {
-    // Parse format spec: [fill][align][sign][#][0][width][.precision][type]
     let mut fill = ' ';
-    let mut align = '>'; // default right-align for numbers
+    let mut explicit_fill = false;
+    let mut align: Option<char> = None;
     let mut sign = '-';
     let mut alternate = false;
     let mut width: Option<usize> = None;
+    let mut grouping: Option<char> = None;
     let mut precision: Option<usize> = None;
-    let mut format_type = "";
 
     let chars: Vec<char> = spec.chars().collect();
     let mut i = 0;
+    let is_align = |c: char| matches!(c, '<' | '>' | '^' | '=');
 
     // Check for fill+align (must be first if present)
-    if chars.len() >= 2 && (chars[1] == '<' || chars[1] == '>' || chars[1] == '^') {
+    if chars.len() >= 2 && is_align(chars[1]) {
         fill = chars[0];
-        align = chars[1];
+        explicit_fill = true;
+        align = Some(chars[1]);
         i = 2;
-    } else if !chars.is_empty() && (chars[0] == '<' || chars[0] == '>' || chars[0] == '^') {
-        align = chars[0];
+    } else if !chars.is_empty() && is_align(chars[0]) {
+        align = Some(chars[0]);
         i = 1;
     }
 
@@ -55,9 +62,12 @@ pub fn format_value(value: &QValue, spec: &str) -> Result<String, String> {
         i += 1;
     }
 
-    // Check for zero padding
+    // Zero padding: zeros after the sign, unless an alignment is given
     if i < chars.len() && chars[i] == '0' {
-        fill = '0';
+        if !explicit_fill {
+            fill = '0';
+        }
+        align.get_or_insert('=');
         i += 1;
     }
 
@@ -68,7 +78,13 @@ pub fn format_value(value: &QValue, spec: &str) -> Result<String, String> {
         i += 1;
     }
     if !width_str.is_empty() {
-        width = Some(width_str.parse().unwrap());
+        width = Some(width_str.parse().map_err(|_| format!("ValueErr: Format width too large: {}", width_str))?);
+    }
+
+    // Thousands separator
+    if i < chars.len() && (chars[i] == ',' || chars[i] == '_') {
+        grouping = Some(chars[i]);
+        i += 1;
     }
 
     // Parse precision
@@ -79,158 +95,240 @@ pub fn format_value(value: &QValue, spec: &str) -> Result<String, String> {
             prec_str.push(chars[i]);
             i += 1;
         }
-        if !prec_str.is_empty() {
-            precision = Some(prec_str.parse().unwrap());
+        if prec_str.is_empty() {
+            return Err(format!("ValueErr: Format specifier missing precision: '{}'", spec));
         }
+        precision = Some(prec_str.parse().map_err(|_| format!("ValueErr: Format precision too large: {}", prec_str))?);
     }
 
-    // Parse format type (rest of string)
-    if i < chars.len() {
-        format_type = &spec[i..];
+    // Format type: at most one character
+    let format_type: String = chars[i..].iter().collect();
+    if format_type.chars().count() > 1 {
+        return Err(format!("ValueErr: Invalid format specifier '{}'", spec));
     }
+    let unknown = || format!("ValueErr: Unknown format code '{}' for {}", format_type, value.as_obj().cls());
 
-    // Format the value based on type
-    let formatted = match value {
+    // Numbers are formatted as (negative, prefix, digits) so padding can go
+    // between the sign and the digits
+    let number: Option<(bool, String, String)> = match value {
         QValue::Int(n) => {
             let num = n.value;
-            let base_str = match format_type {
-                "x" => format!("{:x}", num),
-                "X" => format!("{:X}", num),
-                "b" => format!("{:b}", num),
-                "o" => format!("{:o}", num),
-                "e" => {
-                    if let Some(prec) = precision {
-                        format!("{:.prec$e}", num as f64, prec = prec)
-                    } else {
-                        format!("{:e}", num as f64)
-                    }
+            let magnitude = num.unsigned_abs();
+            match format_type.as_str() {
+                "x" | "X" | "b" | "o" => {
+                    let digits = match format_type.as_str() {
+                        "x" => format!("{:x}", magnitude),
+                        "X" => format!("{:X}", magnitude),
+                        "b" => format!("{:b}", magnitude),
+                        _ => format!("{:o}", magnitude),
+                    };
+                    Some((num < 0, radix_prefix(&format_type, alternate), digits))
                 }
-                "E" => {
-                    if let Some(prec) = precision {
-                        format!("{:.prec$E}", num as f64, prec = prec)
-                    } else {
-                        format!("{:E}", num as f64)
-                    }
+                "d" => Some((num < 0, String::new(), magnitude.to_string())),
+                "" if precision.is_none() => Some((num < 0, String::new(), magnitude.to_string())),
+                "" | "f" | "F" | "e" | "E" | "g" | "G" | "%" => {
+                    let decimal = rust_decimal::Decimal::from(num);
+                    Some((num < 0, String::new(), decimal_digits(decimal.abs(), &format_type, precision, alternate)))
                 }
-                _ => {
-                    // Default number formatting
-                    if let Some(prec) = precision {
-                        format!("{:.prec$}", num as f64, prec = prec)
-                    } else {
-                        format!("{}", num)
-                    }
+                _ => return Err(unknown()),
+            }
+        }
+        QValue::BigInt(n) => {
+            use num_traits::Signed;
+            let magnitude = n.value.abs();
+            let negative = n.value.is_negative();
+            match format_type.as_str() {
+                "" | "d" => Some((negative, String::new(), magnitude.to_string())),
+                "x" | "X" | "b" | "o" => {
+                    let digits = match format_type.as_str() {
+                        "x" => magnitude.to_str_radix(16),
+                        "X" => magnitude.to_str_radix(16).to_uppercase(),
+                        "b" => magnitude.to_str_radix(2),
+                        _ => magnitude.to_str_radix(8),
+                    };
+                    Some((negative, radix_prefix(&format_type, alternate), digits))
                 }
-            };
-
-            // Add alternate form prefix if requested
-            let mut result = if alternate {
-                match format_type {
-                    "x" | "X" => format!("0x{}", base_str),
-                    "b" => format!("0b{}", base_str),
-                    "o" => format!("0o{}", base_str),
-                    _ => base_str,
+                "f" | "F" | "e" | "E" | "g" | "G" | "%" => {
+                    use num_traits::ToPrimitive;
+                    let float = magnitude.to_f64().unwrap_or(f64::INFINITY);
+                    Some((negative, String::new(), float_digits(float, &format_type, precision, alternate)))
                 }
-            } else {
-                base_str
-            };
-
-            // Add sign if requested
-            if sign == '+' && num >= 0 {
-                result = format!("+{}", result);
-            } else if sign == ' ' && num >= 0 {
-                result = format!(" {}", result);
+                _ => return Err(unknown()),
             }
-
-            result
         }
         QValue::Float(n) => {
             let num = n.value;
-            let base_str = match format_type {
-                "x" => format!("{:x}", num as i64),
-                "X" => format!("{:X}", num as i64),
-                "b" => format!("{:b}", num as i64),
-                "o" => format!("{:o}", num as i64),
-                "e" => {
-                    if let Some(prec) = precision {
-                        format!("{:.prec$e}", num, prec = prec)
-                    } else {
-                        format!("{:e}", num)
-                    }
+            let negative = num < 0.0;
+            match format_type.as_str() {
+                "x" | "X" | "b" | "o" => {
+                    let truncated = (num as i64).unsigned_abs();
+                    let digits = match format_type.as_str() {
+                        "x" => format!("{:x}", truncated),
+                        "X" => format!("{:X}", truncated),
+                        "b" => format!("{:b}", truncated),
+                        _ => format!("{:o}", truncated),
+                    };
+                    Some((negative, radix_prefix(&format_type, alternate), digits))
                 }
-                "E" => {
-                    if let Some(prec) = precision {
-                        format!("{:.prec$E}", num, prec = prec)
-                    } else {
-                        format!("{:E}", num)
-                    }
+                "" if precision.is_none() => {
+                    // The value's display form, which follows sys.set_float_format
+                    let shown = n.str();
+                    Some((negative, String::new(), shown.trim_start_matches('-').to_string()))
                 }
-                _ => {
-                    // Default number formatting
-                    if let Some(prec) = precision {
-                        format!("{:.prec$}", num, prec = prec)
-                    } else {
-                        format!("{}", num)
-                    }
+                "" | "f" | "F" | "e" | "E" | "g" | "G" | "%" => {
+                    Some((negative, String::new(), float_digits(num.abs(), &format_type, precision, alternate)))
                 }
-            };
-
-            // Add alternate form prefix if requested
-            let mut result = if alternate {
-                match format_type {
-                    "x" | "X" => format!("0x{}", base_str),
-                    "b" => format!("0b{}", base_str),
-                    "o" => format!("0o{}", base_str),
-                    _ => base_str,
+                _ => return Err(unknown()),
+            }
+        }
+        QValue::Decimal(d) => {
+            let negative = d.value.is_sign_negative() && !d.value.is_zero();
+            match format_type.as_str() {
+                "" | "f" | "F" | "e" | "E" | "g" | "G" | "%" => {
+                    Some((negative, String::new(), decimal_digits(d.value.abs(), &format_type, precision, alternate)))
                 }
-            } else {
-                base_str
-            };
-
-            // Add sign if requested
-            if sign == '+' && num >= 0.0 {
-                result = format!("+{}", result);
-            } else if sign == ' ' && num >= 0.0 {
-                result = format!(" {}", result);
+                _ => return Err(unknown()),
             }
-
-            result
         }
-        QValue::Str(s) => {
-            if let Some(prec) = precision {
-                s.value[..prec.min(s.value.len())].to_string()
+        _ => None,
+    };
+
+    let (body, numeric) = match number {
+        Some((negative, prefix, digits)) => {
+            let digits = match grouping {
+                Some(separator) => group_digits(&digits, separator, matches!(format_type.as_str(), "x" | "X" | "b" | "o")),
+                None => digits,
+            };
+            let sign_str = if negative {
+                "-"
             } else {
-                s.value.as_ref().clone()
+                match sign {
+                    '+' => "+",
+                    ' ' => " ",
+                    _ => "",
+                }
+            };
+            (vec![format!("{}{}", sign_str, prefix), digits], true)
+        }
+        None => {
+            if !matches!(format_type.as_str(), "" | "s") {
+                return Err(unknown());
             }
+            let text = match value {
+                QValue::Str(s) => match precision {
+                    Some(prec) => s.value.chars().take(prec).collect(),
+                    None => s.value.as_ref().clone(),
+                },
+                QValue::Bool(b) => b.value.to_string(),
+                QValue::Nil(_) => "nil".to_string(),
+                _ => value.as_str(),
+            };
+            (vec![String::new(), text], false)
         }
-        QValue::Bool(b) => b.value.to_string(),
-        QValue::Nil(_) => "nil".to_string(),
-        _ => value.as_str(),
     };
 
     // Apply width and alignment
-    if let Some(w) = width {
-        if formatted.len() < w {
-            let padding = w - formatted.len();
-            let result = match align {
-                '<' => format!("{}{}", formatted, fill.to_string().repeat(padding)),
-                '>' => format!("{}{}", fill.to_string().repeat(padding), formatted),
-                '^' => {
-                    let left_pad = padding / 2;
-                    let right_pad = padding - left_pad;
-                    format!("{}{}{}",
-                        fill.to_string().repeat(left_pad),
-                        formatted,
-                        fill.to_string().repeat(right_pad))
-                }
-                _ => formatted,
+    let len: usize = body.iter().map(|part| part.chars().count()).sum();
+    let padding = width.map(|w| w.saturating_sub(len)).unwrap_or(0);
+    let pad = |n: usize| fill.to_string().repeat(n);
+    let align = align.unwrap_or(if numeric { '>' } else { '<' });
+    Ok(match align {
+        '<' => format!("{}{}{}", body[0], body[1], pad(padding)),
+        '^' => {
+            let left_pad = padding / 2;
+            format!("{}{}{}{}", pad(left_pad), body[0], body[1], pad(padding - left_pad))
+        }
+        '=' => format!("{}{}{}", body[0], pad(padding), body[1]),
+        _ => format!("{}{}{}", pad(padding), body[0], body[1]),
+    })
+}
+
+/// "0x", "0b" or "0o" for the alternate form of a radix format
+fn radix_prefix(format_type: &str, alternate: bool) -> String {
+    match (alternate, format_type) {
+        (true, "x") => "0x",
+        (true, "X") => "0X",
+        (true, "b") => "0b",
+        (true, "o") => "0o",
+        _ => "",
+    }.to_string()
+}
+
+/// Digits of a non-negative float for the f/F/e/E/g/G/% types; with no type
+/// the precision gives a fixed number of places
+fn float_digits(num: f64, format_type: &str, precision: Option<usize>, alternate: bool) -> String {
+    match format_type {
+        "e" | "E" => {
+            let digits = match precision {
+                Some(prec) => format!("{:.prec$e}", num, prec = prec),
+                None => format!("{:e}", num),
             };
-            Ok(result)
+            if format_type == "E" { digits.to_uppercase() } else { digits }
+        }
+        "g" | "G" => {
+            let digits = general_digits(num, precision.unwrap_or(6).max(1), alternate);
+            if format_type == "G" { digits.to_uppercase() } else { digits }
+        }
+        "%" => format!("{}%", crate::types::float::to_fixed(num * 100.0, precision.unwrap_or(6))),
+        "F" => crate::types::float::to_fixed(num, precision.unwrap_or(6)).to_uppercase(),
+        "f" => crate::types::float::to_fixed(num, precision.unwrap_or(6)),
+        _ => crate::types::float::to_fixed(num, precision.unwrap_or(0)),
+    }
+}
+
+/// Digits of a non-negative Decimal (or Int) for the number format types. A
+/// Decimal keeps its own digits unless a precision is given.
+fn decimal_digits(num: rust_decimal::Decimal, format_type: &str, precision: Option<usize>, alternate: bool) -> String {
+    use rust_decimal::prelude::ToPrimitive;
+    match (format_type, precision) {
+        ("" | "f" | "F", None) => num.to_string(),
+        ("" | "f" | "F", Some(prec)) => crate::types::decimal::to_fixed(num, prec),
+        ("%", _) => format!("{}%", crate::types::decimal::to_fixed(num * rust_decimal::Decimal::ONE_HUNDRED, precision.unwrap_or(6))),
+        _ => float_digits(num.to_f64().unwrap_or(0.0), format_type, precision, alternate),
+    }
+}
+
+/// The `g` format: fixed notation for moderate exponents, scientific
+/// otherwise, with `precision` significant digits and trailing zeros removed
+/// (kept in the alternate form)
+fn general_digits(num: f64, precision: usize, alternate: bool) -> String {
+    if num == 0.0 || !num.is_finite() {
+        return if num.is_nan() { "NaN".to_string() } else if num.is_infinite() { "inf".to_string() } else { "0".to_string() };
+    }
+    let scientific = format!("{:.prec$e}", num, prec = precision - 1);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let strip = |s: String| -> String {
+        if alternate || !s.contains('.') {
+            s
         } else {
-            Ok(formatted)
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
         }
+    };
+    if exponent < -4 || exponent >= precision as i32 {
+        format!("{}e{}", strip(mantissa.to_string()), exponent)
     } else {
-        Ok(formatted)
+        strip(crate::types::float::to_fixed(num, (precision as i32 - 1 - exponent).max(0) as usize))
+    }
+}
+
+/// Insert `separator` between groups of digits in the integer part of
+/// `digits`: every four digits in radix formats, every three otherwise
+fn group_digits(digits: &str, separator: char, radix: bool) -> String {
+    let (integer, rest) = if radix {
+        (digits, "")
+    } else {
+        digits.split_at(digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len()))
+    };
+    let size = if radix { 4 } else { 3 };
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % size == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(c);
     }
+    grouped + rest
 }
 
 /// Process escape sequences in strings: \n, \t, \r, \\, \"
@@ -0,0 +1,88 @@
+// "Did you mean ...?" hints for failed name and attribute lookups
+//
+// When a variable, module member, method or field isn't found, the closest
+// name among the ones that were available is appended to the error message.
+// Closeness is the edit distance with adjacent transpositions counted as one
+// edit, so `lenght` finds `length`. A candidate must be within a third of the
+// name's length (at least one edit); names differing only in case always
+// match. Dunder names are only suggested for names that start with `__`.
+
+use crate::scope::Scope;
+use crate::types::{QModule, QType};
+use crate::BUILTIN_FUNCTIONS;
+
+/// The candidate closest to `name`, if any is close enough to be a likely typo
+pub fn closest<S: AsRef<str>>(name: &str, candidates: &[S]) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    let lower = name.to_lowercase();
+    let mut best: Option<(usize, &str)> = None;
+    for candidate in candidates.iter().map(|c| c.as_ref()) {
+        if candidate == name || (candidate.starts_with("__") && !name.starts_with("__")) {
+            continue;
+        }
+        let distance = if candidate.to_lowercase() == lower { 0 } else { distance(name, candidate) };
+        if distance > max_distance {
+            continue;
+        }
+        let better = match best {
+            Some((d, c)) => distance < d || (distance == d && candidate < c),
+            None => true,
+        };
+        if better {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate.to_string())
+}
+
+/// " (did you mean 'x'?)" for the closest candidate, or an empty string
+pub fn did_you_mean<S: AsRef<str>>(name: &str, candidates: &[S]) -> String {
+    closest(name, candidates)
+        .map(|candidate| format!(" (did you mean '{}'?)", candidate))
+        .unwrap_or_default()
+}
+
+/// Suggestion for an undefined variable or function, from the variables in
+/// scope and the builtin functions
+pub fn for_variable(name: &str, scope: &Scope) -> String {
+    let mut candidates = scope.variable_names();
+    candidates.extend(BUILTIN_FUNCTIONS.iter().map(|name| name.to_string()));
+    did_you_mean(name, &candidates)
+}
+
+/// Suggestion for a missing module member, from the module's public members
+pub fn for_member(name: &str, module: &QModule) -> String {
+    did_you_mean(name, &module.public_member_names())
+}
+
+/// Suggestion for a missing instance method, from the type's instance
+/// methods and fields (a field may hold a callable)
+pub fn for_method(name: &str, qtype: &QType) -> String {
+    let mut candidates: Vec<&str> = qtype.methods.keys()
+        .filter(|method| !method.starts_with("__class__:"))
+        .map(|method| method.as_str())
+        .collect();
+    candidates.extend(qtype.fields.iter().map(|field| field.name.as_str()));
+    did_you_mean(name, &candidates)
+}
+
+/// Suggestion for a missing static method, from the type's static methods
+pub fn for_static_method(name: &str, qtype: &QType) -> String {
+    let candidates: Vec<&str> = qtype.methods.keys()
+        .filter_map(|method| method.strip_prefix("__class__:"))
+        .collect();
+    did_you_mean(name, &candidates)
+}
+
+/// Suggestion for a missing field, from the type's fields
+pub fn for_field(name: &str, qtype: &QType) -> String {
+    let candidates: Vec<&str> = qtype.fields.iter().map(|field| field.name.as_str()).collect();
+    did_you_mean(name, &candidates)
+}
+
+/// Edit distance between `a` and `b`, counting a swap of adjacent characters
+/// as a single edit (the same measure as std/text/fuzzy's
+/// `levenshtein(a, b, transpositions: true)`)
+fn distance(a: &str, b: &str) -> usize {
+    strsim::osa_distance(a, b)
+}
@@ -0,0 +1,168 @@
+// Execution tracing for `quest run --trace`
+//
+// Every statement is logged as it starts: the time since the run began, its
+// file:line, the grammar rule it parsed as and the first line of its source.
+// With call tracing on, user function calls are logged too, with their bound
+// arguments and return value, and everything inside a call is indented one
+// level deeper. Module filters keep only events from files whose path contains
+// one of the given strings. Like coverage, statements are located through
+// `Scope::source_file`, so code without a known file is not traced.
+
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::scope::Scope;
+use crate::types::{QUserFun, QValue};
+use crate::Rule;
+
+/// Longest source excerpt or value shown before it is cut off with "..."
+const MAX_TEXT: usize = 80;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    static CALLS: Cell<bool> = const { Cell::new(false) };
+    static MODULES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static OUTPUT: RefCell<Option<File>> = const { RefCell::new(None) };
+    static START: Cell<Option<Instant>> = const { Cell::new(None) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Location of the statement being run, used for the calls it makes
+    static LOCATION: RefCell<Option<(Rc<String>, usize)>> = const { RefCell::new(None) };
+}
+
+/// What to trace; built from the `quest run` flags
+#[derive(Default)]
+pub struct TraceOptions {
+    pub calls: bool,
+    pub modules: Vec<String>,
+    pub output: Option<String>,
+}
+
+/// Start tracing for the rest of the process
+pub fn enable(options: TraceOptions) -> Result<(), String> {
+    if let Some(path) = &options.output {
+        let file = File::create(path).map_err(|e| format!("Failed to create trace file '{}': {}", path, e))?;
+        OUTPUT.with(|out| *out.borrow_mut() = Some(file));
+    }
+    CALLS.with(|c| c.set(options.calls));
+    MODULES.with(|m| *m.borrow_mut() = options.modules);
+    START.with(|s| s.set(Some(Instant::now())));
+    ENABLED.with(|e| e.set(true));
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.with(|e| e.get())
+}
+
+/// Log the statement starting at `line` (relative to the parsed text)
+pub fn statement(scope: &Scope, line: usize, rule: Rule, source: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let Some(file) = &scope.source_file else {
+        return;
+    };
+    let line = line + scope.line_offset;
+    LOCATION.with(|loc| *loc.borrow_mut() = Some((Rc::clone(file), line)));
+    if !matches_module(file) {
+        return;
+    }
+    let text = source.lines().next().unwrap_or("").trim();
+    emit(&format!("{}:{} {:?}: {}", display_path(file), line, rule, truncate(text)));
+}
+
+/// Log a call to `func` once its parameters are bound in `func_scope`. The
+/// returned guard keeps the call's statements indented until it is dropped.
+pub fn enter_call(func: &QUserFun, name: &str, func_scope: &Scope) -> CallGuard {
+    if !is_enabled() || !CALLS.with(|c| c.get()) {
+        return CallGuard { traced: false, caller: None };
+    }
+    let caller = LOCATION.with(|loc| loc.borrow().clone());
+    let traced = func.source_file.as_ref().is_none_or(|file| matches_module(file));
+    if traced {
+        let mut args: Vec<String> = func.params.iter()
+            .map(|param| format!("{}: {}", param, rep(func_scope.get(param))))
+            .collect();
+        for name in func.varargs.iter().chain(func.kwargs.iter()) {
+            args.push(format!("{}: {}", name, rep(func_scope.get(name))));
+        }
+        let location = caller.as_ref()
+            .map(|(file, line)| format!("{}:{} ", display_path(file), line))
+            .unwrap_or_default();
+        emit(&format!("{}call {}({})", location, name, args.join(", ")));
+    }
+    DEPTH.with(|d| d.set(d.get() + 1));
+    CallGuard { traced, caller }
+}
+
+/// Keeps the statements of a traced call indented, and restores the caller's
+/// location when the call ends; see `enter_call`
+pub struct CallGuard {
+    traced: bool,
+    caller: Option<(Rc<String>, usize)>,
+}
+
+impl CallGuard {
+    /// Log the value the call returned and end it
+    pub fn returned(self, name: &str, value: &QValue) {
+        if self.traced {
+            DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+            emit(&format!("return {} -> {}", name, truncate(&value.as_obj()._rep())));
+            DEPTH.with(|d| d.set(d.get() + 1));
+        }
+    }
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        if is_enabled() && CALLS.with(|c| c.get()) {
+            DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+            LOCATION.with(|loc| *loc.borrow_mut() = self.caller.take());
+        }
+    }
+}
+
+fn matches_module(file: &str) -> bool {
+    MODULES.with(|modules| {
+        let modules = modules.borrow();
+        modules.is_empty() || modules.iter().any(|m| file.contains(m.as_str()))
+    })
+}
+
+fn emit(message: &str) {
+    let elapsed = START.with(|s| s.get()).map(|s| s.elapsed().as_secs_f64() * 1000.0).unwrap_or(0.0);
+    let indent = "  ".repeat(DEPTH.with(|d| d.get()));
+    let line = format!("[trace {:>10.3}ms] {}{}\n", elapsed, indent, message);
+    let written = OUTPUT.with(|out| match out.borrow_mut().as_mut() {
+        Some(file) => {
+            let _ = file.write_all(line.as_bytes());
+            true
+        }
+        None => false,
+    });
+    if !written {
+        let _ = std::io::stderr().write_all(line.as_bytes());
+    }
+}
+
+/// A file's path relative to the current directory when it is inside it
+fn display_path(file: &str) -> String {
+    std::env::current_dir().ok()
+        .and_then(|cwd| std::path::Path::new(file).strip_prefix(cwd).ok().map(|p| p.display().to_string()))
+        .unwrap_or_else(|| file.to_string())
+}
+
+fn rep(value: Option<QValue>) -> String {
+    value.map(|v| truncate(&v.as_obj()._rep())).unwrap_or_else(|| "nil".to_string())
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_TEXT {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_TEXT).collect();
+    format!("{}...", cut)
+}
@@ -1,9 +1,9 @@
 use super::*;
 use std::cell::RefCell;
 use std::rc::Rc;
-use crate::{arg_err, attr_err, index_err};
+use crate::{arg_err, attr_err, index_err, type_err, value_err};
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct QArray {
     pub elements: Rc<RefCell<Vec<QValue>>>,
     pub id: u64,
@@ -263,34 +263,27 @@ impl QArray {
                 }
                 Ok(QValue::Bool(QBool::new(self.elements.borrow().is_empty())))
             }
-            "sort" => {
-                // Mutates: Sort array in place, returns self for chaining
-                if !args.is_empty() {
-                    return arg_err!("sort expects 0 arguments, got {}", args.len());
-                }
-                let mut elements = self.elements.borrow_mut();
-
-                // Sort with type-aware comparison
-                elements.sort_by(|a, b| {
-                    compare_values(a, b).unwrap_or(std::cmp::Ordering::Equal)
-                });
-
-                drop(elements);  // Release borrow before cloning self
-                Ok(QValue::Array(self.clone()))
-            }
-            "sorted" => {
-                // Non-mutating: Return sorted copy
-                if !args.is_empty() {
-                    return arg_err!("sorted expects 0 arguments, got {}", args.len());
+            "sort" | "sorted" => {
+                // sort() mutates and returns self for chaining; sorted() returns a copy.
+                // natural: true orders digit runs by value, so "file2" < "file10"
+                let natural = match args.as_slice() {
+                    [] => false,
+                    [QValue::Dict(opts)] if opts.has("natural") => opts.get("natural").is_some_and(|v| v.as_bool()),
+                    _ => return arg_err!("{} expects 0 arguments or natural:, got {}", method_name, args.len()),
+                };
+                let compare = |a: &QValue, b: &QValue| match (a, b) {
+                    (QValue::Str(x), QValue::Str(y)) if natural => crate::modules::collate::natural_cmp(&x.value, &y.value),
+                    // Type-aware comparison
+                    _ => compare_values(a, b).unwrap_or(std::cmp::Ordering::Equal),
+                };
+                if method_name == "sort" {
+                    self.elements.borrow_mut().sort_by(compare);
+                    Ok(QValue::Array(self.clone()))
+                } else {
+                    let mut new_elements = self.elements.borrow().clone();
+                    new_elements.sort_by(compare);
+                    Ok(QValue::Array(QArray::new(new_elements)))
                 }
-                let mut new_elements = self.elements.borrow().clone();
-
-                // Sort with type-aware comparison
-                new_elements.sort_by(|a, b| {
-                    compare_values(a, b).unwrap_or(std::cmp::Ordering::Equal)
-                });
-
-                Ok(QValue::Array(QArray::new(new_elements)))
             }
             "clear" => {
                 // Mutates: Remove all elements, returns self for chaining
@@ -347,11 +340,88 @@ impl QArray {
 
                 Ok(elements.remove(index))
             }
+            "lazy" => {
+                // Lazy pipeline over this array (see QIterator)
+                if !args.is_empty() {
+                    return arg_err!("lazy expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Iterator(Box::new(QIterator::new(self))))
+            }
+            "zip" => {
+                // Pair elements with the other arrays': [[a0, b0], [a1, b1], ...],
+                // as long as the shortest array
+                let mut others = Vec::with_capacity(args.len());
+                for arg in &args {
+                    match arg {
+                        QValue::Array(other) => others.push(other.elements.borrow().clone()),
+                        _ => return type_err!("zip expects Array arguments, got {}", arg.as_obj().cls()),
+                    }
+                }
+                let elements = self.elements.borrow();
+                let len = others.iter().map(Vec::len).fold(elements.len(), usize::min);
+                let tuples = (0..len)
+                    .map(|i| {
+                        let mut tuple = Vec::with_capacity(others.len() + 1);
+                        tuple.push(elements[i].clone());
+                        tuple.extend(others.iter().map(|other| other[i].clone()));
+                        QValue::Array(QArray::new(tuple))
+                    })
+                    .collect();
+                Ok(QValue::Array(QArray::new(tuples)))
+            }
+            "flatten" => {
+                // Flatten nested arrays, all the way down or `depth` levels
+                if args.len() > 1 {
+                    return arg_err!("flatten expects 0 or 1 arguments (depth), got {}", args.len());
+                }
+                let depth = match args.first() {
+                    None | Some(QValue::Nil(_)) => None,
+                    Some(QValue::Int(n)) if n.value >= 0 => Some(n.value as usize),
+                    Some(QValue::Int(n)) => return value_err!("flatten depth must not be negative, got {}", n.value),
+                    Some(other) => return type_err!("flatten depth must be Int, got {}", other.as_obj().cls()),
+                };
+                let mut flat = Vec::new();
+                flatten_into(&self.elements.borrow(), depth, &mut flat);
+                Ok(QValue::Array(QArray::new(flat)))
+            }
+            "chunk" | "window" => {
+                // chunk(n): consecutive pieces of n (the last may be shorter);
+                // window(n): every run of n adjacent elements
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument (size), got {}", method_name, args.len());
+                }
+                let size = match &args[0] {
+                    QValue::Int(n) if n.value > 0 => n.value as usize,
+                    QValue::Int(n) => return value_err!("{} size must be positive, got {}", method_name, n.value),
+                    other => return type_err!("{} size must be Int, got {}", method_name, other.as_obj().cls()),
+                };
+                let elements = self.elements.borrow();
+                let to_array = |slice: &[QValue]| QValue::Array(QArray::new(slice.to_vec()));
+                let parts = if method_name == "chunk" {
+                    elements.chunks(size).map(to_array).collect()
+                } else {
+                    elements.windows(size).map(to_array).collect()
+                };
+                Ok(QValue::Array(QArray::new(parts)))
+            }
             _ => attr_err!("Array has no method '{}'", method_name),
         }
     }
 }
 
+/// Append `elements` to `out`, expanding nested arrays up to `depth` levels
+/// (all levels when `None`)
+fn flatten_into(elements: &[QValue], depth: Option<usize>, out: &mut Vec<QValue>) {
+    for element in elements {
+        match element {
+            QValue::Array(inner) if depth != Some(0) => {
+                flatten_into(&inner.elements.borrow(), depth.map(|d| d - 1), out);
+            }
+            _ => out.push(element.clone()),
+        }
+    }
+}
+
 impl QObj for QArray {
     fn cls(&self) -> String {
         "Array".to_string()
@@ -384,6 +454,16 @@ impl QObj for QArray {
     }
 }
 
+impl Clone for QArray {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Array", self.id);
+        QArray {
+            elements: Rc::clone(&self.elements),
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QArray {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Array", self.id);
@@ -2,7 +2,7 @@ use super::*;
 use std::sync::OnceLock;
 use crate::{attr_err, arg_err};
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct QBool {
     pub value: bool,
     pub id: u64,
@@ -87,6 +87,16 @@ impl QObj for QBool {
     }
 }
 
+impl Clone for QBool {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Bool", self.id);
+        QBool {
+            value: self.value,
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QBool {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Bool", self.id);
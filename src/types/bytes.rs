@@ -1,7 +1,7 @@
 use super::*;
 use crate::{arg_err, attr_err, index_err, value_err};
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct QBytes {
     pub data: Vec<u8>,
     pub id: u64,
@@ -142,6 +142,16 @@ impl QObj for QBytes {
     }
 }
 
+impl Clone for QBytes {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Bytes", self.id);
+        QBytes {
+            data: self.data.clone(),
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QBytes {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Bytes", self.id);
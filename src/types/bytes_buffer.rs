@@ -0,0 +1,467 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use crate::{arg_err, attr_err, index_err, type_err, value_err};
+use crate::control_flow::EvalError;
+use crate::types::*;
+
+/// Buffer contents and the read position
+#[derive(Debug, Default)]
+struct BufferState {
+    data: Vec<u8>,
+    position: usize,
+}
+
+/// A growable byte buffer for building and parsing binary messages
+/// (std/encoding/struct's BytesBuffer).
+///
+/// Writes append to the end; reads consume from the read position, so one
+/// buffer can collect data from a socket or serial port while complete
+/// messages are read off the front. Clones share the same buffer.
+#[derive(Debug)]
+pub struct QBytesBuffer {
+    state: Rc<RefCell<BufferState>>,
+    pub id: u64,
+}
+
+/// A fixed-size number in a read_*/write_* method name
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumKind {
+    U8, I8, U16, I16, U32, I32, U64, I64, F32, F64,
+}
+
+impl NumKind {
+    fn size(self) -> usize {
+        match self {
+            NumKind::U8 | NumKind::I8 => 1,
+            NumKind::U16 | NumKind::I16 => 2,
+            NumKind::U32 | NumKind::I32 | NumKind::F32 => 4,
+            NumKind::U64 | NumKind::I64 | NumKind::F64 => 8,
+        }
+    }
+
+    /// Integer range as (min, max); floats have none
+    fn range(self) -> Option<(i128, i128)> {
+        match self {
+            NumKind::U8 => Some((0, u8::MAX as i128)),
+            NumKind::I8 => Some((i8::MIN as i128, i8::MAX as i128)),
+            NumKind::U16 => Some((0, u16::MAX as i128)),
+            NumKind::I16 => Some((i16::MIN as i128, i16::MAX as i128)),
+            NumKind::U32 => Some((0, u32::MAX as i128)),
+            NumKind::I32 => Some((i32::MIN as i128, i32::MAX as i128)),
+            NumKind::U64 => Some((0, u64::MAX as i128)),
+            NumKind::I64 => Some((i64::MIN as i128, i64::MAX as i128)),
+            NumKind::F32 | NumKind::F64 => None,
+        }
+    }
+}
+
+/// Parse "u16_le", "i8", "f64_be", ... into the number kind and whether it is
+/// little-endian. Single bytes take no suffix; wider numbers require one.
+fn parse_num_type(name: &str) -> Option<(NumKind, bool)> {
+    let (kind, endian) = match name.split_once('_') {
+        Some((kind, endian)) => (kind, Some(endian)),
+        None => (name, None),
+    };
+    let kind = match kind {
+        "u8" => NumKind::U8,
+        "i8" => NumKind::I8,
+        "u16" => NumKind::U16,
+        "i16" => NumKind::I16,
+        "u32" => NumKind::U32,
+        "i32" => NumKind::I32,
+        "u64" => NumKind::U64,
+        "i64" => NumKind::I64,
+        "f32" => NumKind::F32,
+        "f64" => NumKind::F64,
+        _ => return None,
+    };
+    match (kind.size(), endian) {
+        (1, None) => Some((kind, true)),
+        (1, Some(_)) => None,
+        (_, Some("le")) => Some((kind, true)),
+        (_, Some("be")) => Some((kind, false)),
+        _ => None,
+    }
+}
+
+impl QBytesBuffer {
+    pub fn new(data: Vec<u8>) -> Self {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("BytesBuffer", id);
+        QBytesBuffer { state: Rc::new(RefCell::new(BufferState { data, position: 0 })), id }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.borrow().data.len()
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.state.borrow().data.clone()
+    }
+
+    /// Overwrite the buffer at `offset`, growing it as needed (struct.pack_into)
+    pub fn write_at(&self, offset: usize, bytes: &[u8]) -> Result<(), String> {
+        let mut state = self.state.borrow_mut();
+        if offset > state.data.len() {
+            return Err(format!("IndexErr: offset {} is past the end of the buffer (length {})", offset, state.data.len()));
+        }
+        let end = offset + bytes.len();
+        if end > state.data.len() {
+            state.data.resize(end, 0);
+        }
+        state.data[offset..end].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Take `n` bytes from the read position
+    fn take(&self, method_name: &str, n: usize) -> Result<Vec<u8>, String> {
+        let mut state = self.state.borrow_mut();
+        let remaining = state.data.len() - state.position;
+        if n > remaining {
+            return Err(format!("IndexErr: {} needs {} bytes, {} remaining", method_name, n, remaining));
+        }
+        let start = state.position;
+        state.position += n;
+        Ok(state.data[start..start + n].to_vec())
+    }
+
+    fn write_number(&self, method_name: &str, kind: NumKind, little: bool, value: &QValue) -> Result<(), String> {
+        let bytes = match kind.range() {
+            Some((min, max)) => {
+                let n: i128 = match value {
+                    QValue::Int(i) => i.value as i128,
+                    QValue::BigInt(b) => b.value.to_i128().unwrap_or(i128::MAX),
+                    QValue::Bool(b) => b.value as i128,
+                    other => return Err(format!("TypeErr: {} expects Int, got {}", method_name, other.as_obj().cls())),
+                };
+                if n < min || n > max {
+                    return Err(format!("ValueErr: {} is out of range for {} ({} to {})", n, method_name, min, max));
+                }
+                let le = (n as u64).to_le_bytes();
+                let mut bytes = le[..kind.size()].to_vec();
+                if !little {
+                    bytes.reverse();
+                }
+                bytes
+            }
+            None => {
+                let f = match value {
+                    QValue::Float(f) => f.value,
+                    QValue::Int(i) => i.value as f64,
+                    QValue::Decimal(d) => d.value.to_f64().unwrap_or(f64::NAN),
+                    other => return Err(format!("TypeErr: {} expects a number, got {}", method_name, other.as_obj().cls())),
+                };
+                match (kind, little) {
+                    (NumKind::F32, true) => (f as f32).to_le_bytes().to_vec(),
+                    (NumKind::F32, false) => (f as f32).to_be_bytes().to_vec(),
+                    (_, true) => f.to_le_bytes().to_vec(),
+                    (_, false) => f.to_be_bytes().to_vec(),
+                }
+            }
+        };
+        self.state.borrow_mut().data.extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn read_number(&self, method_name: &str, kind: NumKind, little: bool) -> Result<QValue, String> {
+        let mut bytes = self.take(method_name, kind.size())?;
+        if !little {
+            bytes.reverse();
+        }
+        // Little-endian from here on; widen to 8 bytes, sign-extending signed kinds
+        let negative = bytes.last().is_some_and(|b| b & 0x80 != 0);
+        let signed = matches!(kind, NumKind::I8 | NumKind::I16 | NumKind::I32 | NumKind::I64);
+        let mut wide = [if signed && negative { 0xff } else { 0 }; 8];
+        wide[..bytes.len()].copy_from_slice(&bytes);
+        Ok(match kind {
+            NumKind::F32 => QValue::Float(QFloat::new(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64)),
+            NumKind::F64 => QValue::Float(QFloat::new(f64::from_le_bytes(wide))),
+            NumKind::U64 => {
+                let n = u64::from_le_bytes(wide);
+                match i64::try_from(n) {
+                    Ok(n) => QValue::Int(QInt::new(n)),
+                    Err(_) => QValue::BigInt(QBigInt::new(BigInt::from(n))),
+                }
+            }
+            _ => QValue::Int(QInt::new(i64::from_le_bytes(wide))),
+        })
+    }
+
+    fn write_varint(&self, mut n: u64) {
+        let mut state = self.state.borrow_mut();
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                state.data.push(byte);
+                return;
+            }
+            state.data.push(byte | 0x80);
+        }
+    }
+
+    fn read_varint(&self, method_name: &str) -> Result<u64, String> {
+        let mut state = self.state.borrow_mut();
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        let mut offset = state.position;
+        loop {
+            let byte = match state.data.get(offset) {
+                Some(byte) => *byte,
+                None => return Err(format!("IndexErr: {} ran out of bytes in the middle of a varint", method_name)),
+            };
+            offset += 1;
+            if shift >= 64 || (shift == 63 && byte > 1) {
+                return Err(format!("ValueErr: {} varint is longer than 64 bits", method_name));
+            }
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                state.position = offset;
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        // write_u16_le(value), read_i32_be(), ...
+        if let Some((direction, num_type)) = method_name.split_once('_') {
+            if let (Some((kind, little)), "write" | "read") = (parse_num_type(num_type), direction) {
+                if direction == "write" {
+                    if args.len() != 1 {
+                        return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
+                    }
+                    self.write_number(method_name, kind, little, &args[0])?;
+                    return Ok(QValue::BytesBuffer(self.clone()));
+                }
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                return Ok(self.read_number(method_name, kind, little)?);
+            }
+        }
+
+        match method_name {
+            "write_varint" | "write_svarint" => {
+                // Unsigned LEB128 (as in protobuf); svarint zigzag-encodes signed values first
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
+                }
+                let n = match &args[0] {
+                    QValue::Int(i) => i.value,
+                    other => return type_err!("{} expects Int, got {}", method_name, other.as_obj().cls()),
+                };
+                if method_name == "write_svarint" {
+                    self.write_varint(((n << 1) ^ (n >> 63)) as u64);
+                } else if n < 0 {
+                    return value_err!("write_varint expects a non-negative Int, got {} (use write_svarint)", n);
+                } else {
+                    self.write_varint(n as u64);
+                }
+                Ok(QValue::BytesBuffer(self.clone()))
+            }
+            "read_varint" | "read_svarint" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                let n = self.read_varint(method_name)?;
+                if method_name == "read_svarint" {
+                    return Ok(QValue::Int(QInt::new(((n >> 1) as i64) ^ -((n & 1) as i64))));
+                }
+                Ok(match i64::try_from(n) {
+                    Ok(n) => QValue::Int(QInt::new(n)),
+                    Err(_) => QValue::BigInt(QBigInt::new(BigInt::from(n))),
+                })
+            }
+            "write_bytes" => {
+                // Bytes, another BytesBuffer, or an Array of byte values
+                if args.len() != 1 {
+                    return arg_err!("write_bytes expects 1 argument, got {}", args.len());
+                }
+                let bytes = match &args[0] {
+                    QValue::Bytes(b) => b.data.clone(),
+                    QValue::BytesBuffer(b) => b.to_vec(),
+                    QValue::Array(a) => {
+                        let mut bytes = Vec::new();
+                        for value in a.elements.borrow().iter() {
+                            match value {
+                                QValue::Int(i) if (0..=255).contains(&i.value) => bytes.push(i.value as u8),
+                                _ => return value_err!("write_bytes array elements must be Ints from 0 to 255, got {}", value.as_str()),
+                            }
+                        }
+                        bytes
+                    }
+                    other => return type_err!("write_bytes expects Bytes or Array, got {}", other.as_obj().cls()),
+                };
+                self.state.borrow_mut().data.extend_from_slice(&bytes);
+                Ok(QValue::BytesBuffer(self.clone()))
+            }
+            "write_str" => {
+                // UTF-8 encoded, without a length or terminator
+                if args.len() != 1 {
+                    return arg_err!("write_str expects 1 argument, got {}", args.len());
+                }
+                let text = match &args[0] {
+                    QValue::Str(s) => s.value.clone(),
+                    other => return type_err!("write_str expects Str, got {}", other.as_obj().cls()),
+                };
+                self.state.borrow_mut().data.extend_from_slice(text.as_bytes());
+                Ok(QValue::BytesBuffer(self.clone()))
+            }
+            "read_bytes" | "read_str" | "peek" => {
+                // read_bytes(n) / read_str(n): consume n bytes; peek(n): look without consuming.
+                // With no argument, everything remaining.
+                if args.len() > 1 {
+                    return arg_err!("{} expects 0 or 1 arguments (count), got {}", method_name, args.len());
+                }
+                let remaining = self.remaining();
+                let n = match args.first() {
+                    None => remaining,
+                    Some(QValue::Int(n)) if n.value >= 0 => n.value as usize,
+                    Some(QValue::Int(n)) => return value_err!("{} count must not be negative, got {}", method_name, n.value),
+                    Some(other) => return type_err!("{} count must be Int, got {}", method_name, other.as_obj().cls()),
+                };
+                if method_name == "peek" {
+                    let state = self.state.borrow();
+                    let end = state.position + n.min(remaining);
+                    return Ok(QValue::Bytes(QBytes::new(state.data[state.position..end].to_vec())));
+                }
+                let bytes = self.take(method_name, n)?;
+                if method_name == "read_str" {
+                    return match String::from_utf8(bytes) {
+                        Ok(text) => Ok(QValue::Str(QString::new(text))),
+                        Err(e) => value_err!("read_str got invalid UTF-8: {}", e),
+                    };
+                }
+                Ok(QValue::Bytes(QBytes::new(bytes)))
+            }
+            "slice" => {
+                // slice(start, end = len) - Bytes copy; negative indices count from the end
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("slice expects 1 or 2 arguments (start, end), got {}", args.len());
+                }
+                let state = self.state.borrow();
+                let len = state.data.len() as i64;
+                let index = |value: &QValue| -> Result<usize, String> {
+                    let i = match value {
+                        QValue::Int(i) => i.value,
+                        other => return Err(format!("TypeErr: slice indices must be Int, got {}", other.as_obj().cls())),
+                    };
+                    Ok((if i < 0 { len + i } else { i }).clamp(0, len) as usize)
+                };
+                let start = index(&args[0])?;
+                let end = match args.get(1) {
+                    Some(end) => index(end)?,
+                    None => len as usize,
+                };
+                Ok(QValue::Bytes(QBytes::new(state.data[start..end.max(start)].to_vec())))
+            }
+            "to_bytes" => {
+                if !args.is_empty() {
+                    return arg_err!("to_bytes expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Bytes(QBytes::new(self.to_vec())))
+            }
+            "len" | "remaining" | "tell" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                let n = match method_name {
+                    "len" => self.len(),
+                    "remaining" => self.remaining(),
+                    _ => self.state.borrow().position,
+                };
+                Ok(QValue::Int(QInt::new(n as i64)))
+            }
+            "seek" => {
+                // Move the read position
+                if args.len() != 1 {
+                    return arg_err!("seek expects 1 argument (position), got {}", args.len());
+                }
+                let mut state = self.state.borrow_mut();
+                let position = args[0].as_num()? as i64;
+                if position < 0 || position as usize > state.data.len() {
+                    return index_err!("seek position {} is outside the buffer (length {})", position, state.data.len());
+                }
+                state.position = position as usize;
+                Ok(QValue::BytesBuffer(self.clone()))
+            }
+            "compact" => {
+                // Drop the bytes already read, keeping the rest
+                if !args.is_empty() {
+                    return arg_err!("compact expects 0 arguments, got {}", args.len());
+                }
+                let mut state = self.state.borrow_mut();
+                let position = state.position;
+                state.data.drain(..position);
+                state.position = 0;
+                Ok(QValue::BytesBuffer(self.clone()))
+            }
+            "clear" => {
+                if !args.is_empty() {
+                    return arg_err!("clear expects 0 arguments, got {}", args.len());
+                }
+                let mut state = self.state.borrow_mut();
+                state.data.clear();
+                state.position = 0;
+                Ok(QValue::BytesBuffer(self.clone()))
+            }
+            _ => attr_err!("BytesBuffer has no method '{}'", method_name),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        let state = self.state.borrow();
+        state.data.len() - state.position
+    }
+}
+
+impl QObj for QBytesBuffer {
+    fn cls(&self) -> String {
+        "BytesBuffer".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "bytesbuffer"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "bytesbuffer" || type_name == "obj"
+    }
+
+    fn str(&self) -> String {
+        let state = self.state.borrow();
+        format!("<BytesBuffer {} bytes, position {}>", state.data.len(), state.position)
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Growable byte buffer with binary read/write helpers".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Clone for QBytesBuffer {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("BytesBuffer", self.id);
+        QBytesBuffer { state: Rc::clone(&self.state), id: self.id }
+    }
+}
+
+impl Drop for QBytesBuffer {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("BytesBuffer", self.id);
+    }
+}
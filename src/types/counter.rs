@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::{arg_err, attr_err, key_err, type_err};
+use crate::control_flow::EvalError;
+use crate::types::*;
+
+/// Counts stored in first-seen order, so ties in most_common()
+/// come out in the order the keys were first counted.
+#[derive(Debug, Clone, Default)]
+pub struct CounterState {
+    counts: HashMap<SetElement, i64>,
+    order: Vec<SetElement>,
+}
+
+impl CounterState {
+    fn add(&mut self, key: SetElement, n: i64) {
+        match self.counts.get_mut(&key) {
+            Some(count) => *count += n,
+            None => {
+                self.order.push(key.clone());
+                self.counts.insert(key, n);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &SetElement) -> bool {
+        if self.counts.remove(key).is_some() {
+            self.order.retain(|k| k != key);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn get(&self, key: &SetElement) -> i64 {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// (key, count) pairs in first-seen order
+    fn entries(&self) -> Vec<(SetElement, i64)> {
+        self.order.iter().map(|k| (k.clone(), self.counts[k])).collect()
+    }
+
+    /// (key, count) pairs from most to least common
+    fn most_common(&self) -> Vec<(SetElement, i64)> {
+        let mut entries = self.entries();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.1));
+        entries
+    }
+}
+
+/// Multiset of hashable values, like Python's collections.Counter.
+/// Missing keys count as zero.
+#[derive(Debug)]
+pub struct QCounter {
+    pub state: Rc<RefCell<CounterState>>,
+    pub id: u64,
+}
+
+impl QCounter {
+    pub fn new(state: CounterState) -> Self {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("Counter", id);
+        QCounter { state: Rc::new(RefCell::new(state)), id }
+    }
+
+    /// Build a counter from an Array or Str of items, or a Dict of counts
+    pub fn from_qvalue(value: &QValue) -> Result<Self, String> {
+        let counter = QCounter::new(CounterState::default());
+        counter.update(value, 1)?;
+        Ok(counter)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.borrow().counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.borrow().counts.is_empty()
+    }
+
+    /// (key, count) pairs in first-seen order
+    pub fn items(&self) -> Vec<(QValue, i64)> {
+        self.state.borrow().entries().into_iter().map(|(k, n)| (k.to_qvalue(), n)).collect()
+    }
+
+    /// Count of a key, zero when missing
+    pub fn count(&self, key: &QValue) -> Result<QValue, String> {
+        let key = SetElement::from_qvalue(key)?;
+        Ok(QValue::Int(QInt::new(self.state.borrow().get(&key))))
+    }
+
+    pub fn set(&self, key: &QValue, value: &QValue) -> Result<(), String> {
+        let key = SetElement::from_qvalue(key)?;
+        let n = match value {
+            QValue::Int(i) => i.value,
+            other => return type_err!("Counter counts must be Int, got {}", other.as_obj().cls()),
+        };
+        let mut state = self.state.borrow_mut();
+        let current = state.get(&key);
+        state.add(key, n - current);
+        Ok(())
+    }
+
+    /// Add (sign = 1) or subtract (sign = -1) counts from another source
+    fn update(&self, source: &QValue, sign: i64) -> Result<(), String> {
+        let mut additions = Vec::new();
+        match source {
+            QValue::Array(arr) => {
+                for item in arr.elements.borrow().iter() {
+                    additions.push((SetElement::from_qvalue(item)?, 1));
+                }
+            }
+            QValue::Str(s) => {
+                for c in s.value.chars() {
+                    additions.push((SetElement::Str(c.to_string()), 1));
+                }
+            }
+            QValue::Dict(d) => {
+                for (key, count) in d.map.borrow().iter() {
+                    match count {
+                        QValue::Int(n) => additions.push((SetElement::Str(key.clone()), n.value)),
+                        other => return type_err!("Counter counts must be Int, got {} for '{}'", other.as_obj().cls(), key),
+                    }
+                }
+            }
+            QValue::Counter(c) => additions = c.state.borrow().entries(),
+            other => return type_err!("Counter expects Array, Str, Dict or Counter, got {}", other.as_obj().cls()),
+        }
+        let mut state = self.state.borrow_mut();
+        for (key, n) in additions {
+            state.add(key, sign * n);
+        }
+        Ok(())
+    }
+
+    /// `+` and `-` between counters. Like Python, only positive counts are kept.
+    pub fn combine(&self, op: &str, other: &QCounter) -> Result<QCounter, String> {
+        let sign = match op {
+            "+" => 1,
+            "-" => -1,
+            _ => return type_err!("Unsupported operator '{}' for Counter", op),
+        };
+        let mut result = self.state.borrow().clone();
+        for (key, n) in other.state.borrow().entries() {
+            result.add(key, sign * n);
+        }
+        let mut positive = CounterState::default();
+        for (key, n) in result.entries() {
+            if n > 0 {
+                positive.add(key, n);
+            }
+        }
+        Ok(QCounter::new(positive))
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        match method_name {
+            "most_common" => {
+                // most_common(n = all) -> [[key, count], ...] from most to least common
+                if args.len() > 1 {
+                    return arg_err!("most_common expects 0 or 1 arguments, got {}", args.len());
+                }
+                let mut entries = self.state.borrow().most_common();
+                if let Some(n) = args.first() {
+                    entries.truncate(n.as_num()?.max(0.0) as usize);
+                }
+                Ok(pairs_to_array(entries))
+            }
+            "update" | "subtract" => {
+                // Mutates: add or subtract counts, returns self for chaining
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
+                }
+                self.update(&args[0], if method_name == "update" { 1 } else { -1 })?;
+                Ok(QValue::Counter(self.clone()))
+            }
+            "add" => {
+                // add(key, n = 1) - increment one key
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("add expects 1 or 2 arguments (key, n), got {}", args.len());
+                }
+                let key = SetElement::from_qvalue(&args[0])?;
+                let n = match args.get(1) {
+                    Some(QValue::Int(n)) => n.value,
+                    Some(other) => return type_err!("add count must be Int, got {}", other.as_obj().cls()),
+                    None => 1,
+                };
+                self.state.borrow_mut().add(key, n);
+                Ok(QValue::Counter(self.clone()))
+            }
+            "get" => {
+                if args.len() != 1 {
+                    return arg_err!("get expects 1 argument, got {}", args.len());
+                }
+                Ok(self.count(&args[0])?)
+            }
+            "contains" => {
+                if args.len() != 1 {
+                    return arg_err!("contains expects 1 argument, got {}", args.len());
+                }
+                let key = SetElement::from_qvalue(&args[0])?;
+                Ok(QValue::Bool(QBool::new(self.state.borrow().counts.contains_key(&key))))
+            }
+            "remove" => {
+                if args.len() != 1 {
+                    return arg_err!("remove expects 1 argument, got {}", args.len());
+                }
+                let key = SetElement::from_qvalue(&args[0])?;
+                if !self.state.borrow_mut().remove(&key) {
+                    return key_err!("Key {} not found in Counter", args[0].as_str());
+                }
+                Ok(QValue::Counter(self.clone()))
+            }
+            "total" => {
+                if !args.is_empty() {
+                    return arg_err!("total expects 0 arguments, got {}", args.len());
+                }
+                let total: i64 = self.state.borrow().counts.values().sum();
+                Ok(QValue::Int(QInt::new(total)))
+            }
+            "elements" => {
+                // Each key repeated by its count; keys with counts below one are skipped
+                if !args.is_empty() {
+                    return arg_err!("elements expects 0 arguments, got {}", args.len());
+                }
+                let mut elements = Vec::new();
+                for (key, n) in self.state.borrow().entries() {
+                    for _ in 0..n.max(0) {
+                        elements.push(key.to_qvalue());
+                    }
+                }
+                Ok(QValue::Array(QArray::new(elements)))
+            }
+            "keys" | "values" | "items" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                let entries = self.state.borrow().entries();
+                Ok(match method_name {
+                    "keys" => QValue::Array(QArray::new(entries.into_iter().map(|(k, _)| k.to_qvalue()).collect())),
+                    "values" => QValue::Array(QArray::new(entries.into_iter().map(|(_, n)| QValue::Int(QInt::new(n))).collect())),
+                    _ => pairs_to_array(entries),
+                })
+            }
+            "to_dict" => {
+                // Keys become strings, as with any Dict
+                if !args.is_empty() {
+                    return arg_err!("to_dict expects 0 arguments, got {}", args.len());
+                }
+                let map: IndexMap<String, QValue> = self.state.borrow().entries().into_iter()
+                    .map(|(k, n)| (k.to_qvalue().as_str(), QValue::Int(QInt::new(n))))
+                    .collect();
+                Ok(QValue::Dict(Box::new(QDict::new(map))))
+            }
+            "copy" => {
+                if !args.is_empty() {
+                    return arg_err!("copy expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Counter(QCounter::new(self.state.borrow().clone())))
+            }
+            "plus" | "minus" => {
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
+                }
+                let other = match &args[0] {
+                    QValue::Counter(c) => c,
+                    other => return type_err!("{} expects Counter, got {}", method_name, other.as_obj().cls()),
+                };
+                let op = if method_name == "plus" { "+" } else { "-" };
+                Ok(QValue::Counter(self.combine(op, other)?))
+            }
+            "clear" => {
+                if !args.is_empty() {
+                    return arg_err!("clear expects 0 arguments, got {}", args.len());
+                }
+                *self.state.borrow_mut() = CounterState::default();
+                Ok(QValue::Counter(self.clone()))
+            }
+            "len" => {
+                if !args.is_empty() {
+                    return arg_err!("len expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Int(QInt::new(self.len() as i64)))
+            }
+            "empty" => {
+                if !args.is_empty() {
+                    return arg_err!("empty expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Bool(QBool::new(self.is_empty())))
+            }
+            "cls" => Ok(QValue::Str(QString::new(self.cls()))),
+            "_id" => Ok(QValue::Int(QInt::new(self.id as i64))),
+            "str" => Ok(QValue::Str(QString::new(self.str()))),
+            "_rep" => Ok(QValue::Str(QString::new(self._rep()))),
+            "_doc" => Ok(QValue::Str(QString::new(self._doc()))),
+            _ => attr_err!("Unknown method '{}' for Counter", method_name),
+        }
+    }
+}
+
+fn pairs_to_array(entries: Vec<(SetElement, i64)>) -> QValue {
+    QValue::Array(QArray::new(entries.into_iter()
+        .map(|(k, n)| QValue::Array(QArray::new(vec![k.to_qvalue(), QValue::Int(QInt::new(n))])))
+        .collect()))
+}
+
+impl Clone for QCounter {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Counter", self.id);
+        QCounter { state: Rc::clone(&self.state), id: self.id }
+    }
+}
+
+impl Drop for QCounter {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("Counter", self.id);
+    }
+}
+
+impl QObj for QCounter {
+    fn cls(&self) -> String {
+        "Counter".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Counter"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Counter"
+    }
+
+    fn str(&self) -> String {
+        let entries: Vec<String> = self.state.borrow().most_common().into_iter()
+            .map(|(k, n)| match k {
+                SetElement::Str(s) => format!("\"{}\": {}", s, n),
+                other => format!("{}: {}", other.to_qvalue().as_str(), n),
+            })
+            .collect();
+        format!("Counter{{{}}}", entries.join(", "))
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Counter: Counts of hashable values; missing keys count as zero".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
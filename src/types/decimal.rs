@@ -1,7 +1,7 @@
 use super::*;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use std::str::FromStr;
-use crate::{arg_err, attr_err};
+use crate::{arg_err, attr_err, value_err};
 
 #[derive(Debug, Clone)]
 pub struct QDecimal {
@@ -269,6 +269,13 @@ impl QDecimal {
                 }
                 Ok(QValue::Str(QString::new(self.value.to_string())))
             }
+            "to_fixed" => {
+                let places = super::float::places_arg("to_fixed", &args)?.unwrap_or(0);
+                if places < 0 {
+                    return value_err!("to_fixed places must be non-negative, got {}", places);
+                }
+                Ok(QValue::Str(QString::new(to_fixed(self.value, places as usize))))
+            }
             // Number trait methods (aliases and additions)
             "add" => self.call_method("plus", args),
             "sub" => self.call_method("minus", args),
@@ -295,10 +302,9 @@ impl QDecimal {
                 Ok(QValue::Decimal(QDecimal::new(-self.value)))
             }
             "round" => {
-                if !args.is_empty() {
-                    return arg_err!("round expects 0 arguments, got {}", args.len());
-                }
-                Ok(QValue::Decimal(QDecimal::new(self.value.round())))
+                // round() / round(places): halves round away from zero, like Float
+                let places = super::float::places_arg("round", &args)?.unwrap_or(0);
+                Ok(QValue::Decimal(QDecimal::new(round_to(self.value, places))))
             }
             "floor" => {
                 if !args.is_empty() {
@@ -368,6 +374,27 @@ impl QDecimal {
     }
 }
 
+/// `value` rounded to `places` decimal places (to tens, hundreds, ... when
+/// negative), with halves rounded away from zero
+pub fn round_to(value: Decimal, places: i32) -> Decimal {
+    if places >= 0 {
+        return value.round_dp_with_strategy(places as u32, RoundingStrategy::MidpointAwayFromZero);
+    }
+    let mut factor = Decimal::ONE;
+    for _ in 0..-places {
+        match factor.checked_mul(Decimal::TEN) {
+            Some(f) => factor = f,
+            None => return Decimal::ZERO,
+        }
+    }
+    (value / factor).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero) * factor
+}
+
+/// `value` with exactly `places` digits after the decimal point
+pub fn to_fixed(value: Decimal, places: usize) -> String {
+    format!("{:.*}", places, round_to(value, places.min(28) as i32))
+}
+
 impl QObj for QDecimal {
     fn cls(&self) -> String {
         "Decimal".to_string()
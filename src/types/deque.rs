@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::{arg_err, attr_err, index_err, type_err, value_err};
+use crate::control_flow::EvalError;
+use crate::types::*;
+
+/// Double-ended queue with O(1) push and pop at both ends.
+/// An optional maxlen bounds the queue: pushing onto a full deque
+/// discards an element from the opposite end.
+#[derive(Debug)]
+pub struct QDeque {
+    pub elements: Rc<RefCell<VecDeque<QValue>>>,
+    pub maxlen: Option<usize>,
+    pub id: u64,
+}
+
+impl QDeque {
+    pub fn new(elements: Vec<QValue>, maxlen: Option<usize>) -> Self {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("Deque", id);
+        let deque = QDeque {
+            elements: Rc::new(RefCell::new(VecDeque::with_capacity(elements.len()))),
+            maxlen,
+            id,
+        };
+        for elem in elements {
+            deque.push_back(elem);
+        }
+        deque
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.borrow().is_empty()
+    }
+
+    pub fn push_back(&self, value: QValue) {
+        let mut elements = self.elements.borrow_mut();
+        if self.maxlen == Some(0) {
+            return;
+        }
+        if self.maxlen == Some(elements.len()) {
+            elements.pop_front();
+        }
+        elements.push_back(value);
+    }
+
+    pub fn push_front(&self, value: QValue) {
+        let mut elements = self.elements.borrow_mut();
+        if self.maxlen == Some(0) {
+            return;
+        }
+        if self.maxlen == Some(elements.len()) {
+            elements.pop_back();
+        }
+        elements.push_front(value);
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
+        match method_name {
+            "push" | "push_back" => {
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument, got {}", method_name, args.len());
+                }
+                self.push_back(args[0].clone());
+                Ok(QValue::Deque(self.clone()))
+            }
+            "push_front" => {
+                if args.len() != 1 {
+                    return arg_err!("push_front expects 1 argument, got {}", args.len());
+                }
+                self.push_front(args[0].clone());
+                Ok(QValue::Deque(self.clone()))
+            }
+            "pop" | "pop_back" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                match self.elements.borrow_mut().pop_back() {
+                    Some(value) => Ok(value),
+                    None => index_err!("pop from empty Deque"),
+                }
+            }
+            "pop_front" => {
+                if !args.is_empty() {
+                    return arg_err!("pop_front expects 0 arguments, got {}", args.len());
+                }
+                match self.elements.borrow_mut().pop_front() {
+                    Some(value) => Ok(value),
+                    None => index_err!("pop_front from empty Deque"),
+                }
+            }
+            "front" | "back" => {
+                // Peek without removing; nil when empty
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                let elements = self.elements.borrow();
+                let value = if method_name == "front" { elements.front() } else { elements.back() };
+                Ok(value.cloned().unwrap_or(QValue::Nil(QNil)))
+            }
+            "get" => {
+                if args.len() != 1 {
+                    return arg_err!("get expects 1 argument (index), got {}", args.len());
+                }
+                let elements = self.elements.borrow();
+                let index = crate::normalize_index(args[0].as_num()? as i64, elements.len(), "Deque")?;
+                Ok(elements[index].clone())
+            }
+            "extend" | "extend_front" => {
+                // extend_front pushes each element in turn, so they end up reversed
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument (array), got {}", method_name, args.len());
+                }
+                let values = match &args[0] {
+                    QValue::Array(arr) => arr.elements.borrow().clone(),
+                    QValue::Deque(other) => other.elements.borrow().iter().cloned().collect(),
+                    other => return type_err!("{} expects Array or Deque, got {}", method_name, other.as_obj().cls()),
+                };
+                for value in values {
+                    if method_name == "extend" {
+                        self.push_back(value);
+                    } else {
+                        self.push_front(value);
+                    }
+                }
+                Ok(QValue::Deque(self.clone()))
+            }
+            "rotate" => {
+                // rotate(n) moves n elements from the back to the front; negative n rotates left
+                if args.len() > 1 {
+                    return arg_err!("rotate expects 0 or 1 arguments, got {}", args.len());
+                }
+                let n = match args.first() {
+                    Some(v) => v.as_num()? as i64,
+                    None => 1,
+                };
+                let mut elements = self.elements.borrow_mut();
+                if !elements.is_empty() {
+                    let len = elements.len() as i64;
+                    let shift = n.rem_euclid(len) as usize;
+                    elements.rotate_right(shift);
+                }
+                drop(elements);
+                Ok(QValue::Deque(self.clone()))
+            }
+            "contains" => {
+                if args.len() != 1 {
+                    return arg_err!("contains expects 1 argument, got {}", args.len());
+                }
+                let found = self.elements.borrow().iter().any(|v| values_equal(v, &args[0]));
+                Ok(QValue::Bool(QBool::new(found)))
+            }
+            "remove" => {
+                // Remove the first occurrence of a value
+                if args.len() != 1 {
+                    return arg_err!("remove expects 1 argument, got {}", args.len());
+                }
+                let mut elements = self.elements.borrow_mut();
+                match elements.iter().position(|v| values_equal(v, &args[0])) {
+                    Some(index) => {
+                        elements.remove(index);
+                        drop(elements);
+                        Ok(QValue::Deque(self.clone()))
+                    }
+                    None => value_err!("Value {} not found in Deque", args[0].as_str()),
+                }
+            }
+            "clear" => {
+                if !args.is_empty() {
+                    return arg_err!("clear expects 0 arguments, got {}", args.len());
+                }
+                self.elements.borrow_mut().clear();
+                Ok(QValue::Deque(self.clone()))
+            }
+            "len" => {
+                if !args.is_empty() {
+                    return arg_err!("len expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Int(QInt::new(self.len() as i64)))
+            }
+            "empty" => {
+                if !args.is_empty() {
+                    return arg_err!("empty expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Bool(QBool::new(self.is_empty())))
+            }
+            "maxlen" => {
+                if !args.is_empty() {
+                    return arg_err!("maxlen expects 0 arguments, got {}", args.len());
+                }
+                Ok(match self.maxlen {
+                    Some(n) => QValue::Int(QInt::new(n as i64)),
+                    None => QValue::Nil(QNil),
+                })
+            }
+            "to_array" => {
+                if !args.is_empty() {
+                    return arg_err!("to_array expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Array(QArray::new(self.elements.borrow().iter().cloned().collect())))
+            }
+            "cls" => Ok(QValue::Str(QString::new(self.cls()))),
+            "_id" => Ok(QValue::Int(QInt::new(self.id as i64))),
+            "str" => Ok(QValue::Str(QString::new(self.str()))),
+            "_rep" => Ok(QValue::Str(QString::new(self._rep()))),
+            "_doc" => Ok(QValue::Str(QString::new(self._doc()))),
+            _ => attr_err!("Unknown method '{}' for Deque", method_name),
+        }
+    }
+}
+
+impl Clone for QDeque {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Deque", self.id);
+        QDeque {
+            elements: Rc::clone(&self.elements),
+            maxlen: self.maxlen,
+            id: self.id,
+        }
+    }
+}
+
+impl Drop for QDeque {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("Deque", self.id);
+    }
+}
+
+impl QObj for QDeque {
+    fn cls(&self) -> String {
+        "Deque".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Deque"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Deque"
+    }
+
+    fn str(&self) -> String {
+        let strings: Vec<String> = self.elements.borrow().iter().map(|e| e.as_str()).collect();
+        format!("Deque[{}]", strings.join(", "))
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Deque: Double-ended queue with O(1) push and pop at both ends".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
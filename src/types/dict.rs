@@ -1,27 +1,58 @@
 use super::*;
 use std::cell::RefCell;
 use std::rc::Rc;
+use indexmap::IndexMap;
 
-#[derive(Debug, Clone)]
+/// Dict keys iterate in insertion order
+#[derive(Debug)]
 pub struct QDict {
-    pub map: Rc<RefCell<HashMap<String, QValue>>>,
+    pub map: Rc<RefCell<IndexMap<String, QValue>>>,
     pub id: u64,
+    /// Called to create missing keys on index access (DefaultDict)
+    pub default_factory: Option<Rc<QUserFun>>,
 }
 
 impl QDict {
-    pub fn new(map: HashMap<String, QValue>) -> Self {
+    pub fn new(map: IndexMap<String, QValue>) -> Self {
         let id = next_object_id();
         crate::alloc_counter::track_alloc("Dict", id);
         QDict {
             map: Rc::new(RefCell::new(map)),
             id,
+            default_factory: None,
         }
     }
 
+    pub fn with_default(map: IndexMap<String, QValue>, factory: QUserFun) -> Self {
+        let mut dict = Self::new(map);
+        dict.default_factory = Some(Rc::new(factory));
+        dict
+    }
+
     pub fn get(&self, key: &str) -> Option<QValue> {
         self.map.borrow().get(key).cloned()
     }
 
+    /// Index access: like get(), but a missing key is created by calling the
+    /// default factory when the dict has one
+    pub fn get_or_default(&self, key: &str, scope: &mut crate::Scope) -> Result<Option<QValue>, EvalError> {
+        if let Some(value) = self.get(key) {
+            return Ok(Some(value));
+        }
+        let factory = match &self.default_factory {
+            Some(factory) => Rc::clone(factory),
+            None => return Ok(None),
+        };
+        let value = crate::function_call::call_user_function(
+            &factory,
+            crate::function_call::CallArguments::positional_only(Vec::new()),
+            scope,
+            None,
+        )?;
+        self.map.borrow_mut().insert(key.to_string(), value.clone());
+        Ok(Some(value))
+    }
+
     pub fn has(&self, key: &str) -> bool {
         self.map.borrow().contains_key(key)
     }
@@ -103,9 +134,32 @@ impl QDict {
 
                 let new_map = self.map.borrow().clone();
                 let mut new_map = new_map;
-                new_map.remove(&key);
+                new_map.shift_remove(&key);
                 Ok(QValue::Dict(Box::new(QDict::new(new_map))))
             }
+            "sort_by_key" | "sort_by_value" => {
+                // Returns new dict with entries reordered (immutable)
+                // sort_by_value() / sort_by_value(true) / sort_by_value(reverse: true)
+                let reverse = match _args.as_slice() {
+                    [] => false,
+                    [QValue::Bool(b)] => b.value,
+                    [QValue::Dict(opts)] if opts.has("reverse") => opts.get("reverse").is_some_and(|v| v.as_bool()),
+                    _ => return arg_err!("{}() expects 0 or 1 arguments (reverse), got {}", method_name, _args.len()),
+                };
+                let mut entries: Vec<(String, QValue)> = self.map.borrow().iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                // Stable sort, so ties keep their insertion order in either direction
+                entries.sort_by(|a, b| {
+                    let ordering = if method_name == "sort_by_key" {
+                        a.0.cmp(&b.0)
+                    } else {
+                        compare_values(&a.1, &b.1).unwrap_or(std::cmp::Ordering::Equal)
+                    };
+                    if reverse { ordering.reverse() } else { ordering }
+                });
+                Ok(QValue::Dict(Box::new(QDict::new(entries.into_iter().collect()))))
+            }
             "clone" => {
                 // Returns a deep copy of the dict
                 if !_args.is_empty() {
@@ -114,11 +168,58 @@ impl QDict {
                 let new_map = self.map.borrow().clone();
                 Ok(QValue::Dict(Box::new(QDict::new(new_map))))
             }
+            "merge" | "deep_merge" => {
+                // Returns new dict with the other dicts' entries added, later ones winning;
+                // deep_merge() merges nested dicts key by key instead of replacing them
+                let mut merged = self.map.borrow().clone();
+                for other in &_args {
+                    match other {
+                        QValue::Dict(other) => merge_into(&mut merged, &other.map.borrow(), method_name == "deep_merge"),
+                        _ => return type_err!("{}() expects Dict arguments, got {}", method_name, other.as_obj().cls()),
+                    }
+                }
+                Ok(QValue::Dict(Box::new(QDict::new(merged))))
+            }
+            "invert" => {
+                // Returns new dict mapping each value (as a string) to its key; later keys win
+                if !_args.is_empty() {
+                    return arg_err!("invert() expects 0 arguments, got {}", _args.len());
+                }
+                let inverted = self.map.borrow().iter()
+                    .map(|(k, v)| (v.as_str(), QValue::Str(QString::new(k.clone()))))
+                    .collect();
+                Ok(QValue::Dict(Box::new(QDict::new(inverted))))
+            }
+            "get_or_set" => {
+                // Mutates: returns the value for key, first storing default if the key is missing
+                if _args.len() != 2 {
+                    return arg_err!("get_or_set() expects 2 arguments (key, default), got {}", _args.len());
+                }
+                let key = _args[0].as_str();
+                let value = self.map.borrow_mut().entry(key).or_insert_with(|| _args[1].clone()).clone();
+                Ok(value)
+            }
             _ => attr_err!("Dict has no method '{}'", method_name),
         }
     }
 }
 
+/// Copy `other`'s entries into `target`. With `deep`, a Dict value whose key
+/// already holds a Dict is merged into a copy of it rather than replacing it.
+fn merge_into(target: &mut IndexMap<String, QValue>, other: &IndexMap<String, QValue>, deep: bool) {
+    for (key, value) in other {
+        if deep {
+            if let (Some(QValue::Dict(existing)), QValue::Dict(incoming)) = (target.get(key), value) {
+                let mut nested = existing.map.borrow().clone();
+                merge_into(&mut nested, &incoming.map.borrow(), true);
+                target.insert(key.clone(), QValue::Dict(Box::new(QDict::new(nested))));
+                continue;
+            }
+        }
+        target.insert(key.clone(), value.clone());
+    }
+}
+
 impl QObj for QDict {
     fn cls(&self) -> String {
         "Dict".to_string()
@@ -134,10 +235,9 @@ impl QObj for QDict {
 
     fn str(&self) -> String {
         let map = self.map.borrow();
-        let mut pairs: Vec<String> = map.iter()
+        let pairs: Vec<String> = map.iter()
             .map(|(k, v)| format!("{}: {}", k, v.as_str()))
             .collect();
-        pairs.sort();
         format!("{{{}}}", pairs.join(", "))
     }
 
@@ -154,6 +254,17 @@ impl QObj for QDict {
     }
 }
 
+impl Clone for QDict {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Dict", self.id);
+        QDict {
+            map: Rc::clone(&self.map),
+            default_factory: self.default_factory.clone(),
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QDict {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Dict", self.id);
@@ -21,6 +21,7 @@ pub enum ExceptionType {
     ImportErr,         // Module import failed
     SyntaxErr,         // Syntax or parsing error
     ConfigurationErr,  // Configuration system error (QEP-053)
+    WarningErr,        // Warning raised by `-W error`
 
     // User-defined exception (from Quest code)
     Custom(String),
@@ -43,6 +44,7 @@ impl ExceptionType {
             ExceptionType::ImportErr => "ImportErr",
             ExceptionType::SyntaxErr => "SyntaxErr",
             ExceptionType::ConfigurationErr => "ConfigurationErr",
+            ExceptionType::WarningErr => "WarningErr",
             ExceptionType::Custom(name) => name,
         }
     }
@@ -80,6 +82,7 @@ impl ExceptionType {
             "ImportErr" => ExceptionType::ImportErr,
             "SyntaxErr" => ExceptionType::SyntaxErr,
             "ConfigurationErr" => ExceptionType::ConfigurationErr,
+            "WarningErr" => ExceptionType::WarningErr,
             _ => ExceptionType::Custom(s.to_string()),
         }
     }
@@ -1,8 +1,91 @@
+use std::cell::Cell;
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
 use crate::types::{QValue, QObj, QInt, QDecimal, QString, next_object_id, try_call_qobj_method};
-use crate::{arg_err, attr_err};
+use crate::{arg_err, attr_err, value_err};
 use crate::control_flow::EvalError;
 
-#[derive(Debug, Clone)]
+/// How Floats are displayed; see `sys.set_float_format`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatMode {
+    /// As few digits as needed (after rounding to the precision, if one is set)
+    Shortest,
+    /// Always exactly the precision's number of digits after the point
+    Fixed,
+}
+
+thread_local! {
+    static FLOAT_FORMAT: Cell<(Option<usize>, FloatMode)> = const { Cell::new((None, FloatMode::Shortest)) };
+}
+
+/// Change how Floats are displayed by `str()`, `puts` and interpolation
+pub fn set_float_format(precision: Option<usize>, mode: FloatMode) {
+    FLOAT_FORMAT.with(|f| f.set((precision, mode)));
+}
+
+/// The current display precision and mode
+pub fn float_format() -> (Option<usize>, FloatMode) {
+    FLOAT_FORMAT.with(|f| f.get())
+}
+
+/// `value` rounded to `places` decimal places (to tens, hundreds, ... when
+/// negative), with halves rounded away from zero. Rounding works on the
+/// digits the value displays as, so 2.675 rounds to 2.68 as it does for a
+/// Decimal.
+pub fn round_to(value: f64, places: i32) -> f64 {
+    if !value.is_finite() {
+        return value;
+    }
+    match Decimal::from_f64(value) {
+        Some(d) => crate::types::decimal::round_to(d, places).to_f64().unwrap_or(value),
+        // Too large for a Decimal, so there are no fractional digits to round
+        None if places >= 0 => value,
+        None => {
+            // Parsed rather than powi() so the power of ten is exact
+            let factor: f64 = format!("1e{}", -places).parse().unwrap_or(f64::INFINITY);
+            if factor.is_infinite() {
+                // Rounding to a unit beyond f64's range
+                return 0f64.copysign(value);
+            }
+            (value / factor).round() * factor
+        }
+    }
+}
+
+/// `value` with exactly `places` digits after the decimal point, rounded
+/// like `round_to`
+pub fn to_fixed(value: f64, places: usize) -> String {
+    if value.is_finite() {
+        if let Some(d) = Decimal::from_f64(value) {
+            return crate::types::decimal::to_fixed(d, places);
+        }
+    }
+    format!("{:.*}", places, value)
+}
+
+/// The optional `places` argument of round() and to_fixed()
+pub(crate) fn places_arg(method_name: &str, args: &[QValue]) -> Result<Option<i32>, String> {
+    match args {
+        [] => Ok(None),
+        [QValue::Int(n)] => i32::try_from(n.value).map(Some)
+            .map_err(|_| format!("ValueErr: {} places out of range: {}", method_name, n.value)),
+        [other] => Err(format!("TypeErr: {} places must be Int, got {}", method_name, other.q_type())),
+        _ => Err(format!("ArgErr: {} expects 0 or 1 arguments, got {}", method_name, args.len())),
+    }
+}
+
+/// Shortest display of a Float: whole numbers without a fraction
+fn shortest(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e10 {
+        format!("{:.0}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug)]
 pub struct QFloat {
     pub value: f64,
     pub id: u64,
@@ -256,10 +339,18 @@ impl QFloat {
                 Ok(QValue::Int(QInt::new(self.value.ceil() as i64)))
             }
             "round" => {
-                if !args.is_empty() {
-                    return arg_err!("round expects 0 arguments, got {}", args.len());
+                // round() gives an Int; round(places) a Float rounded to that many places
+                match places_arg("round", &args)? {
+                    None => Ok(QValue::Int(QInt::new(self.value.round() as i64))),
+                    Some(places) => Ok(QValue::Float(QFloat::new(round_to(self.value, places)))),
                 }
-                Ok(QValue::Int(QInt::new(self.value.round() as i64)))
+            }
+            "to_fixed" => {
+                let places = places_arg("to_fixed", &args)?.unwrap_or(0);
+                if places < 0 {
+                    return value_err!("to_fixed places must be non-negative, got {}", places);
+                }
+                Ok(QValue::Str(QString::new(to_fixed(self.value, places as usize))))
             }
             "to_int" => {
                 if !args.is_empty() {
@@ -360,11 +451,13 @@ impl QObj for QFloat {
     }
 
     fn str(&self) -> String {
-        // Display as integer if it's a whole number and not too large
-        if self.value.fract() == 0.0 && self.value.abs() < 1e10 {
-            format!("{:.0}", self.value)
-        } else {
-            self.value.to_string()
+        match float_format() {
+            (None, _) => shortest(self.value),
+            (Some(places), FloatMode::Fixed) => to_fixed(self.value, places),
+            (Some(places), FloatMode::Shortest) => {
+                // + 0.0 so values that round to zero don't display as "-0"
+                shortest(round_to(self.value, places as i32) + 0.0)
+            }
         }
     }
 
@@ -381,6 +474,16 @@ impl QObj for QFloat {
     }
 }
 
+impl Clone for QFloat {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Float", self.id);
+        QFloat {
+            value: self.value,
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QFloat {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Float", self.id);
@@ -69,6 +69,10 @@ pub struct QUserFun {
     /// QEP-057: Line offset for accurate line numbers in stack traces
     /// The line number in the source file where the function body starts
     pub line_offset: usize,
+    /// File the function was defined in (set while coverage is collected)
+    pub source_file: Option<Rc<String>>,
+    /// Full definition text, for inspect.source()
+    pub source: Option<Rc<String>>,
 }
 
 impl QUserFun {
@@ -97,6 +101,8 @@ impl QUserFun {
             kwargs_type: None,
             return_type: None,
             line_offset: 0,  // QEP-057: TODO - capture actual line offset
+            source_file: None,
+            source: None,
         }
     }
 
@@ -130,6 +136,8 @@ impl QUserFun {
             kwargs_type,
             return_type,
             line_offset: 0,  // QEP-057: TODO - capture actual line offset
+            source_file: None,
+            source: None,
         }
     }
 
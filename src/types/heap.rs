@@ -0,0 +1,428 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use crate::{arg_err, attr_err, index_err, type_err};
+use crate::control_flow::EvalError;
+use crate::function_call::{call_user_function, CallArguments};
+use crate::types::*;
+use crate::Scope;
+
+/// An element of a binary heap. Heaps order entries by priority and
+/// break ties by insertion order, so equal priorities come out FIFO.
+#[derive(Debug, Clone)]
+struct HeapEntry {
+    priority: QValue,
+    seq: u64,
+    value: QValue,
+}
+
+/// Binary heap storage shared by Heap and PriorityQueue. The smallest
+/// priority is on top unless a compare function says otherwise.
+#[derive(Debug, Default)]
+pub struct HeapState {
+    entries: Vec<HeapEntry>,
+    compare: Option<QUserFun>,
+    next_seq: u64,
+}
+
+impl HeapState {
+    pub fn new(compare: Option<QUserFun>) -> Self {
+        HeapState { entries: Vec::new(), compare, next_seq: 0 }
+    }
+
+    fn entry(&mut self, priority: QValue, value: QValue) -> HeapEntry {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        HeapEntry { priority, seq, value }
+    }
+}
+
+/// Sift operations run on entries moved out of the shared state, so a compare
+/// function that looks at the heap itself never hits an outstanding borrow.
+struct Sifter<'a> {
+    compare: Option<QUserFun>,
+    scope: &'a mut Scope,
+}
+
+impl Sifter<'_> {
+    /// Whether `a` should come out of the heap before `b`
+    fn before(&mut self, a: &HeapEntry, b: &HeapEntry) -> Result<bool, EvalError> {
+        let ordering = match &self.compare {
+            Some(compare) => {
+                if call_compare(compare, self.scope, &a.priority, &b.priority)? {
+                    Ordering::Less
+                } else if call_compare(compare, self.scope, &b.priority, &a.priority)? {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            }
+            None => compare_values(&a.priority, &b.priority).unwrap_or(Ordering::Equal),
+        };
+        Ok(ordering.then(a.seq.cmp(&b.seq)) == Ordering::Less)
+    }
+
+    fn sift_up(&mut self, entries: &mut [HeapEntry], mut index: usize) -> Result<(), EvalError> {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !self.before(&entries[index], &entries[parent])? {
+                break;
+            }
+            entries.swap(index, parent);
+            index = parent;
+        }
+        Ok(())
+    }
+
+    fn sift_down(&mut self, entries: &mut [HeapEntry], mut index: usize) -> Result<(), EvalError> {
+        loop {
+            let mut first = index;
+            for child in [2 * index + 1, 2 * index + 2] {
+                if child < entries.len() && self.before(&entries[child], &entries[first])? {
+                    first = child;
+                }
+            }
+            if first == index {
+                return Ok(());
+            }
+            entries.swap(index, first);
+            index = first;
+        }
+    }
+
+    fn push(&mut self, entries: &mut Vec<HeapEntry>, entry: HeapEntry) -> Result<(), EvalError> {
+        entries.push(entry);
+        let last = entries.len() - 1;
+        self.sift_up(entries, last)
+    }
+
+    fn pop(&mut self, entries: &mut Vec<HeapEntry>) -> Result<Option<HeapEntry>, EvalError> {
+        let last = match entries.pop() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        if entries.is_empty() {
+            return Ok(Some(last));
+        }
+        let top = std::mem::replace(&mut entries[0], last);
+        self.sift_down(entries, 0)?;
+        Ok(Some(top))
+    }
+}
+
+fn call_compare(compare: &QUserFun, scope: &mut Scope, a: &QValue, b: &QValue) -> Result<bool, EvalError> {
+    let args = CallArguments::positional_only(vec![a.clone(), b.clone()]);
+    Ok(call_user_function(compare, args, scope, None)?.as_bool())
+}
+
+/// Run `f` against the heap's entries with the state borrow released,
+/// putting the entries back afterwards even if the compare function fails.
+fn with_entries<T>(
+    state: &Rc<RefCell<HeapState>>,
+    scope: &mut Scope,
+    f: impl FnOnce(&mut Sifter, &mut Vec<HeapEntry>) -> Result<T, EvalError>,
+) -> Result<T, EvalError> {
+    let (mut entries, compare) = {
+        let mut state = state.borrow_mut();
+        (std::mem::take(&mut state.entries), state.compare.clone())
+    };
+    let mut sifter = Sifter { compare, scope };
+    let result = f(&mut sifter, &mut entries);
+    state.borrow_mut().entries = entries;
+    result
+}
+
+/// Entries in the order they would be popped, without modifying the heap
+fn sorted_entries(state: &Rc<RefCell<HeapState>>, scope: &mut Scope) -> Result<Vec<HeapEntry>, EvalError> {
+    let (mut entries, compare) = {
+        let state = state.borrow();
+        (state.entries.clone(), state.compare.clone())
+    };
+    let mut sifter = Sifter { compare, scope };
+    let mut sorted = Vec::with_capacity(entries.len());
+    while let Some(entry) = sifter.pop(&mut entries)? {
+        sorted.push(entry);
+    }
+    Ok(sorted)
+}
+
+fn push_all(state: &Rc<RefCell<HeapState>>, scope: &mut Scope, values: Vec<QValue>) -> Result<(), EvalError> {
+    let new_entries: Vec<HeapEntry> = {
+        let mut state = state.borrow_mut();
+        values.into_iter().map(|v| state.entry(v.clone(), v)).collect()
+    };
+    with_entries(state, scope, |sifter, entries| {
+        for entry in new_entries {
+            sifter.push(entries, entry)?;
+        }
+        Ok(())
+    })
+}
+
+/// Binary heap of values. Smallest value on top by default; pass a compare
+/// function returning true when its first argument should come out first.
+#[derive(Debug)]
+pub struct QHeap {
+    pub state: Rc<RefCell<HeapState>>,
+    pub id: u64,
+}
+
+impl QHeap {
+    pub fn new(compare: Option<QUserFun>, values: Vec<QValue>, scope: &mut Scope) -> Result<Self, EvalError> {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("Heap", id);
+        let heap = QHeap { state: Rc::new(RefCell::new(HeapState::new(compare))), id };
+        push_all(&heap.state, scope, values)?;
+        Ok(heap)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.borrow().entries.is_empty()
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
+        match method_name {
+            "push" => {
+                if args.len() != 1 {
+                    return arg_err!("push expects 1 argument, got {}", args.len());
+                }
+                push_all(&self.state, scope, args)?;
+                Ok(QValue::Heap(self.clone()))
+            }
+            "extend" => {
+                if args.len() != 1 {
+                    return arg_err!("extend expects 1 argument (array), got {}", args.len());
+                }
+                let values = match &args[0] {
+                    QValue::Array(arr) => arr.elements.borrow().clone(),
+                    other => return type_err!("extend expects Array, got {}", other.as_obj().cls()),
+                };
+                push_all(&self.state, scope, values)?;
+                Ok(QValue::Heap(self.clone()))
+            }
+            "pop" => {
+                if !args.is_empty() {
+                    return arg_err!("pop expects 0 arguments, got {}", args.len());
+                }
+                match with_entries(&self.state, scope, |sifter, entries| sifter.pop(entries))? {
+                    Some(entry) => Ok(entry.value),
+                    None => index_err!("pop from empty Heap"),
+                }
+            }
+            "peek" => {
+                if !args.is_empty() {
+                    return arg_err!("peek expects 0 arguments, got {}", args.len());
+                }
+                Ok(self.state.borrow().entries.first()
+                    .map(|entry| entry.value.clone())
+                    .unwrap_or(QValue::Nil(QNil)))
+            }
+            "to_array" => {
+                // Values in pop order; the heap is left untouched
+                if !args.is_empty() {
+                    return arg_err!("to_array expects 0 arguments, got {}", args.len());
+                }
+                let values = sorted_entries(&self.state, scope)?.into_iter().map(|e| e.value).collect();
+                Ok(QValue::Array(QArray::new(values)))
+            }
+            _ => heap_common_method(&self.state, "Heap", QValue::Heap(self.clone()), self, method_name, args),
+        }
+    }
+}
+
+impl Clone for QHeap {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Heap", self.id);
+        QHeap { state: Rc::clone(&self.state), id: self.id }
+    }
+}
+
+impl Drop for QHeap {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("Heap", self.id);
+    }
+}
+
+impl QObj for QHeap {
+    fn cls(&self) -> String {
+        "Heap".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "Heap"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "Heap"
+    }
+
+    fn str(&self) -> String {
+        let state = self.state.borrow();
+        match state.entries.first() {
+            Some(top) => format!("Heap(len: {}, top: {})", state.entries.len(), top.value.as_str()),
+            None => "Heap(len: 0)".to_string(),
+        }
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Heap: Binary heap with O(log n) push and pop of the smallest value".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Queue of items ordered by a separate priority. Lowest priority first by
+/// default; items with equal priority come out in the order they were pushed.
+#[derive(Debug)]
+pub struct QPriorityQueue {
+    pub state: Rc<RefCell<HeapState>>,
+    pub id: u64,
+}
+
+impl QPriorityQueue {
+    pub fn new(compare: Option<QUserFun>) -> Self {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("PriorityQueue", id);
+        QPriorityQueue { state: Rc::new(RefCell::new(HeapState::new(compare))), id }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.borrow().entries.is_empty()
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
+        match method_name {
+            "push" => {
+                if args.len() != 2 {
+                    return arg_err!("push expects 2 arguments (item, priority), got {}", args.len());
+                }
+                let entry = self.state.borrow_mut().entry(args[1].clone(), args[0].clone());
+                with_entries(&self.state, scope, |sifter, entries| sifter.push(entries, entry))?;
+                Ok(QValue::PriorityQueue(self.clone()))
+            }
+            "pop" | "pop_with_priority" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                let entry = match with_entries(&self.state, scope, |sifter, entries| sifter.pop(entries))? {
+                    Some(entry) => entry,
+                    None => return index_err!("{} from empty PriorityQueue", method_name),
+                };
+                if method_name == "pop" {
+                    Ok(entry.value)
+                } else {
+                    Ok(QValue::Array(QArray::new(vec![entry.value, entry.priority])))
+                }
+            }
+            "peek" | "peek_priority" => {
+                if !args.is_empty() {
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
+                }
+                let state = self.state.borrow();
+                Ok(match state.entries.first() {
+                    Some(entry) if method_name == "peek" => entry.value.clone(),
+                    Some(entry) => entry.priority.clone(),
+                    None => QValue::Nil(QNil),
+                })
+            }
+            "to_array" => {
+                // Items in pop order; the queue is left untouched
+                if !args.is_empty() {
+                    return arg_err!("to_array expects 0 arguments, got {}", args.len());
+                }
+                let values = sorted_entries(&self.state, scope)?.into_iter().map(|e| e.value).collect();
+                Ok(QValue::Array(QArray::new(values)))
+            }
+            _ => heap_common_method(&self.state, "PriorityQueue", QValue::PriorityQueue(self.clone()), self, method_name, args),
+        }
+    }
+}
+
+impl Clone for QPriorityQueue {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("PriorityQueue", self.id);
+        QPriorityQueue { state: Rc::clone(&self.state), id: self.id }
+    }
+}
+
+impl Drop for QPriorityQueue {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("PriorityQueue", self.id);
+    }
+}
+
+impl QObj for QPriorityQueue {
+    fn cls(&self) -> String {
+        "PriorityQueue".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "PriorityQueue"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "PriorityQueue"
+    }
+
+    fn str(&self) -> String {
+        let state = self.state.borrow();
+        match state.entries.first() {
+            Some(top) => format!("PriorityQueue(len: {}, next: {})", state.entries.len(), top.value.as_str()),
+            None => "PriorityQueue(len: 0)".to_string(),
+        }
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "PriorityQueue: Items ordered by priority, lowest first, FIFO among equal priorities".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Methods that behave the same for Heap and PriorityQueue
+fn heap_common_method(
+    state: &Rc<RefCell<HeapState>>,
+    type_name: &str,
+    this: QValue,
+    obj: &dyn QObj,
+    method_name: &str,
+    args: Vec<QValue>,
+) -> Result<QValue, EvalError> {
+    match method_name {
+        "len" | "empty" | "clear" | "cls" | "_id" | "str" | "_rep" | "_doc" if !args.is_empty() => {
+            arg_err!("{} expects 0 arguments, got {}", method_name, args.len())
+        }
+        "len" => Ok(QValue::Int(QInt::new(state.borrow().entries.len() as i64))),
+        "empty" => Ok(QValue::Bool(QBool::new(state.borrow().entries.is_empty()))),
+        "clear" => {
+            state.borrow_mut().entries.clear();
+            Ok(this)
+        }
+        "cls" => Ok(QValue::Str(QString::new(obj.cls()))),
+        "_id" => Ok(QValue::Int(QInt::new(obj._id() as i64))),
+        "str" => Ok(QValue::Str(QString::new(obj.str()))),
+        "_rep" => Ok(QValue::Str(QString::new(obj._rep()))),
+        "_doc" => Ok(QValue::Str(QString::new(obj._doc()))),
+        _ => attr_err!("Unknown method '{}' for {}", method_name, type_name),
+    }
+}
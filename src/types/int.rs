@@ -1,9 +1,9 @@
 use super::*;
 use std::sync::OnceLock;
-use crate::{arg_err, attr_err};
+use crate::{arg_err, attr_err, value_err};
 use crate::control_flow::EvalError;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct QInt {
     pub value: i64,
     pub id: u64,
@@ -288,6 +288,19 @@ impl QInt {
                 }
                 Ok(QValue::Int(QInt::new(-self.value)))
             }
+            "round" if !args.is_empty() => {
+                // round(places): negative places round to tens, hundreds, ... (halves away from zero)
+                let places = super::float::places_arg("round", &args)?.unwrap_or(0);
+                let rounded = super::decimal::round_to(rust_decimal::Decimal::from(self.value), places);
+                Ok(QValue::Int(QInt::new(rounded.to_i64().unwrap_or(0))))
+            }
+            "to_fixed" => {
+                let places = super::float::places_arg("to_fixed", &args)?.unwrap_or(0);
+                if places < 0 {
+                    return value_err!("to_fixed places must be non-negative, got {}", places);
+                }
+                Ok(QValue::Str(QString::new(super::decimal::to_fixed(rust_decimal::Decimal::from(self.value), places as usize))))
+            }
             "round" | "floor" | "ceil" | "trunc" => {
                 // For integers, these are all identity operations
                 if !args.is_empty() {
@@ -351,6 +364,16 @@ impl QObj for QInt {
     }
 }
 
+impl Clone for QInt {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Int", self.id);
+        QInt {
+            value: self.value,
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QInt {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Int", self.id);
@@ -0,0 +1,310 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::{arg_err, attr_err, type_err, value_err};
+use crate::control_flow::EvalError;
+use crate::function_call::{call_user_function, CallArguments};
+use crate::types::*;
+use crate::Scope;
+
+/// One step of a lazy pipeline
+#[derive(Debug, Clone)]
+enum Stage {
+    Map(Rc<QUserFun>),
+    Filter(Rc<QUserFun>),
+    Take(usize),
+    Skip(usize),
+    TakeWhile(Rc<QUserFun>),
+    SkipWhile(Rc<QUserFun>),
+}
+
+/// A lazy pipeline over an array, created by `arr.lazy()`.
+///
+/// Adapters (map, filter, take, ...) return a new Iterator with one more
+/// stage and do no work. Consumers (to_array, each, reduce, ...) run the
+/// stages over the array one element at a time, so no intermediate arrays
+/// are built and `take`/`find`/`first` stop reading the array early. An
+/// Iterator describes a pipeline rather than holding a position: every
+/// consumer starts from the beginning of the array as it is at that moment.
+#[derive(Debug)]
+pub struct QIterator {
+    source: Rc<RefCell<Vec<QValue>>>,
+    stages: Rc<Vec<Stage>>,
+    pub id: u64,
+}
+
+/// What a consumer wants after seeing an element
+enum Flow {
+    Continue,
+    Stop,
+}
+
+impl QIterator {
+    pub fn new(source: &QArray) -> Self {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("Iterator", id);
+        QIterator { source: Rc::clone(&source.elements), stages: Rc::new(Vec::new()), id }
+    }
+
+    /// This pipeline with `stage` appended
+    fn with_stage(&self, stage: Stage) -> QValue {
+        let id = next_object_id();
+        crate::alloc_counter::track_alloc("Iterator", id);
+        let mut stages = self.stages.as_ref().clone();
+        stages.push(stage);
+        QValue::Iterator(Box::new(QIterator { source: Rc::clone(&self.source), stages: Rc::new(stages), id }))
+    }
+
+    /// Feed each element that makes it through the stages to `consume`
+    fn run<F>(&self, scope: &mut Scope, mut consume: F) -> Result<(), String>
+    where
+        F: FnMut(QValue, &mut Scope) -> Result<Flow, String>,
+    {
+        // Per-run counters for take/skip and whether skip_while has finished
+        let mut counts = vec![0usize; self.stages.len()];
+        let mut index = 0;
+        'elements: loop {
+            // Read one element at a time so callbacks may touch the array
+            let element = match self.source.borrow().get(index) {
+                Some(element) => element.clone(),
+                None => return Ok(()),
+            };
+            index += 1;
+
+            let mut value = element;
+            let mut last = false;
+            for (stage, count) in self.stages.iter().zip(counts.iter_mut()) {
+                match stage {
+                    Stage::Map(f) => value = call(f, value, scope)?,
+                    Stage::Filter(f) => {
+                        if !call(f, value.clone(), scope)?.as_bool() {
+                            if last {
+                                return Ok(());
+                            }
+                            continue 'elements;
+                        }
+                    }
+                    Stage::Take(n) => {
+                        if *count >= *n {
+                            return Ok(());
+                        }
+                        *count += 1;
+                        last = last || *count == *n;
+                    }
+                    Stage::Skip(n) => {
+                        if *count < *n {
+                            *count += 1;
+                            continue 'elements;
+                        }
+                    }
+                    Stage::TakeWhile(f) => {
+                        if !call(f, value.clone(), scope)?.as_bool() {
+                            return Ok(());
+                        }
+                    }
+                    Stage::SkipWhile(f) => {
+                        if *count == 0 {
+                            if call(f, value.clone(), scope)?.as_bool() {
+                                continue 'elements;
+                            }
+                            *count = 1;
+                        }
+                    }
+                }
+            }
+            if let Flow::Stop = consume(value, scope)? {
+                return Ok(());
+            }
+            if last {
+                return Ok(());
+            }
+        }
+    }
+
+    /// All elements the pipeline produces
+    pub fn collect(&self, scope: &mut Scope) -> Result<Vec<QValue>, String> {
+        let mut values = Vec::new();
+        self.run(scope, |value, _| {
+            values.push(value);
+            Ok(Flow::Continue)
+        })?;
+        Ok(values)
+    }
+
+    pub fn call_method(&self, method_name: &str, args: Vec<QValue>, scope: &mut Scope) -> Result<QValue, EvalError> {
+        if let Some(result) = try_call_qobj_method(self, method_name, &args) {
+            return result;
+        }
+
+        match method_name {
+            // Adapters
+            "map" | "filter" | "take_while" | "skip_while" => {
+                let f = function_arg(method_name, &args)?;
+                Ok(self.with_stage(match method_name {
+                    "map" => Stage::Map(f),
+                    "filter" => Stage::Filter(f),
+                    "take_while" => Stage::TakeWhile(f),
+                    _ => Stage::SkipWhile(f),
+                }))
+            }
+            "take" | "skip" => {
+                if args.len() != 1 {
+                    return arg_err!("{} expects 1 argument (count), got {}", method_name, args.len());
+                }
+                let n = match &args[0] {
+                    QValue::Int(n) if n.value >= 0 => n.value as usize,
+                    QValue::Int(n) => return value_err!("{} count must not be negative, got {}", method_name, n.value),
+                    other => return type_err!("{} count must be Int, got {}", method_name, other.as_obj().cls()),
+                };
+                Ok(self.with_stage(if method_name == "take" { Stage::Take(n) } else { Stage::Skip(n) }))
+            }
+
+            // Consumers
+            "to_array" => {
+                if !args.is_empty() {
+                    return arg_err!("to_array expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Array(QArray::new(self.collect(scope)?)))
+            }
+            "each" => {
+                let f = function_arg(method_name, &args)?;
+                self.run(scope, |value, scope| {
+                    call(&f, value, scope)?;
+                    Ok(Flow::Continue)
+                })?;
+                Ok(QValue::Nil(QNil))
+            }
+            "reduce" => {
+                // reduce(fn, initial)
+                if args.len() != 2 {
+                    return arg_err!("reduce expects 2 arguments (function, initial), got {}", args.len());
+                }
+                let f = function_arg(method_name, &args[..1])?;
+                let mut accumulator = Some(args[1].clone());
+                self.run(scope, |value, scope| {
+                    let acc = accumulator.take().unwrap_or(QValue::Nil(QNil));
+                    let call_args = CallArguments::positional_only(vec![acc, value]);
+                    accumulator = Some(call_user_function(&f, call_args, scope, None)?);
+                    Ok(Flow::Continue)
+                })?;
+                Ok(accumulator.unwrap_or(QValue::Nil(QNil)))
+            }
+            "count" => {
+                if !args.is_empty() {
+                    return arg_err!("count expects 0 arguments, got {}", args.len());
+                }
+                let mut count = 0;
+                self.run(scope, |_, _| {
+                    count += 1;
+                    Ok(Flow::Continue)
+                })?;
+                Ok(QValue::Int(QInt::new(count)))
+            }
+            "first" => {
+                if !args.is_empty() {
+                    return arg_err!("first expects 0 arguments, got {}", args.len());
+                }
+                let mut first = QValue::Nil(QNil);
+                self.run(scope, |value, _| {
+                    first = value;
+                    Ok(Flow::Stop)
+                })?;
+                Ok(first)
+            }
+            "find" => {
+                let f = function_arg(method_name, &args)?;
+                let mut found = QValue::Nil(QNil);
+                self.run(scope, |value, scope| {
+                    if call(&f, value.clone(), scope)?.as_bool() {
+                        found = value;
+                        return Ok(Flow::Stop);
+                    }
+                    Ok(Flow::Continue)
+                })?;
+                Ok(found)
+            }
+            "any" | "all" => {
+                // any stops at the first match, all at the first miss
+                let f = function_arg(method_name, &args)?;
+                let want = method_name == "any";
+                let mut result = !want;
+                self.run(scope, |value, scope| {
+                    if call(&f, value, scope)?.as_bool() == want {
+                        result = want;
+                        return Ok(Flow::Stop);
+                    }
+                    Ok(Flow::Continue)
+                })?;
+                Ok(QValue::Bool(QBool::new(result)))
+            }
+            _ => attr_err!("Iterator has no method '{}'", method_name),
+        }
+    }
+}
+
+/// The single function argument of an adapter or consumer
+fn function_arg(method_name: &str, args: &[QValue]) -> Result<Rc<QUserFun>, String> {
+    match args {
+        [QValue::UserFun(f)] => Ok(Rc::new(f.as_ref().clone())),
+        [_] => Err(format!("{} expects a function argument", method_name)),
+        _ => Err(format!("ArgErr: {} expects 1 argument (function), got {}", method_name, args.len())),
+    }
+}
+
+fn call(f: &QUserFun, value: QValue, scope: &mut Scope) -> Result<QValue, String> {
+    call_user_function(f, CallArguments::positional_only(vec![value]), scope, None).map_err(|e| e.to_string())
+}
+
+impl QObj for QIterator {
+    fn cls(&self) -> String {
+        "Iterator".to_string()
+    }
+
+    fn q_type(&self) -> &'static str {
+        "iterator"
+    }
+
+    fn is(&self, type_name: &str) -> bool {
+        type_name == "iterator" || type_name == "obj"
+    }
+
+    fn str(&self) -> String {
+        let stages: Vec<&str> = self.stages.iter().map(|stage| match stage {
+            Stage::Map(_) => "map",
+            Stage::Filter(_) => "filter",
+            Stage::Take(_) => "take",
+            Stage::Skip(_) => "skip",
+            Stage::TakeWhile(_) => "take_while",
+            Stage::SkipWhile(_) => "skip_while",
+        }).collect();
+        if stages.is_empty() {
+            "<Iterator>".to_string()
+        } else {
+            format!("<Iterator: {}>", stages.join(" -> "))
+        }
+    }
+
+    fn _rep(&self) -> String {
+        self.str()
+    }
+
+    fn _doc(&self) -> String {
+        "Lazy pipeline over an array, created by arr.lazy()".to_string()
+    }
+
+    fn _id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Clone for QIterator {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Iterator", self.id);
+        QIterator { source: Rc::clone(&self.source), stages: Rc::clone(&self.stages), id: self.id }
+    }
+}
+
+impl Drop for QIterator {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("Iterator", self.id);
+    }
+}
@@ -15,25 +15,32 @@ const MAX_STR_DEPTH: usize = 50;
 
 // Submodules
 mod int;
-mod float;
+pub mod float;
 pub mod decimal;
 pub mod bigint;
 mod bool;
-mod string;
+pub mod string;
 mod bytes;
+mod bytes_buffer;
 mod nil;
 mod ndarray;
+mod slice;
 mod function;
 mod module;
 pub mod array;
+mod iterator;
 mod dict;
 mod set;
+mod deque;
+mod heap;
+mod counter;
 mod user_types;
 mod exception;
 mod uuid;
 mod stringio;
 mod system_stream;
 mod redirect_guard;
+pub mod serde_bridge;
 
 #[cfg(test)]
 mod size_test;
@@ -56,13 +63,19 @@ pub use bigint::{QBigInt, create_bigint_type};
 pub use bool::QBool;
 pub use string::QString;
 pub use bytes::QBytes;
+pub use bytes_buffer::QBytesBuffer;
 pub use nil::QNil;
 pub use ndarray::QNDArray;
+pub use slice::{IndexEntry, SliceRange};
 pub use function::{QFun, QUserFun, create_fn};
 pub use module::QModule;
 pub use array::{QArray, create_array_type};
+pub use iterator::QIterator;
 pub use dict::QDict;
 pub use set::{QSet, SetElement};
+pub use deque::QDeque;
+pub use heap::{QHeap, QPriorityQueue};
+pub use counter::{QCounter, CounterState};
 pub use user_types::{FieldDef, QType, QStruct, QTrait, TraitMethod};
 pub use exception::{QException, ExceptionType};
 pub use uuid::QUuid;
@@ -104,6 +117,10 @@ pub fn values_equal(a: &QValue, b: &QValue) -> bool {
         }
         // Compare Types by name (for QEP-037 exception type comparison)
         (QValue::Type(a_type), QValue::Type(b_type)) => a_type.name == b_type.name,
+        // Urls are equal when they serialize the same
+        (QValue::Url(a_url), QValue::Url(b_url)) => a_url.str() == b_url.str(),
+        (QValue::IpAddr(a_ip), QValue::IpAddr(b_ip)) => a_ip.addr == b_ip.addr,
+        (QValue::Cidr(a_net), QValue::Cidr(b_net)) => a_net.net == b_net.net,
         _ => false, // Different types or unsupported types (Dict, Fun, etc.)
     }
 }
@@ -138,6 +155,10 @@ pub fn compare_values(a: &QValue, b: &QValue) -> Option<std::cmp::Ordering> {
         }
         // Nil is equal to nil
         (QValue::Nil(_), QValue::Nil(_)) => Some(Ordering::Equal),
+        // IP addresses and networks compare numerically
+        (QValue::IpAddr(_), QValue::IpAddr(_)) | (QValue::Cidr(_), QValue::Cidr(_)) => {
+            crate::modules::net::ip::compare_ip(a, b)
+        }
 
         // Mixed types: order by type priority
         // Nil < Bool < Int < Float < Str < Array < Dict < Fun < Module
@@ -216,13 +237,22 @@ pub enum QValue {
     Bool(QBool),
     Str(QString),
     Bytes(QBytes),
+    // Growable byte buffer with a read position (struct.BytesBuffer)
+    BytesBuffer(QBytesBuffer),
     Nil(QNil),
     Fun(QFun),
     UserFun(Box<QUserFun>),
     Module(Box<QModule>),
     Array(QArray),
+    // Lazy pipeline over an array (arr.lazy())
+    Iterator(Box<QIterator>),
     Dict(Box<QDict>),
     Set(QSet),
+    // Collections (from std/collections module)
+    Deque(QDeque),
+    Heap(QHeap),
+    PriorityQueue(QPriorityQueue),
+    Counter(QCounter),
     Type(Box<QType>),
     Struct(Rc<RefCell<QStruct>>),
     Trait(QTrait),
@@ -235,8 +265,12 @@ pub enum QValue {
     Time(crate::modules::time::QTime),
     Span(crate::modules::time::QSpan),
     DateRange(crate::modules::time::QDateRange),
+    Stopwatch(crate::modules::time::QStopwatch),
     // Serial port (from std/serial module)
     SerialPort(crate::modules::serial::QSerialPort),
+    // Modbus master and CAN bus (from std/serial/modbus and std/serial/can)
+    ModbusClient(crate::modules::serial::modbus::QModbusClient),
+    CanBus(crate::modules::serial::can::QCanBus),
     // SQLite database (from std/db/sqlite module)
     SqliteConnection(crate::modules::db::sqlite::QSqliteConnection),
     SqliteCursor(crate::modules::db::sqlite::QSqliteCursor),
@@ -246,14 +280,60 @@ pub enum QValue {
     // MySQL database (from std/db/mysql module)
     MysqlConnection(crate::modules::db::mysql::QMysqlConnection),
     MysqlCursor(crate::modules::db::mysql::QMysqlCursor),
+    // Connection pools (shared by the db drivers)
+    ConnectionPool(crate::modules::db::pool::QConnectionPool),
+    PoolLease(crate::modules::db::pool::QPoolLease),
+    Transaction(crate::modules::db::transaction::QTransaction),
+    RowIterator(crate::modules::db::stream::QRowIterator),
+    ImapClient(crate::modules::email::imap::QImapClient),
+    Pop3Client(crate::modules::email::pop3::QPop3Client),
+    FtpClient(crate::modules::net::ftp::QFtpClient),
+    SftpClient(crate::modules::net::sftp::QSftpClient),
+    // Messaging (from std/msg/nats module)
+    NatsClient(crate::modules::msg::nats::QNatsClient),
+    NatsSubscription(crate::modules::msg::nats::QNatsSubscription),
     // HTML templates (from std/html/templates module)
     HtmlTemplate(crate::modules::html::QHtmlTemplate),
+    SafeString(crate::modules::html::QSafeString),
+    // Regular expressions (from std/regex module)
+    RegexPattern(Box<crate::modules::regex::QRegexPattern>),
+    RegexMatch(Box<crate::modules::regex::QRegexMatch>),
     // HTTP client (from std/http/client module)
     HttpClient(crate::modules::http::QHttpClient),
     HttpRequest(crate::modules::http::QHttpRequest),
     HttpResponse(crate::modules::http::QHttpResponse),
+    // XML (from std/encoding/xml module)
+    XmlElement(crate::modules::encoding::xml::QXmlElement),
+    XmlReader(crate::modules::encoding::xml::QXmlReader),
+    // Streaming CSV (from std/encoding/csv module)
+    CsvReader(crate::modules::encoding::csv::QCsvReader),
+    CsvWriter(crate::modules::encoding::csv::QCsvWriter),
+    // Streaming JSON parser (from std/encoding/json module)
+    JsonStream(crate::modules::encoding::json::QJsonStream),
+    // Compiled JSON Schema (from std/encoding/jsonschema module)
+    JsonSchema(crate::modules::encoding::jsonschema::QJsonSchema),
+    // Protocol Buffers schema (from std/encoding/proto module)
+    ProtoSchema(crate::modules::encoding::proto::QProtoSchema),
+    // TAR archives (from std/compress/tar module)
+    TarReader(crate::modules::compress::tar::QTarReader),
+    TarWriter(crate::modules::compress::tar::QTarWriter),
+    // Streaming compression (from std/compress modules)
+    Compressor(crate::modules::compress::stream::QCompressor),
+    Decompressor(crate::modules::compress::stream::QDecompressor),
+    CompressedReader(crate::modules::compress::stream::QCompressedReader),
+    CompressedWriter(crate::modules::compress::stream::QCompressedWriter),
+    // Signing keys (from std/crypto module)
+    CryptoKey(crate::modules::crypto::keys::QCryptoKey),
     // Random number generator (from std/rand module)
     Rng(Box<crate::modules::rand::QRng>),
+    Cache(crate::modules::cache::QCache),
+    // Parsed URL (from std/http/urlparse module)
+    Url(crate::modules::http::url::QUrl),
+    // IP addresses and networks (from std/net/ip module)
+    IpAddr(crate::modules::net::ip::QIpAddr),
+    Cidr(crate::modules::net::ip::QCidr),
+    // Raster image (from std/image module)
+    Image(crate::modules::image::QImage),
     // StringIO (from std/io module)
     StringIO(Rc<RefCell<QStringIO>>),
     // System streams (from std/sys module)
@@ -262,6 +342,7 @@ pub enum QValue {
     RedirectGuard(Box<QRedirectGuard>),
     // Process types (from std/process module)
     ProcessResult(crate::modules::process::QProcessResult),
+    PipelineResult(crate::modules::process::QPipelineResult),
     Process(crate::modules::process::QProcess),
     WritableStream(crate::modules::process::QWritableStream),
     ReadableStream(crate::modules::process::QReadableStream),
@@ -283,8 +364,14 @@ impl QValue {
             QValue::UserFun(f) => f.as_ref(),
             QValue::Module(m) => m.as_ref(),
             QValue::Array(a) => a,
+            QValue::Iterator(it) => it.as_ref(),
+            QValue::BytesBuffer(b) => b,
             QValue::Dict(d) => d.as_ref(),
             QValue::Set(s) => s,
+            QValue::Deque(d) => d,
+            QValue::Heap(h) => h,
+            QValue::PriorityQueue(pq) => pq,
+            QValue::Counter(c) => c,
             QValue::Type(t) => t.as_ref(),
             QValue::Struct(s) => {
                 // For Struct wrapped in Rc<RefCell<>>, use the same pattern as StringIO
@@ -303,18 +390,53 @@ impl QValue {
             QValue::Time(t) => t,
             QValue::Span(s) => s,
             QValue::DateRange(dr) => dr,
+            QValue::Stopwatch(sw) => sw,
             QValue::SerialPort(sp) => sp,
+            QValue::ModbusClient(c) => c,
+            QValue::CanBus(b) => b,
             QValue::SqliteConnection(conn) => conn,
             QValue::SqliteCursor(cursor) => cursor,
             QValue::PostgresConnection(conn) => conn,
             QValue::PostgresCursor(cursor) => cursor,
             QValue::MysqlConnection(conn) => conn,
             QValue::MysqlCursor(cursor) => cursor,
+            QValue::ConnectionPool(pool) => pool,
+            QValue::PoolLease(lease) => lease,
+            QValue::Transaction(tx) => tx,
+            QValue::RowIterator(it) => it,
+            QValue::ImapClient(c) => c,
+            QValue::Pop3Client(c) => c,
+            QValue::FtpClient(c) => c,
+            QValue::SftpClient(c) => c,
+            QValue::NatsClient(c) => c,
+            QValue::NatsSubscription(s) => s,
             QValue::HtmlTemplate(tmpl) => tmpl,
+            QValue::SafeString(s) => s,
+            QValue::RegexPattern(p) => p.as_ref(),
+            QValue::RegexMatch(m) => m.as_ref(),
             QValue::HttpClient(client) => client,
             QValue::HttpRequest(req) => req,
             QValue::HttpResponse(resp) => resp,
+            QValue::XmlElement(el) => el,
+            QValue::XmlReader(reader) => reader,
+            QValue::CsvReader(reader) => reader,
+            QValue::CsvWriter(writer) => writer,
+            QValue::JsonStream(stream) => stream,
+            QValue::JsonSchema(schema) => schema,
+            QValue::ProtoSchema(v) => v,
+            QValue::TarReader(v) => v,
+            QValue::TarWriter(v) => v,
+            QValue::Compressor(v) => v,
+            QValue::Decompressor(v) => v,
+            QValue::CompressedReader(v) => v,
+            QValue::CompressedWriter(v) => v,
+            QValue::CryptoKey(v) => v,
             QValue::Rng(rng) => rng.as_ref(),
+            QValue::Cache(c) => c,
+            QValue::Url(u) => u,
+            QValue::IpAddr(a) => a,
+            QValue::Cidr(n) => n,
+            QValue::Image(img) => img,
             QValue::StringIO(sio) => {
                 // For StringIO wrapped in Rc<RefCell<>>, we need special handling
                 // Return a temporary object that implements QObj
@@ -328,6 +450,7 @@ impl QValue {
             QValue::SystemStream(ss) => ss,
             QValue::RedirectGuard(rg) => rg.as_ref(),
             QValue::ProcessResult(pr) => pr,
+            QValue::PipelineResult(pr) => pr,
             QValue::Process(p) => p,
             QValue::WritableStream(ws) => ws,
             QValue::ReadableStream(rs) => rs,
@@ -350,8 +473,14 @@ impl QValue {
             QValue::UserFun(_) => Err("Cannot convert fun to number".into()),
             QValue::Module(_) => Err("Cannot convert module to number".into()),
             QValue::Array(_) => Err("Cannot convert array to number".into()),
+            QValue::Iterator(_) => Err("Cannot convert iterator to number".into()),
+            QValue::BytesBuffer(_) => Err("Cannot convert BytesBuffer to number".into()),
             QValue::Dict(_) => Err("Cannot convert dict to number".into()),
             QValue::Set(_) => Err("Cannot convert set to number".into()),
+            QValue::Deque(_) => Err("Cannot convert deque to number".into()),
+            QValue::Heap(_) => Err("Cannot convert heap to number".into()),
+            QValue::PriorityQueue(_) => Err("Cannot convert priority queue to number".into()),
+            QValue::Counter(_) => Err("Cannot convert counter to number".into()),
             QValue::Type(_) => Err("Cannot convert type to number".into()),
             QValue::Struct(_) => Err("Cannot convert struct to number".into()),
             QValue::Trait(_) => Err("Cannot convert trait to number".into()),
@@ -363,22 +492,58 @@ impl QValue {
             QValue::Time(_) => Err("Cannot convert time to number".into()),
             QValue::Span(_) => Err("Cannot convert span to number".into()),
             QValue::DateRange(_) => Err("Cannot convert date range to number".into()),
+            QValue::Stopwatch(_) => Err("Cannot convert stopwatch to number".into()),
             QValue::SerialPort(_) => Err("Cannot convert serial port to number".into()),
+            QValue::ModbusClient(_) => Err("Cannot convert Modbus client to number".into()),
+            QValue::CanBus(_) => Err("Cannot convert CAN bus to number".into()),
             QValue::SqliteConnection(_) => Err("Cannot convert sqlite connection to number".into()),
             QValue::SqliteCursor(_) => Err("Cannot convert sqlite cursor to number".into()),
             QValue::PostgresConnection(_) => Err("Cannot convert postgres connection to number".into()),
             QValue::PostgresCursor(_) => Err("Cannot convert postgres cursor to number".into()),
             QValue::MysqlConnection(_) => Err("Cannot convert mysql connection to number".into()),
             QValue::MysqlCursor(_) => Err("Cannot convert mysql cursor to number".into()),
+            QValue::ConnectionPool(_) => Err("Cannot convert connection pool to number".into()),
+            QValue::PoolLease(_) => Err("Cannot convert pool lease to number".into()),
+            QValue::Transaction(_) => Err("Cannot convert transaction to number".into()),
+            QValue::RowIterator(_) => Err("Cannot convert row iterator to number".into()),
+            QValue::ImapClient(_) => Err("Cannot convert IMAP client to number".into()),
+            QValue::Pop3Client(_) => Err("Cannot convert POP3 client to number".into()),
+            QValue::FtpClient(_) => Err("Cannot convert FTP client to number".into()),
+            QValue::SftpClient(_) => Err("Cannot convert SFTP client to number".into()),
+            QValue::NatsClient(_) => Err("Cannot convert NATS client to number".into()),
+            QValue::NatsSubscription(_) => Err("Cannot convert NATS subscription to number".into()),
             QValue::HtmlTemplate(_) => Err("Cannot convert html template to number".into()),
+            QValue::SafeString(_) => Err("Cannot convert safe string to number".into()),
+            QValue::RegexPattern(_) => Err("Cannot convert regex pattern to number".into()),
+            QValue::RegexMatch(_) => Err("Cannot convert regex match to number".into()),
             QValue::HttpClient(_) => Err("Cannot convert http client to number".into()),
             QValue::HttpRequest(_) => Err("Cannot convert http request to number".into()),
             QValue::HttpResponse(_) => Err("Cannot convert http response to number".into()),
+            QValue::XmlElement(_) => Err("Cannot convert XmlElement to number".into()),
+            QValue::XmlReader(_) => Err("Cannot convert XmlReader to number".into()),
+            QValue::CsvReader(_) => Err("Cannot convert CsvReader to number".into()),
+            QValue::CsvWriter(_) => Err("Cannot convert CsvWriter to number".into()),
+            QValue::JsonStream(_) => Err("Cannot convert JsonStream to number".into()),
+            QValue::JsonSchema(_) => Err("Cannot convert JsonSchema to number".into()),
+            QValue::ProtoSchema(_) => Err("Cannot convert ProtoSchema to number".into()),
+            QValue::TarReader(_) => Err("Cannot convert TarReader to number".into()),
+            QValue::TarWriter(_) => Err("Cannot convert TarWriter to number".into()),
+            QValue::Compressor(_) => Err("Cannot convert Compressor to number".into()),
+            QValue::Decompressor(_) => Err("Cannot convert Decompressor to number".into()),
+            QValue::CompressedReader(_) => Err("Cannot convert CompressedReader to number".into()),
+            QValue::CompressedWriter(_) => Err("Cannot convert CompressedWriter to number".into()),
+            QValue::CryptoKey(_) => Err("Cannot convert CryptoKey to number".into()),
             QValue::Rng(_) => Err("Cannot convert RNG to number".into()),
+            QValue::Cache(_) => Err("Cannot convert Cache to number".into()),
+            QValue::Url(_) => Err("Cannot convert Url to number".into()),
+            QValue::IpAddr(_) => Err("Cannot convert IpAddr to number (use to_int())".into()),
+            QValue::Cidr(_) => Err("Cannot convert Cidr to number".into()),
+            QValue::Image(_) => Err("Cannot convert Image to number".into()),
             QValue::StringIO(_) => Err("Cannot convert StringIO to number".into()),
             QValue::SystemStream(_) => Err("Cannot convert SystemStream to number".into()),
             QValue::RedirectGuard(_) => Err("Cannot convert RedirectGuard to number".into()),
             QValue::ProcessResult(_) => Err("Cannot convert ProcessResult to number".into()),
+            QValue::PipelineResult(_) => Err("Cannot convert PipelineResult to number".into()),
             QValue::Process(_) => Err("Cannot convert Process to number".into()),
             QValue::WritableStream(_) => Err("Cannot convert WritableStream to number".into()),
             QValue::ReadableStream(_) => Err("Cannot convert ReadableStream to number".into()),
@@ -400,8 +565,14 @@ impl QValue {
             QValue::UserFun(_) => true, // User functions are truthy
             QValue::Module(_) => true, // Modules are truthy
             QValue::Array(a) => !a.elements.borrow().is_empty(), // Empty arrays are falsy
+            QValue::Iterator(_) => true, // Iterators are truthy (their length isn't known without running them)
+            QValue::BytesBuffer(b) => b.len() > 0, // Empty buffers are falsy, like empty Bytes
             QValue::Dict(d) => !d.as_ref().map.borrow().is_empty(), // Empty dicts are falsy
             QValue::Set(s) => !s.is_empty(), // Empty sets are falsy
+            QValue::Deque(d) => !d.is_empty(),
+            QValue::Heap(h) => !h.is_empty(),
+            QValue::PriorityQueue(pq) => !pq.is_empty(),
+            QValue::Counter(c) => !c.is_empty(),
             QValue::Type(_) => true, // Types are truthy
             QValue::Struct(_) => true, // Struct instances are truthy
             QValue::Trait(_) => true, // Traits are truthy
@@ -413,22 +584,50 @@ impl QValue {
             QValue::Time(_) => true, // Times are truthy
             QValue::Span(_) => true, // Spans are truthy
             QValue::DateRange(_) => true, // Date ranges are truthy
+            QValue::Stopwatch(_) => true, // Stopwatches are truthy
             QValue::SerialPort(_) => true, // Serial ports are truthy
+            QValue::ModbusClient(_) | QValue::CanBus(_) => true, // Fieldbus connections are truthy
             QValue::SqliteConnection(_) => true, // SQLite connections are truthy
             QValue::SqliteCursor(_) => true, // SQLite cursors are truthy
             QValue::PostgresConnection(_) => true, // Postgres connections are truthy
             QValue::PostgresCursor(_) => true, // Postgres cursors are truthy
             QValue::MysqlConnection(_) => true, // MySQL connections are truthy
             QValue::MysqlCursor(_) => true, // MySQL cursors are truthy
+            QValue::ConnectionPool(_) => true, // Connection pools are truthy
+            QValue::PoolLease(_) => true, // Pool leases are truthy
+            QValue::Transaction(_) => true, // Transactions are truthy
+            QValue::RowIterator(_) => true, // Row iterators are truthy
+            QValue::ImapClient(_) | QValue::Pop3Client(_) => true, // Mail clients are truthy
+            QValue::FtpClient(_) | QValue::SftpClient(_) => true, // File transfer clients are truthy
+            QValue::NatsClient(_) | QValue::NatsSubscription(_) => true, // Messaging handles are truthy
             QValue::HtmlTemplate(_) => true, // HTML templates are truthy
+            QValue::SafeString(s) => !s.value.is_empty(), // Like Str
+            QValue::RegexPattern(_) => true,
+            QValue::RegexMatch(_) => true, // search() returns nil when nothing matches
             QValue::HttpClient(_) => true, // HTTP clients are truthy
             QValue::HttpRequest(_) => true, // HTTP requests are truthy
             QValue::HttpResponse(_) => true, // HTTP responses are truthy
+            QValue::XmlElement(_) | QValue::XmlReader(_) => true,
+            QValue::CsvReader(_) | QValue::CsvWriter(_) | QValue::JsonStream(_) | QValue::JsonSchema(_) => true,
+            QValue::ProtoSchema(_) => true,
+            QValue::TarReader(_) => true,
+            QValue::TarWriter(_) => true,
+            QValue::Compressor(_) => true,
+            QValue::Decompressor(_) => true,
+            QValue::CompressedReader(_) => true,
+            QValue::CompressedWriter(_) => true,
+            QValue::CryptoKey(_) => true,
             QValue::Rng(_) => true, // RNG objects are truthy
+            QValue::Cache(_) => true, // Caches are truthy, even when empty
+            QValue::Url(_) => true,
+            QValue::IpAddr(_) => true,
+            QValue::Cidr(_) => true,
+            QValue::Image(_) => true,
             QValue::StringIO(sio) => !sio.borrow().empty(), // Empty StringIO is falsy
             QValue::SystemStream(_) => true, // System streams are truthy
             QValue::RedirectGuard(rg) => rg.is_active(), // Active guards are truthy, restored are falsy
             QValue::ProcessResult(pr) => pr.code == 0, // Success is truthy, failure is falsy
+            QValue::PipelineResult(pr) => pr.code == 0,
             QValue::Process(_) => true, // Processes are truthy
             QValue::WritableStream(_) => true, // Writable streams are truthy
             QValue::ReadableStream(_) => true, // Readable streams are truthy
@@ -466,8 +665,14 @@ impl QValue {
             QValue::UserFun(f) => f.str(),
             QValue::Module(m) => m.str(),
             QValue::Array(a) => a.str(),
+            QValue::Iterator(it) => it.str(),
+            QValue::BytesBuffer(b) => b.str(),
             QValue::Dict(d) => d.str(),
             QValue::Set(s) => s.str(),
+            QValue::Deque(d) => d.str(),
+            QValue::Heap(h) => h.str(),
+            QValue::PriorityQueue(pq) => pq.str(),
+            QValue::Counter(c) => c.str(),
             QValue::Type(t) => t.str(),
             QValue::Struct(s) => s.borrow().str(),
             QValue::Trait(t) => t.str(),
@@ -479,22 +684,58 @@ impl QValue {
             QValue::Time(t) => t.str(),
             QValue::Span(s) => s.str(),
             QValue::DateRange(dr) => dr.str(),
+            QValue::Stopwatch(sw) => sw.str(),
             QValue::SerialPort(sp) => sp.str(),
+            QValue::ModbusClient(c) => c.str(),
+            QValue::CanBus(b) => b.str(),
             QValue::SqliteConnection(conn) => conn.str(),
             QValue::SqliteCursor(cursor) => cursor.str(),
             QValue::PostgresConnection(conn) => conn.str(),
             QValue::PostgresCursor(cursor) => cursor.str(),
             QValue::MysqlConnection(conn) => conn.str(),
             QValue::MysqlCursor(cursor) => cursor.str(),
+            QValue::ConnectionPool(pool) => pool.str(),
+            QValue::PoolLease(lease) => lease.str(),
+            QValue::Transaction(tx) => tx.str(),
+            QValue::RowIterator(it) => it.str(),
+            QValue::ImapClient(c) => c.str(),
+            QValue::Pop3Client(c) => c.str(),
+            QValue::FtpClient(c) => c.str(),
+            QValue::SftpClient(c) => c.str(),
+            QValue::NatsClient(c) => c.str(),
+            QValue::NatsSubscription(s) => s.str(),
             QValue::HtmlTemplate(tmpl) => tmpl.str(),
+            QValue::SafeString(s) => s.value.clone(),
+            QValue::RegexPattern(p) => p.str(),
+            QValue::RegexMatch(m) => m.str(),
             QValue::HttpClient(client) => client.str(),
             QValue::HttpRequest(req) => req.str(),
             QValue::HttpResponse(resp) => resp.str(),
+            QValue::XmlElement(el) => el.str(),
+            QValue::XmlReader(reader) => reader.str(),
+            QValue::CsvReader(reader) => reader.str(),
+            QValue::CsvWriter(writer) => writer.str(),
+            QValue::JsonStream(stream) => stream.str(),
+            QValue::JsonSchema(schema) => schema.str(),
+            QValue::ProtoSchema(v) => v.str(),
+            QValue::TarReader(v) => v.str(),
+            QValue::TarWriter(v) => v.str(),
+            QValue::Compressor(v) => v.str(),
+            QValue::Decompressor(v) => v.str(),
+            QValue::CompressedReader(v) => v.str(),
+            QValue::CompressedWriter(v) => v.str(),
+            QValue::CryptoKey(v) => v.str(),
             QValue::Rng(rng) => rng.str(),
+            QValue::Cache(c) => c.str(),
+            QValue::Url(u) => u.str(),
+            QValue::IpAddr(a) => a.str(),
+            QValue::Cidr(n) => n.str(),
+            QValue::Image(img) => img.str(),
             QValue::StringIO(sio) => sio.borrow().str(),
             QValue::SystemStream(ss) => ss.str(),
             QValue::RedirectGuard(rg) => rg.str(),
             QValue::ProcessResult(pr) => pr.str(),
+            QValue::PipelineResult(pr) => pr.str(),
             QValue::Process(p) => p.str(),
             QValue::WritableStream(ws) => ws.str(),
             QValue::ReadableStream(rs) => rs.str(),
@@ -521,8 +762,14 @@ impl QValue {
             QValue::UserFun(_) => "UserFun",
             QValue::Module(_) => "Module",
             QValue::Array(_) => "Array",
+            QValue::Iterator(_) => "Iterator",
+            QValue::BytesBuffer(_) => "BytesBuffer",
             QValue::Dict(_) => "Dict",
             QValue::Set(_) => "Set",
+            QValue::Deque(_) => "Deque",
+            QValue::Heap(_) => "Heap",
+            QValue::PriorityQueue(_) => "PriorityQueue",
+            QValue::Counter(_) => "Counter",
             QValue::Type(_) => "Type",
             QValue::Struct(_) => "Struct",
             QValue::Trait(_) => "Trait",
@@ -534,22 +781,58 @@ impl QValue {
             QValue::Time(_) => "Time",
             QValue::Span(_) => "Span",
             QValue::DateRange(_) => "DateRange",
+            QValue::Stopwatch(_) => "Stopwatch",
             QValue::SerialPort(_) => "SerialPort",
+            QValue::ModbusClient(_) => "ModbusClient",
+            QValue::CanBus(_) => "CanBus",
             QValue::SqliteConnection(_) => "SqliteConnection",
             QValue::SqliteCursor(_) => "SqliteCursor",
             QValue::PostgresConnection(_) => "PostgresConnection",
             QValue::PostgresCursor(_) => "PostgresCursor",
             QValue::MysqlConnection(_) => "MysqlConnection",
             QValue::MysqlCursor(_) => "MysqlCursor",
+            QValue::ConnectionPool(_) => "ConnectionPool",
+            QValue::PoolLease(_) => "PoolLease",
+            QValue::Transaction(_) => "Transaction",
+            QValue::RowIterator(_) => "RowIterator",
+            QValue::ImapClient(_) => "ImapClient",
+            QValue::Pop3Client(_) => "Pop3Client",
+            QValue::FtpClient(_) => "FtpClient",
+            QValue::SftpClient(_) => "SftpClient",
+            QValue::NatsClient(_) => "NatsClient",
+            QValue::NatsSubscription(_) => "NatsSubscription",
             QValue::HtmlTemplate(_) => "HtmlTemplate",
+            QValue::SafeString(_) => "SafeString",
+            QValue::RegexPattern(_) => "Pattern",
+            QValue::RegexMatch(_) => "Match",
             QValue::HttpClient(_) => "HttpClient",
             QValue::HttpRequest(_) => "HttpRequest",
             QValue::HttpResponse(_) => "HttpResponse",
+            QValue::XmlElement(_) => "XmlElement",
+            QValue::XmlReader(_) => "XmlReader",
+            QValue::CsvReader(_) => "CsvReader",
+            QValue::CsvWriter(_) => "CsvWriter",
+            QValue::JsonStream(_) => "JsonStream",
+            QValue::JsonSchema(_) => "JsonSchema",
+            QValue::ProtoSchema(_) => "ProtoSchema",
+            QValue::TarReader(_) => "TarReader",
+            QValue::TarWriter(_) => "TarWriter",
+            QValue::Compressor(_) => "Compressor",
+            QValue::Decompressor(_) => "Decompressor",
+            QValue::CompressedReader(_) => "CompressedReader",
+            QValue::CompressedWriter(_) => "CompressedWriter",
+            QValue::CryptoKey(_) => "CryptoKey",
             QValue::Rng(_) => "RNG",
+            QValue::Cache(_) => "Cache",
+            QValue::Url(_) => "Url",
+            QValue::IpAddr(_) => "IpAddr",
+            QValue::Cidr(_) => "Cidr",
+            QValue::Image(_) => "Image",
             QValue::StringIO(_) => "StringIO",
             QValue::SystemStream(_) => "SystemStream",
             QValue::RedirectGuard(_) => "RedirectGuard",
             QValue::ProcessResult(_) => "ProcessResult",
+            QValue::PipelineResult(_) => "PipelineResult",
             QValue::Process(_) => "Process",
             QValue::WritableStream(_) => "WritableStream",
             QValue::ReadableStream(_) => "ReadableStream",
@@ -744,10 +1027,98 @@ where
             }
             Ok(QValue::Int(QInt::new(-1)))
         }
+        "sort_by" => {
+            // sort_by(fn) - Sorted copy, ordered by each element's key (stable)
+            if args.len() != 1 {
+                return arg_err!("sort_by expects 1 argument (function), got {}", args.len());
+            }
+            let user_fn = match &args[0] {
+                QValue::UserFun(user_fn) => user_fn,
+                _ => return Err("sort_by expects a function argument".into())
+            };
+
+            let elements = arr.elements.borrow().clone();
+            let mut keyed = Vec::with_capacity(elements.len());
+            for elem in elements {
+                let key = call_user_fn(user_fn, vec![elem.clone()], scope)?;
+                keyed.push((key, elem));
+            }
+            keyed.sort_by(|(a, _), (b, _)| compare_values(a, b).unwrap_or(std::cmp::Ordering::Equal));
+            Ok(QValue::Array(QArray::new(keyed.into_iter().map(|(_, elem)| elem).collect())))
+        }
+        "group_by" => {
+            // group_by(fn) - Dict from each key (as a string) to the elements with that key
+            if args.len() != 1 {
+                return arg_err!("group_by expects 1 argument (function), got {}", args.len());
+            }
+            let user_fn = match &args[0] {
+                QValue::UserFun(user_fn) => user_fn,
+                _ => return Err("group_by expects a function argument".into())
+            };
+
+            let elements = arr.elements.borrow().clone();
+            let mut groups: indexmap::IndexMap<String, Vec<QValue>> = indexmap::IndexMap::new();
+            for elem in elements {
+                let key = call_user_fn(user_fn, vec![elem.clone()], scope)?.as_str();
+                groups.entry(key).or_default().push(elem);
+            }
+            let map = groups.into_iter()
+                .map(|(key, group)| (key, QValue::Array(QArray::new(group))))
+                .collect();
+            Ok(QValue::Dict(Box::new(QDict::new(map))))
+        }
+        "unique" => {
+            // unique(fn = nil) - Copy without repeated elements (or repeated keys), keeping the first
+            if args.len() > 1 {
+                return arg_err!("unique expects 0 or 1 arguments (function), got {}", args.len());
+            }
+            let elements = arr.elements.borrow().clone();
+            let keys = match args.first() {
+                None | Some(QValue::Nil(_)) => elements.clone(),
+                Some(QValue::UserFun(user_fn)) => {
+                    let mut keys = Vec::with_capacity(elements.len());
+                    for elem in &elements {
+                        keys.push(call_user_fn(user_fn, vec![elem.clone()], scope)?);
+                    }
+                    keys
+                }
+                Some(_) => return Err("unique expects a function argument".into())
+            };
+            Ok(QValue::Array(QArray::new(unique_by_key(elements, keys))))
+        }
         _ => attr_err!("Unknown array higher-order method: {}", method_name)
     }
 }
 
+/// The elements whose key is the first occurrence of that key, in order
+fn unique_by_key(elements: Vec<QValue>, keys: Vec<QValue>) -> Vec<QValue> {
+    let mut unique = Vec::new();
+    // Int and Str keys (the common case) are deduplicated with a hash set;
+    // any other key falls back to pairwise comparison
+    if keys.iter().all(|key| matches!(key, QValue::Int(_) | QValue::Str(_))) {
+        let mut seen = std::collections::HashSet::new();
+        for (elem, key) in elements.into_iter().zip(keys) {
+            let hashed = match key {
+                QValue::Int(i) => Ok(i.value),
+                QValue::Str(s) => Err(s.value.clone()),
+                _ => unreachable!(),
+            };
+            if seen.insert(hashed) {
+                unique.push(elem);
+            }
+        }
+    } else {
+        let mut seen: Vec<QValue> = Vec::new();
+        for (elem, key) in elements.into_iter().zip(keys) {
+            if !seen.iter().any(|k| values_equal(k, &key)) {
+                seen.push(key);
+                unique.push(elem);
+            }
+        }
+    }
+    unique
+}
+
 // Higher-order dict methods that need scope access
 pub fn call_dict_higher_order_method<F>(
     dict: &QDict,
@@ -782,6 +1153,40 @@ where
             }
             Ok(QValue::Nil(QNil))
         }
+        "map_values" | "filter" => {
+            // map_values(fn) - New dict with each value transformed
+            // filter(fn) - New dict with the entries fn returns true for
+            // fn takes (value) or (key, value)
+            if args.len() != 1 {
+                return arg_err!("{} expects 1 argument (function), got {}", method_name, args.len());
+            }
+            let user_fn = match &args[0] {
+                QValue::UserFun(user_fn) => user_fn,
+                _ => return Err(format!("{} expects a function argument", method_name).into())
+            };
+            if user_fn.params.len() != 1 && user_fn.params.len() != 2 {
+                return Err(format!("dict.{} function must accept 1 or 2 parameters (value) or (key, value)", method_name).into());
+            }
+
+            let entries: Vec<(String, QValue)> = dict.map.borrow().iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            let mut result = indexmap::IndexMap::new();
+            for (key, value) in entries {
+                let fn_args = if user_fn.params.len() == 2 {
+                    vec![QValue::Str(QString::new(key.clone())), value.clone()]
+                } else {
+                    vec![value.clone()]
+                };
+                let returned = call_user_fn(user_fn, fn_args, scope)?;
+                if method_name == "map_values" {
+                    result.insert(key, returned);
+                } else if returned.as_bool() {
+                    result.insert(key, value);
+                }
+            }
+            Ok(QValue::Dict(Box::new(QDict::new(result))))
+        }
         _ => attr_err!("Unknown dict higher-order method: {}", method_name)
     }
 }
@@ -1,6 +1,20 @@
 use super::*;
 use std::collections::HashSet;
 
+/// A member replaced with `sys.patch_member`, and the value to put back
+struct Patch {
+    original: QValue,
+    current: QValue,
+    /// Member tables written, so a Quest module's own functions see the patch too
+    tables: Vec<Rc<RefCell<HashMap<String, QValue>>>>,
+}
+
+thread_local! {
+    /// Patched members by (module source path, member name). Every instance of a
+    /// built-in module reads through this, since each import creates a new one.
+    static PATCHES: RefCell<HashMap<(String, String), Patch>> = RefCell::new(HashMap::new());
+}
+
 // ============================================================================
 // QModule - Module with public/private encapsulation
 // ============================================================================
@@ -47,10 +61,22 @@ impl QModule {
         public_items: HashSet<String>,
         source_path: Option<String>,
         doc: Option<String>
+    ) -> Self {
+        Self::with_shared_members(name, Rc::new(RefCell::new(members)), public_items, source_path, doc)
+    }
+
+    /// Create module backed by an existing member table (a Quest module's own scope),
+    /// so replacing a member is also seen by the module's internal calls
+    pub fn with_shared_members(
+        name: String,
+        members: Rc<RefCell<HashMap<String, QValue>>>,
+        public_items: HashSet<String>,
+        source_path: Option<String>,
+        doc: Option<String>
     ) -> Self {
         QModule {
             name,
-            members: Rc::new(RefCell::new(members)),
+            members,
             public_items,
             doc,
             source_path,
@@ -61,10 +87,59 @@ impl QModule {
     /// Get a member by name (only if public)
     /// Returns None if member doesn't exist or is private
     pub fn get_member(&self, member_name: &str) -> Option<QValue> {
-        if self.public_items.contains(member_name) {
-            self.members.borrow().get(member_name).cloned()
-        } else {
-            None
+        if !self.public_items.contains(member_name) {
+            return None;
+        }
+        if let Some(patched) = self.patched_member(member_name) {
+            return Some(patched);
+        }
+        self.members.borrow().get(member_name).cloned()
+    }
+
+    fn patch_key(&self, member_name: &str) -> Option<(String, String)> {
+        self.source_path.as_ref().map(|path| (path.clone(), member_name.to_string()))
+    }
+
+    fn patched_member(&self, member_name: &str) -> Option<QValue> {
+        let key = self.patch_key(member_name)?;
+        PATCHES.with(|patches| patches.borrow().get(&key).map(|p| p.current.clone()))
+    }
+
+    /// Replace a public member until unpatch_member() (test.mock). The new value is
+    /// seen through every import of the module and, for Quest modules, by the
+    /// module's own functions. Returns whether the member was already patched.
+    pub fn patch_member(&self, member_name: &str, value: QValue) -> Result<bool, String> {
+        let (Some(original), Some(key)) = (self.get_member(member_name), self.patch_key(member_name)) else {
+            return Err(format!("AttrErr: Module {} has no member '{}'", self.name, member_name));
+        };
+        self.members.borrow_mut().insert(member_name.to_string(), value.clone());
+        Ok(PATCHES.with(|patches| {
+            let mut patches = patches.borrow_mut();
+            match patches.get_mut(&key) {
+                Some(patch) => {
+                    patch.current = value;
+                    if !patch.tables.iter().any(|t| Rc::ptr_eq(t, &self.members)) {
+                        patch.tables.push(Rc::clone(&self.members));
+                    }
+                    true
+                }
+                None => {
+                    let tables = vec![Rc::clone(&self.members)];
+                    patches.insert(key, Patch { original, current: value, tables });
+                    false
+                }
+            }
+        }))
+    }
+
+    /// Put back the member replaced by the first patch_member() call. Does
+    /// nothing if the member isn't patched.
+    pub fn unpatch_member(&self, member_name: &str) {
+        let Some(key) = self.patch_key(member_name) else { return };
+        if let Some(patch) = PATCHES.with(|patches| patches.borrow_mut().remove(&key)) {
+            for table in &patch.tables {
+                table.borrow_mut().insert(member_name.to_string(), patch.original.clone());
+            }
         }
     }
 
@@ -1,18 +1,24 @@
 use super::*;
-use ::ndarray::{ArrayD, IxDyn, Axis, Array2, Ix2};
-use crate::{arg_err, index_err, value_err, attr_err};
+use ::ndarray::{ArcArray, ArrayD, IxDyn, Axis, Array2, Ix2, SliceInfo, SliceInfoElem, Zip};
+use crate::{arg_err, index_err, value_err, attr_err, type_err};
 
 /// QNDArray - N-dimensional array for numerical computing
-/// Wraps ndarray::ArrayD for efficient matrix/tensor operations
-#[derive(Debug, Clone)]
+/// Wraps a reference-counted ndarray so copies, slices, transposes and
+/// reshapes share the underlying buffer instead of copying it
+#[derive(Debug)]
 pub struct QNDArray {
-    pub data: ArrayD<f64>,  // Dynamic dimensions, f64 elements for now
+    pub data: ArcArray<f64, IxDyn>,  // Dynamic dimensions, f64 elements for now
     pub id: u64,
 }
 
 impl QNDArray {
     /// Create new NDArray from ArrayD
     pub fn new(data: ArrayD<f64>) -> Self {
+        Self::from_shared(data.into_shared())
+    }
+
+    /// Create an NDArray sharing the buffer of `data` (a view of another array)
+    pub fn from_shared(data: ArcArray<f64, IxDyn>) -> Self {
         let id = next_object_id();
         crate::alloc_counter::track_alloc("NDArray", id);
         QNDArray { data, id }
@@ -99,30 +105,84 @@ impl QNDArray {
         if self.ndim() != 2 {
             return value_err!("transpose requires 2D array, got {}D", self.ndim());
         }
-        let transposed = self.data.t().to_owned();
-        Ok(Self::new(transposed))
+        Ok(Self::from_shared(self.data.clone().reversed_axes()))
     }
 
-    /// Reshape to new shape
-    pub fn reshape(&self, new_shape: Vec<usize>) -> Result<Self, String> {
-        let new_size: usize = new_shape.iter().product();
+    /// Reshape to new shape. One dimension may be -1, meaning whatever size
+    /// makes the element count match.
+    pub fn reshape(&self, new_shape: Vec<i64>) -> Result<Self, String> {
+        let known: i64 = new_shape.iter().filter(|&&d| d != -1).product();
+        let inferred = new_shape.iter().filter(|&&d| d == -1).count();
+        if inferred > 1 || new_shape.iter().any(|&d| d < -1) {
+            return value_err!("Invalid shape {:?}: dimensions must be non-negative, with at most one -1", new_shape);
+        }
+        let size = self.size() as i64;
+        let shape: Vec<usize> = if inferred == 1 {
+            if known == 0 || size % known != 0 {
+                return value_err!("Cannot reshape array of size {} into shape {:?}", size, new_shape);
+            }
+            new_shape.iter().map(|&d| if d == -1 { (size / known) as usize } else { d as usize }).collect()
+        } else {
+            new_shape.iter().map(|&d| d as usize).collect()
+        };
+
+        let new_size: usize = shape.iter().product();
         if new_size != self.size() {
             return value_err!(
                 "Cannot reshape array of size {} into shape {:?} (size {})",
                 self.size(),
-                new_shape,
+                shape,
                 new_size
             );
         }
+        Ok(self.reshape_to(&shape))
+    }
 
-        // Use to_shape() which handles non-contiguous arrays by copying if needed
-        let reshaped = self
-            .data
-            .to_shape(IxDyn(&new_shape))
-            .map_err(|e| format!("Reshape error: {}", e))?
-            .to_owned();
-
-        Ok(Self::new(reshaped))
+    /// Reshape to a shape with the same element count, sharing the buffer
+    /// when the data is contiguous and copying it otherwise
+    fn reshape_to(&self, shape: &[usize]) -> Self {
+        if self.data.is_standard_layout() {
+            if let Ok(view) = self.data.clone().into_shape_with_order(IxDyn(shape)) {
+                return Self::from_shared(view);
+            }
+        }
+        let copied = ArrayD::from_shape_vec(IxDyn(shape), self.data.iter().copied().collect())
+            .expect("element count was checked");
+        Self::new(copied)
+    }
+
+    /// Index with integers and slices, e.g. `nd[1]`, `nd[1:3, :]` or
+    /// `nd[0, -1]`. Missing trailing axes are taken whole. Returns a Float
+    /// when every axis is indexed by an integer, otherwise an NDArray view
+    /// sharing this array's data.
+    pub fn index(&self, entries: &[IndexEntry]) -> Result<QValue, EvalError> {
+        if entries.len() > self.ndim() {
+            return index_err!("Too many indices for {}D array: got {}", self.ndim(), entries.len());
+        }
+        let shape = self.shape();
+        let mut elems = Vec::with_capacity(self.ndim());
+        for (axis, entry) in entries.iter().enumerate() {
+            elems.push(match entry {
+                IndexEntry::At(value) => {
+                    let index = match value {
+                        QValue::Int(i) => i.value,
+                        other => return type_err!("NDArray index must be Int or a slice, got {}", other.as_obj().cls()),
+                    };
+                    SliceInfoElem::Index(crate::normalize_index(index, shape[axis], "NDArray")? as isize)
+                }
+                IndexEntry::Range(range) => range.to_ndarray_elem(shape[axis])?,
+            });
+        }
+        elems.extend((entries.len()..self.ndim()).map(|_| SliceInfoElem::from(..)));
+
+        let all_integers = entries.len() == self.ndim() && entries.iter().all(|e| matches!(e, IndexEntry::At(_)));
+        let info = SliceInfo::<Vec<SliceInfoElem>, IxDyn, IxDyn>::try_from(elems)
+            .map_err(|e| format!("Invalid index: {}", e))?;
+        let view = self.data.clone().slice_move(info);
+        if all_integers {
+            return Ok(QValue::Float(QFloat::new(view.iter().next().copied().unwrap_or(0.0))));
+        }
+        Ok(QValue::NDArray(Self::from_shared(view)))
     }
 
     /// Matrix multiplication (2D only)
@@ -287,56 +347,63 @@ impl QNDArray {
         }
     }
 
-    /// Element-wise addition
-    pub fn add(&self, other: &QNDArray) -> Result<Self, String> {
-        if self.shape() != other.shape() {
-            return value_err!(
-                "Shape mismatch for addition: {:?} vs {:?}",
+    /// Apply `op` element-wise, broadcasting the shapes NumPy-style
+    fn broadcast_with(&self, other: &QNDArray, what: &str, op: impl Fn(f64, f64) -> f64) -> Result<Self, String> {
+        let shape = broadcast_shape(&self.shape(), &other.shape()).ok_or_else(|| {
+            format!(
+                "ValueErr: Shapes {:?} and {:?} cannot be broadcast together for {}",
                 self.shape(),
-                other.shape()
-            );
-        }
-        let result = &self.data + &other.data;
+                other.shape(),
+                what
+            )
+        })?;
+        // Both broadcasts succeed since the shape was checked above
+        let left = self.data.broadcast(IxDyn(&shape)).ok_or("Broadcast failed")?;
+        let right = other.data.broadcast(IxDyn(&shape)).ok_or("Broadcast failed")?;
+        let result = Zip::from(&left).and(&right).map_collect(|&a, &b| op(a, b));
         Ok(Self::new(result))
     }
 
+    /// Element-wise addition
+    pub fn add(&self, other: &QNDArray) -> Result<Self, String> {
+        self.broadcast_with(other, "addition", |a, b| a + b)
+    }
+
     /// Element-wise subtraction
     pub fn sub(&self, other: &QNDArray) -> Result<Self, String> {
-        if self.shape() != other.shape() {
-            return value_err!(
-                "Shape mismatch for subtraction: {:?} vs {:?}",
-                self.shape(),
-                other.shape()
-            );
-        }
-        let result = &self.data - &other.data;
-        Ok(Self::new(result))
+        self.broadcast_with(other, "subtraction", |a, b| a - b)
     }
 
     /// Element-wise multiplication (Hadamard product)
     pub fn mul(&self, other: &QNDArray) -> Result<Self, String> {
-        if self.shape() != other.shape() {
-            return value_err!(
-                "Shape mismatch for multiplication: {:?} vs {:?}",
-                self.shape(),
-                other.shape()
-            );
-        }
-        let result = &self.data * &other.data;
-        Ok(Self::new(result))
+        self.broadcast_with(other, "multiplication", |a, b| a * b)
     }
 
     /// Element-wise division
     pub fn div(&self, other: &QNDArray) -> Result<Self, String> {
-        if self.shape() != other.shape() {
-            return value_err!(
-                "Shape mismatch for division: {:?} vs {:?}",
-                self.shape(),
-                other.shape()
-            );
-        }
-        let result = &self.data / &other.data;
-        Ok(Self::new(result))
+        self.broadcast_with(other, "division", |a, b| a / b)
+    }
+
+    /// Element-wise remainder
+    pub fn rem(&self, other: &QNDArray) -> Result<Self, String> {
+        self.broadcast_with(other, "modulo", |a, b| a % b)
+    }
+
+    /// Arithmetic operator (`+ - * / %`) where either operand is an NDArray
+    /// and the other is an NDArray or a number
+    pub fn apply_operator(op: &str, left: &QValue, right: &QValue) -> Result<QValue, EvalError> {
+        let (mut left_scalar, mut right_scalar) = (None, None);
+        let left = operator_operand(op, left, &mut left_scalar)?;
+        let right = operator_operand(op, right, &mut right_scalar)?;
+        let result = match op {
+            "+" => left.add(right)?,
+            "-" => left.sub(right)?,
+            "*" => left.mul(right)?,
+            "/" => left.div(right)?,
+            "%" => left.rem(right)?,
+            _ => return type_err!("Unsupported operator for NDArray: {}", op),
+        };
+        Ok(QValue::NDArray(result))
     }
 
     /// Scalar addition
@@ -365,18 +432,17 @@ impl QNDArray {
 
     /// Flatten to 1D array
     pub fn flatten(&self) -> Self {
-        let flat = self.data.to_shape(IxDyn(&[self.size()])).unwrap().to_owned();
-        Self::new(flat)
+        self.reshape_to(&[self.size()])
     }
 
     /// Explicit copy
     pub fn copy(&self) -> Self {
-        Self::new(self.data.clone())
+        Self::new(self.data.to_owned())
     }
 
     /// Convert to nested Quest arrays
     pub fn to_array(&self) -> QValue {
-        fn recursive_to_array(data: &ArrayD<f64>, indices: &mut Vec<usize>, depth: usize) -> QValue {
+        fn recursive_to_array(data: &ArcArray<f64, IxDyn>, indices: &mut Vec<usize>, depth: usize) -> QValue {
             if depth == data.ndim() {
                 // Leaf: return scalar
                 let val = data[IxDyn(indices)];
@@ -399,6 +465,31 @@ impl QNDArray {
         recursive_to_array(&self.data, &mut indices, 0)
     }
 
+    /// Optional axis argument: `sum()`, `sum(1)`, `sum(nil)` or `sum(axis: 1)`.
+    /// Negative axes count from the last dimension.
+    fn parse_axis(&self, args: &[QValue]) -> Result<Option<usize>, EvalError> {
+        let axis = match args {
+            [] => return Ok(None),
+            [QValue::Dict(opts)] => match opts.get("axis") {
+                Some(axis) => axis,
+                None => return Ok(None),
+            },
+            [axis] => axis.clone(),
+            _ => return arg_err!("expects 0 or 1 arguments (axis), got {}", args.len()),
+        };
+        let axis = match axis {
+            QValue::Int(i) => i.value,
+            QValue::Nil(_) => return Ok(None),
+            _ => return type_err!("axis must be integer or nil"),
+        };
+        let ndim = self.ndim() as i64;
+        let resolved = if axis < 0 { axis + ndim } else { axis };
+        if resolved < 0 || resolved >= ndim {
+            return index_err!("Axis {} out of bounds for {}D array", axis, ndim);
+        }
+        Ok(Some(resolved as usize))
+    }
+
     /// Call method on NDArray
     pub fn call_method(&self, method_name: &str, args: Vec<QValue>) -> Result<QValue, EvalError> {
         // Try QObj trait methods first
@@ -445,7 +536,7 @@ impl QNDArray {
                     QValue::Array(arr) => {
                         arr.elements.borrow().iter().map(|v| {
                             match v {
-                                QValue::Int(i) => Ok(i.value as usize),
+                                QValue::Int(i) => Ok(i.value),
                                 _ => Err("reshape shape must contain integers".into()),
                             }
                         }).collect::<Result<Vec<_>, EvalError>>()?
@@ -467,47 +558,27 @@ impl QNDArray {
                 Ok(QValue::NDArray(result))
             }
             "sum" => {
-                let axis = if args.is_empty() {
-                    None
-                } else if args.len() == 1 {
-                    Some(match &args[0] {
-                        QValue::Int(i) => i.value as usize,
-                        QValue::Nil(_) => return self.sum(None),
-                        _ => return Err("sum axis must be integer or nil".into()),
-                    })
-                } else {
-                    return arg_err!("sum expects 0 or 1 arguments, got {}", args.len());
-                };
+                let axis = self.parse_axis(&args)?;
                 self.sum(axis)
             }
             "mean" => {
-                let axis = if args.is_empty() {
-                    None
-                } else if args.len() == 1 {
-                    Some(match &args[0] {
-                        QValue::Int(i) => i.value as usize,
-                        QValue::Nil(_) => return self.mean(None),
-                        _ => return Err("mean axis must be integer or nil".into()),
-                    })
-                } else {
-                    return arg_err!("mean expects 0 or 1 arguments, got {}", args.len());
-                };
+                let axis = self.parse_axis(&args)?;
                 self.mean(axis)
             }
             "min" => {
-                let axis = parse_optional_axis(&args)?;
+                let axis = self.parse_axis(&args)?;
                 self.min(axis)
             }
             "max" => {
-                let axis = parse_optional_axis(&args)?;
+                let axis = self.parse_axis(&args)?;
                 self.max(axis)
             }
             "std" => {
-                let axis = parse_optional_axis(&args)?;
+                let axis = self.parse_axis(&args)?;
                 self.std(axis)
             }
             "var" => {
-                let axis = parse_optional_axis(&args)?;
+                let axis = self.parse_axis(&args)?;
                 self.var(axis)
             }
             "add" => {
@@ -626,21 +697,37 @@ impl QNDArray {
     }
 }
 
-/// Helper to parse optional axis argument
-fn parse_optional_axis(args: &[QValue]) -> Result<Option<usize>, String> {
-    if args.is_empty() {
-        Ok(None)
-    } else if args.len() == 1 {
-        match &args[0] {
-            QValue::Int(i) => Ok(Some(i.value as usize)),
-            QValue::Nil(_) => Ok(None),
-            _ => Err("axis must be integer or nil".into()),
+/// An operator operand as an NDArray; numbers become a 0-dimensional array
+/// stored in `scalar` so they broadcast against any shape
+fn operator_operand<'a>(op: &str, value: &'a QValue, scalar: &'a mut Option<QNDArray>) -> Result<&'a QNDArray, EvalError> {
+    match value {
+        QValue::NDArray(nda) => Ok(nda),
+        QValue::Int(_) | QValue::Float(_) | QValue::Decimal(_) | QValue::BigInt(_) => {
+            Ok(scalar.insert(QNDArray::full(vec![], value.as_num()?)))
         }
-    } else {
-        arg_err!("expects 0 or 1 arguments, got {}", args.len())
+        other => type_err!("Unsupported operand type for {}: NDArray and {}", op, other.as_obj().cls()),
     }
 }
 
+/// Shape that two shapes broadcast to, or None if they are incompatible.
+/// Shapes are aligned from the last dimension; each pair of dimensions must
+/// be equal or one of them must be 1.
+fn broadcast_shape(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let ndim = a.len().max(b.len());
+    let mut shape = vec![0; ndim];
+    for i in 0..ndim {
+        let dim_a = if i < ndim - a.len() { 1 } else { a[i - (ndim - a.len())] };
+        let dim_b = if i < ndim - b.len() { 1 } else { b[i - (ndim - b.len())] };
+        shape[i] = match (dim_a, dim_b) {
+            (x, y) if x == y => x,
+            (1, y) => y,
+            (x, 1) => x,
+            _ => return None,
+        };
+    }
+    Some(shape)
+}
+
 impl QObj for QNDArray {
     fn cls(&self) -> String {
         "NDArray".to_string()
@@ -677,6 +764,16 @@ impl QObj for QNDArray {
     }
 }
 
+impl Clone for QNDArray {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("NDArray", self.id);
+        QNDArray {
+            data: self.data.clone(),
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QNDArray {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("NDArray", self.id);
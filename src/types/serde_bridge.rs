@@ -0,0 +1,834 @@
+// Serde bridge for Quest values
+//
+// Lets native modules and embedders move structured data in and out of Quest
+// without hand-building QDict/QArray trees:
+//
+//   QValue::from_serde(json)        serde_json::Value -> QValue
+//   value.to_serde()                QValue -> serde_json::Value
+//   to_qvalue(&my_struct)           any T: Serialize -> QValue
+//   from_qvalue::<MyStruct>(&value) QValue -> any T: Deserialize
+//
+// QValue also implements Serialize and Deserialize directly, so it can be
+// written to or read from any serde format (YAML, TOML, bincode, ...).
+//
+// Mapping: unit/None <-> nil, integers <-> Int (BigInt beyond i64), floats <->
+// Float, strings/chars <-> Str, byte buffers <-> Bytes, sequences/tuples <->
+// Array, maps/structs <-> Dict. Enum variants use serde's externally tagged
+// form ("Variant" or {"Variant": payload}), matching serde_json.
+
+use std::fmt;
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq};
+use serde::{forward_to_deserialize_any, Deserialize};
+use crate::modules::encoding::json_utils::qvalue_to_json;
+use crate::types::*;
+
+/// Error raised while converting between QValue and serde data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerdeError(pub String);
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl QValue {
+    /// Convert a serde_json::Value into a Quest value.
+    pub fn from_serde(json: serde_json::Value) -> QValue {
+        match json {
+            serde_json::Value::Null => QValue::Nil(QNil),
+            serde_json::Value::Bool(b) => QValue::Bool(QBool::new(b)),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    QValue::Int(QInt::new(i))
+                } else {
+                    QValue::Float(QFloat::new(n.as_f64().unwrap_or(f64::NAN)))
+                }
+            }
+            serde_json::Value::String(s) => QValue::Str(QString::new(s)),
+            serde_json::Value::Array(arr) => {
+                QValue::Array(QArray::new(arr.into_iter().map(QValue::from_serde).collect()))
+            }
+            serde_json::Value::Object(obj) => {
+                let map: IndexMap<String, QValue> = obj.into_iter()
+                    .map(|(key, val)| (key, QValue::from_serde(val)))
+                    .collect();
+                QValue::Dict(Box::new(QDict::new(map)))
+            }
+        }
+    }
+
+    /// Convert a Quest value into a serde_json::Value.
+    /// Fails for values with no JSON form (functions, modules, connections).
+    #[allow(dead_code)]
+    pub fn to_serde(&self) -> Result<serde_json::Value, String> {
+        qvalue_to_json(self)
+    }
+}
+
+/// Serialize any serde value into a Quest value.
+#[allow(dead_code)]
+pub fn to_qvalue<T: Serialize + ?Sized>(value: &T) -> Result<QValue, String> {
+    value.serialize(QValueSerializer).map_err(|e| e.0)
+}
+
+/// Deserialize a Quest value into any serde type.
+#[allow(dead_code)]
+pub fn from_qvalue<T: DeserializeOwned>(value: &QValue) -> Result<T, String> {
+    T::deserialize(value.clone()).map_err(|e| e.0)
+}
+
+fn bigint_value(value: BigInt) -> QValue {
+    match value.to_i64() {
+        Some(i) => QValue::Int(QInt::new(i)),
+        None => QValue::BigInt(QBigInt::new(value)),
+    }
+}
+
+fn dict_value(map: IndexMap<String, QValue>) -> QValue {
+    QValue::Dict(Box::new(QDict::new(map)))
+}
+
+// ============================================================================
+// QValue -> serde
+// ============================================================================
+
+impl Serialize for QValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            QValue::Nil(_) => serializer.serialize_unit(),
+            QValue::Bool(b) => serializer.serialize_bool(b.value),
+            QValue::Int(i) => serializer.serialize_i64(i.value),
+            QValue::Float(f) => serializer.serialize_f64(f.value),
+            QValue::Str(s) => serializer.serialize_str(&s.value),
+            QValue::SafeString(s) => serializer.serialize_str(&s.value),
+            QValue::Bytes(b) => serializer.serialize_bytes(&b.data),
+            QValue::Array(arr) => {
+                let elements = arr.elements.borrow();
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for elem in elements.iter() {
+                    seq.serialize_element(elem)?;
+                }
+                seq.end()
+            }
+            QValue::Deque(d) => {
+                let elements = d.elements.borrow();
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for elem in elements.iter() {
+                    seq.serialize_element(elem)?;
+                }
+                seq.end()
+            }
+            QValue::Set(set) => {
+                let elements = set.elements.borrow();
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for elem in elements.iter() {
+                    seq.serialize_element(&elem.to_qvalue())?;
+                }
+                seq.end()
+            }
+            QValue::Dict(dict) => {
+                let map = dict.map.borrow();
+                let mut out = serializer.serialize_map(Some(map.len()))?;
+                for (key, val) in map.iter() {
+                    out.serialize_entry(key, val)?;
+                }
+                out.end()
+            }
+            // Everything else takes its JSON representation (BigInt as a
+            // string, timestamps as ISO 8601, structs as objects, ...)
+            other => qvalue_to_json(other)
+                .map_err(ser::Error::custom)?
+                .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for QValue {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            QValue::Nil(_) => visitor.visit_unit(),
+            QValue::Bool(b) => visitor.visit_bool(b.value),
+            QValue::Int(i) => visitor.visit_i64(i.value),
+            QValue::Float(f) => visitor.visit_f64(f.value),
+            QValue::Decimal(d) => visitor.visit_f64(d.value.to_f64().unwrap_or(f64::NAN)),
+            QValue::BigInt(bi) => {
+                if let Some(i) = bi.value.to_i64() {
+                    visitor.visit_i64(i)
+                } else if let Some(u) = bi.value.to_u64() {
+                    visitor.visit_u64(u)
+                } else if let Some(i) = bi.value.to_i128() {
+                    visitor.visit_i128(i)
+                } else if let Some(u) = bi.value.to_u128() {
+                    visitor.visit_u128(u)
+                } else {
+                    visitor.visit_string(bi.value.to_string())
+                }
+            }
+            QValue::Str(s) => visitor.visit_string(s.value.as_ref().clone()),
+            QValue::SafeString(s) => visitor.visit_string(s.value),
+            QValue::Bytes(b) => visitor.visit_byte_buf(b.data.clone()),
+            QValue::Array(arr) => {
+                let elements = arr.elements.borrow().clone();
+                visit_elements(elements, visitor)
+            }
+            QValue::Deque(d) => {
+                let elements: Vec<QValue> = d.elements.borrow().iter().cloned().collect();
+                visit_elements(elements, visitor)
+            }
+            QValue::Set(set) => {
+                let elements: Vec<QValue> = set.elements.borrow().iter().map(|e| e.to_qvalue()).collect();
+                visit_elements(elements, visitor)
+            }
+            QValue::Dict(dict) => {
+                let map = dict.map.borrow().clone();
+                let mut access = MapDeserializer::new(map.into_iter().map(|(k, v)| (DictKey(k), v)));
+                let value = visitor.visit_map(&mut access)?;
+                access.end()?;
+                Ok(value)
+            }
+            other => {
+                let json = qvalue_to_json(&other).map_err(SerdeError)?;
+                serde_json::Value::deserialize_any(json, visitor).map_err(de::Error::custom)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            QValue::Nil(_) => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self {
+            QValue::Str(s) => visitor.visit_enum(s.value.as_ref().clone().into_deserializer()),
+            QValue::Dict(dict) if dict.map.borrow().len() == 1 => {
+                let map = dict.map.borrow().clone();
+                let access = MapDeserializer::new(map.into_iter().map(|(k, v)| (DictKey(k), v)));
+                visitor.visit_enum(MapAccessDeserializer::new(access))
+            }
+            other => Err(de::Error::custom(format!(
+                "expected a variant name or a single-key Dict for enum, got {}",
+                other.q_type()
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Feed elements to a visitor, rejecting any it leaves unconsumed.
+fn visit_elements<'de, V: Visitor<'de>>(elements: Vec<QValue>, visitor: V) -> Result<V::Value, SerdeError> {
+    let mut access = SeqDeserializer::new(elements.into_iter());
+    let value = visitor.visit_seq(&mut access)?;
+    access.end()?;
+    Ok(value)
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for QValue {
+    type Deserializer = QValue;
+
+    fn into_deserializer(self) -> QValue {
+        self
+    }
+}
+
+/// A Dict key being deserialized. Keys are always strings in Quest, so
+/// numeric and boolean key types (e.g. `HashMap<u32, _>`) parse the string,
+/// as serde_json does for object keys.
+struct DictKey(String);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+                match self.0.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => visitor.visit_string(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for DictKey {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for DictKey {
+    type Deserializer = DictKey;
+
+    fn into_deserializer(self) -> DictKey {
+        self
+    }
+}
+
+// ============================================================================
+// serde -> QValue
+// ============================================================================
+
+impl<'de> Deserialize<'de> for QValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<QValue, D::Error> {
+        deserializer.deserialize_any(QValueVisitor)
+    }
+}
+
+struct QValueVisitor;
+
+impl<'de> Visitor<'de> for QValueVisitor {
+    type Value = QValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any value representable in Quest")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<QValue, E> {
+        Ok(QValue::Bool(QBool::new(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<QValue, E> {
+        Ok(QValue::Int(QInt::new(v)))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<QValue, E> {
+        Ok(bigint_value(BigInt::from(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<QValue, E> {
+        Ok(bigint_value(BigInt::from(v)))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<QValue, E> {
+        Ok(bigint_value(BigInt::from(v)))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<QValue, E> {
+        Ok(QValue::Float(QFloat::new(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<QValue, E> {
+        Ok(QValue::Str(QString::new(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<QValue, E> {
+        Ok(QValue::Str(QString::new(v)))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<QValue, E> {
+        Ok(QValue::Bytes(QBytes::new(v.to_vec())))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<QValue, E> {
+        Ok(QValue::Bytes(QBytes::new(v)))
+    }
+
+    fn visit_unit<E>(self) -> Result<QValue, E> {
+        Ok(QValue::Nil(QNil))
+    }
+
+    fn visit_none<E>(self) -> Result<QValue, E> {
+        Ok(QValue::Nil(QNil))
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<QValue, D::Error> {
+        QValue::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<QValue, D::Error> {
+        QValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<QValue, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(elem) = seq.next_element::<QValue>()? {
+            elements.push(elem);
+        }
+        Ok(QValue::Array(QArray::new(elements)))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut access: A) -> Result<QValue, A::Error> {
+        let mut map = IndexMap::new();
+        while let Some(key) = access.next_key::<QValue>()? {
+            let key = dict_key(&key).map_err(de::Error::custom)?;
+            map.insert(key, access.next_value::<QValue>()?);
+        }
+        Ok(dict_value(map))
+    }
+}
+
+/// Dict keys are strings; scalar keys from non-JSON formats are stringified.
+fn dict_key(key: &QValue) -> Result<String, String> {
+    match key {
+        QValue::Str(s) => Ok(s.value.as_ref().clone()),
+        QValue::Int(_) | QValue::BigInt(_) | QValue::Float(_) | QValue::Bool(_) | QValue::Nil(_) => Ok(key.as_str()),
+        other => Err(format!("Dict keys must be strings or scalars, got {}", other.q_type())),
+    }
+}
+
+// ============================================================================
+// Serializer producing QValue
+// ============================================================================
+
+/// serde Serializer whose output is a QValue.
+pub struct QValueSerializer;
+
+impl ser::Serializer for QValueSerializer {
+    type Ok = QValue;
+    type Error = SerdeError;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeDict;
+    type SerializeStruct = SerializeDict;
+    type SerializeStructVariant = SerializeDict;
+
+    fn serialize_bool(self, v: bool) -> Result<QValue, SerdeError> {
+        Ok(QValue::Bool(QBool::new(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<QValue, SerdeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<QValue, SerdeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<QValue, SerdeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<QValue, SerdeError> {
+        Ok(QValue::Int(QInt::new(v)))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<QValue, SerdeError> {
+        Ok(bigint_value(BigInt::from(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<QValue, SerdeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<QValue, SerdeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<QValue, SerdeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<QValue, SerdeError> {
+        Ok(bigint_value(BigInt::from(v)))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<QValue, SerdeError> {
+        Ok(bigint_value(BigInt::from(v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<QValue, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<QValue, SerdeError> {
+        Ok(QValue::Float(QFloat::new(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<QValue, SerdeError> {
+        Ok(QValue::Str(QString::new(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<QValue, SerdeError> {
+        Ok(QValue::Str(QString::new(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<QValue, SerdeError> {
+        Ok(QValue::Bytes(QBytes::new(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<QValue, SerdeError> {
+        Ok(QValue::Nil(QNil))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<QValue, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<QValue, SerdeError> {
+        Ok(QValue::Nil(QNil))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<QValue, SerdeError> {
+        Ok(QValue::Nil(QNil))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<QValue, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<QValue, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<QValue, SerdeError> {
+        let mut map = IndexMap::new();
+        map.insert(variant.to_string(), value.serialize(QValueSerializer)?);
+        Ok(dict_value(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray { elements: Vec::with_capacity(len.unwrap_or(0)), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray { elements: Vec::with_capacity(len), variant: Some(variant) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeDict, SerdeError> {
+        Ok(SerializeDict { map: IndexMap::new(), pending_key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeDict, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeDict, SerdeError> {
+        Ok(SerializeDict { map: IndexMap::new(), pending_key: None, variant: Some(variant) })
+    }
+}
+
+/// Wrap a finished value as {variant: value} for tuple/struct enum variants.
+fn tag_variant(variant: Option<&'static str>, value: QValue) -> QValue {
+    match variant {
+        Some(name) => {
+            let mut map = IndexMap::new();
+            map.insert(name.to_string(), value);
+            dict_value(map)
+        }
+        None => value,
+    }
+}
+
+pub struct SerializeArray {
+    elements: Vec<QValue>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeArray {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.elements.push(value.serialize(QValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<QValue, SerdeError> {
+        Ok(tag_variant(self.variant, QValue::Array(QArray::new(self.elements))))
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+pub struct SerializeDict {
+    map: IndexMap<String, QValue>,
+    pending_key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeDict {
+    fn finish(self) -> Result<QValue, SerdeError> {
+        Ok(tag_variant(self.variant, dict_value(self.map)))
+    }
+}
+
+impl ser::SerializeMap for SerializeDict {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let key = key.serialize(QValueSerializer)?;
+        self.pending_key = Some(dict_key(&key).map_err(SerdeError)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self.pending_key.take()
+            .ok_or_else(|| SerdeError("serialize_value called before serialize_key".to_string()))?;
+        self.map.insert(key, value.serialize(QValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeDict {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError> {
+        self.map.insert(key.to_string(), value.serialize(QValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeDict {
+    type Ok = QValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError> {
+        self.map.insert(key.to_string(), value.serialize(QValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<QValue, SerdeError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect { w: i32, h: i32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        port: u16,
+        tags: Vec<String>,
+        limit: Option<i64>,
+        shapes: Vec<Shape>,
+        counts: BTreeMap<u32, bool>,
+    }
+
+    fn sample() -> Config {
+        let mut counts = BTreeMap::new();
+        counts.insert(7, true);
+        Config {
+            name: "api".to_string(),
+            port: 8080,
+            tags: vec!["a".to_string(), "b".to_string()],
+            limit: None,
+            shapes: vec![Shape::Point, Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }],
+            counts,
+        }
+    }
+
+    #[test]
+    fn struct_round_trip() {
+        let value = to_qvalue(&sample()).unwrap();
+        let QValue::Dict(ref dict) = value else { panic!("expected Dict") };
+        assert_eq!(dict.map.borrow().keys().cloned().collect::<Vec<_>>(),
+            vec!["name", "port", "tags", "limit", "shapes", "counts"]);
+        assert_eq!(from_qvalue::<Config>(&value).unwrap(), sample());
+    }
+
+    #[test]
+    fn matches_serde_json_shape() {
+        let value = to_qvalue(&sample()).unwrap();
+        assert_eq!(value.to_serde().unwrap(), serde_json::to_value(sample()).unwrap());
+        let back = QValue::from_serde(serde_json::to_value(sample()).unwrap());
+        assert_eq!(from_qvalue::<Config>(&back).unwrap(), sample());
+    }
+
+    #[test]
+    fn scalars_map_to_quest_types() {
+        assert!(matches!(to_qvalue(&u64::MAX).unwrap(), QValue::BigInt(_)));
+        assert!(matches!(to_qvalue(&42u64).unwrap(), QValue::Int(_)));
+        assert!(matches!(to_qvalue(&serde_bytes_like()).unwrap(), QValue::Bytes(_)));
+        assert!(matches!(to_qvalue(&()).unwrap(), QValue::Nil(_)));
+        assert_eq!(from_qvalue::<u64>(&to_qvalue(&u64::MAX).unwrap()).unwrap(), u64::MAX);
+    }
+
+    fn serde_bytes_like() -> impl Serialize {
+        struct Raw;
+        impl Serialize for Raw {
+            fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_bytes(&[1, 2, 3])
+            }
+        }
+        Raw
+    }
+
+    #[test]
+    fn qvalue_is_serde_value() {
+        let value: QValue = serde_json::from_str(r#"{"a": [1, 2.5, null, "x"]}"#).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"a":[1,2.5,null,"x"]}"#);
+        let yaml: QValue = serde_yaml::from_str("1: one\ntrue: yes\n").unwrap();
+        assert_eq!(yaml.to_serde().unwrap(), serde_json::json!({"1": "one", "true": "yes"}));
+    }
+
+    #[test]
+    fn reports_type_mismatches() {
+        let value = to_qvalue(&vec!["x"]).unwrap();
+        let err = from_qvalue::<Config>(&value).unwrap_err();
+        assert!(err.contains("expected struct Config"), "{}", err);
+        assert!(to_qvalue(&QValue::Fun(QFun::new("f".into(), "m".into()))).is_err());
+    }
+}
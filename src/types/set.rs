@@ -344,6 +344,7 @@ impl QSet {
 
 impl Clone for QSet {
     fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Set", self.id);
         QSet {
             elements: Rc::clone(&self.elements),
             id: self.id,
@@ -351,6 +352,12 @@ impl Clone for QSet {
     }
 }
 
+impl Drop for QSet {
+    fn drop(&mut self) {
+        crate::alloc_counter::track_dealloc("Set", self.id);
+    }
+}
+
 impl QObj for QSet {
     fn cls(&self) -> String {
         "Set".to_string()
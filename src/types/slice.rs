@@ -0,0 +1,66 @@
+// Slice syntax support: a[start:stop:step] and nd[1:3, :]
+use super::*;
+use crate::value_err;
+
+/// Slice bounds as written; missing parts are None
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SliceRange {
+    pub start: Option<i64>,
+    pub stop: Option<i64>,
+    pub step: Option<i64>,
+}
+
+/// One entry of an index expression: `a[i]` or `a[start:stop]`
+#[derive(Debug, Clone)]
+pub enum IndexEntry {
+    At(QValue),
+    Range(SliceRange),
+}
+
+impl SliceRange {
+    /// Resolve against a length the way Python does: negative bounds count
+    /// from the end and out-of-range bounds are clamped. Returns
+    /// (start, stop, step); with a negative step, stop may be -1.
+    pub fn resolve(&self, len: usize) -> Result<(i64, i64, i64), EvalError> {
+        let len = len as i64;
+        let step = self.step.unwrap_or(1);
+        if step == 0 {
+            return value_err!("Slice step cannot be zero");
+        }
+        let (low, high) = if step > 0 { (0, len) } else { (-1, len - 1) };
+        let clamp = |bound: Option<i64>, default: i64| match bound {
+            None => default,
+            Some(b) if b < 0 => (b + len).max(low),
+            Some(b) => b.min(high),
+        };
+        if step > 0 {
+            Ok((clamp(self.start, 0), clamp(self.stop, len), step))
+        } else {
+            Ok((clamp(self.start, len - 1), clamp(self.stop, -1), step))
+        }
+    }
+
+    /// Positions selected from a sequence of `len` items, in order
+    pub fn indices(&self, len: usize) -> Result<Vec<usize>, EvalError> {
+        let (start, stop, step) = self.resolve(len)?;
+        let mut indices = Vec::new();
+        let mut i = start;
+        while (step > 0 && i < stop) || (step < 0 && i > stop) {
+            indices.push(i as usize);
+            i += step;
+        }
+        Ok(indices)
+    }
+
+    /// The equivalent ndarray slice of an axis of length `len`
+    pub fn to_ndarray_elem(&self, len: usize) -> Result<::ndarray::SliceInfoElem, EvalError> {
+        let (start, stop, step) = self.resolve(len)?;
+        // ndarray walks a negative step backwards from the end of the range
+        let (low, high) = if step > 0 { (start, stop.max(start)) } else { (stop + 1, (start + 1).max(stop + 1)) };
+        Ok(::ndarray::SliceInfoElem::Slice {
+            start: low as isize,
+            end: Some(high as isize),
+            step: step as isize,
+        })
+    }
+}
@@ -1,9 +1,10 @@
 use super::*;
 use std::rc::Rc;
 use num_traits::Num;  // For BigInt::from_str_radix
-use crate::{arg_err, attr_err};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::{arg_err, attr_err, type_err, value_err};
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct QString {
     pub value: Rc<String>,
     pub id: u64,
@@ -139,9 +140,9 @@ impl QString {
                 let result = !self.value.is_empty() && self.value.chars().all(|c| c.is_alphanumeric());
                 Ok(QValue::Bool(QBool::new(result)))
             }
-            "isalpha" => {
+            "isalpha" | "is_alpha" => {
                 if !args.is_empty() {
-                    return arg_err!("isalpha expects 0 arguments, got {}", args.len());
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
                 }
                 let result = !self.value.is_empty() && self.value.chars().all(|c| c.is_alphabetic());
                 Ok(QValue::Bool(QBool::new(result)))
@@ -153,9 +154,9 @@ impl QString {
                 let result = self.value.chars().all(|c| c.is_ascii());
                 Ok(QValue::Bool(QBool::new(result)))
             }
-            "isdigit" => {
+            "isdigit" | "is_digit" => {
                 if !args.is_empty() {
-                    return arg_err!("isdigit expects 0 arguments, got {}", args.len());
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
                 }
                 let result = !self.value.is_empty() && self.value.chars().all(|c| c.is_ascii_digit());
                 Ok(QValue::Bool(QBool::new(result)))
@@ -183,9 +184,9 @@ impl QString {
                 let all_upper = self.value.chars().filter(|c| c.is_alphabetic()).all(|c| c.is_uppercase());
                 Ok(QValue::Bool(QBool::new(has_cased && all_upper)))
             }
-            "isspace" => {
+            "isspace" | "is_space" => {
                 if !args.is_empty() {
-                    return arg_err!("isspace expects 0 arguments, got {}", args.len());
+                    return arg_err!("{} expects 0 arguments, got {}", method_name, args.len());
                 }
                 let result = !self.value.is_empty() && self.value.chars().all(|c| c.is_whitespace());
                 Ok(QValue::Bool(QBool::new(result)))
@@ -533,6 +534,173 @@ impl QString {
 
                 Ok(QValue::Array(QArray::new(parts)))
             }
+            "rsplit" => {
+                // Split from the right, at most `maxsplit` times (all when omitted or negative)
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("rsplit expects 1 or 2 arguments (sep, maxsplit), got {}", args.len());
+                }
+                let delimiter = args[0].as_str();
+                if delimiter.is_empty() {
+                    return value_err!("rsplit separator must not be empty");
+                }
+                let maxsplit = match args.get(1) {
+                    Some(arg) => arg.as_num()? as i64,
+                    None => -1,
+                };
+
+                let mut parts: Vec<QValue> = if maxsplit < 0 {
+                    self.value.rsplit(&delimiter)
+                        .map(|s| QValue::Str(QString::new(s.to_string())))
+                        .collect()
+                } else {
+                    self.value.rsplitn(maxsplit as usize + 1, &delimiter)
+                        .map(|s| QValue::Str(QString::new(s.to_string())))
+                        .collect()
+                };
+                parts.reverse();
+
+                Ok(QValue::Array(QArray::new(parts)))
+            }
+            "split_lines" => {
+                // Split at line boundaries (\n, \r\n, \r and the other Unicode line breaks)
+                if args.len() > 1 {
+                    return arg_err!("split_lines expects 0 or 1 arguments (keepends), got {}", args.len());
+                }
+                let keepends = args.first().map(|arg| arg.as_bool()).unwrap_or(false);
+                let lines = split_lines(&self.value, keepends)
+                    .into_iter()
+                    .map(|line| QValue::Str(QString::new(line.to_string())))
+                    .collect();
+                Ok(QValue::Array(QArray::new(lines)))
+            }
+            "partition" => {
+                // Split at the first occurrence of sep: [before, sep, after]
+                if args.len() != 1 {
+                    return arg_err!("partition expects 1 argument, got {}", args.len());
+                }
+                let sep = args[0].as_str();
+                if sep.is_empty() {
+                    return value_err!("partition separator must not be empty");
+                }
+                let (before, found, after) = match self.value.split_once(&sep) {
+                    Some((before, after)) => (before, sep.as_str(), after),
+                    None => (self.value.as_str(), "", ""),
+                };
+                Ok(QValue::Array(QArray::new(vec![
+                    QValue::Str(QString::new(before.to_string())),
+                    QValue::Str(QString::new(found.to_string())),
+                    QValue::Str(QString::new(after.to_string())),
+                ])))
+            }
+            "center" | "ljust" | "rjust" => {
+                // Pad to `width` characters with `fill` (a space by default)
+                if args.is_empty() || args.len() > 2 {
+                    return arg_err!("{} expects 1 or 2 arguments (width, fill), got {}", method_name, args.len());
+                }
+                let width = args[0].as_num()?.max(0.0) as usize;
+                let fill = match args.get(1) {
+                    Some(arg) => {
+                        let fill = arg.as_str();
+                        let mut chars = fill.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => c,
+                            _ => return arg_err!("{} fill must be a single character, got {:?}", method_name, fill),
+                        }
+                    }
+                    None => ' ',
+                };
+                let padding = width.saturating_sub(self.value.chars().count());
+                let left = match method_name {
+                    "center" => padding / 2,
+                    "rjust" => padding,
+                    _ => 0,
+                };
+                let mut result = String::with_capacity(self.value.len() + padding);
+                result.extend(std::iter::repeat_n(fill, left));
+                result.push_str(&self.value);
+                result.extend(std::iter::repeat_n(fill, padding - left));
+                Ok(QValue::Str(QString::new(result)))
+            }
+            "casefold" => {
+                // Lowercase for caseless comparison (ß → ss, final ς → σ)
+                if !args.is_empty() {
+                    return arg_err!("casefold expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Str(QString::new(casefold(&self.value))))
+            }
+            "removeprefix" => {
+                if args.len() != 1 {
+                    return arg_err!("removeprefix expects 1 argument, got {}", args.len());
+                }
+                let prefix = args[0].as_str();
+                let result = self.value.strip_prefix(prefix.as_str()).unwrap_or(&self.value);
+                Ok(QValue::Str(QString::new(result.to_string())))
+            }
+            "removesuffix" => {
+                if args.len() != 1 {
+                    return arg_err!("removesuffix expects 1 argument, got {}", args.len());
+                }
+                let suffix = args[0].as_str();
+                let result = self.value.strip_suffix(suffix.as_str()).unwrap_or(&self.value);
+                Ok(QValue::Str(QString::new(result.to_string())))
+            }
+            "translate" => {
+                // translate({"a": "b", "c": nil}) maps characters (nil deletes them);
+                // translate("abc", "xyz") maps each character of the first string
+                // to the one at the same position in the second
+                let mut result = String::with_capacity(self.value.len());
+                match args.as_slice() {
+                    [QValue::Dict(table)] => {
+                        let table = table.map.borrow();
+                        let mut key = String::new();
+                        for c in self.value.chars() {
+                            key.clear();
+                            key.push(c);
+                            match table.get(&key) {
+                                Some(QValue::Str(replacement)) => result.push_str(&replacement.value),
+                                Some(QValue::Nil(_)) => {}
+                                Some(other) => return type_err!(
+                                    "translate table values must be Str or nil, got {}", other.as_obj().cls()
+                                ),
+                                None => result.push(c),
+                            }
+                        }
+                    }
+                    [from, to] => {
+                        let from: Vec<char> = from.as_str().chars().collect();
+                        let to: Vec<char> = to.as_str().chars().collect();
+                        if from.len() != to.len() {
+                            return value_err!(
+                                "translate strings must have the same length, got {} and {}", from.len(), to.len()
+                            );
+                        }
+                        for c in self.value.chars() {
+                            match from.iter().position(|&f| f == c) {
+                                Some(i) => result.push(to[i]),
+                                None => result.push(c),
+                            }
+                        }
+                    }
+                    _ => return arg_err!("translate expects a Dict or 2 Str arguments (from, to), got {} arguments", args.len()),
+                }
+                Ok(QValue::Str(QString::new(result)))
+            }
+            "graphemes" => {
+                // User-perceived characters: "e\u0301" and flags with modifiers stay whole
+                if !args.is_empty() {
+                    return arg_err!("graphemes expects 0 arguments, got {}", args.len());
+                }
+                let graphemes = self.value.graphemes(true)
+                    .map(|g| QValue::Str(QString::new(g.to_string())))
+                    .collect();
+                Ok(QValue::Array(QArray::new(graphemes)))
+            }
+            "grapheme_len" => {
+                if !args.is_empty() {
+                    return arg_err!("grapheme_len expects 0 arguments, got {}", args.len());
+                }
+                Ok(QValue::Int(QInt::new(self.value.graphemes(true).count() as i64)))
+            }
             "slice" => {
                 // Return substring from start to end (exclusive)
                 if args.len() != 2 {
@@ -732,6 +900,47 @@ impl QString {
     }
 }
 
+/// Split at line boundaries like Python's str.splitlines: \r\n counts as one
+/// break, and a trailing break doesn't produce an empty last line
+fn split_lines(s: &str, keepends: bool) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = match c {
+            '\r' if matches!(chars.peek(), Some((_, '\n'))) => {
+                chars.next();
+                i + 2
+            }
+            '\n' | '\r' | '\x0b' | '\x0c' | '\x1c' | '\x1d' | '\x1e' | '\u{85}' | '\u{2028}' | '\u{2029}' => {
+                i + c.len_utf8()
+            }
+            _ => continue,
+        };
+        lines.push(if keepends { &s[start..end] } else { &s[start..i] });
+        start = end;
+    }
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+    lines
+}
+
+/// Lowercase with the Unicode case folds that lowercasing misses, so
+/// "Straße".casefold() == "STRASSE".casefold()
+pub(crate) fn casefold(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            'ſ' => folded.push('s'),
+            _ => folded.extend(c.to_lowercase()),
+        }
+    }
+    folded
+}
+
 impl QObj for QString {
     fn cls(&self) -> String {
         "Str".to_string()
@@ -763,6 +972,16 @@ impl QObj for QString {
     }
 }
 
+impl Clone for QString {
+    fn clone(&self) -> Self {
+        crate::alloc_counter::track_clone("Str", self.id);
+        QString {
+            value: Rc::clone(&self.value),
+            id: self.id,
+        }
+    }
+}
+
 impl Drop for QString {
     fn drop(&mut self) {
         crate::alloc_counter::track_dealloc("Str", self.id);
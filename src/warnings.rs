@@ -0,0 +1,137 @@
+// Runtime warnings (`sys.warn`, `-W`)
+//
+// A warning has a category ("UserWarning", "DeprecationWarning", or any name a
+// library picks) and is reported at a call site. What happens to it is decided
+// by filters of the form ACTION[:CATEGORY], read from QUEST_WARNINGS
+// (comma-separated) and then from each `-W` flag; the last filter that
+// matches wins. The default action prints a warning once per category,
+// message and call site, so a deprecated call in a loop is reported once.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use crate::scope::Scope;
+
+/// Category used by `sys.warn` when none is given
+pub const USER_WARNING: &str = "UserWarning";
+/// Category for APIs that are going away
+pub const DEPRECATION_WARNING: &str = "DeprecationWarning";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    /// Print once per category, message and call site
+    Default,
+    /// Print every time
+    Always,
+    /// Don't print
+    Ignore,
+    /// Raise a WarningErr instead
+    Error,
+}
+
+thread_local! {
+    static FILTERS: RefCell<Vec<(Action, Option<String>)>> = RefCell::new(env_filters());
+    static SHOWN: RefCell<HashSet<(String, String, String)>> = RefCell::new(HashSet::new());
+}
+
+fn env_filters() -> Vec<(Action, Option<String>)> {
+    std::env::var("QUEST_WARNINGS").unwrap_or_default()
+        .split(',')
+        .filter(|spec| !spec.trim().is_empty())
+        .filter_map(|spec| match parse_filter(spec.trim()) {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                eprintln!("Ignoring QUEST_WARNINGS entry: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_filter(spec: &str) -> Result<(Action, Option<String>), String> {
+    // Python's ACTION::CATEGORY spelling is accepted too
+    let (action, category) = match spec.split_once(':') {
+        Some((action, category)) => (action, Some(category.trim_start_matches(':').to_string()).filter(|c| !c.is_empty())),
+        None => (spec, None),
+    };
+    let action = match action {
+        "default" => Action::Default,
+        "always" => Action::Always,
+        "ignore" => Action::Ignore,
+        "error" => Action::Error,
+        _ => return Err(format!("Invalid warning action '{}' (expected default, always, ignore or error)", action)),
+    };
+    Ok((action, category))
+}
+
+/// Add a `-W ACTION[:CATEGORY]` filter; it takes precedence over earlier ones
+pub fn add_filter(spec: &str) -> Result<(), String> {
+    let filter = parse_filter(spec)?;
+    FILTERS.with(|filters| filters.borrow_mut().push(filter));
+    Ok(())
+}
+
+fn action_for(category: &str) -> Action {
+    FILTERS.with(|filters| {
+        filters.borrow().iter().rev()
+            .find(|(_, c)| c.as_deref().is_none_or(|c| c == category))
+            .map(|(action, _)| *action)
+            .unwrap_or(Action::Default)
+    })
+}
+
+/// Report a warning at the current statement, or `stacklevel - 1` calls up
+/// the stack. Returns an error (a WarningErr) when the warning's action is
+/// "error".
+pub fn warn(scope: &Scope, message: &str, category: &str, stacklevel: usize) -> Result<(), String> {
+    let action = action_for(category);
+    if action == Action::Ignore {
+        return Ok(());
+    }
+    if action == Action::Error {
+        return Err(format!("WarningErr: {}: {}", category, message));
+    }
+
+    let location = call_site(scope, stacklevel);
+    if action == Action::Default {
+        let key = (category.to_string(), message.to_string(), location.clone());
+        if !SHOWN.with(|shown| shown.borrow_mut().insert(key)) {
+            return Ok(());
+        }
+    }
+
+    let text = if location.is_empty() {
+        format!("{}: {}\n", category, message)
+    } else {
+        format!("{}: {}: {}\n", location, category, message)
+    };
+    scope.stderr_target.write(&text)
+}
+
+/// `file:line` of the statement running `stacklevel - 1` frames up the call stack
+fn call_site(scope: &Scope, stacklevel: usize) -> String {
+    let (file, line) = if stacklevel <= 1 {
+        match &scope.source_file {
+            Some(file) => (Some(file.to_string()), scope.current_line.map(|line| line + scope.line_offset)),
+            None => (scope.current_file.clone(), scope.current_line),
+        }
+    } else {
+        let stack = scope.call_stack.borrow();
+        match stack.len().checked_sub(stacklevel - 1).and_then(|i| stack.get(i)) {
+            Some(frame) => (frame.file.clone(), frame.line),
+            None => (None, None),
+        }
+    };
+    match (file, line) {
+        (Some(file), Some(line)) => format!("{}:{}", display_path(&file), line),
+        (Some(file), None) => display_path(&file),
+        _ => String::new(),
+    }
+}
+
+/// A file's path relative to the current directory when it is inside it
+fn display_path(file: &str) -> String {
+    std::env::current_dir().ok()
+        .and_then(|cwd| std::path::Path::new(file).strip_prefix(cwd).ok().map(|p| p.display().to_string()))
+        .unwrap_or_else(|| file.to_string())
+}
@@ -0,0 +1,120 @@
+// Watch mode for `quest run --watch`
+//
+// The script runs in a child `quest` process so every run starts from a fresh
+// interpreter (module cache, sys.argv, globals). The script and every local
+// (non-std) module it reaches through `use` are watched; their directories
+// are watched rather than the files themselves, because editors often save by
+// renaming a new file over the old one. A change kills a run that is still
+// going, clears the screen and starts again. The dependency list is rebuilt
+// after every change so newly added imports are picked up.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use pest::Parser;
+
+use crate::{QuestParser, Rule};
+
+/// How long to wait for more events after a change before re-running
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Run `script` with `args`, and again whenever it or a module it uses changes
+pub fn watch_script(script: &Path, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let script = script.canonicalize()
+        .map_err(|e| format!("Failed to read file '{}': {}", script.display(), e))?;
+    let exe = std::env::current_exe()?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut watched_dirs = BTreeSet::new();
+
+    loop {
+        let files = dependencies(&script);
+        let dirs: BTreeSet<PathBuf> = files.iter().filter_map(|f| f.parent().map(Path::to_path_buf)).collect();
+        for dir in dirs.difference(&watched_dirs) {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        for dir in watched_dirs.difference(&dirs) {
+            let _ = watcher.unwatch(dir);
+        }
+        watched_dirs = dirs;
+
+        print!("\x1b[2J\x1b[H");
+        println!("\x1b[2m[watch] {} ({} file(s) watched)\x1b[0m", script.display(), files.len());
+        let started = Instant::now();
+        let mut child = Some(Command::new(&exe).arg(&script).args(args).spawn()?);
+
+        // Wait for a change to one of the files, reporting when the run ends
+        loop {
+            if let Some(running) = child.as_mut() {
+                if let Some(status) = running.try_wait()? {
+                    report_exit(status, started.elapsed());
+                    child = None;
+                }
+            }
+            match rx.recv_timeout(Duration::from_millis(50)) {
+                Ok(Ok(event)) if is_change(&event, &files) => break,
+                Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        // Let a burst of writes (save, format-on-save) settle into one re-run
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        if let Some(running) = child.as_mut() {
+            stop(running);
+        }
+    }
+}
+
+fn is_change(event: &notify::Event, files: &BTreeSet<PathBuf>) -> bool {
+    !matches!(event.kind, EventKind::Access(_))
+        && event.paths.iter().any(|p| files.contains(p) || p.canonicalize().is_ok_and(|p| files.contains(&p)))
+}
+
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn report_exit(status: std::process::ExitStatus, elapsed: Duration) {
+    let millis = elapsed.as_secs_f64() * 1000.0;
+    if status.success() {
+        println!("\x1b[2m[watch] finished in {:.0}ms; waiting for changes\x1b[0m", millis);
+    } else {
+        let code = status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string());
+        println!("\x1b[31m[watch] exited with status {} after {:.0}ms; waiting for changes\x1b[0m", code, millis);
+    }
+}
+
+/// The script plus every local module it uses, directly or not (canonical paths)
+fn dependencies(script: &Path) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![script.to_path_buf()];
+    while let Some(file) = pending.pop() {
+        if !files.insert(file.clone()) {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(&file) else { continue };
+        // A file that doesn't parse is still watched, so fixing it triggers a run
+        let Ok(pairs) = QuestParser::parse(Rule::program, &source) else { continue };
+        let from = file.to_string_lossy();
+        for pair in pairs.flatten().filter(|p| p.as_rule() == Rule::use_statement) {
+            let Some(path) = pair.into_inner().next() else { continue };
+            let use_path = crate::string_utils::parse_string(path.as_str());
+            if use_path.starts_with("std/") {
+                continue;
+            }
+            if let Ok(resolved) = crate::module_loader::resolve_module_path_from(&use_path, Some(&from), &[]) {
+                if let Ok(resolved) = Path::new(&resolved).canonicalize() {
+                    pending.push(resolved);
+                }
+            }
+        }
+    }
+    files
+}
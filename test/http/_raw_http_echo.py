@@ -0,0 +1,25 @@
+"""Answer one HTTP request with its raw body, for tests that check the exact
+bytes a client sends. Prints the port it listens on first."""
+import socket
+
+server = socket.socket()
+server.settimeout(10)
+server.bind(("127.0.0.1", 0))
+server.listen(1)
+print(server.getsockname()[1], flush=True)
+
+conn, _ = server.accept()
+data = b""
+while b"\r\n\r\n" not in data:
+    data += conn.recv(65536)
+head, body = data.split(b"\r\n\r\n", 1)
+length = 0
+for line in head.split(b"\r\n")[1:]:
+    name, _, value = line.partition(b":")
+    if name.strip().lower() == b"content-length":
+        length = int(value)
+while len(body) < length:
+    body += conn.recv(65536)
+conn.sendall(b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n"
+             b"Content-Length: %d\r\nConnection: close\r\n\r\n" % len(body) + body)
+conn.close()
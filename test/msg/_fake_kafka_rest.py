@@ -0,0 +1,145 @@
+"""A small Kafka REST Proxy (API v2) for tests. Prints the port it listens on
+first and exits when stdin closes.
+
+Topics live in memory with two partitions; records without a partition go to
+partition 0. Consumer instances read every subscribed partition from their
+group's committed offsets. A few paths and names behave specially:
+
+  GET /_fake/groups/<group>   committed offsets of a group as {"<topic>/<p>": offset}
+  GET /_fake/instances        names of the consumer instances still alive
+  topic "forbidden"           sends fail with 403 and a Kafka error body
+  topic "full"                sends succeed but each offset carries an error
+"""
+import json
+import sys
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+V2 = "application/vnd.kafka.v2+json"
+
+lock = threading.Lock()
+topics = {}
+committed = {}
+instances = {}
+counter = [0]
+
+
+def partitions(topic):
+    return topics.setdefault(topic, {0: [], 1: []})
+
+
+class Handler(BaseHTTPRequestHandler):
+    def log_message(self, *args):
+        pass
+
+    def reply(self, status, body=None, content_type=V2):
+        data = b"" if body is None else json.dumps(body).encode()
+        self.send_response(status)
+        self.send_header("Content-Type", content_type)
+        self.send_header("Content-Length", str(len(data)))
+        self.end_headers()
+        self.wfile.write(data)
+
+    def error(self, status, code, message):
+        self.reply(status, {"error_code": code, "message": message})
+
+    def body(self):
+        data = self.rfile.read(int(self.headers.get("Content-Length", 0)))
+        return json.loads(data) if data else None
+
+    def instance(self, parts):
+        # /consumers/<group>/instances/<name>[/...]
+        if len(parts) >= 4 and parts[0] == "consumers" and parts[2] == "instances":
+            return instances.get((parts[1], parts[3]))
+        return None
+
+    def do_GET(self):
+        path, _, query = self.path.partition("?")
+        parts = path.strip("/").split("/")
+        with lock:
+            if parts[:2] == ["_fake", "groups"]:
+                return self.reply(200, committed.get(parts[2], {}), "application/json")
+            if parts == ["_fake", "instances"]:
+                return self.reply(200, sorted(name for _, name in instances), "application/json")
+            inst = self.instance(parts)
+            if inst is None:
+                return self.error(404, 40403, "Consumer instance not found.")
+            if parts[4:] != ["records"]:
+                return self.error(404, 404, "HTTP 404 Not Found")
+            wanted = "application/vnd.kafka." + inst["format"] + ".v2+json"
+            if self.headers.get("Accept") != wanted:
+                return self.error(406, 40601, "Accept " + str(self.headers.get("Accept")) + " does not match format")
+            records = []
+            for topic in inst["topics"]:
+                for partition, log in partitions(topic).items():
+                    tp = topic + "/" + str(partition)
+                    start = inst["position"].get(tp, committed.get(inst["group"], {}).get(tp, 0))
+                    for offset in range(start, len(log)):
+                        records.append(dict(log[offset], topic=topic, partition=partition, offset=offset))
+                    inst["position"][tp] = len(log)
+            return self.reply(200, records, wanted)
+
+    def do_POST(self):
+        parts = self.path.strip("/").split("/")
+        body = self.body()
+        with lock:
+            if len(parts) == 2 and parts[0] == "topics":
+                return self.produce(parts[1], body)
+            if len(parts) == 2 and parts[0] == "consumers":
+                counter[0] += 1
+                name = body.get("name") or "instance-" + str(counter[0])
+                instances[(parts[1], name)] = {
+                    "group": parts[1], "format": body["format"], "topics": [], "position": {}
+                }
+                host = self.headers["Host"]
+                return self.reply(200, {
+                    "instance_id": name,
+                    "base_uri": "http://" + host + "/consumers/" + parts[1] + "/instances/" + name,
+                })
+            inst = self.instance(parts)
+            if inst is None:
+                return self.error(404, 40403, "Consumer instance not found.")
+            if parts[4:] == ["subscription"]:
+                inst["topics"] = body["topics"]
+                return self.reply(204)
+            if parts[4:] == ["offsets"]:
+                group = committed.setdefault(inst["group"], {})
+                if body is None:
+                    group.update(inst["position"])
+                else:
+                    for o in body["offsets"]:
+                        group[o["topic"] + "/" + str(o["partition"])] = o["offset"] + 1
+                return self.reply(200)
+            return self.error(404, 404, "HTTP 404 Not Found")
+
+    def produce(self, topic, body):
+        content_type = self.headers.get("Content-Type")
+        if content_type not in ("application/vnd.kafka.json.v2+json", "application/vnd.kafka.binary.v2+json"):
+            return self.error(415, 415, "HTTP 415 Unsupported Media Type")
+        if topic == "forbidden":
+            return self.error(403, 40301, "Not authorized to access topics: [forbidden]")
+        offsets = []
+        for record in body["records"]:
+            if topic == "full":
+                offsets.append({"partition": None, "offset": None, "error_code": 50002, "error": "Record too large"})
+                continue
+            partition = record.get("partition", 0)
+            log = partitions(topic)[partition]
+            log.append({"key": record.get("key"), "value": record["value"]})
+            offsets.append({"partition": partition, "offset": len(log) - 1, "error_code": None, "error": None})
+        self.reply(200, {"key_schema_id": None, "value_schema_id": None, "offsets": offsets})
+
+    def do_DELETE(self):
+        parts = self.path.strip("/").split("/")
+        with lock:
+            if self.instance(parts) is None or len(parts) != 4:
+                return self.error(404, 40403, "Consumer instance not found.")
+            del instances[(parts[1], parts[3])]
+            self.reply(204)
+
+
+server = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+server.daemon_threads = True
+print(server.server_address[1], flush=True)
+threading.Thread(target=server.serve_forever, daemon=True).start()
+sys.stdin.read()
@@ -0,0 +1,141 @@
+"""A small NATS server for tests. Prints the port it listens on first and
+exits when stdin closes.
+
+Routes PUB to every matching SUB across connections (with `*` and `>`
+wildcards, queue groups and UNSUB <sid> <max>) and answers PINGs. A few
+subjects are handled by the server itself:
+
+  _fake.connect  replies with the requester's CONNECT options as JSON
+  _fake.ping     sends the requester a PING and replies "pong" once its PONG arrives
+  svc.upper      replies with the payload in upper case
+
+A CONNECT with user "denied" gets -ERR and the connection is closed.
+"""
+import json
+import socket
+import sys
+import threading
+
+INFO = {"server_id": "fake", "version": "2.10.0", "proto": 1, "max_payload": 1024}
+
+lock = threading.Lock()
+subs = []
+
+
+def matches(pattern, subject):
+    pattern, subject = pattern.split("."), subject.split(".")
+    for i, token in enumerate(pattern):
+        if token == ">":
+            return len(subject) > i
+        if i >= len(subject) or token not in ("*", subject[i]):
+            return False
+    return len(pattern) == len(subject)
+
+
+class Conn:
+    def __init__(self, sock):
+        self.sock = sock
+        self.file = sock.makefile("rb")
+        self.write_lock = threading.Lock()
+        self.options = {}
+        self.awaiting_pong = None
+
+    def send(self, data):
+        with self.write_lock:
+            self.sock.sendall(data)
+
+    def deliver(self, subject, sid, reply, payload):
+        head = f"MSG {subject} {sid} {reply} {len(payload)}" if reply else f"MSG {subject} {sid} {len(payload)}"
+        self.send(head.encode() + b"\r\n" + payload + b"\r\n")
+
+
+def route(subject, reply, payload):
+    chosen, groups = [], set()
+    with lock:
+        for sub in [s for s in subs if matches(s["subject"], subject)]:
+            if sub["queue"] is not None:
+                if sub["queue"] in groups:
+                    continue
+                groups.add(sub["queue"])
+            chosen.append(sub)
+            if sub["max"] is not None:
+                sub["max"] -= 1
+                if sub["max"] <= 0:
+                    subs.remove(sub)
+    for sub in chosen:
+        sub["conn"].deliver(subject, sub["sid"], reply, payload)
+
+
+def handle(conn, line):
+    """Handle one protocol line; returns False to hang up"""
+    op, _, rest = line.partition(" ")
+    parts = rest.split()
+    op = op.upper()
+    if op == "CONNECT":
+        conn.options = json.loads(rest)
+        if conn.options.get("user") == "denied":
+            conn.send(b"-ERR 'Authorization Violation'\r\n")
+            return False
+    elif op == "PING":
+        conn.send(b"PONG\r\n")
+    elif op == "PONG" and conn.awaiting_pong:
+        reply, conn.awaiting_pong = conn.awaiting_pong, None
+        route(reply, None, b"pong")
+    elif op == "SUB":
+        queue = parts[1] if len(parts) == 3 else None
+        with lock:
+            subs.append({"conn": conn, "sid": parts[-1], "subject": parts[0], "queue": queue, "max": None})
+    elif op == "UNSUB":
+        with lock:
+            for sub in subs:
+                if sub["conn"] is conn and sub["sid"] == parts[0]:
+                    if len(parts) > 1:
+                        sub["max"] = int(parts[1])
+                    else:
+                        subs.remove(sub)
+                    break
+    elif op == "PUB":
+        subject, size = parts[0], int(parts[-1])
+        reply = parts[1] if len(parts) == 3 else None
+        payload = conn.file.read(size + 2)[:size]
+        if subject == "_fake.connect":
+            route(reply, None, json.dumps(conn.options).encode())
+        elif subject == "_fake.ping":
+            conn.awaiting_pong = reply
+            conn.send(b"PING\r\n")
+        elif subject == "svc.upper":
+            route(reply, None, payload.upper())
+        else:
+            route(subject, reply, payload)
+    return True
+
+
+def serve(sock):
+    conn = Conn(sock)
+    try:
+        conn.send(b"INFO " + json.dumps(INFO).encode() + b"\r\n")
+        while True:
+            line = conn.file.readline()
+            if not line or not handle(conn, line.rstrip(b"\r\n").decode()):
+                break
+    except OSError:
+        pass
+    finally:
+        with lock:
+            subs[:] = [s for s in subs if s["conn"] is not conn]
+        sock.close()
+
+
+def accept(server):
+    while True:
+        sock, _ = server.accept()
+        threading.Thread(target=serve, args=(sock,), daemon=True).start()
+
+
+server = socket.socket()
+server.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
+server.bind(("127.0.0.1", 0))
+server.listen()
+print(server.getsockname()[1], flush=True)
+threading.Thread(target=accept, args=(server,), daemon=True).start()
+sys.stdin.read()